@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use dbtonic::configuration::dbtonic_config::{ParsingConfig, SqlDialect};
+use dbtonic::parser::model_node::ModelNode;
+
+// A model the size of a large mart: a chain of CTEs over refs, each with a
+// comment, a join and a few dozen columns
+fn large_model(ctes: usize) -> String {
+    let mut sql = String::from("WITH ");
+    for i in 0..ctes {
+        if i > 0 {
+            sql.push_str(",\n");
+        }
+        sql.push_str(&format!("-- step {i}\nstep_{i} AS (\n    SELECT\n"));
+        let columns: Vec<String> = (0..30)
+            .map(|column| format!("        orders.column_{column} AS step_{i}_column_{column}"))
+            .collect();
+        sql.push_str(&columns.join(",\n"));
+        sql.push_str(&format!(
+            "\n    FROM {{{{ ref('stg_orders_{i}') }}}} AS orders\n    \
+             LEFT JOIN {{{{ ref('stg_customers') }}}} AS customers ON orders.customer_id = customers.id\n    \
+             WHERE orders.status <> 'cancelled'\n)"
+        ));
+    }
+    sql.push_str(&format!("\nSELECT * FROM step_{}", ctes - 1));
+    sql
+}
+
+fn parse_models(c: &mut Criterion) {
+    let parsing = ParsingConfig::default();
+    let path = PathBuf::from("models/marts/orders.sql");
+
+    let mut group = c.benchmark_group("parse_models");
+    for ctes in [1, 50] {
+        let sql = large_model(ctes);
+        group.bench_function(format!("from_sql_{ctes}_ctes"), |b| {
+            b.iter(|| ModelNode::from_sql(path.clone(), black_box(sql.clone()), &parsing, SqlDialect::Generic))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_models);
+criterion_main!(benches);
@@ -0,0 +1,4 @@
+// The cache module
+
+// Publishes the on-disk cache of parse and rule results under .dbtonic/cache
+pub mod parse_cache;
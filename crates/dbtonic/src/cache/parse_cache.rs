@@ -0,0 +1,310 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::configuration::dbtonic_config::SqlDialect;
+use crate::parser::model_node::ModelNode;
+use crate::rules::rules_engine::Violation;
+
+// Entries written by a different version of dbtonic may hold results from an
+// older parser or rule set, so they are treated as stale.
+pub const CACHE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Every cache entry is a json file starting with the version that wrote it
+// and the file it was written for. The rest of the entry depends on what's
+// being cached, so it's ignored here.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CacheEntryHeader {
+    pub version: String,
+    pub source: PathBuf,
+}
+
+// The violations the per-model rules found the last time a model was
+// evaluated, along with the key of everything they depended on
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ViolationsEntry {
+    pub version: String,
+    pub source: PathBuf,
+    pub key: String,
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Debug, PartialEq, Default)]
+pub struct CacheStatus {
+    pub entries: usize,
+    pub stale_entries: usize,
+    pub size_bytes: u64,
+}
+
+pub struct ParseCache {
+    pub dir: PathBuf,
+}
+
+impl ParseCache {
+    pub fn new(project_dir: &Path) -> Self {
+        ParseCache {
+            dir: project_dir.join(".dbtonic").join("cache"),
+        }
+    }
+
+    pub fn status(&self) -> io::Result<CacheStatus> {
+        let mut status = CacheStatus::default();
+
+        for path in self.entry_paths()? {
+            status.entries += 1;
+            status.size_bytes += fs::metadata(&path)?.len();
+            if is_stale(&path) {
+                status.stale_entries += 1;
+            }
+        }
+
+        Ok(status)
+    }
+
+    // Removes the whole cache. Returns the number of entries removed.
+    pub fn clear(&self) -> io::Result<usize> {
+        let entries = self.entry_paths()?.len();
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        Ok(entries)
+    }
+
+    // Removes the entries written by other versions of dbtonic or for files
+    // that have since been deleted or renamed, along with any that can't be
+    // read. Returns the number of entries removed.
+    pub fn prune(&self) -> io::Result<usize> {
+        let mut removed = 0;
+
+        for path in self.entry_paths()? {
+            if is_stale(&path) {
+                fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    // The model's violations from the last run, if nothing they depend on
+    // has changed since. `key` is a `fingerprint` of those inputs.
+    pub fn read_violations(&self, model_path: &Path, key: &str) -> Option<Vec<Violation>> {
+        let contents = fs::read_to_string(self.entry_path(model_path)).ok()?;
+        let entry: ViolationsEntry = serde_json::from_str(&contents).ok()?;
+        if entry.version != CACHE_VERSION || entry.key != key {
+            return None;
+        }
+        Some(entry.violations)
+    }
+
+    pub fn write_violations(&self, model_path: &Path, key: &str, violations: &[Violation]) -> io::Result<()> {
+        // Stored absolute so `prune` can check it from any directory
+        let source = fs::canonicalize(model_path).unwrap_or_else(|_| model_path.to_path_buf());
+        let entry = ViolationsEntry {
+            version: CACHE_VERSION.to_string(),
+            source,
+            key: key.to_string(),
+            violations: violations.to_vec(),
+        };
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.entry_path(model_path), serde_json::to_string(&entry)?)
+    }
+
+    // One entry per model, so a changed model overwrites its old results
+    fn entry_path(&self, model_path: &Path) -> PathBuf {
+        self.dir.join(format!("{}.json", fingerprint(&[&model_path.to_string_lossy()])))
+    }
+
+    fn entry_paths(&self) -> io::Result<Vec<PathBuf>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut paths = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "json") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        Ok(paths)
+    }
+}
+
+// A hex sha256 of the parts, which can't collide by moving text from one
+// part to the next
+pub fn fingerprint(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// The key of everything outside the models that the per-model rules depend
+// on: the config, the dialect models are parsed with, which `--dialect` can
+// change, the folder configs in dbt_project.yml, which rules are registered
+// and the dbtonic binary itself, which covers plugins and development builds
+// that don't bump the version
+pub fn project_key(project_dir: &Path, config_path: Option<&Path>, dialect: SqlDialect, rule_names: &[String]) -> String {
+    let read = |path: PathBuf| fs::read_to_string(path).unwrap_or_default();
+    let binary_modified = std::env::current_exe()
+        .and_then(fs::metadata)
+        .and_then(|metadata| metadata.modified())
+        .map(|modified| format!("{:?}", modified))
+        .unwrap_or_default();
+    fingerprint(&[
+        CACHE_VERSION,
+        &binary_modified,
+        &config_path.map(|config_path| read(config_path.to_path_buf())).unwrap_or_default(),
+        &format!("{:?}", dialect),
+        &read(project_dir.join("dbt_project.yml")),
+        &rule_names.join(","),
+    ])
+}
+
+// The key of a model's cached violations: the project's key along with the
+// model's path, SQL, yml, what manifest.json says about it and whether
+// anything refs it
+pub fn model_key(project_key: &str, model_node: &ModelNode, is_leaf: bool) -> String {
+    let yaml = model_node
+        .data
+        .yaml
+        .as_ref()
+        .and_then(|yaml| serde_json::to_string(yaml).ok())
+        .unwrap_or_default();
+    let manifest = model_node
+        .data
+        .manifest
+        .as_ref()
+        .and_then(|manifest| serde_json::to_string(manifest).ok())
+        .unwrap_or_default();
+    fingerprint(&[
+        project_key,
+        &model_node.path.to_string_lossy(),
+        &model_node.data.sql,
+        model_node.data.compiled_sql.as_deref().unwrap_or_default(),
+        &yaml,
+        &manifest,
+        &is_leaf.to_string(),
+    ])
+}
+
+fn is_stale(path: &Path) -> bool {
+    let header = fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<CacheEntryHeader>(&contents).ok());
+
+    match header {
+        Some(header) => header.version != CACHE_VERSION || !header.source.exists(),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::tokens::Location;
+    use crate::rules::edits::TextEdit;
+    use crate::rules::rules_engine::Severity;
+
+    fn write_entry(cache: &ParseCache, name: &str, contents: &str) {
+        fs::create_dir_all(&cache.dir).unwrap();
+        fs::write(cache.dir.join(name), contents).unwrap();
+    }
+
+    #[test]
+    fn test_status_and_prune() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let cache = ParseCache::new(project_dir.path());
+
+        let source = project_dir.path().join("dbt_project.yml");
+        fs::write(&source, "name: shop").unwrap();
+        let current = serde_json::json!({"version": CACHE_VERSION, "source": source}).to_string();
+        let outdated = serde_json::json!({"version": "0.0.1", "source": source}).to_string();
+        write_entry(&cache, "orders.json", &current);
+        write_entry(&cache, "customers.json", &outdated);
+        write_entry(&cache, "payments.json", "not json");
+
+        let status = cache.status().unwrap();
+        assert_eq!(status.entries, 3);
+        assert_eq!(status.stale_entries, 2);
+        assert!(status.size_bytes > 0);
+
+        assert_eq!(cache.prune().unwrap(), 2);
+        assert_eq!(cache.status().unwrap().entries, 1);
+        assert!(cache.dir.join("orders.json").exists());
+    }
+
+    #[test]
+    fn test_prune_deleted_models() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let cache = ParseCache::new(project_dir.path());
+        let models_dir = project_dir.path().join("models");
+        fs::create_dir_all(&models_dir).unwrap();
+        let orders = models_dir.join("orders.sql");
+        let customers = models_dir.join("customers.sql");
+        fs::write(&orders, "select 1").unwrap();
+        fs::write(&customers, "select 1").unwrap();
+
+        cache.write_violations(&orders, "key", &[]).unwrap();
+        cache.write_violations(&customers, "key", &[]).unwrap();
+        assert_eq!(cache.status().unwrap().stale_entries, 0);
+
+        fs::remove_file(&customers).unwrap();
+        assert_eq!(cache.status().unwrap().stale_entries, 1);
+        assert_eq!(cache.prune().unwrap(), 1);
+        assert_eq!(cache.status().unwrap().entries, 1);
+        assert_eq!(cache.read_violations(&orders, "key"), Some(vec![]));
+    }
+
+    #[test]
+    fn test_violations_round_trip() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let cache = ParseCache::new(project_dir.path());
+        let model_path = project_dir.path().join("models").join("orders.sql");
+        fs::create_dir_all(model_path.parent().unwrap()).unwrap();
+        fs::write(&model_path, "select * from payments").unwrap();
+        let violations = vec![
+            Violation::new("no_select_star".to_string(), "Failed.".to_string(), Some(Location { line: 1, column: 8 }))
+                .with_end_location(Location { line: 1, column: 9 })
+                .with_edits(vec![TextEdit {
+                    start: Location { line: 1, column: 8 },
+                    end: Location { line: 1, column: 9 },
+                    replacement: "id".to_string(),
+                }]),
+            Violation::new("yaml_exists".to_string(), "Failed.".to_string(), None).with_severity(Severity::Warn),
+        ];
+
+        assert_eq!(cache.read_violations(&model_path, "key"), None);
+        cache.write_violations(&model_path, "key", &violations).unwrap();
+        assert_eq!(cache.read_violations(&model_path, "key"), Some(violations));
+        assert_eq!(cache.read_violations(&model_path, "changed"), None);
+        assert_eq!(cache.status().unwrap().stale_entries, 0);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(fingerprint(&["ab", "c"]), fingerprint(&["ab", "c"]));
+        assert_ne!(fingerprint(&["ab", "c"]), fingerprint(&["a", "bc"]));
+        assert_eq!(fingerprint(&[]).len(), 64);
+    }
+
+    #[test]
+    fn test_clear() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let cache = ParseCache::new(project_dir.path());
+
+        // Clearing a cache that was never written is fine
+        assert_eq!(cache.clear().unwrap(), 0);
+
+        write_entry(&cache, "orders.json", r#"{"version": "0.0.1"}"#);
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(!cache.dir.exists());
+        assert_eq!(cache.status().unwrap(), CacheStatus::default());
+    }
+}
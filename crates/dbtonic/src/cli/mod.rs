@@ -1,74 +1,640 @@
 // General modules
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
 use std::process;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 // The cli module
 use clap::ArgMatches;
+use dbtranslate::ast::Statement;
+use dbtranslate::tokenizer::Tokenizer;
+
+// Watching the project for changes
+use notify::{EventKind, RecursiveMode, Watcher};
 
 // Multithreading
 use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::ParallelIterator;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 // Internal objects
-use crate::configuration::dbtonic_config::DbtonicConfig;
+use crate::configuration::dbtonic_config::{
+    DbtonicConfig, ParseErrorBehavior, SqlDialect, CONFIG_FILE_NAME, DEFAULT_CONFIG,
+};
+use crate::configuration::schema;
 use crate::parser::dag::DAG;
-use crate::rules::rules_engine::{RulesEngine,RuleResult};
+use crate::parser::dbt_project_yaml::DbtProjectYaml;
+use crate::validation::dbt_project_operations::DbtProject;
+use crate::parser::model_node::ModelNode;
+use crate::parser::model_selection::Selection;
+use crate::rules::rules_engine::{Plugin, RulesEngine, RulesEngines, Severity, Violation};
+use crate::rules::edits::{apply_edits, diff, without_overlaps};
+use crate::reporting::report::{ModelReport, Report, SkippedModel};
+use crate::reporting::sinks::{create_sinks, use_color};
+use crate::reporting::diagnostics::{create_renderer, Diagnostics, OutputFormat};
+use crate::reporting::coverage::ParseCoverage;
+use crate::reporting::lint;
+use crate::reporting::lineage_graph::{self, GraphFormat};
+use crate::reporting::model_docs;
+use crate::translate;
+use crate::reporting::timing::{RuleTiming, TimingReport};
+use crate::cache::parse_cache::{self, ParseCache};
+use crate::stats::git_history::GitHistory;
+use crate::stats::project_stats::ProjectStats;
+use crate::parser::ast_query::Selector;
+use crate::lsp::server as lsp_server;
+
+pub fn evaluate(evaluate_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject, plugins: &[Plugin]) {
+    // Read the config file
+    let config = read_config(evaluate_matches, config_path);
+    let selection = read_selection(evaluate_matches);
+
+    let report = create_report(
+        &config,
+        project,
+        evaluate_matches.value_of("model"),
+        &selection,
+        evaluate_matches.is_present("timing"),
+        !evaluate_matches.is_present("no-cache"),
+        false,
+        plugins,
+    );
+
+    // Hand the results to every configured sink. A failing sink shouldn't
+    // stop the others from getting the results.
+    let format = read_format(evaluate_matches, OutputFormat::Pretty);
+    let diagnostics = Diagnostics::from_report(&report).relative_to(&project.root).with_sources();
+    for sink in create_sinks(&config.output.sinks, config.output.max_locations, format) {
+        if let Err(e) = sink.write(&diagnostics) {
+            eprintln!("Error writing results to {}: {}", sink.name(), e);
+        }
+    }
+
+    if config.parsing.fail_on_skipped && !report.skipped.is_empty() {
+        process::exit(1);
+    }
+
+}
+
+pub fn lint(lint_matches: &ArgMatches, config_path: Option<&Path>, run_dir: &Path, project: &DbtProject, plugins: &[Plugin]) {
+    let config = read_config(lint_matches, config_path);
+
+    // clap only lets through the values Severity knows about
+    let fail_level: Severity = lint_matches
+        .value_of("fail-level")
+        .unwrap_or("error")
+        .parse()
+        .unwrap_or(Severity::Error);
+
+    // clap only takes `--filename` along with `--stdin`
+    let report = match lint_matches.value_of("filename") {
+        Some(filename) => create_stdin_report(&config, project, resolve_file(run_dir, filename), plugins),
+        None => {
+            let files: Vec<PathBuf> = lint_matches
+                .values_of("files")
+                .map(|files| files.map(|file| resolve_file(run_dir, file)).collect())
+                .unwrap_or_default();
+            create_report(
+                &config,
+                project,
+                lint_matches.value_of("model"),
+                &read_selection(lint_matches).with_files(files),
+                false,
+                !lint_matches.is_present("no-cache"),
+                lint_matches.is_present("compiled"),
+                plugins,
+            )
+        }
+    };
+    print_lint_report(&report, &project.root, &config, read_format(lint_matches, OutputFormat::Plain), fail_level);
+}
+
+// Exits with a non-zero code when the report fails at `fail_level`
+fn print_lint_report(report: &Report, root: &Path, config: &DbtonicConfig, format: OutputFormat, fail_level: Severity) {
+    let renderer = create_renderer(format, config.output.max_locations, use_color());
+    println!("{}", renderer.render(&Diagnostics::from_report(report).relative_to(root)));
+
+    if lint::fails(report, fail_level) || (config.parsing.fail_on_skipped && !report.skipped.is_empty()) {
+        process::exit(1);
+    }
+}
+
+// Runs `dbt compile`, then with `--compiled` lints the SQL it rendered
+// like `lint --compiled` does
+pub fn compile(compile_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject, plugins: &[Plugin]) {
+    project.check_dbt_version();
+    project.run_dbt_compile();
+    if !compile_matches.is_present("compiled") {
+        return;
+    }
+
+    let config = read_config(compile_matches, config_path);
+    let report = create_report(&config, project, None, &Selection::default(), false, true, true, plugins);
+    print_lint_report(&report, &project.root, &config, OutputFormat::Plain, Severity::Error);
+}
+
+pub fn fix(fix_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject, plugins: &[Plugin]) {
+    let config = read_config(fix_matches, config_path);
+    let selection = read_selection(fix_matches);
+    let dag = DAG::create(fix_matches.value_of("model"), project, &config.parsing, config.dialect);
+    let selected = selection.models(&dag.lintable_nodes(&config.resources), &dag.lineage, &project.root);
+    let parsed_models: Vec<&ModelNode> = dag.lintable_nodes(&config.resources)
+        .into_iter()
+        .filter(|model_node| !model_node.parse_failed() && selected.contains(&model_node.model_name))
+        .collect();
+    let rules_engines = RulesEngines::create(&config, plugins, project, &parsed_models)
+        .with_lineage(&dag.lineage)
+        .with_singular_tests(&dag.singular_tests);
+    let dry_run = fix_matches.is_present("dry-run");
+
+    let mut fixed_models = 0;
+    let mut fixed_edits = 0;
+    for model_node in parsed_models {
+        let fixes = rules_engines.for_model(model_node).run_fixes(model_node);
+        let rule_names: Vec<String> = fixes
+            .iter()
+            .map(|(rule_name, edits)| format!("{} ({})", rule_name, edits.len()))
+            .collect();
+        let edits = without_overlaps(fixes.into_iter().flat_map(|(_, edits)| edits).collect());
+
+        let sql = &model_node.data.sql;
+        let fixed = apply_edits(sql, &edits);
+        if &fixed == sql {
+            continue;
+        }
+        fixed_models += 1;
+        fixed_edits += edits.len();
+
+        if dry_run {
+            println!("{}", diff(&model_node.path.display().to_string(), sql, &fixed));
+        } else if let Err(e) = fs::write(&model_node.path, fixed) {
+            eprintln!("Error writing {}: {}", model_node.path.display(), e);
+            process::exit(1);
+        } else {
+            println!("Fixed {}: {}", model_node.model_name, rule_names.join(", "));
+        }
+    }
+
+    let verb = if dry_run { "Would fix" } else { "Fixed" };
+    println!("{} {} issue(s) in {} model(s)", verb, fixed_edits, fixed_models);
+}
+
+// Parses the project (or a single model) and runs every rule on the
+// selected models. Project rules still see the whole project, though only
+// their violations in the selected models are kept. With `cache`,
+// models that haven't changed since the last run reuse its violations
+// instead of running the per-model rules again. With `compiled`, the rules
+// run on the SQL `dbt compile` rendered instead of the models' Jinja.
+#[allow(clippy::too_many_arguments)]
+fn create_report(
+    config: &DbtonicConfig,
+    project: &DbtProject,
+    model: Option<&str>,
+    selection: &Selection,
+    timing: bool,
+    cache: bool,
+    compiled: bool,
+    plugins: &[Plugin],
+) -> Report {
+    let start = Instant::now();
 
-pub fn evaluate(evaluate_matches: &ArgMatches) {
     // Instantiate the DAG
-    let dag = DAG::create(evaluate_matches.value_of("model"));
+    let (mut dag, dag_timings) = DAG::create_timed(model, project, &config.parsing, config.dialect);
+    if compiled {
+        let count = dag.use_compiled_sql(&project.root, &config.parsing, config.dialect);
+        eprintln!("Linting the compiled SQL of {} model(s), run `dbt compile` to update it", count);
+    }
+    let selected = selection.models(&dag.lintable_nodes(&config.resources), &dag.lineage, &project.root);
 
-    // Read the config file
-    let config = match DbtonicConfig::read() {
-        Ok(config) => config,
-        Err(e) => {
-            eprintln!("Error reading dbtonic.toml: {:?}", e);
+    // Models the parser couldn't handle either stop the run or are skipped
+    if config.parsing.on_error == ParseErrorBehavior::Abort {
+        let failed_model = dag.lintable_nodes(&config.resources)
+            .into_iter()
+            .find(|model_node| selected.contains(&model_node.model_name) && model_node.parse_failed());
+        if let Some(model_node) = failed_model {
+            eprintln!("Error parsing model {}: {}", model_node.model_name, model_node.parse_errors());
             process::exit(1);
         }
+    }
+
+    // Timing every rule means running every rule, so `--timing` skips the cache
+    // The cache only knows the models' files, not whether they were compiled
+    let rules_run = run_rules(config, project, &dag, &selected, cache && !timing && !compiled, compiled, plugins);
+    if !timing {
+        return rules_run.report;
+    }
+    rules_run.report.with_timing(TimingReport::create(
+        start.elapsed(),
+        dag_timings.models,
+        dag_timings.resolution,
+        rules_run.model_rule_timings,
+        rules_run.project_rule_timings,
+    ))
+}
+
+// Lints the SQL piped to stdin as the model at `path`, so editors can lint
+// unsaved buffers. Only the per-model rules run, since the rest of the
+// project is still read from disk.
+fn create_stdin_report(config: &DbtonicConfig, project: &DbtProject, path: PathBuf, plugins: &[Plugin]) -> Report {
+    let sql = read_stdin();
+    let dag = DAG::create(None, project, &config.parsing, config.dialect);
+    let Some(model_node) = dag.model_from_sql(path, &sql, &config.resources, &config.parsing, config.dialect, &project.root) else {
+        return Report::create(vec![], vec![], vec![]);
     };
 
-    // Create the RuleRunner
-    let rules_engine = RulesEngine::create(&config);
+    if model_node.parse_failed() {
+        if config.parsing.on_error == ParseErrorBehavior::Abort {
+            eprintln!("Error parsing model {}: {}", model_node.model_name, model_node.parse_errors());
+            process::exit(1);
+        }
+        return Report::create(vec![], vec![], vec![SkippedModel::create(&model_node)]);
+    }
+
+    let rules_engines = RulesEngines::create(config, plugins, project, &[&model_node])
+        .with_lineage(&dag.lineage)
+        .with_singular_tests(&dag.singular_tests);
+    let violations = rules_engines.for_model(&model_node).run_violations(&model_node);
+    let model_report = ModelReport { model_name: model_node.model_name.clone(), path: model_node.path.clone(), violations };
+    Report::create(vec![model_report], vec![], vec![])
+}
+
+fn read_stdin() -> String {
+    match std::io::read_to_string(std::io::stdin()) {
+        Ok(sql) => sql,
+        Err(e) => {
+            eprintln!("Error reading stdin: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+struct RulesRun {
+    report: Report,
+    model_rule_timings: HashMap<String, Vec<RuleTiming>>,
+    project_rule_timings: Vec<RuleTiming>,
+}
+
+// Runs the per-model rules on the selected models and the project rules on
+// the whole DAG, keeping the project violations in the selected models or in
+// none. Models that failed to parse are reported as skipped.
+fn run_rules(
+    config: &DbtonicConfig,
+    project: &DbtProject,
+    dag: &DAG,
+    selected: &BTreeSet<String>,
+    cache: bool,
+    compiled: bool,
+    plugins: &[Plugin],
+) -> RulesRun {
+    let base_path = &project.root;
+    let (skipped_models, parsed_models): (Vec<&ModelNode>, Vec<&ModelNode>) = dag.lintable_nodes(&config.resources)
+        .into_iter()
+        .filter(|model_node| selected.contains(&model_node.model_name))
+        .partition(|model_node| model_node.parse_failed());
+
+    // Create the RuleRunners, one for each set of `[overrides]` the models match
+    let mut rules_engines = RulesEngines::create(config, plugins, project, &parsed_models)
+        .with_lineage(&dag.lineage)
+        .with_singular_tests(&dag.singular_tests);
+    if compiled {
+        rules_engines = rules_engines.for_compiled_sql();
+    }
+
+    let parse_cache = cache.then(|| ParseCache::new(base_path));
+    let project_key = parse_cache::project_key(
+        base_path,
+        config.path.as_deref(),
+        config.dialect,
+        &rules_engines.project().rule_names(),
+    );
+    let cached_models = AtomicUsize::new(0);
+
+    // Run the rules that look at the whole project first, so the models'
+    // `dbtonic:ignore` comments can cover the violations they point at
+    let (mut project_violations, project_rule_timings) = rules_engines.project().run_project_rules_timed(dag);
+
+    // The rules see the whole project, but only what's in the selected
+    // models is reported. Violations that aren't in a model always are.
+    let unselected: BTreeSet<&String> = dag.lintable_nodes(&config.resources)
+        .into_iter()
+        .map(|model_node| &model_node.model_name)
+        .filter(|model_name| !selected.contains(*model_name))
+        .collect();
+    project_violations.retain(|violation| violation.model.as_ref().is_none_or(|model| !unselected.contains(model)));
+    let mut project_violations_by_path: HashMap<&Path, Vec<Violation>> = HashMap::new();
+    for violation in &project_violations {
+        if let Some(path) = &violation.path {
+            project_violations_by_path.entry(path).or_default().push(violation.clone());
+        }
+    }
 
-    // Run the rules on each of the models in the DAG using multi-threading
-    let rules_engine_arc = Arc::new(rules_engine);
-    let results: Vec<_> = dag.model_nodes
+    // Run the rules on each of the models in the DAG using multi-threading.
+    // What's cached is before suppressing, since the project violations
+    // aren't.
+    let rules_engines_arc = Arc::new(rules_engines);
+    let model_runs: Vec<(ModelReport, Vec<RuleTiming>, Vec<Violation>)> = parsed_models
         .par_iter()
         .map(|model_node| {
-            let rule_results = rules_engine_arc.run_rules(model_node);
-            (model_node.model_name.clone(), rule_results)
+            let rules_engine = rules_engines_arc.for_model(model_node);
+            let model_key = parse_cache::model_key(&project_key, model_node, rules_engines_arc.is_leaf_model(model_node));
+            let cached = parse_cache.as_ref().and_then(|parse_cache| parse_cache.read_violations(&model_node.path, &model_key));
+            let (violations, rule_timings) = match cached {
+                Some(violations) => {
+                    cached_models.fetch_add(1, Ordering::Relaxed);
+                    (violations, vec![])
+                }
+                None => {
+                    let (violations, rule_timings) = rules_engine.run_violations_timed(model_node);
+                    if let Some(parse_cache) = &parse_cache {
+                        // A cache that can't be written only costs the next run some time
+                        let _ = parse_cache.write_violations(&model_node.path, &model_key, &violations);
+                    }
+                    (violations, rule_timings)
+                }
+            };
+
+            // Project rules set the model on their violations, which is how
+            // they're told apart again after suppressing
+            let project_violations = project_violations_by_path.get(model_node.path.as_path()).cloned().unwrap_or_default();
+            let (kept_project_violations, violations): (Vec<Violation>, Vec<Violation>) = rules_engine
+                .suppress(model_node, violations.into_iter().chain(project_violations).collect())
+                .into_iter()
+                .partition(|violation| violation.model.is_some());
+
+            let model_report = ModelReport { model_name: model_node.model_name.clone(), path: model_node.path.clone(), violations };
+            (model_report, rule_timings, kept_project_violations)
         })
         .collect();
 
-    // Print the results
-    for (model_name, rule_results) in results {
-        let failed_results: Vec<_> = rule_results.into_iter().filter(|(_, result)| matches!(result, RuleResult::Fail(_))).collect();
-    
-        if !failed_results.is_empty() {
-            println!("Results for model: {}", model_name);
-            for (rule_name, result) in failed_results {
-                if let RuleResult::Fail(message) = result {
-                    println!("  {}: FAIL\n    Reason: {}", rule_name, message);
+    let cached_models = cached_models.into_inner();
+    if cached_models > 0 {
+        eprintln!("{} unchanged model(s) reused their results from .dbtonic/cache", cached_models);
+    }
+
+    // Drop the project violations a model suppressed, keeping the order the
+    // project rules reported them in. What each model kept is in that order
+    // too, so it can be matched off the front.
+    let mut models = vec![];
+    let mut model_rule_timings = HashMap::new();
+    let mut kept_by_path: HashMap<PathBuf, VecDeque<Violation>> = HashMap::new();
+    for (model_report, rule_timings, kept_project_violations) in model_runs {
+        kept_by_path.insert(model_report.path.clone(), kept_project_violations.into());
+        model_rule_timings.insert(model_report.model_name.clone(), rule_timings);
+        models.push(model_report);
+    }
+    project_violations.retain(|violation| match violation.path.as_ref().and_then(|path| kept_by_path.get_mut(path)) {
+        Some(kept) if kept.front() == Some(violation) => {
+            kept.pop_front();
+            true
+        }
+        Some(_) => false,
+        None => true,
+    });
+
+    // Keep the models we couldn't parse so they don't silently disappear
+    let skipped: Vec<SkippedModel> = skipped_models.iter().map(|model_node| SkippedModel::create(model_node)).collect();
+
+    RulesRun {
+        report: Report::create(models, project_violations, skipped),
+        model_rule_timings,
+        project_rule_timings,
+    }
+}
+
+fn read_config(matches: &ArgMatches, config_path: Option<&Path>) -> DbtonicConfig {
+    match DbtonicConfig::read(config_path) {
+        Ok(config) => with_flags(config, matches),
+        Err(e) => {
+            eprintln!("Error reading {}: {:?}", config_name(config_path), e);
+            process::exit(1);
+        }
+    }
+}
+
+// The config file, for errors
+fn config_name(config_path: Option<&Path>) -> String {
+    match config_path {
+        Some(config_path) => config_path.display().to_string(),
+        None => CONFIG_FILE_NAME.to_string(),
+    }
+}
+
+// Flags that win over what dbtonic.toml says
+fn with_flags(mut config: DbtonicConfig, matches: &ArgMatches) -> DbtonicConfig {
+    if let Some(dialect) = matches.value_of("dialect") {
+        config.dialect = SqlDialect::from_name(dialect);
+    }
+    if matches.is_present("render") {
+        config.parsing.render = true;
+    }
+    config
+}
+
+// `--select` and `--exclude`, which every model passes when they're left out
+fn read_selection(matches: &ArgMatches) -> Selection {
+    let values = |name: &str| -> Vec<&str> { matches.values_of(name).map(|values| values.collect()).unwrap_or_default() };
+    match Selection::parse(&values("select"), &values("exclude")) {
+        Ok(selection) => selection,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+// `--format`, or `default` when it's left out
+fn read_format(matches: &ArgMatches, default: OutputFormat) -> OutputFormat {
+    let Some(name) = matches.value_of("format") else {
+        return default;
+    };
+    match name.parse() {
+        Ok(format) => format,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+// A file passed on the command line, as an absolute path to compare with the
+// models' paths. Files that aren't on disk yet, like an editor's new buffer,
+// can't be canonicalized so their `..`s are resolved by hand.
+fn resolve_file(run_dir: &Path, file: &str) -> PathBuf {
+    let path = run_dir.join(file);
+    fs::canonicalize(&path).unwrap_or_else(|_| {
+        let mut resolved = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::ParentDir => {
+                    resolved.pop();
                 }
+                Component::CurDir => {}
+                component => resolved.push(component),
             }
         }
+        resolved
+    })
+}
+
+pub fn watch(watch_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject, plugins: &[Plugin]) {
+    let selection = read_selection(watch_matches);
+    let base_path = project.root.clone();
+    let mut project = project.clone();
+    let mut config = read_config(watch_matches, config_path);
+    let config_path = config_path.map(Path::to_path_buf);
+    let mut dag = DAG::create(None, &project, &config.parsing, config.dialect);
+
+    let (sender, receiver) = mpsc::channel();
+    let watcher = notify::recommended_watcher(sender).and_then(|mut watcher| {
+        let project = &dag.project;
+        let dirs = project
+            .model_paths
+            .iter()
+            .chain(&project.test_paths)
+            .chain(&project.analysis_paths)
+            .chain(&project.snapshot_paths)
+            .chain(&project.seed_paths)
+            .chain(&project.macro_paths);
+        for dir in dirs.map(|dir| base_path.join(dir)).filter(|dir| dir.is_dir()) {
+            watcher.watch(&dir, RecursiveMode::Recursive)?;
+        }
+        // For dbtonic.toml and dbt_project.yml. Editors often save by
+        // replacing the file, which would end a watch on the file itself.
+        watcher.watch(&base_path, RecursiveMode::NonRecursive)?;
+        if let Some(config_dir) = config_path.as_deref().and_then(Path::parent).filter(|dir| *dir != base_path) {
+            watcher.watch(config_dir, RecursiveMode::NonRecursive)?;
+        }
+        Ok(watcher)
+    });
+    // Dropping the watcher stops the notifications, so keep it alive
+    let _watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("Error watching {}: {}", base_path.display(), e);
+            process::exit(1);
+        }
+    };
+
+    let selected = selection.models(&dag.lintable_nodes(&config.resources), &dag.lineage, &base_path);
+    println!("{}", lint::render(&run_rules(&config, &project, &dag, &selected, true, false, plugins).report, &project.root));
+
+    loop {
+        eprintln!("Watching for changes, press Ctrl-C to stop");
+        let Some(changed_paths) = next_changes(&receiver, &dag.project, &base_path, config_path.as_deref()) else {
+            return;
+        };
+        let names: Vec<String> = changed_paths
+            .iter()
+            .map(|path| path.strip_prefix(&base_path).unwrap_or(path).display().to_string())
+            .collect();
+        println!("\nChanged: {}", names.join(", "));
+
+        // The config and dbt_project.yml can change the results of every
+        // model, so start over. Otherwise only re-parse what changed.
+        let to_lint = if changed_paths.iter().any(|path| is_project_file(path, &base_path, config_path.as_deref())) {
+            // A dbtonic.toml may have been created in the project's root
+            let current_config_path = config_path.clone().or_else(|| DbtonicConfig::find(&base_path));
+            config = match DbtonicConfig::read(current_config_path.as_deref()) {
+                Ok(config) => with_flags(config, watch_matches),
+                Err(e) => {
+                    eprintln!("Error reading {}, fix it to carry on: {:?}", config_name(current_config_path.as_deref()), e);
+                    continue;
+                }
+            };
+            project = match DbtProject::load(&base_path) {
+                Ok(project) => project,
+                Err(e) => {
+                    eprintln!("{}, fix it to carry on", e);
+                    continue;
+                }
+            };
+            dag = DAG::create(None, &project, &config.parsing, config.dialect);
+            selection.models(&dag.lintable_nodes(&config.resources), &dag.lineage, &base_path)
+        } else {
+            let changed = dag.refresh(&base_path, &changed_paths, &config.parsing, config.dialect);
+            let selected = selection.models(&dag.lintable_nodes(&config.resources), &dag.lineage, &base_path);
+            changed.intersection(&selected).cloned().collect()
+        };
+        println!("{}", lint::render(&run_rules(&config, &project, &dag, &to_lint, true, false, plugins).report, &project.root));
     }
+}
 
+// Blocks until a model, yml or config file changes, then waits for the
+// burst of events editors write a file with to settle. `None` once the
+// watcher has stopped.
+fn next_changes(
+    receiver: &mpsc::Receiver<notify::Result<notify::Event>>,
+    project: &DbtProjectYaml,
+    base_path: &Path,
+    config_path: Option<&Path>,
+) -> Option<Vec<PathBuf>> {
+    loop {
+        let mut changed_paths = BTreeSet::new();
+        let mut next = Some(receiver.recv().ok()?);
+        while let Some(result) = next {
+            // Reads don't change anything, and errors leave nothing to re-lint
+            if let Some(event) = result.ok().filter(|event| !matches!(event.kind, EventKind::Access(_))) {
+                changed_paths.extend(event.paths.into_iter().filter(|path| is_watched(path, project, base_path, config_path)));
+            }
+            next = receiver.recv_timeout(Duration::from_millis(100)).ok();
+        }
+        if !changed_paths.is_empty() {
+            return Some(changed_paths.into_iter().collect());
+        }
+    }
+}
+
+fn is_watched(path: &Path, project: &DbtProjectYaml, base_path: &Path, config_path: Option<&Path>) -> bool {
+    let is_resource_file = (project.resource_type(path, base_path).is_some() || project.is_macro_path(path, base_path))
+        && path.extension().is_some_and(|extension| extension == "sql" || extension == "yml" || extension == "yaml");
+    is_resource_file || project.is_seed_path(path, base_path) || is_project_file(path, base_path, config_path)
+}
+
+// Includes a dbtonic.toml created in the project's root while watching
+fn is_project_file(path: &Path, base_path: &Path, config_path: Option<&Path>) -> bool {
+    Some(path) == config_path || path == base_path.join(CONFIG_FILE_NAME) || path == base_path.join("dbt_project.yml")
+}
+
+// The editor starts the server, so everything but the protocol goes to
+// stderr, where editors log it
+pub fn lsp(lsp_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject, plugins: &[Plugin]) {
+    let config = read_config(lsp_matches, config_path);
+    if let Err(e) = lsp_server::run(config, project.clone(), plugins) {
+        eprintln!("Error running the language server: {}", e);
+        process::exit(1);
+    }
 }
 
-pub fn get_ast(get_ast_matches: &ArgMatches) {
+pub fn get_ast(get_ast_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject) {
+    let config = read_config(get_ast_matches, config_path);
+    let json = get_ast_matches.value_of("format") == Some("json");
+
+    // SQL piped to stdin doesn't need the rest of the project
+    if get_ast_matches.is_present("stdin") {
+        let model_node = ModelNode::from_sql(PathBuf::from("stdin"), read_stdin(), &config.parsing, config.dialect);
+        if model_node.parse_failed() {
+            eprintln!("Error parsing stdin: {}", model_node.parse_errors());
+            process::exit(1);
+        }
+        print_ast("stdin", &model_node.data.ast, json);
+        return;
+    }
 
     // Initialize the DAG
-    let dag = DAG::create(get_ast_matches.value_of("model"));
+    let dag = DAG::create(get_ast_matches.value_of("model"), project, &config.parsing, config.dialect);
 
     // Find the model node for the specified model
     if let Some(model_name) = get_ast_matches.value_of("model") {
         if let Some(model_node) = dag.model_nodes.iter().find(|node| node.model_name == model_name) {
-            // Print the AST for the specified model
-            println!("AST for model: {}", model_name);
-            for stmt in &model_node.data.ast {
-                println!("{:#?}", stmt);
+            if model_node.parse_failed() {
+                eprintln!("Error parsing model {}: {}", model_name, model_node.parse_errors());
+                process::exit(1);
             }
+
+            print_ast(&format!("model: {}", model_name), &model_node.data.ast, json);
         } else {
             eprintln!("Error: Model not found: {}", model_name);
             process::exit(1);
@@ -79,18 +645,40 @@ pub fn get_ast(get_ast_matches: &ArgMatches) {
     }
 }
 
-pub fn get_tokens(get_tokens_matches: &ArgMatches) {
+// JSON is printed on its own, so it can be piped straight into another tool
+fn print_ast(label: &str, statements: &[Statement], json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(statements).expect("the AST always serializes"));
+        return;
+    }
+    println!("AST for {}", label);
+    for stmt in statements {
+        println!("{:#?}", stmt);
+    }
+}
+
+pub fn get_tokens(get_tokens_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject) {
 
     // Initialize the DAG
-    let dag = DAG::create(get_tokens_matches.value_of("model"));
+    let config = read_config(get_tokens_matches, config_path);
+    let dag = DAG::create(get_tokens_matches.value_of("model"), project, &config.parsing, config.dialect);
 
     // Find the model node for the specified model
     if let Some(model_name) = get_tokens_matches.value_of("model") {
         if let Some(model_node) = dag.model_nodes.iter().find(|node| node.model_name == model_name) {
-            // Print the AST for the specified model
+            // Models don't keep their tokens once they're parsed, so the
+            // SQL is tokenized again here
+            let dialect = config.dialect.parser_dialect();
+            let tokens = match Tokenizer::new(&*dialect, &model_node.data.sql).tokenize_with_location() {
+                Ok(tokens) => tokens,
+                Err(e) => {
+                    eprintln!("Error tokenizing {}: {}", model_name, e);
+                    process::exit(1);
+                }
+            };
             println!("Tokens for model: {}", model_name);
-            for tokens in &model_node.data.tokens {
-                println!("{:#?}", tokens);
+            for token in &tokens {
+                println!("{:#?}", token.token);
             }
         } else {
             eprintln!("Error: Model not found: {}", model_name);
@@ -100,4 +688,313 @@ pub fn get_tokens(get_tokens_matches: &ArgMatches) {
         eprintln!("Error: No model specified");
         process::exit(1);
     }
-}
\ No newline at end of file
+}
+
+pub fn coverage(coverage_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject) {
+    let config = read_config(coverage_matches, config_path);
+    let dag = DAG::create(coverage_matches.value_of("model"), project, &config.parsing, config.dialect);
+
+    let base_path = &project.root;
+    println!("{}", ParseCoverage::from_models(&dag.model_nodes, base_path));
+
+    // List the models the parser couldn't handle so gaps can be reported
+    for model_node in dag.model_nodes.iter().filter(|model_node| model_node.parse_failed()) {
+        println!("Failed model: {}", model_node.path.strip_prefix(base_path).unwrap_or(&model_node.path).display());
+        println!("  Reason: {}", model_node.parse_errors());
+    }
+}
+
+pub fn query(query_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject) {
+    let selector = match Selector::parse(query_matches.value_of("selector").unwrap_or_default()) {
+        Ok(selector) => selector,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let config = read_config(query_matches, config_path);
+    let dag = DAG::create(query_matches.value_of("model"), project, &config.parsing, config.dialect);
+
+    // Print matches grep style, as `path:line:column: node`, with paths
+    // relative to the project
+    let mut total_matches = 0;
+    for model_node in &dag.model_nodes {
+        let path = model_node.path.strip_prefix(&project.root).unwrap_or(&model_node.path).display();
+        for node in selector.select(&model_node.data.ast) {
+            if node.span.is_empty() {
+                println!("{}: {}", path, node.text);
+            } else {
+                println!("{}:{}:{}: {}", path, node.span.start.line, node.span.start.column, node.text);
+            }
+            total_matches += 1;
+        }
+    }
+    println!("{} {}", total_matches, if total_matches == 1 { "match" } else { "matches" });
+}
+
+pub fn translate(translate_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject) {
+    let config = read_config(translate_matches, config_path);
+    let to = SqlDialect::from_name(translate_matches.value_of("to").unwrap_or_default());
+    let model = translate_matches.value_of("model");
+    let dag = DAG::create(model, project, &config.parsing, config.dialect);
+    let base_path = &project.root;
+
+    let model_nodes: Vec<&ModelNode> = dag.model_nodes.iter().filter(|model_node| model.is_none_or(|model| model_node.model_name == model)).collect();
+    if let (Some(model), true) = (model, model_nodes.is_empty()) {
+        eprintln!("Error: Model not found: {}", model);
+        process::exit(1);
+    }
+
+    let mut untranslatable = 0;
+    for model_node in model_nodes {
+        let relative_path = model_node.path.strip_prefix(base_path).unwrap_or(&model_node.path);
+        // Statements that didn't parse would be dropped from the translation
+        if model_node.data.errors.is_some() {
+            eprintln!("Skipped {}: {}", relative_path.display(), model_node.parse_errors());
+            continue;
+        }
+        let translation = translate::translate(model_node.data.ast.clone(), config.dialect, to);
+        for construct in &translation.untranslatable {
+            eprintln!("{}: can't translate {}: {}", relative_path.display(), construct.sql, construct.reason);
+        }
+        untranslatable += translation.untranslatable.len();
+
+        match translate_matches.value_of("output-dir") {
+            Some(output_dir) => {
+                let output_path = project.root.join(output_dir).join(relative_path);
+                let written = output_path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(&output_path, format!("{}\n", translation.sql)));
+                if let Err(e) = written {
+                    eprintln!("Error writing {}: {}", output_path.display(), e);
+                    process::exit(1);
+                }
+            }
+            None => println!("-- {}\n{}\n", relative_path.display(), translation.sql),
+        }
+    }
+    if untranslatable > 0 {
+        eprintln!("{} construct(s) have no equivalent in {} and were left as they are", untranslatable, to.name());
+    }
+}
+
+pub fn lineage(lineage_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject) {
+    let model_name = lineage_matches.value_of("model").unwrap_or_default();
+    let config = read_config(lineage_matches, config_path);
+    let dag = DAG::create(None, project, &config.parsing, config.dialect);
+
+    for cycle in dag.lineage.cycles() {
+        let names: Vec<String> = cycle.iter().map(|node| node.to_string()).collect();
+        eprintln!("Warning: {} ref each other in a cycle", names.join(", "));
+    }
+
+    if !dag.model_nodes.iter().any(|model_node| model_node.model_name == model_name) {
+        eprintln!("Error: there is no model called '{}'", model_name);
+        process::exit(1);
+    }
+
+    let upstream = dag.lineage.ancestors(model_name);
+    let downstream = dag.lineage.descendants(model_name);
+    if !lineage_matches.is_present("downstream") {
+        println!("Upstream of {} ({}):", model_name, upstream.len());
+        for node in &upstream {
+            println!("  {}", node);
+        }
+    }
+    if !lineage_matches.is_present("upstream") {
+        println!("Downstream of {} ({}):", model_name, downstream.len());
+        for node in &downstream {
+            println!("  {}", node);
+        }
+    }
+}
+
+pub fn dag(dag_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject) {
+    let config = read_config(dag_matches, config_path);
+    let dag = DAG::create(None, project, &config.parsing, config.dialect);
+    let format = GraphFormat::from_name(dag_matches.value_of("format").unwrap_or("dot"));
+    println!("{}", lineage_graph::render(&dag.lineage, format));
+}
+
+// Writes a Markdown page per selected model, and an index linking them,
+// to the output directory
+pub fn docs(docs_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject, plugins: &[Plugin]) {
+    let config = read_config(docs_matches, config_path);
+    let selection = read_selection(docs_matches);
+    let output_dir = project.root.join(docs_matches.value_of("output-dir").unwrap_or("target/dbtonic_docs"));
+
+    let dag = DAG::create(None, project, &config.parsing, config.dialect);
+    let base_path = &project.root;
+    let selected = selection.models(&dag.lintable_nodes(&config.resources), &dag.lineage, base_path);
+    let report = run_rules(&config, project, &dag, &selected, true, false, plugins).report;
+    let violations: HashMap<&str, &[Violation]> = report
+        .models
+        .iter()
+        .map(|model_report| (model_report.model_name.as_str(), model_report.violations.as_slice()))
+        .collect();
+
+    if let Err(e) = fs::create_dir_all(&output_dir) {
+        eprintln!("Error creating {}: {}", output_dir.display(), e);
+        process::exit(1);
+    }
+    let mut model_pages = vec![];
+    for model_node in dag.model_nodes.iter().filter(|model_node| selected.contains(&model_node.model_name)) {
+        let model_violations = violations.get(model_node.model_name.as_str()).copied().unwrap_or_default();
+        let page = model_docs::render(model_node, &dag, model_violations, base_path);
+        let page_path = output_dir.join(format!("{}.md", model_node.model_name));
+        if let Err(e) = fs::write(&page_path, page) {
+            eprintln!("Error writing {}: {}", page_path.display(), e);
+            process::exit(1);
+        }
+        model_pages.push((model_node, model_violations.len()));
+    }
+
+    let index_path = output_dir.join("index.md");
+    if let Err(e) = fs::write(&index_path, model_docs::render_index(&model_pages)) {
+        eprintln!("Error writing {}: {}", index_path.display(), e);
+        process::exit(1);
+    }
+    println!("Wrote docs for {} model(s) to {}", model_pages.len(), output_dir.display());
+}
+
+pub fn stats(stats_matches: &ArgMatches, config_path: Option<&Path>, project: &DbtProject) {
+    let config = read_config(stats_matches, config_path);
+    let dag = DAG::create(None, project, &config.parsing, config.dialect);
+    let stats = ProjectStats::create(&dag, &config.rule_options.model_complexity);
+    let json = stats_matches.value_of("format") == Some("json");
+
+    if !stats_matches.is_present("git") {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&stats).expect("stats always serialize"));
+        } else {
+            println!("{}", stats.render());
+        }
+        return;
+    }
+
+    let months: i64 = parse_number_arg(stats_matches, "months");
+    let min_co_changes: usize = parse_number_arg(stats_matches, "min-co-changes");
+
+    let project_dir = &project.root;
+    let history = match GitHistory::read(project_dir, &project.yaml.model_paths) {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("Error reading git history: {}", e);
+            process::exit(1);
+        }
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default();
+
+    let stale_models = history.stale_models(&dag, project_dir, now, months);
+    let co_changed = history.co_changed_models(min_co_changes);
+    if json {
+        let mut value = serde_json::to_value(&stats).expect("stats always serialize");
+        value["stale_models"] = serde_json::json!(stale_models);
+        value["co_changed_models"] = serde_json::json!(co_changed);
+        println!("{}", serde_json::to_string_pretty(&value).expect("stats always serialize"));
+        return;
+    }
+
+    println!("{}", stats.render());
+    println!("\nModels unchanged for {}+ months that still have dependents: {}", months, stale_models.len());
+    for stale_model in stale_models {
+        println!(
+            "  {}: unchanged for {} months, used by {}",
+            stale_model.model_name,
+            stale_model.months_unchanged,
+            stale_model.dependents.join(", ")
+        );
+    }
+
+    println!("\nModels changed together in {}+ commits: {}", min_co_changes, co_changed.len());
+    for pair in co_changed {
+        println!(
+            "  {} and {}: {} commits, consider merging them",
+            pair.models.0, pair.models.1, pair.commits_together
+        );
+    }
+}
+
+fn parse_number_arg<T: std::str::FromStr>(matches: &ArgMatches, name: &str) -> T {
+    match matches.value_of(name).unwrap_or_default().parse() {
+        Ok(value) => value,
+        Err(_) => {
+            eprintln!("Error: --{} must be a number", name);
+            process::exit(1);
+        }
+    }
+}
+
+pub fn cache(cache_matches: &ArgMatches, project: &DbtProject) {
+    let cache = ParseCache::new(&project.root);
+
+    let result = match cache_matches.subcommand_name() {
+        Some("status") => cache.status().map(|status| {
+            println!("Cache: {}", cache.dir.display());
+            println!("  {} entries ({} stale), {} bytes", status.entries, status.stale_entries, status.size_bytes);
+        }),
+        Some("clear") => cache.clear().map(|removed| println!("Removed {} cache entries", removed)),
+        Some("prune") => cache.prune().map(|removed| println!("Removed {} stale cache entries", removed)),
+        _ => Ok(()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error managing the cache in {}: {}", cache.dir.display(), e);
+        process::exit(1);
+    }
+}
+
+// dbtonic.toml belongs at the project's root, but the config commands also
+// work outside a project, in the directory dbtonic was run from
+pub fn config(config_matches: &ArgMatches, config_path: Option<&Path>, run_dir: &Path, plugins: &[Plugin]) {
+    let base_path = DbtProject::find_root(run_dir).unwrap_or_else(|| run_dir.to_path_buf());
+    match config_matches.subcommand() {
+        Some(("validate", _)) => validate_config(config_path, &base_path, plugins),
+        Some(("init", init_matches)) => init_config(&base_path, init_matches.is_present("force")),
+        _ => {}
+    }
+}
+
+fn validate_config(config_path: Option<&Path>, base_path: &Path, plugins: &[Plugin]) {
+    let Some(config_path) = config_path else {
+        println!("There's no {}, so dbtonic uses its defaults. `dbtonic config init` writes them out.", CONFIG_FILE_NAME);
+        return;
+    };
+    let config_str = fs::read_to_string(config_path).unwrap_or_else(|e| {
+        eprintln!("Error reading {}: {}", config_path.display(), e);
+        process::exit(1);
+    });
+
+    let name = config_path.strip_prefix(base_path).unwrap_or(config_path).display().to_string();
+    let rule_names = RulesEngine::known_rule_names(&DbtonicConfig::default(), plugins);
+    let problems = schema::validate(&config_str, &rule_names);
+    for problem in &problems {
+        println!("{}: {}: {}", name, problem.severity.label(), problem.message);
+    }
+
+    let errors = problems.iter().filter(|problem| problem.severity == Severity::Error).count();
+    if problems.is_empty() {
+        println!("{} is valid", name);
+    } else {
+        println!("{} error(s), {} warning(s)", errors, problems.len() - errors);
+    }
+    if errors > 0 {
+        process::exit(1);
+    }
+}
+
+// Writes the default config to the project's root
+fn init_config(base_path: &Path, force: bool) {
+    let config_path = base_path.join(CONFIG_FILE_NAME);
+    if config_path.exists() && !force {
+        eprintln!("{} already exists, pass --force to overwrite it", config_path.display());
+        process::exit(1);
+    }
+    if let Err(e) = fs::write(&config_path, DEFAULT_CONFIG) {
+        eprintln!("Error writing {}: {}", config_path.display(), e);
+        process::exit(1);
+    }
+    println!("Wrote {}", config_path.display());
+}
@@ -1,24 +1,635 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use dbtranslate::dialect::{
+    BigQueryDialect, Dialect, DuckDbDialect, GenericDialect, PostgreSqlDialect, RedshiftSqlDialect, SnowflakeDialect,
+    TrinoDialect,
+};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use glob::Pattern;
+use crate::configuration::rule_options::{take_rule_blocks, RuleOptionOverrides, RuleOptions};
+use crate::reporting::sinks::SinkConfig;
+use crate::parser::ast_query::Selector;
+use crate::rules::rules_engine::Severity;
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, Clone)]
 pub struct DbtonicConfig {
+    // The warehouse the project runs on
+    #[serde(default)]
+    pub dialect: SqlDialect,
     pub rules: Rules,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default)]
+    pub parsing: ParsingConfig,
+    // Rules written as AST selectors, e.g. `functions[name=datediff]`
+    #[serde(default)]
+    pub custom_rules: Vec<CustomRuleConfig>,
+    // Functions the forbidden_functions rule reports, with why
+    #[serde(default)]
+    pub forbidden_functions: Vec<ForbiddenFunctionConfig>,
+    // Overrides the severity of a rule, keyed by the rule's name
+    #[serde(default)]
+    pub severity: HashMap<String, Severity>,
+    // The options in the `[rules.<name>]` blocks, which parse() takes out
+    // of `[rules]`
+    #[serde(skip)]
+    pub rule_options: RuleOptions,
+    // Whether the rules also run on singular tests, analyses and snapshots
+    #[serde(default)]
+    pub resources: ResourcesConfig,
+    // Rule settings for the models under particular paths, least specific
+    // first
+    #[serde(default, deserialize_with = "deserialize_overrides")]
+    pub overrides: Vec<PathOverride>,
+    // The file the config was read from, if there was one
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SqlDialect {
+    #[default]
+    Generic,
+    Postgres,
+    Redshift,
+    Snowflake,
+    Bigquery,
+    Duckdb,
+    Trino,
+}
+
+// What a warehouse does to the case of unquoted identifiers
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IdentifierFolding {
+    Lower,
+    Upper,
+    // Unquoted identifiers keep the case they were written in
+    None,
+}
+
+impl SqlDialect {
+    pub fn identifier_folding(&self) -> IdentifierFolding {
+        match self {
+            SqlDialect::Generic | SqlDialect::Postgres | SqlDialect::Redshift | SqlDialect::Duckdb | SqlDialect::Trino => {
+                IdentifierFolding::Lower
+            }
+            SqlDialect::Snowflake => IdentifierFolding::Upper,
+            SqlDialect::Bigquery => IdentifierFolding::None,
+        }
+    }
+
+    // The quotes the warehouse's own SQL uses for identifiers
+    pub fn quote_style(&self) -> QuoteStyle {
+        match self {
+            SqlDialect::Bigquery => QuoteStyle::Backtick,
+            _ => QuoteStyle::Double,
+        }
+    }
+
+    // The dialect dbtranslate parses models with
+    pub fn parser_dialect(&self) -> Box<dyn Dialect> {
+        match self {
+            SqlDialect::Generic => Box::new(GenericDialect {}),
+            SqlDialect::Postgres => Box::new(PostgreSqlDialect {}),
+            SqlDialect::Redshift => Box::new(RedshiftSqlDialect {}),
+            SqlDialect::Snowflake => Box::new(SnowflakeDialect {}),
+            SqlDialect::Bigquery => Box::new(BigQueryDialect {}),
+            SqlDialect::Duckdb => Box::new(DuckDbDialect {}),
+            SqlDialect::Trino => Box::new(TrinoDialect {}),
+        }
+    }
+
+    // clap only lets through the names listed for `--dialect`
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "postgres" => SqlDialect::Postgres,
+            "redshift" => SqlDialect::Redshift,
+            "snowflake" => SqlDialect::Snowflake,
+            "bigquery" => SqlDialect::Bigquery,
+            "duckdb" => SqlDialect::Duckdb,
+            "trino" => SqlDialect::Trino,
+            _ => SqlDialect::Generic,
+        }
+    }
+
+    // The name `--dialect` and dbtonic.toml use for the dialect
+    pub fn name(&self) -> &'static str {
+        match self {
+            SqlDialect::Generic => "generic",
+            SqlDialect::Postgres => "postgres",
+            SqlDialect::Redshift => "redshift",
+            SqlDialect::Snowflake => "snowflake",
+            SqlDialect::Bigquery => "bigquery",
+            SqlDialect::Duckdb => "duckdb",
+            SqlDialect::Trino => "trino",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct Rules {
     pub unique_not_null_or_combination_rule: bool,
     pub model_yaml_exists: bool,
+    #[serde(default = "default_true")]
+    pub model_has_tests: bool,
+    #[serde(default = "default_true")]
+    pub exposure_validation: bool,
+    #[serde(default = "default_true")]
+    pub semantic_layer_references: bool,
+    // Off by default, since its violations are errors and would fail
+    // projects that lint today
+    #[serde(default)]
+    pub multiple_statements: bool,
+    #[serde(default = "default_true")]
+    pub trailing_semicolon: bool,
+    #[serde(default = "default_true")]
+    pub explicit_aliases: bool,
+    #[serde(default = "default_true")]
+    pub identifier_quoting: bool,
+    #[serde(default = "default_true")]
+    pub duplicate_logic: bool,
+    #[serde(default = "default_true")]
+    pub no_env_var: bool,
+    #[serde(default = "default_true")]
+    pub ref_aliases: bool,
+    #[serde(default = "default_true")]
+    pub no_select_star: bool,
+    #[serde(default = "default_true")]
+    pub hardcoded_references: bool,
+    #[serde(default = "default_true")]
+    pub cross_database_references: bool,
+    #[serde(default = "default_true")]
+    pub ambiguous_columns: bool,
+    #[serde(default = "default_true")]
+    pub no_implicit_cross_joins: bool,
+    #[serde(default = "default_true")]
+    pub duplicate_column_aliases: bool,
+    #[serde(default = "default_true")]
+    pub explicit_union: bool,
+    #[serde(default = "default_true")]
+    pub quote_style: bool,
+    #[serde(default = "default_true")]
+    pub keyword_case: bool,
+    #[serde(default = "default_true")]
+    pub forbidden_functions: bool,
+    #[serde(default = "default_true")]
+    pub layered_architecture: bool,
+    #[serde(default = "default_true")]
+    pub ref_cycles: bool,
+    #[serde(default = "default_true")]
+    pub missing_refs: bool,
+    #[serde(default = "default_true")]
+    pub undeclared_sources: bool,
+    #[serde(default = "default_true")]
+    pub misspelled_jinja: bool,
+    #[serde(default = "default_true")]
+    pub require_materialization: bool,
+    #[serde(default = "default_true")]
+    pub incremental_filter: bool,
+    #[serde(default = "default_true")]
+    pub unused_incremental_filter: bool,
+    // Off by default, since not every project uses the import CTE style
+    #[serde(default)]
+    pub final_select_from_cte: bool,
+    // Off by default, since some marts are meant to be distinct lists
+    #[serde(default)]
+    pub no_select_distinct: bool,
+    #[serde(default = "default_true")]
+    pub max_joins: bool,
+    #[serde(default = "default_true")]
+    pub no_order_by: bool,
+    #[serde(default = "default_true")]
+    pub model_complexity: bool,
     // Add more rules as I get to them
 }
 
+fn default_true() -> bool {
+    true
+}
+
+// A rule that fails once for every node its selector matches
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct CustomRuleConfig {
+    pub name: String,
+    #[serde(deserialize_with = "deserialize_selector")]
+    pub selector: Selector,
+    pub message: String,
+}
+
+// A function models shouldn't call, e.g. `rand`. The message says why, or
+// what to use instead.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct ForbiddenFunctionConfig {
+    pub name: String,
+    pub message: String,
+    // Only report the function in incremental models, e.g.
+    // `current_timestamp`, which changes every time one runs
+    #[serde(default)]
+    pub incremental_only: bool,
+}
+
+// Bad selectors are reported when the config is read rather than when the
+// rule runs
+fn deserialize_selector<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Selector, D::Error> {
+    let selector = String::deserialize(deserializer)?;
+    Selector::parse(&selector).map_err(serde::de::Error::custom)
+}
+
+// The `[rules.no_select_star]` options. Import CTEs like
+// `orders AS (SELECT * FROM {{ ref('orders') }})` are the usual dbt style,
+// so only the model's final SELECT is checked by default.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct SelectStarConfig {
+    #[serde(default = "default_true")]
+    pub allow_in_ctes: bool,
+    // Staging models are the ones under a `staging` directory or named `stg_*`
+    #[serde(default)]
+    pub allow_in_staging: bool,
+}
+
+impl Default for SelectStarConfig {
+    fn default() -> Self {
+        SelectStarConfig {
+            allow_in_ctes: true,
+            allow_in_staging: false,
+        }
+    }
+}
+
+// The `[rules.require_materialization]` options. Models in a folder that sets
+// `+materialized` in dbt_project.yml pass by default; turn off
+// `allow_inherited` to have every model set it itself.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct MaterializationConfig {
+    #[serde(default = "default_true")]
+    pub allow_inherited: bool,
+}
+
+impl Default for MaterializationConfig {
+    fn default() -> Self {
+        MaterializationConfig { allow_inherited: true }
+    }
+}
+
+// The `[rules.max_joins]` options
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct JoinsConfig {
+    #[serde(default = "default_max_joins")]
+    pub threshold: usize,
+}
+
+fn default_max_joins() -> usize {
+    7
+}
+
+impl Default for JoinsConfig {
+    fn default() -> Self {
+        JoinsConfig { threshold: default_max_joins() }
+    }
+}
+
+// The `[rules.model_complexity]` options. A model scores each weight for every join,
+// CTE, window function and CASE expression it has, and for every level its
+// subqueries are nested.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct ComplexityConfig {
+    #[serde(default = "default_max_complexity")]
+    pub threshold: usize,
+    #[serde(default = "default_join_weight")]
+    pub join_weight: usize,
+    #[serde(default = "default_cte_weight")]
+    pub cte_weight: usize,
+    #[serde(default = "default_window_function_weight")]
+    pub window_function_weight: usize,
+    #[serde(default = "default_subquery_weight")]
+    pub subquery_weight: usize,
+    #[serde(default = "default_case_weight")]
+    pub case_weight: usize,
+}
+
+fn default_max_complexity() -> usize {
+    30
+}
+
+fn default_join_weight() -> usize {
+    2
+}
+
+fn default_cte_weight() -> usize {
+    1
+}
+
+fn default_window_function_weight() -> usize {
+    2
+}
+
+fn default_subquery_weight() -> usize {
+    3
+}
+
+fn default_case_weight() -> usize {
+    1
+}
+
+impl Default for ComplexityConfig {
+    fn default() -> Self {
+        ComplexityConfig {
+            threshold: default_max_complexity(),
+            join_weight: default_join_weight(),
+            cte_weight: default_cte_weight(),
+            window_function_weight: default_window_function_weight(),
+            subquery_weight: default_subquery_weight(),
+            case_weight: default_case_weight(),
+        }
+    }
+}
+
+// The `[rules.no_order_by]` options, listing models by name, e.g. the
+// ones a BI tool reads directly
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct OrderByConfig {
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+// The `[rules.quote_style]` options. Without a style, the dialect's is
+// used.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy, Default)]
+#[serde(deny_unknown_fields)]
+pub struct QuotingConfig {
+    pub style: Option<QuoteStyle>,
+}
+
+// The quotes a quoted identifier is written with
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteStyle {
+    // `"orders"`
+    Double,
+    // `` `orders` ``
+    Backtick,
+}
+
+impl QuoteStyle {
+    pub fn quote(&self) -> char {
+        match self {
+            QuoteStyle::Double => '"',
+            QuoteStyle::Backtick => '`',
+        }
+    }
+}
+
+// The `[rules.keyword_case]` options
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy, Default)]
+#[serde(deny_unknown_fields)]
+pub struct KeywordsConfig {
+    #[serde(default)]
+    pub case: KeywordCase,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KeywordCase {
+    // Whichever case the model's first keyword is in
+    #[default]
+    Consistent,
+    Upper,
+    Lower,
+}
+
+// The `[rules.layered_architecture]` options: globs, relative to the project, for the paths of
+// each layer's models. Staging models select from sources, and the
+// intermediate and marts models build on them with refs.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LayersConfig {
+    #[serde(
+        default = "default_staging_paths",
+        deserialize_with = "deserialize_patterns",
+        serialize_with = "serialize_patterns"
+    )]
+    pub staging: Vec<Pattern>,
+    #[serde(
+        default = "default_intermediate_paths",
+        deserialize_with = "deserialize_patterns",
+        serialize_with = "serialize_patterns"
+    )]
+    pub intermediate: Vec<Pattern>,
+    #[serde(
+        default = "default_marts_paths",
+        deserialize_with = "deserialize_patterns",
+        serialize_with = "serialize_patterns"
+    )]
+    pub marts: Vec<Pattern>,
+}
+
+impl Default for LayersConfig {
+    fn default() -> Self {
+        LayersConfig {
+            staging: default_staging_paths(),
+            intermediate: default_intermediate_paths(),
+            marts: default_marts_paths(),
+        }
+    }
+}
+
+fn default_staging_paths() -> Vec<Pattern> {
+    vec![Pattern::new("models/staging/**").unwrap()]
+}
+
+fn default_intermediate_paths() -> Vec<Pattern> {
+    vec![Pattern::new("models/intermediate/**").unwrap()]
+}
+
+fn default_marts_paths() -> Vec<Pattern> {
+    vec![Pattern::new("models/marts/**").unwrap()]
+}
+
+fn deserialize_patterns<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Pattern>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|glob| Pattern::new(&glob).map_err(|e| serde::de::Error::custom(format!("invalid glob '{}': {}", glob, e))))
+        .collect()
+}
+
+fn serialize_patterns<S: Serializer>(patterns: &[Pattern], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(patterns.iter().map(|pattern| pattern.as_str()))
+}
+
+// The `[rules.model_yaml_exists]` options. By default a model passes yaml_exists as soon as
+// a yml file lists it.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy, Default)]
+#[serde(deny_unknown_fields)]
+pub struct YamlConfig {
+    #[serde(default)]
+    pub require_description: bool,
+    // Every column listed in the yml needs a description, and it must list
+    // at least one
+    #[serde(default)]
+    pub require_column_descriptions: bool,
+}
+
+// The `[resources]` section. Singular tests (in `tests/`), analyses (in
+// `analyses/`) and snapshots (in `snapshots/`) are always parsed, but the
+// rules only run on them when they're switched on here.
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy, Default)]
+pub struct ResourcesConfig {
+    #[serde(default)]
+    pub tests: bool,
+    #[serde(default)]
+    pub analyses: bool,
+    #[serde(default)]
+    pub snapshots: bool,
+}
+
+// An `[overrides."<glob>"]` section, which changes the rules for the
+// models whose path, relative to the project, matches the glob, e.g.
+// `models/staging/**`
+#[derive(Debug, PartialEq, Clone)]
+pub struct PathOverride {
+    pub pattern: Pattern,
+    pub config: OverrideConfig,
+}
+
+// The settings an override can change. Rules are turned on and off with the
+// same names as in `[rules]`, and a rule's block replaces the top-level
+// one.
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+pub struct OverrideConfig {
+    #[serde(default)]
+    pub rules: BTreeMap<String, bool>,
+    #[serde(default)]
+    pub severity: HashMap<String, Severity>,
+    // Set by parse(), like the top-level ones
+    #[serde(skip)]
+    pub rule_options: RuleOptionOverrides,
+}
+
+// Bad globs are reported when the config is read. The overrides are sorted
+// so that the ones for deeper directories come later and win.
+fn deserialize_overrides<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<PathOverride>, D::Error> {
+    let sections = BTreeMap::<String, OverrideConfig>::deserialize(deserializer)?;
+    let mut overrides = sections
+        .into_iter()
+        .map(|(glob, config)| {
+            Pattern::new(&glob)
+                .map(|pattern| PathOverride { pattern, config })
+                .map_err(|e| serde::de::Error::custom(format!("invalid glob '{}' in [overrides]: {}", glob, e)))
+        })
+        .collect::<Result<Vec<PathOverride>, D::Error>>()?;
+    overrides.sort_by_key(|path_override| {
+        let glob = path_override.pattern.as_str();
+        (glob.matches('/').count(), glob.len())
+    });
+    Ok(overrides)
+}
+
+// Controls how results are presented in the terminal
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct OutputConfig {
+    // The number of locations to print when a rule fires several times in one model
+    #[serde(default = "default_max_locations")]
+    pub max_locations: usize,
+    // Where results are written. Defaults to the terminal only.
+    #[serde(default = "default_sinks")]
+    pub sinks: Vec<SinkConfig>,
+}
+
+fn default_max_locations() -> usize {
+    5
+}
+
+fn default_sinks() -> Vec<SinkConfig> {
+    vec![SinkConfig::Stdout]
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        OutputConfig {
+            max_locations: default_max_locations(),
+            sinks: default_sinks(),
+        }
+    }
+}
+
+// Controls what happens to models that the parser can't handle
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct ParsingConfig {
+    #[serde(default)]
+    pub on_error: ParseErrorBehavior,
+    // Whether skipped models should make the run exit with a non-zero code
+    #[serde(default)]
+    pub fail_on_skipped: bool,
+    // Guards against generated megafiles. A model that goes over any of
+    // these fails to parse and is handled like any other parse error.
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: usize,
+    #[serde(default = "default_max_statement_length")]
+    pub max_statement_length: usize,
+    #[serde(default = "default_parse_timeout_ms")]
+    pub parse_timeout_ms: u64,
+    // Whether a count of the parsed files is shown while the project loads,
+    // when stderr is a terminal
+    #[serde(default = "default_true")]
+    pub progress: bool,
+    // Whether models that don't parse are rendered with dbtonic's own Jinja
+    // renderer and parsed again
+    #[serde(default)]
+    pub render: bool,
+}
+
+fn default_max_tokens() -> usize {
+    1_000_000
+}
+
+// In bytes, of each statement in a model
+fn default_max_statement_length() -> usize {
+    10_000_000
+}
+
+fn default_parse_timeout_ms() -> u64 {
+    10_000
+}
+
+impl Default for ParsingConfig {
+    fn default() -> Self {
+        ParsingConfig {
+            on_error: ParseErrorBehavior::default(),
+            fail_on_skipped: false,
+            max_tokens: default_max_tokens(),
+            max_statement_length: default_max_statement_length(),
+            parse_timeout_ms: default_parse_timeout_ms(),
+            progress: true,
+            render: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ParseErrorBehavior {
+    // Record the model as skipped and carry on with the rest of the project
+    #[default]
+    Skip,
+    // Stop the run at the first model that fails to parse
+    Abort,
+}
+
 #[derive(Debug)]
 pub enum DbtonicConfigError {
     IoError(io::Error),
     TomlError(toml::de::Error),
+    // A `[rules.<name>]` block for a rule that doesn't exist, or with an
+    // option the rule doesn't take
+    RuleOptionError(String),
 }
 
 impl From<io::Error> for DbtonicConfigError {
@@ -33,29 +644,171 @@ impl From<toml::de::Error> for DbtonicConfigError {
     }
 }
 
+pub const CONFIG_FILE_NAME: &str = "dbtonic.toml";
+pub const CONFIG_ENV_VAR: &str = "DBTONIC_CONFIG";
+// What `dbtonic config init` writes, which reads as the defaults
+pub const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+
 impl DbtonicConfig {
-    pub fn read() -> Result<Self, DbtonicConfigError> {
-        let config_path = Path::new("dbtonic.toml");
-        DbtonicConfig::read_from_path(config_path)
+    pub fn read(config_path: Option<&Path>) -> Result<Self, DbtonicConfigError> {
+        match config_path {
+            Some(config_path) => DbtonicConfig::read_from_path(config_path),
+            None => Ok(DbtonicConfig::default()),
+        }
+    }
+
+    // The file `--config` or `DBTONIC_CONFIG` names, relative to `run_dir`,
+    // otherwise the nearest dbtonic.toml to `run_dir`, if there is one. Unlike
+    // a dbtonic.toml that isn't there, a file that was asked for has to
+    // exist, which reading it checks.
+    pub fn locate(run_dir: &Path, config_flag: Option<&str>) -> Option<PathBuf> {
+        let named = config_flag.map(PathBuf::from).or_else(|| std::env::var_os(CONFIG_ENV_VAR).map(PathBuf::from));
+        match named {
+            Some(config_path) => {
+                let config_path = run_dir.join(config_path);
+                Some(fs::canonicalize(&config_path).unwrap_or(config_path))
+            }
+            None => DbtonicConfig::find(run_dir),
+        }
+    }
+
+    // Looks for dbtonic.toml in `start` and then each directory above it,
+    // the way git finds a .gitignore, so a config at the root of a
+    // repository covers the dbt projects inside it
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        start.ancestors().map(|dir| dir.join(CONFIG_FILE_NAME)).find(|config_path| config_path.is_file())
     }
 
     pub fn read_from_path(config_path: &Path) -> Result<Self, DbtonicConfigError> {
-        match fs::read_to_string(config_path) {
-            Ok(config_str) => {
-                let config = toml::from_str(&config_str)?;
-                Ok(config)
+        let config_str = fs::read_to_string(config_path)?;
+        let mut config = DbtonicConfig::parse(&config_str)?;
+        config.path = Some(config_path.to_path_buf());
+        Ok(config)
+    }
+
+    pub fn parse(config_str: &str) -> Result<Self, DbtonicConfigError> {
+        let mut table: toml::Table = toml::from_str(config_str)?;
+        let blocks = take_rule_blocks(&mut table, &Rules::names()).map_err(DbtonicConfigError::RuleOptionError)?;
+        let mut config: DbtonicConfig = if blocks.is_empty() {
+            // Errors from the text point at the line they're on
+            toml::from_str(config_str)?
+        } else {
+            toml::Value::Table(table).try_into()?
+        };
+        config.rule_options = blocks.options().map_err(DbtonicConfigError::RuleOptionError)?;
+        for path_override in config.overrides.iter_mut() {
+            path_override.config.rule_options = blocks
+                .override_options(path_override.pattern.as_str())
+                .map_err(DbtonicConfigError::RuleOptionError)?;
+        }
+        Ok(config)
+    }
+
+    // The indexes of the overrides that apply to a model, given its path
+    // relative to the project
+    pub fn matching_overrides(&self, relative_path: &Path) -> Vec<usize> {
+        self.overrides
+            .iter()
+            .enumerate()
+            .filter(|(_, path_override)| path_override.pattern.matches_path(relative_path))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    // This config with the given overrides applied on top, in order
+    pub fn with_overrides(&self, indexes: &[usize]) -> DbtonicConfig {
+        let mut config = self.clone();
+        for path_override in indexes.iter().filter_map(|&index| self.overrides.get(index)) {
+            let overrides = &path_override.config;
+            config.rules = config.rules.with_overrides(&overrides.rules);
+            config.severity.extend(overrides.severity.clone());
+            config.rule_options = config.rule_options.with_overrides(&overrides.rule_options);
+        }
+        config
+    }
+}
+
+impl Rules {
+    // Every name in `[rules]`
+    pub fn names() -> Vec<String> {
+        match toml::Value::try_from(DbtonicConfig::default().rules) {
+            Ok(toml::Value::Table(rules)) => rules.keys().cloned().collect(),
+            _ => vec![],
+        }
+    }
+
+    // Every rule turned on
+    pub fn all_enabled() -> Rules {
+        let all: BTreeMap<String, bool> = Rules::names().into_iter().map(|rule_name| (rule_name, true)).collect();
+        DbtonicConfig::default().rules.with_overrides(&all)
+    }
+
+    // Turns rules on and off by their `[rules]` name. Names that aren't
+    // rules are ignored, the same as in `[rules]` itself.
+    fn with_overrides(&self, overrides: &BTreeMap<String, bool>) -> Rules {
+        let Ok(toml::Value::Table(mut rules)) = toml::Value::try_from(self) else {
+            return self.clone();
+        };
+        for (rule_name, enabled) in overrides {
+            if rules.contains_key(rule_name) {
+                rules.insert(rule_name.clone(), toml::Value::Boolean(*enabled));
             }
-            Err(_) => Ok(DbtonicConfig::default()),
         }
+        toml::Value::Table(rules).try_into().unwrap_or_else(|_| self.clone())
     }
+}
 
+impl Default for DbtonicConfig {
     // These are the default rules whenever the file is not found
-    pub fn default() -> Self {
+    fn default() -> Self {
         DbtonicConfig {
+            dialect: SqlDialect::Generic,
             rules: Rules {
                 unique_not_null_or_combination_rule: true,
                 model_yaml_exists: true,
+                model_has_tests: true,
+                exposure_validation: true,
+                semantic_layer_references: true,
+                multiple_statements: false,
+                trailing_semicolon: true,
+                explicit_aliases: true,
+                identifier_quoting: true,
+                duplicate_logic: true,
+                no_env_var: true,
+                ref_aliases: true,
+                no_select_star: true,
+                hardcoded_references: true,
+                cross_database_references: true,
+                ambiguous_columns: true,
+                no_implicit_cross_joins: true,
+                duplicate_column_aliases: true,
+                explicit_union: true,
+                quote_style: true,
+                keyword_case: true,
+                forbidden_functions: true,
+                layered_architecture: true,
+                ref_cycles: true,
+                missing_refs: true,
+                undeclared_sources: true,
+                misspelled_jinja: true,
+                require_materialization: true,
+                incremental_filter: true,
+                unused_incremental_filter: true,
+                final_select_from_cte: false,
+                no_select_distinct: false,
+                max_joins: true,
+                no_order_by: true,
+                model_complexity: true,
             },
+            output: OutputConfig::default(),
+            parsing: ParsingConfig::default(),
+            custom_rules: vec![],
+            forbidden_functions: vec![],
+            severity: HashMap::new(),
+            rule_options: RuleOptions::default(),
+            resources: ResourcesConfig::default(),
+            overrides: vec![],
+            path: None,
         }
     }
 }
@@ -73,10 +826,64 @@ mod tests {
         assert_eq!(
             default_config,
             DbtonicConfig {
+                dialect: SqlDialect::Generic,
                 rules: Rules {
                     unique_not_null_or_combination_rule: true,
                     model_yaml_exists: true,
+                    model_has_tests: true,
+                    exposure_validation: true,
+                    semantic_layer_references: true,
+                    multiple_statements: false,
+                    trailing_semicolon: true,
+                    explicit_aliases: true,
+                    identifier_quoting: true,
+                    duplicate_logic: true,
+                    no_env_var: true,
+                    ref_aliases: true,
+                    no_select_star: true,
+                    hardcoded_references: true,
+                    cross_database_references: true,
+                    ambiguous_columns: true,
+                    no_implicit_cross_joins: true,
+                    duplicate_column_aliases: true,
+                    explicit_union: true,
+                    quote_style: true,
+                    keyword_case: true,
+                    forbidden_functions: true,
+                    layered_architecture: true,
+                    ref_cycles: true,
+                    missing_refs: true,
+                    undeclared_sources: true,
+                    misspelled_jinja: true,
+                    require_materialization: true,
+                    incremental_filter: true,
+                    unused_incremental_filter: true,
+                    final_select_from_cte: false,
+                    no_select_distinct: false,
+                    max_joins: true,
+                    no_order_by: true,
+                    model_complexity: true,
                 },
+                output: OutputConfig {
+                    max_locations: 5,
+                    sinks: vec![SinkConfig::Stdout],
+                },
+                parsing: ParsingConfig {
+                    on_error: ParseErrorBehavior::Skip,
+                    fail_on_skipped: false,
+                    max_tokens: 1_000_000,
+                    max_statement_length: 10_000_000,
+                    parse_timeout_ms: 10_000,
+                    progress: true,
+                    render: false,
+                },
+                custom_rules: vec![],
+                forbidden_functions: vec![],
+                severity: HashMap::new(),
+                rule_options: RuleOptions::default(),
+                resources: ResourcesConfig::default(),
+                overrides: vec![],
+                path: None,
             }
         );
     }
@@ -99,12 +906,415 @@ model_yaml_exists = false
         assert_eq!(
             config,
             DbtonicConfig {
+                dialect: SqlDialect::Generic,
                 rules: Rules {
                     unique_not_null_or_combination_rule: false,
                     model_yaml_exists: false,
+                    model_has_tests: true,
+                    exposure_validation: true,
+                    semantic_layer_references: true,
+                    multiple_statements: false,
+                    trailing_semicolon: true,
+                    explicit_aliases: true,
+                    identifier_quoting: true,
+                    duplicate_logic: true,
+                    no_env_var: true,
+                    ref_aliases: true,
+                    no_select_star: true,
+                    hardcoded_references: true,
+                    cross_database_references: true,
+                    ambiguous_columns: true,
+                    no_implicit_cross_joins: true,
+                    duplicate_column_aliases: true,
+                    explicit_union: true,
+                    quote_style: true,
+                    keyword_case: true,
+                    forbidden_functions: true,
+                    layered_architecture: true,
+                    ref_cycles: true,
+                    missing_refs: true,
+                    undeclared_sources: true,
+                    misspelled_jinja: true,
+                    require_materialization: true,
+                    incremental_filter: true,
+                    unused_incremental_filter: true,
+                    final_select_from_cte: false,
+                    no_select_distinct: false,
+                    max_joins: true,
+                    no_order_by: true,
+                    model_complexity: true,
                 },
+                output: OutputConfig::default(),
+                parsing: ParsingConfig::default(),
+                custom_rules: vec![],
+                forbidden_functions: vec![],
+                severity: HashMap::new(),
+                rule_options: RuleOptions::default(),
+                resources: ResourcesConfig::default(),
+                overrides: vec![],
+                path: Some(config_path),
             }
         );
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_default_config_file() {
+        assert_eq!(DbtonicConfig::parse(DEFAULT_CONFIG).unwrap(), DbtonicConfig::default());
+    }
+
+    #[test]
+    fn test_find_looks_in_parent_directories() {
+        let temp_dir = tempdir().unwrap();
+        let staging_dir = temp_dir.path().join("models").join("staging");
+        fs::create_dir_all(&staging_dir).unwrap();
+        assert_eq!(DbtonicConfig::find(&staging_dir), None);
+
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        File::create(&config_path).unwrap();
+        assert_eq!(DbtonicConfig::find(&staging_dir), Some(config_path));
+
+        // The nearest one wins
+        let models_config_path = temp_dir.path().join("models").join("dbtonic.toml");
+        File::create(&models_config_path).unwrap();
+        assert_eq!(DbtonicConfig::find(&staging_dir), Some(models_config_path));
+    }
+
+    #[test]
+    fn test_a_named_config_has_to_be_readable() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = DbtonicConfig::locate(temp_dir.path(), Some("ci")).unwrap();
+        assert_eq!(config_path, temp_dir.path().join("ci"));
+        assert!(matches!(DbtonicConfig::read(Some(&config_path)), Err(DbtonicConfigError::IoError(_))));
+
+        fs::create_dir(&config_path).unwrap();
+        assert!(matches!(DbtonicConfig::read(Some(&config_path)), Err(DbtonicConfigError::IoError(_))));
+        assert_eq!(DbtonicConfig::read(None).unwrap(), DbtonicConfig::default());
+    }
+
+    #[test]
+    fn test_read_output_config() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[output]
+max_locations = 2
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let config = DbtonicConfig::read_from_path(&config_path).unwrap();
+
+        assert_eq!(config.output.max_locations, 2);
+        assert_eq!(config.output.sinks, vec![SinkConfig::Stdout]);
+    }
+
+    #[test]
+    fn test_read_dialect_config() {
+        let config_str = r#"
+dialect = "snowflake"
+
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let config = DbtonicConfig::read_from_path(&config_path).unwrap();
+
+        assert_eq!(config.dialect, SqlDialect::Snowflake);
+        assert_eq!(config.dialect.identifier_folding(), IdentifierFolding::Upper);
+        assert_eq!(config.dialect.quote_style(), QuoteStyle::Double);
+        assert_eq!(config.rule_options.quote_style.style, None);
+    }
+
+    #[test]
+    fn test_read_output_sinks_config() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[[output.sinks]]
+type = "stdout"
+
+[[output.sinks]]
+type = "file"
+path = "target/dbtonic_results.json"
+
+[[output.sinks]]
+type = "webhook"
+url = "https://example.com/dbtonic"
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let config = DbtonicConfig::read_from_path(&config_path).unwrap();
+
+        assert_eq!(
+            config.output.sinks,
+            vec![
+                SinkConfig::Stdout,
+                SinkConfig::File { path: "target/dbtonic_results.json".into() },
+                SinkConfig::Webhook { url: "https://example.com/dbtonic".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_parsing_config() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[parsing]
+on_error = "abort"
+fail_on_skipped = true
+max_tokens = 5000
+max_statement_length = 20000
+parse_timeout_ms = 500
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let config = DbtonicConfig::read_from_path(&config_path).unwrap();
+
+        assert_eq!(
+            config.parsing,
+            ParsingConfig {
+                on_error: ParseErrorBehavior::Abort,
+                fail_on_skipped: true,
+                max_tokens: 5000,
+                max_statement_length: 20000,
+                parse_timeout_ms: 500,
+                progress: true,
+                render: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_select_star_config() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[rules.no_select_star]
+allow_in_ctes = false
+allow_in_staging = true
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let config = DbtonicConfig::read_from_path(&config_path).unwrap();
+
+        assert!(config.rules.no_select_star);
+        assert_eq!(config.rule_options.no_select_star, SelectStarConfig { allow_in_ctes: false, allow_in_staging: true });
+    }
+
+    #[test]
+    fn test_read_custom_rules_config() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[[custom_rules]]
+name = "no_cross_joins"
+selector = "joins[kind=cross]"
+message = "Cross joins are easy to get wrong, join on a key instead."
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let config = DbtonicConfig::read_from_path(&config_path).unwrap();
+
+        assert_eq!(
+            config.custom_rules,
+            vec![CustomRuleConfig {
+                name: "no_cross_joins".to_string(),
+                selector: Selector::parse("joins[kind=cross]").unwrap(),
+                message: "Cross joins are easy to get wrong, join on a key instead.".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_read_severity_config() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[severity]
+explicit_aliases = "error"
+duplicate_logic = "info"
+no_env_var = "warning"
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let config = DbtonicConfig::read_from_path(&config_path).unwrap();
+
+        assert_eq!(
+            config.severity,
+            HashMap::from([
+                ("explicit_aliases".to_string(), Severity::Error),
+                ("duplicate_logic".to_string(), Severity::Info),
+                ("no_env_var".to_string(), Severity::Warn),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_read_custom_rules_config_with_bad_selector() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[[custom_rules]]
+name = "no_datediff"
+selector = "function[name=datediff]"
+message = "Use the datediff macro."
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let error = DbtonicConfig::read_from_path(&config_path).unwrap_err();
+        assert!(format!("{:?}", error).contains("Did you mean 'functions'?"));
+    }
+
+    #[test]
+    fn test_read_overrides_config() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[rules.max_joins]
+threshold = 4
+
+[overrides."models/staging/**".rules]
+explicit_aliases = false
+not_a_rule = false
+
+[overrides."models/staging/stripe/**"]
+severity = { no_select_star = "info" }
+rules = { max_joins = { threshold = 1 } }
+
+[overrides."models/**".severity]
+no_select_star = "error"
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let config = DbtonicConfig::read_from_path(&config_path).unwrap();
+        let globs: Vec<&str> = config.overrides.iter().map(|path_override| path_override.pattern.as_str()).collect();
+        assert_eq!(globs, vec!["models/**", "models/staging/**", "models/staging/stripe/**"]);
+
+        let overrides = config.matching_overrides(Path::new("models/staging/stripe/stg_payments.sql"));
+        assert_eq!(overrides, vec![0, 1, 2]);
+        let stripe = config.with_overrides(&overrides);
+        assert!(!stripe.rules.explicit_aliases);
+        assert!(stripe.rules.no_select_star);
+        assert_eq!(stripe.severity.get("no_select_star"), Some(&Severity::Info));
+        assert_eq!(stripe.rule_options.max_joins.threshold, 1);
+
+        let marts = config.with_overrides(&config.matching_overrides(Path::new("models/marts/orders.sql")));
+        assert!(marts.rules.explicit_aliases);
+        assert_eq!(marts.severity.get("no_select_star"), Some(&Severity::Error));
+        assert_eq!(marts.rule_options.max_joins.threshold, 4);
+    }
+
+    #[test]
+    fn test_read_rule_blocks() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[rules.max_joins]
+threshold = 6
+severity = "error"
+
+[rules.no_order_by]
+allow = ["daily_revenue_report"]
+
+[rules.quote_style]
+style = "backtick"
+
+[overrides."models/staging/**".rules.max_joins]
+enabled = false
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let config = DbtonicConfig::read_from_path(&config_path).unwrap();
+        assert!(config.rules.max_joins);
+        assert_eq!(config.rule_options.max_joins.threshold, 6);
+        assert_eq!(config.severity.get("max_joins"), Some(&Severity::Error));
+        assert_eq!(config.rule_options.no_order_by.allow, vec!["daily_revenue_report".to_string()]);
+        assert_eq!(config.rule_options.quote_style.style, Some(QuoteStyle::Backtick));
+
+        let staging = config.with_overrides(&config.matching_overrides(Path::new("models/staging/stg_orders.sql")));
+        assert!(!staging.rules.max_joins);
+
+        file.write_all(b"\n[rules.no_select_star]\nallow_in_cte = false\n").unwrap();
+        let error = DbtonicConfig::read_from_path(&config_path).unwrap_err();
+        assert!(format!("{:?}", error).contains("unknown field `allow_in_cte`, expected `allow_in_ctes` or `allow_in_staging`"));
+    }
+
+    #[test]
+    fn test_read_overrides_with_a_bad_glob() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[overrides."models/[staging/**".rules]
+explicit_aliases = false
+"#;
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("dbtonic.toml");
+        let mut file = File::create(&config_path).unwrap();
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        assert!(DbtonicConfig::read_from_path(&config_path).is_err());
+    }
+}
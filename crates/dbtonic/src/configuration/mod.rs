@@ -1 +1,3 @@
-pub mod dbtonic_config;
\ No newline at end of file
+pub mod dbtonic_config;
+pub mod rule_options;
+pub mod schema;
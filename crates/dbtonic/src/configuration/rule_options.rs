@@ -0,0 +1,314 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use toml::{Table, Value};
+use crate::configuration::dbtonic_config::{
+    ComplexityConfig, JoinsConfig, KeywordsConfig, LayersConfig, MaterializationConfig, OrderByConfig, QuotingConfig,
+    SelectStarConfig, YamlConfig,
+};
+use crate::reporting::suggestions::with_suggestion;
+use crate::rules::rules_engine::Severity;
+
+// The options set in `[rules.<name>]` blocks, under the name of the rule
+// that reads them. The other rules only take `enabled` and `severity`.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleOptions {
+    pub model_yaml_exists: YamlConfig,
+    pub no_select_star: SelectStarConfig,
+    pub require_materialization: MaterializationConfig,
+    pub max_joins: JoinsConfig,
+    pub model_complexity: ComplexityConfig,
+    pub no_order_by: OrderByConfig,
+    pub quote_style: QuotingConfig,
+    pub keyword_case: KeywordsConfig,
+    pub layered_architecture: LayersConfig,
+}
+
+// The blocks in an `[overrides."<glob>".rules]` table, each of which
+// replaces the top-level block for its rule
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleOptionOverrides {
+    pub model_yaml_exists: Option<YamlConfig>,
+    pub no_select_star: Option<SelectStarConfig>,
+    pub require_materialization: Option<MaterializationConfig>,
+    pub max_joins: Option<JoinsConfig>,
+    pub model_complexity: Option<ComplexityConfig>,
+    pub no_order_by: Option<OrderByConfig>,
+    pub quote_style: Option<QuotingConfig>,
+    pub keyword_case: Option<KeywordsConfig>,
+    pub layered_architecture: Option<LayersConfig>,
+}
+
+impl RuleOptions {
+    // The rules that take options
+    pub fn names() -> Vec<String> {
+        match Value::try_from(RuleOptions::default()) {
+            Ok(Value::Table(options)) => options.keys().cloned().collect(),
+            _ => vec![],
+        }
+    }
+
+    pub fn with_overrides(&self, overrides: &RuleOptionOverrides) -> RuleOptions {
+        RuleOptions {
+            model_yaml_exists: overrides.model_yaml_exists.unwrap_or(self.model_yaml_exists),
+            no_select_star: overrides.no_select_star.unwrap_or(self.no_select_star),
+            require_materialization: overrides.require_materialization.unwrap_or(self.require_materialization),
+            max_joins: overrides.max_joins.unwrap_or(self.max_joins),
+            model_complexity: overrides.model_complexity.unwrap_or(self.model_complexity),
+            no_order_by: overrides.no_order_by.clone().unwrap_or_else(|| self.no_order_by.clone()),
+            quote_style: overrides.quote_style.unwrap_or(self.quote_style),
+            keyword_case: overrides.keyword_case.unwrap_or(self.keyword_case),
+            layered_architecture: overrides
+                .layered_architecture
+                .clone()
+                .unwrap_or_else(|| self.layered_architecture.clone()),
+        }
+    }
+}
+
+// The options every rule takes
+const ENABLED: &str = "enabled";
+const SEVERITY: &str = "severity";
+
+// The rules whose `[rules]` key isn't the name they report violations
+// under, which is the name `[severity]` goes by
+const RENAMED_RULES: &[(&str, &str)] = &[
+    ("model_yaml_exists", "yaml_exists"),
+    ("unique_not_null_or_combination_rule", "unique_not_null_or_combination"),
+];
+
+fn severity_key(rule_name: &str) -> String {
+    RENAMED_RULES
+        .iter()
+        .find(|(config_key, _)| *config_key == rule_name)
+        .map_or(rule_name, |(_, name)| name)
+        .to_string()
+}
+
+// The `[rules.<name>]` blocks taken out of a config, less their `enabled`
+// and `severity`
+#[derive(Debug, Default)]
+pub struct RuleBlocks {
+    found: bool,
+    rules: Table,
+    // Keyed by the override's glob
+    overrides: BTreeMap<String, Table>,
+}
+
+impl RuleBlocks {
+    // Whether the config had any blocks
+    pub fn is_empty(&self) -> bool {
+        !self.found
+    }
+
+    pub fn options(&self) -> Result<RuleOptions, String> {
+        read(&self.rules)
+    }
+
+    pub fn override_options(&self, glob: &str) -> Result<RuleOptionOverrides, String> {
+        self.overrides.get(glob).map_or_else(|| Ok(RuleOptionOverrides::default()), read)
+    }
+}
+
+// Each block was checked when it was taken out
+fn read<T: DeserializeOwned>(blocks: &Table) -> Result<T, String> {
+    Value::Table(blocks.clone()).try_into().map_err(|e| e.message().to_string())
+}
+
+// Takes the `[rules.<name>]` blocks out of `config`, e.g.
+//
+//     [rules.max_joins]
+//     threshold = 6
+//     severity = "error"
+//
+// leaves `max_joins = true` in `[rules]` and `max_joins = "error"` in
+// `[severity]`, and `threshold = 6` in the max_joins block returned. The
+// blocks in `[overrides."<glob>".rules]` are taken out of their override
+// the same way.
+pub fn take_rule_blocks(config: &mut Table, rule_names: &[String]) -> Result<RuleBlocks, String> {
+    let mut blocks = RuleBlocks::default();
+    blocks.rules = take_in(config, "rules", rule_names, &mut blocks.found)?;
+    if let Some(Value::Table(overrides)) = config.get_mut("overrides") {
+        for (glob, path_override) in overrides.iter_mut() {
+            if let Value::Table(path_override) = path_override {
+                let path = format!("overrides.\"{}\".rules", glob);
+                let override_blocks = take_in(path_override, &path, rule_names, &mut blocks.found)?;
+                blocks.overrides.insert(glob.clone(), override_blocks);
+            }
+        }
+    }
+    Ok(blocks)
+}
+
+// Takes the blocks out of the `rules` table of `config`, which is either
+// the whole config or one override. `path` is where the table is, for
+// errors.
+fn take_in(config: &mut Table, path: &str, rule_names: &[String], found: &mut bool) -> Result<Table, String> {
+    let Some(Value::Table(rules)) = config.get_mut("rules") else {
+        return Ok(Table::new());
+    };
+    let rule_blocks: Vec<(String, Table)> = rules
+        .iter()
+        .filter_map(|(rule_name, value)| match value {
+            Value::Table(block) => Some((rule_name.clone(), block.clone())),
+            _ => None,
+        })
+        .collect();
+    *found |= !rule_blocks.is_empty();
+
+    let option_names = RuleOptions::names();
+    let mut severities = vec![];
+    let mut options = Table::new();
+    for (rule_name, mut block) in rule_blocks {
+        if !rule_names.contains(&rule_name) {
+            let message = format!("[{}.{}] isn't a rule.", path, rule_name);
+            return Err(with_suggestion(message, &rule_name, rule_names.iter().map(|name| name.as_str())));
+        }
+        let enabled = match block.remove(ENABLED) {
+            Some(Value::Boolean(enabled)) => enabled,
+            Some(value) => {
+                return Err(format!("`{}` in [{}.{}] should be true or false, not {}.", ENABLED, path, rule_name, value));
+            }
+            None => true,
+        };
+        rules.insert(rule_name.clone(), Value::Boolean(enabled));
+        if let Some(severity) = block.remove(SEVERITY) {
+            if severity.clone().try_into::<Severity>().is_err() {
+                return Err(format!(
+                    "`{}` in [{}.{}] should be \"info\", \"warn\" or \"error\", not {}.",
+                    SEVERITY, path, rule_name, severity
+                ));
+            }
+            severities.push((severity_key(&rule_name), severity));
+        }
+        let Some(name) = block.keys().next() else {
+            continue;
+        };
+        if !option_names.contains(&rule_name) {
+            return Err(format!(
+                "[{}.{}] has an unknown option `{}`. {} only takes {} and {}.",
+                path, rule_name, name, rule_name, ENABLED, SEVERITY
+            ));
+        }
+        // Read on its own, so a mistake is reported with the block it's in
+        let rule_options = Table::from_iter([(rule_name.clone(), Value::Table(block))]);
+        read::<RuleOptions>(&rule_options).map_err(|e| format!("[{}.{}]: {}.", path, rule_name, e))?;
+        options.extend(rule_options);
+    }
+
+    if !severities.is_empty() {
+        let value = config.entry(SEVERITY.to_string()).or_insert_with(|| Value::Table(Table::new()));
+        if !value.is_table() {
+            *value = Value::Table(Table::new());
+        }
+        if let Value::Table(severity) = value {
+            severity.extend(severities);
+        }
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule_names() -> Vec<String> {
+        ["max_joins", "model_yaml_exists", "no_order_by", "no_select_star", "quote_style", "ref_cycles"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    fn take(config: &str) -> Result<(Table, RuleBlocks), String> {
+        let mut config: Table = toml::from_str(config).unwrap();
+        let blocks = take_rule_blocks(&mut config, &rule_names())?;
+        Ok((config, blocks))
+    }
+
+    #[test]
+    fn test_take_rule_blocks() {
+        let (config, blocks) = take(
+            r#"
+[rules]
+no_order_by = false
+
+[rules.max_joins]
+threshold = 6
+severity = "error"
+
+[rules.no_select_star]
+enabled = false
+allow_in_staging = true
+
+[overrides."models/staging/**".rules.max_joins]
+threshold = 10
+"#,
+        )
+        .unwrap();
+
+        let expected: Table = toml::from_str(
+            r#"
+[rules]
+no_order_by = false
+max_joins = true
+no_select_star = false
+
+[severity]
+max_joins = "error"
+
+[overrides."models/staging/**".rules]
+max_joins = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(config, expected);
+        assert!(!blocks.is_empty());
+
+        let options = blocks.options().unwrap();
+        assert_eq!(options.max_joins, JoinsConfig { threshold: 6 });
+        assert_eq!(options.no_select_star, SelectStarConfig { allow_in_ctes: true, allow_in_staging: true });
+        assert_eq!(options.no_order_by, OrderByConfig::default());
+
+        let staging = blocks.override_options("models/staging/**").unwrap();
+        assert_eq!(staging.max_joins, Some(JoinsConfig { threshold: 10 }));
+        assert_eq!(staging.no_select_star, None);
+        assert_eq!(options.with_overrides(&staging).max_joins, JoinsConfig { threshold: 10 });
+        assert_eq!(blocks.override_options("models/marts/**").unwrap(), RuleOptionOverrides::default());
+    }
+
+    #[test]
+    fn test_severity_of_a_renamed_rule() {
+        let (config, _) = take("[rules.model_yaml_exists]\nseverity = \"info\"\n").unwrap();
+
+        let expected: Table = toml::from_str("[rules]\nmodel_yaml_exists = true\n\n[severity]\nyaml_exists = \"info\"\n").unwrap();
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn test_take_rule_blocks_errors() {
+        let error = |config: &str| take(config).unwrap_err();
+        assert_eq!(
+            error("[rules.max_joins]\ntreshold = 6\n"),
+            "[rules.max_joins]: unknown field `treshold`, expected `threshold`."
+        );
+        assert_eq!(error("[rules.max_jions]\nthreshold = 6\n"), "[rules.max_jions] isn't a rule. Did you mean 'max_joins'?");
+        assert_eq!(
+            error("[rules.max_joins]\nthreshold = \"six\"\n"),
+            "[rules.max_joins]: invalid type: string \"six\", expected usize."
+        );
+        assert_eq!(
+            error("[rules.ref_cycles]\nthreshold = 6\n"),
+            "[rules.ref_cycles] has an unknown option `threshold`. ref_cycles only takes enabled and severity."
+        );
+        assert_eq!(
+            error("[overrides.\"models/**\".rules.no_order_by]\nseverity = \"fatal\"\n"),
+            "`severity` in [overrides.\"models/**\".rules.no_order_by] should be \"info\", \"warn\" or \"error\", not \"fatal\"."
+        );
+        assert_eq!(
+            error("[rules.quote_style]\nstyle = \"bracket\"\n"),
+            "[rules.quote_style]: unknown variant `bracket`, expected `double` or `backtick`."
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use toml::{Table, Value};
+use crate::configuration::dbtonic_config::{DbtonicConfig, DbtonicConfigError, Rules};
+use crate::reporting::suggestions::with_suggestion;
+use crate::rules::rules_engine::Severity;
+
+// Something `dbtonic config validate` found in a config file: a setting
+// dbtonic would reject or ignore
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConfigProblem {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl ConfigProblem {
+    fn error(message: String) -> Self {
+        ConfigProblem { severity: Severity::Error, message }
+    }
+}
+
+const TOP_LEVEL_KEYS: &[&str] = &[
+    "dialect",
+    "rules",
+    "output",
+    "parsing",
+    "custom_rules",
+    "forbidden_functions",
+    "severity",
+    "resources",
+    "overrides",
+];
+
+// The sections that hold settings, and their keys
+const SECTIONS: &[(&str, &[&str])] = &[
+    ("output", &["max_locations", "sinks"]),
+    ("parsing", &["on_error", "fail_on_skipped", "max_tokens", "max_statement_length", "parse_timeout_ms", "progress", "render"]),
+    ("resources", &["tests", "analyses", "snapshots"]),
+];
+
+const OVERRIDE_KEYS: &[&str] = &["rules", "severity"];
+
+const CUSTOM_RULE_KEYS: &[&str] = &["name", "selector", "message"];
+
+const FORBIDDEN_FUNCTION_KEYS: &[&str] = &["name", "message", "incremental_only"];
+
+// Checks a config file's text against what dbtonic reads. `rule_names` are
+// the names `[severity]` can use: every built-in rule's, whether or not
+// it's turned on, and the plugins'. Custom rules are taken from the file.
+pub fn validate(config_str: &str, rule_names: &[String]) -> Vec<ConfigProblem> {
+    let table: Table = match toml::from_str(config_str) {
+        Ok(table) => table,
+        Err(e) => return vec![ConfigProblem::error(e.to_string())],
+    };
+
+    let mut problems = vec![];
+    check_keys(&table, None, TOP_LEVEL_KEYS, &mut problems);
+    check_sections(&table, &mut problems);
+
+    let toggle_names = Rules::names();
+    if let Some(Value::Table(rules)) = table.get("rules") {
+        check_toggles(rules, "rules", &toggle_names, &mut problems);
+    }
+
+    let mut severity_names: Vec<String> = rule_names.to_vec();
+    if let Some(Value::Array(custom_rules)) = table.get("custom_rules") {
+        for (index, custom_rule) in custom_rules.iter().enumerate() {
+            if let Value::Table(custom_rule) = custom_rule {
+                check_keys(custom_rule, Some(&format!("custom_rules.{}", index)), CUSTOM_RULE_KEYS, &mut problems);
+                if let Some(Value::String(name)) = custom_rule.get("name") {
+                    severity_names.push(name.clone());
+                }
+            }
+        }
+    }
+    if let Some(Value::Array(functions)) = table.get("forbidden_functions") {
+        for (index, function) in functions.iter().enumerate() {
+            if let Value::Table(function) = function {
+                let path = format!("forbidden_functions.{}", index);
+                check_keys(function, Some(&path), FORBIDDEN_FUNCTION_KEYS, &mut problems);
+            }
+        }
+    }
+    if let Some(Value::Table(severity)) = table.get("severity") {
+        check_severity_names(severity, "severity", &severity_names, &mut problems);
+    }
+
+    if let Some(Value::Table(overrides)) = table.get("overrides") {
+        for (glob, path_override) in overrides {
+            let Value::Table(path_override) = path_override else {
+                continue;
+            };
+            let path = format!("overrides.\"{}\"", glob);
+            check_keys(path_override, Some(&path), OVERRIDE_KEYS, &mut problems);
+            if let Some(Value::Table(rules)) = path_override.get("rules") {
+                check_toggles(rules, &format!("{}.rules", path), &toggle_names, &mut problems);
+            }
+            if let Some(Value::Table(severity)) = path_override.get("severity") {
+                check_severity_names(severity, &format!("{}.severity", path), &severity_names, &mut problems);
+            }
+        }
+    }
+
+    // The types are checked by reading the config the way every command
+    // does, which stops at the first mistake
+    match DbtonicConfig::parse(config_str) {
+        Ok(_) => {}
+        Err(DbtonicConfigError::RuleOptionError(message)) => problems.push(ConfigProblem::error(message)),
+        Err(DbtonicConfigError::TomlError(e)) => problems.push(ConfigProblem::error(e.to_string().trim_end().to_string())),
+        Err(e) => problems.push(ConfigProblem::error(format!("{:?}", e))),
+    }
+    problems
+}
+
+fn check_keys(table: &Table, path: Option<&str>, known_keys: &[&str], problems: &mut Vec<ConfigProblem>) {
+    for key in table.keys().filter(|key| !known_keys.contains(&key.as_str())) {
+        let message = match path {
+            Some(path) => format!("Unknown key `{}` in [{}].", key, path),
+            None => format!("Unknown key `{}`.", key),
+        };
+        problems.push(ConfigProblem::error(with_suggestion(message, key, known_keys.iter().copied())));
+    }
+}
+
+fn check_sections(table: &Table, problems: &mut Vec<ConfigProblem>) {
+    for (section, known_keys) in SECTIONS {
+        if let Some(Value::Table(settings)) = table.get(*section) {
+            check_keys(settings, Some(section), known_keys, problems);
+        }
+    }
+}
+
+// Rules turned on and off by name. Blocks are checked when the config is
+// read.
+fn check_toggles(rules: &Table, path: &str, toggle_names: &[String], problems: &mut Vec<ConfigProblem>) {
+    for (rule_name, value) in rules {
+        if value.is_bool() && !toggle_names.contains(rule_name) {
+            let message = format!("Unknown rule `{}` in [{}], which is ignored.", rule_name, path);
+            problems.push(ConfigProblem::error(with_suggestion(
+                message,
+                rule_name,
+                toggle_names.iter().map(|name| name.as_str()),
+            )));
+        }
+    }
+}
+
+fn check_severity_names(severity: &Table, path: &str, rule_names: &[String], problems: &mut Vec<ConfigProblem>) {
+    for rule_name in severity.keys().filter(|rule_name| !rule_names.contains(rule_name)) {
+        let message = format!("Unknown rule `{}` in [{}], which is ignored.", rule_name, path);
+        problems.push(ConfigProblem::error(with_suggestion(message, rule_name, rule_names.iter().map(|name| name.as_str()))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::dbtonic_config::DEFAULT_CONFIG;
+
+    fn rule_names() -> Vec<String> {
+        ["max_joins", "explicit_aliases", "yaml_exists"].iter().map(|name| name.to_string()).collect()
+    }
+
+    fn messages(config_str: &str) -> Vec<(Severity, String)> {
+        validate(config_str, &rule_names()).into_iter().map(|problem| (problem.severity, problem.message)).collect()
+    }
+
+    #[test]
+    fn test_validate_default_config() {
+        assert_eq!(messages(DEFAULT_CONFIG), vec![]);
+    }
+
+    #[test]
+    fn test_validate_unknown_keys_and_rules() {
+        let config_str = r#"
+dialet = "snowflake"
+
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+max_join = false
+
+[severity]
+explicit_alias = "warn"
+no_datediff = "error"
+
+[[custom_rules]]
+name = "no_datediff"
+selector = "functions[name=datediff]"
+mesage = "Use the datediff macro."
+
+[overrides."models/staging/**"]
+yaml = { require_descriptions = true }
+"#;
+        let mut problems = messages(config_str);
+        // Then the first error reading the config, with the line it's on
+        let (severity, read_error) = problems.pop().unwrap();
+        assert_eq!(severity, Severity::Error);
+        assert!(read_error.starts_with("TOML parse error at line 13"), "{}", read_error);
+        assert!(read_error.ends_with("missing field `message`"), "{}", read_error);
+        assert_eq!(
+            problems,
+            vec![
+                (Severity::Error, "Unknown key `dialet`. Did you mean 'dialect'?".to_string()),
+                (Severity::Error, "Unknown rule `max_join` in [rules], which is ignored. Did you mean 'max_joins'?".to_string()),
+                (Severity::Error, "Unknown key `mesage` in [custom_rules.0]. Did you mean 'message'?".to_string()),
+                (
+                    Severity::Error,
+                    "Unknown rule `explicit_alias` in [severity], which is ignored. Did you mean 'explicit_aliases'?".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "Unknown key `yaml` in [overrides.\"models/staging/**\"].".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_types_and_rule_blocks() {
+        let config_str = r#"
+[rules]
+unique_not_null_or_combination_rule = true
+model_yaml_exists = true
+
+[output]
+max_locations = "seven"
+"#;
+        let problems = messages(config_str);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].0, Severity::Error);
+        assert!(problems[0].1.contains("invalid type: string \"seven\", expected usize"), "{}", problems[0].1);
+
+        assert_eq!(
+            messages("[rules.max_joins]\nthreshold = -1\n"),
+            vec![(Severity::Error, "[rules.max_joins]: invalid value: integer `-1`, expected usize.".to_string())]
+        );
+        // The sections rule options were set in before the blocks
+        assert_eq!(
+            messages("[rules]\nunique_not_null_or_combination_rule = true\nmodel_yaml_exists = true\n\n[joins]\nmax_per_model = 7\n"),
+            vec![(Severity::Error, "Unknown key `joins`.".to_string())]
+        );
+    }
+}
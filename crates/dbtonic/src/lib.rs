@@ -3,16 +3,56 @@ pub mod cli;
 pub mod parser;
 pub mod rules;
 pub mod configuration;
+pub mod reporting;
+pub mod cache;
+pub mod stats;
+pub mod lsp;
+pub mod translate;
 
-use clap::{App, Arg, SubCommand};
+use clap::{App, AppSettings, Arg, SubCommand};
+use crate::configuration::dbtonic_config::DbtonicConfig;
 use crate::validation::dbt_project_operations::DbtProject;
+use crate::rules::rules_engine::Plugin;
+use crate::reporting::diagnostics::OutputFormat;
 
 pub fn run(args: Vec<String>) {
+    run_with_plugins(args, &[]);
+}
+
+// The same as `run`, but every command that runs rules also runs the ones
+// the plugins register. This is how a crate that depends on dbtonic ships
+// a binary with its own rules:
+//
+//     fn register(rules_engine: &mut RulesEngine) {
+//         rules_engine.register(Box::new(NoLegacySchemas {}));
+//     }
+//
+//     fn main() {
+//         dbtonic::run_with_plugins(std::env::args().collect(), &[register]);
+//     }
+pub fn run_with_plugins(args: Vec<String>, plugins: &[Plugin]) {
 
     let app = App::new("dbtonic")
     .version("0.1.0")
     .author("Callum McCann")
     .about("Your friendly neighborhood build tool Connoisseur")
+    .arg(Arg::with_name("dialect")
+        .long("dialect")
+        .value_name("DIALECT")
+        .possible_values(["generic", "postgres", "redshift", "snowflake", "bigquery", "duckdb", "trino"])
+        .global(true)
+        .help("The SQL dialect to parse models with, instead of the one in dbtonic.toml")
+        .takes_value(true))
+    .arg(Arg::with_name("config")
+        .long("config")
+        .value_name("FILE")
+        .global(true)
+        .help("The config file to use, instead of the nearest dbtonic.toml")
+        .takes_value(true))
+    .arg(Arg::with_name("render")
+        .long("render")
+        .global(true)
+        .help("Render models that don't parse with dbtonic's own Jinja renderer and parse them again"))
     .subcommand(SubCommand::with_name("hello")
         .about("Says hello to the user"))
     .subcommand(SubCommand::with_name("evaluate")
@@ -21,14 +61,147 @@ pub fn run(args: Vec<String>) {
             .long("model")
             .value_name("FILE")
             .help("Defines the SQL model to evaluate")
+            .takes_value(true))
+        .arg(Arg::with_name("select")
+            .long("select")
+            .short('s')
+            .value_name("SELECTOR")
+            .multiple_values(true)
+            .help("Only evaluate these models, dbt style, e.g. 'stg_orders+', 'tag:nightly' or 'path:models/staging'")
+            .takes_value(true))
+        .arg(Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("SELECTOR")
+            .multiple_values(true)
+            .help("Leaves these models out, using the same syntax as --select")
+            .takes_value(true))
+        .arg(Arg::with_name("timing")
+            .long("timing")
+            .help("Reports how long tokenizing, parsing, building the DAG and each rule took"))
+        .arg(Arg::with_name("no-cache")
+            .long("no-cache")
+            .help("Runs every rule on every model instead of reusing results for unchanged models from .dbtonic/cache"))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .value_name("FORMAT")
+            .possible_values(OutputFormat::NAMES)
+            .default_value("pretty")
+            .help("How the results are printed to stdout: one line per violation, grouped by model, json or a SARIF log")
             .takes_value(true)))
+    .subcommand(SubCommand::with_name("lint")
+        .about("Runs every rule and exits with a non-zero code when one fails, for gating CI")
+        .arg(Arg::with_name("model")
+            .long("model")
+            .value_name("FILE")
+            .help("Defines the SQL model to lint")
+            .takes_value(true))
+        .arg(Arg::with_name("select")
+            .long("select")
+            .short('s')
+            .value_name("SELECTOR")
+            .multiple_values(true)
+            .help("Only lint these models, dbt style, e.g. 'stg_orders+', 'tag:nightly' or 'path:models/staging'")
+            .takes_value(true))
+        .arg(Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("SELECTOR")
+            .multiple_values(true)
+            .help("Leaves these models out, using the same syntax as --select")
+            .takes_value(true))
+        .arg(Arg::with_name("files")
+            .value_name("FILES")
+            .multiple_values(true)
+            .help("Only lint these model files, e.g. the changed files a pre-commit hook passes"))
+        .arg(Arg::with_name("stdin")
+            .long("stdin")
+            .requires("filename")
+            .conflicts_with_all(&["model", "select", "exclude", "files"])
+            .help("Lints the SQL piped to stdin as the model in --filename, e.g. an editor's unsaved buffer"))
+        .arg(Arg::with_name("filename")
+            .long("filename")
+            .value_name("FILE")
+            .requires("stdin")
+            .help("The model file the SQL piped to stdin belongs to")
+            .takes_value(true))
+        .arg(Arg::with_name("fail-level")
+            .long("fail-level")
+            .value_name("LEVEL")
+            .possible_values(["info", "warn", "error"])
+            .default_value("error")
+            .help("The lowest severity that makes the command fail")
+            .takes_value(true))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .value_name("FORMAT")
+            .possible_values(OutputFormat::NAMES)
+            .default_value("plain")
+            .help("How the results are printed: one line per violation, grouped by model, json or a SARIF log")
+            .takes_value(true))
+        .arg(Arg::with_name("no-cache")
+            .long("no-cache")
+            .help("Runs every rule on every model instead of reusing results for unchanged models from .dbtonic/cache"))
+        .arg(Arg::with_name("compiled")
+            .long("compiled")
+            .conflicts_with("stdin")
+            .help("Lints the SQL 'dbt compile' wrote to target/compiled instead of the models' Jinja")))
+    .subcommand(SubCommand::with_name("fix")
+        .about("Applies the fixes rules know about to the model files")
+        .arg(Arg::with_name("model")
+            .long("model")
+            .value_name("FILE")
+            .help("Defines the SQL model to fix")
+            .takes_value(true))
+        .arg(Arg::with_name("select")
+            .long("select")
+            .short('s')
+            .value_name("SELECTOR")
+            .multiple_values(true)
+            .help("Only fix these models, dbt style, e.g. 'stg_orders+', 'tag:nightly' or 'path:models/staging'")
+            .takes_value(true))
+        .arg(Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("SELECTOR")
+            .multiple_values(true)
+            .help("Leaves these models out, using the same syntax as --select")
+            .takes_value(true))
+        .arg(Arg::with_name("dry-run")
+            .long("dry-run")
+            .help("Prints a diff of the fixes instead of writing them")))
+    .subcommand(SubCommand::with_name("watch")
+        .about("Lints the project, then lints the models again as their SQL or yml changes")
+        .arg(Arg::with_name("select")
+            .long("select")
+            .short('s')
+            .value_name("SELECTOR")
+            .multiple_values(true)
+            .help("Only lint these models, dbt style, e.g. 'stg_orders+', 'tag:nightly' or 'path:models/staging'")
+            .takes_value(true))
+        .arg(Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("SELECTOR")
+            .multiple_values(true)
+            .help("Leaves these models out, using the same syntax as --select")
+            .takes_value(true)))
+    .subcommand(SubCommand::with_name("lsp")
+        .about("Runs a language server on stdin and stdout, for editors to show violations as models are edited"))
     .subcommand(SubCommand::with_name("get-ast")
         .about("Returns the AST of a specific model")
         .arg(Arg::with_name("model")
             .long("model")
-            .required(true)
+            .required_unless_present("stdin")
             .takes_value(true)
-            .help("Defines the SQL model to get AST for")))
+            .help("Defines the SQL model to get AST for"))
+        .arg(Arg::with_name("stdin")
+            .long("stdin")
+            .conflicts_with("model")
+            .help("Returns the AST of the SQL piped to stdin instead"))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .value_name("FORMAT")
+            .possible_values(["text", "json"])
+            .default_value("text")
+            .help("Rust's debug output, or the statements as a JSON array for other tools to read")
+            .takes_value(true)))
     .subcommand(SubCommand::with_name("get-tokens")
         .about("Returns the Tokens of a specific model")
         .arg(Arg::with_name("model")
@@ -36,8 +209,134 @@ pub fn run(args: Vec<String>) {
             .required(true)
             .takes_value(true)
             .help("Defines the SQL model to get Tokens for")))
+    .subcommand(SubCommand::with_name("coverage")
+        .about("Reports how many models parsed cleanly, partially or failed, by directory")
+        .arg(Arg::with_name("model")
+            .long("model")
+            .value_name("FILE")
+            .help("Defines the SQL model to report on")
+            .takes_value(true)))
+    .subcommand(SubCommand::with_name("query")
+        .about("Finds nodes in the project's SQL that match a selector, e.g. 'functions[name=datediff]'")
+        .arg(Arg::with_name("selector")
+            .value_name("SELECTOR")
+            .required(true)
+            .help("The selector to match, e.g. 'joins[kind=cross]'"))
+        .arg(Arg::with_name("model")
+            .long("model")
+            .value_name("FILE")
+            .help("Defines the SQL model to search")
+            .takes_value(true)))
+    .subcommand(SubCommand::with_name("translate")
+        .about("Rewrites the models' SQL, parsed with --dialect, for another warehouse and reports what can't be translated")
+        .arg(Arg::with_name("to")
+            .long("to")
+            .value_name("DIALECT")
+            .possible_values(["generic", "postgres", "redshift", "snowflake", "bigquery", "duckdb", "trino"])
+            .required(true)
+            .help("The dialect to translate the models to")
+            .takes_value(true))
+        .arg(Arg::with_name("model")
+            .long("model")
+            .value_name("MODEL")
+            .help("Only translates this model")
+            .takes_value(true))
+        .arg(Arg::with_name("output-dir")
+            .long("output-dir")
+            .value_name("DIR")
+            .help("Writes the translated models here, in the same folders as the project, instead of printing them")
+            .takes_value(true)))
+    .subcommand(SubCommand::with_name("lineage")
+        .about("Lists the models and sources upstream and downstream of a model, based on its refs and sources")
+        .arg(Arg::with_name("model")
+            .value_name("MODEL")
+            .required(true)
+            .help("The name of the model"))
+        .arg(Arg::with_name("upstream")
+            .long("upstream")
+            .conflicts_with("downstream")
+            .help("Only lists what the model is built from"))
+        .arg(Arg::with_name("downstream")
+            .long("downstream")
+            .help("Only lists what is built from the model")))
+    .subcommand(SubCommand::with_name("dag")
+        .about("Prints the graph of models and sources built from the project's refs and sources")
+        .arg(Arg::with_name("format")
+            .long("format")
+            .value_name("FORMAT")
+            .possible_values(["dot", "mermaid"])
+            .default_value("dot")
+            .help("Graphviz DOT, e.g. for 'dot -Tsvg', or a Mermaid flowchart")
+            .takes_value(true)))
+    .subcommand(SubCommand::with_name("docs")
+        .about("Writes a Markdown page per model with its lineage, config, columns and rule results, e.g. for pull requests")
+        .arg(Arg::with_name("output-dir")
+            .long("output-dir")
+            .value_name("DIR")
+            .default_value("target/dbtonic_docs")
+            .help("Where the pages are written")
+            .takes_value(true))
+        .arg(Arg::with_name("select")
+            .long("select")
+            .short('s')
+            .value_name("SELECTOR")
+            .multiple_values(true)
+            .help("Only document these models, dbt style, e.g. 'stg_orders+', 'tag:nightly' or 'path:models/staging'")
+            .takes_value(true))
+        .arg(Arg::with_name("exclude")
+            .long("exclude")
+            .value_name("SELECTOR")
+            .multiple_values(true)
+            .help("Leaves these models out, using the same syntax as --select")
+            .takes_value(true)))
+    .subcommand(SubCommand::with_name("stats")
+        .about("Prints statistics about the dbt project")
+        .arg(Arg::with_name("format")
+            .long("format")
+            .value_name("FORMAT")
+            .possible_values(["text", "json"])
+            .default_value("text")
+            .help("How the statistics are printed")
+            .takes_value(true))
+        .arg(Arg::with_name("git")
+            .long("git")
+            .help("Adds stale models and models that change together, based on git history"))
+        .arg(Arg::with_name("months")
+            .long("months")
+            .value_name("N")
+            .default_value("6")
+            .help("How many months a model must go unchanged to count as stale")
+            .takes_value(true))
+        .arg(Arg::with_name("min-co-changes")
+            .long("min-co-changes")
+            .value_name("N")
+            .default_value("5")
+            .help("How many commits two models must share to be reported as changing together")
+            .takes_value(true)))
+    .subcommand(SubCommand::with_name("cache")
+        .about("Inspects and manages the on-disk cache in .dbtonic/cache")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("status")
+            .about("Shows the size of the cache and how many entries are stale"))
+        .subcommand(SubCommand::with_name("clear")
+            .about("Removes every entry from the cache"))
+        .subcommand(SubCommand::with_name("prune")
+            .about("Removes entries written by other versions of dbtonic or for models that no longer exist")))
+    .subcommand(SubCommand::with_name("config")
+        .about("Checks dbtonic.toml or writes a new one")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("validate")
+            .about("Reports unknown rules and keys, deprecated keys and values of the wrong type"))
+        .subcommand(SubCommand::with_name("init")
+            .about("Writes a dbtonic.toml with every rule and setting at its default")
+            .arg(Arg::with_name("force")
+                .long("force")
+                .help("Overwrites an existing dbtonic.toml"))))
     .subcommand(SubCommand::with_name("compile")
-        .about("Runs 'dbt compile' in the current directory"))
+        .about("Runs 'dbt compile' in the project's root")
+        .arg(Arg::with_name("compiled")
+            .long("compiled")
+            .help("Then lints the SQL dbt compiled, like 'lint --compiled'")))
     ;
 
     let matches = app.get_matches_from_safe(args).unwrap_or_else(|e| {
@@ -45,30 +344,86 @@ pub fn run(args: Vec<String>) {
         std::process::exit(1);
     });
 
-    let dbt_project = DbtProject{};
-    DbtProject::validate(&dbt_project);
+    // `--config` and the files given to `lint` are relative to where dbtonic
+    // was run, while the project's files are relative to its root. Without
+    // `--config`, the nearest dbtonic.toml is found from where dbtonic was
+    // run, so one in a folder below the project's root is used when run from it.
+    let run_dir = std::env::current_dir().unwrap_or_default();
+    let config_path = DbtonicConfig::locate(&run_dir, matches.value_of("config"));
+    let config_path = config_path.as_deref();
+
+    // Only the commands that read the project need to be run in one
+    let dbt_project = || DbtProject::validate(&run_dir);
 
-    if let Some(_) = matches.subcommand_matches("hello") {
+    if matches.subcommand_matches("hello").is_some() {
         println!("Hello person, I am dbtonic your friendly neighborhood dbt Connoisseur");
 
     } else if let Some(evaluate_matches) = matches.subcommand_matches("evaluate") {
-        cli::evaluate(evaluate_matches);
+        cli::evaluate(evaluate_matches, config_path, &dbt_project(), plugins);
+    }
+
+    if let Some(lint_matches) = matches.subcommand_matches("lint") {
+        cli::lint(lint_matches, config_path, &run_dir, &dbt_project(), plugins);
+    }
+
+    if let Some(fix_matches) = matches.subcommand_matches("fix") {
+        cli::fix(fix_matches, config_path, &dbt_project(), plugins);
+    }
+
+    if let Some(watch_matches) = matches.subcommand_matches("watch") {
+        cli::watch(watch_matches, config_path, &dbt_project(), plugins);
+    }
+
+    if let Some(lsp_matches) = matches.subcommand_matches("lsp") {
+        cli::lsp(lsp_matches, config_path, &dbt_project(), plugins);
     }
 
     if let Some(get_ast_matches) = matches.subcommand_matches("get-ast") {
-        cli::get_ast(get_ast_matches);
+        cli::get_ast(get_ast_matches, config_path, &dbt_project());
     }
 
     if let Some(get_tokens_matches) = matches.subcommand_matches("get-tokens") {
-        cli::get_tokens(get_tokens_matches);
+        cli::get_tokens(get_tokens_matches, config_path, &dbt_project());
+    }
+
+    if let Some(coverage_matches) = matches.subcommand_matches("coverage") {
+        cli::coverage(coverage_matches, config_path, &dbt_project());
+    }
+
+    if let Some(query_matches) = matches.subcommand_matches("query") {
+        cli::query(query_matches, config_path, &dbt_project());
+    }
+
+    if let Some(translate_matches) = matches.subcommand_matches("translate") {
+        cli::translate(translate_matches, config_path, &dbt_project());
+    }
+
+    if let Some(lineage_matches) = matches.subcommand_matches("lineage") {
+        cli::lineage(lineage_matches, config_path, &dbt_project());
+    }
+
+    if let Some(dag_matches) = matches.subcommand_matches("dag") {
+        cli::dag(dag_matches, config_path, &dbt_project());
+    }
+
+    if let Some(docs_matches) = matches.subcommand_matches("docs") {
+        cli::docs(docs_matches, config_path, &dbt_project(), plugins);
+    }
+
+    if let Some(stats_matches) = matches.subcommand_matches("stats") {
+        cli::stats(stats_matches, config_path, &dbt_project());
+    }
+
+    if let Some(cache_matches) = matches.subcommand_matches("cache") {
+        cli::cache(cache_matches, &dbt_project());
+    }
+
+    if let Some(config_matches) = matches.subcommand_matches("config") {
+        cli::config(config_matches, config_path, &run_dir, plugins);
     }
 
-    if let Some(_) = matches.subcommand_matches("compile") {
-        // Check if dbt is installed
-        DbtProject::check_dbt_version(&dbt_project);
-    
-        // Run 'dbt compile' in the current directory
-        DbtProject::run_dbt_compile(&dbt_project);
+    if let Some(compile_matches) = matches.subcommand_matches("compile") {
+        cli::compile(compile_matches, config_path, &dbt_project(), plugins);
     }
 
 }
@@ -0,0 +1,134 @@
+use std::path::Path;
+use dbtranslate::dialect::GenericDialect;
+use dbtranslate::tokenizer::Tokenizer;
+use dbtranslate::tokens::{Location, Token, TokenWithLocation};
+use crate::parser::dbt_project_yaml::DbtProjectYaml;
+use crate::parser::model_node::ModelNode;
+use crate::rules::ast_rules::require_materialization::materialization;
+
+// A `ref()` call in a model's SQL, from the `ref` to the closing paren
+#[derive(Debug, PartialEq, Clone)]
+pub struct RefCall {
+    pub model_name: String,
+    pub start: Location,
+    pub end: Location,
+}
+
+// The ref calls in the SQL. Read from the tokens rather than the AST, so
+// they're found in a model that doesn't parse while it's being edited. For
+// `ref('package', 'model')` the model is the last argument.
+pub fn ref_calls(sql: &str) -> Vec<RefCall> {
+    let tokens: Vec<TokenWithLocation> = Tokenizer::new(&GenericDialect {}, sql)
+        .tokenize_with_location()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|token| !matches!(token.token, Token::Whitespace(_)))
+        .collect();
+
+    let mut ref_calls = vec![];
+    for (index, token) in tokens.iter().enumerate() {
+        let is_ref = matches!(&token.token, Token::Word(word) if word.quote_style.is_none() && word.value.eq_ignore_ascii_case("ref"))
+            && tokens.get(index + 1).map(|token| &token.token) == Some(&Token::LParen);
+        if !is_ref {
+            continue;
+        }
+        let mut model_name = None;
+        for argument in &tokens[index + 2..] {
+            match &argument.token {
+                Token::SingleQuotedString(name) | Token::DoubleQuotedString(name) => model_name = Some(name.to_string()),
+                // The dialect reads `"orders"` as a quoted identifier
+                Token::Word(word) if word.quote_style == Some('"') => model_name = Some(word.value.to_string()),
+                Token::Comma => {}
+                Token::RParen => {
+                    if let Some(model_name) = model_name.take() {
+                        ref_calls.push(RefCall {
+                            model_name,
+                            start: token.location.clone(),
+                            end: Location { line: argument.location.line, column: argument.location.column + 1 },
+                        });
+                    }
+                    break;
+                }
+                _ => break,
+            }
+        }
+    }
+    ref_calls
+}
+
+// The ref call the location is in, if it's in one
+pub fn ref_at(sql: &str, location: &Location) -> Option<RefCall> {
+    let position = (location.line, location.column);
+    ref_calls(sql)
+        .into_iter()
+        .find(|ref_call| (ref_call.start.line, ref_call.start.column) <= position && position < (ref_call.end.line, ref_call.end.column))
+}
+
+// Markdown describing a model for an editor's hover: where it is, how it's
+// materialized, its tags and its description from the yml
+pub fn describe_model(model_node: &ModelNode, project: &DbtProjectYaml, base_path: &Path) -> String {
+    let path = model_node.path.strip_prefix(base_path).unwrap_or(&model_node.path);
+    let mut sections = vec![format!("**{}**\n\n`{}`", model_node.model_name, path.display())];
+
+    let mut config = vec![];
+    if let Some(materialized) = materialization(model_node, project) {
+        config.push(format!("- materialized: `{}`", materialized));
+    }
+    let tags = model_node.tags();
+    if !tags.is_empty() {
+        let tags: Vec<String> = tags.iter().map(|tag| format!("`{}`", tag)).collect();
+        config.push(format!("- tags: {}", tags.join(", ")));
+    }
+    if !config.is_empty() {
+        sections.push(config.join("\n"));
+    }
+
+    if let Some(description) = model_node.data.yaml.as_ref().and_then(|yaml| yaml.description.as_deref()) {
+        sections.push(description.trim().to_string());
+    }
+    sections.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_ref_calls() {
+        let sql = "SELECT id\nFROM {{ ref('orders') }} AS o\nJOIN {{ ref(\"shop\", 'customers') }} AS c ON TRUE\n\
+                   JOIN {{ ref( }} AS broken ON TRUE";
+        let ref_calls = ref_calls(sql);
+
+        assert_eq!(
+            ref_calls,
+            vec![
+                RefCall {
+                    model_name: "orders".to_string(),
+                    start: Location { line: 2, column: 9 },
+                    end: Location { line: 2, column: 22 },
+                },
+                RefCall {
+                    model_name: "customers".to_string(),
+                    start: Location { line: 3, column: 9 },
+                    end: Location { line: 3, column: 33 },
+                },
+            ]
+        );
+        assert_eq!(ref_at(sql, &Location { line: 2, column: 15 }).map(|ref_call| ref_call.model_name), Some("orders".to_string()));
+        assert_eq!(ref_at(sql, &Location { line: 2, column: 22 }), None);
+        assert_eq!(ref_at(sql, &Location { line: 1, column: 3 }), None);
+    }
+
+    #[test]
+    fn test_describe_model() {
+        let sql = "{{ config(materialized='table', tags=['nightly']) }} SELECT id FROM {{ ref('stg_orders') }}";
+        let mut model_node = ModelNode::from_test_sql("orders", sql);
+        model_node.path = PathBuf::from("/project/models/marts/orders.sql");
+
+        assert_eq!(
+            describe_model(&model_node, &DbtProjectYaml::default(), Path::new("/project")),
+            "**orders**\n\n`models/marts/orders.sql`\n\n- materialized: `table`\n- tags: `nightly`"
+        );
+    }
+}
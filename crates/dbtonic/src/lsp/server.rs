@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use dbtranslate::tokens::Location;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{CodeActionRequest, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionProviderCapability, Diagnostic,
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DidSaveTextDocumentParams, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, MarkupContent, MarkupKind, NumberOrString, OneOf, Position, PublishDiagnosticsParams,
+    Range, SaveOptions, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    TextDocumentSyncSaveOptions, Url, WorkspaceEdit,
+};
+use crate::configuration::dbtonic_config::DbtonicConfig;
+use crate::lsp::navigation::{describe_model, ref_at};
+use crate::parser::dag::DAG;
+use crate::parser::model_node::ModelNode;
+use crate::rules::rules_engine::{Plugin, RulesEngines, Severity, Violation};
+use crate::validation::dbt_project_operations::DbtProject;
+
+// Serves the Language Server Protocol over stdin and stdout, so editors
+// show the rules' violations as the models are edited, jump to the model
+// a `ref()` points at, show its config on hover and apply the rules' fixes
+// as quick fixes. The DAG and the project rules' violations are worked out
+// once and again whenever a file is saved, while the open models are linted
+// from the editor's unsaved text.
+pub fn run(config: DbtonicConfig, project: DbtProject, plugins: &[Plugin]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (connection, io_threads) = Connection::stdio();
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+
+    let mut server = Server::create(config, project, plugins);
+    server.serve(&connection)?;
+
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
+            open_close: Some(true),
+            change: Some(TextDocumentSyncKind::FULL),
+            save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions { include_text: Some(false) })),
+            ..TextDocumentSyncOptions::default()
+        })),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    }
+}
+
+struct Server<'a> {
+    config: DbtonicConfig,
+    plugins: &'a [Plugin],
+    project: DbtProject,
+    dag: DAG,
+    rules_engines: RulesEngines,
+    // The project rules' violations in the saved files
+    project_violations: Vec<Violation>,
+    // The text the editor has for each open document, saved or not
+    documents: HashMap<Url, String>,
+}
+
+impl<'a> Server<'a> {
+    fn create(config: DbtonicConfig, project: DbtProject, plugins: &'a [Plugin]) -> Self {
+        let dag = DAG::create(None, &project, &config.parsing, config.dialect);
+        let mut server = Server {
+            rules_engines: RulesEngines::create(&config, plugins, &project, &[]),
+            config,
+            plugins,
+            project,
+            dag,
+            project_violations: vec![],
+            documents: HashMap::new(),
+        };
+        server.run_project_rules();
+        server
+    }
+
+    // Sets up the engines for the DAG as it is and runs the project rules
+    fn run_project_rules(&mut self) {
+        self.rules_engines =
+            RulesEngines::create(&self.config, self.plugins, &self.project, &self.dag.lintable_nodes(&self.config.resources))
+                .with_lineage(&self.dag.lineage)
+                .with_singular_tests(&self.dag.singular_tests);
+        self.project_violations = self.rules_engines.project().run_project_rules(&self.dag);
+    }
+
+    fn serve(&mut self, connection: &Connection) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    connection.sender.send(Message::Response(self.respond(request)))?;
+                }
+                Message::Notification(notification) => {
+                    for (uri, diagnostics) in self.notify(notification) {
+                        let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+                        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+                        connection.sender.send(Message::Notification(notification))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn respond(&self, request: Request) -> Response {
+        let result = match request.method.as_str() {
+            GotoDefinition::METHOD => serde_json::from_value(request.params)
+                .map(|params| self.definition(params))
+                .and_then(serde_json::to_value),
+            HoverRequest::METHOD => serde_json::from_value(request.params)
+                .map(|params| self.hover(params))
+                .and_then(serde_json::to_value),
+            CodeActionRequest::METHOD => serde_json::from_value(request.params)
+                .map(|params| self.code_actions(params))
+                .and_then(serde_json::to_value),
+            _ => {
+                let message = format!("dbtonic doesn't handle {}", request.method);
+                return Response::new_err(request.id, ErrorCode::MethodNotFound as i32, message);
+            }
+        };
+        match result {
+            Ok(value) => Response::new_ok(request.id, value),
+            Err(e) => Response::new_err(request.id, ErrorCode::InvalidParams as i32, e.to_string()),
+        }
+    }
+
+    // Handles a notification, returning the diagnostics to publish for
+    // each document it changed
+    fn notify(&mut self, notification: Notification) -> Vec<(Url, Vec<Diagnostic>)> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Ok(params) = notification.extract::<DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD) else {
+                    return vec![];
+                };
+                let uri = params.text_document.uri;
+                self.documents.insert(uri.clone(), params.text_document.text);
+                vec![(uri.clone(), self.diagnostics(&uri))]
+            }
+            DidChangeTextDocument::METHOD => {
+                let Ok(params) = notification.extract::<DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD) else {
+                    return vec![];
+                };
+                // With full syncing the last change is the whole document
+                let Some(change) = params.content_changes.into_iter().last() else {
+                    return vec![];
+                };
+                let uri = params.text_document.uri;
+                self.documents.insert(uri.clone(), change.text);
+                vec![(uri.clone(), self.diagnostics(&uri))]
+            }
+            DidSaveTextDocument::METHOD => {
+                let Ok(params) = notification.extract::<DidSaveTextDocumentParams>(DidSaveTextDocument::METHOD) else {
+                    return vec![];
+                };
+                // A saved yml can change the results of any open model
+                if let Ok(path) = params.text_document.uri.to_file_path() {
+                    self.dag.refresh(&self.project.root, &[path], &self.config.parsing, self.config.dialect);
+                    self.run_project_rules();
+                }
+                let uris: Vec<Url> = self.documents.keys().cloned().collect();
+                uris.into_iter().map(|uri| (uri.clone(), self.diagnostics(&uri))).collect()
+            }
+            DidCloseTextDocument::METHOD => {
+                let Ok(params) = notification.extract::<DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD) else {
+                    return vec![];
+                };
+                self.documents.remove(&params.text_document.uri);
+                vec![(params.text_document.uri, vec![])]
+            }
+            _ => vec![],
+        }
+    }
+
+    // The violations in the document's current text. Documents that aren't
+    // models, or tests and analyses the rules don't run on, have none.
+    fn diagnostics(&self, uri: &Url) -> Vec<Diagnostic> {
+        let Some(model_node) = self.model_node(uri) else {
+            return vec![];
+        };
+        let parse_errors = model_node.data.errors.as_deref().unwrap_or_default().iter().map(|error| {
+            diagnostic(error.location.as_ref(), None, DiagnosticSeverity::ERROR, "parse_error", error.message.clone())
+        });
+        if model_node.parse_failed() {
+            return parse_errors.collect();
+        }
+        // The statements that did parse are still linted, alongside the
+        // errors for the ones that didn't
+        let violations = self.violations(&model_node);
+        parse_errors.chain(violations.iter().map(to_diagnostic)).collect()
+    }
+
+    // The model in the document's current text, if it's one the rules run on
+    fn model_node(&self, uri: &Url) -> Option<ModelNode> {
+        let (Ok(path), Some(sql)) = (uri.to_file_path(), self.documents.get(uri)) else {
+            return None;
+        };
+        self.dag.model_from_sql(
+            path,
+            sql,
+            &self.config.resources,
+            &self.config.parsing,
+            self.config.dialect,
+            &self.project.root,
+        )
+    }
+
+    // The model's violations, along with the project rules' violations that
+    // point at a place in it. Those come from the saved file, so they're
+    // only as current as the last save.
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let project_violations = self
+            .project_violations
+            .iter()
+            .filter(|violation| violation.path.as_ref() == Some(&model_node.path) && violation.location.is_some())
+            .cloned()
+            .collect();
+        self.rules_engines.for_model(model_node).run_violations_with(model_node, project_violations)
+    }
+
+    // A quick fix for each violation with edits in the requested range
+    fn code_actions(&self, params: CodeActionParams) -> Vec<CodeActionOrCommand> {
+        let uri = params.text_document.uri;
+        let Some(model_node) = self.model_node(&uri).filter(|model_node| !model_node.parse_failed()) else {
+            return vec![];
+        };
+        self.violations(&model_node)
+            .iter()
+            .filter(|violation| !violation.edits.is_empty())
+            .map(|violation| (violation, to_diagnostic(violation)))
+            .filter(|(_, diagnostic)| diagnostic.range.start <= params.range.end && params.range.start <= diagnostic.range.end)
+            .map(|(violation, diagnostic)| CodeActionOrCommand::CodeAction(to_code_action(&uri, violation, diagnostic)))
+            .collect()
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let model_node = self.referenced_model(&position.text_document.uri, position.position)?.1;
+        let uri = Url::from_file_path(&model_node.path).ok()?;
+        let start = Position { line: 0, character: 0 };
+        Some(GotoDefinitionResponse::Scalar(lsp_types::Location { uri, range: Range { start, end: start } }))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let (range, model_node) = self.referenced_model(&position.text_document.uri, position.position)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: describe_model(model_node, &self.dag.project, &self.project.root),
+            }),
+            range: Some(range),
+        })
+    }
+
+    // The model the `ref()` at the position points at, and where the ref is
+    fn referenced_model(&self, uri: &Url, position: Position) -> Option<(Range, &ModelNode)> {
+        let sql = match self.documents.get(uri) {
+            Some(sql) => sql.clone(),
+            None => fs::read_to_string(uri.to_file_path().ok()?).ok()?,
+        };
+        let ref_call = ref_at(&sql, &to_location(position))?;
+        let model_node = self.dag.model_nodes.iter().find(|model_node| model_node.model_name == ref_call.model_name)?;
+        Some((Range { start: to_position(&ref_call.start), end: to_position(&ref_call.end) }, model_node))
+    }
+}
+
+fn to_diagnostic(violation: &Violation) -> Diagnostic {
+    let severity = match violation.severity {
+        Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warn => DiagnosticSeverity::WARNING,
+        Severity::Info => DiagnosticSeverity::INFORMATION,
+    };
+    diagnostic(
+        violation.location.as_ref(),
+        violation.end_location.as_ref(),
+        severity,
+        &violation.rule_name,
+        violation.message.clone(),
+    )
+}
+
+// A quick fix applying the violation's edits to the document
+fn to_code_action(uri: &Url, violation: &Violation, diagnostic: Diagnostic) -> CodeAction {
+    let edits = violation
+        .edits
+        .iter()
+        .map(|edit| lsp_types::TextEdit {
+            range: Range { start: to_position(&edit.start), end: to_position(&edit.end) },
+            new_text: edit.replacement.clone(),
+        })
+        .collect();
+    CodeAction {
+        title: format!("Fix {}", violation.rule_name),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diagnostic]),
+        edit: Some(WorkspaceEdit { changes: Some(HashMap::from([(uri.clone(), edits)])), ..WorkspaceEdit::default() }),
+        is_preferred: Some(true),
+        ..CodeAction::default()
+    }
+}
+
+// Violations without a location point at the start of the model, and
+// ones without an end at a single character
+fn diagnostic(
+    start: Option<&Location>,
+    end: Option<&Location>,
+    severity: DiagnosticSeverity,
+    code: &str,
+    message: String,
+) -> Diagnostic {
+    let start = start.map(to_position).unwrap_or(Position { line: 0, character: 0 });
+    let end = end.map(to_position).unwrap_or(Position { line: start.line, character: start.character + 1 });
+    Diagnostic {
+        range: Range { start, end },
+        severity: Some(severity),
+        code: Some(NumberOrString::String(code.to_string())),
+        source: Some("dbtonic".to_string()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+// dbtonic counts lines and columns from 1, the protocol from 0
+fn to_position(location: &Location) -> Position {
+    Position {
+        line: location.line.saturating_sub(1) as u32,
+        character: location.column.saturating_sub(1) as u32,
+    }
+}
+
+fn to_location(position: Position) -> Location {
+    Location { line: position.line as u64 + 1, column: position.character as u64 + 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::edits::TextEdit;
+
+    #[test]
+    fn test_to_diagnostic() {
+        let mut violation = Violation::new(
+            "explicit_aliases".to_string(),
+            "The function call `SUM(amount)` has no alias.".to_string(),
+            Some(Location { line: 2, column: 5 }),
+        )
+        .with_end_location(Location { line: 2, column: 16 });
+        violation.severity = Severity::Warn;
+
+        let diagnostic = to_diagnostic(&violation);
+
+        assert_eq!(
+            diagnostic.range,
+            Range { start: Position { line: 1, character: 4 }, end: Position { line: 1, character: 15 } }
+        );
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(diagnostic.code, Some(NumberOrString::String("explicit_aliases".to_string())));
+        assert_eq!(to_location(diagnostic.range.start), Location { line: 2, column: 5 });
+    }
+
+    #[test]
+    fn test_to_code_action() {
+        let violation = Violation::new(
+            "keyword_case".to_string(),
+            "The keyword `select` should be upper case.".to_string(),
+            Some(Location { line: 1, column: 1 }),
+        )
+        .with_edits(vec![TextEdit {
+            start: Location { line: 1, column: 1 },
+            end: Location { line: 1, column: 7 },
+            replacement: "SELECT".to_string(),
+        }]);
+        let uri = Url::parse("file:///project/models/orders.sql").unwrap();
+
+        let code_action = to_code_action(&uri, &violation, to_diagnostic(&violation));
+
+        assert_eq!(code_action.kind, Some(CodeActionKind::QUICKFIX));
+        let changes = code_action.edit.unwrap().changes.unwrap();
+        assert_eq!(
+            changes[&uri],
+            vec![lsp_types::TextEdit {
+                range: Range { start: Position { line: 0, character: 0 }, end: Position { line: 0, character: 6 } },
+                new_text: "SELECT".to_string(),
+            }]
+        );
+    }
+}
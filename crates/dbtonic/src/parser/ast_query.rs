@@ -0,0 +1,724 @@
+use std::fmt;
+use dbtranslate::ast::{
+    DbtConfigValue, DbtMacroCall, Expr, FunctionArg, FunctionArgExpr, Join, JoinConstraint, JoinOperator, OrderByExpr, Query,
+    SelectItem, SetExpr, Spanned, Statement, TableFactor, TableWithJoins,
+};
+use dbtranslate::tokens::Span;
+use crate::reporting::suggestions::with_suggestion;
+
+// The kinds of node a selector can ask for, along with the attributes each
+// one can be filtered on.
+pub const NODE_KINDS: [(&str, &[&str]); 14] = [
+    ("functions", &["name", "args", "distinct", "window"]),
+    ("joins", &["kind", "table"]),
+    ("tables", &["name", "args"]),
+    ("refs", &["name", "package", "version"]),
+    ("sources", &["source", "table"]),
+    // `{{ this }}`
+    ("this", &[]),
+    ("ctes", &["name"]),
+    ("subqueries", &["depth"]),
+    ("casts", &["type"]),
+    ("cases", &["branches"]),
+    ("columns", &["name", "table"]),
+    ("vars", &["name", "default"]),
+    ("env_vars", &["name", "default"]),
+    ("macros", &["namespace", "name"]),
+];
+
+// A piece of a model's AST that a selector matched
+#[derive(Debug, PartialEq, Clone)]
+pub struct AstNode {
+    pub kind: &'static str,
+    pub attributes: Vec<(&'static str, String)>,
+    // The node rendered back to SQL
+    pub text: String,
+    // Where the node is in the model, empty if the parser couldn't tell
+    pub span: Span,
+}
+
+impl AstNode {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| *attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// A parsed selector such as `functions[name=datediff]` or
+// `joins[kind=cross]`. Every filter has to hold for a node to match.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Selector {
+    pub kind: &'static str,
+    pub filters: Vec<Filter>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Filter {
+    pub attribute: String,
+    pub value: String,
+    // `[name!=value]` rather than `[name=value]`
+    pub negated: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SelectorError {
+    UnknownKind(String),
+    UnknownAttribute(String),
+    Malformed(String),
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SelectorError::UnknownKind(message) => write!(f, "Unknown node kind: {}", message),
+            SelectorError::UnknownAttribute(message) => write!(f, "Unknown attribute: {}", message),
+            SelectorError::Malformed(message) => write!(f, "Malformed selector: {}", message),
+        }
+    }
+}
+
+impl Selector {
+    pub fn parse(selector: &str) -> Result<Selector, SelectorError> {
+        let selector = selector.trim();
+        let (kind, mut rest) = match selector.find('[') {
+            Some(index) => (&selector[..index], &selector[index..]),
+            None => (selector, ""),
+        };
+
+        let (kind, attributes) = NODE_KINDS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(kind.trim()))
+            .copied()
+            .ok_or_else(|| {
+                SelectorError::UnknownKind(with_suggestion(
+                    format!("'{}'.", kind.trim()),
+                    kind.trim(),
+                    NODE_KINDS.iter().map(|(name, _)| *name),
+                ))
+            })?;
+
+        let mut filters = vec![];
+        while !rest.is_empty() {
+            let end = match (rest.starts_with('['), rest.find(']')) {
+                (true, Some(end)) => end,
+                _ => return Err(SelectorError::Malformed(format!("expected `[attribute=value]` in '{}'", selector))),
+            };
+            filters.push(parse_filter(&rest[1..end], kind, attributes)?);
+            rest = rest[end + 1..].trim_start();
+        }
+
+        Ok(Selector { kind, filters })
+    }
+
+    pub fn matches(&self, node: &AstNode) -> bool {
+        node.kind == self.kind
+            && self.filters.iter().all(|filter| {
+                let equal = node
+                    .attribute(&filter.attribute)
+                    .is_some_and(|value| value.eq_ignore_ascii_case(&filter.value));
+                equal != filter.negated
+            })
+    }
+
+    // Every node in the statements that the selector matches, in the order
+    // they appear in the query
+    pub fn select(&self, statements: &[Statement]) -> Vec<AstNode> {
+        ast_nodes(statements)
+            .into_iter()
+            .filter(|node| self.matches(node))
+            .collect()
+    }
+}
+
+fn parse_filter(filter: &str, kind: &str, attributes: &[&str]) -> Result<Filter, SelectorError> {
+    let (attribute, value, negated) = match (filter.find("!="), filter.find('=')) {
+        (Some(index), _) => (&filter[..index], &filter[index + 2..], true),
+        (None, Some(index)) => (&filter[..index], &filter[index + 1..], false),
+        (None, None) => return Err(SelectorError::Malformed(format!("expected `attribute=value` but found '{}'", filter))),
+    };
+
+    let attribute = attribute.trim().to_lowercase();
+    if !attributes.contains(&attribute.as_str()) {
+        return Err(SelectorError::UnknownAttribute(with_suggestion(
+            format!("{} have no attribute '{}'.", kind, attribute),
+            &attribute,
+            attributes.iter().copied(),
+        )));
+    }
+
+    // Quotes are optional, `[name='datediff']` and `[name=datediff]` are the same
+    let value = value.trim().trim_matches(|c| c == '\'' || c == '"').to_string();
+
+    Ok(Filter { attribute, value, negated })
+}
+
+// Walks the statements and collects every node the selector language knows
+// about. Unlike `selects`, this does go into subqueries inside expressions.
+pub fn ast_nodes(statements: &[Statement]) -> Vec<AstNode> {
+    let mut nodes = vec![];
+    for statement in statements {
+        let Statement::Query(query) = statement;
+        collect_query(query, &mut nodes);
+    }
+    set_subquery_depths(&mut nodes);
+    nodes
+}
+
+// The nodes in a single expression, including any subqueries in it
+pub fn expr_nodes(expr: &Expr) -> Vec<AstNode> {
+    let mut nodes = vec![];
+    collect_expr(expr, &mut nodes);
+    set_subquery_depths(&mut nodes);
+    nodes
+}
+
+// A subquery is one deeper than the subqueries around it, so one directly
+// in the model's query or a CTE has a depth of 1
+fn set_subquery_depths(nodes: &mut [AstNode]) {
+    let spans: Vec<Span> = nodes.iter().filter(|node| node.kind == "subqueries").map(|node| node.span.clone()).collect();
+    for node in nodes.iter_mut().filter(|node| node.kind == "subqueries") {
+        let depth = 1 + spans.iter().filter(|span| contains(span, &node.span)).count();
+        node.attributes = vec![("depth", depth.to_string())];
+    }
+}
+
+pub fn contains(outer: &Span, inner: &Span) -> bool {
+    !outer.is_empty() && !inner.is_empty() && outer != inner && outer.start <= inner.start && inner.end <= outer.end
+}
+
+fn subquery_node(text: String, span: Span) -> AstNode {
+    // The depth is filled in once every subquery has been found
+    AstNode { kind: "subqueries", attributes: vec![], text, span }
+}
+
+fn collect_query(query: &Query, nodes: &mut Vec<AstNode>) {
+    if let Some(config) = &query.config {
+        // By key, since they're kept in a HashMap
+        let mut values: Vec<_> = config.values.iter().collect();
+        values.sort_by_key(|(key, _)| key.as_str());
+        for (_, value) in values {
+            collect_config_value(value, nodes);
+        }
+    }
+    if let Some(with) = &query.with {
+        for cte in &with.cte_tables {
+            nodes.push(AstNode {
+                kind: "ctes",
+                attributes: vec![("name", cte.alias.name.value.to_string())],
+                text: cte.to_string(),
+                span: cte.span(),
+            });
+            collect_query(&cte.query, nodes);
+        }
+    }
+    collect_set_expr(&query.body, nodes);
+    collect_order_by(&query.order_by, nodes);
+    if let Some(limit) = &query.limit {
+        collect_expr(limit, nodes);
+    }
+}
+
+// The Jinja in a `config()` value, like `enabled=var('run_heavy_models')`
+fn collect_config_value(value: &DbtConfigValue, nodes: &mut Vec<AstNode>) {
+    match value {
+        DbtConfigValue::Jinja(expr) => collect_expr(expr, nodes),
+        DbtConfigValue::List(values) => values.iter().for_each(|value| collect_config_value(value, nodes)),
+        DbtConfigValue::Dict(entries) => entries.values().for_each(|value| collect_config_value(value, nodes)),
+        _ => {}
+    }
+}
+
+fn collect_set_expr(set_expr: &SetExpr, nodes: &mut Vec<AstNode>) {
+    match set_expr {
+        SetExpr::Select(select) => {
+            for item in &select.projection {
+                match item {
+                    SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => collect_expr(expr, nodes),
+                    _ => {}
+                }
+            }
+            for table_with_joins in &select.from {
+                collect_table_with_joins(table_with_joins, nodes);
+            }
+            let clauses = select.selection.iter()
+                .chain(&select.group_by)
+                .chain(&select.having)
+                .chain(&select.qualify);
+            for expr in clauses {
+                collect_expr(expr, nodes);
+            }
+        }
+        SetExpr::Query(query) => collect_query(query, nodes),
+        SetExpr::SetOperation { left, right, .. } => {
+            collect_set_expr(left, nodes);
+            collect_set_expr(right, nodes);
+        }
+        SetExpr::Values(values) => {
+            for expr in values.rows.iter().flatten() {
+                collect_expr(expr, nodes);
+            }
+        }
+        SetExpr::Insert(_) | SetExpr::Table(_) | SetExpr::JinjaBlock(_) => {}
+    }
+}
+
+fn collect_table_with_joins(table_with_joins: &TableWithJoins, nodes: &mut Vec<AstNode>) {
+    collect_table_factor(&table_with_joins.relation, nodes);
+    for join in &table_with_joins.joins {
+        nodes.push(AstNode {
+            kind: "joins",
+            attributes: vec![
+                ("kind", join_kind(&join.join_operator).to_string()),
+                ("table", relation_name(&join.relation)),
+            ],
+            text: join.to_string().trim().to_string(),
+            span: join.span(),
+        });
+        collect_join(join, nodes);
+    }
+}
+
+fn collect_join(join: &Join, nodes: &mut Vec<AstNode>) {
+    collect_table_factor(&join.relation, nodes);
+    match &join.join_operator {
+        JoinOperator::Inner(constraint)
+        | JoinOperator::LeftOuter(constraint)
+        | JoinOperator::RightOuter(constraint)
+        | JoinOperator::FullOuter(constraint)
+        | JoinOperator::LeftSemi(constraint)
+        | JoinOperator::RightSemi(constraint)
+        | JoinOperator::LeftAnti(constraint)
+        | JoinOperator::RightAnti(constraint) => {
+            if let JoinConstraint::On(expr) = constraint {
+                collect_expr(expr, nodes);
+            }
+        }
+        JoinOperator::CrossJoin | JoinOperator::CrossApply | JoinOperator::OuterApply | JoinOperator::PositionalJoin => {}
+    }
+}
+
+fn join_kind(join_operator: &JoinOperator) -> &'static str {
+    match join_operator {
+        JoinOperator::Inner(_) => "inner",
+        JoinOperator::LeftOuter(_) => "left",
+        JoinOperator::RightOuter(_) => "right",
+        JoinOperator::FullOuter(_) => "full",
+        JoinOperator::CrossJoin => "cross",
+        JoinOperator::LeftSemi(_) => "left_semi",
+        JoinOperator::RightSemi(_) => "right_semi",
+        JoinOperator::LeftAnti(_) => "left_anti",
+        JoinOperator::RightAnti(_) => "right_anti",
+        JoinOperator::CrossApply => "cross_apply",
+        JoinOperator::OuterApply => "outer_apply",
+        JoinOperator::PositionalJoin => "positional",
+    }
+}
+
+// What a join is joining to: the table, the ref'd model or the source table
+pub fn relation_name(relation: &TableFactor) -> String {
+    match relation {
+        TableFactor::Table { name, .. } => name.to_string(),
+        TableFactor::DbtRef { model_name, .. } => model_name.value.to_string(),
+        TableFactor::DbtSource { source_name, table_name, .. } => format!("{}.{}", source_name.value, table_name.value),
+        other => other.to_string(),
+    }
+}
+
+fn collect_table_factor(table_factor: &TableFactor, nodes: &mut Vec<AstNode>) {
+    match table_factor {
+        TableFactor::Table { name, args, .. } => {
+            nodes.push(AstNode {
+                kind: "tables",
+                attributes: vec![
+                    ("name", name.to_string()),
+                    // Only table-valued functions like `generate_series(1, 10)` have args
+                    ("args", args.as_ref().map(|args| args.len().to_string()).unwrap_or_default()),
+                ],
+                text: table_factor.to_string(),
+                span: table_factor.span(),
+            });
+            for arg in args.iter().flatten() {
+                collect_function_arg(arg, nodes);
+            }
+        }
+        TableFactor::Derived { subquery, .. } => {
+            nodes.push(subquery_node(table_factor.to_string(), table_factor.span()));
+            collect_query(subquery, nodes);
+        }
+        TableFactor::Function { name, args, .. } => {
+            nodes.push(AstNode {
+                kind: "tables",
+                attributes: vec![("name", name.to_string()), ("args", args.len().to_string())],
+                text: table_factor.to_string(),
+                span: table_factor.span(),
+            });
+            for arg in args {
+                collect_function_arg(arg, nodes);
+            }
+        }
+        TableFactor::DbtRef { package, model_name, version, .. } => nodes.push(AstNode {
+            kind: "refs",
+            attributes: vec![
+                ("name", model_name.value.to_string()),
+                ("package", package.as_ref().map(|package| package.value.to_string()).unwrap_or_default()),
+                // `v=2` and `v='2'` are the same version
+                ("version", version.as_ref().map(|version| version.to_string().trim_matches(['\'', '"']).to_string()).unwrap_or_default()),
+            ],
+            text: table_factor.to_string(),
+            span: table_factor.span(),
+        }),
+        TableFactor::DbtSource { source_name, table_name, .. } => nodes.push(AstNode {
+            kind: "sources",
+            attributes: vec![("source", source_name.value.to_string()), ("table", table_name.value.to_string())],
+            text: table_factor.to_string(),
+            span: table_factor.span(),
+        }),
+        TableFactor::DbtThis { .. } => nodes.push(AstNode {
+            kind: "this",
+            attributes: vec![],
+            text: table_factor.to_string(),
+            span: table_factor.span(),
+        }),
+        TableFactor::DbtMacro { call, .. } => nodes.push(macro_node(call)),
+        TableFactor::TableFunction { expr, .. } | TableFactor::Pivot { aggregate_function: expr, .. } => collect_expr(expr, nodes),
+        TableFactor::UNNEST { array_exprs, .. } => collect_exprs(array_exprs, nodes),
+        TableFactor::NestedJoin { table_with_joins, .. } => collect_table_with_joins(table_with_joins, nodes),
+    }
+}
+
+fn macro_node(call: &DbtMacroCall) -> AstNode {
+    AstNode {
+        kind: "macros",
+        attributes: vec![
+            ("namespace", call.namespace.as_ref().map(|namespace| namespace.value.to_string()).unwrap_or_default()),
+            ("name", call.name.value.to_string()),
+        ],
+        text: call.to_string(),
+        span: call.span(),
+    }
+}
+
+fn collect_function_arg(arg: &FunctionArg, nodes: &mut Vec<AstNode>) {
+    let arg = match arg {
+        FunctionArg::Named { arg, .. } => arg,
+        FunctionArg::Unnamed(arg) => arg,
+    };
+    if let FunctionArgExpr::Expr(expr) = arg {
+        collect_expr(expr, nodes);
+    }
+}
+
+fn collect_order_by(order_by: &[OrderByExpr], nodes: &mut Vec<AstNode>) {
+    for order_by_expr in order_by {
+        collect_expr(&order_by_expr.expr, nodes);
+    }
+}
+
+fn collect_exprs<'a>(exprs: impl IntoIterator<Item = &'a Expr>, nodes: &mut Vec<AstNode>) {
+    for expr in exprs {
+        collect_expr(expr, nodes);
+    }
+}
+
+fn collect_expr(expr: &Expr, nodes: &mut Vec<AstNode>) {
+    match expr {
+        Expr::Function(function) => {
+            nodes.push(AstNode {
+                kind: "functions",
+                attributes: vec![
+                    ("name", function.name.to_string()),
+                    ("args", function.args.len().to_string()),
+                    ("distinct", function.distinct.to_string()),
+                    ("window", function.over.is_some().to_string()),
+                ],
+                text: expr.to_string(),
+                span: expr.span(),
+            });
+            for arg in &function.args {
+                collect_function_arg(arg, nodes);
+            }
+            if let Some(over) = &function.over {
+                collect_exprs(&over.partition_by, nodes);
+                collect_order_by(&over.order_by, nodes);
+            }
+        }
+        Expr::Cast { expr: inner, data_type }
+        | Expr::TryCast { expr: inner, data_type }
+        | Expr::SafeCast { expr: inner, data_type } => {
+            nodes.push(AstNode {
+                kind: "casts",
+                attributes: vec![("type", data_type.to_string())],
+                text: expr.to_string(),
+                span: expr.span(),
+            });
+            collect_expr(inner, nodes);
+        }
+        Expr::JsonAccess { left, right, .. } => collect_exprs([left.as_ref(), right.as_ref()], nodes),
+        Expr::CompositeAccess { expr, .. }
+        | Expr::IsFalse(expr)
+        | Expr::IsNotFalse(expr)
+        | Expr::IsTrue(expr)
+        | Expr::IsNotTrue(expr)
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::IsUnknown(expr)
+        | Expr::IsNotUnknown(expr)
+        | Expr::AnyOp(expr)
+        | Expr::AllOp(expr)
+        | Expr::UnaryOp { expr, .. }
+        | Expr::AtTimeZone { timestamp: expr, .. }
+        | Expr::Extract { expr, .. }
+        | Expr::Ceil { expr, .. }
+        | Expr::Floor { expr, .. }
+        | Expr::Collate { expr, .. }
+        | Expr::Nested(expr) => collect_expr(expr, nodes),
+        Expr::IsDistinctFrom(left, right)
+        | Expr::IsNotDistinctFrom(left, right)
+        | Expr::BinaryOp { left, right, .. }
+        | Expr::Position { expr: left, r#in: right }
+        | Expr::AggregateExpressionWithFilter { expr: left, filter: right } => {
+            collect_exprs([left.as_ref(), right.as_ref()], nodes)
+        }
+        Expr::Like { expr, pattern, .. }
+        | Expr::ILike { expr, pattern, .. }
+        | Expr::SimilarTo { expr, pattern, .. } => collect_exprs([expr.as_ref(), pattern.as_ref()], nodes),
+        Expr::InList { expr, list, .. } => {
+            collect_expr(expr, nodes);
+            collect_exprs(list, nodes);
+        }
+        Expr::InSubquery { expr: inner, subquery, .. } => {
+            collect_expr(inner, nodes);
+            nodes.push(subquery_node(format!("({})", subquery), expr.span()));
+            collect_query(subquery, nodes);
+        }
+        Expr::InUnnest { expr, array_expr, .. } => collect_exprs([expr.as_ref(), array_expr.as_ref()], nodes),
+        Expr::Between { expr, low, high, .. } => collect_exprs([expr.as_ref(), low.as_ref(), high.as_ref()], nodes),
+        Expr::Substring { expr, substring_from, substring_for } => {
+            collect_expr(expr, nodes);
+            collect_exprs(substring_from.iter().chain(substring_for).map(|expr| expr.as_ref()), nodes);
+        }
+        Expr::Trim { expr, trim_what, .. } => {
+            collect_expr(expr, nodes);
+            collect_exprs(trim_what.iter().map(|expr| expr.as_ref()), nodes);
+        }
+        Expr::Overlay { expr, overlay_what, overlay_from, overlay_for } => {
+            collect_exprs([expr.as_ref(), overlay_what.as_ref(), overlay_from.as_ref()], nodes);
+            collect_exprs(overlay_for.iter().map(|expr| expr.as_ref()), nodes);
+        }
+        Expr::MapAccess { column, keys } => {
+            collect_expr(column, nodes);
+            collect_exprs(keys, nodes);
+        }
+        Expr::Case { operand, conditions, results, else_result } => {
+            nodes.push(AstNode {
+                kind: "cases",
+                attributes: vec![("branches", conditions.len().to_string())],
+                text: expr.to_string(),
+                span: expr.span(),
+            });
+            collect_exprs(operand.iter().map(|expr| expr.as_ref()), nodes);
+            collect_exprs(conditions, nodes);
+            collect_exprs(results, nodes);
+            collect_exprs(else_result.iter().map(|expr| expr.as_ref()), nodes);
+        }
+        Expr::Exists { subquery, .. } | Expr::Subquery(subquery) | Expr::ArraySubquery(subquery) => {
+            nodes.push(subquery_node(format!("({})", subquery), expr.span()));
+            collect_query(subquery, nodes);
+        }
+        Expr::ListAgg(list_agg) => {
+            collect_expr(&list_agg.expr, nodes);
+            collect_exprs(list_agg.separator.iter().map(|expr| expr.as_ref()), nodes);
+        }
+        Expr::ArrayAgg(array_agg) => collect_expr(&array_agg.expr, nodes),
+        Expr::GroupingSets(sets) | Expr::Cube(sets) | Expr::Rollup(sets) => collect_exprs(sets.iter().flatten(), nodes),
+        Expr::Tuple(exprs) | Expr::Struct { values: exprs, .. } => collect_exprs(exprs, nodes),
+        Expr::Named { expr, .. } => collect_expr(expr, nodes),
+        Expr::Lambda { body, .. } => collect_expr(body, nodes),
+        Expr::ArrayIndex { obj, indexes } => {
+            collect_expr(obj, nodes);
+            collect_exprs(indexes, nodes);
+        }
+        Expr::Array(array) => collect_exprs(&array.elem, nodes),
+        Expr::Interval { value, .. } => collect_expr(value, nodes),
+        // The SQL inside the block is kept as tokens, only the SQL in front of it is parsed
+        Expr::JinjaBlock { expr, .. } => collect_exprs(expr.iter().map(|expr| expr.as_ref()), nodes),
+        Expr::DbtVar { name, default } | Expr::DbtEnvVar { name, default } => nodes.push(AstNode {
+            kind: if matches!(expr, Expr::DbtVar { .. }) { "vars" } else { "env_vars" },
+            attributes: vec![
+                ("name", name.value.to_string()),
+                ("default", default.as_ref().map(|default| default.to_string()).unwrap_or_default()),
+            ],
+            text: expr.to_string(),
+            span: expr.span(),
+        }),
+        Expr::DbtMacroCall(call) => nodes.push(macro_node(call)),
+        Expr::Identifier(ident) => nodes.push(AstNode {
+            kind: "columns",
+            attributes: vec![("name", ident.value.to_string()), ("table", String::new())],
+            text: expr.to_string(),
+            span: expr.span(),
+        }),
+        Expr::CompoundIdentifier(idents) => {
+            let Some((name, table)) = idents.split_last() else {
+                return;
+            };
+            let table = table.iter().map(|ident| ident.to_string()).collect::<Vec<_>>().join(".");
+            nodes.push(AstNode {
+                kind: "columns",
+                attributes: vec![("name", name.value.to_string()), ("table", table)],
+                text: expr.to_string(),
+                span: expr.span(),
+            });
+        }
+        Expr::Value(_)
+        | Expr::IntroducedString { .. }
+        | Expr::TypedString { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::dialect::GenericDialect;
+    use dbtranslate::parser::Parser;
+
+    fn select(selector: &str, sql: &str) -> Vec<String> {
+        let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+        Selector::parse(selector)
+            .unwrap()
+            .select(&statements)
+            .into_iter()
+            .map(|node| node.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        assert_eq!(
+            Selector::parse("Functions[name = 'datediff'][args!=3]").unwrap(),
+            Selector {
+                kind: "functions",
+                filters: vec![
+                    Filter { attribute: "name".to_string(), value: "datediff".to_string(), negated: false },
+                    Filter { attribute: "args".to_string(), value: "3".to_string(), negated: true },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_selector_errors() {
+        assert_eq!(
+            Selector::parse("function[name=datediff]"),
+            Err(SelectorError::UnknownKind("'function'. Did you mean 'functions'?".to_string()))
+        );
+        assert_eq!(
+            Selector::parse("joins[kinds=cross]"),
+            Err(SelectorError::UnknownAttribute("joins have no attribute 'kinds'. Did you mean 'kind'?".to_string()))
+        );
+        assert!(matches!(Selector::parse("joins[kind=cross"), Err(SelectorError::Malformed(_))));
+        assert!(matches!(Selector::parse("joins[kind]"), Err(SelectorError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_select_functions_anywhere_in_the_query() {
+        let sql = "WITH a AS (SELECT DATEDIFF('day', created_at, updated_at) AS age FROM {{ ref('orders') }}) \
+                   SELECT id FROM a WHERE id IN (SELECT datediff('day', x, y) FROM b) \
+                   AND CAST(total AS INT) > COUNT(id)";
+
+        assert_eq!(
+            select("functions[name=datediff]", sql),
+            vec!["DATEDIFF('day', created_at, updated_at)", "datediff('day', x, y)"]
+        );
+        assert_eq!(select("functions[name!=datediff]", sql), vec!["COUNT(id)"]);
+        assert_eq!(select("casts[type=int]", sql), vec!["CAST(total AS INT)"]);
+    }
+
+    #[test]
+    fn test_select_window_functions_and_cases() {
+        let sql = "SELECT ROW_NUMBER() OVER (PARTITION BY customer_id ORDER BY created_at) AS n, COUNT(id), \
+                   CASE WHEN amount > 100 THEN 'large' WHEN amount > 10 THEN 'medium' ELSE 'small' END AS size \
+                   FROM orders";
+
+        assert_eq!(
+            select("functions[window=true]", sql),
+            vec!["ROW_NUMBER() OVER (PARTITION BY customer_id ORDER BY created_at)"]
+        );
+        assert_eq!(select("functions[window=false]", sql), vec!["COUNT(id)"]);
+        assert_eq!(select("cases[branches=2]", sql).len(), 1);
+    }
+
+    #[test]
+    fn test_select_joins_refs_and_sources() {
+        let sql = "SELECT o.id FROM {{ ref('orders') }} AS o \
+                   JOIN {{ ref('core', 'accounts', v=2) }} AS a ON o.account_id = a.id \
+                   CROSS JOIN {{ source('stripe', 'payments') }} AS p \
+                   LEFT JOIN customers AS c ON o.customer_id = c.id";
+
+        assert_eq!(
+            select("joins[kind=cross]", sql),
+            vec!["CROSS JOIN {{ source('stripe', 'payments') }} AS p"]
+        );
+        assert_eq!(select("joins[table=customers]", sql).len(), 1);
+        assert_eq!(select("refs[name=orders]", sql).len(), 1);
+        assert_eq!(select("refs[package=core][version=2]", sql), vec!["{{ ref('core', 'accounts', v=2) }} AS a"]);
+        assert_eq!(select("refs[package=]", sql), vec!["{{ ref('orders') }} AS o"]);
+        assert_eq!(select("sources[source=stripe][table=payments]", sql).len(), 1);
+        assert_eq!(select("tables", sql), vec!["customers AS c"]);
+        assert_eq!(select("tables[args=]", sql), vec!["customers AS c"]);
+    }
+
+    #[test]
+    fn test_select_subqueries_with_their_depth() {
+        let sql = "WITH a AS (SELECT id FROM (SELECT id FROM orders) AS o) \
+                   SELECT id FROM a WHERE EXISTS (SELECT 1 FROM b WHERE b.id IN (SELECT id FROM c))";
+
+        assert_eq!(
+            select("subqueries[depth=1]", sql),
+            vec!["(SELECT id FROM orders) AS o", "(SELECT 1 FROM b WHERE b.id IN (SELECT id FROM c))"]
+        );
+        assert_eq!(select("subqueries[depth=2]", sql), vec!["(SELECT id FROM c)"]);
+    }
+
+    #[test]
+    fn test_select_columns() {
+        let sql = "SELECT o.id, status, raw.orders.amount FROM raw.orders AS o WHERE status = 'paid'";
+
+        assert_eq!(select("columns[table=]", sql), vec!["status", "status"]);
+        assert_eq!(select("columns[table=o]", sql), vec!["o.id"]);
+        assert_eq!(select("columns[name=amount]", sql), vec!["raw.orders.amount"]);
+    }
+
+    #[test]
+    fn test_select_vars() {
+        let sql = "SELECT id FROM orders \
+                   WHERE created_at >= {{ var('start_date') }} AND status = {{ var('status', 'placed') }}";
+
+        assert_eq!(select("vars[name=start_date][default=]", sql), vec!["{{ var('start_date') }}"]);
+        assert_eq!(select("vars[default!=]", sql), vec!["{{ var('status', 'placed') }}"]);
+    }
+
+    #[test]
+    fn test_select_vars_in_config() {
+        let sql = "{{ config(enabled=var('run_heavy_models'), materialized='table') }} SELECT id FROM orders";
+
+        assert_eq!(select("vars[name=run_heavy_models]", sql), vec!["{{ var('run_heavy_models') }}"]);
+    }
+
+    #[test]
+    fn test_select_macros() {
+        let sql = "SELECT {{ dbt_utils.star(ref('orders')) }}, {{ cents_to_dollars('amount') }} AS amount \
+                   FROM {{ dbt_utils.date_spine('day', '2020-01-01', '2021-01-01') }} AS days";
+
+        assert_eq!(
+            select("macros[namespace=dbt_utils]", sql),
+            vec![
+                "{{ dbt_utils.star(ref('orders')) }}",
+                "{{ dbt_utils.date_spine('day', '2020-01-01', '2021-01-01') }}",
+            ]
+        );
+        assert_eq!(select("macros[name=cents_to_dollars][namespace=]", sql).len(), 1);
+    }
+}
@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::configuration::dbtonic_config::{ParsingConfig, SqlDialect};
+use crate::parser::dbt_project_yaml::DbtProjectYaml;
+use crate::parser::model_node::ModelNode;
+
+// Where `dbt compile` writes a model's rendered SQL, e.g.
+// `target/compiled/jaffle_shop/models/staging/stg_orders.sql`
+pub fn compiled_path(project: &DbtProjectYaml, base_path: &Path, model_path: &Path) -> PathBuf {
+    let relative_path = model_path.strip_prefix(base_path).unwrap_or(model_path);
+    base_path.join(&project.target_path).join("compiled").join(&project.name).join(relative_path)
+}
+
+// The SQL dbt compiled the model to, unless it hasn't been compiled or the
+// model has changed since
+pub fn read_compiled_sql(project: &DbtProjectYaml, base_path: &Path, model_path: &Path) -> Option<String> {
+    let path = compiled_path(project, base_path, model_path);
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    if let (Some(compiled), Some(model)) = (modified(&path), modified(model_path)) {
+        if compiled < model {
+            return None;
+        }
+    }
+    fs::read_to_string(path).ok()
+}
+
+// Swaps each node's Jinja for the SQL dbt compiled it to, parsed, so the
+// rules see what runs in the warehouse. Its yml and what the manifest says
+// about it carry over. Nodes without compiled SQL are left as they are, and
+// the number that were swapped is returned.
+pub fn use_compiled_sql(
+    nodes: &mut [ModelNode],
+    project: &DbtProjectYaml,
+    base_path: &Path,
+    parsing: &ParsingConfig,
+    dialect: SqlDialect,
+) -> usize {
+    let mut swapped = 0;
+    for node in nodes {
+        let Some(compiled_sql) = read_compiled_sql(project, base_path, &node.path) else {
+            continue;
+        };
+        let mut compiled = ModelNode::from_sql(node.path.clone(), compiled_sql.clone(), parsing, dialect);
+        compiled.model_name = node.model_name.clone();
+        compiled.resource_type = node.resource_type;
+        compiled.data.compiled_sql = Some(compiled_sql);
+        compiled.data.yaml = node.data.yaml.take();
+        compiled.data.manifest = node.data.manifest.take();
+        *node = compiled;
+        swapped += 1;
+    }
+    swapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> DbtProjectYaml {
+        DbtProjectYaml { name: "jaffle_shop".to_string(), ..DbtProjectYaml::default() }
+    }
+
+    #[test]
+    fn test_compiled_path() {
+        let path = compiled_path(&project(), Path::new("/project"), Path::new("/project/models/staging/stg_orders.sql"));
+        assert_eq!(path, PathBuf::from("/project/target/compiled/jaffle_shop/models/staging/stg_orders.sql"));
+    }
+
+    #[test]
+    fn test_use_compiled_sql() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_path = dir.path().to_path_buf();
+        let model_path = base_path.join("models").join("orders.sql");
+        let compiled_dir = base_path.join("target").join("compiled").join("jaffle_shop").join("models");
+        fs::create_dir_all(base_path.join("models")).unwrap();
+        fs::create_dir_all(&compiled_dir).unwrap();
+        let jinja = "SELECT id FROM {{ ref('stg_orders') }}";
+        fs::write(&model_path, jinja).unwrap();
+        fs::write(compiled_dir.join("orders.sql"), "SELECT id FROM analytics.stg_orders").unwrap();
+
+        let parsing = ParsingConfig::default();
+        let mut nodes = vec![
+            ModelNode::from_sql(model_path.clone(), jinja.to_string(), &parsing, SqlDialect::Generic),
+            ModelNode::from_sql(base_path.join("models").join("new.sql"), "SELECT 1".to_string(), &parsing, SqlDialect::Generic),
+        ];
+        let swapped = use_compiled_sql(&mut nodes, &project(), &base_path, &parsing, SqlDialect::Generic);
+
+        assert_eq!(swapped, 1);
+        assert_eq!(nodes[0].model_name, "orders");
+        assert_eq!(nodes[0].data.sql, "SELECT id FROM analytics.stg_orders");
+        assert_eq!(nodes[0].data.ast[0].to_string(), "SELECT id FROM analytics.stg_orders");
+        assert_eq!(nodes[1].data.sql, "SELECT 1");
+    }
+}
@@ -1,89 +1,469 @@
 
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use glob::glob;
-use crate::parser::model_node::ModelNode;
-use crate::parser::model_yaml::{ModelYaml, YamlFile};
+use crate::parser::model_node::{ModelNode, ParseTimings, ResourceType};
+use crate::parser::macro_node::MacroNode;
+use crate::parser::model_yaml::ModelYaml;
+use crate::parser::semantic_layer::{SemanticInput, SemanticLayer, SemanticNode};
+use crate::parser::exposure_yaml::ExposureYaml;
+use crate::parser::source_yaml::SourceYaml;
+use crate::parser::property_file::PropertyFile;
+use crate::parser::lineage::{Lineage, LineageNode};
+use crate::parser::dbt_project_yaml::DbtProjectYaml;
+use crate::validation::dbt_project_operations::DbtProject;
+use crate::parser::manifest::Manifest;
+use crate::parser::compiled;
+use crate::parser::render::{self, Renderer};
+use crate::parser::snapshot;
+use crate::parser::seed::SeedNode;
+use crate::configuration::dbtonic_config::{ParsingConfig, ResourcesConfig, SqlDialect};
+use crate::reporting::progress::Progress;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 pub struct DAG {
     pub model_nodes: Vec<ModelNode>,
+    pub semantic_layer: SemanticLayer,
+    pub semantic_nodes: Vec<SemanticNode>,
+    pub exposures: Vec<ExposureYaml>,
+    // The sources declared in the yml, which models select from with `{{ source() }}`
+    pub sources: Vec<SourceYaml>,
+    // Which models and sources each model selects from
+    pub lineage: Lineage,
+    // Parsed like models, but left out of the lineage since nothing can
+    // select from them
+    pub singular_tests: Vec<ModelNode>,
+    pub analyses: Vec<ModelNode>,
+    // Named after their `{% snapshot %}` block, and in the lineage since
+    // models can ref them
+    pub snapshots: Vec<ModelNode>,
+    // The project's own macros, from every file in its macro paths
+    pub macros: Vec<MacroNode>,
+    // The CSVs in the seed paths, which models can ref
+    pub seeds: Vec<SeedNode>,
+    // Where the models, tests, analyses, snapshots and seeds are, along with
+    // the folder configs
+    pub project: DbtProjectYaml,
+    // dbt's target/manifest.json, if it has been written
+    pub manifest: Option<Manifest>,
+}
+
+// How long building the DAG took, for `evaluate --timing`
+pub struct DagTimings {
+    pub models: Vec<ParseTimings>,
+    // Everything apart from tokenizing and parsing the models
+    pub resolution: Duration,
 }
 
 impl DAG {
-    pub fn create(model: Option<&str>) -> Self {
-        let base_path = std::env::current_dir().unwrap();
-        let model_file_paths = Self::get_model_file_paths(model,&base_path);
-        let yaml_file_paths = Self::get_yaml_file_paths(model, &base_path);
+    pub fn create(model: Option<&str>, project: &DbtProject, parsing: &ParsingConfig, dialect: SqlDialect) -> Self {
+        Self::create_timed(model, project, parsing, dialect).0
+    }
 
-        let mut model_nodes: Vec<ModelNode> = model_file_paths
-            .into_iter()
-            .filter_map(|path| ModelNode::from_path(path))
+    // The same as `create`, but also reports how long each model took to
+    // parse and how long the rest of building the DAG (finding files,
+    // reading yaml and linking it to models) took
+    pub fn create_timed(model: Option<&str>, project: &DbtProject, parsing: &ParsingConfig, dialect: SqlDialect) -> (Self, DagTimings) {
+        let start = Instant::now();
+        let base_path = project.root.as_path();
+        let project = project.yaml.clone();
+        let model_file_paths = Self::get_model_file_paths(model, &project, base_path);
+        let yaml_file_paths = Self::get_yaml_file_paths(model, &project, base_path);
+
+        // A single model was asked for, so there's no need for the tests,
+        // analyses and snapshots
+        let mut file_paths: Vec<(PathBuf, ResourceType)> =
+            model_file_paths.into_iter().map(|path| (path, ResourceType::Model)).collect();
+        if model.is_none() {
+            for resource_type in [ResourceType::SingularTest, ResourceType::Analysis, ResourceType::Snapshot] {
+                let paths = Self::get_resource_file_paths(resource_type, &project, base_path);
+                file_paths.extend(paths.into_iter().map(|path| (path, resource_type)));
+            }
+        }
+
+        // Parsing is most of the work in large projects, so the files are
+        // parsed in parallel. rayon keeps them in the order they were found.
+        let parse_start = Instant::now();
+        let progress = Progress::new("Parsing", file_paths.len(), parsing.progress);
+        let renderer = Self::renderer(&project, parsing);
+        let parsed: Vec<(ModelNode, ParseTimings)> = file_paths
+            .into_par_iter()
+            .filter_map(|(path, resource_type)| {
+                let parsed = ModelNode::from_path_timed(path, parsing, dialect);
+                progress.tick();
+                parsed.map(|(mut node, timings)| {
+                    Self::finish_parsing(&mut node, resource_type, renderer.as_ref(), parsing, dialect);
+                    (node, timings)
+                })
+            })
             .collect();
+        progress.finish();
+        let parse_time = parse_start.elapsed();
+
+        let (mut model_nodes, mut singular_tests, mut analyses, mut snapshots) = (vec![], vec![], vec![], vec![]);
+        let mut parse_timings = Vec::with_capacity(parsed.len());
+        for (node, timings) in parsed {
+            parse_timings.push(timings);
+            match node.resource_type {
+                ResourceType::Model => model_nodes.push(node),
+                ResourceType::SingularTest => singular_tests.push(node),
+                ResourceType::Analysis => analyses.push(node),
+                ResourceType::Snapshot => snapshots.push(node),
+            }
+        }
+
+        let (macros, seeds) = match model {
+            Some(_) => (vec![], vec![]),
+            None => (
+                Self::read_macros(&Self::get_macro_file_paths(&project, base_path), dialect),
+                Self::read_seeds(&Self::get_seed_file_paths(&project, base_path)),
+            ),
+        };
+
+        let (model_yamls, exposures, sources, semantic_layer) = Self::read_yaml_files(&yaml_file_paths);
+        Self::combine_model_nodes_and_yamls(&mut model_nodes, &model_yamls);
+        let manifest = Manifest::load(&base_path.join(&project.target_path));
+        if let Some(manifest) = &manifest {
+            for nodes in [&mut model_nodes, &mut singular_tests, &mut analyses, &mut snapshots] {
+                manifest.reconcile(nodes, base_path);
+            }
+        }
+        let semantic_nodes = semantic_layer.to_nodes();
+
+        // The models' own timings add up across threads, so the time spent
+        // parsing is taken off the wall clock instead
+        let timings = DagTimings {
+            resolution: start.elapsed().saturating_sub(parse_time),
+            models: parse_timings,
+        };
 
-        let model_yamls: Vec<ModelYaml> = yaml_file_paths
+        let lineage =
+            Lineage::from_models_and_seeds(model_nodes.iter().chain(&snapshots), &seeds).with_semantic_nodes(&semantic_nodes);
+        let dag = DAG {
+            model_nodes,
+            semantic_layer,
+            semantic_nodes,
+            exposures,
+            sources,
+            lineage,
+            singular_tests,
+            analyses,
+            snapshots,
+            macros,
+            seeds,
+            project,
+            manifest,
+        };
+        (dag, timings)
+    }
+
+    // dbtonic's own Jinja renderer, for models that don't parse as written,
+    // when `parsing.render` is on
+    fn renderer(project: &DbtProjectYaml, parsing: &ParsingConfig) -> Option<Renderer> {
+        parsing.render.then(|| Renderer::new(project))
+    }
+
+    // What's left once a file has been parsed as it's written: snapshots are
+    // parsed again from inside their block, and the models that still don't
+    // parse are rendered when there's a renderer
+    fn finish_parsing(
+        model_node: &mut ModelNode,
+        resource_type: ResourceType,
+        renderer: Option<&Renderer>,
+        parsing: &ParsingConfig,
+        dialect: SqlDialect,
+    ) {
+        model_node.resource_type = resource_type;
+        if resource_type == ResourceType::Snapshot {
+            snapshot::parse_snapshot(model_node, parsing, dialect);
+        }
+        if let Some(renderer) = renderer {
+            render::parse_rendered(model_node, renderer, parsing, dialect);
+        }
+    }
+
+    // Lints what `dbt compile` rendered rather than the Jinja, see
+    // `compiled::use_compiled_sql`. The lineage still comes from the Jinja,
+    // since compiling replaces the refs with table names. Returns how many
+    // nodes had compiled SQL.
+    pub fn use_compiled_sql(&mut self, base_path: &Path, parsing: &ParsingConfig, dialect: SqlDialect) -> usize {
+        [&mut self.model_nodes, &mut self.singular_tests, &mut self.analyses, &mut self.snapshots]
             .into_iter()
-            .filter_map(|path| YamlFile::from_file(path).ok())
-            .flat_map(|models| models.into_iter())
+            .map(|nodes| compiled::use_compiled_sql(nodes, &self.project, base_path, parsing, dialect))
+            .sum()
+    }
+
+    // The models, along with the singular tests, analyses and snapshots the
+    // `[resources]` section turns the rules on for
+    pub fn lintable_nodes(&self, resources: &ResourcesConfig) -> Vec<&ModelNode> {
+        let singular_tests = self.singular_tests.iter().filter(|_| resources.tests);
+        let analyses = self.analyses.iter().filter(|_| resources.analyses);
+        let snapshots = self.snapshots.iter().filter(|_| resources.snapshots);
+        self.model_nodes.iter().chain(singular_tests).chain(analyses).chain(snapshots).collect()
+    }
+
+    // The model at `path` parsed from unsaved text, e.g. an editor's buffer,
+    // and given the yml and manifest the DAG has for it. Files that aren't
+    // models, or tests and analyses the rules don't run on, give None.
+    pub fn model_from_sql(
+        &self,
+        path: PathBuf,
+        sql: &str,
+        resources: &ResourcesConfig,
+        parsing: &ParsingConfig,
+        dialect: SqlDialect,
+        base_path: &Path,
+    ) -> Option<ModelNode> {
+        let existing = self.lintable_nodes(resources).into_iter().find(|model_node| model_node.path == path);
+        // A model created since the DAG was built
+        let is_new_model = path.extension().is_some_and(|extension| extension == "sql")
+            && self.project.resource_type(&path, base_path) == Some(ResourceType::Model);
+        if existing.is_none() && !is_new_model {
+            return None;
+        }
+        let resource_type = existing.map_or(ResourceType::Model, |existing| existing.resource_type);
+        let mut model_node = ModelNode::from_sql(path, sql.to_string(), parsing, dialect);
+        let renderer = Self::renderer(&self.project, parsing);
+        Self::finish_parsing(&mut model_node, resource_type, renderer.as_ref(), parsing, dialect);
+        if let Some(existing) = existing {
+            model_node.data.yaml = existing.data.yaml.clone();
+            model_node.data.manifest = existing.data.manifest.clone();
+        }
+        Some(model_node)
+    }
+
+    fn nodes_mut(&mut self, resource_type: ResourceType) -> &mut Vec<ModelNode> {
+        match resource_type {
+            ResourceType::Model => &mut self.model_nodes,
+            ResourceType::SingularTest => &mut self.singular_tests,
+            ResourceType::Analysis => &mut self.analyses,
+            ResourceType::Snapshot => &mut self.snapshots,
+        }
+    }
+
+    // Re-reads the files in `changed_paths`, e.g. for `dbtonic watch`,
+    // without parsing the rest of the project again. The yml is cheap to
+    // read, so all of it is. Returns the names of the models whose SQL or
+    // yml changed, including ones that were deleted.
+    pub fn refresh(
+        &mut self,
+        base_path: &Path,
+        changed_paths: &[PathBuf],
+        parsing: &ParsingConfig,
+        dialect: SqlDialect,
+    ) -> BTreeSet<String> {
+        let mut changed = BTreeSet::new();
+        let renderer = Self::renderer(&self.project, parsing);
+        for path in changed_paths.iter().filter(|path| self.project.is_seed_path(path, base_path)) {
+            self.seeds.retain(|seed| &seed.path != path);
+            self.seeds.extend(Self::read_seeds(std::slice::from_ref(path)));
+            self.seeds.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        for path in changed_paths.iter().filter(|path| path.extension().is_some_and(|extension| extension == "sql")) {
+            if self.project.is_macro_path(path, base_path) {
+                self.macros.retain(|macro_node| &macro_node.path != path);
+                self.macros.extend(Self::read_macros(std::slice::from_ref(path), dialect));
+                continue;
+            }
+            let Some(resource_type) = self.project.resource_type(path, base_path) else {
+                continue;
+            };
+            let mut model_node = match path.is_file() {
+                true => ModelNode::from_path(path.clone(), parsing, dialect),
+                false => None,
+            };
+            if let Some(model_node) = &mut model_node {
+                Self::finish_parsing(model_node, resource_type, renderer.as_ref(), parsing, dialect);
+                if let Some(manifest) = &self.manifest {
+                    manifest.reconcile(std::slice::from_mut(model_node), base_path);
+                }
+            }
+
+            let nodes = self.nodes_mut(resource_type);
+            if let Some(index) = nodes.iter().position(|model_node| &model_node.path == path) {
+                changed.insert(nodes.remove(index).model_name);
+            }
+            if let Some(model_node) = model_node {
+                changed.insert(model_node.model_name.clone());
+                nodes.push(model_node);
+            }
+            nodes.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+
+        let previous_yamls: HashMap<String, Option<ModelYaml>> = self
+            .model_nodes
+            .iter()
+            .map(|model_node| (model_node.model_name.clone(), model_node.data.yaml.clone()))
             .collect();
+        let (model_yamls, exposures, sources, semantic_layer) =
+            Self::read_yaml_files(&Self::get_yaml_file_paths(None, &self.project, base_path));
+        Self::combine_model_nodes_and_yamls(&mut self.model_nodes, &model_yamls);
+        for model_node in &self.model_nodes {
+            if previous_yamls.get(&model_node.model_name) != Some(&model_node.data.yaml) {
+                changed.insert(model_node.model_name.clone());
+            }
+        }
 
-        Self::combine_model_nodes_and_yamls(&mut model_nodes, &model_yamls);
+        self.semantic_nodes = semantic_layer.to_nodes();
+        self.semantic_layer = semantic_layer;
+        self.exposures = exposures;
+        self.sources = sources;
+        self.lineage = Lineage::from_models_and_seeds(self.model_nodes.iter().chain(&self.snapshots), &self.seeds)
+            .with_semantic_nodes(&self.semantic_nodes);
+        changed
+    }
 
-        DAG { model_nodes }
+    // Whether the yml declares the table for the source, as
+    // `{{ source('source_name', 'table_name') }}` needs
+    pub fn declares_source(&self, source_name: &str, table_name: &str) -> bool {
+        self.sources.iter().any(|source| source.name == source_name && source.has_table(table_name))
     }
 
-    fn get_model_file_paths(model: Option<&str>, base_path: &Path) -> Vec<PathBuf> {
-        let pattern = match model {
-            Some(m) => format!("{}/models/**/{}*.sql", base_path.display(), m),
-            None => format!("{}/models/**/*.sql", base_path.display()),
-        };
-    
+    // Returns the models that ref `model_name` directly
+    pub fn model_dependents(&self, model_name: &str) -> Vec<&ModelNode> {
+        let children = self.lineage.children(model_name);
+        self.model_nodes
+            .iter()
+            .filter(|node| children.contains(&&LineageNode::Model(node.model_name.clone())))
+            .collect()
+    }
+
+    // Returns every semantic model, metric and saved query that is built on
+    // top of `input`, directly or through other semantic nodes, from the
+    // lineage. They're in lineage order: semantic models, then metrics, then
+    // saved queries.
+    pub fn semantic_dependents(&self, input: &SemanticInput) -> Vec<&SemanticNode> {
+        self.lineage
+            .descendants_of(&LineageNode::from(input))
+            .into_iter()
+            .filter_map(|node| match node {
+                LineageNode::Semantic(node_type, name) => self
+                    .semantic_nodes
+                    .iter()
+                    .find(|semantic_node| semantic_node.node_type == *node_type && &semantic_node.name == name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // The files under the project's model paths that match `extension`,
+    // only the ones starting with `model` if it's given
+    fn get_model_path_files(model: Option<&str>, extension: &str, project: &DbtProjectYaml, base_path: &Path) -> Vec<PathBuf> {
         let mut file_paths = vec![];
-    
-        for entry in glob(&pattern).expect("Failed to read glob pattern") {
-            if let Ok(path) = entry {
+
+        for model_path in &project.model_paths {
+            let pattern = match model {
+                Some(m) => format!("{}/{}/**/{}*.{}", base_path.display(), model_path, m, extension),
+                None => format!("{}/{}/**/*.{}", base_path.display(), model_path, extension),
+            };
+            for path in glob(&pattern).expect("Failed to read glob pattern").flatten() {
                 file_paths.push(path);
             }
         }
-    
+
+        file_paths
+    }
+
+    fn get_model_file_paths(model: Option<&str>, project: &DbtProjectYaml, base_path: &Path) -> Vec<PathBuf> {
+        let file_paths = Self::get_model_path_files(model, "sql", project, base_path);
+
+        // On stderr, so `--format json` and `--format sarif` can be piped
         if file_paths.is_empty() {
-            println!("No model files found.");
+            eprintln!("No model files found.");
         } else {
             //TODO Remove this once I add some watch functions
-            println!("{} model file(s) found",file_paths.len())
+            eprintln!("{} model file(s) found",file_paths.len())
         }
-    
-        return file_paths
-    
+
+        file_paths
     }
 
-    fn get_yaml_file_paths(model: Option<&str>, base_path: &Path) -> Vec<PathBuf> {
-        let pattern = match model {
-            Some(m) => format!("{}/models/**/{}*.yml", base_path.display(), m),
-            None => format!("{}/models/**/*.yml", base_path.display()),
-        };
-    
-        let mut file_paths = vec![];
-    
-        for entry in glob(&pattern).expect("Failed to read glob pattern") {
-            if let Ok(path) = entry {
-                file_paths.push(path);
-            }
-        }
-    
+    fn get_resource_file_paths(resource_type: ResourceType, project: &DbtProjectYaml, base_path: &Path) -> Vec<PathBuf> {
+        project
+            .paths(resource_type)
+            .iter()
+            .flat_map(|dir| glob(&format!("{}/{}/**/*.sql", base_path.display(), dir)).expect("Failed to read glob pattern"))
+            .flatten()
+            .filter(|path| project.resource_type(path, base_path) == Some(resource_type))
+            .collect()
+    }
+
+    fn get_macro_file_paths(project: &DbtProjectYaml, base_path: &Path) -> Vec<PathBuf> {
+        project
+            .macro_paths
+            .iter()
+            .flat_map(|dir| glob(&format!("{}/{}/**/*.sql", base_path.display(), dir)).expect("Failed to read glob pattern"))
+            .flatten()
+            .collect()
+    }
+
+    // The macros in the files, in order. A file that doesn't parse is left
+    // out, since it only matters to rules about macros.
+    fn read_macros(macro_file_paths: &[PathBuf], dialect: SqlDialect) -> Vec<MacroNode> {
+        macro_file_paths
+            .iter()
+            .filter(|path| path.is_file())
+            .flat_map(|path| MacroNode::from_path(path.clone(), dialect).unwrap_or_default())
+            .collect()
+    }
+
+    fn get_seed_file_paths(project: &DbtProjectYaml, base_path: &Path) -> Vec<PathBuf> {
+        project
+            .seed_paths
+            .iter()
+            .flat_map(|dir| glob(&format!("{}/{}/**/*.csv", base_path.display(), dir)).expect("Failed to read glob pattern"))
+            .flatten()
+            .collect()
+    }
+
+    // The seeds whose files are still there
+    fn read_seeds(seed_file_paths: &[PathBuf]) -> Vec<SeedNode> {
+        seed_file_paths
+            .iter()
+            .filter(|path| path.is_file())
+            .map(|path| SeedNode::from_path(path.clone()))
+            .collect()
+    }
+
+    // dbt reads property files with either extension
+    fn get_yaml_file_paths(model: Option<&str>, project: &DbtProjectYaml, base_path: &Path) -> Vec<PathBuf> {
+        let mut file_paths = Self::get_model_path_files(model, "yml", project, base_path);
+        file_paths.extend(Self::get_model_path_files(model, "yaml", project, base_path));
+
         if file_paths.is_empty() {
-            println!("No yml files found.");
+            eprintln!("No yml files found.");
         }
-    
-        return file_paths
-    
+
+        file_paths
     }
 
-    fn combine_model_nodes_and_yamls(model_nodes: &mut Vec<ModelNode>, model_yamls: &Vec<ModelYaml>) {
+    // The models, exposures, sources and semantic layer described in the yml
+    // files. What can't be read is reported on stderr and left out, so one
+    // bad block doesn't stop the rules.
+    fn read_yaml_files(yaml_file_paths: &[PathBuf]) -> (Vec<ModelYaml>, Vec<ExposureYaml>, Vec<SourceYaml>, SemanticLayer) {
+        let mut combined = PropertyFile::default();
+        for path in yaml_file_paths {
+            let (property_file, errors) = PropertyFile::from_file(path);
+            for error in errors {
+                eprintln!("Ignoring part of {}: {}", path.display(), error);
+            }
+            combined.models.extend(property_file.models);
+            combined.exposures.extend(property_file.exposures);
+            combined.sources.extend(property_file.sources);
+            combined.semantic_layer.extend(property_file.semantic_layer);
+        }
+
+        (combined.models, combined.exposures, combined.sources, combined.semantic_layer)
+    }
+
+    fn combine_model_nodes_and_yamls(model_nodes: &mut [ModelNode], model_yamls: &[ModelYaml]) {
         for model_node in model_nodes {
-            model_node.data.yaml = match model_yamls.iter().find(|m| m.name == model_node.model_name) {
-                Some(model_yaml) => Some(model_yaml.to_owned()),
-                None => None,
-            };
+            model_node.data.yaml = model_yamls
+                .iter()
+                .find(|m| m.name == model_node.model_name)
+                .map(|model_yaml| model_yaml.to_owned());
         }
     }
 
@@ -93,6 +473,9 @@ impl fmt::Debug for DAG {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("DAG")
             .field("model_nodes", &self.model_nodes)
+            .field("semantic_nodes", &self.semantic_nodes)
+            .field("exposures", &self.exposures)
+            .field("lineage", &self.lineage)
             .finish()
     }
 }
@@ -103,10 +486,35 @@ impl fmt::Display for DAG {
         for (i, model_node) in self.model_nodes.iter().enumerate() {
             writeln!(f, "  {}. {}", i + 1, model_node)?;
         }
+        for semantic_node in &self.semantic_nodes {
+            writeln!(f, "  - {}", semantic_node)?;
+        }
         Ok(())
     }
 }
 
+#[cfg(test)]
+impl DAG {
+    // A DAG of just the models and the lineage between them, for tests.
+    // Tests that need yml, macros, seeds and so on set them on it.
+    pub(crate) fn from_test_models(model_nodes: Vec<ModelNode>) -> DAG {
+        DAG {
+            lineage: Lineage::from_models(&model_nodes),
+            model_nodes,
+            semantic_layer: SemanticLayer::default(),
+            semantic_nodes: vec![],
+            exposures: vec![],
+            sources: vec![],
+            singular_tests: vec![],
+            analyses: vec![],
+            snapshots: vec![],
+            macros: vec![],
+            seeds: vec![],
+            project: DbtProjectYaml::default(),
+            manifest: None,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -122,7 +530,7 @@ mod tests {
         let file_path = models_dir.join("test_model.sql");
         fs::write(&file_path, "").unwrap();
 
-        let model_file_paths = DAG::get_model_file_paths(None, dir.path());
+        let model_file_paths = DAG::get_model_file_paths(None, &DbtProjectYaml::default(), dir.path());
 
         // Check if the test_model.sql file is found
         assert!(model_file_paths.into_iter().any(|path| path == file_path));
@@ -138,15 +546,181 @@ mod tests {
         fs::create_dir(&models_dir).unwrap();
         let file_path = models_dir.join("test_yaml.yml"); // Change this line
         fs::write(&file_path, "").unwrap();
+        let yaml_extension_path = models_dir.join("schema.yaml");
+        fs::write(&yaml_extension_path, "").unwrap();
 
-        let yaml_file_paths = DAG::get_yaml_file_paths(None, dir.path());
+        let yaml_file_paths = DAG::get_yaml_file_paths(None, &DbtProjectYaml::default(), dir.path());
 
         // Check if the test_yaml.yml file is found
-        assert!(yaml_file_paths.into_iter().any(|path| path == file_path));
+        assert!(yaml_file_paths.iter().any(|path| path == &file_path));
+        assert!(yaml_file_paths.iter().any(|path| path == &yaml_extension_path));
 
         dir.close().unwrap();
     }
 
+    #[test]
+    fn test_get_resource_file_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        for sub_dir in ["tests/generic", "analyses"] {
+            fs::create_dir_all(dir.path().join(sub_dir)).unwrap();
+        }
+        let singular_test = dir.path().join("tests").join("assert_positive_totals.sql");
+        let analysis = dir.path().join("analyses").join("revenue_by_month.sql");
+        for path in [&singular_test, &analysis, &dir.path().join("tests").join("generic").join("is_positive.sql")] {
+            fs::write(path, "SELECT 1").unwrap();
+        }
+
+        // Generic tests are macros, not SQL dbt runs as is
+        let project = DbtProjectYaml::default();
+        assert_eq!(DAG::get_resource_file_paths(ResourceType::SingularTest, &project, dir.path()), vec![singular_test]);
+        assert_eq!(DAG::get_resource_file_paths(ResourceType::Analysis, &project, dir.path()), vec![analysis]);
+    }
+
+    #[test]
+    fn test_get_macro_file_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("macros").join("utils")).unwrap();
+        let macro_file = dir.path().join("macros").join("utils").join("cents_to_dollars.sql");
+        fs::write(&macro_file, "{% macro cents_to_dollars(column_name) %}({{ column_name }} / 100){% endmacro %}").unwrap();
+        fs::write(dir.path().join("macros").join("broken.sql"), "{% macro broken( %}").unwrap();
+
+        let macro_file_paths = DAG::get_macro_file_paths(&DbtProjectYaml::default(), dir.path());
+        assert_eq!(macro_file_paths.len(), 2);
+        let macros = DAG::read_macros(&macro_file_paths, SqlDialect::Generic);
+        assert_eq!(macros.iter().map(|node| (node.name.as_str(), &node.path)).collect::<Vec<_>>(), vec![("cents_to_dollars", &macro_file)]);
+    }
+
+    #[test]
+    fn test_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let models_dir = dir.path().join("models");
+        fs::create_dir(&models_dir).unwrap();
+        let orders = models_dir.join("orders.sql");
+        let revenue = models_dir.join("revenue.sql");
+        fs::write(&orders, "SELECT id FROM {{ source('shop', 'orders') }}").unwrap();
+        fs::write(&revenue, "SELECT id FROM {{ ref('orders') }} AS orders").unwrap();
+
+        let mut dag = DAG::from_test_models(vec![]);
+        let parsing = ParsingConfig::default();
+        let changed = dag.refresh(dir.path(), &[orders.clone(), revenue.clone()], &parsing, SqlDialect::Generic);
+        assert_eq!(changed.into_iter().collect::<Vec<String>>(), vec!["orders", "revenue"]);
+        assert_eq!(dag.model_dependents("orders").len(), 1);
+
+        // Only the model whose yml changed needs its rules run again
+        fs::write(models_dir.join("schema.yml"), "version: 2\nmodels:\n  - name: orders\n").unwrap();
+        let changed = dag.refresh(dir.path(), &[models_dir.join("schema.yml")], &parsing, SqlDialect::Generic);
+        assert_eq!(changed.into_iter().collect::<Vec<String>>(), vec!["orders"]);
+        assert!(dag.model_nodes[0].data.yaml.is_some());
+
+        fs::remove_file(&revenue).unwrap();
+        let changed = dag.refresh(dir.path(), &[revenue], &parsing, SqlDialect::Generic);
+        assert_eq!(changed.into_iter().collect::<Vec<String>>(), vec!["revenue"]);
+        assert_eq!(dag.model_nodes.len(), 1);
+        assert!(dag.model_dependents("orders").is_empty());
+
+        // Macros aren't models, so they don't change any
+        let macros_dir = dir.path().join("macros");
+        fs::create_dir(&macros_dir).unwrap();
+        fs::write(macros_dir.join("cents.sql"), "{% macro cents(column_name) %}{{ column_name }} / 100{% endmacro %}").unwrap();
+        let changed = dag.refresh(dir.path(), &[macros_dir.join("cents.sql")], &parsing, SqlDialect::Generic);
+        assert!(changed.is_empty());
+        assert_eq!(dag.macros[0].name, "cents");
+        fs::remove_file(macros_dir.join("cents.sql")).unwrap();
+        dag.refresh(dir.path(), &[macros_dir.join("cents.sql")], &parsing, SqlDialect::Generic);
+        assert!(dag.macros.is_empty());
+
+        // Snapshots are named after their block, which is what refs use
+        let snapshots_dir = dir.path().join("snapshots");
+        fs::create_dir(&snapshots_dir).unwrap();
+        let snapshot = snapshots_dir.join("orders.sql");
+        fs::write(&snapshot, "{% snapshot orders_snapshot %}\nSELECT * FROM {{ ref('orders') }}\n{% endsnapshot %}").unwrap();
+        let changed = dag.refresh(dir.path(), &[snapshot], &parsing, SqlDialect::Generic);
+        assert_eq!(changed.into_iter().collect::<Vec<String>>(), vec!["orders_snapshot"]);
+        assert_eq!(dag.snapshots[0].resource_type, ResourceType::Snapshot);
+        assert_eq!(dag.lineage.children("orders"), vec![&LineageNode::Model("orders_snapshot".to_string())]);
+
+        // Refs to a seed are linked to it once its CSV is there
+        let seeds_dir = dir.path().join("seeds");
+        fs::create_dir(&seeds_dir).unwrap();
+        let countries = seeds_dir.join("countries.csv");
+        fs::write(&countries, "code,name\nGB,United Kingdom\n").unwrap();
+        let revenue = models_dir.join("revenue.sql");
+        fs::write(&revenue, "SELECT id FROM {{ ref('countries') }} AS countries").unwrap();
+        dag.refresh(dir.path(), &[countries, revenue], &parsing, SqlDialect::Generic);
+        assert_eq!(dag.seeds[0].columns, vec!["code", "name"]);
+        assert_eq!(dag.lineage.parents("revenue"), vec![&LineageNode::Seed("countries".to_string())]);
+    }
+
+    #[test]
+    fn test_model_from_sql() {
+        let dir = tempfile::tempdir().unwrap();
+        let models_dir = dir.path().join("models");
+        fs::create_dir(&models_dir).unwrap();
+        let orders = models_dir.join("orders.sql");
+        fs::write(&orders, "SELECT id FROM {{ source('shop', 'orders') }}").unwrap();
+        fs::write(models_dir.join("schema.yml"), "version: 2\nmodels:\n  - name: orders\n").unwrap();
+
+        let mut dag = DAG::from_test_models(vec![]);
+        let parsing = ParsingConfig::default();
+        dag.refresh(dir.path(), &[orders.clone(), models_dir.join("schema.yml")], &parsing, SqlDialect::Generic);
+        let resources = ResourcesConfig::default();
+        let model_from_sql = |path: PathBuf, sql: &str| {
+            dag.model_from_sql(path, sql, &resources, &parsing, SqlDialect::Generic, dir.path())
+        };
+
+        // The unsaved SQL is parsed, and the yml comes from the DAG
+        let model_node = model_from_sql(orders, "SELECT id, amount FROM {{ source('shop', 'orders') }}").unwrap();
+        assert_eq!(model_node.data.sql, "SELECT id, amount FROM {{ source('shop', 'orders') }}");
+        assert!(model_node.data.yaml.is_some());
+
+        assert_eq!(model_from_sql(models_dir.join("new_model.sql"), "SELECT 1").unwrap().model_name, "new_model");
+        assert!(model_from_sql(models_dir.join("README.md"), "SELECT 1").is_none());
+        assert!(model_from_sql(dir.path().join("macros/cents.sql"), "SELECT 1").is_none());
+    }
+
+    #[test]
+    fn test_semantic_dependents() {
+        let semantic_yaml = r#"
+semantic_models:
+  - name: orders
+    model: ref('orders')
+    measures:
+      - name: order_total
+        agg: sum
+  - name: revenue
+    model: ref('revenue')
+metrics:
+  - name: revenue
+    type: simple
+    type_params:
+      measure: order_total
+saved_queries:
+  - name: daily_revenue
+    query_params:
+      metrics: [revenue]
+"#;
+        let semantic_layer: SemanticLayer = serde_yaml::from_str(semantic_yaml).unwrap();
+        let semantic_nodes = semantic_layer.to_nodes();
+        let dag = DAG {
+            lineage: Lineage::default().with_semantic_nodes(&semantic_nodes),
+            semantic_nodes,
+            semantic_layer,
+            ..DAG::from_test_models(vec![])
+        };
+
+        let dependents = |input: SemanticInput| -> Vec<String> {
+            dag.semantic_dependents(&input).into_iter().map(|node| format!("{}: {}", node.node_type, node.name)).collect()
+        };
+
+        assert_eq!(
+            dependents(SemanticInput::Model("orders".to_string())),
+            vec!["semantic_model: orders", "metric: revenue", "saved_query: daily_revenue"]
+        );
+        // The revenue semantic model is built on the revenue model, not the
+        // metric with the same name
+        assert_eq!(dependents(SemanticInput::Model("revenue".to_string())), vec!["semantic_model: revenue"]);
+    }
+
     // TODO: Fix this test
     // #[test]
     // fn test_combine_model_nodes_and_yamls() {
@@ -0,0 +1,241 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use serde_yaml::Value;
+use crate::parser::model_node::ResourceType;
+use crate::parser::model_yaml::YamlParseError;
+
+// The parts of dbt_project.yml dbtonic needs: where to find the project's
+// SQL and the folder configs. `models` is kept as raw yaml because its keys
+// are the project's own folder names, nested as deep as the folders are.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct DbtProjectYaml {
+    #[serde(default)]
+    pub name: String,
+    // dbt called these `source-paths` before 1.0
+    #[serde(rename = "model-paths", alias = "source-paths", default = "default_model_paths")]
+    pub model_paths: Vec<String>,
+    #[serde(rename = "test-paths", default = "default_test_paths")]
+    pub test_paths: Vec<String>,
+    #[serde(rename = "analysis-paths", default = "default_analysis_paths")]
+    pub analysis_paths: Vec<String>,
+    #[serde(rename = "macro-paths", default = "default_macro_paths")]
+    pub macro_paths: Vec<String>,
+    #[serde(rename = "snapshot-paths", default = "default_snapshot_paths")]
+    pub snapshot_paths: Vec<String>,
+    // dbt called these `data-paths` before 1.0
+    #[serde(rename = "seed-paths", alias = "data-paths", default = "default_seed_paths")]
+    pub seed_paths: Vec<String>,
+    // Where dbt writes manifest.json
+    #[serde(rename = "target-path", default = "default_target_path")]
+    pub target_path: String,
+    #[serde(default)]
+    pub models: Value,
+    #[serde(default)]
+    pub vars: Value,
+}
+
+fn default_model_paths() -> Vec<String> {
+    vec!["models".to_string()]
+}
+
+fn default_test_paths() -> Vec<String> {
+    vec!["tests".to_string()]
+}
+
+fn default_analysis_paths() -> Vec<String> {
+    vec!["analyses".to_string()]
+}
+
+fn default_macro_paths() -> Vec<String> {
+    vec!["macros".to_string()]
+}
+
+fn default_snapshot_paths() -> Vec<String> {
+    vec!["snapshots".to_string()]
+}
+
+fn default_seed_paths() -> Vec<String> {
+    vec!["seeds".to_string()]
+}
+
+fn default_target_path() -> String {
+    "target".to_string()
+}
+
+impl Default for DbtProjectYaml {
+    fn default() -> Self {
+        DbtProjectYaml {
+            name: String::new(),
+            model_paths: default_model_paths(),
+            test_paths: default_test_paths(),
+            analysis_paths: default_analysis_paths(),
+            macro_paths: default_macro_paths(),
+            snapshot_paths: default_snapshot_paths(),
+            seed_paths: default_seed_paths(),
+            target_path: default_target_path(),
+            models: Value::Null,
+            vars: Value::Null,
+        }
+    }
+}
+
+impl DbtProjectYaml {
+    pub fn from_file(file_path: PathBuf) -> Result<DbtProjectYaml, YamlParseError> {
+        let content = fs::read_to_string(file_path)?;
+        Ok(serde_yaml::from_str(&content)?)
+    }
+
+    // The directories, relative to the project, that hold the resource type
+    pub fn paths(&self, resource_type: ResourceType) -> &[String] {
+        match resource_type {
+            ResourceType::Model => &self.model_paths,
+            ResourceType::SingularTest => &self.test_paths,
+            ResourceType::Analysis => &self.analysis_paths,
+            ResourceType::Snapshot => &self.snapshot_paths,
+        }
+    }
+
+    // What kind of resource a file is, from the directory it's in. Generic
+    // tests in a test path's `generic` directory are macros rather than SQL
+    // dbt runs as is.
+    pub fn resource_type(&self, path: &Path, base_path: &Path) -> Option<ResourceType> {
+        let in_paths = |paths: &[String]| paths.iter().any(|dir| path.starts_with(base_path.join(dir)));
+        let is_generic_test = self.test_paths.iter().any(|dir| path.starts_with(base_path.join(dir).join("generic")));
+
+        if in_paths(&self.model_paths) {
+            Some(ResourceType::Model)
+        } else if in_paths(&self.test_paths) && !is_generic_test {
+            Some(ResourceType::SingularTest)
+        } else if in_paths(&self.analysis_paths) {
+            Some(ResourceType::Analysis)
+        } else if in_paths(&self.snapshot_paths) {
+            Some(ResourceType::Snapshot)
+        } else {
+            None
+        }
+    }
+
+    // Whether the file is in one of the directories dbt reads macros from
+    pub fn is_macro_path(&self, path: &Path, base_path: &Path) -> bool {
+        self.macro_paths.iter().any(|dir| path.starts_with(base_path.join(dir)))
+    }
+
+    // Whether the file is a CSV in one of the directories dbt loads seeds from
+    pub fn is_seed_path(&self, path: &Path, base_path: &Path) -> bool {
+        path.extension().is_some_and(|extension| extension == "csv")
+            && self.seed_paths.iter().any(|dir| path.starts_with(base_path.join(dir)))
+    }
+
+    // The value of a config like `materialized` that dbt_project.yml sets
+    // for the folder a model is in. Configs are written as `+materialized`
+    // or `materialized` under `models`, then the project name, then one key
+    // per folder, and the deepest folder that sets one wins.
+    pub fn model_config(&self, model_path: &Path, key: &str) -> Option<String> {
+        let folders = self.model_folders(model_path)?;
+        let mut value = config_value(&self.models, key);
+
+        let mut node = match self.models.get(self.name.as_str()) {
+            Some(node) => node,
+            None => return value,
+        };
+        if let Some(project_value) = config_value(node, key) {
+            value = Some(project_value);
+        }
+
+        for folder in folders {
+            node = match node.get(folder.as_str()) {
+                Some(child) => child,
+                None => break,
+            };
+            if let Some(folder_value) = config_value(node, key) {
+                value = Some(folder_value);
+            }
+        }
+        value
+    }
+
+    // The folders between the model path and the model's file, e.g.
+    // `["staging", "stripe"]` for `models/staging/stripe/stg_payments.sql`
+    fn model_folders(&self, model_path: &Path) -> Option<Vec<String>> {
+        let folders: Vec<String> = model_path
+            .parent()?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .collect();
+
+        let model_dirs: Vec<&str> = self
+            .model_paths
+            .iter()
+            .filter_map(|model_path| Path::new(model_path).file_name()?.to_str())
+            .collect();
+        let start = folders.iter().position(|folder| model_dirs.contains(&folder.as_str()))?;
+        Some(folders[start + 1..].to_vec())
+    }
+}
+
+fn config_value(node: &Value, key: &str) -> Option<String> {
+    node.get(format!("+{}", key).as_str())
+        .or_else(|| node.get(key))
+        .and_then(Value::as_str)
+        .map(|value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT_YAML: &str = r#"
+name: jaffle_shop
+version: "1.0.0"
+models:
+  +materialized: view
+  jaffle_shop:
+    marts:
+      +materialized: table
+      finance:
+        materialized: incremental
+    staging:
+      +schema: staging
+"#;
+
+    #[test]
+    fn test_model_config_uses_the_deepest_folder() {
+        let project: DbtProjectYaml = serde_yaml::from_str(PROJECT_YAML).unwrap();
+
+        let materialized = |path: &str| project.model_config(Path::new(path), "materialized");
+        assert_eq!(materialized("/project/models/marts/orders.sql").as_deref(), Some("table"));
+        assert_eq!(materialized("/project/models/marts/finance/revenue.sql").as_deref(), Some("incremental"));
+        assert_eq!(materialized("/project/models/staging/stg_orders.sql").as_deref(), Some("view"));
+        assert_eq!(materialized("/project/analyses/orders.sql"), None);
+    }
+
+    #[test]
+    fn test_model_config_without_models() {
+        let project: DbtProjectYaml = serde_yaml::from_str("name: jaffle_shop\nmodel-paths: [\"src/models\"]\n").unwrap();
+
+        assert_eq!(project.model_paths, vec!["src/models".to_string()]);
+        assert_eq!(project.test_paths, vec!["tests".to_string()]);
+        assert_eq!(project.model_config(Path::new("src/models/orders.sql"), "materialized"), None);
+    }
+
+    #[test]
+    fn test_resource_type() {
+        let project: DbtProjectYaml =
+            serde_yaml::from_str("name: jaffle_shop\nsource-paths: [\"src\"]\nanalysis-paths: [\"analysis\"]\n").unwrap();
+        let base_path = Path::new("/project");
+
+        let resource_type = |path: &str| project.resource_type(Path::new(path), base_path);
+        assert_eq!(resource_type("/project/src/staging/stg_orders.sql"), Some(ResourceType::Model));
+        assert_eq!(resource_type("/project/tests/assert_positive_totals.sql"), Some(ResourceType::SingularTest));
+        assert_eq!(resource_type("/project/tests/generic/is_positive.sql"), None);
+        assert_eq!(resource_type("/project/analysis/revenue_by_month.sql"), Some(ResourceType::Analysis));
+        assert_eq!(resource_type("/project/snapshots/orders_snapshot.sql"), Some(ResourceType::Snapshot));
+        assert_eq!(resource_type("/project/models/orders.sql"), None);
+        assert_eq!(resource_type("/project/macros/cents_to_dollars.sql"), None);
+        assert!(project.is_macro_path(Path::new("/project/macros/cents_to_dollars.sql"), base_path));
+        assert!(!project.is_macro_path(Path::new("/project/src/staging/stg_orders.sql"), base_path));
+        assert!(project.is_seed_path(Path::new("/project/seeds/country_codes.csv"), base_path));
+        assert!(!project.is_seed_path(Path::new("/project/seeds/README.md"), base_path));
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use crate::parser::semantic_layer::{parse_ref_target, RefTarget};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct ExposureYamlFile {
+    #[serde(default)]
+    pub exposures: Vec<ExposureYaml>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct ExposureYaml {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub exposure_type: Option<String>,
+    pub maturity: Option<String>,
+    pub url: Option<String>,
+    pub description: Option<String>,
+    pub owner: Option<ExposureOwner>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct ExposureOwner {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+// The parsed form of an entry in an exposure's `depends_on` list
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExposureDependency {
+    Ref(RefTarget),
+    Source(String, String),
+    Metric(String),
+    Unknown(String),
+}
+
+impl ExposureYaml {
+    pub fn dependencies(&self) -> Vec<ExposureDependency> {
+        self.depends_on
+            .iter()
+            .map(|dependency| ExposureDependency::parse(dependency))
+            .collect()
+    }
+}
+
+impl ExposureDependency {
+    pub fn parse(dependency: &str) -> ExposureDependency {
+        let dependency = dependency.trim();
+
+        if let Some(target) = parse_ref_target(dependency) {
+            return ExposureDependency::Ref(target);
+        }
+
+        if let Some(args) = function_args(dependency, "source") {
+            if let [source_name, table_name] = args.as_slice() {
+                return ExposureDependency::Source(source_name.clone(), table_name.clone());
+            }
+        }
+
+        if let Some(args) = function_args(dependency, "metric") {
+            if let [metric_name] = args.as_slice() {
+                return ExposureDependency::Metric(metric_name.clone());
+            }
+        }
+
+        ExposureDependency::Unknown(dependency.to_string())
+    }
+}
+
+// Splits `name('a', 'b')` into its unquoted arguments
+fn function_args(value: &str, function_name: &str) -> Option<Vec<String>> {
+    let inner = value
+        .strip_prefix(function_name)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+
+    Some(
+        inner
+            .split(',')
+            .map(|arg| arg.trim().trim_matches(|c| c == '\'' || c == '"').to_string())
+            .filter(|arg| !arg.is_empty())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_exposure_dependencies() {
+        let exposure_yaml = r#"
+exposures:
+  - name: weekly_metrics
+    type: dashboard
+    maturity: high
+    owner:
+      name: Callum
+      email: callum@example.com
+    depends_on:
+      - ref('fct_orders')
+      - source('ecom', 'customers')
+      - metric('revenue')
+      - something_else
+"#;
+        let exposure_file: ExposureYamlFile = serde_yaml::from_str(exposure_yaml).unwrap();
+        let exposure = &exposure_file.exposures[0];
+
+        assert_eq!(exposure.maturity.as_deref(), Some("high"));
+        assert_eq!(
+            exposure.dependencies(),
+            vec![
+                ExposureDependency::Ref(RefTarget { package: None, name: "fct_orders".to_string(), version: None }),
+                ExposureDependency::Source("ecom".to_string(), "customers".to_string()),
+                ExposureDependency::Metric("revenue".to_string()),
+                ExposureDependency::Unknown("something_else".to_string()),
+            ]
+        );
+    }
+
+}
@@ -0,0 +1,442 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
+use crate::parser::ast_query::ast_nodes;
+use crate::parser::model_node::ModelNode;
+use crate::parser::seed::SeedNode;
+use crate::parser::semantic_layer::{SemanticInput, SemanticNode, SemanticNodeType};
+
+// Something a model can select from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LineageNode {
+    Model(String),
+    // A CSV the project loads with `dbt seed`, which models ref like a model
+    Seed(String),
+    // The source's name and the table's name
+    Source(String, String),
+    // A semantic model, metric or saved query, which is built on models or
+    // other semantic nodes but can't be selected from in SQL
+    Semantic(SemanticNodeType, String),
+}
+
+impl fmt::Display for LineageNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineageNode::Model(model_name) | LineageNode::Seed(model_name) => write!(f, "{}", model_name),
+            LineageNode::Source(source_name, table_name) => write!(f, "{}.{}", source_name, table_name),
+            LineageNode::Semantic(node_type, name) => write!(f, "{}: {}", node_type, name),
+        }
+    }
+}
+
+// The graph of which models and sources each model selects from, built
+// from the `{{ ref() }}` and `{{ source() }}` calls in the models, and of
+// what the semantic layer is built on. Edges point from the parent to the
+// node built on it. Refs to models that aren't in the project still get a
+// node, so rules can report them.
+#[derive(Debug, Default)]
+pub struct Lineage {
+    graph: DiGraph<LineageNode, ()>,
+    indexes: HashMap<LineageNode, NodeIndex>,
+    // Models that select from `{{ this }}`, like incremental models reading
+    // what they built last time. They're kept out of the graph, since unlike
+    // a model that refs itself they aren't a cycle dbt refuses to build.
+    self_references: BTreeSet<String>,
+}
+
+impl Lineage {
+    pub fn from_models<'a>(model_nodes: impl IntoIterator<Item = &'a ModelNode>) -> Self {
+        Lineage::from_models_and_seeds(model_nodes, &[])
+    }
+
+    // Refs to a seed's name are to the seed, unless a model has the name too
+    pub fn from_models_and_seeds<'a>(model_nodes: impl IntoIterator<Item = &'a ModelNode>, seeds: &[SeedNode]) -> Self {
+        let mut lineage = Lineage::default();
+        let model_nodes: Vec<&ModelNode> = model_nodes.into_iter().collect();
+        let seed_names: HashSet<&str> = seeds
+            .iter()
+            .map(|seed| seed.name.as_str())
+            .filter(|name| !model_nodes.iter().any(|model_node| model_node.model_name == *name))
+            .collect();
+        for seed_name in &seed_names {
+            lineage.add_node(LineageNode::Seed(seed_name.to_string()));
+        }
+        for model_node in model_nodes {
+            let model = lineage.add_node(LineageNode::Model(model_node.model_name.clone()));
+            for parent in parents(model_node, &seed_names) {
+                let parent = lineage.add_node(parent);
+                lineage.graph.update_edge(parent, model, ());
+            }
+            if selects_from_this(model_node) {
+                lineage.self_references.insert(model_node.model_name.clone());
+            }
+        }
+        lineage
+    }
+
+    // Adds the semantic models, metrics and saved queries, with edges from
+    // what each is built on
+    pub fn with_semantic_nodes(mut self, semantic_nodes: &[SemanticNode]) -> Self {
+        for semantic_node in semantic_nodes {
+            let node = self.add_node(LineageNode::Semantic(semantic_node.node_type, semantic_node.name.clone()));
+            for input in &semantic_node.depends_on {
+                let parent = self.add_node(LineageNode::from(input));
+                self.graph.update_edge(parent, node, ());
+            }
+        }
+        self
+    }
+
+    fn add_node(&mut self, node: LineageNode) -> NodeIndex {
+        if let Some(index) = self.indexes.get(&node) {
+            return *index;
+        }
+        let index = self.graph.add_node(node.clone());
+        self.indexes.insert(node, index);
+        index
+    }
+
+    // Every model, seed and source, in the order they were found
+    pub fn nodes(&self) -> impl Iterator<Item = &LineageNode> {
+        self.graph.node_weights()
+    }
+
+    // Every (parent, child) pair
+    pub fn edges(&self) -> Vec<(&LineageNode, &LineageNode)> {
+        self.graph
+            .raw_edges()
+            .iter()
+            .map(|edge| (&self.graph[edge.source()], &self.graph[edge.target()]))
+            .collect()
+    }
+
+    // The models that select from `{{ this }}`, sorted by name
+    pub fn self_references(&self) -> impl Iterator<Item = &str> {
+        self.self_references.iter().map(|model_name| model_name.as_str())
+    }
+
+    pub fn selects_from_itself(&self, model_name: &str) -> bool {
+        self.self_references.contains(model_name)
+    }
+
+    // The models and sources the model selects from directly
+    pub fn parents(&self, model_name: &str) -> Vec<&LineageNode> {
+        self.neighbors(model_name, Direction::Incoming)
+    }
+
+    // The models that select from the model directly
+    pub fn children(&self, model_name: &str) -> Vec<&LineageNode> {
+        self.neighbors(model_name, Direction::Outgoing)
+    }
+
+    // Everything upstream of the model, models first and sorted by name
+    pub fn ancestors(&self, model_name: &str) -> Vec<&LineageNode> {
+        self.reachable(model_name, Direction::Incoming, None)
+    }
+
+    // Everything downstream of the model, sorted by name
+    pub fn descendants(&self, model_name: &str) -> Vec<&LineageNode> {
+        self.reachable(model_name, Direction::Outgoing, None)
+    }
+
+    // Everything downstream of any node, e.g. the metrics built on a
+    // semantic model
+    pub fn descendants_of(&self, node: &LineageNode) -> Vec<&LineageNode> {
+        match self.indexes.get(node) {
+            Some(start) => self.reachable_from(*start, Direction::Outgoing, None),
+            None => vec![],
+        }
+    }
+
+    // The ancestors at most `generations` refs away, e.g. 1 for the parents
+    pub fn ancestors_within(&self, model_name: &str, generations: usize) -> Vec<&LineageNode> {
+        self.reachable(model_name, Direction::Incoming, Some(generations))
+    }
+
+    // The descendants at most `generations` refs away
+    pub fn descendants_within(&self, model_name: &str, generations: usize) -> Vec<&LineageNode> {
+        self.reachable(model_name, Direction::Outgoing, Some(generations))
+    }
+
+    // The groups of models that ref each other in a loop, which dbt refuses
+    // to build. Each cycle is sorted by name, and so is the list.
+    pub fn cycles(&self) -> Vec<Vec<&LineageNode>> {
+        let mut cycles: Vec<Vec<&LineageNode>> = tarjan_scc(&self.graph)
+            .into_iter()
+            .filter(|component| component.len() > 1 || self.graph.contains_edge(component[0], component[0]))
+            .map(|component| {
+                let mut cycle: Vec<&LineageNode> = component.into_iter().map(|index| &self.graph[index]).collect();
+                cycle.sort();
+                cycle
+            })
+            .collect();
+        cycles.sort();
+        cycles
+    }
+
+    // Each cycle as the path its refs take, from its first model by name
+    // back to it, e.g. `[a, b, a]` when a refs b and b refs a. A model that
+    // refs itself is `[a, a]`.
+    pub fn cycle_paths(&self) -> Vec<Vec<&LineageNode>> {
+        self.cycles().into_iter().map(|cycle| self.cycle_path(&cycle)).collect()
+    }
+
+    // Breadth first through the parents in the cycle, so the path is the
+    // shortest one back to the start
+    fn cycle_path<'a>(&'a self, cycle: &[&'a LineageNode]) -> Vec<&'a LineageNode> {
+        let start = self.indexes[cycle[0]];
+        let in_cycle: HashSet<NodeIndex> = cycle.iter().map(|node| self.indexes[*node]).collect();
+        // Where each model was reached from
+        let mut reached_from: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(index) = queue.pop_front() {
+            let mut parents: Vec<NodeIndex> = self
+                .graph
+                .neighbors_directed(index, Direction::Incoming)
+                .filter(|parent| in_cycle.contains(parent))
+                .collect();
+            parents.sort_by_key(|parent| &self.graph[*parent]);
+            for parent in parents {
+                if parent == start {
+                    let mut path = vec![&self.graph[start]];
+                    let mut steps = vec![];
+                    let mut current = index;
+                    while current != start {
+                        steps.push(&self.graph[current]);
+                        current = reached_from[&current];
+                    }
+                    path.extend(steps.into_iter().rev());
+                    path.push(&self.graph[start]);
+                    return path;
+                }
+                if let Entry::Vacant(entry) = reached_from.entry(parent) {
+                    entry.insert(index);
+                    queue.push_back(parent);
+                }
+            }
+        }
+        cycle.to_vec()
+    }
+
+    // The model called `name`, or the seed when there's no model
+    fn index_of(&self, name: &str) -> Option<NodeIndex> {
+        let index = self.indexes.get(&LineageNode::Model(name.to_string()));
+        index.or_else(|| self.indexes.get(&LineageNode::Seed(name.to_string()))).copied()
+    }
+
+    fn neighbors(&self, model_name: &str, direction: Direction) -> Vec<&LineageNode> {
+        let Some(index) = self.index_of(model_name) else {
+            return vec![];
+        };
+        let neighbors: BTreeSet<&LineageNode> = self
+            .graph
+            .neighbors_directed(index, direction)
+            .map(|neighbor| &self.graph[neighbor])
+            .collect();
+        neighbors.into_iter().collect()
+    }
+
+    // Breadth first, one generation at a time, so `max_generations` can stop it
+    fn reachable(&self, model_name: &str, direction: Direction, max_generations: Option<usize>) -> Vec<&LineageNode> {
+        match self.index_of(model_name) {
+            Some(start) => self.reachable_from(start, direction, max_generations),
+            None => vec![],
+        }
+    }
+
+    fn reachable_from(&self, start: NodeIndex, direction: Direction, max_generations: Option<usize>) -> Vec<&LineageNode> {
+        let mut reached: BTreeSet<NodeIndex> = BTreeSet::new();
+        let mut generation = vec![start];
+        let mut generations = 0;
+        while !generation.is_empty() && max_generations.is_none_or(|max| generations < max) {
+            let mut next_generation = vec![];
+            for index in generation {
+                for neighbor in self.graph.neighbors_directed(index, direction) {
+                    if reached.insert(neighbor) {
+                        next_generation.push(neighbor);
+                    }
+                }
+            }
+            generation = next_generation;
+            generations += 1;
+        }
+        // A model in a cycle is its own ancestor, which isn't useful to report
+        reached.remove(&start);
+
+        let nodes: BTreeSet<&LineageNode> = reached.into_iter().map(|index| &self.graph[index]).collect();
+        nodes.into_iter().collect()
+    }
+}
+
+impl From<&SemanticInput> for LineageNode {
+    fn from(input: &SemanticInput) -> Self {
+        match input {
+            SemanticInput::Model(model_name) => LineageNode::Model(model_name.clone()),
+            SemanticInput::Node(node_type, name) => LineageNode::Semantic(*node_type, name.clone()),
+        }
+    }
+}
+
+// What the model selects from. Models without an AST, e.g. because they
+// failed to parse, fall back to reading their refs from the tokens.
+fn parents(model_node: &ModelNode, seed_names: &HashSet<&str>) -> Vec<LineageNode> {
+    let resolve = |name: String| match seed_names.contains(name.as_str()) {
+        true => LineageNode::Seed(name),
+        false => LineageNode::Model(name),
+    };
+    if model_node.parse_failed() || model_node.data.ast.is_empty() {
+        return model_node.refs().into_iter().map(resolve).collect();
+    }
+
+    ast_nodes(&model_node.data.ast)
+        .into_iter()
+        .filter_map(|node| match node.kind {
+            "refs" => Some(resolve(node.attribute("name")?.to_string())),
+            "sources" => Some(LineageNode::Source(
+                node.attribute("source")?.to_string(),
+                node.attribute("table")?.to_string(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+fn selects_from_this(model_node: &ModelNode) -> bool {
+    ast_nodes(&model_node.data.ast).iter().any(|node| node.kind == "this")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn names(nodes: Vec<&LineageNode>) -> Vec<String> {
+        nodes.into_iter().map(|node| node.to_string()).collect()
+    }
+
+    fn lineage() -> Lineage {
+        Lineage::from_models(&[
+            ModelNode::from_test_sql("stg_orders", "SELECT id FROM {{ source('shop', 'orders') }}"),
+            ModelNode::from_test_sql("stg_payments", "SELECT order_id FROM {{ source('stripe', 'payments') }}"),
+            ModelNode::from_test_sql(
+                "orders",
+                "WITH payments AS (SELECT order_id FROM {{ ref('stg_payments') }}) \
+                 SELECT id FROM {{ ref('stg_orders') }} AS o JOIN payments ON o.id = payments.order_id",
+            ),
+            ModelNode::from_test_sql("revenue", "SELECT id FROM {{ ref('orders') }} AS orders"),
+        ])
+    }
+
+    #[test]
+    fn test_parents_and_children() {
+        let lineage = lineage();
+
+        assert_eq!(names(lineage.parents("orders")), vec!["stg_orders", "stg_payments"]);
+        assert_eq!(names(lineage.children("stg_orders")), vec!["orders"]);
+        assert_eq!(lineage.edges().len(), 5);
+        assert!(lineage.parents("not_a_model").is_empty());
+    }
+
+    #[test]
+    fn test_ancestors_and_descendants() {
+        let lineage = lineage();
+
+        assert_eq!(
+            names(lineage.ancestors("revenue")),
+            vec!["orders", "stg_orders", "stg_payments", "shop.orders", "stripe.payments"]
+        );
+        assert_eq!(names(lineage.descendants("stg_payments")), vec!["orders", "revenue"]);
+        assert_eq!(names(lineage.ancestors_within("revenue", 1)), vec!["orders"]);
+        assert_eq!(names(lineage.descendants_within("stg_payments", 1)), vec!["orders"]);
+        assert!(lineage.cycles().is_empty());
+    }
+
+    #[test]
+    fn test_cycle_paths_follow_refs() {
+        let lineage = Lineage::from_models(&[
+            ModelNode::from_test_sql("a", "SELECT id FROM {{ ref('c') }} AS c"),
+            ModelNode::from_test_sql("b", "SELECT id FROM {{ ref('a') }} AS a"),
+            ModelNode::from_test_sql("c", "SELECT id FROM {{ ref('b') }} AS b"),
+        ]);
+
+        let cycle_paths: Vec<Vec<String>> = lineage.cycle_paths().into_iter().map(names).collect();
+        assert_eq!(cycle_paths, vec![vec!["a", "c", "b", "a"]]);
+    }
+
+    #[test]
+    fn test_cycles() {
+        let lineage = Lineage::from_models(&[
+            ModelNode::from_test_sql("a", "SELECT id FROM {{ ref('b') }} AS b"),
+            ModelNode::from_test_sql("b", "SELECT id FROM {{ ref('a') }} AS a"),
+            ModelNode::from_test_sql("c", "SELECT id FROM {{ ref('c') }} AS c"),
+        ]);
+
+        let cycles: Vec<Vec<String>> = lineage.cycles().into_iter().map(names).collect();
+        assert_eq!(cycles, vec![vec!["a".to_string(), "b".to_string()], vec!["c".to_string()]]);
+        let cycle_paths: Vec<Vec<String>> = lineage.cycle_paths().into_iter().map(names).collect();
+        assert_eq!(cycle_paths, vec![vec!["a", "b", "a"], vec!["c", "c"]]);
+        assert_eq!(names(lineage.ancestors("a")), vec!["b"]);
+    }
+
+    #[test]
+    fn test_this_is_a_self_reference_rather_than_a_cycle() {
+        let lineage = Lineage::from_models(&[
+            ModelNode::from_test_sql("stg_events", "SELECT id FROM {{ source('app', 'events') }}"),
+            ModelNode::from_test_sql("events", "SELECT id FROM {{ ref('stg_events') }} WHERE id > (SELECT MAX(id) FROM {{ this }})"),
+        ]);
+
+        assert!(lineage.selects_from_itself("events"));
+        assert!(!lineage.selects_from_itself("stg_events"));
+        assert_eq!(lineage.self_references().collect::<Vec<_>>(), vec!["events"]);
+        assert_eq!(names(lineage.parents("events")), vec!["stg_events"]);
+        assert!(lineage.cycles().is_empty());
+    }
+
+    #[test]
+    fn test_refs_to_seeds() {
+        let seed = |name: &str| SeedNode { name: name.to_string(), path: PathBuf::new(), columns: vec![] };
+        let lineage = Lineage::from_models_and_seeds(
+            &[
+                ModelNode::from_test_sql("orders", "SELECT id FROM {{ ref('country_codes') }} AS c JOIN {{ ref('stg_orders') }} AS o ON c.id = o.id"),
+                ModelNode::from_test_sql("stg_orders", "SELECT id FROM {{ source('shop', 'orders') }}"),
+            ],
+            &[seed("country_codes"), seed("stg_orders"), seed("unused")],
+        );
+
+        assert_eq!(
+            lineage.parents("orders"),
+            vec![&LineageNode::Model("stg_orders".to_string()), &LineageNode::Seed("country_codes".to_string())]
+        );
+        assert_eq!(names(lineage.children("country_codes")), vec!["orders"]);
+        assert!(lineage.nodes().any(|node| node == &LineageNode::Seed("unused".to_string())));
+        assert!(!lineage.nodes().any(|node| node == &LineageNode::Seed("stg_orders".to_string())));
+    }
+
+    #[test]
+    fn test_semantic_nodes() {
+        let semantic_nodes = vec![
+            SemanticNode {
+                name: "orders".to_string(),
+                node_type: SemanticNodeType::SemanticModel,
+                depends_on: vec![SemanticInput::Model("orders".to_string())],
+            },
+            SemanticNode {
+                name: "revenue".to_string(),
+                node_type: SemanticNodeType::Metric,
+                depends_on: vec![SemanticInput::Node(SemanticNodeType::SemanticModel, "orders".to_string())],
+            },
+        ];
+        let lineage = lineage().with_semantic_nodes(&semantic_nodes);
+
+        assert_eq!(names(lineage.children("orders")), vec!["revenue", "semantic_model: orders"]);
+        assert_eq!(names(lineage.descendants("stg_orders")), vec!["orders", "revenue", "semantic_model: orders", "metric: revenue"]);
+        assert_eq!(
+            names(lineage.descendants_of(&LineageNode::Semantic(SemanticNodeType::SemanticModel, "orders".to_string()))),
+            vec!["metric: revenue"]
+        );
+        assert!(lineage.cycles().is_empty());
+    }
+}
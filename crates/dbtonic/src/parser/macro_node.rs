@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::PathBuf;
+use dbtranslate::ast::JinjaMacroArg;
+use dbtranslate::parser::Parser;
+use dbtranslate::tokens::Token;
+use crate::configuration::dbtonic_config::SqlDialect;
+
+// A `{% macro %}` from a file in the project's macro paths. The body is
+// Jinja that renders to SQL rather than SQL, so it's kept as tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroNode {
+    pub name: String,
+    pub path: PathBuf,
+    pub args: Vec<JinjaMacroArg>,
+    pub body: Vec<Token<'static>>,
+}
+
+impl MacroNode {
+    // Every macro in the file. A file can hold any number of them.
+    pub fn from_path(path: PathBuf, dialect: SqlDialect) -> Result<Vec<MacroNode>, String> {
+        let sql = fs::read_to_string(&path).map_err(|e| format!("Error reading the file: {}", e))?;
+        MacroNode::from_sql(path, &sql, dialect)
+    }
+
+    pub fn from_sql(path: PathBuf, sql: &str, dialect: SqlDialect) -> Result<Vec<MacroNode>, String> {
+        let dialect = dialect.parser_dialect();
+        let macros = Parser::new(dialect.as_ref())
+            .try_with_sql(sql)
+            .and_then(|mut parser| parser.parse_jinja_macros())
+            .map_err(|e| e.to_string())?;
+        Ok(macros
+            .into_iter()
+            .map(|jinja_macro| MacroNode {
+                name: jinja_macro.name.value.to_string(),
+                path: path.clone(),
+                args: jinja_macro.args,
+                body: jinja_macro.body,
+            })
+            .collect())
+    }
+
+    pub fn arg_names(&self) -> Vec<&str> {
+        self.args.iter().map(|arg| arg.name.value.as_ref()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_sql() {
+        let sql = "{% macro cents_to_dollars(column_name, precision=2) %}\n\
+                   ({{ column_name }} / 100)::numeric(16, {{ precision }})\n\
+                   {% endmacro %}\n\n\
+                   {% macro limit_in_dev(n=100) -%}\n\
+                   {% if target.name == 'dev' %}LIMIT {{ n }}{% endif %}\n\
+                   {%- endmacro %}\n";
+
+        let macros = MacroNode::from_sql(PathBuf::from("macros/utils.sql"), sql, SqlDialect::Generic).unwrap();
+        assert_eq!(macros.iter().map(|node| node.name.as_str()).collect::<Vec<_>>(), vec!["cents_to_dollars", "limit_in_dev"]);
+        assert_eq!(macros[0].arg_names(), vec!["column_name", "precision"]);
+        assert_eq!(macros[0].args[1].to_string(), "precision=2");
+        assert_eq!(macros[1].path, PathBuf::from("macros/utils.sql"));
+
+        let error = MacroNode::from_sql(PathBuf::from("macros/broken.sql"), "{% macro broken( %}", SqlDialect::Generic);
+        assert!(error.is_err());
+    }
+}
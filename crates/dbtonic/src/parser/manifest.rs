@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::parser::model_node::ModelNode;
+
+// The parts of dbt's `target/manifest.json` dbtonic needs. dbt writes it on
+// every `dbt parse`, `dbt compile` or `dbt run`, with the configs, tags and
+// SQL after Jinja is rendered, and the tests attached to each model, which
+// dbtonic can't work out from the files alone.
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+pub struct Manifest {
+    // Keyed by unique id, e.g. `model.jaffle_shop.orders`
+    #[serde(default)]
+    pub nodes: HashMap<String, ManifestNode>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct ManifestNode {
+    pub unique_id: String,
+    pub name: String,
+    // `model`, `test`, `analysis`, `seed`, `snapshot` and so on
+    pub resource_type: String,
+    // Relative to the project, e.g. `models/marts/orders.sql`
+    #[serde(default)]
+    pub original_file_path: String,
+    #[serde(default)]
+    pub config: ManifestNodeConfig,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // A hash of the file dbt read, to tell whether the model has changed
+    // since
+    pub checksum: Option<Checksum>,
+    // Called `compiled_sql` before dbt 1.3. Only there after a compile.
+    #[serde(alias = "compiled_sql")]
+    pub compiled_code: Option<String>,
+    #[serde(default)]
+    pub depends_on: DependsOn,
+    // Generic tests only: which test it is, the model it tests and the column
+    pub test_metadata: Option<TestMetadata>,
+    pub attached_node: Option<String>,
+    pub column_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+pub struct ManifestNodeConfig {
+    pub materialized: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct Checksum {
+    pub name: String,
+    pub checksum: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+pub struct DependsOn {
+    #[serde(default)]
+    pub nodes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct TestMetadata {
+    pub name: String,
+    // e.g. `dbt_utils`, or none for dbt's own tests
+    pub namespace: Option<String>,
+}
+
+// What the manifest says about one model, attached to it when the DAG is
+// built
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct ManifestModel {
+    pub unique_id: String,
+    // dbt's resolved configs, including the ones set for the model's folder
+    pub materialized: Option<String>,
+    pub tags: Vec<String>,
+    pub tests: Vec<ManifestTest>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct ManifestTest {
+    // e.g. `unique`, `dbt_utils.unique_combination_of_columns`, or the
+    // file name of a singular test
+    pub name: String,
+    pub column_name: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Io(std::io::Error),
+    SerdeJson(serde_json::Error),
+}
+
+impl From<std::io::Error> for ManifestError {
+    fn from(error: std::io::Error) -> Self {
+        ManifestError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ManifestError {
+    fn from(error: serde_json::Error) -> Self {
+        ManifestError::SerdeJson(error)
+    }
+}
+
+impl Manifest {
+    pub fn from_file(file_path: PathBuf) -> Result<Manifest, ManifestError> {
+        let content = fs::read_to_string(file_path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    // The manifest in the project's target path, if dbt has written one.
+    // One that can't be read is reported and then ignored, so an old or
+    // half-written manifest doesn't stop the rules that don't need it.
+    pub fn load(target_path: &Path) -> Option<Manifest> {
+        let manifest_path = target_path.join("manifest.json");
+        if !manifest_path.is_file() {
+            return None;
+        }
+        match Manifest::from_file(manifest_path.clone()) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                eprintln!("Ignoring {}: {:?}", manifest_path.display(), e);
+                None
+            }
+        }
+    }
+
+    // Attaches what the manifest knows to the nodes it describes, matched
+    // on their paths. Nodes added since dbt last ran are left without.
+    // Compiled SQL is only taken when the file hasn't changed since it was
+    // compiled.
+    pub fn reconcile(&self, model_nodes: &mut [ModelNode], base_path: &Path) {
+        let manifest_nodes: HashMap<(&str, &Path), &ManifestNode> = self
+            .nodes
+            .values()
+            .map(|node| ((node.resource_type.as_str(), Path::new(&node.original_file_path)), node))
+            .collect();
+        let tests = self.tests();
+
+        for model_node in model_nodes {
+            let relative_path = model_node.path.strip_prefix(base_path).unwrap_or(&model_node.path);
+            let Some(node) = manifest_nodes.get(&(model_node.resource_type.label(), relative_path)) else {
+                model_node.data.manifest = None;
+                continue;
+            };
+
+            if model_node.data.compiled_sql.is_none() && node.matches_checksum(&model_node.data.sql) {
+                model_node.data.compiled_sql = node.compiled_code.clone();
+            }
+            let mut tags = node.config.tags.clone();
+            for tag in &node.tags {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+            model_node.data.manifest = Some(ManifestModel {
+                unique_id: node.unique_id.clone(),
+                materialized: node.config.materialized.clone(),
+                tags,
+                tests: tests.get(node.unique_id.as_str()).cloned().unwrap_or_default(),
+            });
+        }
+    }
+
+    // The tests on each node, keyed by the node's unique id. Generic tests
+    // say which node they're attached to, and singular tests count for
+    // every node they select from.
+    fn tests(&self) -> HashMap<&str, Vec<ManifestTest>> {
+        let mut test_nodes: Vec<&ManifestNode> = self.nodes.values().filter(|node| node.resource_type == "test").collect();
+        test_nodes.sort_by(|a, b| a.unique_id.cmp(&b.unique_id));
+
+        let mut tests: HashMap<&str, Vec<ManifestTest>> = HashMap::new();
+        for test_node in test_nodes {
+            let name = match &test_node.test_metadata {
+                Some(TestMetadata { name, namespace: Some(namespace) }) => format!("{}.{}", namespace, name),
+                Some(TestMetadata { name, namespace: None }) => name.clone(),
+                None => test_node.name.clone(),
+            };
+            let tested_nodes: Vec<&str> = match &test_node.attached_node {
+                Some(attached_node) => vec![attached_node.as_str()],
+                None => test_node.depends_on.nodes.iter().map(|node| node.as_str()).collect(),
+            };
+            for tested_node in tested_nodes {
+                tests
+                    .entry(tested_node)
+                    .or_default()
+                    .push(ManifestTest { name: name.clone(), column_name: test_node.column_name.clone() });
+            }
+        }
+        tests
+    }
+}
+
+impl ManifestNode {
+    // dbt hashes the file as it read it, before rendering any Jinja
+    fn matches_checksum(&self, sql: &str) -> bool {
+        match &self.checksum {
+            Some(checksum) if checksum.name == "sha256" => {
+                let digest: String = Sha256::digest(sql.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+                digest == checksum.checksum
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORDERS_SQL: &str = "SELECT id FROM {{ ref('stg_orders') }} AS stg_orders";
+
+    fn model_node(path: &str, sql: &str) -> ModelNode {
+        let path = PathBuf::from(path);
+        let mut model_node = ModelNode::from_test_sql(&path.file_stem().unwrap().to_string_lossy(), sql);
+        model_node.path = path;
+        model_node
+    }
+
+    fn manifest() -> Manifest {
+        let checksum: String = Sha256::digest(ORDERS_SQL.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+        let json = format!(
+            r#"{{
+                "metadata": {{ "dbt_version": "1.7.0" }},
+                "nodes": {{
+                    "model.shop.orders": {{
+                        "unique_id": "model.shop.orders",
+                        "name": "orders",
+                        "resource_type": "model",
+                        "original_file_path": "models/marts/orders.sql",
+                        "config": {{ "materialized": "table", "tags": ["nightly"] }},
+                        "tags": ["nightly", "finance"],
+                        "checksum": {{ "name": "sha256", "checksum": "{}" }},
+                        "compiled_code": "SELECT id FROM analytics.stg_orders AS stg_orders",
+                        "depends_on": {{ "nodes": ["model.shop.stg_orders"] }}
+                    }},
+                    "model.shop.stg_orders": {{
+                        "unique_id": "model.shop.stg_orders",
+                        "name": "stg_orders",
+                        "resource_type": "model",
+                        "original_file_path": "models/staging/stg_orders.sql",
+                        "config": {{ "materialized": "view" }},
+                        "checksum": {{ "name": "sha256", "checksum": "out of date" }},
+                        "compiled_code": "SELECT id FROM raw.orders"
+                    }},
+                    "test.shop.unique_orders_id.4f2a": {{
+                        "unique_id": "test.shop.unique_orders_id.4f2a",
+                        "name": "unique_orders_id",
+                        "resource_type": "test",
+                        "test_metadata": {{ "name": "unique", "namespace": null }},
+                        "attached_node": "model.shop.orders",
+                        "column_name": "id",
+                        "depends_on": {{ "nodes": ["model.shop.orders"] }}
+                    }},
+                    "test.shop.assert_orders_have_ids": {{
+                        "unique_id": "test.shop.assert_orders_have_ids",
+                        "name": "assert_orders_have_ids",
+                        "resource_type": "test",
+                        "depends_on": {{ "nodes": ["model.shop.orders"] }}
+                    }}
+                }}
+            }}"#,
+            checksum
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let mut model_nodes = vec![
+            model_node("/project/models/marts/orders.sql", ORDERS_SQL),
+            model_node("/project/models/staging/stg_orders.sql", "SELECT id FROM {{ source('shop', 'orders') }}"),
+            model_node("/project/models/marts/revenue.sql", "SELECT id FROM {{ ref('orders') }} AS orders"),
+        ];
+        manifest().reconcile(&mut model_nodes, Path::new("/project"));
+
+        assert_eq!(
+            model_nodes[0].data.manifest,
+            Some(ManifestModel {
+                unique_id: "model.shop.orders".to_string(),
+                materialized: Some("table".to_string()),
+                tags: vec!["nightly".to_string(), "finance".to_string()],
+                tests: vec![
+                    ManifestTest { name: "assert_orders_have_ids".to_string(), column_name: None },
+                    ManifestTest { name: "unique".to_string(), column_name: Some("id".to_string()) },
+                ],
+            })
+        );
+        assert_eq!(model_nodes[0].data.compiled_sql.as_deref(), Some("SELECT id FROM analytics.stg_orders AS stg_orders"));
+
+        // Changed since dbt compiled it, so the compiled SQL is stale
+        assert!(model_nodes[1].data.manifest.as_ref().is_some_and(|manifest| manifest.tests.is_empty()));
+        assert_eq!(model_nodes[1].data.compiled_sql, None);
+
+        // Added since dbt last ran
+        assert_eq!(model_nodes[2].data.manifest, None);
+    }
+
+    #[test]
+    fn test_load_ignores_missing_and_malformed_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Manifest::load(dir.path()), None);
+
+        fs::write(dir.path().join("manifest.json"), "{ \"nodes\": [").unwrap();
+        assert_eq!(Manifest::load(dir.path()), None);
+    }
+}
@@ -1,5 +1,21 @@
 pub mod model_node;
+pub mod macro_node;
+pub mod snapshot;
+pub mod seed;
 pub mod model_yaml;
+pub mod semantic_layer;
+pub mod exposure_yaml;
+pub mod source_yaml;
+pub mod property_file;
+pub mod dbt_project_yaml;
+pub mod manifest;
+pub mod compiled;
+pub mod render;
 pub mod dag;
+pub mod lineage;
+pub mod model_selection;
+pub mod selects;
+pub mod normalize;
+pub mod ast_query;
 // pub mod visitors;
 pub mod visits;
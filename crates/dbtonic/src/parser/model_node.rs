@@ -2,23 +2,76 @@ use std::fmt;
 use std::fs;
 use std::path::PathBuf;
 use std::borrow::Cow;
-use dbtranslate::ast::Statement;
-use dbtranslate::dialect::GenericDialect;
-use dbtranslate::parser::Parser;
-use dbtranslate::tokenizer::{Tokenizer};
-use dbtranslate::tokens::{Token};
+use std::time::Duration;
+use dbtranslate::ast::{DbtConfigValue, Statement};
+use dbtranslate::parser::{Parser, ParserError};
+use dbtranslate::tokens::{Location, Token, TokenWithLocation, Trivia};
 use crate::parser::model_yaml::ModelYaml;
+use crate::parser::manifest::ManifestModel;
+use crate::configuration::dbtonic_config::{ParsingConfig, SqlDialect};
+use crate::reporting::timing::timed;
 
 
 pub struct ModelNode {
     pub model_name: String,
+    pub resource_type: ResourceType,
+    pub path: PathBuf,
     pub data: ModelData,
 }
 
+// What kind of dbt resource the SQL file is. Singular tests, analyses and
+// snapshots are parsed like models, but only some rules apply to them.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ResourceType {
+    Model,
+    SingularTest,
+    Analysis,
+    Snapshot,
+}
+
+impl ResourceType {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResourceType::Model => "model",
+            ResourceType::SingularTest => "test",
+            ResourceType::Analysis => "analysis",
+            ResourceType::Snapshot => "snapshot",
+        }
+    }
+}
+
+// Something that stopped the parser, and where it happened when we know.
+// The location is kept apart from the message so reports can point at it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub message: String,
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{} at line {}, column {}", self.message, location.line, location.column),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+// How much of a model the parser understood
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParseStatus {
+    Clean,
+    // An AST was produced but the parser also recorded errors
+    Partial,
+    Failed,
+}
+
 impl fmt::Debug for ModelNode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ModelNode")
             .field("model_name", &self.model_name)
+            .field("resource_type", &self.resource_type)
+            .field("path", &self.path)
             .field("data", &self.data)
             .finish()
     }
@@ -32,25 +85,41 @@ impl fmt::Display for ModelNode {
     }
 }
 
+// How long reading a model took, for `evaluate --timing`
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseTimings {
+    pub model_name: String,
+    pub tokenize: Duration,
+    pub parse: Duration,
+}
+
 // This is the model data struct
 pub struct ModelData {
     pub ast: Vec<Statement>,
-    pub tokens: Vec<Token>,
+    // The models the SQL refs, read from its tokens while it was parsed
+    pub refs: Vec<String>,
+    // The whitespace and comments between the tokens, by the token they
+    // come before
+    pub trivia: Trivia<'static>,
     pub sql: String,
     pub compiled_sql: Option<String>,
     pub yaml: Option<ModelYaml>,
-    pub errors: Option<Vec<String>>,
+    pub errors: Option<Vec<ParseError>>,
+    // What dbt's manifest.json says about the model, when there is one
+    pub manifest: Option<ManifestModel>,
 }
 
 impl fmt::Debug for ModelData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ModelData")
             .field("ast", &self.ast)
-            .field("tokens", &self.tokens)
+            .field("refs", &self.refs)
+            .field("trivia", &self.trivia)
             .field("sql", &self.sql)
             .field("compiled_sql", &self.sql)
             .field("yaml", &self.yaml)
             .field("errors", &self.errors)
+            .field("manifest", &self.manifest)
             .finish()
     }
 }
@@ -58,7 +127,7 @@ impl fmt::Debug for ModelData {
 impl fmt::Display for ModelData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "AST: {:?}", self.ast)?;
-        writeln!(f, "Tokens: {:?}", self.tokens)?;
+        writeln!(f, "Refs: {:?}", self.refs)?;
         writeln!(f, "SQL: {}", self.sql)?;
         writeln!(f, "Compiled SQL: {}", self.sql)?;
         writeln!(f, "YAML: {:?}", self.yaml)?;
@@ -68,73 +137,245 @@ impl fmt::Display for ModelData {
 }
 
 impl ModelNode {
-    pub fn create(model_name: String, ast: Vec<Statement>, tokens: Vec<Token>, sql: String, compiled_sql: Option<String>, yaml: Option<ModelYaml>, errors: Option<Vec<String>>) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn create(model_name: String, path: PathBuf, ast: Vec<Statement>, refs: Vec<String>, trivia: Trivia<'static>, sql: String, compiled_sql: Option<String>, yaml: Option<ModelYaml>, errors: Option<Vec<ParseError>>) -> Self {
         ModelNode {
             model_name,
+            resource_type: ResourceType::Model,
+            path,
             data: ModelData {
                 ast,
-                tokens,
+                refs,
+                trivia,
                 sql,
                 compiled_sql,
                 yaml,
                 errors,
+                manifest: None,
             },
         }
     }
     
     // How to use this function:
-    // let model_node = ModelNode::from_path(path)?;
-    pub fn from_path(path: PathBuf) -> Option<ModelNode> {
-    
-        let path_str = path.to_str()?;
+    // let model_node = ModelNode::from_path(path, &config.parsing, config.dialect)?;
+    pub fn from_path(path: PathBuf, parsing: &ParsingConfig, dialect: SqlDialect) -> Option<ModelNode> {
+        ModelNode::from_path_timed(path, parsing, dialect).map(|(model_node, _)| model_node)
+    }
 
-        let file_path = PathBuf::from(path_str);
-        let model_path: Cow<'_, str> = match file_path.file_name() {
-            Some(name) => name.to_string_lossy().into(),
+    // The same as `from_path`, but also reports how long tokenizing and
+    // parsing the model took
+    pub fn from_path_timed(path: PathBuf, parsing: &ParsingConfig, dialect: SqlDialect) -> Option<(ModelNode, ParseTimings)> {
+        path.to_str()?;
+        match fs::read_to_string(&path) {
+            Ok(sql) => Some(ModelNode::from_sql_timed(path, sql, parsing, dialect)),
+            // A file we can't read is reported like one we can't parse,
+            // rather than going missing from the results
+            Err(e) => {
+                let (mut model_node, timings) = ModelNode::from_sql_timed(path, String::new(), parsing, dialect);
+                model_node.data.errors = Some(vec![ParseError { message: format!("Error reading the file: {}", e), location: None }]);
+                Some((model_node, timings))
+            }
+        }
+    }
+
+    // Parses SQL that hasn't been saved to `path` yet, e.g. a model being
+    // edited in an editor
+    pub fn from_sql(path: PathBuf, sql: String, parsing: &ParsingConfig, dialect: SqlDialect) -> ModelNode {
+        ModelNode::from_sql_timed(path, sql, parsing, dialect).0
+    }
+
+    fn from_sql_timed(path: PathBuf, sql: String, parsing: &ParsingConfig, dialect: SqlDialect) -> (ModelNode, ParseTimings) {
+        let model_path: Cow<'_, str> = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
             None => "".into(),
         };
-        let model_name = model_path.trim_end_matches(".sql").to_string();    
+        let model_name = model_path.trim_end_matches(".sql").to_string();
 
-        let sql = match fs::read_to_string(&path) {
-            Ok(s) => s,
-            Err(_) => return None, // Return early if file can't be read
-        };
-    
-        let dialect = GenericDialect {}; // or AnsiDialect, or your own dialect ...
-
-        let tokens: Vec<Token> = {
-            match Tokenizer::new(&dialect, &sql).tokenize() {
-                Ok(t) => t,
-                Err(e) => {
-                    eprintln!("Error tokenizing SQL: {:?}", e);
-                    vec![]
-                }
-            }
-        };
+        let dialect = dialect.parser_dialect();
 
-        let ast_result = Parser::parse_sql(&dialect, &sql);
+        // The tokens borrow from `sql` for as long as the model is being
+        // parsed. The parser is lent them and hands them back once it's done,
+        // so the SQL is only tokenized once and nothing is copied out of it
+        // but the refs and comments the model keeps. Tokenizing stops as
+        // soon as the model goes over the token or statement length limits.
+        let parser = Parser::new(dialect.as_ref())
+            .with_token_limit(parsing.max_tokens)
+            .with_statement_length_limit(parsing.max_statement_length)
+            .with_time_limit(Duration::from_millis(parsing.parse_timeout_ms));
+        let (tokens, tokenize_time) = timed(|| parser.tokenizer(&sql).tokenize_with_location());
 
-        let (ast, errors) = match ast_result {
-            Ok(ast) => (ast, None),
+        // Parser errors come with the location where the parser gave up on
+        // a statement, and it carries on with the next one, so one broken
+        // statement doesn't cost us the rest of the model.
+        let ((ast, errors, tokens), parse_time) = timed(|| match tokens {
+            Ok(tokens) => match parser.try_with_tokens(tokens) {
+                Ok(mut parser) => {
+                    let (ast, errors) = parser.parse_statements_recovering();
+                    let errors: Vec<ParseError> = errors
+                        .into_iter()
+                        .map(|e| ParseError { message: e.error.to_string(), location: Some(e.location) })
+                        .collect();
+                    (ast, errors, parser.into_tokens())
+                }
+                Err(e) => (vec![], vec![ParseError { message: e.to_string(), location: None }], vec![]),
+            },
+            // A model over the limits keeps none of its tokens either
             Err(e) => {
-                eprintln!("Error in parsing model {}: {:?}", model_name, e);
-                (vec![], Some(vec![format!("{:?}", e)]))
+                let location = Location { line: e.line, column: e.col };
+                let message = match e.limit_exceeded {
+                    true => ParserError::from(e).to_string(),
+                    false => format!("sql parser error: {}", e.message),
+                };
+                (vec![], vec![ParseError { message, location: Some(location) }], vec![])
             }
+        });
+        let errors = (!errors.is_empty()).then_some(errors);
+
+        let (tokens, trivia) = Trivia::split(tokens);
+        let refs = refs(&tokens);
+        let trivia = trivia.into_owned();
+
+        let timings = ParseTimings {
+            model_name: model_name.clone(),
+            tokenize: tokenize_time,
+            parse: parse_time,
         };
+        let model_node = ModelNode::create(model_name, path, ast, refs, trivia, sql, None, None, errors);
     
-        let model_node = ModelNode::create(model_name, ast, tokens, sql , None, None, errors);
-    
-        return Some(model_node)
+        (model_node, timings)
     
     }
+
+    // Models that could not be parsed keep their errors so they can be
+    // reported as skipped rather than silently passing the AST rules.
+    pub fn parse_failed(&self) -> bool {
+        self.parse_status() == ParseStatus::Failed
+    }
+
+    // Every error the parser recorded for the model, in one line
+    pub fn parse_errors(&self) -> String {
+        let errors = self.data.errors.as_deref().unwrap_or_default();
+        errors.iter().map(ParseError::to_string).collect::<Vec<_>>().join("; ")
+    }
+
+    // dbt's style guide keeps staging models in a `staging` directory and
+    // prefixes them with `stg_`
+    pub fn is_staging(&self) -> bool {
+        self.model_name.starts_with("stg_") || self.path.components().any(|component| component.as_os_str() == "staging")
+    }
+
+    // A value from the model's `{{ config() }}` block, e.g. a snapshot's
+    // `strategy` or `unique_key`
+    pub fn config_value(&self, key: &str) -> Option<&DbtConfigValue> {
+        self.data.ast.iter().find_map(|statement| match statement {
+            Statement::Query(query) => query.config.as_ref()?.values.get(key),
+        })
+    }
+
+    // The keys set in the model's `{{ config() }}` block, sorted
+    pub fn config_keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self
+            .data
+            .ast
+            .iter()
+            .flat_map(|statement| match statement {
+                Statement::Query(query) => query.config.iter().flat_map(|config| config.values.keys()),
+            })
+            .map(|key| key.as_str())
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    // The tags from the model's `{{ config() }}` block, its yml config and
+    // dbt's manifest.json
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = vec![];
+        match self.config_value("tags") {
+            Some(DbtConfigValue::String(tag)) => tags.push(tag.clone()),
+            Some(DbtConfigValue::List(config_tags)) => {
+                tags.extend(config_tags.iter().filter_map(|tag| tag.as_str()).map(|tag| tag.to_string()))
+            }
+            _ => {}
+        }
+        if let Some(yaml_tags) = self.data.yaml.as_ref().and_then(|yaml| yaml.config.as_ref()?.tags.as_ref()) {
+            tags.extend(yaml_tags.values());
+        }
+        // dbt's resolved tags also have the ones set for the model's folder
+        if let Some(manifest) = &self.data.manifest {
+            for tag in &manifest.tags {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+        tags.dedup();
+        tags
+    }
+
+    // The models this model selects from through `{{ ref('...') }}`
+    pub fn refs(&self) -> Vec<String> {
+        self.data.refs.clone()
+    }
+
+    pub fn parse_status(&self) -> ParseStatus {
+        match (&self.data.errors, self.data.ast.is_empty()) {
+            (None, _) => ParseStatus::Clean,
+            (Some(_), false) => ParseStatus::Partial,
+            (Some(_), true) => ParseStatus::Failed,
+        }
+    }
  
 }
 
+// The models `{{ ref('...') }}`d in the tokens the parser reads. This reads
+// the tokens rather than the AST so refs are found even in models that
+// failed to parse. The model is the last positional argument, since
+// `ref('package', 'model')` names the package first, and a version is
+// passed as `v=` or `version=`.
+fn refs(tokens: &[TokenWithLocation]) -> Vec<String> {
+    let mut refs: Vec<String> = vec![];
+    for (index, window) in tokens.windows(3).enumerate() {
+        let is_ref = matches!(window[0].token, Token::DoubleLBrace)
+            && matches!(&window[1].token, Token::Word(w) if w.value.eq_ignore_ascii_case("ref"))
+            && matches!(window[2].token, Token::LParen);
+        if !is_ref {
+            continue;
+        }
+        let args: Vec<&TokenWithLocation> =
+            tokens[index + 3..].iter().take_while(|token| token.token != Token::RParen).collect();
+        let model_name = args
+            .rsplit(|token| token.token == Token::Comma)
+            .find_map(|arg| match arg {
+                [token] => match &token.token {
+                    Token::SingleQuotedString(s) | Token::DoubleQuotedString(s) => Some(s.to_string()),
+                    Token::Word(w) => Some(w.value.to_string()),
+                    _ => None,
+                },
+                // `v=2`, or anything else that isn't a name
+                _ => None,
+            });
+        if let Some(model_name) = model_name.filter(|model_name| !refs.contains(model_name)) {
+            refs.push(model_name);
+        }
+    }
+
+    refs
+}
+
+#[cfg(test)]
+impl ModelNode {
+    // A model read from `sql` the way `from_path` reads a file, as if it
+    // were saved at `models/<model_name>.sql`, for tests
+    pub(crate) fn from_test_sql(model_name: &str, sql: &str) -> ModelNode {
+        let path = PathBuf::from(format!("models/{}.sql", model_name));
+        ModelNode::from_sql(path, sql.to_string(), &ParsingConfig::default(), SqlDialect::Generic)
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
 
     #[test]
     fn test_from_path() {
@@ -143,12 +384,158 @@ mod tests {
         let file_path = temp_dir.path().join("test_model.sql");
         fs::write(&file_path, "SELECT * FROM ( SELECT 1 FROM {{ ref('test_model') }} )").unwrap();
 
-        let model_node = ModelNode::from_path(PathBuf::from(file_path)).unwrap();
+        let model_node = ModelNode::from_path(file_path, &ParsingConfig::default(), SqlDialect::Generic).unwrap();
 
         assert_eq!(model_node.model_name, "test_model");
         assert_eq!(model_node.data.sql, "SELECT * FROM ( SELECT 1 FROM {{ ref('test_model') }} )");
         assert!(!model_node.data.ast.is_empty());
-        assert!(!model_node.data.tokens.is_empty());
+        assert_eq!(model_node.refs(), vec!["test_model".to_string()]);
+        assert!(!model_node.parse_failed());
+    }
+
+    #[test]
+    fn test_from_path_records_parse_error_location() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("broken_model.sql");
+        fs::write(&file_path, "SELECT 1\nFROM {{ 42 }}").unwrap();
+
+        let model_node = ModelNode::from_path(file_path, &ParsingConfig::default(), SqlDialect::Generic).unwrap();
+
+        assert!(model_node.parse_failed());
+        assert!(model_node.data.ast.is_empty());
+        let errors = model_node.data.errors.unwrap();
+        assert_eq!(errors[0].location, Some(Location { line: 2, column: 6 }));
+    }
+
+    #[test]
+    fn test_from_path_records_tokenizer_error_location() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("unterminated_model.sql");
+        fs::write(&file_path, "SELECT 1\nWHERE a = 'oops").unwrap();
+
+        let model_node = ModelNode::from_path(file_path, &ParsingConfig::default(), SqlDialect::Generic).unwrap();
+
+        assert!(model_node.parse_failed());
+        let errors = model_node.data.errors.unwrap();
+        assert_eq!(errors[0].message, "sql parser error: Unterminated string literal");
+        assert_eq!(errors[0].location, Some(Location { line: 2, column: 11 }));
+    }
+
+    #[test]
+    fn test_from_path_keeps_unreadable_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir_path = temp_dir.path().join("not_a_file.sql");
+        fs::create_dir(&dir_path).unwrap();
+
+        let model_node = ModelNode::from_path(dir_path, &ParsingConfig::default(), SqlDialect::Generic).unwrap();
+
+        assert!(model_node.parse_failed());
+        assert!(model_node.parse_errors().starts_with("Error reading the file"), "{}", model_node.parse_errors());
+    }
+
+    #[test]
+    fn test_from_path_recovers_after_parse_error() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("partly_broken_model.sql");
+        fs::write(&file_path, "SELECT 1\nFROM {{ 42 }};\nSELECT 2 FROM orders").unwrap();
+
+        let model_node = ModelNode::from_path(file_path, &ParsingConfig::default(), SqlDialect::Generic).unwrap();
+
+        assert_eq!(model_node.parse_status(), ParseStatus::Partial);
+        assert_eq!(model_node.data.ast.len(), 1);
+        assert_eq!(model_node.data.ast[0].to_string(), "SELECT 2 FROM orders");
+        let errors = model_node.data.errors.unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location, Some(Location { line: 2, column: 6 }));
+    }
+
+    #[test]
+    fn test_refs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("orders.sql");
+        fs::write(
+            &file_path,
+            "SELECT * FROM {{ ref('stg_orders') }} JOIN {{ ref(\"stg_payments\") }} USING (id)\nJOIN {{ ref('stg_orders') }} USING (id)",
+        ).unwrap();
+
+        let model_node = ModelNode::from_path(file_path, &ParsingConfig::default(), SqlDialect::Generic).unwrap();
+
+        assert_eq!(model_node.refs(), vec!["stg_orders".to_string(), "stg_payments".to_string()]);
+    }
+
+    #[test]
+    fn test_refs_to_packages_and_versions() {
+        let model_node = ModelNode::from_test_sql(
+            "orders",
+            "SELECT * FROM {{ ref('finance', 'stg_payments') }} JOIN {{ ref('stg_orders', v=2) }} USING (id)\n\
+             JOIN {{ ref('shop', 'stg_customers', version=1) }} USING (id)",
+        );
+
+        assert_eq!(model_node.refs(), vec!["stg_payments".to_string(), "stg_orders".to_string(), "stg_customers".to_string()]);
+    }
+
+    #[test]
+    fn test_from_path_uses_dialect() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("events.sql");
+        fs::write(
+            &file_path,
+            "SELECT e.payload:user.id::varchar AS user_id, item.value:sku AS sku \
+             FROM {{ ref('stg_events') }} AS e, LATERAL FLATTEN(input => e.payload:items) AS item",
+        ).unwrap();
+
+        let snowflake = ModelNode::from_path(file_path.clone(), &ParsingConfig::default(), SqlDialect::Snowflake).unwrap();
+        assert!(!snowflake.parse_failed(), "{:?}", snowflake.data.errors);
+        assert_eq!(snowflake.refs(), vec!["stg_events".to_string()]);
+
+        let postgres = ModelNode::from_path(file_path, &ParsingConfig::default(), SqlDialect::Postgres).unwrap();
+        assert!(postgres.parse_failed());
+
+        let file_path = temp_dir.path().join("customers.sql");
+        fs::write(
+            &file_path,
+            "SELECT STRUCT(c.id AS id, SAFE.PARSE_DATE('%Y%m%d', c.signed_up) AS signed_up) AS customer, \
+             c.orders[SAFE_OFFSET(0)].total AS first_order_total \
+             FROM {{ ref('stg_customers') }} AS c",
+        ).unwrap();
+        let bigquery = ModelNode::from_path(file_path, &ParsingConfig::default(), SqlDialect::Bigquery).unwrap();
+        assert!(!bigquery.parse_failed(), "{:?}", bigquery.data.errors);
+
+        let file_path = temp_dir.path().join("order_totals.sql");
+        fs::write(
+            &file_path,
+            "SELECT * EXCLUDE (notes), MAX(COLUMNS('amount_.*')) \
+             FROM {{ ref('orders') }} AS o POSITIONAL JOIN {{ ref('totals') }} AS t",
+        ).unwrap();
+        let duckdb = ModelNode::from_path(file_path, &ParsingConfig::default(), SqlDialect::Duckdb).unwrap();
+        assert!(!duckdb.parse_failed(), "{:?}", duckdb.data.errors);
+
+        let file_path = temp_dir.path().join("order_items.sql");
+        fs::write(
+            &file_path,
+            "SELECT o.id, item, position, TRY(CAST(item AS INTEGER)) AS quantity, filter(o.tags, t -> t <> '') AS tags \
+             FROM {{ ref('orders') }} AS o CROSS JOIN UNNEST(o.items) WITH ORDINALITY AS i (item, position)",
+        ).unwrap();
+        let trino = ModelNode::from_path(file_path, &ParsingConfig::default(), SqlDialect::Trino).unwrap();
+        assert!(!trino.parse_failed(), "{:?}", trino.data.errors);
+    }
+
+    #[test]
+    fn test_from_path_enforces_parsing_limits() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("huge_model.sql");
+        fs::write(&file_path, "SELECT 1, 2, 3, 4, 5 FROM {{ ref('orders') }}").unwrap();
+
+        let parsing = ParsingConfig {
+            max_tokens: 10,
+            ..Default::default()
+        };
+        let model_node = ModelNode::from_path(file_path, &parsing, SqlDialect::Generic).unwrap();
+
+        assert!(model_node.parse_failed());
+        assert!(model_node.refs().is_empty());
+        let errors = model_node.data.errors.unwrap();
+        assert!(errors[0].message.contains("exceeds the limit of 10"), "{}", errors[0]);
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,293 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use glob::Pattern;
+use crate::parser::lineage::{Lineage, LineageNode};
+use crate::parser::model_node::ModelNode;
+
+// How far a `+` reaches up or down the lineage
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Depth {
+    // No `+`
+    None,
+    // `+model` or `model+`
+    Unlimited,
+    // `2+model` or `model+2`
+    Generations(usize),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Method {
+    // `orders` or `stg_*`
+    Name(Pattern),
+    // `tag:nightly`
+    Tag(String),
+    // `path:models/staging`, or a bare value with a `/` in it
+    Path(String),
+}
+
+// One selector from `--select`, such as `+tag:nightly` or `stg_orders+1`
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModelSelector {
+    pub method: Method,
+    pub parents: Depth,
+    pub children: Depth,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SelectionError(pub String);
+
+impl fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Malformed selection: {}", self.0)
+    }
+}
+
+impl ModelSelector {
+    pub fn parse(selector: &str) -> Result<ModelSelector, SelectionError> {
+        let (parents, rest) = match selector.find('+') {
+            Some(plus) if selector[..plus].chars().all(|c| c.is_ascii_digit()) => {
+                (depth(&selector[..plus], selector)?, &selector[plus + 1..])
+            }
+            _ => (Depth::None, selector),
+        };
+        let (value, children) = match rest.rfind('+') {
+            Some(plus) if rest[plus + 1..].chars().all(|c| c.is_ascii_digit()) => {
+                (&rest[..plus], depth(&rest[plus + 1..], selector)?)
+            }
+            _ => (rest, Depth::None),
+        };
+        if value.is_empty() {
+            return Err(SelectionError(format!("'{}' doesn't name any models", selector)));
+        }
+
+        let method = match value.split_once(':') {
+            Some(("tag", tag)) => Method::Tag(tag.to_string()),
+            Some(("path", path)) => Method::Path(path.to_string()),
+            Some((method, _)) => {
+                return Err(SelectionError(format!("unknown method '{}' in '{}', expected 'tag' or 'path'", method, selector)))
+            }
+            None if value.contains('/') => Method::Path(value.to_string()),
+            None => Method::Name(
+                Pattern::new(value).map_err(|e| SelectionError(format!("'{}' isn't a valid pattern: {}", value, e)))?,
+            ),
+        };
+        Ok(ModelSelector { method, parents, children })
+    }
+
+    fn matches(&self, model_node: &ModelNode, base_path: &Path) -> bool {
+        match &self.method {
+            Method::Name(pattern) => pattern.matches(&model_node.model_name),
+            Method::Tag(tag) => model_node.tags().contains(tag),
+            Method::Path(path) => {
+                let model_path = model_node.path.strip_prefix(base_path).unwrap_or(&model_node.path);
+                let path = PathBuf::from(path.trim_end_matches('/'));
+                model_path.starts_with(&path)
+                    || Pattern::new(&path.to_string_lossy()).is_ok_and(|pattern| pattern.matches_path(model_path))
+            }
+        }
+    }
+
+    // The models the selector matches, with their parents and children
+    fn select(&self, model_nodes: &[&ModelNode], lineage: &Lineage, base_path: &Path) -> BTreeSet<String> {
+        let mut selected = BTreeSet::new();
+        for model_node in model_nodes.iter().filter(|model_node| self.matches(model_node, base_path)) {
+            let model_name = model_node.model_name.as_str();
+            selected.insert(model_name.to_string());
+            let parents = match self.parents {
+                Depth::None => vec![],
+                Depth::Unlimited => lineage.ancestors(model_name),
+                Depth::Generations(generations) => lineage.ancestors_within(model_name, generations),
+            };
+            let children = match self.children {
+                Depth::None => vec![],
+                Depth::Unlimited => lineage.descendants(model_name),
+                Depth::Generations(generations) => lineage.descendants_within(model_name, generations),
+            };
+            // Sources aren't models, so there's nothing to run on them
+            for node in parents.into_iter().chain(children) {
+                if let LineageNode::Model(name) = node {
+                    selected.insert(name.clone());
+                }
+            }
+        }
+        selected
+    }
+}
+
+fn depth(digits: &str, selector: &str) -> Result<Depth, SelectionError> {
+    if digits.is_empty() {
+        return Ok(Depth::Unlimited);
+    }
+    digits
+        .parse()
+        .map(Depth::Generations)
+        .map_err(|_| SelectionError(format!("'{}' is too many generations in '{}'", digits, selector)))
+}
+
+// What `--select` and `--exclude` pick out, dbt style. Selectors separated
+// by spaces are unioned and ones joined by commas are intersected, so
+// `tag:nightly,path:models/marts stg_orders+` selects the nightly marts as
+// well as stg_orders and everything built from it. Without `--select` every
+// model is selected. Model files passed on the command line, as pre-commit
+// hooks do, narrow the selection down to those files.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Selection {
+    select: Vec<Vec<ModelSelector>>,
+    exclude: Vec<Vec<ModelSelector>>,
+    files: Vec<PathBuf>,
+}
+
+impl Selection {
+    pub fn parse(select: &[&str], exclude: &[&str]) -> Result<Selection, SelectionError> {
+        Ok(Selection { select: parse_union(select)?, exclude: parse_union(exclude)?, files: vec![] })
+    }
+
+    // Only selects the models in these files, given as absolute paths.
+    // Files that aren't models select nothing.
+    pub fn with_files(mut self, files: Vec<PathBuf>) -> Selection {
+        self.files = files;
+        self
+    }
+
+    // The names of the selected models
+    pub fn models(&self, model_nodes: &[&ModelNode], lineage: &Lineage, base_path: &Path) -> BTreeSet<String> {
+        let mut selected = match self.select.is_empty() {
+            true => model_nodes.iter().map(|model_node| model_node.model_name.clone()).collect(),
+            false => union(&self.select, model_nodes, lineage, base_path),
+        };
+        for model_name in union(&self.exclude, model_nodes, lineage, base_path) {
+            selected.remove(&model_name);
+        }
+        if !self.files.is_empty() {
+            let in_files: BTreeSet<&String> = model_nodes
+                .iter()
+                .filter(|model_node| self.files.contains(&model_node.path))
+                .map(|model_node| &model_node.model_name)
+                .collect();
+            selected.retain(|model_name| in_files.contains(model_name));
+        }
+        selected
+    }
+}
+
+fn parse_union(values: &[&str]) -> Result<Vec<Vec<ModelSelector>>, SelectionError> {
+    values
+        .iter()
+        .flat_map(|value| value.split_whitespace())
+        .map(|intersection| intersection.split(',').map(ModelSelector::parse).collect())
+        .collect()
+}
+
+fn union(
+    intersections: &[Vec<ModelSelector>],
+    model_nodes: &[&ModelNode],
+    lineage: &Lineage,
+    base_path: &Path,
+) -> BTreeSet<String> {
+    let mut selected = BTreeSet::new();
+    for intersection in intersections {
+        let mut sets = intersection.iter().map(|selector| selector.select(model_nodes, lineage, base_path));
+        let Some(first) = sets.next() else {
+            continue;
+        };
+        selected.extend(sets.fold(first, |all, set| all.intersection(&set).cloned().collect()));
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model_node(path: &str, sql: &str) -> ModelNode {
+        let path = PathBuf::from(path);
+        let mut model_node = ModelNode::from_test_sql(&path.file_stem().unwrap().to_string_lossy(), sql);
+        model_node.path = path;
+        model_node
+    }
+
+    fn model_nodes() -> Vec<ModelNode> {
+        vec![
+            model_node("/project/models/staging/stg_orders.sql", "SELECT id FROM {{ source('shop', 'orders') }}"),
+            model_node("/project/models/staging/stg_payments.sql", "SELECT order_id FROM {{ source('stripe', 'payments') }}"),
+            model_node(
+                "/project/models/marts/orders.sql",
+                "{{ config(tags=['nightly', 'finance']) }} \
+                 SELECT id FROM {{ ref('stg_orders') }} AS o JOIN {{ ref('stg_payments') }} AS p ON o.id = p.order_id",
+            ),
+            model_node(
+                "/project/models/marts/revenue.sql",
+                "{{ config(tags='nightly') }} SELECT id FROM {{ ref('orders') }} AS orders",
+            ),
+        ]
+    }
+
+    fn select(select: &[&str], exclude: &[&str]) -> Vec<String> {
+        let model_nodes = model_nodes();
+        let lineage = Lineage::from_models(&model_nodes);
+        Selection::parse(select, exclude)
+            .unwrap()
+            .models(&model_nodes.iter().collect::<Vec<&ModelNode>>(), &lineage, Path::new("/project"))
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ModelSelector::parse("2+tag:nightly+").unwrap(),
+            ModelSelector { method: Method::Tag("nightly".to_string()), parents: Depth::Generations(2), children: Depth::Unlimited }
+        );
+        assert_eq!(ModelSelector::parse("models/staging").unwrap().method, Method::Path("models/staging".to_string()));
+        assert_eq!(
+            ModelSelector::parse("config:materialized"),
+            Err(SelectionError("unknown method 'config' in 'config:materialized', expected 'tag' or 'path'".to_string()))
+        );
+        assert!(ModelSelector::parse("+").is_err());
+    }
+
+    #[test]
+    fn test_select_with_graph_operators() {
+        assert_eq!(select(&["stg_orders+"], &[]), vec!["orders", "revenue", "stg_orders"]);
+        assert_eq!(select(&["stg_orders+1"], &[]), vec!["orders", "stg_orders"]);
+        assert_eq!(select(&["+orders"], &[]), vec!["orders", "stg_orders", "stg_payments"]);
+        assert_eq!(select(&["stg_*"], &[]), vec!["stg_orders", "stg_payments"]);
+    }
+
+    #[test]
+    fn test_select_by_tag_and_path() {
+        assert_eq!(select(&["tag:finance"], &[]), vec!["orders"]);
+        assert_eq!(select(&["tag:nightly"], &[]), vec!["orders", "revenue"]);
+        assert_eq!(select(&["path:models/staging"], &[]), vec!["stg_orders", "stg_payments"]);
+        assert_eq!(select(&["models/marts/*.sql"], &[]), vec!["orders", "revenue"]);
+    }
+
+    #[test]
+    fn test_unions_intersections_and_exclusions() {
+        assert_eq!(select(&["tag:finance stg_payments"], &[]), vec!["orders", "stg_payments"]);
+        assert_eq!(select(&["+revenue,path:models/staging"], &[]), vec!["stg_orders", "stg_payments"]);
+        assert_eq!(select(&["stg_orders+"], &["tag:nightly"]), vec!["stg_orders"]);
+        assert_eq!(select(&[], &["path:models/marts"]), vec!["stg_orders", "stg_payments"]);
+    }
+
+    #[test]
+    fn test_select_files() {
+        let model_nodes = model_nodes();
+        let lineage = Lineage::from_models(&model_nodes);
+        let select = |select: &[&str], files: &[&str]| -> Vec<String> {
+            Selection::parse(select, &[])
+                .unwrap()
+                .with_files(files.iter().map(PathBuf::from).collect())
+                .models(&model_nodes.iter().collect::<Vec<&ModelNode>>(), &lineage, Path::new("/project"))
+                .into_iter()
+                .collect()
+        };
+        assert_eq!(
+            select(&[], &["/project/models/marts/orders.sql", "/project/models/staging/stg_orders.sql"]),
+            vec!["orders", "stg_orders"]
+        );
+        assert_eq!(select(&["path:models/staging"], &["/project/models/marts/orders.sql", "/project/models/staging/stg_orders.sql"]), vec!["stg_orders"]);
+        assert_eq!(select(&[], &["/project/README.md"]), Vec::<String>::new());
+    }
+}
@@ -60,6 +60,8 @@ pub struct ModelYaml {
     pub group: Option<String>,
     pub latest_version: Option<f64>,
     pub meta: Option<serde_json::Value>,
+    // dbt 1.8 renamed `tests` to `data_tests`
+    #[serde(alias = "data_tests")]
     pub tests: Option<Vec<Tests>>,
     pub versions: Option<Vec<Version>>,
 }
@@ -140,6 +142,7 @@ pub struct ColumnProperties {
     pub meta: Option<serde_json::Value>,
     pub policy_tags: Option<Vec<String>>,
     pub quote: Option<BooleanOrJinjaString>,
+    #[serde(alias = "data_tests")]
     pub tests: Option<Vec<Tests>>,
     pub tags: Option<StringOrArrayOfStrings>,
 }
@@ -225,6 +228,15 @@ pub enum StringOrArrayOfStrings {
     Array(Vec<String>),
 }
 
+impl StringOrArrayOfStrings {
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            StringOrArrayOfStrings::Single(value) => vec![value.clone()],
+            StringOrArrayOfStrings::Array(values) => values.clone(),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for StringOrArrayOfStrings {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -260,6 +272,7 @@ pub struct ModelConfigs {
     pub labels: Option<std::collections::HashMap<String, String>>,
     pub materialized: Option<String>,
     pub sql_header: Option<String>,
+    pub tags: Option<StringOrArrayOfStrings>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -323,6 +336,25 @@ pub enum Tests {
     UniqueTest(UniqueTestContents),
 }
 
+impl Tests {
+    // The name of the test, e.g. `unique` for both `- unique` and
+    // `- unique: {config: {severity: warn}}`, or
+    // `dbt_utils.unique_combination_of_columns`
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Tests::String(name) => Some(name),
+            Tests::CustomTest(value) => match value.as_mapping() {
+                Some(mapping) if mapping.len() == 1 => mapping.iter().next()?.0.as_str(),
+                _ => None,
+            },
+            Tests::RelationshipsTest(_) => Some("relationships"),
+            Tests::AcceptedValuesTest(_) => Some("accepted_values"),
+            Tests::NotNullTest(_) => Some("not_null"),
+            Tests::UniqueTest(_) => Some("unique"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct RelationshipsTestContents {
     relationships: RelationshipsProperties,
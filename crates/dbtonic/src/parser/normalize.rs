@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use dbtranslate::ast::{Query, SelectItem, SetExpr, Spanned, Statement, TableFactor, TableWithJoins};
+use dbtranslate::dialect::GenericDialect;
+use dbtranslate::tokenizer::Tokenizer;
+use dbtranslate::tokens::{Span, Token};
+
+// A CTE or subquery along with a normalized rendering of its logic. Two
+// pieces of SQL with the same fingerprint do the same thing, even if they're
+// formatted, cased or named differently.
+#[derive(Debug, PartialEq, Clone)]
+pub struct QueryFingerprint {
+    // Where the query lives in the model, e.g. "CTE totals"
+    pub label: String,
+    pub fingerprint: String,
+    pub span: Span,
+}
+
+// Renders the query in a canonical form: the AST's own formatting with
+// whitespace collapsed and keywords and unquoted names lower cased. Strings
+// keep their case, since `'Paid'` and `'paid'` don't match the same rows.
+// The names the query gives its columns, tables and CTEs are numbered in
+// the order they appear, so `SUM(amount) AS total` and `SUM(amount) AS
+// lifetime_total` render the same. The name a CTE is given isn't part of
+// its body, so it doesn't affect the result either.
+pub fn normalize_query(query: &Query) -> String {
+    let mut aliases = vec![];
+    collect_aliases(query, &mut aliases);
+    let aliases: Vec<String> = aliases.into_iter().map(|alias| alias.to_lowercase()).collect();
+
+    let sql = query.to_string();
+    let Ok(tokens) = Tokenizer::new(&GenericDialect {}, &sql).tokenize() else {
+        return sql.to_lowercase().split_whitespace().collect::<Vec<&str>>().join(" ");
+    };
+    let mut numbered: HashMap<String, usize> = HashMap::new();
+    let mut normalized = String::with_capacity(sql.len());
+    for token in tokens {
+        match token {
+            Token::Whitespace(_) => {
+                if !normalized.is_empty() && !normalized.ends_with(' ') {
+                    normalized.push(' ');
+                }
+            }
+            Token::Word(word) if word.quote_style.is_none() => {
+                let name = word.value.to_lowercase();
+                if aliases.contains(&name) {
+                    let next = numbered.len() + 1;
+                    normalized.push_str(&format!("_{}", numbered.entry(name).or_insert(next)));
+                } else {
+                    normalized.push_str(&name);
+                }
+            }
+            token => normalized.push_str(&token.to_string()),
+        }
+    }
+    normalized.trim_end().to_string()
+}
+
+// The names the query gives to its columns, tables and CTEs
+fn collect_aliases(query: &Query, aliases: &mut Vec<String>) {
+    if let Some(with) = &query.with {
+        for cte in &with.cte_tables {
+            aliases.push(cte.alias.name.value.to_string());
+            collect_aliases(&cte.query, aliases);
+        }
+    }
+    collect_set_expr_aliases(&query.body, aliases);
+}
+
+fn collect_set_expr_aliases(set_expr: &SetExpr, aliases: &mut Vec<String>) {
+    match set_expr {
+        SetExpr::Select(select) => {
+            for item in &select.projection {
+                if let SelectItem::ExprWithAlias { alias, .. } = item {
+                    aliases.push(alias.value.to_string());
+                }
+            }
+            for table_with_joins in &select.from {
+                collect_relation_aliases(table_with_joins, aliases);
+            }
+        }
+        SetExpr::Query(query) => collect_aliases(query, aliases),
+        SetExpr::SetOperation { left, right, .. } => {
+            collect_set_expr_aliases(left, aliases);
+            collect_set_expr_aliases(right, aliases);
+        }
+        _ => {}
+    }
+}
+
+fn collect_relation_aliases(table_with_joins: &TableWithJoins, aliases: &mut Vec<String>) {
+    for relation in std::iter::once(&table_with_joins.relation).chain(table_with_joins.joins.iter().map(|join| &join.relation)) {
+        let alias = match relation {
+            TableFactor::Table { alias, .. }
+            | TableFactor::Function { alias, .. }
+            | TableFactor::DbtRef { alias, .. }
+            | TableFactor::DbtSource { alias, .. }
+            | TableFactor::DbtThis { alias, .. }
+            | TableFactor::DbtMacro { alias, .. }
+            | TableFactor::TableFunction { alias, .. }
+            | TableFactor::UNNEST { alias, .. } => alias,
+            TableFactor::Derived { subquery, alias, .. } => {
+                collect_aliases(subquery, aliases);
+                alias
+            }
+            TableFactor::NestedJoin { table_with_joins, alias } => {
+                collect_relation_aliases(table_with_joins, aliases);
+                alias
+            }
+            TableFactor::Pivot { table_alias, .. } => table_alias,
+        };
+        if let Some(alias) = alias {
+            aliases.push(alias.name.value.to_string());
+        }
+    }
+}
+
+// Fingerprints every CTE and FROM clause subquery in the statements. Trivial
+// queries like `SELECT * FROM {{ ref('orders') }}` are left out since dbt
+// projects are full of them by design.
+pub fn query_fingerprints(statements: &[Statement]) -> Vec<QueryFingerprint> {
+    let mut fingerprints = vec![];
+    for statement in statements {
+        let Statement::Query(query) = statement;
+        collect_query(query, &mut fingerprints);
+    }
+    fingerprints
+}
+
+fn collect_query(query: &Query, fingerprints: &mut Vec<QueryFingerprint>) {
+    if let Some(with) = &query.with {
+        for cte in &with.cte_tables {
+            push_fingerprint(format!("CTE {}", cte.alias.name), &cte.query, cte.span(), fingerprints);
+            collect_query(&cte.query, fingerprints);
+        }
+    }
+    collect_set_expr(&query.body, fingerprints);
+}
+
+fn collect_set_expr(set_expr: &SetExpr, fingerprints: &mut Vec<QueryFingerprint>) {
+    match set_expr {
+        SetExpr::Select(select) => {
+            for table_with_joins in &select.from {
+                collect_table_with_joins(table_with_joins, fingerprints);
+            }
+        }
+        SetExpr::Query(query) => collect_query(query, fingerprints),
+        SetExpr::SetOperation { left, right, .. } => {
+            collect_set_expr(left, fingerprints);
+            collect_set_expr(right, fingerprints);
+        }
+        _ => {}
+    }
+}
+
+fn collect_table_with_joins(table_with_joins: &TableWithJoins, fingerprints: &mut Vec<QueryFingerprint>) {
+    for relation in std::iter::once(&table_with_joins.relation).chain(table_with_joins.joins.iter().map(|join| &join.relation)) {
+        match relation {
+            TableFactor::Derived { subquery, alias, .. } => {
+                let label = match alias {
+                    Some(alias) => format!("subquery {}", alias.name),
+                    None => "subquery".to_string(),
+                };
+                push_fingerprint(label, subquery, relation.span(), fingerprints);
+                collect_query(subquery, fingerprints);
+            }
+            TableFactor::NestedJoin { table_with_joins, .. } => collect_table_with_joins(table_with_joins, fingerprints),
+            _ => {}
+        }
+    }
+}
+
+fn push_fingerprint(label: String, query: &Query, span: Span, fingerprints: &mut Vec<QueryFingerprint>) {
+    if !is_trivial(query) {
+        fingerprints.push(QueryFingerprint { label, fingerprint: normalize_query(query), span });
+    }
+}
+
+// `SELECT * FROM <one relation>` with nothing else going on
+fn is_trivial(query: &Query) -> bool {
+    match &*query.body {
+        SetExpr::Select(select) => {
+            query.with.is_none()
+                && select.projection.iter().all(|item| matches!(item, SelectItem::Wildcard(_)))
+                && select.from.len() <= 1
+                && select.from.iter().all(|from| from.joins.is_empty() && !matches!(from.relation, TableFactor::Derived { .. }))
+                && select.selection.is_none()
+                && select.group_by.is_empty()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::dialect::GenericDialect;
+    use dbtranslate::parser::Parser;
+
+    #[test]
+    fn test_query_fingerprints_ignore_formatting_and_names() {
+        let first = Parser::parse_sql(
+            &GenericDialect {},
+            "WITH source AS (SELECT * FROM {{ ref('orders') }}), \
+             totals AS (SELECT customer_id, SUM(amount) AS total FROM source GROUP BY customer_id) \
+             SELECT * FROM totals",
+        ).unwrap();
+        let second = Parser::parse_sql(
+            &GenericDialect {},
+            "SELECT * FROM (select customer_id,\n    sum(amount) as total\nfrom source group by customer_id) AS customer_totals",
+        ).unwrap();
+
+        let first = query_fingerprints(&first);
+        let second = query_fingerprints(&second);
+
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].label, "CTE totals");
+        assert_eq!(second[0].label, "subquery customer_totals");
+        assert_eq!(first[0].fingerprint, second[0].fingerprint);
+    }
+
+    #[test]
+    fn test_normalize_query_numbers_aliases_and_keeps_strings() {
+        let fingerprint = |sql: &str| {
+            let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+            query_fingerprints(&statements).remove(0).fingerprint
+        };
+
+        let first = fingerprint(
+            "WITH totals AS (SELECT o.customer_id, SUM(o.amount) AS total FROM orders AS o \
+             WHERE o.status = 'Paid' GROUP BY o.customer_id) SELECT * FROM totals",
+        );
+        let renamed = fingerprint(
+            "WITH totals AS (SELECT ord.customer_id, SUM(ord.amount) AS lifetime_value FROM orders AS ord \
+             WHERE ord.status = 'Paid' GROUP BY ord.customer_id) SELECT * FROM totals",
+        );
+        let lower_cased_string = fingerprint(
+            "WITH totals AS (SELECT o.customer_id, SUM(o.amount) AS total FROM orders AS o \
+             WHERE o.status = 'paid' GROUP BY o.customer_id) SELECT * FROM totals",
+        );
+
+        assert_eq!(first, renamed);
+        assert_ne!(first, lower_cased_string);
+        assert!(first.contains("'Paid'"));
+    }
+}
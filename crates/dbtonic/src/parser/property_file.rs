@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::Path;
+use serde::de::DeserializeOwned;
+use serde_yaml::Value;
+use crate::parser::exposure_yaml::ExposureYaml;
+use crate::parser::model_yaml::ModelYaml;
+use crate::parser::semantic_layer::SemanticLayer;
+use crate::parser::source_yaml::SourceYaml;
+
+// The blocks of a property file dbtonic reads: its models, exposures,
+// sources and semantic layer
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PropertyFile {
+    pub models: Vec<ModelYaml>,
+    pub exposures: Vec<ExposureYaml>,
+    pub sources: Vec<SourceYaml>,
+    pub semantic_layer: SemanticLayer,
+}
+
+impl PropertyFile {
+    // The file is parsed once and each block read from it on its own, so a
+    // mistake in one block only loses that block. What couldn't be read is
+    // returned alongside what could.
+    pub fn from_file(file_path: &Path) -> (PropertyFile, Vec<String>) {
+        match fs::read_to_string(file_path) {
+            Ok(content) => PropertyFile::parse(&content),
+            Err(e) => (PropertyFile::default(), vec![e.to_string()]),
+        }
+    }
+
+    pub fn parse(content: &str) -> (PropertyFile, Vec<String>) {
+        let value: Value = match serde_yaml::from_str(content) {
+            Ok(value) => value,
+            Err(e) => return (PropertyFile::default(), vec![e.to_string()]),
+        };
+
+        let mut errors = vec![];
+        let property_file = PropertyFile {
+            models: block(&value, "models", &mut errors),
+            exposures: block(&value, "exposures", &mut errors),
+            sources: block(&value, "sources", &mut errors),
+            semantic_layer: SemanticLayer {
+                semantic_models: block(&value, "semantic_models", &mut errors),
+                metrics: block(&value, "metrics", &mut errors),
+                saved_queries: block(&value, "saved_queries", &mut errors),
+            },
+        };
+        (property_file, errors)
+    }
+}
+
+// The block under `key`, which files without it don't need
+fn block<T: DeserializeOwned + Default>(value: &Value, key: &str, errors: &mut Vec<String>) -> T {
+    match value.get(key) {
+        None | Some(Value::Null) => T::default(),
+        Some(block) => serde_yaml::from_value(block.clone()).unwrap_or_else(|e| {
+            errors.push(format!("`{}`: {}", key, e));
+            T::default()
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_block_is_read_on_its_own() {
+        let (property_file, errors) = PropertyFile::parse(
+            "version: 2\n\
+             models:\n  \
+               - name: orders\n\
+             sources:\n  \
+               - name: shop\n    \
+                 tables: orders\n\
+             metrics:\n  \
+               - name: revenue\n",
+        );
+
+        assert_eq!(property_file.models.len(), 1);
+        assert!(property_file.sources.is_empty());
+        assert_eq!(property_file.semantic_layer.metrics[0].name, "revenue");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("`sources`: "), "{}", errors[0]);
+    }
+
+    #[test]
+    fn test_a_file_that_isnt_yaml() {
+        let (property_file, errors) = PropertyFile::parse("models: [\n");
+
+        assert_eq!(property_file, PropertyFile::default());
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,140 @@
+use minijinja::value::{Kwargs, Rest};
+use minijinja::{context, Environment, Error, ErrorKind, Value};
+use crate::configuration::dbtonic_config::{ParsingConfig, SqlDialect};
+use crate::parser::dbt_project_yaml::DbtProjectYaml;
+use crate::parser::model_node::ModelNode;
+
+// Renders a model's Jinja without dbt, into SQL close enough to what
+// `dbt compile` writes to parse. It knows the parts of dbt most models use:
+// `var`, `env_var`, `config`, `is_incremental`, `this` and `target`, along
+// with Jinja's own `if`, `for` and `set`. Refs and sources are left as Jinja,
+// which the parser reads, so the lineage and the rules about them still see
+// them. Anything else, like the project's macros, fails to render.
+pub struct Renderer {
+    env: Environment<'static>,
+}
+
+impl Renderer {
+    pub fn new(project: &DbtProjectYaml) -> Self {
+        let mut env = Environment::new();
+        env.set_keep_trailing_newline(true);
+
+        // `ref('package', 'model')` and `ref('model', version=2)` are the model
+        env.add_function("ref", |args: Rest<String>, _kwargs: Kwargs| -> Result<Value, Error> {
+            match args.last() {
+                Some(model_name) => Ok(Value::from_safe_string(format!("{{{{ ref('{}') }}}}", model_name))),
+                None => Err(Error::new(ErrorKind::MissingArgument, "ref() needs the name of a model")),
+            }
+        });
+        env.add_function("source", |source_name: String, table_name: String| {
+            Value::from_safe_string(format!("{{{{ source('{}', '{}') }}}}", source_name, table_name))
+        });
+
+        // Only the project's top level vars, not the ones scoped to a package
+        let vars = Value::from_serialize(&project.vars);
+        env.add_function("var", move |name: String, default: Option<Value>| -> Result<Value, Error> {
+            match (vars.get_attr(&name), default) {
+                (Ok(value), _) if !value.is_undefined() && !value.is_none() => Ok(value),
+                (_, Some(default)) => Ok(default),
+                _ => Err(Error::new(ErrorKind::UndefinedError, format!("Required var '{}' not found", name))),
+            }
+        });
+        env.add_function("env_var", |name: String, default: Option<String>| -> Result<String, Error> {
+            match (std::env::var(&name), default) {
+                (Ok(value), _) => Ok(value),
+                (Err(_), Some(default)) => Ok(default),
+                (Err(_), None) => Err(Error::new(ErrorKind::UndefinedError, format!("Env var required but not provided: '{}'", name))),
+            }
+        });
+        // Configs don't change the SQL
+        env.add_function("config", |_args: Rest<Value>, _kwargs: Kwargs| String::new());
+        // A full refresh, which is what a model that hasn't been built runs
+        env.add_function("is_incremental", || false);
+        env.add_global("target", context! { name => "dev", schema => "dbt", database => "", type => "" });
+
+        Renderer { env }
+    }
+
+    pub fn render(&self, model_name: &str, sql: &str) -> Result<String, String> {
+        self.env
+            .render_str(sql, context! { this => Value::from_safe_string(model_name.to_string()) })
+            .map_err(|e| e.to_string())
+    }
+}
+
+// For a model that didn't parse as written, parses its rendered Jinja
+// instead. Only the AST comes from the rendered SQL: the node keeps its own
+// SQL, tokens and comments, so the rules on the SQL's text, suppressions and
+// fixes still work on the file. Returns whether the rendered SQL parsed.
+pub fn parse_rendered(model_node: &mut ModelNode, renderer: &Renderer, parsing: &ParsingConfig, dialect: SqlDialect) -> bool {
+    if model_node.data.errors.is_none() {
+        return false;
+    }
+    let Ok(rendered) = renderer.render(&model_node.model_name, &model_node.data.sql) else {
+        return false;
+    };
+    let parsed = ModelNode::from_sql(model_node.path.clone(), rendered, parsing, dialect);
+    if parsed.data.errors.is_some() {
+        return false;
+    }
+    model_node.data.ast = parsed.data.ast;
+    model_node.data.errors = None;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn renderer() -> Renderer {
+        let project: DbtProjectYaml = serde_yaml::from_str("name: shop\nvars:\n  start_date: '2020-01-01'\n").unwrap();
+        Renderer::new(&project)
+    }
+
+    #[test]
+    fn test_render_dbt_functions() {
+        let sql = "{{ config(materialized='table') }}\n\
+                   SELECT * FROM {{ ref('orders') }} JOIN {{ ref('core', 'customers') }} USING (id)\n\
+                   JOIN {{ source('stripe', 'payments') }} USING (id)\n\
+                   WHERE created_at > '{{ var(\"start_date\") }}' AND region = '{{ var(\"region\", \"eu\") }}'\n\
+                   {% if is_incremental() %}AND id > (SELECT MAX(id) FROM {{ this }}){% endif %}\n";
+
+        assert_eq!(
+            renderer().render("orders_enriched", sql).unwrap(),
+            "\nSELECT * FROM {{ ref('orders') }} JOIN {{ ref('customers') }} USING (id)\n\
+             JOIN {{ source('stripe', 'payments') }} USING (id)\n\
+             WHERE created_at > '2020-01-01' AND region = 'eu'\n\n"
+        );
+    }
+
+    #[test]
+    fn test_render_loops() {
+        let sql = "SELECT\n{% for method in ['card', 'cash'] %}  SUM(CASE WHEN method = '{{ method }}' THEN amount END) AS {{ method }}_amount{% if not loop.last %},{% endif %}\n{% endfor %}FROM payments";
+
+        assert_eq!(
+            renderer().render("payments", sql).unwrap(),
+            "SELECT\n  SUM(CASE WHEN method = 'card' THEN amount END) AS card_amount,\n  \
+             SUM(CASE WHEN method = 'cash' THEN amount END) AS cash_amount\nFROM payments"
+        );
+    }
+
+    #[test]
+    fn test_render_errors() {
+        assert!(renderer().render("m", "SELECT {{ var('missing') }}").unwrap_err().contains("Required var 'missing' not found"));
+        assert!(renderer().render("m", "SELECT {{ my_macro() }}").is_err());
+    }
+
+    #[test]
+    fn test_parse_rendered_falls_back_for_models_that_do_not_parse() {
+        let parsing = ParsingConfig::default();
+        let sql = "{% set columns = ['a', 'b'] %}SELECT {{ columns | join(', ') }} FROM {{ ref('orders') }}";
+        let mut model_node = ModelNode::from_sql(PathBuf::from("models/m.sql"), sql.to_string(), &parsing, SqlDialect::Generic);
+        assert!(model_node.data.errors.is_some());
+
+        assert!(parse_rendered(&mut model_node, &renderer(), &parsing, SqlDialect::Generic));
+        assert_eq!(model_node.data.errors, None);
+        assert_eq!(model_node.data.ast[0].to_string(), "SELECT a, b FROM {{ ref('orders') }}");
+        assert_eq!(model_node.data.sql, sql);
+    }
+}
@@ -0,0 +1,82 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+// A CSV file from the project's seed paths, which dbt loads as a table
+// named after the file. Its columns are the CSV's header row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedNode {
+    pub name: String,
+    pub path: PathBuf,
+    pub columns: Vec<String>,
+}
+
+impl SeedNode {
+    // A seed that can't be read still exists for the refs to it, so it's
+    // kept without its columns
+    pub fn from_path(path: PathBuf) -> SeedNode {
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let columns = read_header(&path).map(|header| header_columns(&header)).unwrap_or_default();
+        SeedNode { name, path, columns }
+    }
+}
+
+fn read_header(path: &PathBuf) -> Option<String> {
+    let mut header = String::new();
+    BufReader::new(File::open(path).ok()?).read_line(&mut header).ok()?;
+    Some(header)
+}
+
+// The column names in a CSV header. Names can be quoted, with `""` for a
+// quote inside one, to hold commas.
+fn header_columns(header: &str) -> Vec<String> {
+    let header = header.trim_start_matches('\u{feff}').trim_end_matches(['\r', '\n']);
+    if header.trim().is_empty() {
+        return vec![];
+    }
+
+    let mut columns = vec![];
+    let mut column = String::new();
+    let mut in_quotes = false;
+    let mut chars = header.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                column.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => columns.push(std::mem::take(&mut column).trim().to_string()),
+            _ => column.push(c),
+        }
+    }
+    columns.push(column.trim().to_string());
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_header_columns() {
+        assert_eq!(header_columns("id,first_name, last_name\r\n"), vec!["id", "first_name", "last_name"]);
+        assert_eq!(header_columns("\u{feff}\"id\",\"name, full\",\"say \"\"hi\"\"\"\n"), vec!["id", "name, full", "say \"hi\""]);
+        assert!(header_columns("\n").is_empty());
+    }
+
+    #[test]
+    fn test_from_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("country_codes.csv");
+        fs::write(&path, "code,name\nGB,United Kingdom\n").unwrap();
+
+        let seed = SeedNode::from_path(path.clone());
+        assert_eq!(seed, SeedNode { name: "country_codes".to_string(), path, columns: vec!["code".to_string(), "name".to_string()] });
+
+        let missing = SeedNode::from_path(dir.path().join("missing.csv"));
+        assert_eq!(missing.name, "missing");
+        assert!(missing.columns.is_empty());
+    }
+}
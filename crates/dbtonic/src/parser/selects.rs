@@ -0,0 +1,143 @@
+use dbtranslate::ast::{Query, Select, SetExpr, Statement, TableFactor, TableWithJoins};
+
+// Collects every SELECT in the statements: the top level query, its CTEs,
+// both sides of set operations and subqueries in FROM clauses. Subqueries
+// inside expressions (e.g. `IN (SELECT ...)`) aren't visited.
+pub fn selects(statements: &[Statement]) -> Vec<&Select> {
+    walk(statements).selects
+}
+
+// Collects every query in the statements, in the same places as `selects`,
+// along with the parenthesized queries in set operations
+pub fn queries(statements: &[Statement]) -> Vec<&Query> {
+    walk(statements).queries
+}
+
+// The SELECTs whose rows the model returns: the top level query and both
+// sides of its set operations, but not its CTEs or subqueries
+pub fn final_selects(statements: &[Statement]) -> Vec<&Select> {
+    let mut selects = vec![];
+    for statement in statements {
+        let Statement::Query(query) = statement;
+        collect_final_set_expr(&query.body, &mut selects);
+    }
+    selects
+}
+
+fn collect_final_set_expr<'a>(set_expr: &'a SetExpr, selects: &mut Vec<&'a Select>) {
+    match set_expr {
+        SetExpr::Select(select) => selects.push(select),
+        SetExpr::Query(query) => collect_final_set_expr(&query.body, selects),
+        SetExpr::SetOperation { left, right, .. } => {
+            collect_final_set_expr(left, selects);
+            collect_final_set_expr(right, selects);
+        }
+        _ => {}
+    }
+}
+
+#[derive(Default)]
+struct Walk<'a> {
+    selects: Vec<&'a Select>,
+    queries: Vec<&'a Query>,
+}
+
+fn walk(statements: &[Statement]) -> Walk<'_> {
+    let mut walk = Walk::default();
+    for statement in statements {
+        let Statement::Query(query) = statement;
+        collect_query(query, &mut walk);
+    }
+    walk
+}
+
+fn collect_query<'a>(query: &'a Query, walk: &mut Walk<'a>) {
+    walk.queries.push(query);
+    if let Some(with) = &query.with {
+        for cte in &with.cte_tables {
+            collect_query(&cte.query, walk);
+        }
+    }
+    collect_set_expr(&query.body, walk);
+}
+
+fn collect_set_expr<'a>(set_expr: &'a SetExpr, walk: &mut Walk<'a>) {
+    match set_expr {
+        SetExpr::Select(select) => {
+            walk.selects.push(select);
+            for table_with_joins in &select.from {
+                collect_table_with_joins(table_with_joins, walk);
+            }
+        }
+        SetExpr::Query(query) => collect_query(query, walk),
+        SetExpr::SetOperation { left, right, .. } => {
+            collect_set_expr(left, walk);
+            collect_set_expr(right, walk);
+        }
+        _ => {}
+    }
+}
+
+fn collect_table_with_joins<'a>(table_with_joins: &'a TableWithJoins, walk: &mut Walk<'a>) {
+    collect_table_factor(&table_with_joins.relation, walk);
+    for join in &table_with_joins.joins {
+        collect_table_factor(&join.relation, walk);
+    }
+}
+
+fn collect_table_factor<'a>(table_factor: &'a TableFactor, walk: &mut Walk<'a>) {
+    match table_factor {
+        TableFactor::Derived { subquery, .. } => collect_query(subquery, walk),
+        TableFactor::NestedJoin { table_with_joins, .. } => collect_table_with_joins(table_with_joins, walk),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::dialect::GenericDialect;
+    use dbtranslate::parser::Parser;
+
+    #[test]
+    fn test_selects_visits_ctes_unions_and_subqueries() {
+        let sql = "WITH a AS (SELECT 1 AS x) \
+                   SELECT x FROM a JOIN (SELECT 2 AS y) AS b ON TRUE \
+                   UNION ALL SELECT 3 FROM {{ ref('orders') }}";
+        let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+
+        let projections: Vec<String> = selects(&statements)
+            .into_iter()
+            .map(|select| select.projection[0].to_string())
+            .collect();
+
+        assert_eq!(projections, vec!["1 AS x", "x", "2 AS y", "3"]);
+    }
+
+    #[test]
+    fn test_final_selects_skips_ctes_and_subqueries() {
+        let sql = "WITH a AS (SELECT 1 AS x) \
+                   SELECT x FROM a JOIN (SELECT 2 AS y) AS b ON TRUE \
+                   UNION ALL SELECT 3 FROM {{ ref('orders') }}";
+        let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+
+        let projections: Vec<String> = final_selects(&statements)
+            .into_iter()
+            .map(|select| select.projection[0].to_string())
+            .collect();
+
+        assert_eq!(projections, vec!["x", "3"]);
+    }
+
+    #[test]
+    fn test_queries_visits_ctes_subqueries_and_parentheses() {
+        let sql = "WITH a AS (SELECT 1 AS x) \
+                   SELECT x FROM (SELECT 2 AS x) AS b \
+                   UNION ALL (SELECT 3 FROM a)";
+        let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+
+        let queries: Vec<String> = queries(&statements).into_iter().skip(1).map(|query| query.to_string()).collect();
+
+        assert_eq!(queries, vec!["SELECT 1 AS x", "SELECT 2 AS x", "SELECT 3 FROM a"]);
+    }
+}
@@ -0,0 +1,607 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::parser::model_node::ModelNode;
+use crate::reporting::suggestions::with_suggestion;
+
+// The semantic layer lives in the same property files as models but under
+// the `semantic_models`, `metrics` and `saved_queries` keys. Every key is
+// optional so a file that only contains models parses to an empty layer.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct SemanticLayer {
+    #[serde(default)]
+    pub semantic_models: Vec<SemanticModelYaml>,
+    #[serde(default)]
+    pub metrics: Vec<MetricYaml>,
+    #[serde(default)]
+    pub saved_queries: Vec<SavedQueryYaml>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct SemanticModelYaml {
+    pub name: String,
+    // Written as `ref('model_name')` in the yaml
+    pub model: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub entities: Vec<SemanticField>,
+    #[serde(default)]
+    pub dimensions: Vec<SemanticField>,
+    #[serde(default)]
+    pub measures: Vec<SemanticField>,
+}
+
+// Entities, dimensions and measures all share the same shape for our purposes:
+// a name and an optional expression that defaults to the column of that name.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct SemanticField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: Option<String>,
+    pub agg: Option<String>,
+    pub expr: Option<serde_yaml::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct MetricYaml {
+    pub name: String,
+    pub label: Option<String>,
+    pub description: Option<String>,
+    #[serde(rename = "type")]
+    pub metric_type: Option<String>,
+    pub type_params: Option<MetricTypeParams>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct MetricTypeParams {
+    pub measure: Option<MeasureReference>,
+    pub numerator: Option<MeasureReference>,
+    pub denominator: Option<MeasureReference>,
+    pub metrics: Option<Vec<MeasureReference>>,
+}
+
+// Measures and metrics can be referenced either by name or with a mapping
+// such as `{name: order_total, filter: ...}`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum MeasureReference {
+    Name(String),
+    Detailed { name: String },
+}
+
+impl MeasureReference {
+    pub fn name(&self) -> &str {
+        match self {
+            MeasureReference::Name(name) => name,
+            MeasureReference::Detailed { name } => name,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct SavedQueryYaml {
+    pub name: String,
+    pub description: Option<String>,
+    pub query_params: Option<SavedQueryParams>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct SavedQueryParams {
+    #[serde(default)]
+    pub metrics: Vec<String>,
+    #[serde(default)]
+    pub group_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SemanticNodeType {
+    SemanticModel,
+    Metric,
+    SavedQuery,
+}
+
+impl fmt::Display for SemanticNodeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemanticNodeType::SemanticModel => write!(f, "semantic_model"),
+            SemanticNodeType::Metric => write!(f, "metric"),
+            SemanticNodeType::SavedQuery => write!(f, "saved_query"),
+        }
+    }
+}
+
+// A semantic layer object in the DAG. `depends_on` holds the models,
+// semantic models or metrics that it is built from.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SemanticNode {
+    pub name: String,
+    pub node_type: SemanticNodeType,
+    pub depends_on: Vec<SemanticInput>,
+}
+
+// Something a semantic node is built from. Names are only unique within a
+// kind, e.g. a model, a semantic model and a metric can all be `orders`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SemanticInput {
+    Model(String),
+    Node(SemanticNodeType, String),
+}
+
+impl SemanticNode {
+    // The node as an input to the nodes built on it
+    pub fn as_input(&self) -> SemanticInput {
+        SemanticInput::Node(self.node_type, self.name.clone())
+    }
+}
+
+impl fmt::Display for SemanticInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemanticInput::Model(model_name) => write!(f, "model: {}", model_name),
+            SemanticInput::Node(node_type, name) => write!(f, "{}: {}", node_type, name),
+        }
+    }
+}
+
+impl fmt::Display for SemanticNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let depends_on: Vec<String> = self.depends_on.iter().map(|input| input.to_string()).collect();
+        write!(f, "{}: {} (depends on: {})", self.node_type, self.name, depends_on.join(", "))
+    }
+}
+
+impl SemanticLayer {
+    pub fn extend(&mut self, other: SemanticLayer) {
+        self.semantic_models.extend(other.semantic_models);
+        self.metrics.extend(other.metrics);
+        self.saved_queries.extend(other.saved_queries);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.semantic_models.is_empty() && self.metrics.is_empty() && self.saved_queries.is_empty()
+    }
+
+    pub fn to_nodes(&self) -> Vec<SemanticNode> {
+        let mut nodes = vec![];
+
+        for semantic_model in &self.semantic_models {
+            nodes.push(SemanticNode {
+                name: semantic_model.name.clone(),
+                node_type: SemanticNodeType::SemanticModel,
+                depends_on: parse_ref_target(&semantic_model.model)
+                    .into_iter()
+                    .map(|target| SemanticInput::Model(target.name))
+                    .collect(),
+            });
+        }
+
+        for metric in &self.metrics {
+            let mut depends_on: Vec<SemanticInput> = vec![];
+            for measure in metric_measures(metric) {
+                if let Some(semantic_model) = self.semantic_model_for_measure(measure) {
+                    let input = SemanticInput::Node(SemanticNodeType::SemanticModel, semantic_model.name.clone());
+                    if !depends_on.contains(&input) {
+                        depends_on.push(input);
+                    }
+                }
+            }
+            for input_metric in metric_inputs(metric) {
+                let input = SemanticInput::Node(SemanticNodeType::Metric, input_metric.to_string());
+                if !depends_on.contains(&input) {
+                    depends_on.push(input);
+                }
+            }
+            nodes.push(SemanticNode {
+                name: metric.name.clone(),
+                node_type: SemanticNodeType::Metric,
+                depends_on,
+            });
+        }
+
+        for saved_query in &self.saved_queries {
+            nodes.push(SemanticNode {
+                name: saved_query.name.clone(),
+                node_type: SemanticNodeType::SavedQuery,
+                depends_on: saved_query_metrics(saved_query)
+                    .iter()
+                    .map(|metric_name| SemanticInput::Node(SemanticNodeType::Metric, metric_name.clone()))
+                    .collect(),
+            });
+        }
+
+        nodes
+    }
+
+    // Checks that every model, column, measure and metric referenced by the
+    // semantic layer exists. Columns are only checked when the model's yaml
+    // documents its columns, since that's the only place we can learn them.
+    // Models in other packages aren't read, so refs to them are left alone.
+    pub fn validate(&self, model_nodes: &[ModelNode], project_name: &str) -> Vec<String> {
+        let mut errors = vec![];
+
+        for semantic_model in &self.semantic_models {
+            let model_name = match parse_ref_target(&semantic_model.model) {
+                Some(target) if !target.is_in_project(project_name) => continue,
+                Some(target) => target.name,
+                None => {
+                    errors.push(format!(
+                        "Semantic model '{}' has an invalid model reference '{}'. Expected ref('model_name').",
+                        semantic_model.name, semantic_model.model
+                    ));
+                    continue;
+                }
+            };
+
+            let model_node = match model_nodes.iter().find(|node| node.model_name == model_name) {
+                Some(model_node) => model_node,
+                None => {
+                    errors.push(with_suggestion(
+                        format!(
+                            "Semantic model '{}' references model '{}' which does not exist.",
+                            semantic_model.name, model_name
+                        ),
+                        &model_name,
+                        model_nodes.iter().map(|node| node.model_name.as_str()),
+                    ));
+                    continue;
+                }
+            };
+
+            let documented_columns: Option<Vec<String>> = model_node.data.yaml
+                .as_ref()
+                .and_then(|yaml| yaml.columns.as_ref())
+                .map(|columns| columns.iter().map(|c| c.name.to_lowercase()).collect());
+
+            if let Some(columns) = documented_columns {
+                let fields = semantic_model.entities.iter()
+                    .chain(semantic_model.dimensions.iter())
+                    .chain(semantic_model.measures.iter());
+                for field in fields {
+                    if let Some(column) = field_column(field) {
+                        if !columns.contains(&column.to_lowercase()) {
+                            errors.push(format!(
+                                "Semantic model '{}' uses column '{}' which is not defined on model '{}'.",
+                                semantic_model.name, column, model_name
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        for metric in &self.metrics {
+            for measure in metric_measures(metric) {
+                if self.semantic_model_for_measure(measure).is_none() {
+                    errors.push(with_suggestion(
+                        format!(
+                            "Metric '{}' references measure '{}' which is not defined in any semantic model.",
+                            metric.name, measure
+                        ),
+                        measure,
+                        self.measure_names(),
+                    ));
+                }
+            }
+            for input_metric in metric_inputs(metric) {
+                if !self.metrics.iter().any(|m| m.name == input_metric) {
+                    errors.push(with_suggestion(
+                        format!(
+                            "Metric '{}' references metric '{}' which does not exist.",
+                            metric.name, input_metric
+                        ),
+                        input_metric,
+                        self.metrics.iter().map(|m| m.name.as_str()),
+                    ));
+                }
+            }
+        }
+
+        for saved_query in &self.saved_queries {
+            for metric_name in saved_query_metrics(saved_query) {
+                if !self.metrics.iter().any(|m| &m.name == metric_name) {
+                    errors.push(with_suggestion(
+                        format!(
+                            "Saved query '{}' references metric '{}' which does not exist.",
+                            saved_query.name, metric_name
+                        ),
+                        metric_name,
+                        self.metrics.iter().map(|m| m.name.as_str()),
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn measure_names(&self) -> impl Iterator<Item = &str> {
+        self.semantic_models
+            .iter()
+            .flat_map(|semantic_model| semantic_model.measures.iter())
+            .map(|measure| measure.name.as_str())
+    }
+
+    fn semantic_model_for_measure(&self, measure: &str) -> Option<&SemanticModelYaml> {
+        self.semantic_models
+            .iter()
+            .find(|semantic_model| semantic_model.measures.iter().any(|m| m.name == measure))
+    }
+}
+
+// What a `ref()` in yml points at
+#[derive(Debug, PartialEq, Clone)]
+pub struct RefTarget {
+    pub package: Option<String>,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl RefTarget {
+    // Whether the ref is to a node in the project rather than another
+    // package, whose nodes aren't read
+    pub fn is_in_project(&self, project_name: &str) -> bool {
+        self.package.as_deref().is_none_or(|package| package == project_name)
+    }
+}
+
+// Reads `ref('model_name')`, `ref('package', 'model_name')` and either with
+// a `v=2` or `version=2` after the names, the way dbt takes them
+pub fn parse_ref_target(model: &str) -> Option<RefTarget> {
+    let inner = model.trim().strip_prefix("ref")?.trim_start().strip_prefix('(')?.strip_suffix(')')?;
+
+    let mut names = vec![];
+    let mut version = None;
+    for argument in split_arguments(inner)? {
+        let keyword = argument.split_once('=').filter(|(key, _)| !is_quoted(key.trim()));
+        match keyword {
+            Some((key, value)) if matches!(key.trim(), "v" | "version") && version.is_none() => {
+                let value = value.trim();
+                let value = if is_quoted(value) { &value[1..value.len() - 1] } else { value };
+                if value.is_empty() {
+                    return None;
+                }
+                version = Some(value.to_string());
+            }
+            None if is_quoted(argument) && version.is_none() && names.len() < 2 => {
+                names.push(argument[1..argument.len() - 1].to_string());
+            }
+            _ => return None,
+        }
+    }
+
+    let name = names.pop().filter(|name| !name.is_empty())?;
+    Some(RefTarget { package: names.pop(), name, version })
+}
+
+// The trimmed arguments between a call's parens, split on the commas that
+// aren't in a string. None when a string isn't closed.
+fn split_arguments(inner: &str) -> Option<Vec<&str>> {
+    let mut arguments = vec![];
+    let mut quote = None;
+    let mut start = 0;
+    for (index, c) in inner.char_indices() {
+        match (quote, c) {
+            (Some(open), _) if c == open => quote = None,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ',') => {
+                arguments.push(inner[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    arguments.push(inner[start..].trim());
+    Some(arguments.into_iter().filter(|argument| !argument.is_empty()).collect())
+}
+
+fn is_quoted(value: &str) -> bool {
+    value.len() >= 2
+        && ((value.starts_with('\'') && value.ends_with('\'')) || (value.starts_with('"') && value.ends_with('"')))
+}
+
+// The column a semantic field reads from. Fields whose expression is SQL
+// rather than a plain column name can't be checked, so they return None.
+fn field_column(field: &SemanticField) -> Option<String> {
+    match &field.expr {
+        None => Some(field.name.clone()),
+        Some(serde_yaml::Value::String(expr)) => {
+            let is_identifier = !expr.is_empty()
+                && expr.chars().all(|c| c.is_alphanumeric() || c == '_')
+                && !expr.chars().all(|c| c.is_numeric());
+            if is_identifier {
+                Some(expr.clone())
+            } else {
+                None
+            }
+        }
+        Some(_) => None,
+    }
+}
+
+fn metric_measures(metric: &MetricYaml) -> Vec<&str> {
+    match &metric.type_params {
+        Some(params) => [&params.measure, &params.numerator, &params.denominator]
+            .into_iter()
+            .flatten()
+            .map(|measure| measure.name())
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn metric_inputs(metric: &MetricYaml) -> Vec<&str> {
+    match metric.type_params.as_ref().and_then(|params| params.metrics.as_ref()) {
+        Some(metrics) => metrics.iter().map(|m| m.name()).collect(),
+        None => vec![],
+    }
+}
+
+fn saved_query_metrics(saved_query: &SavedQueryYaml) -> &[String] {
+    match &saved_query.query_params {
+        Some(params) => &params.metrics,
+        None => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model_yaml::{ColumnProperties, ModelYaml};
+
+    const SEMANTIC_YAML: &str = r#"
+semantic_models:
+  - name: orders
+    model: ref('orders')
+    entities:
+      - name: order_id
+        type: primary
+    dimensions:
+      - name: ordered_at
+        type: time
+    measures:
+      - name: order_total
+        agg: sum
+        expr: amount
+      - name: order_count
+        agg: sum
+        expr: 1
+
+metrics:
+  - name: revenue
+    label: Revenue
+    type: simple
+    type_params:
+      measure: order_total
+  - name: revenue_per_order
+    type: derived
+    type_params:
+      metrics:
+        - revenue
+        - name: order_volume
+
+saved_queries:
+  - name: daily_revenue
+    query_params:
+      metrics:
+        - revenue
+"#;
+
+    fn model_node(name: &str, columns: Vec<&str>) -> ModelNode {
+        let columns = columns
+            .into_iter()
+            .map(|column| ColumnProperties {
+                name: column.to_string(),
+                constraints: None,
+                data_type: None,
+                description: None,
+                meta: None,
+                policy_tags: None,
+                quote: None,
+                tests: None,
+                tags: None,
+            })
+            .collect();
+
+        let mut model_node = ModelNode::from_test_sql(name, "");
+        model_node.data.yaml = Some(ModelYaml {
+            name: name.to_string(),
+            columns: Some(columns),
+            ..Default::default()
+        });
+        model_node
+    }
+
+    #[test]
+    fn test_parse_ref_target() {
+        let target = |package: Option<&str>, name: &str, version: Option<&str>| {
+            Some(RefTarget { package: package.map(String::from), name: name.to_string(), version: version.map(String::from) })
+        };
+        assert_eq!(parse_ref_target("ref('orders')"), target(None, "orders", None));
+        assert_eq!(parse_ref_target("ref(\"orders\")"), target(None, "orders", None));
+        assert_eq!(parse_ref_target("ref('shop', 'orders')"), target(Some("shop"), "orders", None));
+        assert_eq!(parse_ref_target("ref('orders', v=2)"), target(None, "orders", Some("2")));
+        assert_eq!(parse_ref_target("ref('shop', 'orders', version='2')"), target(Some("shop"), "orders", Some("2")));
+        assert_eq!(parse_ref_target("orders"), None);
+        assert_eq!(parse_ref_target("ref(orders)"), None);
+        assert_eq!(parse_ref_target("ref('a', 'b', 'c')"), None);
+        assert_eq!(parse_ref_target("ref('orders', v=2, 'shop')"), None);
+    }
+
+    #[test]
+    fn test_semantic_layer_refs_to_packages_and_versions() {
+        let semantic_layer: SemanticLayer = serde_yaml::from_str(
+            r#"
+semantic_models:
+  - name: orders
+    model: ref('orders', v=2)
+  - name: payments
+    model: ref('shop', 'payments')
+  - name: refunds
+    model: ref('jaffle_shop', 'refunds')
+"#,
+        )
+        .unwrap();
+        let model_nodes = vec![model_node("orders", vec![])];
+
+        assert_eq!(
+            semantic_layer.validate(&model_nodes, "jaffle_shop"),
+            vec!["Semantic model 'refunds' references model 'refunds' which does not exist.".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_semantic_layer_to_nodes() {
+        let semantic_layer: SemanticLayer = serde_yaml::from_str(SEMANTIC_YAML).unwrap();
+        let nodes = semantic_layer.to_nodes();
+
+        assert_eq!(nodes.len(), 4);
+        assert_eq!(nodes[0].node_type, SemanticNodeType::SemanticModel);
+        assert_eq!(nodes[0].depends_on, vec![SemanticInput::Model("orders".to_string())]);
+        assert_eq!(nodes[1].name, "revenue");
+        assert_eq!(nodes[1].depends_on, vec![SemanticInput::Node(SemanticNodeType::SemanticModel, "orders".to_string())]);
+        assert_eq!(nodes[3].node_type, SemanticNodeType::SavedQuery);
+        assert_eq!(nodes[3].depends_on, vec![SemanticInput::Node(SemanticNodeType::Metric, "revenue".to_string())]);
+    }
+
+    #[test]
+    fn test_semantic_layer_validation() {
+        let semantic_layer: SemanticLayer = serde_yaml::from_str(SEMANTIC_YAML).unwrap();
+        let model_nodes = vec![model_node("orders", vec!["order_id", "ordered_at"])];
+
+        let errors = semantic_layer.validate(&model_nodes, "jaffle_shop");
+
+        assert_eq!(
+            errors,
+            vec![
+                "Semantic model 'orders' uses column 'amount' which is not defined on model 'orders'.".to_string(),
+                "Metric 'revenue_per_order' references metric 'order_volume' which does not exist.".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_semantic_layer_missing_model() {
+        let semantic_layer: SemanticLayer = serde_yaml::from_str(SEMANTIC_YAML).unwrap();
+
+        let errors = semantic_layer.validate(&[], "jaffle_shop");
+
+        assert!(errors.contains(
+            &"Semantic model 'orders' references model 'orders' which does not exist.".to_string()
+        ));
+    }
+
+    #[test]
+    fn test_semantic_layer_missing_model_suggestion() {
+        let semantic_layer: SemanticLayer = serde_yaml::from_str(SEMANTIC_YAML).unwrap();
+        let model_nodes = vec![model_node("order", vec![])];
+
+        let errors = semantic_layer.validate(&model_nodes, "jaffle_shop");
+
+        assert!(errors.contains(
+            &"Semantic model 'orders' references model 'orders' which does not exist. Did you mean 'order'?".to_string()
+        ));
+    }
+
+}
@@ -0,0 +1,117 @@
+use std::ops::Range;
+use crate::configuration::dbtonic_config::{ParsingConfig, SqlDialect};
+use crate::parser::model_node::ModelNode;
+
+// A `{% snapshot name %} ... {% endsnapshot %}` block, by the byte ranges
+// of its two tags
+#[derive(Debug, PartialEq)]
+pub struct SnapshotBlock<'a> {
+    pub name: &'a str,
+    pub start_tag: Range<usize>,
+    pub end_tag: Range<usize>,
+}
+
+// The first snapshot block in the file. dbt allows one per file in practice,
+// and the SQL outside it is only whitespace.
+pub fn snapshot_block(sql: &str) -> Option<SnapshotBlock<'_>> {
+    let (start_tag, name) = find_tag(sql, 0, "snapshot")?;
+    let (end_tag, _) = find_tag(sql, start_tag.end, "endsnapshot")?;
+    Some(SnapshotBlock { name, start_tag, end_tag })
+}
+
+// The next `{% keyword ... %}` tag from `from`, along with what comes after
+// the keyword. The tag can have `-` whitespace control on either side.
+fn find_tag<'a>(sql: &'a str, from: usize, keyword: &str) -> Option<(Range<usize>, &'a str)> {
+    let mut offset = from;
+    while let Some(found) = sql[offset..].find("{%") {
+        let start = offset + found;
+        let end = start + 2 + sql[start + 2..].find("%}")?;
+        let inner = sql[start + 2..end].trim_start_matches('-').trim_end_matches('-').trim();
+        let mut words = inner.splitn(2, char::is_whitespace);
+        if words.next() == Some(keyword) {
+            return Some((start..end + 2, words.next().unwrap_or_default().trim()));
+        }
+        offset = end + 2;
+    }
+    None
+}
+
+// The SQL with the block's tags turned into spaces, so the config and
+// SELECT inside parse like a model's and keep their lines and columns
+fn without_tags(sql: &str, block: &SnapshotBlock) -> String {
+    let mut blanked = String::with_capacity(sql.len());
+    for (index, c) in sql.char_indices() {
+        let in_tag = block.start_tag.contains(&index) || block.end_tag.contains(&index);
+        blanked.push(if in_tag && c != '\n' { ' ' } else { c });
+    }
+    blanked
+}
+
+// Parses the config and SELECT inside a snapshot file's block. The node is
+// named after the block rather than the file, since that's what refs use,
+// and keeps the file's SQL for the rules on its text. Files without a block
+// are left with the errors from parsing them as they are.
+pub fn parse_snapshot(model_node: &mut ModelNode, parsing: &ParsingConfig, dialect: SqlDialect) {
+    let Some(block) = snapshot_block(&model_node.data.sql) else {
+        return;
+    };
+    let sql = without_tags(&model_node.data.sql, &block);
+    let parsed = ModelNode::from_sql(model_node.path.clone(), sql, parsing, dialect);
+    if !block.name.is_empty() {
+        model_node.model_name = block.name.to_string();
+    }
+    model_node.data.ast = parsed.data.ast;
+    model_node.data.refs = parsed.data.refs;
+    model_node.data.trivia = parsed.data.trivia;
+    model_node.data.errors = parsed.data.errors;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use dbtranslate::ast::DbtConfigValue;
+    use dbtranslate::tokens::Location;
+
+    const SNAPSHOT: &str = "{% snapshot orders_snapshot %}\n\
+                            {{ config(target_schema='snapshots', unique_key='id', strategy='timestamp', updated_at='updated_at') }}\n\
+                            SELECT * FROM {{ source('shop', 'orders') }}\n\
+                            {%- endsnapshot %}\n";
+
+    #[test]
+    fn test_snapshot_block() {
+        let block = snapshot_block(SNAPSHOT).unwrap();
+        assert_eq!(block.name, "orders_snapshot");
+        assert_eq!(&SNAPSHOT[block.start_tag], "{% snapshot orders_snapshot %}");
+        assert_eq!(&SNAPSHOT[block.end_tag], "{%- endsnapshot %}");
+
+        assert_eq!(snapshot_block("SELECT 1"), None);
+        assert_eq!(snapshot_block("{% snapshot orders_snapshot %}\nSELECT 1"), None);
+    }
+
+    #[test]
+    fn test_parse_snapshot() {
+        let parsing = ParsingConfig::default();
+        let path = PathBuf::from("snapshots/orders.sql");
+        let mut model_node = ModelNode::from_sql(path, SNAPSHOT.to_string(), &parsing, SqlDialect::Generic);
+        assert!(model_node.parse_failed());
+
+        parse_snapshot(&mut model_node, &parsing, SqlDialect::Generic);
+        assert_eq!(model_node.model_name, "orders_snapshot");
+        assert_eq!(model_node.data.errors, None);
+        assert_eq!(model_node.data.sql, SNAPSHOT);
+        assert_eq!(model_node.config_value("strategy"), Some(&DbtConfigValue::String("timestamp".to_string())));
+        assert_eq!(model_node.config_value("unique_key").and_then(|value| value.as_str()), Some("id"));
+    }
+
+    #[test]
+    fn test_parse_snapshot_keeps_locations() {
+        let parsing = ParsingConfig::default();
+        let sql = "{% snapshot broken %}\nSELECT 1\nFROM {{ 42 }}\n{% endsnapshot %}";
+        let mut model_node = ModelNode::from_sql(PathBuf::from("snapshots/broken.sql"), sql.to_string(), &parsing, SqlDialect::Generic);
+
+        parse_snapshot(&mut model_node, &parsing, SqlDialect::Generic);
+        let errors = model_node.data.errors.unwrap();
+        assert_eq!(errors[0].location, Some(Location { line: 3, column: 6 }));
+    }
+}
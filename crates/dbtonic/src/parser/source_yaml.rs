@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::parser::model_yaml::YamlParseError;
+
+// The `sources:` block of a property file
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct SourceYamlFile {
+    #[serde(default)]
+    pub sources: Vec<SourceYaml>,
+}
+
+impl SourceYamlFile {
+    pub fn from_file(file_path: PathBuf) -> Result<Vec<SourceYaml>, YamlParseError> {
+        let content = fs::read_to_string(file_path)?;
+        let source_file: SourceYamlFile = serde_yaml::from_str(&content)?;
+        Ok(source_file.sources)
+    }
+}
+
+// A source and the tables declared for it, which models select from with
+// `{{ source('name', 'table') }}`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct SourceYaml {
+    pub name: String,
+    pub database: Option<String>,
+    pub schema: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tables: Vec<SourceTableYaml>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct SourceTableYaml {
+    pub name: String,
+    // The table's name in the warehouse, when it isn't `name`
+    pub identifier: Option<String>,
+    pub description: Option<String>,
+}
+
+impl SourceYaml {
+    pub fn has_table(&self, table_name: &str) -> bool {
+        self.tables.iter().any(|table| table.name == table_name)
+    }
+
+    pub fn table_names(&self) -> impl Iterator<Item = &str> {
+        self.tables.iter().map(|table| table.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sources.yml");
+        fs::write(
+            &path,
+            "version: 2\n\
+             sources:\n  \
+               - name: shop\n    \
+                 schema: raw_shop\n    \
+                 tables:\n      \
+                   - name: orders\n      \
+                   - name: customers\n        \
+                     identifier: shop_customers\n\
+             models:\n  \
+               - name: stg_orders\n",
+        )
+        .unwrap();
+
+        let sources = SourceYamlFile::from_file(path).unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].schema.as_deref(), Some("raw_shop"));
+        assert_eq!(sources[0].table_names().collect::<Vec<_>>(), vec!["orders", "customers"]);
+        assert_eq!(sources[0].tables[1].identifier.as_deref(), Some("shop_customers"));
+        assert!(sources[0].has_table("orders"));
+        assert!(!sources[0].has_table("payments"));
+    }
+}
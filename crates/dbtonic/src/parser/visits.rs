@@ -1,61 +1,94 @@
-// use sqlparser::parser::Parser;
-// use sqlparser::dialect::GenericDialect;
-// use sqlparser::ast::{ObjectName, Expr};
-// use sqlparser::ast::visitor::Visitor;
-// use sqlparser::ast::query::TableFactor;
-// use core::ops::ControlFlow;
-// use sqlparser::ast::Ident;
-
-// // A structure that records statements and relations
-//  #[derive(Default)]
-// struct DbtRefVisitor {
-//     dbt_refs: Vec<Ident>,
-// }
-
-// // Visit relations and exprs before children are visited (depth first walk)
-// // Note you can also visit statements and visit exprs after children have been visitoed
-// impl Visitor for DbtRefVisitor {
-//     type Break = ();
-
-//     fn pre_visit_relation(&mut self, relation: &ObjectName) -> ControlFlow<Self::Break> {
-//         if let Some(TableFactor::DbtRef { model_name, .. }) = relation.0.get(0) {
-//             self.dbt_refs.push(model_name.clone());
-//         }
-//         ControlFlow::Continue(())
-//     }
-// }
-
-
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use sqlparser::dialect::GenericDialect;
-//     use sqlparser::parser::Parser;
-//     use sqlparser::tokenizer::Tokenizer;
-
-//     #[test]
-//     fn test_dbt_refs() {
-//     let sql = "SELECT a FROM foo where x IN (SELECT y FROM bar)";
-//     let statements = Parser::parse_sql(&GenericDialect{}, sql).unwrap();
-
-//     // Drive the visitor through the AST
-//     let mut visitor = DbtRefVisitor::default();
-//     for statement in &statements {
-//         statement.visit(&mut visitor);
-//     }
-
-//     dbg!(&visitor.visited);
-//     // The visitor has visited statements and expressions in pre-traversal order
-//     let expected : Vec<_> = [
-//         "PRE: EXPR: a",
-//         "PRE: RELATION: foo",
-//         "PRE: EXPR: x IN (SELECT y FROM bar)",
-//         "PRE: EXPR: x",
-//         "PRE: EXPR: y",
-//         "PRE: RELATION: bar",
-//     ]
-//     .into_iter().map(|s| s.to_string()).collect();
-
-//     assert_eq!(visitor.visited, expected);
-//     }
-// }
\ No newline at end of file
+use core::ops::ControlFlow;
+use dbtranslate::ast::{visit_expressions, visit_table_factors, Expr, JinjaBlock, Statement, TableFactor};
+use crate::parser::selects::selects;
+
+// Walkers over the whole AST built on dbtranslate's visitor. Unlike the
+// hand-rolled walks in `selects`, they reach everything: CTEs, both sides of
+// set operations, joins, and subqueries inside expressions like
+// `IN (SELECT ...)`. Rules that only care about the nodes of one kind, and
+// not where they are in the query, should use these.
+
+// Calls `f` with every table factor in the statements, parents before the
+// relations nested inside them, in the order they're written
+pub fn for_each_table_factor(statements: &[Statement], mut f: impl FnMut(&TableFactor)) {
+    for statement in statements {
+        let _ = visit_table_factors(statement, |table_factor| {
+            f(table_factor);
+            ControlFlow::<()>::Continue(())
+        });
+    }
+}
+
+// Calls `f` with every expression in the statements, parents before their
+// sub-expressions, in the order they're written
+pub fn for_each_expr(statements: &[Statement], mut f: impl FnMut(&Expr)) {
+    for statement in statements {
+        let _ = visit_expressions(statement, |expr| {
+            f(expr);
+            ControlFlow::<()>::Continue(())
+        });
+    }
+}
+
+// Calls `f` with every `{% if %}` and `{% for %}` block the parser kept:
+// the ones after a SELECT's FROM clause, then the ones inside expressions
+// like `WHERE a = 1 {% if x %} AND b = 2 {% endif %}`. Blocks in SELECTs
+// inside expressions aren't visited, like in `selects`.
+pub fn for_each_jinja_block(statements: &[Statement], mut f: impl FnMut(&JinjaBlock)) {
+    for select in selects(statements) {
+        select.jinja_blocks.iter().for_each(&mut f);
+    }
+    for_each_expr(statements, |expr| {
+        if let Expr::JinjaBlock { block, .. } = expr {
+            f(block);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::dialect::GenericDialect;
+    use dbtranslate::parser::Parser;
+    use crate::parser::ast_query::relation_name;
+
+    #[test]
+    fn test_for_each_table_factor_reaches_every_relation() {
+        let sql = "WITH a AS (SELECT id FROM {{ ref('orders') }}) \
+                   SELECT a.id FROM a JOIN (SELECT id FROM raw.customers) AS c ON a.id = c.id \
+                   WHERE a.id IN (SELECT id FROM {{ source('stripe', 'payments') }}) \
+                   UNION ALL SELECT id FROM refunds";
+        let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+
+        let mut relations = vec![];
+        for_each_table_factor(&statements, |table_factor| {
+            if !matches!(table_factor, TableFactor::Derived { .. }) {
+                relations.push(relation_name(table_factor));
+            }
+        });
+
+        assert_eq!(relations, vec!["orders", "a", "raw.customers", "stripe.payments", "refunds"]);
+    }
+
+    #[test]
+    fn test_for_each_expr_visits_parents_first() {
+        let statements = Parser::parse_sql(&GenericDialect {}, "SELECT a + 1 FROM t WHERE b = 2").unwrap();
+
+        let mut exprs = vec![];
+        for_each_expr(&statements, |expr| exprs.push(expr.to_string()));
+
+        assert_eq!(exprs, vec!["a + 1", "a", "1", "b = 2", "b", "2"]);
+    }
+
+    #[test]
+    fn test_for_each_jinja_block_finds_blocks_after_from_and_in_expressions() {
+        let sql = "WITH a AS (SELECT id FROM t {% if x %}WHERE id > 1{% endif %}) \
+                   SELECT id FROM a WHERE id < 10 {% if y %} AND id <> 5 {% endif %}";
+        let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+
+        let mut blocks = vec![];
+        for_each_jinja_block(&statements, |block| blocks.push(block.to_string()));
+
+        assert_eq!(blocks, vec!["{% if x %}WHERE id > 1{% endif %}", "{% if y %} AND id <> 5 {% endif %}"]);
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use crate::parser::model_node::{ModelNode, ParseStatus};
+
+// How many models in a group parsed cleanly, partially or not at all
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CoverageCounts {
+    pub clean: usize,
+    pub partial: usize,
+    pub failed: usize,
+}
+
+impl CoverageCounts {
+    pub fn total(&self) -> usize {
+        self.clean + self.partial + self.failed
+    }
+
+    pub fn percentage(&self, count: usize) -> f64 {
+        if self.total() == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / self.total() as f64
+        }
+    }
+
+    fn add(&mut self, status: ParseStatus) {
+        match status {
+            ParseStatus::Clean => self.clean += 1,
+            ParseStatus::Partial => self.partial += 1,
+            ParseStatus::Failed => self.failed += 1,
+        }
+    }
+}
+
+impl fmt::Display for CoverageCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} clean ({:.1}%), {} partial ({:.1}%), {} failed ({:.1}%)",
+            self.clean,
+            self.percentage(self.clean),
+            self.partial,
+            self.percentage(self.partial),
+            self.failed,
+            self.percentage(self.failed)
+        )
+    }
+}
+
+// Parse coverage for the whole project and for each directory of models, so
+// users can see where the parser's gaps are.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ParseCoverage {
+    pub overall: CoverageCounts,
+    pub directories: BTreeMap<String, CoverageCounts>,
+}
+
+impl ParseCoverage {
+    // Directories are shown relative to `base_path`, usually the project root
+    pub fn from_models(model_nodes: &[ModelNode], base_path: &Path) -> Self {
+        let mut coverage = ParseCoverage::default();
+
+        for model_node in model_nodes {
+            let status = model_node.parse_status();
+            let directory = model_node.path
+                .parent()
+                .map(|parent| parent.strip_prefix(base_path).unwrap_or(parent))
+                .map(|parent| parent.display().to_string())
+                .unwrap_or_default();
+
+            coverage.overall.add(status);
+            coverage.directories.entry(directory).or_default().add(status);
+        }
+
+        coverage
+    }
+}
+
+impl fmt::Display for ParseCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Parse coverage: {} model(s)", self.overall.total())?;
+        write!(f, "  {}", self.overall)?;
+        for (directory, counts) in &self.directories {
+            write!(f, "\n  {}: {}", directory, counts)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::parser::model_node::ParseError;
+
+    fn model_node(path: &str, errors: Option<Vec<ParseError>>) -> ModelNode {
+        let mut model_node = ModelNode::from_test_sql("test_model", "");
+        model_node.path = PathBuf::from(path);
+        model_node.data.errors = errors;
+        model_node
+    }
+
+    #[test]
+    fn test_parse_coverage_by_directory() {
+        let model_nodes = vec![
+            model_node("/project/models/staging/stg_orders.sql", None),
+            model_node("/project/models/staging/stg_payments.sql", Some(vec![ParseError { message: "sql parser error".to_string(), location: None }])),
+            model_node("/project/models/marts/orders.sql", None),
+        ];
+
+        let coverage = ParseCoverage::from_models(&model_nodes, Path::new("/project"));
+
+        assert_eq!(coverage.overall, CoverageCounts { clean: 2, partial: 0, failed: 1 });
+        assert_eq!(
+            coverage.to_string(),
+            "Parse coverage: 3 model(s)\n  \
+             2 clean (66.7%), 0 partial (0.0%), 1 failed (33.3%)\n  \
+             models/marts: 1 clean (100.0%), 0 partial (0.0%), 0 failed (0.0%)\n  \
+             models/staging: 1 clean (50.0%), 0 partial (0.0%), 1 failed (50.0%)"
+        );
+    }
+}
@@ -0,0 +1,598 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use dbtranslate::tokens::Location;
+use serde::Serialize;
+use crate::reporting::report::{Report, SkippedModel, Summary};
+use crate::reporting::sarif::Sarif;
+use crate::reporting::timing::TimingReport;
+use crate::rules::edits::{serialize_position, TextEdit};
+use crate::rules::rules_engine::{serialize_location, Severity, Violation};
+
+// One thing a rule found, with everything a renderer needs to show it and
+// nothing about how it's shown
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct Diagnostic {
+    // `None` for project rules that aren't about any one model
+    pub model: Option<String>,
+    pub path: Option<PathBuf>,
+    pub rule_name: String,
+    pub severity: Severity,
+    pub message: String,
+    pub span: Option<Span>,
+    // What to change, for violations that carry a fix
+    pub suggestion: Option<String>,
+    // The fix itself, for editors to offer as a quick fix
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub edits: Vec<TextEdit>,
+}
+
+// Where a diagnostic points. `end` is only known to some rules.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct Span {
+    #[serde(serialize_with = "serialize_position")]
+    pub start: Location,
+    #[serde(serialize_with = "serialize_location")]
+    pub end: Option<Location>,
+}
+
+// Everything a run found, in the order the models were evaluated and then
+// the project's. Renderers turn it into text.
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct Diagnostics {
+    pub diagnostics: Vec<Diagnostic>,
+    pub skipped: Vec<SkippedModel>,
+    pub summary: Summary,
+    // Only filled in when `evaluate --timing` is used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<TimingReport>,
+    // The text of each model file, for the code frames `Pretty` shows
+    #[serde(skip)]
+    pub sources: HashMap<PathBuf, String>,
+    // The project root that paths are relative to, for reading the sources
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+impl Diagnostic {
+    fn create(model: Option<(&str, &PathBuf)>, violation: &Violation) -> Self {
+        Diagnostic {
+            model: model.map(|(model_name, _)| model_name.to_string()).or_else(|| violation.model.clone()),
+            path: model.map(|(_, path)| path.clone()).or_else(|| violation.path.clone()),
+            rule_name: violation.rule_name.clone(),
+            severity: violation.severity,
+            message: violation.message.clone(),
+            span: violation.location.clone().map(|start| Span { start, end: violation.end_location.clone() }),
+            suggestion: suggestion(violation),
+            edits: violation.edits.clone(),
+        }
+    }
+}
+
+impl Diagnostics {
+    pub fn from_report(report: &Report) -> Self {
+        let mut diagnostics: Vec<Diagnostic> = vec![];
+        for model in &report.models {
+            let model_key = Some((model.model_name.as_str(), &model.path));
+            diagnostics.extend(model.violations.iter().map(|violation| Diagnostic::create(model_key, violation)));
+        }
+        diagnostics.extend(report.project.iter().map(|violation| Diagnostic::create(None, violation)));
+
+        Diagnostics {
+            diagnostics,
+            skipped: report.skipped.clone(),
+            summary: report.summary.clone(),
+            timing: report.timing.clone(),
+            sources: HashMap::new(),
+            root: PathBuf::new(),
+        }
+    }
+
+    // Makes the paths relative to the project's root, so every format shows
+    // them the same way wherever dbtonic was run from
+    pub fn relative_to(mut self, root: &Path) -> Self {
+        let relative = |path: &mut PathBuf| {
+            if let Ok(stripped) = path.strip_prefix(root) {
+                *path = stripped.to_path_buf();
+            }
+        };
+        self.diagnostics.iter_mut().filter_map(|diagnostic| diagnostic.path.as_mut()).for_each(relative);
+        self.skipped.iter_mut().map(|skipped| &mut skipped.path).for_each(relative);
+        self.root = root.to_path_buf();
+        self
+    }
+
+    // Reads the files the diagnostics point at. Files that can't be read
+    // are shown without a code frame.
+    pub fn with_sources(mut self) -> Self {
+        for path in self.diagnostics.iter().filter_map(|diagnostic| diagnostic.path.as_ref()) {
+            if !self.sources.contains_key(path) {
+                if let Ok(source) = fs::read_to_string(self.root.join(path)) {
+                    self.sources.insert(path.clone(), source);
+                }
+            }
+        }
+        self
+    }
+
+    // The diagnostics of each model that has any, in order
+    pub fn by_model(&self) -> Vec<(&str, Vec<&Diagnostic>)> {
+        let mut models: Vec<(&str, Vec<&Diagnostic>)> = vec![];
+        for diagnostic in &self.diagnostics {
+            let Some(model) = diagnostic.model.as_deref() else {
+                continue;
+            };
+            match models.iter_mut().find(|(model_name, _)| *model_name == model) {
+                Some((_, diagnostics)) => diagnostics.push(diagnostic),
+                None => models.push((model, vec![diagnostic])),
+            }
+        }
+        models
+    }
+
+    pub fn project(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.model.is_none())
+    }
+}
+
+// The fix a violation's edits make, put into words
+fn suggestion(violation: &Violation) -> Option<String> {
+    match violation.edits.as_slice() {
+        [] => None,
+        [edit] if edit.replacement.is_empty() => Some("Remove it.".to_string()),
+        [edit] => Some(format!("Replace it with `{}`.", edit.replacement)),
+        _ => Some("`dbtonic fix` can fix this.".to_string()),
+    }
+}
+
+// Turns the diagnostics of a run into text. `--format` picks one.
+pub trait Renderer {
+    fn render(&self, diagnostics: &Diagnostics) -> String;
+}
+
+// What `--format` names, for `evaluate` and `lint` alike
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OutputFormat {
+    Plain,
+    Pretty,
+    // The same json the file and webhook sinks send
+    Json,
+    // A SARIF log, for GitHub code scanning
+    Sarif,
+}
+
+impl OutputFormat {
+    pub const NAMES: [&'static str; 4] = ["plain", "pretty", "json", "sarif"];
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(OutputFormat::Plain),
+            "pretty" => Ok(OutputFormat::Pretty),
+            "json" => Ok(OutputFormat::Json),
+            "sarif" => Ok(OutputFormat::Sarif),
+            other => Err(format!("Unknown format '{}', expected one of {}", other, OutputFormat::NAMES.join(", "))),
+        }
+    }
+}
+
+// The renderer for a `--format`
+pub fn create_renderer(format: OutputFormat, max_locations: usize, color: bool) -> Box<dyn Renderer> {
+    match format {
+        OutputFormat::Plain => Box::new(Plain),
+        OutputFormat::Pretty => Box::new(Pretty { max_locations, color }),
+        OutputFormat::Json => Box::new(Json),
+        OutputFormat::Sarif => Box::new(Sarif),
+    }
+}
+
+// One line per diagnostic, in the `file:line:column: severity: message [rule]`
+// shape CI logs and editors know how to read, followed by a count of what
+// was found
+pub struct Plain;
+
+impl Renderer for Plain {
+    fn render(&self, diagnostics: &Diagnostics) -> String {
+        let mut lines: Vec<String> = diagnostics.diagnostics.iter().map(plain_line).collect();
+        for skipped in &diagnostics.skipped {
+            let file = match &skipped.location {
+                Some(location) => format!("{}:{}:{}", skipped.path.display(), location.line, location.column),
+                None => skipped.path.display().to_string(),
+            };
+            lines.push(format!("{}: skipped: {}", file, skipped.reason));
+        }
+
+        let summary = &diagnostics.summary;
+        lines.push(format!(
+            "{} error(s), {} warning(s), {} info in {} model(s), {} skipped (parse error)",
+            summary.errors, summary.warnings, summary.info, summary.evaluated, summary.skipped
+        ));
+        lines.join("\n")
+    }
+}
+
+fn plain_line(diagnostic: &Diagnostic) -> String {
+    let file = match &diagnostic.path {
+        Some(path) => path.display().to_string(),
+        None => "project".to_string(),
+    };
+    let severity = diagnostic.severity.label();
+    match &diagnostic.span {
+        Some(span) => format!(
+            "{}:{}:{}: {}: {} [{}]",
+            file, span.start.line, span.start.column, severity, diagnostic.message, diagnostic.rule_name
+        ),
+        None => format!("{}: {}: {} [{}]", file, severity, diagnostic.message, diagnostic.rule_name),
+    }
+}
+
+// The human readable output, like rustc's: each failure with the line it's
+// on underlined, errors first. Repeated failures of the same rule in a model
+// are shown once, with a frame for each of the first `max_locations`.
+pub struct Pretty {
+    pub max_locations: usize,
+    pub color: bool,
+}
+
+impl Renderer for Pretty {
+    fn render(&self, diagnostics: &Diagnostics) -> String {
+        let mut blocks: Vec<String> = vec![];
+
+        for (_, model_diagnostics) in diagnostics.by_model() {
+            for group in group_by_rule(model_diagnostics) {
+                blocks.push(self.render_group(&group, diagnostics));
+            }
+        }
+        for group in group_by_rule(diagnostics.project().collect()) {
+            blocks.push(self.render_group(&group, diagnostics));
+        }
+
+        // List the models we couldn't parse so they don't silently disappear
+        for skipped in &diagnostics.skipped {
+            let file = match &skipped.location {
+                Some(location) => format!("{}:{}:{}", skipped.path.display(), location.line, location.column),
+                None => skipped.path.display().to_string(),
+            };
+            blocks.push(format!(
+                "{}: {} couldn't be parsed, so it was skipped\n --> {}\n  = {}",
+                self.paint(Severity::Warn, Severity::Warn.label()),
+                skipped.model_name,
+                file,
+                skipped.reason
+            ));
+        }
+
+        let summary = &diagnostics.summary;
+        blocks.push(format!(
+            "{} model(s) evaluated, {} with failures, {} skipped (parse error)\n\
+             {} error(s), {} warning(s), {} info",
+            summary.evaluated, summary.with_failures, summary.skipped, summary.errors, summary.warnings, summary.info
+        ));
+
+        if let Some(timing) = &diagnostics.timing {
+            blocks.push(timing.to_string());
+        }
+
+        blocks.join("\n\n")
+    }
+}
+
+impl Pretty {
+    // `severity[rule]: message`, then where each failure is
+    fn render_group(&self, group: &[&Diagnostic], diagnostics: &Diagnostics) -> String {
+        let first = group[0];
+        let severity = self.paint(first.severity, first.severity.label());
+        let mut lines = vec![if group.len() > 1 {
+            format!("{}[{}]: {} ({} occurrences)", severity, first.rule_name, first.message, group.len())
+        } else {
+            format!("{}[{}]: {}", severity, first.rule_name, first.message)
+        }];
+
+        let shown = &group[..group.len().min(self.max_locations.max(1))];
+        // Wide enough for the biggest line number in the frames
+        let width = shown
+            .iter()
+            .filter_map(|diagnostic| diagnostic.span.as_ref())
+            .map(|span| span.start.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let pad = " ".repeat(width);
+
+        for diagnostic in shown {
+            let path = diagnostic.path.as_ref().map(|path| path.display());
+            match (&path, &diagnostic.span) {
+                (Some(path), Some(span)) => lines.push(format!("{}--> {}:{}:{}", pad, path, span.start.line, span.start.column)),
+                (Some(path), None) => lines.push(format!("{}--> {}", pad, path)),
+                (None, _) => {}
+            }
+            let source = diagnostic.path.as_ref().and_then(|path| diagnostics.sources.get(path));
+            if let (Some(span), Some(source)) = (&diagnostic.span, source) {
+                let label = (diagnostic.message != first.message).then_some(diagnostic.message.as_str());
+                lines.extend(self.code_frame(diagnostic.severity, span, label, source, &pad));
+            }
+            if let Some(suggestion) = &diagnostic.suggestion {
+                lines.push(format!("{} = help: {}", pad, suggestion));
+            }
+        }
+
+        let hidden = group.len() - shown.len();
+        if hidden > 0 {
+            lines.push(format!("{} = and {} more", pad, hidden));
+        }
+        lines.join("\n")
+    }
+
+    // The line the span starts on, with carets under the span. Spans going
+    // over several lines are underlined to the end of the first one. `label`
+    // goes after the carets, for occurrences whose message isn't the group's.
+    fn code_frame(&self, severity: Severity, span: &Span, label: Option<&str>, source: &str, pad: &str) -> Vec<String> {
+        let Some(line) = source.lines().nth((span.start.line as usize).saturating_sub(1)) else {
+            return vec![];
+        };
+        let line_length = line.chars().count();
+        let start = (span.start.column as usize).saturating_sub(1).min(line_length);
+        let end = match &span.end {
+            Some(end) if end.line == span.start.line => (end.column as usize).saturating_sub(1).min(line_length),
+            Some(_) => line_length,
+            None => start + 1,
+        };
+        let carets = "^".repeat(end.saturating_sub(start).max(1));
+        let indent: String = line.chars().take(start).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+
+        vec![
+            format!("{} |", pad),
+            format!("{:>width$} | {}", span.start.line, line, width = pad.len()),
+            match label {
+                Some(label) => format!("{} | {}{}", pad, indent, self.paint(severity, &format!("{} {}", carets, label))),
+                None => format!("{} | {}{}", pad, indent, self.paint(severity, &carets)),
+            },
+        ]
+    }
+
+    fn paint(&self, severity: Severity, text: &str) -> String {
+        if !self.color {
+            return text.to_string();
+        }
+        severity_color(severity, text)
+    }
+}
+
+// The diagnostics of one model, or of the project, by rule, most severe first
+fn group_by_rule(diagnostics: Vec<&Diagnostic>) -> Vec<Vec<&Diagnostic>> {
+    let mut groups: Vec<Vec<&Diagnostic>> = vec![];
+    for diagnostic in diagnostics {
+        match groups.iter_mut().find(|group| group[0].rule_name == diagnostic.rule_name) {
+            Some(group) => group.push(diagnostic),
+            None => groups.push(vec![diagnostic]),
+        }
+    }
+    groups.sort_by_key(|group| std::cmp::Reverse(group[0].severity));
+    groups
+}
+
+// The diagnostics as a flat json list, for tools that don't want to walk
+// the report's models
+pub struct Json;
+
+impl Renderer for Json {
+    fn render(&self, diagnostics: &Diagnostics) -> String {
+        serde_json::to_string_pretty(diagnostics).expect("diagnostics always serialize")
+    }
+}
+
+// `text` in the severity's color: red, yellow or blue
+fn severity_color(severity: Severity, text: &str) -> String {
+    let code = match severity {
+        Severity::Error => "31",
+        Severity::Warn => "33",
+        Severity::Info => "34",
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model_node::ModelNode;
+    use crate::reporting::report::ModelReport;
+    use crate::rules::edits::TextEdit;
+
+    fn report() -> Report {
+        Report::create(
+            vec![ModelReport {
+                model_name: "orders".to_string(),
+                path: PathBuf::from("models/orders.sql"),
+                violations: vec![
+                    Violation::new(
+                        "identifier_quoting".to_string(),
+                        "The identifier \"id\" doesn't need quotes.".to_string(),
+                        Some(Location { line: 1, column: 8 }),
+                    )
+                    .with_end_location(Location { line: 1, column: 12 })
+                    .with_edits(vec![TextEdit {
+                        start: Location { line: 1, column: 8 },
+                        end: Location { line: 1, column: 12 },
+                        replacement: "id".to_string(),
+                    }])
+                    .with_severity(Severity::Warn),
+                    Violation::new("yaml_exists".to_string(), "The model has no yml.".to_string(), None),
+                ],
+            }],
+            vec![Violation::new(
+                "exposure_validation".to_string(),
+                "The exposure 'weekly_kpis' has no owner.".to_string(),
+                None,
+            )],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_from_report() {
+        let diagnostics = Diagnostics::from_report(&report());
+
+        assert_eq!(diagnostics.diagnostics.len(), 3);
+        assert_eq!(
+            diagnostics.diagnostics[0],
+            Diagnostic {
+                model: Some("orders".to_string()),
+                path: Some(PathBuf::from("models/orders.sql")),
+                rule_name: "identifier_quoting".to_string(),
+                severity: Severity::Warn,
+                message: "The identifier \"id\" doesn't need quotes.".to_string(),
+                span: Some(Span { start: Location { line: 1, column: 8 }, end: Some(Location { line: 1, column: 12 }) }),
+                suggestion: Some("Replace it with `id`.".to_string()),
+                edits: vec![TextEdit {
+                    start: Location { line: 1, column: 8 },
+                    end: Location { line: 1, column: 12 },
+                    replacement: "id".to_string(),
+                }],
+            }
+        );
+        assert_eq!(diagnostics.diagnostics[1].suggestion, None);
+        assert_eq!(diagnostics.by_model().len(), 1);
+        assert_eq!(diagnostics.by_model()[0].1.len(), 2);
+        assert_eq!(
+            diagnostics.project().map(|diagnostic| diagnostic.rule_name.as_str()).collect::<Vec<&str>>(),
+            vec!["exposure_validation"]
+        );
+        assert_eq!(diagnostics.summary.errors, 2);
+    }
+
+    #[test]
+    fn test_project_violations_in_a_model() {
+        let model_node = ModelNode::from_test_sql("refunds", "SELECT id FROM {{ ref('refunds') }}");
+        let violation = Violation::new(
+            "ref_cycles".to_string(),
+            "The model 'refunds' refs itself.".to_string(),
+            Some(Location { line: 1, column: 23 }),
+        )
+        .in_model(&model_node);
+        let diagnostics = Diagnostics::from_report(&Report::create(vec![], vec![violation], vec![]));
+
+        assert_eq!(diagnostics.project().count(), 0);
+        assert_eq!(diagnostics.by_model()[0].0, "refunds");
+        assert_eq!(
+            Plain.render(&diagnostics).lines().next(),
+            Some("models/refunds.sql:1:23: error: The model 'refunds' refs itself. [ref_cycles]")
+        );
+    }
+
+    #[test]
+    fn test_paths_relative_to_the_project_root() {
+        let mut report = report();
+        report.models[0].path = PathBuf::from("/work/project/models/orders.sql");
+        report.skipped.push(SkippedModel {
+            model_name: "payments".to_string(),
+            path: PathBuf::from("/work/project/models/payments.sql"),
+            location: Some(Location { line: 2, column: 6 }),
+            reason: "sql parser error".to_string(),
+        });
+        let diagnostics = Diagnostics::from_report(&report).relative_to(Path::new("/work/project"));
+
+        let plain = Plain.render(&diagnostics);
+        assert!(plain.starts_with("models/orders.sql:1:8: warn:"), "{}", plain);
+        assert!(plain.contains("\nmodels/payments.sql:2:6: skipped:"), "{}", plain);
+        let json: serde_json::Value = serde_json::from_str(&Json.render(&diagnostics)).unwrap();
+        assert_eq!(json["diagnostics"][0]["path"], "models/orders.sql");
+        assert_eq!(json["skipped"][0]["path"], "models/payments.sql");
+    }
+
+    #[test]
+    fn test_pretty_renderer() {
+        let report = report();
+        let mut models = report.models.clone();
+        models[0].violations.extend((3..=5).map(|line| {
+            Violation::new(
+                "identifier_quoting".to_string(),
+                format!("The identifier on line {} doesn't need quotes.", line),
+                Some(Location { line, column: 3 }),
+            )
+            .with_severity(Severity::Warn)
+        }));
+        let mut diagnostics = Diagnostics::from_report(&Report::create(models, report.project, vec![]));
+        diagnostics.sources.insert(
+            PathBuf::from("models/orders.sql"),
+            "SELECT \"id\",\n  total,\n  \"name\",\n  \"email\"\nFROM orders".to_string(),
+        );
+
+        assert_eq!(
+            Pretty { max_locations: 2, color: false }.render(&diagnostics),
+            "error[yaml_exists]: The model has no yml.\n \
+             --> models/orders.sql\n\
+             \n\
+             warn[identifier_quoting]: The identifier \"id\" doesn't need quotes. (4 occurrences)\n \
+             --> models/orders.sql:1:8\n  \
+             |\n\
+             1 | SELECT \"id\",\n  \
+             |        ^^^^\n  \
+             = help: Replace it with `id`.\n \
+             --> models/orders.sql:3:3\n  \
+             |\n\
+             3 |   \"name\",\n  \
+             |   ^ The identifier on line 3 doesn't need quotes.\n  \
+             = and 2 more\n\
+             \n\
+             error[exposure_validation]: The exposure 'weekly_kpis' has no owner.\n\
+             \n1 model(s) evaluated, 1 with failures, 0 skipped (parse error)\n\
+             2 error(s), 4 warning(s), 0 info"
+        );
+    }
+
+    #[test]
+    fn test_pretty_renderer_colors_the_carets() {
+        let mut diagnostics = Diagnostics::from_report(&report());
+        diagnostics.sources.insert(PathBuf::from("models/orders.sql"), "SELECT \"id\" FROM orders".to_string());
+
+        let rendered = Pretty { max_locations: 1, color: true }.render(&diagnostics);
+        assert!(rendered.contains("\x1b[33mwarn\x1b[0m[identifier_quoting]"), "{}", rendered);
+        assert!(rendered.contains("  |        \x1b[33m^^^^\x1b[0m\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn test_json_renderer() {
+        let json: serde_json::Value = serde_json::from_str(&Json.render(&Diagnostics::from_report(&report()))).unwrap();
+
+        assert_eq!(
+            json["diagnostics"][0],
+            serde_json::json!({
+                "model": "orders",
+                "path": "models/orders.sql",
+                "rule_name": "identifier_quoting",
+                "severity": "warn",
+                "message": "The identifier \"id\" doesn't need quotes.",
+                "span": {"start": {"line": 1, "column": 8}, "end": {"line": 1, "column": 12}},
+                "suggestion": "Replace it with `id`.",
+                "edits": [{"start": {"line": 1, "column": 8}, "end": {"line": 1, "column": 12}, "replacement": "id"}],
+            })
+        );
+        assert!(json["diagnostics"][1].get("edits").is_none());
+        assert!(json["diagnostics"][2]["model"].is_null());
+        assert_eq!(json["summary"]["errors"], 2);
+    }
+
+    #[test]
+    fn test_output_format_names() {
+        for name in OutputFormat::NAMES {
+            assert!(name.parse::<OutputFormat>().is_ok());
+        }
+        assert_eq!("sarif".parse(), Ok(OutputFormat::Sarif));
+        assert_eq!(
+            "text".parse::<OutputFormat>(),
+            Err("Unknown format 'text', expected one of plain, pretty, json, sarif".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sarif_renderer_uses_the_project_root() {
+        let root = Path::new("/repository/transform");
+        let diagnostics = Diagnostics::from_report(&report()).relative_to(root);
+        let sarif: serde_json::Value = serde_json::from_str(&create_renderer(OutputFormat::Sarif, 5, false).render(&diagnostics)).unwrap();
+
+        assert_eq!(sarif["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "models/orders.sql");
+    }
+}
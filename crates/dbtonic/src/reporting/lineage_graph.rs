@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use crate::parser::lineage::{Lineage, LineageNode};
+
+// How `dbtonic dag` prints the lineage
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    // clap only lets through the names listed for `--format`
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "mermaid" => GraphFormat::Mermaid,
+            _ => GraphFormat::Dot,
+        }
+    }
+}
+
+pub fn render(lineage: &Lineage, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => render_dot(lineage),
+        GraphFormat::Mermaid => render_mermaid(lineage),
+    }
+}
+
+// A Graphviz digraph, for `dot -Tsvg`. Sources are drawn as cylinders,
+// seeds as notes and the semantic layer as hexagons, and models that select from `{{ this }}` get a dashed edge
+// to themselves.
+fn render_dot(lineage: &Lineage) -> String {
+    let mut lines = vec![
+        "digraph dbtonic {".to_string(),
+        "  rankdir=LR;".to_string(),
+        "  node [shape=box];".to_string(),
+    ];
+    for node in sorted_nodes(lineage) {
+        match node {
+            LineageNode::Model(_) => lines.push(format!("  {};", dot_id(node))),
+            LineageNode::Seed(_) => lines.push(format!("  {} [shape=note];", dot_id(node))),
+            LineageNode::Source(..) => lines.push(format!("  {} [shape=cylinder];", dot_id(node))),
+            LineageNode::Semantic(..) => lines.push(format!("  {} [shape=hexagon];", dot_id(node))),
+        }
+    }
+    for (parent, child) in sorted_edges(lineage) {
+        lines.push(format!("  {} -> {};", dot_id(parent), dot_id(child)));
+    }
+    for model_name in lineage.self_references() {
+        let id = dot_id(&LineageNode::Model(model_name.to_string()));
+        lines.push(format!("  {} -> {} [style=dashed, label=\"this\"];", id, id));
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+fn dot_id(node: &LineageNode) -> String {
+    format!("\"{}\"", node.to_string().replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// A Mermaid flowchart, which GitHub renders in markdown. Mermaid ids can't
+// hold every character a model name can, so nodes get numbered ids and
+// their names as labels. Self references are dotted.
+fn render_mermaid(lineage: &Lineage) -> String {
+    let nodes = sorted_nodes(lineage);
+    let ids: BTreeMap<&LineageNode, String> = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (*node, format!("n{}", index)))
+        .collect();
+
+    let mut lines = vec!["graph LR".to_string()];
+    for node in &nodes {
+        let label = node.to_string().replace('"', "#quot;");
+        match node {
+            LineageNode::Model(_) => lines.push(format!("  {}[\"{}\"]", ids[node], label)),
+            LineageNode::Seed(_) => lines.push(format!("  {}[/\"{}\"/]", ids[node], label)),
+            LineageNode::Source(..) => lines.push(format!("  {}[(\"{}\")]", ids[node], label)),
+            LineageNode::Semantic(..) => lines.push(format!("  {}{{{{\"{}\"}}}}", ids[node], label)),
+        }
+    }
+    for (parent, child) in sorted_edges(lineage) {
+        lines.push(format!("  {} --> {}", ids[parent], ids[child]));
+    }
+    for model_name in lineage.self_references() {
+        if let Some(id) = ids.get(&LineageNode::Model(model_name.to_string())) {
+            lines.push(format!("  {} -. this .-> {}", id, id));
+        }
+    }
+    lines.join("\n")
+}
+
+// Sorted so the output doesn't change between runs unless the lineage does
+fn sorted_nodes(lineage: &Lineage) -> Vec<&LineageNode> {
+    let mut nodes: Vec<&LineageNode> = lineage.nodes().collect();
+    nodes.sort();
+    nodes
+}
+
+fn sorted_edges(lineage: &Lineage) -> Vec<(&LineageNode, &LineageNode)> {
+    let mut edges = lineage.edges();
+    edges.sort();
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::parser::model_node::ModelNode;
+    use crate::parser::seed::SeedNode;
+    use crate::parser::semantic_layer::{SemanticInput, SemanticNode, SemanticNodeType};
+
+    fn lineage() -> Lineage {
+        Lineage::from_models_and_seeds(
+            &[
+                ModelNode::from_test_sql("orders", "SELECT id FROM {{ ref('stg_orders') }} AS stg_orders JOIN {{ ref('countries') }} USING (id)"),
+                ModelNode::from_test_sql("stg_orders", "SELECT id FROM {{ source('shop', 'orders') }} WHERE id > (SELECT MAX(id) FROM {{ this }})"),
+            ],
+            &[SeedNode { name: "countries".to_string(), path: PathBuf::new(), columns: vec![] }],
+        )
+        .with_semantic_nodes(&[SemanticNode {
+            name: "orders".to_string(),
+            node_type: SemanticNodeType::SemanticModel,
+            depends_on: vec![SemanticInput::Model("orders".to_string())],
+        }])
+    }
+
+    #[test]
+    fn test_render_dot() {
+        assert_eq!(
+            render(&lineage(), GraphFormat::Dot),
+            "digraph dbtonic {\n  \
+               rankdir=LR;\n  \
+               node [shape=box];\n  \
+               \"orders\";\n  \
+               \"stg_orders\";\n  \
+               \"countries\" [shape=note];\n  \
+               \"shop.orders\" [shape=cylinder];\n  \
+               \"semantic_model: orders\" [shape=hexagon];\n  \
+               \"orders\" -> \"semantic_model: orders\";\n  \
+               \"stg_orders\" -> \"orders\";\n  \
+               \"countries\" -> \"orders\";\n  \
+               \"shop.orders\" -> \"stg_orders\";\n  \
+               \"stg_orders\" -> \"stg_orders\" [style=dashed, label=\"this\"];\n\
+             }"
+        );
+    }
+
+    #[test]
+    fn test_render_mermaid() {
+        assert_eq!(
+            render(&lineage(), GraphFormat::Mermaid),
+            "graph LR\n  \
+               n0[\"orders\"]\n  \
+               n1[\"stg_orders\"]\n  \
+               n2[/\"countries\"/]\n  \
+               n3[(\"shop.orders\")]\n  \
+               n4{{\"semantic_model: orders\"}}\n  \
+               n0 --> n4\n  \
+               n1 --> n0\n  \
+               n2 --> n0\n  \
+               n3 --> n1\n  \
+               n1 -. this .-> n1"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::Path;
+use crate::reporting::diagnostics::{Diagnostics, Plain, Renderer};
+use crate::reporting::report::Report;
+use crate::rules::rules_engine::Severity;
+
+// The default output of `dbtonic lint`, one line per violation, with paths
+// relative to the project's `root`
+pub fn render(report: &Report, root: &Path) -> String {
+    Plain.render(&Diagnostics::from_report(report).relative_to(root))
+}
+
+// Whether any violation is at or above `fail_level`
+pub fn fails(report: &Report, fail_level: Severity) -> bool {
+    report.violations().any(|violation| violation.severity >= fail_level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use dbtranslate::tokens::Location;
+    use crate::reporting::report::{ModelReport, SkippedModel};
+    use crate::rules::rules_engine::Violation;
+
+    fn report(project_severity: Severity) -> Report {
+        Report::create(
+            vec![ModelReport {
+                model_name: "orders".to_string(),
+                path: PathBuf::from("models/orders.sql"),
+                violations: vec![Violation::new(
+                    "explicit_aliases".to_string(),
+                    "The function call `SUM(amount)` has no alias.".to_string(),
+                    Some(Location { line: 3, column: 8 }),
+                )
+                .with_severity(Severity::Warn)],
+            }],
+            vec![Violation::new(
+                "exposure_validation".to_string(),
+                "The exposure 'weekly_kpis' has no owner.".to_string(),
+                None,
+            )
+            .with_severity(project_severity)],
+            vec![SkippedModel {
+                model_name: "payments".to_string(),
+                path: PathBuf::from("models/payments.sql"),
+                location: Some(Location { line: 2, column: 6 }),
+                reason: "sql parser error".to_string(),
+            }],
+        )
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render(&report(Severity::Error), Path::new("")),
+            "models/orders.sql:3:8: warn: The function call `SUM(amount)` has no alias. [explicit_aliases]\n\
+             project: error: The exposure 'weekly_kpis' has no owner. [exposure_validation]\n\
+             models/payments.sql:2:6: skipped: sql parser error\n\
+             1 error(s), 1 warning(s), 0 info in 1 model(s), 1 skipped (parse error)"
+        );
+    }
+
+    #[test]
+    fn test_fails_at_or_above_the_fail_level() {
+        assert!(fails(&report(Severity::Error), Severity::Error));
+        assert!(!fails(&report(Severity::Warn), Severity::Error));
+        assert!(fails(&report(Severity::Warn), Severity::Warn));
+        assert!(!fails(&report(Severity::Info), Severity::Error));
+        assert!(fails(&report(Severity::Info), Severity::Info));
+        assert!(!fails(&Report::default(), Severity::Warn));
+    }
+}
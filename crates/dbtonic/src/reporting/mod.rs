@@ -0,0 +1,32 @@
+// The reporting module
+
+// Appends "did you mean" hints to messages about names that don't resolve
+pub mod suggestions;
+
+// Summarises how much of the project the parser could handle
+pub mod coverage;
+
+// The results of a run and the sinks they are written to
+pub mod report;
+pub mod sinks;
+
+// The results of a run as a flat list, and the renderers that print it
+pub mod diagnostics;
+
+// The one line per violation output of `dbtonic lint`
+pub mod lint;
+
+// The SARIF log GitHub code scanning reads, for `evaluate --format sarif`
+pub mod sarif;
+
+// Where the time in a run went, for `evaluate --timing`
+pub mod timing;
+
+// The count of parsed files shown while a large project loads
+pub mod progress;
+
+// The lineage as Graphviz DOT or Mermaid, for `dbtonic dag`
+pub mod lineage_graph;
+
+// A Markdown page per model, for `dbtonic docs`
+pub mod model_docs;
@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use dbtranslate::ast::{helpers, SelectItem, Statement};
+use crate::parser::dag::DAG;
+use crate::parser::lineage::LineageNode;
+use crate::parser::model_node::ModelNode;
+use crate::parser::selects::final_selects;
+use crate::rules::rules_engine::Violation;
+
+// A column the model returns, from its final SELECT
+#[derive(Debug, PartialEq, Clone)]
+pub struct OutputColumn {
+    // `None` when the warehouse picks the name, e.g. for `SUM(amount)`
+    pub name: Option<String>,
+    pub expression: String,
+}
+
+// The columns of the model's final SELECT. With a UNION the first SELECT
+// names them, like in the warehouse. `*` and `alias.*` are listed as is,
+// since what they expand to depends on the parents.
+pub fn output_columns(model_node: &ModelNode) -> Vec<OutputColumn> {
+    let Some(select) = final_selects(&model_node.data.ast).into_iter().next() else {
+        return vec![];
+    };
+    select
+        .projection
+        .iter()
+        .zip(helpers::output_columns(select))
+        .map(|(item, name)| {
+            let expression = match item {
+                SelectItem::ExprWithAlias { expr, .. } | SelectItem::UnnamedExpr(expr) => expr.to_string(),
+                SelectItem::QualifiedWildcard(name, _) => format!("{}.*", name),
+                SelectItem::Wildcard(_) => "*".to_string(),
+            };
+            OutputColumn { name: name.map(|ident| ident.value.to_string()), expression }
+        })
+        .collect()
+}
+
+// The model's configs, with the ones dbt gives precedence to winning: the
+// folder's in dbt_project.yml, then the yml's, then the model's own
+// `{{ config() }}` block. The materialization dbt resolved wins over all of
+// them when there's a manifest.json.
+pub fn configs(model_node: &ModelNode, dag: &DAG) -> BTreeMap<String, String> {
+    let mut configs = BTreeMap::new();
+    if let Some(materialized) = dag.project.model_config(&model_node.path, "materialized") {
+        configs.insert("materialized".to_string(), materialized);
+    }
+
+    let yaml_config = model_node.data.yaml.as_ref().and_then(|yaml| yaml.config.as_ref());
+    if let Some(serde_json::Value::Object(values)) = yaml_config.and_then(|config| serde_json::to_value(config).ok()) {
+        for (key, value) in values {
+            match value {
+                serde_json::Value::Null => {}
+                serde_json::Value::String(value) => {
+                    configs.insert(key, value);
+                }
+                value => {
+                    configs.insert(key, value.to_string());
+                }
+            }
+        }
+    }
+
+    for statement in &model_node.data.ast {
+        let Statement::Query(query) = statement;
+        for (key, value) in query.config.iter().flat_map(|config| &config.values) {
+            configs.insert(key.clone(), value.to_string());
+        }
+    }
+
+    if let Some(materialized) = model_node.data.manifest.as_ref().and_then(|manifest| manifest.materialized.clone()) {
+        configs.insert("materialized".to_string(), materialized);
+    }
+    configs
+}
+
+// A Markdown page describing the model, e.g. to attach to a pull request
+pub fn render(model_node: &ModelNode, dag: &DAG, violations: &[Violation], base_path: &Path) -> String {
+    let model_name = model_node.model_name.as_str();
+    let yaml = model_node.data.yaml.as_ref();
+    let relative_path = model_node.path.strip_prefix(base_path).unwrap_or(&model_node.path);
+
+    let mut lines = vec![format!("# {}", model_name), String::new(), format!("`{}`", relative_path.display())];
+    if let Some(description) = yaml.and_then(|yaml| yaml.description.as_deref()) {
+        lines.extend([String::new(), description.trim().to_string()]);
+    }
+
+    lines.extend([String::new(), "## Config".to_string(), String::new()]);
+    let configs = configs(model_node, dag);
+    let tags = model_node.tags();
+    if configs.is_empty() && tags.is_empty() {
+        lines.push("None set.".to_string());
+    } else {
+        lines.extend(["| Config | Value |".to_string(), "| --- | --- |".to_string()]);
+        for (key, value) in configs.iter().filter(|(key, _)| key.as_str() != "tags") {
+            lines.push(format!("| `{}` | {} |", key, table_cell(value)));
+        }
+        if !tags.is_empty() {
+            lines.push(format!("| `tags` | {} |", table_cell(&tags.join(", "))));
+        }
+    }
+
+    lines.extend([String::new(), "## Upstream".to_string(), String::new()]);
+    lines.extend(node_list(dag.lineage.parents(model_name)));
+    lines.extend([String::new(), "## Downstream".to_string(), String::new()]);
+    lines.extend(node_list(dag.lineage.children(model_name)));
+
+    lines.extend([String::new(), "## Columns".to_string(), String::new()]);
+    let columns = output_columns(model_node);
+    if columns.is_empty() {
+        lines.push("Couldn't be inferred from the final SELECT.".to_string());
+    } else {
+        let description = |name: &str| -> String {
+            yaml.and_then(|yaml| yaml.columns.as_ref())
+                .and_then(|columns| columns.iter().find(|column| column.name.eq_ignore_ascii_case(name)))
+                .and_then(|column| column.description.as_deref())
+                .map(table_cell)
+                .unwrap_or_default()
+        };
+        lines.extend(["| Column | Expression | Description |".to_string(), "| --- | --- | --- |".to_string()]);
+        for column in columns {
+            let name = column.name.as_deref().unwrap_or("");
+            lines.push(format!(
+                "| {} | `{}` | {} |",
+                table_cell(name),
+                table_cell(&column.expression),
+                description(name)
+            ));
+        }
+    }
+
+    lines.extend([String::new(), "## Rule results".to_string(), String::new()]);
+    if model_node.parse_failed() {
+        lines.push(format!("Skipped, the model couldn't be parsed: {}", model_node.parse_errors()));
+    } else if violations.is_empty() {
+        lines.push("Every rule passed.".to_string());
+    } else {
+        for violation in violations {
+            let location = match &violation.location {
+                Some(location) => format!(" (line {})", location.line),
+                None => String::new(),
+            };
+            lines.push(format!(
+                "- {} `{}`{}: {}",
+                violation.severity.label().to_uppercase(),
+                violation.rule_name,
+                location,
+                violation.message
+            ));
+        }
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+// The page linking to every model's, with how many violations each has
+pub fn render_index(model_pages: &[(&ModelNode, usize)]) -> String {
+    let mut lines = vec![
+        "# Models".to_string(),
+        String::new(),
+        "| Model | Violations |".to_string(),
+        "| --- | --- |".to_string(),
+    ];
+    for (model_node, violations) in model_pages {
+        lines.push(format!("| [{}]({}.md) | {} |", model_node.model_name, model_node.model_name, violations));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn node_list(nodes: Vec<&LineageNode>) -> Vec<String> {
+    if nodes.is_empty() {
+        return vec!["None.".to_string()];
+    }
+    nodes
+        .into_iter()
+        .map(|node| match node {
+            LineageNode::Model(model_name) => format!("- [{}]({}.md)", model_name, model_name),
+            LineageNode::Seed(..) => format!("- `{}` (seed)", node),
+            LineageNode::Source(..) => format!("- `{}` (source)", node),
+            LineageNode::Semantic(..) => format!("- `{}`", node),
+        })
+        .collect()
+}
+
+// Pipes and newlines would break the table
+fn table_cell(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ").replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::rules::rules_engine::Severity;
+    use dbtranslate::tokens::Location;
+
+    fn model_node(model_name: &str, sql: &str, yaml: Option<&str>) -> ModelNode {
+        let mut model_node = ModelNode::from_test_sql(model_name, sql);
+        model_node.path = PathBuf::from(format!("/project/models/{}.sql", model_name));
+        model_node.data.yaml = yaml.map(|yaml| serde_yaml::from_str(yaml).unwrap());
+        model_node
+    }
+
+    fn dag() -> DAG {
+        let model_nodes = vec![
+            model_node(
+                "orders",
+                "{{ config(materialized='table', tags=['finance']) }} \
+                 SELECT o.id, o.status AS order_status, SUM(p.amount) AS amount, COUNT(*), p.* \
+                 FROM {{ ref('stg_orders') }} AS o JOIN {{ source('stripe', 'payments') }} AS p ON o.id = p.order_id",
+                Some("name: orders\ndescription: One row\n  per order.\ncolumns:\n  - name: id\n    description: The primary key.\n"),
+            ),
+            model_node("stg_orders", "SELECT id, status FROM {{ source('shop', 'orders') }}", None),
+        ];
+        DAG::from_test_models(model_nodes)
+    }
+
+    #[test]
+    fn test_output_columns() {
+        let columns: Vec<(Option<String>, String)> = output_columns(&dag().model_nodes[0])
+            .into_iter()
+            .map(|column| (column.name, column.expression))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                (Some("id".to_string()), "o.id".to_string()),
+                (Some("order_status".to_string()), "o.status".to_string()),
+                (Some("amount".to_string()), "SUM(p.amount)".to_string()),
+                (None, "COUNT(*)".to_string()),
+                (None, "p.*".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let dag = dag();
+        let violations = vec![Violation::new(
+            "explicit_aliases".to_string(),
+            "Give `COUNT(*)` an alias.".to_string(),
+            Some(Location { line: 1, column: 98 }),
+        )
+        .with_severity(Severity::Warn)];
+
+        assert_eq!(
+            render(&dag.model_nodes[0], &dag, &violations, Path::new("/project")),
+            "# orders\n\
+             \n\
+             `models/orders.sql`\n\
+             \n\
+             One row per order.\n\
+             \n\
+             ## Config\n\
+             \n\
+             | Config | Value |\n\
+             | --- | --- |\n\
+             | `materialized` | table |\n\
+             | `tags` | finance |\n\
+             \n\
+             ## Upstream\n\
+             \n\
+             - [stg_orders](stg_orders.md)\n\
+             - `stripe.payments` (source)\n\
+             \n\
+             ## Downstream\n\
+             \n\
+             None.\n\
+             \n\
+             ## Columns\n\
+             \n\
+             | Column | Expression | Description |\n\
+             | --- | --- | --- |\n\
+             | id | `o.id` | The primary key. |\n\
+             | order_status | `o.status` |  |\n\
+             | amount | `SUM(p.amount)` |  |\n\
+             |  | `COUNT(*)` |  |\n\
+             |  | `p.*` |  |\n\
+             \n\
+             ## Rule results\n\
+             \n\
+             - WARN `explicit_aliases` (line 1): Give `COUNT(*)` an alias.\n"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// A count of the files done so far, redrawn in place on stderr so large
+// projects don't look stuck while they parse. It's only drawn when stderr
+// is a terminal, so piped and CI output stay clean.
+pub struct Progress {
+    label: &'static str,
+    total: usize,
+    done: AtomicUsize,
+    visible: bool,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, total: usize, enabled: bool) -> Self {
+        Progress { label, total, done: AtomicUsize::new(0), visible: enabled && io::stderr().is_terminal() }
+    }
+
+    // Safe to call from rayon's threads
+    pub fn tick(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.visible && should_draw(done, self.total) {
+            eprint!("\r{}", line(self.label, done, self.total));
+        }
+    }
+
+    // Clears the line so the results start on an empty one
+    pub fn finish(&self) {
+        if self.visible {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+// Redrawing for every file would slow down projects with thousands of
+// models, so the count moves in steps of about 1%
+fn should_draw(done: usize, total: usize) -> bool {
+    let step = (total / 100).max(1);
+    done.is_multiple_of(step) || done == total
+}
+
+fn line(label: &str, done: usize, total: usize) -> String {
+    format!("{} {}/{} ({}%)", label, done, total, done * 100 / total.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draws_in_steps() {
+        let drawn: Vec<usize> = (1..=250).filter(|&done| should_draw(done, 250)).collect();
+        assert_eq!(drawn[..3], [2, 4, 6]);
+        assert_eq!(drawn.last(), Some(&250));
+        assert!((1..=7).all(|done| should_draw(done, 7)));
+        assert_eq!(line("Parsing", 42, 168), "Parsing 42/168 (25%)");
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use dbtranslate::tokens::Location;
+use serde::Serialize;
+use crate::parser::model_node::ModelNode;
+use crate::rules::rules_engine::{serialize_location, Severity, Violation};
+use crate::reporting::timing::TimingReport;
+
+// Everything a single `evaluate` run found. Sinks decide how to present it.
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct Report {
+    pub models: Vec<ModelReport>,
+    pub project: Vec<Violation>,
+    pub skipped: Vec<SkippedModel>,
+    pub summary: Summary,
+    // Only filled in when `evaluate --timing` is used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<TimingReport>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct ModelReport {
+    pub model_name: String,
+    // The model's file, as found when walking the project
+    pub path: PathBuf,
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct SkippedModel {
+    pub model_name: String,
+    pub path: PathBuf,
+    // Where the first parse error is, when the parser knows
+    #[serde(serialize_with = "serialize_location")]
+    pub location: Option<Location>,
+    pub reason: String,
+}
+
+impl SkippedModel {
+    // A model that failed to parse. The first error's location is where the
+    // report points, so it's left out of the reason.
+    pub fn create(model_node: &ModelNode) -> Self {
+        let errors = model_node.data.errors.as_deref().unwrap_or_default();
+        let reason: Vec<String> = errors
+            .iter()
+            .enumerate()
+            .map(|(i, error)| if i == 0 { error.message.clone() } else { error.to_string() })
+            .collect();
+        SkippedModel {
+            model_name: model_node.model_name.clone(),
+            path: model_node.path.clone(),
+            location: errors.first().and_then(|error| error.location.clone()),
+            reason: reason.join("; "),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct Summary {
+    pub evaluated: usize,
+    pub with_failures: usize,
+    pub skipped: usize,
+    // Violations of each severity, across the models and the project
+    pub errors: usize,
+    pub warnings: usize,
+    pub info: usize,
+}
+
+impl Report {
+    pub fn create(models: Vec<ModelReport>, project: Vec<Violation>, skipped: Vec<SkippedModel>) -> Self {
+        let count = |severity: Severity| {
+            models
+                .iter()
+                .flat_map(|model| &model.violations)
+                .chain(&project)
+                .filter(|violation| violation.severity == severity)
+                .count()
+        };
+        let summary = Summary {
+            evaluated: models.len(),
+            with_failures: models.iter().filter(|model| !model.violations.is_empty()).count(),
+            skipped: skipped.len(),
+            errors: count(Severity::Error),
+            warnings: count(Severity::Warn),
+            info: count(Severity::Info),
+        };
+
+        Report { models, project, skipped, summary, timing: None }
+    }
+
+    // Every violation, the models' first and then the project's
+    pub fn violations(&self) -> impl Iterator<Item = &Violation> {
+        self.models
+            .iter()
+            .flat_map(|model| &model.violations)
+            .chain(&self.project)
+    }
+
+    pub fn with_timing(mut self, timing: TimingReport) -> Self {
+        self.timing = Some(timing);
+        self
+    }
+}
@@ -0,0 +1,244 @@
+use std::path::{Path, PathBuf};
+use serde_json::{json, Value};
+use crate::reporting::diagnostics::{Diagnostic, Diagnostics, Renderer};
+use crate::rules::rules_engine::Severity;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+// The file project rules are reported against when they aren't about any
+// one model
+const PROJECT_FILE: &str = "dbt_project.yml";
+
+// Prints the SARIF log for `--format sarif`, with the paths made relative
+// to the repository the project at the diagnostics' root is in
+pub struct Sarif;
+
+impl Renderer for Sarif {
+    fn render(&self, diagnostics: &Diagnostics) -> String {
+        let sarif = render(diagnostics, &diagnostics.root, &repository_root(&diagnostics.root));
+        serde_json::to_string_pretty(&sarif).expect("SARIF logs always serialize")
+    }
+}
+
+// The diagnostics as a SARIF 2.1.0 log, which GitHub code scanning turns
+// into annotations on the lines each violation covers. Paths are made
+// relative to `base_path`, the root of the repository, so they line up with
+// the files in a pull request even when the dbt project at `project_root` is
+// in a folder of its own.
+pub fn render(diagnostics: &Diagnostics, project_root: &Path, base_path: &Path) -> Value {
+    let uri = |path: &Path| relative_uri(&project_root.join(path), base_path);
+    let results: Vec<Value> = diagnostics
+        .diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let uri = match &diagnostic.path {
+                Some(path) => uri(path),
+                None => uri(Path::new(PROJECT_FILE)),
+            };
+            result(diagnostic, &uri)
+        })
+        .collect();
+
+    // Models that couldn't be parsed weren't checked, which is worth
+    // knowing but isn't a violation of any rule
+    let notifications: Vec<Value> = diagnostics
+        .skipped
+        .iter()
+        .map(|skipped| {
+            let mut physical_location = json!({"artifactLocation": artifact(&uri(&skipped.path))});
+            if let Some(location) = &skipped.location {
+                physical_location["region"] = json!({"startLine": location.line, "startColumn": location.column});
+            }
+            json!({
+                "level": "warning",
+                "message": {"text": format!("Skipped {}: {}", skipped.model_name, skipped.reason)},
+                "locations": [{"physicalLocation": physical_location}],
+            })
+        })
+        .collect();
+
+    let mut rule_names: Vec<&str> = diagnostics.diagnostics.iter().map(|diagnostic| diagnostic.rule_name.as_str()).collect();
+    rule_names.sort();
+    rule_names.dedup();
+    let rules: Vec<Value> = rule_names.iter().map(|rule_name| json!({"id": rule_name})).collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "dbtonic",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "invocations": [{
+                "executionSuccessful": true,
+                "toolExecutionNotifications": notifications,
+            }],
+            "results": results,
+        }],
+    })
+}
+
+fn result(diagnostic: &Diagnostic, uri: &str) -> Value {
+    let mut physical_location = json!({"artifactLocation": artifact(uri)});
+    if let Some(span) = &diagnostic.span {
+        let mut region = json!({"startLine": span.start.line, "startColumn": span.start.column});
+        // Our end locations, like SARIF's, point just past the span
+        if let Some(end) = &span.end {
+            region["endLine"] = json!(end.line);
+            region["endColumn"] = json!(end.column);
+        }
+        physical_location["region"] = region;
+    }
+
+    json!({
+        "ruleId": diagnostic.rule_name,
+        "level": level(diagnostic.severity),
+        "message": {"text": diagnostic.message},
+        "locations": [{"physicalLocation": physical_location}],
+    })
+}
+
+fn artifact(uri: &str) -> Value {
+    json!({"uri": uri, "uriBaseId": "%SRCROOT%"})
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warn => "warning",
+        Severity::Info => "note",
+    }
+}
+
+// The root of the git repository the project is in, found by its `.git`,
+// which is a file in worktrees and submodules. Outside a repository the
+// project root is used.
+pub fn repository_root(project_root: &Path) -> PathBuf {
+    project_root
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(project_root)
+        .to_path_buf()
+}
+
+// SARIF wants uris, so the separators are always forward slashes
+fn relative_uri(path: &Path, base_path: &Path) -> String {
+    path.strip_prefix(base_path)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use dbtranslate::tokens::Location;
+    use crate::reporting::report::{ModelReport, Report, SkippedModel};
+    use crate::rules::rules_engine::Violation;
+
+    fn diagnostics() -> Diagnostics {
+        Diagnostics::from_report(&Report::create(
+            vec![ModelReport {
+                model_name: "orders".to_string(),
+                path: PathBuf::from("/project/models/marts/orders.sql"),
+                violations: vec![
+                    Violation::new(
+                        "ref_aliases".to_string(),
+                        "`{{ ref('customers') }}` has no alias. Add `AS customers`.".to_string(),
+                        Some(Location { line: 4, column: 10 }),
+                    )
+                    .with_end_location(Location { line: 4, column: 33 })
+                    .with_severity(Severity::Warn),
+                    Violation::new("multiple_statements".to_string(), "Multiple statements.".to_string(), None),
+                ],
+            }],
+            vec![Violation::new(
+                "exposure_validation".to_string(),
+                "The exposure 'weekly_kpis' has no owner.".to_string(),
+                None,
+            )
+            .with_severity(Severity::Info)],
+            vec![SkippedModel {
+                model_name: "payments".to_string(),
+                path: PathBuf::from("/project/models/payments.sql"),
+                location: None,
+                reason: "sql parser error".to_string(),
+            }],
+        ))
+    }
+
+    #[test]
+    fn test_render_results() {
+        let sarif = render(&diagnostics(), Path::new("/project"), Path::new("/project"));
+        let run = &sarif["runs"][0];
+
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(
+            run["tool"]["driver"]["rules"],
+            json!([{"id": "exposure_validation"}, {"id": "multiple_statements"}, {"id": "ref_aliases"}])
+        );
+        assert_eq!(
+            run["results"][0],
+            json!({
+                "ruleId": "ref_aliases",
+                "level": "warning",
+                "message": {"text": "`{{ ref('customers') }}` has no alias. Add `AS customers`."},
+                "locations": [{"physicalLocation": {
+                    "artifactLocation": {"uri": "models/marts/orders.sql", "uriBaseId": "%SRCROOT%"},
+                    "region": {"startLine": 4, "startColumn": 10, "endLine": 4, "endColumn": 33},
+                }}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_render_results_without_a_location() {
+        let sarif = render(&diagnostics(), Path::new("/project"), Path::new("/project"));
+        let run = &sarif["runs"][0];
+
+        assert_eq!(run["results"][1]["level"], "error");
+        assert!(run["results"][1]["locations"][0]["physicalLocation"].get("region").is_none());
+        assert_eq!(run["results"][2]["level"], "note");
+        assert_eq!(run["results"][2]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "dbt_project.yml");
+        assert_eq!(
+            run["invocations"][0]["toolExecutionNotifications"][0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "models/payments.sql"
+        );
+    }
+
+    #[test]
+    fn test_render_results_for_a_project_in_a_subdirectory() {
+        let repository = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(repository.path().join(".git")).unwrap();
+        let project_root = repository.path().join("transform");
+        std::fs::create_dir_all(&project_root).unwrap();
+        let base_path = repository_root(&project_root);
+        assert_eq!(base_path, repository.path());
+
+        let diagnostics = Diagnostics::from_report(&Report::create(
+            vec![ModelReport {
+                model_name: "orders".to_string(),
+                path: project_root.join("models/orders.sql"),
+                violations: vec![Violation::new("multiple_statements".to_string(), "Multiple statements.".to_string(), None)],
+            }],
+            vec![Violation::new("exposure_validation".to_string(), "No owner.".to_string(), None)],
+            vec![],
+        ));
+        let sarif = render(&diagnostics, &project_root, &base_path);
+        let uris: Vec<&Value> = sarif["runs"][0]["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| &result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"])
+            .collect();
+        assert_eq!(uris, vec!["transform/models/orders.sql", "transform/dbt_project.yml"]);
+    }
+}
@@ -0,0 +1,305 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::time::Duration;
+use serde::Deserialize;
+use crate::reporting::diagnostics::{create_renderer, Diagnostics, OutputFormat};
+
+// Where the results of a run end up. Every configured sink receives the
+// same diagnostics.
+pub trait ReportSink {
+    fn name(&self) -> String;
+    fn write(&self, diagnostics: &Diagnostics) -> Result<(), SinkError>;
+}
+
+#[derive(Debug)]
+pub enum SinkError {
+    IoError(io::Error),
+    JsonError(serde_json::Error),
+    HttpError(String),
+}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkError::IoError(e) => write!(f, "{}", e),
+            SinkError::JsonError(e) => write!(f, "{}", e),
+            SinkError::HttpError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for SinkError {
+    fn from(error: io::Error) -> Self {
+        SinkError::IoError(error)
+    }
+}
+
+impl From<serde_json::Error> for SinkError {
+    fn from(error: serde_json::Error) -> Self {
+        SinkError::JsonError(error)
+    }
+}
+
+// The `[[output.sinks]]` entries in dbtonic.toml
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkConfig {
+    Stdout,
+    File { path: PathBuf },
+    Webhook { url: String },
+}
+
+pub fn create_sinks(configs: &[SinkConfig], max_locations: usize, format: OutputFormat) -> Vec<Box<dyn ReportSink>> {
+    configs
+        .iter()
+        .map(|config| -> Box<dyn ReportSink> {
+            match config {
+                SinkConfig::Stdout => Box::new(StdoutSink { max_locations, color: use_color(), format }),
+                SinkConfig::File { path } => Box::new(FileSink { path: path.clone() }),
+                SinkConfig::Webhook { url } => Box::new(WebhookSink { url: url.clone() }),
+            }
+        })
+        .collect()
+}
+
+// Colors are only worth it on a terminal, and https://no-color.org asks
+// us to leave them out when NO_COLOR is set
+pub fn use_color() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+// Prints the diagnostics in the `--format` of `evaluate`
+pub struct StdoutSink {
+    pub max_locations: usize,
+    pub color: bool,
+    pub format: OutputFormat,
+}
+
+impl StdoutSink {
+    pub fn render(&self, diagnostics: &Diagnostics) -> String {
+        create_renderer(self.format, self.max_locations, self.color).render(diagnostics)
+    }
+}
+
+impl ReportSink for StdoutSink {
+    fn name(&self) -> String {
+        "stdout".to_string()
+    }
+
+    fn write(&self, diagnostics: &Diagnostics) -> Result<(), SinkError> {
+        println!("{}", self.render(diagnostics));
+        Ok(())
+    }
+}
+
+// Writes the diagnostics as json, e.g. for CI to pick up as an artifact
+pub struct FileSink {
+    pub path: PathBuf,
+}
+
+impl ReportSink for FileSink {
+    fn name(&self) -> String {
+        format!("file ({})", self.path.display())
+    }
+
+    fn write(&self, diagnostics: &Diagnostics) -> Result<(), SinkError> {
+        let json = serde_json::to_string_pretty(diagnostics)?;
+        fs::write(&self.path, json)?;
+        Ok(())
+    }
+}
+
+// POSTs the diagnostics as json to a URL so results can be streamed to dashboards
+pub struct WebhookSink {
+    pub url: String,
+}
+
+impl ReportSink for WebhookSink {
+    fn name(&self) -> String {
+        format!("webhook ({})", self.url)
+    }
+
+    fn write(&self, diagnostics: &Diagnostics) -> Result<(), SinkError> {
+        ureq::post(&self.url)
+            .timeout(Duration::from_secs(30))
+            .send_json(diagnostics)
+            .map_err(|e| SinkError::HttpError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::tokens::Location;
+    use crate::reporting::diagnostics::{Json, Renderer};
+    use crate::reporting::report::{ModelReport, Report, SkippedModel};
+    use crate::rules::rules_engine::Violation;
+    use crate::rules::edits::TextEdit;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    fn diagnostics() -> Diagnostics {
+        Diagnostics::from_report(&Report::create(
+            vec![
+                ModelReport {
+                    model_name: "orders".to_string(),
+                    path: PathBuf::from("models/orders.sql"),
+                    violations: vec![Violation::new(
+                        "multiple_statements".to_string(),
+                        "The model contains 2 statements.".to_string(),
+                        Some(Location { line: 3, column: 1 }),
+                    )],
+                },
+                ModelReport {
+                    model_name: "customers".to_string(),
+                    path: PathBuf::from("models/customers.sql"),
+                    violations: vec![],
+                },
+            ],
+            vec![],
+            vec![SkippedModel {
+                model_name: "payments".to_string(),
+                path: PathBuf::from("models/payments.sql"),
+                location: None,
+                reason: "sql parser error".to_string(),
+            }],
+        ))
+    }
+
+    #[test]
+    fn test_stdout_sink_render() {
+        let sink = StdoutSink { max_locations: 5, color: false, format: OutputFormat::Pretty };
+
+        assert_eq!(
+            sink.render(&diagnostics()),
+            "error[multiple_statements]: The model contains 2 statements.\n \
+             --> models/orders.sql:3:1\n\
+             \n\
+             warn: payments couldn't be parsed, so it was skipped\n \
+             --> models/payments.sql\n  \
+             = sql parser error\n\
+             \n2 model(s) evaluated, 1 with failures, 1 skipped (parse error)\n\
+             1 error(s), 0 warning(s), 0 info"
+        );
+    }
+
+    #[test]
+    fn test_file_sink_writes_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.json");
+
+        FileSink { path: path.clone() }.write(&diagnostics()).unwrap();
+
+        // The same json `lint --format json` prints
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json, serde_json::from_str::<serde_json::Value>(&Json.render(&diagnostics())).unwrap());
+        assert_eq!(json["summary"]["with_failures"], 1);
+        assert_eq!(json["diagnostics"][0]["rule_name"], "multiple_statements");
+        assert_eq!(json["diagnostics"][0]["severity"], "error");
+        assert_eq!(json["diagnostics"][0]["model"], "orders");
+        assert_eq!(json["diagnostics"][0]["path"], "models/orders.sql");
+        assert_eq!(json["diagnostics"][0]["span"]["start"]["line"], 3);
+        assert!(json["diagnostics"][0]["span"]["end"].is_null());
+        assert!(json["diagnostics"][0].get("edits").is_none());
+        assert!(json.get("timing").is_none());
+        assert_eq!(json["skipped"][0]["model_name"], "payments");
+    }
+
+    #[test]
+    fn test_file_sink_writes_edits_for_fixable_violations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.json");
+        let violation = Violation::new(
+            "identifier_quoting".to_string(),
+            "The identifier \"orders\" doesn't need quotes.".to_string(),
+            Some(Location { line: 1, column: 15 }),
+        )
+        .with_end_location(Location { line: 1, column: 23 })
+        .with_edits(vec![TextEdit {
+            start: Location { line: 1, column: 15 },
+            end: Location { line: 1, column: 23 },
+            replacement: "orders".to_string(),
+        }]);
+        let report = Report::create(
+            vec![ModelReport {
+                model_name: "orders".to_string(),
+                path: PathBuf::from("models/orders.sql"),
+                violations: vec![violation],
+            }],
+            vec![],
+            vec![],
+        );
+
+        FileSink { path: path.clone() }.write(&Diagnostics::from_report(&report)).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["diagnostics"][0]["span"]["end"], serde_json::json!({"line": 1, "column": 23}));
+        assert_eq!(
+            json["diagnostics"][0]["edits"],
+            serde_json::json!([{
+                "start": {"line": 1, "column": 15},
+                "end": {"line": 1, "column": 23},
+                "replacement": "orders",
+            }])
+        );
+    }
+
+    // Answers one request on a local port with `status`, returning the url
+    // and a handle that yields the request line and body it received
+    fn serve_once(status: &'static str) -> (String, JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/results", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            (request_line.trim().to_string(), String::from_utf8(body).unwrap())
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_webhook_sink_posts_json() {
+        let (url, handle) = serve_once("200 OK");
+
+        WebhookSink { url }.write(&diagnostics()).unwrap();
+
+        let (request_line, body) = handle.join().unwrap();
+        assert_eq!(request_line, "POST /results HTTP/1.1");
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json, serde_json::from_str::<serde_json::Value>(&Json.render(&diagnostics())).unwrap());
+    }
+
+    #[test]
+    fn test_webhook_sink_fails_on_an_error_status() {
+        let (url, handle) = serve_once("500 Internal Server Error");
+
+        let result = WebhookSink { url }.write(&diagnostics());
+
+        handle.join().unwrap();
+        assert!(matches!(&result, Err(SinkError::HttpError(message)) if message.contains("500")), "{:?}", result);
+    }
+}
@@ -0,0 +1,14 @@
+use dbtranslate::suggestions::did_you_mean;
+
+// Appends a "Did you mean '...'?" hint to `message` when one of the
+// `candidates` is a plausible typo of `name`. Otherwise `message` is
+// returned untouched.
+pub fn with_suggestion<'a, I>(message: String, name: &str, candidates: I) -> String
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    match did_you_mean(name, candidates) {
+        Some(suggestion) => format!("{} Did you mean '{}'?", message, suggestion),
+        None => message,
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Serializer};
+use crate::parser::model_node::ParseTimings;
+
+// How many models the terminal output lists. The json has all of them.
+const SLOWEST_MODELS: usize = 10;
+
+// Runs `f` and returns its result along with how long it took
+pub fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+// How long a rule took. For model rules this is summed over every model
+// the rule ran on.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct RuleTiming {
+    pub rule_name: String,
+    #[serde(rename = "ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+}
+
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct ModelTiming {
+    pub model_name: String,
+    #[serde(rename = "tokenize_ms", serialize_with = "serialize_millis")]
+    pub tokenize: Duration,
+    #[serde(rename = "parse_ms", serialize_with = "serialize_millis")]
+    pub parse: Duration,
+    pub rules: Vec<RuleTiming>,
+}
+
+impl ModelTiming {
+    pub fn rules_total(&self) -> Duration {
+        self.rules.iter().map(|rule| rule.duration).sum()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.tokenize + self.parse + self.rules_total()
+    }
+}
+
+// Where the time in a run went, phase by phase. Rules run on several
+// threads, so the phases can add up to more than the wall clock `total`.
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct TimingReport {
+    #[serde(rename = "total_ms", serialize_with = "serialize_millis")]
+    pub total: Duration,
+    #[serde(rename = "tokenize_ms", serialize_with = "serialize_millis")]
+    pub tokenize: Duration,
+    #[serde(rename = "parse_ms", serialize_with = "serialize_millis")]
+    pub parse: Duration,
+    #[serde(rename = "dag_resolution_ms", serialize_with = "serialize_millis")]
+    pub dag_resolution: Duration,
+    // Every rule, slowest first
+    pub rules: Vec<RuleTiming>,
+    // Every model, slowest first
+    pub models: Vec<ModelTiming>,
+}
+
+impl TimingReport {
+    pub fn create(
+        total: Duration,
+        parse_timings: Vec<ParseTimings>,
+        dag_resolution: Duration,
+        mut model_rule_timings: HashMap<String, Vec<RuleTiming>>,
+        project_rule_timings: Vec<RuleTiming>,
+    ) -> Self {
+        let mut models: Vec<ModelTiming> = parse_timings
+            .into_iter()
+            .map(|parse_timings| ModelTiming {
+                rules: model_rule_timings.remove(&parse_timings.model_name).unwrap_or_default(),
+                model_name: parse_timings.model_name,
+                tokenize: parse_timings.tokenize,
+                parse: parse_timings.parse,
+            })
+            .collect();
+        models.sort_by_key(|model| std::cmp::Reverse(model.total()));
+
+        // Add up each rule across models, keeping the order rules first ran in
+        let mut rules: Vec<RuleTiming> = vec![];
+        for rule_timing in models.iter().flat_map(|model| &model.rules).chain(&project_rule_timings) {
+            match rules.iter_mut().find(|rule| rule.rule_name == rule_timing.rule_name) {
+                Some(rule) => rule.duration += rule_timing.duration,
+                None => rules.push(rule_timing.clone()),
+            }
+        }
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.duration));
+
+        TimingReport {
+            total,
+            tokenize: models.iter().map(|model| model.tokenize).sum(),
+            parse: models.iter().map(|model| model.parse).sum(),
+            dag_resolution,
+            rules,
+            models,
+        }
+    }
+}
+
+impl fmt::Display for TimingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Timing: {} total", format_duration(self.total))?;
+        writeln!(f, "  tokenize: {}", format_duration(self.tokenize))?;
+        writeln!(f, "  parse: {}", format_duration(self.parse))?;
+        writeln!(f, "  dag resolution: {}", format_duration(self.dag_resolution))?;
+        writeln!(f, "  rules: {}", format_duration(self.rules.iter().map(|rule| rule.duration).sum()))?;
+
+        writeln!(f, "Rules, slowest first:")?;
+        for rule in &self.rules {
+            writeln!(f, "  {}: {}", rule.rule_name, format_duration(rule.duration))?;
+        }
+
+        write!(f, "Models, slowest first:")?;
+        for model in self.models.iter().take(SLOWEST_MODELS) {
+            write!(
+                f,
+                "\n  {}: {} (tokenize {}, parse {}, rules {})",
+                model.model_name,
+                format_duration(model.total()),
+                format_duration(model.tokenize),
+                format_duration(model.parse),
+                format_duration(model.rules_total())
+            )?;
+        }
+        if self.models.len() > SLOWEST_MODELS {
+            write!(f, "\n  ... and {} more", self.models.len() - SLOWEST_MODELS)?;
+        }
+        Ok(())
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn rule(rule_name: &str, millis: u64) -> RuleTiming {
+        RuleTiming { rule_name: rule_name.to_string(), duration: ms(millis) }
+    }
+
+    fn timing_report() -> TimingReport {
+        let parse_timings = vec![
+            ParseTimings { model_name: "orders".to_string(), tokenize: ms(1), parse: ms(2) },
+            ParseTimings { model_name: "customers".to_string(), tokenize: ms(3), parse: ms(4) },
+        ];
+        let model_rule_timings = HashMap::from([
+            ("orders".to_string(), vec![rule("explicit_aliases", 10), rule("multiple_statements", 1)]),
+            ("customers".to_string(), vec![rule("explicit_aliases", 2), rule("multiple_statements", 1)]),
+        ]);
+
+        TimingReport::create(ms(30), parse_timings, ms(5), model_rule_timings, vec![rule("duplicate_logic", 6)])
+    }
+
+    #[test]
+    fn test_timing_report_aggregates_phases() {
+        let report = timing_report();
+
+        assert_eq!(report.tokenize, ms(4));
+        assert_eq!(report.parse, ms(6));
+        assert_eq!(report.rules, vec![rule("explicit_aliases", 12), rule("duplicate_logic", 6), rule("multiple_statements", 2)]);
+        assert_eq!(
+            report.models.iter().map(|model| model.model_name.as_str()).collect::<Vec<&str>>(),
+            vec!["orders", "customers"]
+        );
+    }
+
+    #[test]
+    fn test_timing_report_display() {
+        assert_eq!(
+            timing_report().to_string(),
+            "Timing: 30.00ms total\n  \
+             tokenize: 4.00ms\n  \
+             parse: 6.00ms\n  \
+             dag resolution: 5.00ms\n  \
+             rules: 20.00ms\n\
+             Rules, slowest first:\n  \
+             explicit_aliases: 12.00ms\n  \
+             duplicate_logic: 6.00ms\n  \
+             multiple_statements: 2.00ms\n\
+             Models, slowest first:\n  \
+             orders: 14.00ms (tokenize 1.00ms, parse 2.00ms, rules 11.00ms)\n  \
+             customers: 10.00ms (tokenize 3.00ms, parse 4.00ms, rules 3.00ms)"
+        );
+    }
+
+    #[test]
+    fn test_timing_report_serializes_millis() {
+        let json = serde_json::to_value(timing_report()).unwrap();
+
+        assert_eq!(json["total_ms"], 30.0);
+        assert_eq!(json["dag_resolution_ms"], 5.0);
+        assert_eq!(json["rules"][0], serde_json::json!({"rule_name": "explicit_aliases", "ms": 12.0}));
+        assert_eq!(json["models"][0]["parse_ms"], 2.0);
+    }
+}
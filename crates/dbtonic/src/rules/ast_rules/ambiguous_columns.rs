@@ -0,0 +1,148 @@
+use dbtranslate::ast::{Expr, Select, SelectItem};
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::selects::selects;
+use crate::parser::ast_query::{contains, expr_nodes, AstNode};
+
+// Date parts such as the `day` in `DATEDIFF(day, a, b)` parse as columns
+const DATE_PARTS: &[&str] = &[
+    "year", "quarter", "month", "week", "day", "dayofweek", "dayofyear", "hour", "minute", "second",
+    "millisecond", "microsecond", "nanosecond", "epoch",
+];
+
+// Flags unqualified columns in the select list and WHERE clause of a SELECT
+// that reads from more than one relation. The warehouse resolves them
+// today, but a reader can't tell where they come from, and the query breaks
+// once another of the relations gains a column with the same name.
+pub struct AmbiguousColumns;
+
+impl Rule for AmbiguousColumns {
+    fn name(&self) -> String {
+        "ambiguous_columns".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Columns must be qualified with their table in queries that read from more than one relation.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        selects(&model_node.data.ast)
+            .into_iter()
+            .filter(|select| relation_count(select) > 1)
+            .flat_map(|select| {
+                let relations = relation_count(select);
+                unqualified_columns(select).into_iter().map(move |column| {
+                    Violation::new(
+                        self.name(),
+                        format!(
+                            "The column `{}` isn't qualified, but the query reads from {} relations, so it's unclear which \
+                             one it comes from. Qualify it with its table's alias, e.g. `<alias>.{}`.",
+                            column.text, relations, column.text
+                        ),
+                        None,
+                    )
+                    .with_span(&column.span)
+                })
+            })
+            .collect()
+    }
+}
+
+// The relations in the SELECT's FROM clause, counting every joined one
+fn relation_count(select: &Select) -> usize {
+    select.from.iter().map(|table_with_joins| 1 + table_with_joins.joins.len()).sum()
+}
+
+// The unqualified columns in the select list and WHERE clause. Subqueries
+// in them read from their own relations, so they're skipped, and so are
+// names that are one of the select list's aliases or a date part.
+fn unqualified_columns(select: &Select) -> Vec<AstNode> {
+    let aliases: Vec<String> = select
+        .projection
+        .iter()
+        .filter_map(|item| match item {
+            SelectItem::ExprWithAlias { alias, .. } => Some(alias.value.to_lowercase()),
+            _ => None,
+        })
+        .collect();
+
+    let exprs = select
+        .projection
+        .iter()
+        .filter_map(|item| match item {
+            SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => Some(expr),
+            _ => None,
+        })
+        .chain(&select.selection);
+
+    exprs
+        .flat_map(|expr: &Expr| {
+            let nodes = expr_nodes(expr);
+            let subqueries: Vec<_> = nodes.iter().filter(|node| node.kind == "subqueries").map(|node| node.span.clone()).collect();
+            nodes
+                .into_iter()
+                .filter(|node| node.kind == "columns" && node.attribute("table") == Some(""))
+                .filter(|node| !subqueries.iter().any(|span| contains(span, &node.span)))
+                .filter(|node| {
+                    let name = node.attribute("name").unwrap_or_default().to_lowercase();
+                    !aliases.contains(&name) && !DATE_PARTS.contains(&name.as_str())
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::tokens::Location;
+
+    #[test]
+    fn test_single_relations_and_qualified_columns_pass() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "WITH orders AS (SELECT id, status FROM {{ ref('orders') }} WHERE status = 'paid') \
+             SELECT o.id, c.name AS customer FROM orders AS o JOIN {{ ref('customers') }} AS c ON o.customer_id = c.id \
+             WHERE o.id IN (SELECT order_id FROM {{ ref('refunds') }})",
+        );
+        assert_eq!(AmbiguousColumns.run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_unqualified_columns_with_several_relations_fail() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "SELECT o.id, status, UPPER(name) AS customer_name, DATEDIFF(day, o.ordered_at, c.created_at) AS age \
+             FROM {{ ref('orders') }} AS o JOIN {{ ref('customers') }} AS c ON o.customer_id = c.id WHERE amount > 0",
+        );
+
+        let violations = AmbiguousColumns.violations(&model_node);
+        assert_eq!(violations[0].location, Some(Location { line: 1, column: 14 }));
+        assert_eq!(violations[0].end_location, Some(Location { line: 1, column: 20 }));
+
+        let messages: Vec<String> = violations.into_iter().map(|violation| violation.message).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The column `status` isn't qualified, but the query reads from 2 relations, so it's unclear which one it \
+                 comes from. Qualify it with its table's alias, e.g. `<alias>.status`."
+                    .to_string(),
+                "The column `name` isn't qualified, but the query reads from 2 relations, so it's unclear which one it \
+                 comes from. Qualify it with its table's alias, e.g. `<alias>.name`."
+                    .to_string(),
+                "The column `amount` isn't qualified, but the query reads from 2 relations, so it's unclear which one it \
+                 comes from. Qualify it with its table's alias, e.g. `<alias>.amount`."
+                    .to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use dbtranslate::ast::{Spanned, TableFactor};
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::visits::for_each_table_factor;
+
+// Flags tables named with their database, e.g. `FROM analytics.raw.orders`.
+// The database usually differs between dev and prod, so a model that names
+// one only works in the environment it was written for.
+pub struct CrossDatabaseReferences;
+
+impl Rule for CrossDatabaseReferences {
+    fn name(&self) -> String {
+        "cross_database_references".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models must not name tables with their database, e.g. `db.schema.table`.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    // Every ref names its database once compiled
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let mut violations = vec![];
+        for_each_table_factor(&model_node.data.ast, |table_factor| {
+            // Table-valued functions like `generate_series(1, 10)` have args
+            let TableFactor::Table { name, args: None, .. } = table_factor else {
+                return;
+            };
+            let [database, schema, table] = name.0.as_slice() else {
+                return;
+            };
+            violations.push(
+                Violation::new(
+                    self.name(),
+                    format!(
+                        "The model reads `{}` from the `{}` database by name, which ties it to one environment. \
+                         Declare it as a source and use `{{{{ source('{}', '{}') }}}}` instead.",
+                        name, database, schema.value, table.value
+                    ),
+                    None,
+                )
+                .with_span(&table_factor.span()),
+            );
+        });
+        violations
+    }
+}
+
+// The parts of a dotted name, e.g. `db`, `raw` and `"Orders"` for
+// `db.raw."Orders"`. Dots inside quotes don't split it.
+pub fn name_parts(name: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (index, c) in name.char_indices() {
+        match (quote, c) {
+            (None, '"' | '`' | '[') => quote = Some(if c == '[' { ']' } else { c }),
+            (Some(closing), _) if c == closing => quote = None,
+            (None, '.') => {
+                parts.push(&name[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&name[start..]);
+    parts
+}
+
+pub fn unquoted(name: &str) -> &str {
+    name.trim_matches(|c| c == '"' || c == '`' || c == '[' || c == ']')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_parts() {
+        assert_eq!(name_parts("orders"), vec!["orders"]);
+        assert_eq!(name_parts("analytics.raw.orders"), vec!["analytics", "raw", "orders"]);
+        assert_eq!(name_parts("\"my.db\".raw.\"Orders\""), vec!["\"my.db\"", "raw", "\"Orders\""]);
+    }
+
+    #[test]
+    fn test_two_part_names_and_refs_pass() {
+        let model_node = ModelNode::from_test_sql("test_model", "SELECT o.id FROM raw.orders AS o JOIN {{ ref('customers') }} AS c ON o.customer_id = c.id");
+        assert_eq!(CrossDatabaseReferences.run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_three_part_names_fail() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "WITH payments AS (SELECT id FROM analytics.stripe.\"Payments\") \
+             SELECT o.id FROM analytics.raw.orders AS o JOIN payments AS p ON o.id = p.id",
+        );
+
+        let messages: Vec<String> = CrossDatabaseReferences
+            .violations(&model_node)
+            .into_iter()
+            .map(|violation| violation.message)
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The model reads `analytics.stripe.\"Payments\"` from the `analytics` database by name, which ties it to one \
+                 environment. Declare it as a source and use `{{ source('stripe', 'Payments') }}` instead."
+                    .to_string(),
+                "The model reads `analytics.raw.orders` from the `analytics` database by name, which ties it to one \
+                 environment. Declare it as a source and use `{{ source('raw', 'orders') }}` instead."
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_three_part_names_in_expression_subqueries_fail() {
+        let model_node = ModelNode::from_test_sql("test_model", "SELECT id FROM {{ ref('orders') }} WHERE id IN (SELECT order_id FROM prod.raw.refunds)");
+
+        let violations = CrossDatabaseReferences.violations(&model_node);
+
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].message.starts_with("The model reads `prod.raw.refunds` from the `prod` database"));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::rules::rules_engine::{Rule, RuleResult, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::configuration::dbtonic_config::CustomRuleConfig;
+
+// A rule declared in dbtonic.toml. It fails once for every node in the
+// model that its selector matches.
+pub struct CustomRule {
+    pub config: CustomRuleConfig,
+}
+
+impl Rule for CustomRule {
+    fn name(&self) -> String {
+        self.config.name.clone()
+    }
+
+    fn description(&self) -> String {
+        self.config.message.clone()
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        self.config
+            .selector
+            .select(&model_node.data.ast)
+            .into_iter()
+            .map(|node| {
+                Violation::new(self.name(), format!("{} Found `{}`.", self.config.message, node.text), None)
+                    .with_span(&node.span)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast_query::Selector;
+
+    fn no_datediff() -> CustomRule {
+        CustomRule {
+            config: CustomRuleConfig {
+                name: "no_datediff".to_string(),
+                selector: Selector::parse("functions[name=datediff]").unwrap(),
+                message: "Use the datediff macro so the model works on every warehouse.".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_custom_rule_passes_without_matches() {
+        let model_node = ModelNode::from_test_sql("test_model", "SELECT DATE_DIFF(b, a, DAY) AS age FROM {{ ref('orders') }}");
+        assert_eq!(no_datediff().run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_custom_rule_fails_for_each_match() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "SELECT DATEDIFF('day', a, b) AS age, datediff('day', b, c) AS other FROM {{ ref('orders') }}",
+        );
+
+        let messages: Vec<String> = no_datediff()
+            .violations(&model_node)
+            .into_iter()
+            .map(|violation| violation.message)
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Use the datediff macro so the model works on every warehouse. Found `DATEDIFF('day', a, b)`.".to_string(),
+                "Use the datediff macro so the model works on every warehouse. Found `datediff('day', b, c)`.".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use dbtranslate::ast::{Expr, Ident, SelectItem, Spanned};
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::selects::selects;
+
+// Flags select lists with two columns of the same name, whether they're
+// aliased, e.g. `a.id AS id, b.id AS id`, or take the name of the column
+// they select. Some warehouses quietly keep only one of them, so models
+// downstream read the wrong column. Names are compared case-insensitively,
+// as most warehouses do.
+pub struct DuplicateColumnAliases;
+
+impl Rule for DuplicateColumnAliases {
+    fn name(&self) -> String {
+        "duplicate_column_aliases".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Columns in a select list must have different names.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let mut violations = vec![];
+        for select in selects(&model_node.data.ast) {
+            // The first column with each name, as written
+            let mut names: HashMap<String, String> = HashMap::new();
+            for item in &select.projection {
+                let Some(name) = output_name(item) else {
+                    continue;
+                };
+                match names.get(&name.value.to_lowercase()) {
+                    Some(first) => violations.push(
+                        Violation::new(
+                            self.name(),
+                            format!(
+                                "`{}` is named `{}`, but so is `{}` earlier in the select list. Give each column its \
+                                 own name.",
+                                item, name.value, first
+                            ),
+                            None,
+                        )
+                        .with_span(&item.span()),
+                    ),
+                    None => {
+                        names.insert(name.value.to_lowercase(), item.to_string());
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+// The name the column gets in the result. Expressions without an alias,
+// other than plain columns, get a name that depends on the warehouse.
+fn output_name(item: &SelectItem) -> Option<&Ident> {
+    match item {
+        SelectItem::ExprWithAlias { alias, .. } => Some(alias),
+        SelectItem::UnnamedExpr(Expr::Identifier(ident)) => Some(ident),
+        SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => idents.last(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::tokens::Location;
+
+    #[test]
+    fn test_distinct_names_pass() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "WITH orders AS (SELECT id FROM {{ ref('orders') }}) \
+             SELECT o.id, c.id AS customer_id, SUM(o.amount) AS amount, COUNT(*) AS orders \
+             FROM orders AS o JOIN {{ ref('customers') }} AS c ON o.customer_id = c.id",
+        );
+        assert_eq!(DuplicateColumnAliases.run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_duplicate_names_fail() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "SELECT o.id, c.id, o.amount AS total, SUM(p.amount) AS Total \
+             FROM {{ ref('orders') }} AS o JOIN {{ ref('customers') }} AS c ON o.customer_id = c.id",
+        );
+
+        let violations = DuplicateColumnAliases.violations(&model_node);
+        assert_eq!(violations[0].location, Some(Location { line: 1, column: 14 }));
+        assert_eq!(violations[0].end_location, Some(Location { line: 1, column: 18 }));
+
+        let messages: Vec<String> = violations.into_iter().map(|violation| violation.message).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`c.id` is named `id`, but so is `o.id` earlier in the select list. Give each column its own name."
+                    .to_string(),
+                "`SUM(p.amount) AS Total` is named `Total`, but so is `o.amount AS total` earlier in the select list. \
+                 Give each column its own name."
+                    .to_string(),
+            ]
+        );
+    }
+}
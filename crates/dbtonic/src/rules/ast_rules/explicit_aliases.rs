@@ -0,0 +1,108 @@
+use dbtranslate::ast::{BinaryOperator, Expr, SelectItem, Spanned, UnaryOperator};
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::selects::selects;
+
+pub struct ExplicitAliases;
+
+impl Rule for ExplicitAliases {
+    fn name(&self) -> String {
+        "explicit_aliases".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Function calls, CASE expressions and arithmetic in a select list must have an explicit alias.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        selects(&model_node.data.ast)
+            .into_iter()
+            .flat_map(|select| select.projection.iter())
+            .filter_map(|item| match item {
+                SelectItem::UnnamedExpr(expr) => computed_kind(expr).map(|kind| (expr, kind)),
+                _ => None,
+            })
+            .map(|(expr, kind)| {
+                Violation::new(
+                    self.name(),
+                    format!(
+                        "The {} `{}` has no alias, so its column name depends on the warehouse. Add `AS <name>`.",
+                        kind, expr
+                    ),
+                    None,
+                )
+                .with_span(&expr.span())
+            })
+            .collect()
+    }
+}
+
+// Describes the expression if it's one that needs an alias
+fn computed_kind(expr: &Expr) -> Option<&'static str> {
+    match expr {
+        Expr::Function(_) => Some("function call"),
+        Expr::Case { .. } => Some("CASE expression"),
+        Expr::BinaryOp { op, .. } if is_arithmetic(op) => Some("arithmetic expression"),
+        Expr::UnaryOp { op: UnaryOperator::Minus, .. } => Some("arithmetic expression"),
+        Expr::Nested(inner) => computed_kind(inner),
+        _ => None,
+    }
+}
+
+fn is_arithmetic(op: &BinaryOperator) -> bool {
+    matches!(
+        op,
+        BinaryOperator::Plus
+            | BinaryOperator::Minus
+            | BinaryOperator::Multiply
+            | BinaryOperator::Divide
+            | BinaryOperator::Modulo
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::tokens::Location;
+
+    #[test]
+    fn test_aliased_expressions_pass() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "SELECT id, SUM(amount) AS total, CASE WHEN id > 1 THEN 1 END AS flag, price * 2 AS doubled FROM {{ ref('orders') }}",
+        );
+        assert_eq!(ExplicitAliases.run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_unaliased_expressions_fail() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "WITH totals AS (SELECT SUM(amount) FROM {{ ref('orders') }}) \
+             SELECT CASE WHEN id > 1 THEN 1 END, (price * 2), id FROM totals",
+        );
+
+        let violations = ExplicitAliases.violations(&model_node);
+        assert_eq!(violations[0].location, Some(Location { line: 1, column: 24 }));
+        assert_eq!(violations[0].end_location, Some(Location { line: 1, column: 34 }));
+
+        let messages: Vec<String> = violations.into_iter().map(|violation| violation.message).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The function call `SUM(amount)` has no alias, so its column name depends on the warehouse. Add `AS <name>`.".to_string(),
+                "The CASE expression `CASE WHEN id > 1 THEN 1 END` has no alias, so its column name depends on the warehouse. Add `AS <name>`.".to_string(),
+                "The arithmetic expression `(price * 2)` has no alias, so its column name depends on the warehouse. Add `AS <name>`.".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use dbtranslate::ast::{SetExpr, SetOperator, SetQuantifier};
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::selects::queries;
+
+// Flags a bare UNION, which removes duplicate rows. Models stacking
+// relations usually mean UNION ALL, and the deduplication costs a sort and
+// can hide rows that happen to be the same. UNION DISTINCT says the
+// deduplication is intended, so it passes.
+pub struct ExplicitUnion;
+
+impl Rule for ExplicitUnion {
+    fn name(&self) -> String {
+        "explicit_union".to_string()
+    }
+
+    fn description(&self) -> String {
+        "UNION must be written as UNION ALL, or as UNION DISTINCT when removing duplicate rows is intended.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let mut violations = vec![];
+        for query in queries(&model_node.data.ast) {
+            self.collect_unions(&query.body, &mut violations);
+        }
+        violations
+    }
+}
+
+impl ExplicitUnion {
+    // Parenthesized queries are left to `queries`, which visits them itself
+    fn collect_unions(&self, set_expr: &SetExpr, violations: &mut Vec<Violation>) {
+        let SetExpr::SetOperation { op, set_quantifier, left, right, op_span } = set_expr else {
+            return;
+        };
+        self.collect_unions(left, violations);
+        if *op == SetOperator::Union && *set_quantifier == SetQuantifier::None {
+            violations.push(
+                Violation::new(
+                    self.name(),
+                    "UNION removes duplicate rows, which is rarely what a model stacking relations means. Write \
+                     UNION ALL to keep every row, or UNION DISTINCT if removing duplicates is intended."
+                        .to_string(),
+                    None,
+                )
+                .with_span(&op_span.0),
+            );
+        }
+        self.collect_unions(right, violations);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::tokens::Location;
+
+    #[test]
+    fn test_explicit_unions_pass() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "SELECT id FROM {{ ref('orders') }} \
+             UNION ALL SELECT id FROM {{ ref('returns') }} \
+             UNION DISTINCT SELECT id FROM {{ ref('exchanges') }} \
+             EXCEPT SELECT id FROM {{ ref('test_orders') }}",
+        );
+        assert_eq!(ExplicitUnion.run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_bare_unions_fail() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "WITH ids AS (SELECT id FROM {{ ref('orders') }} UNION SELECT id FROM {{ ref('returns') }}) \
+             SELECT id FROM ids UNION ALL (SELECT id FROM {{ ref('a') }} UNION SELECT id FROM {{ ref('b') }})",
+        );
+
+        let violations = ExplicitUnion.violations(&model_node);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].location, Some(Location { line: 1, column: 49 }));
+        assert_eq!(violations[0].end_location, Some(Location { line: 1, column: 54 }));
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+use std::slice;
+use dbtranslate::ast::{Spanned, Statement, TableFactor, TableWithJoins};
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::selects::final_selects;
+
+// Enforces the "import CTEs + final select" style, where every ref(),
+// source() and subquery lives in a CTE and the model ends by selecting
+// from those CTEs. Anything else in the final FROM clause is flagged.
+pub struct FinalSelectFromCte;
+
+impl Rule for FinalSelectFromCte {
+    fn name(&self) -> String {
+        "final_select_from_cte".to_string()
+    }
+
+    fn description(&self) -> String {
+        "The model's final SELECT must only read from CTEs defined in the model.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let mut violations = vec![];
+        for statement in &model_node.data.ast {
+            let Statement::Query(query) = statement;
+            let ctes: HashSet<String> = query
+                .with
+                .iter()
+                .flat_map(|with| with.cte_tables.iter())
+                .map(|cte| cte.alias.name.value.to_lowercase())
+                .collect();
+
+            for select in final_selects(slice::from_ref(statement)) {
+                let mut relations = vec![];
+                for table_with_joins in &select.from {
+                    collect_relations(table_with_joins, &mut relations);
+                }
+
+                for relation in relations {
+                    if reads_cte(relation, &ctes) {
+                        continue;
+                    }
+                    violations.push(
+                        Violation::new(
+                            self.name(),
+                            format!(
+                                "The final SELECT reads from `{}`. Move it into a CTE and select from that instead.",
+                                relation
+                            ),
+                            None,
+                        )
+                        .with_span(&relation.span()),
+                    );
+                }
+            }
+        }
+        violations
+    }
+}
+
+// The relations a FROM clause reads, with parenthesized joins flattened
+fn collect_relations<'a>(table_with_joins: &'a TableWithJoins, relations: &mut Vec<&'a TableFactor>) {
+    let joined = std::iter::once(&table_with_joins.relation).chain(table_with_joins.joins.iter().map(|join| &join.relation));
+    for relation in joined {
+        match relation {
+            TableFactor::NestedJoin { table_with_joins, .. } => collect_relations(table_with_joins, relations),
+            _ => relations.push(relation),
+        }
+    }
+}
+
+fn reads_cte(relation: &TableFactor, ctes: &HashSet<String>) -> bool {
+    match relation {
+        TableFactor::Table { name, args: None, .. } => {
+            name.0.len() == 1 && ctes.contains(&name.0[0].value.to_lowercase())
+        }
+        // `LATERAL FLATTEN(input => orders.items)` only unpacks a column of
+        // the relations before it
+        TableFactor::Function { lateral: true, .. } => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(sql: &str) -> Vec<String> {
+        FinalSelectFromCte
+            .violations(&ModelNode::from_test_sql("orders_summary", sql))
+            .into_iter()
+            .map(|violation| violation.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_selecting_from_ctes_passes() {
+        let sql = "WITH orders AS (SELECT id FROM {{ ref('orders') }}), \"Payments\" AS (SELECT order_id FROM {{ ref('payments') }}) \
+                   SELECT orders.id FROM orders LEFT JOIN (\"Payments\" JOIN orders AS o2 ON TRUE) ON orders.id = \"Payments\".order_id";
+        assert!(messages(sql).is_empty());
+        assert!(messages("SELECT 1 AS one").is_empty());
+        let sql = "WITH orders AS (SELECT id, items FROM {{ ref('orders') }}) \
+                   SELECT orders.id, item.value FROM orders, LATERAL FLATTEN(input => orders.items) AS item";
+        assert!(messages(sql).is_empty());
+    }
+
+    #[test]
+    fn test_other_relations_fail() {
+        let sql = "WITH orders AS (SELECT id FROM {{ ref('orders') }}) \
+                   SELECT orders.id FROM orders JOIN {{ ref('payments') }} AS payments ON orders.id = payments.order_id \
+                   UNION ALL SELECT id FROM raw.orders";
+
+        assert_eq!(
+            messages(sql),
+            vec![
+                "The final SELECT reads from `{{ ref('payments') }} AS payments`. Move it into a CTE and select from that instead.".to_string(),
+                "The final SELECT reads from `raw.orders`. Move it into a CTE and select from that instead.".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::configuration::dbtonic_config::ForbiddenFunctionConfig;
+use crate::parser::dbt_project_yaml::DbtProjectYaml;
+use crate::rules::ast_rules::require_materialization::materialization;
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::ast_query::ast_nodes;
+
+// Flags calls to the functions listed in `[[forbidden_functions]]`, each
+// with the message configured for it. Names are compared case-insensitively
+// and in full, so `rand` doesn't match `my_schema.rand`.
+pub struct ForbiddenFunctions {
+    pub functions: Vec<ForbiddenFunctionConfig>,
+    pub project: DbtProjectYaml,
+}
+
+impl Rule for ForbiddenFunctions {
+    fn name(&self) -> String {
+        "forbidden_functions".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models must not call the functions listed in `[[forbidden_functions]]`.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let incremental = materialization(model_node, &self.project).as_deref() == Some("incremental");
+        let functions: Vec<&ForbiddenFunctionConfig> =
+            self.functions.iter().filter(|function| incremental || !function.incremental_only).collect();
+        if functions.is_empty() {
+            return vec![];
+        }
+
+        ast_nodes(&model_node.data.ast)
+            .into_iter()
+            .filter(|node| node.kind == "functions")
+            .filter_map(|node| {
+                let name = node.attribute("name")?;
+                let function = functions.iter().find(|function| function.name.eq_ignore_ascii_case(name))?;
+                let scope = if function.incremental_only { " in incremental models" } else { "" };
+                let violation = Violation::new(
+                    self.name(),
+                    format!("`{}` isn't allowed{}. {}", node.text, scope, function.message),
+                    None,
+                )
+                .with_span(&node.span);
+                Some(violation)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule() -> ForbiddenFunctions {
+        ForbiddenFunctions {
+            functions: vec![
+                ForbiddenFunctionConfig {
+                    name: "rand".to_string(),
+                    message: "Random values change the model's results every run.".to_string(),
+                    incremental_only: false,
+                },
+                ForbiddenFunctionConfig {
+                    name: "current_timestamp".to_string(),
+                    message: "Use the source's loaded_at instead, so reruns load the same rows.".to_string(),
+                    incremental_only: true,
+                },
+            ],
+            project: DbtProjectYaml::default(),
+        }
+    }
+
+    #[test]
+    fn test_other_functions_pass() {
+        let model_node = ModelNode::from_test_sql("test_model", "SELECT id, my_schema.rand() AS r, CURRENT_TIMESTAMP AS loaded_at FROM orders");
+        assert_eq!(rule().run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_forbidden_functions_fail() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "{{ config(materialized='incremental') }} \
+             SELECT id, RAND() AS sample FROM orders WHERE updated_at > CURRENT_TIMESTAMP",
+        );
+
+        let messages: Vec<String> = rule().violations(&model_node).into_iter().map(|violation| violation.message).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "`RAND()` isn't allowed. Random values change the model's results every run.".to_string(),
+                "`CURRENT_TIMESTAMP()` isn't allowed in incremental models. Use the source's loaded_at instead, so reruns \
+                 load the same rows."
+                    .to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::ast_query::ast_nodes;
+use crate::rules::ast_rules::cross_database_references::{name_parts, unquoted};
+
+// Flags tables a model reads by name, e.g. `FROM raw.orders`, instead of
+// through `{{ ref() }}` or `{{ source() }}`. dbt can't see those reads, so
+// they're missing from the lineage and don't change with the target.
+// Unqualified names that match one of the model's CTEs are fine. Names with
+// a database are left to cross_database_references when it's turned on.
+pub struct HardcodedReferences {
+    pub skip_cross_database: bool,
+}
+
+impl Rule for HardcodedReferences {
+    fn name(&self) -> String {
+        "hardcoded_references".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models must read other relations through ref() or source() rather than naming them directly.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    // Every ref is a table name once compiled
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let nodes = ast_nodes(&model_node.data.ast);
+        let ctes: HashSet<String> = nodes
+            .iter()
+            .filter(|node| node.kind == "ctes")
+            .filter_map(|node| node.attribute("name"))
+            .map(|name| name.to_lowercase())
+            .collect();
+
+        nodes
+            .iter()
+            .filter(|node| node.kind == "tables" && node.attribute("args") == Some(""))
+            .filter_map(|node| {
+                let name = node.attribute("name")?;
+                let parts = name_parts(name).len();
+                if parts == 1 && ctes.contains(&unquoted(name).to_lowercase()) {
+                    return None;
+                }
+                if parts == 3 && self.skip_cross_database {
+                    return None;
+                }
+                let violation = Violation::new(
+                    self.name(),
+                    format!(
+                        "The model reads `{}` directly, so dbt can't track it in the lineage. Use `{{{{ ref() }}}}` or `{{{{ source() }}}}` instead.",
+                        name
+                    ),
+                    None,
+                )
+                .with_span(&node.span);
+                Some(violation)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refs_sources_and_ctes_pass() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "WITH orders AS (SELECT id FROM {{ ref('orders') }}), \"Payments\" AS (SELECT id FROM {{ source('stripe', 'payments') }}) \
+             SELECT orders.id FROM orders JOIN \"Payments\" ON orders.id = \"Payments\".id CROSS JOIN generate_series(1, 3)",
+        );
+        assert_eq!(HardcodedReferences { skip_cross_database: true }.run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_tables_read_by_name_fail() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "WITH orders AS (SELECT id FROM raw.orders) \
+             SELECT id FROM orders WHERE id IN (SELECT order_id FROM refunds) \
+             AND id NOT IN (SELECT order_id FROM analytics.raw.disputes)",
+        );
+
+        let messages: Vec<String> = HardcodedReferences { skip_cross_database: true }
+            .violations(&model_node)
+            .into_iter()
+            .map(|violation| violation.message)
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The model reads `raw.orders` directly, so dbt can't track it in the lineage. Use `{{ ref() }}` or `{{ source() }}` instead.".to_string(),
+                "The model reads `refunds` directly, so dbt can't track it in the lineage. Use `{{ ref() }}` or `{{ source() }}` instead.".to_string(),
+            ]
+        );
+    }
+    #[test]
+    fn test_names_with_a_database_are_flagged_without_cross_database_references() {
+        let model_node = ModelNode::from_test_sql("test_model", "SELECT id FROM analytics.raw.orders");
+        assert_eq!(HardcodedReferences { skip_cross_database: true }.run(&model_node), RuleResult::Pass);
+        assert!(matches!(HardcodedReferences { skip_cross_database: false }.run(&model_node), RuleResult::Fail(_)));
+    }
+}
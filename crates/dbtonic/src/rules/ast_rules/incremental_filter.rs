@@ -0,0 +1,161 @@
+use dbtranslate::ast::{JinjaBlock, Statement};
+use dbtranslate::tokens::Token;
+use crate::rules::rules_engine::{Rule, RuleResult, Severity};
+use crate::parser::model_node::{ModelNode, ResourceType};
+use crate::parser::visits::for_each_jinja_block;
+
+// Flags incremental models without an `{% if is_incremental() %}` block.
+// Without one, every run selects all of the model's source rows again and
+// merges or appends them, which costs as much as a full refresh or
+// duplicates rows.
+pub struct IncrementalFilter;
+
+impl Rule for IncrementalFilter {
+    fn name(&self) -> String {
+        "incremental_filter".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Incremental models must filter the rows they select in an `{% if is_incremental() %}` block.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    // Compiling evaluates the `{% if %}`
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    fn applies_to(&self, resource_type: ResourceType) -> bool {
+        resource_type == ResourceType::Model
+    }
+
+    fn applies_to_materialization(&self, materialized: &str) -> bool {
+        materialized == "incremental"
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        match checks_is_incremental(&model_node.data.ast, true) {
+            true => RuleResult::Pass,
+            false => RuleResult::Fail(
+                "The incremental model has no `{% if is_incremental() %}` block filtering its rows, so every run \
+                 processes all of them again."
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+// Flags `is_incremental()` in models that aren't incremental, where it's
+// always false. The filter never runs, which usually means the model was
+// meant to be incremental or the block was copied from one.
+pub struct UnusedIncrementalFilter;
+
+impl Rule for UnusedIncrementalFilter {
+    fn name(&self) -> String {
+        "unused_incremental_filter".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Only incremental models should check `is_incremental()`, since it's always false in the others.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    fn applies_to(&self, resource_type: ResourceType) -> bool {
+        resource_type == ResourceType::Model
+    }
+
+    fn applies_to_materialization(&self, materialized: &str) -> bool {
+        materialized != "incremental"
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        match checks_is_incremental(&model_node.data.ast, false) {
+            true => RuleResult::Fail(
+                "The model checks `is_incremental()`, which is always false since the model isn't incremental."
+                    .to_string(),
+            ),
+            false => RuleResult::Pass,
+        }
+    }
+}
+
+// Whether an `{% if %}` or `{% elif %}` in the statements calls
+// `is_incremental()`. With `guard`, only branches that run on incremental
+// runs and have SQL in them count, so `{% if not is_incremental() %}`
+// doesn't.
+fn checks_is_incremental(statements: &[Statement], guard: bool) -> bool {
+    let mut found = false;
+    for_each_jinja_block(statements, |block| {
+        let JinjaBlock::If(conditional) = block else {
+            return;
+        };
+        for branch in &conditional.branches {
+            let Some(condition) = &branch.condition else {
+                continue;
+            };
+            let words: Vec<&str> = condition
+                .iter()
+                .filter_map(|token| match token {
+                    Token::Word(word) => Some(word.value.as_ref()),
+                    _ => None,
+                })
+                .collect();
+            if !words.contains(&"is_incremental") {
+                continue;
+            }
+            let negated = words.iter().any(|word| word.eq_ignore_ascii_case("not"));
+            let has_sql = branch.body.iter().any(|token| !matches!(token, Token::Whitespace(_)));
+            found |= !guard || (!negated && has_sql);
+        }
+    });
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guarded_filters_pass() {
+        for sql in [
+            "SELECT * FROM {{ ref('events') }} {% if is_incremental() %}WHERE id > (SELECT MAX(id) FROM {{ this }}){% endif %}",
+            "SELECT * FROM {{ ref('events') }} WHERE kind = 'click' {% if is_incremental() %} AND id > 1 {% endif %}",
+            "WITH e AS (SELECT * FROM {{ ref('events') }} {% if is_incremental() %}WHERE id > 1{% endif %}) SELECT * FROM e",
+        ] {
+            assert_eq!(IncrementalFilter.run(&ModelNode::from_test_sql("orders", sql)), RuleResult::Pass, "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_missing_filter_fails() {
+        for sql in [
+            "SELECT * FROM {{ ref('events') }}",
+            "SELECT * FROM {{ ref('events') }} {% if not is_incremental() %}WHERE id > 1{% endif %}",
+            "SELECT * FROM {{ ref('events') }} {% if target.name == 'dev' %}WHERE id > 1{% endif %}",
+        ] {
+            assert!(matches!(IncrementalFilter.run(&ModelNode::from_test_sql("orders", sql)), RuleResult::Fail(_)), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_unused_incremental_filter() {
+        let guarded = ModelNode::from_test_sql("orders", "SELECT * FROM {{ ref('events') }} {% if is_incremental() %}WHERE id > 1{% endif %}");
+        assert_eq!(
+            UnusedIncrementalFilter.run(&guarded),
+            RuleResult::Fail(
+                "The model checks `is_incremental()`, which is always false since the model isn't incremental.".to_string()
+            )
+        );
+        assert_eq!(UnusedIncrementalFilter.run(&ModelNode::from_test_sql("orders", "SELECT * FROM {{ ref('events') }}")), RuleResult::Pass);
+    }
+}
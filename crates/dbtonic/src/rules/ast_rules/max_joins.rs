@@ -0,0 +1,80 @@
+use crate::configuration::dbtonic_config::JoinsConfig;
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::ast_query::ast_nodes;
+
+// Flags models that join more relations than the rule's `threshold` allows.
+// Every JOIN in the model counts, including those in CTEs and subqueries,
+// since a wide model is hard to follow wherever its joins are.
+pub struct MaxJoins {
+    pub config: JoinsConfig,
+}
+
+impl Rule for MaxJoins {
+    fn name(&self) -> String {
+        "max_joins".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models must not join more relations than the configured maximum.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    // A single violation for the model, pointing at the first join over
+    // the limit
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let joins: Vec<_> = ast_nodes(&model_node.data.ast)
+            .into_iter()
+            .filter(|node| node.kind == "joins")
+            .collect();
+
+        match joins.get(self.config.threshold) {
+            Some(first_extra_join) => vec![Violation::new(
+                self.name(),
+                format!(
+                    "The model has {} joins, more than the {} allowed. Split it into intermediate models.",
+                    joins.len(),
+                    self.config.threshold
+                ),
+                None,
+            )
+            .with_span(&first_extra_join.span)],
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::tokens::Location;
+
+    const SQL: &str = "WITH orders AS (SELECT o.id FROM {{ ref('orders') }} AS o JOIN {{ ref('customers') }} AS c ON o.customer_id = c.id)\n\
+                       SELECT orders.id FROM orders LEFT JOIN payments ON orders.id = payments.order_id CROSS JOIN dates";
+
+    #[test]
+    fn test_joins_within_the_limit_pass() {
+        let rule = MaxJoins { config: JoinsConfig { threshold: 3 } };
+        assert_eq!(rule.run(&ModelNode::from_test_sql("orders_summary", SQL)), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_joins_over_the_limit_fail() {
+        let rule = MaxJoins { config: JoinsConfig { threshold: 1 } };
+
+        let violations = rule.violations(&ModelNode::from_test_sql("orders_summary", SQL));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            violations[0].message,
+            "The model has 3 joins, more than the 1 allowed. Split it into intermediate models."
+        );
+        assert_eq!(violations[0].location, Some(Location { line: 2, column: 40 }));
+    }
+}
@@ -1,3 +1,22 @@
 pub mod contains_source_and_ref;
 pub mod contains_multiple_sources;
-pub mod contains_no_source_or_ref;
\ No newline at end of file
+pub mod contains_no_source_or_ref;
+pub mod multiple_statements;
+pub mod explicit_aliases;
+pub mod custom_rule;
+pub mod no_env_var;
+pub mod no_select_star;
+pub mod hardcoded_references;
+pub mod require_materialization;
+pub mod incremental_filter;
+pub mod final_select_from_cte;
+pub mod max_joins;
+pub mod no_order_by;
+pub mod model_complexity;
+pub mod cross_database_references;
+pub mod ambiguous_columns;
+pub mod no_implicit_cross_joins;
+pub mod forbidden_functions;
+pub mod duplicate_column_aliases;
+pub mod no_select_distinct;
+pub mod explicit_union;
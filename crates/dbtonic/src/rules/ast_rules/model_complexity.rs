@@ -0,0 +1,131 @@
+use dbtranslate::ast::Statement;
+use dbtranslate::ast::helpers::max_subquery_depth;
+use crate::configuration::dbtonic_config::ComplexityConfig;
+use crate::rules::rules_engine::{Rule, RuleResult, Severity};
+use crate::parser::model_node::ModelNode;
+use crate::parser::ast_query::ast_nodes;
+
+// What makes a model hard to follow, counted over its whole query,
+// including CTEs and subqueries
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Complexity {
+    pub joins: usize,
+    pub ctes: usize,
+    pub window_functions: usize,
+    // How many subqueries deep the model goes, 0 without any
+    pub subquery_depth: usize,
+    pub case_statements: usize,
+}
+
+impl Complexity {
+    pub fn of(statements: &[Statement]) -> Self {
+        let nodes = ast_nodes(statements);
+        let count = |kind: &str| nodes.iter().filter(|node| node.kind == kind).count();
+        Complexity {
+            joins: count("joins"),
+            ctes: count("ctes"),
+            window_functions: nodes
+                .iter()
+                .filter(|node| node.kind == "functions" && node.attribute("window") == Some("true"))
+                .count(),
+            subquery_depth: statements
+                .iter()
+                .map(|statement| {
+                    let Statement::Query(query) = statement;
+                    max_subquery_depth(query)
+                })
+                .max()
+                .unwrap_or(0),
+            case_statements: count("cases"),
+        }
+    }
+
+    // Each count times its weight
+    pub fn score(&self, config: &ComplexityConfig) -> usize {
+        self.joins * config.join_weight
+            + self.ctes * config.cte_weight
+            + self.window_functions * config.window_function_weight
+            + self.subquery_depth * config.subquery_weight
+            + self.case_statements * config.case_weight
+    }
+}
+
+// Flags models whose complexity score is over the threshold. No single
+// thing is wrong with them, so the message lists what adds up.
+pub struct ModelComplexity {
+    pub config: ComplexityConfig,
+}
+
+impl Rule for ModelComplexity {
+    fn name(&self) -> String {
+        "model_complexity".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models must not score higher than the configured complexity threshold.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        let complexity = Complexity::of(&model_node.data.ast);
+        let score = complexity.score(&self.config);
+        if score <= self.config.threshold {
+            return RuleResult::Pass;
+        }
+        RuleResult::Fail(format!(
+            "The model's complexity is {}, more than the {} allowed ({} join(s), {} CTE(s), {} window function(s), \
+             subqueries {} deep, {} CASE expression(s)). Split it into intermediate models.",
+            score,
+            self.config.threshold,
+            complexity.joins,
+            complexity.ctes,
+            complexity.window_functions,
+            complexity.subquery_depth,
+            complexity.case_statements
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQL: &str = "WITH orders AS (SELECT * FROM {{ ref('orders') }}), \
+                       ranked AS (SELECT *, ROW_NUMBER() OVER (PARTITION BY customer_id ORDER BY created_at) AS n FROM orders) \
+                       SELECT r.id, CASE WHEN r.n = 1 THEN 'first' ELSE 'repeat' END AS kind \
+                       FROM ranked AS r JOIN {{ ref('customers') }} AS c ON r.customer_id = c.id \
+                       WHERE r.id IN (SELECT order_id FROM {{ ref('payments') }})";
+
+    #[test]
+    fn test_complexity() {
+        let complexity = Complexity::of(&ModelNode::from_test_sql("orders_summary", SQL).data.ast);
+        assert_eq!(
+            complexity,
+            Complexity { joins: 1, ctes: 2, window_functions: 1, subquery_depth: 1, case_statements: 1 }
+        );
+        // 1 * 2 + 2 * 1 + 1 * 2 + 1 * 3 + 1 * 1
+        assert_eq!(complexity.score(&ComplexityConfig::default()), 10);
+    }
+
+    #[test]
+    fn test_complexity_within_the_threshold_passes() {
+        let rule = ModelComplexity { config: ComplexityConfig { threshold: 10, ..Default::default() } };
+        assert_eq!(rule.run(&ModelNode::from_test_sql("orders_summary", SQL)), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_complexity_over_the_threshold_fails() {
+        let config = ComplexityConfig { threshold: 10, join_weight: 5, ..Default::default() };
+        assert_eq!(
+            ModelComplexity { config }.run(&ModelNode::from_test_sql("orders_summary", SQL)),
+            RuleResult::Fail(
+                "The model's complexity is 13, more than the 10 allowed (1 join(s), 2 CTE(s), 1 window function(s), \
+                 subqueries 1 deep, 1 CASE expression(s)). Split it into intermediate models."
+                    .to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use dbtranslate::ast::Spanned;
+use crate::rules::rules_engine::{Rule, RuleResult, Violation};
+use crate::parser::model_node::ModelNode;
+
+// Flags every statement after a model's first. dbt wraps the model's SELECT
+// in its own statement, so the extra ones fail at runtime.
+pub struct MultipleStatements;
+
+impl Rule for MultipleStatements {
+    fn name(&self) -> String {
+        "multiple_statements".to_string()
+    }
+
+    fn description(&self) -> String {
+        "The model must contain exactly one statement, since dbt wraps the model's SELECT in its own statement.".to_string()
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let statement_count = model_node.data.ast.len();
+        model_node
+            .data
+            .ast
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(index, statement)| {
+                let message = format!(
+                    "The model contains {} statements, and this is statement {}. dbt expects exactly one SELECT per model.",
+                    statement_count,
+                    index + 1
+                );
+                Violation::new(self.name(), message, None).with_span(&statement.span())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::tokens::Location;
+
+    #[test]
+    fn test_single_statement_passes() {
+        assert_eq!(MultipleStatements.run(&ModelNode::from_test_sql("test_model", "SELECT * FROM {{ ref('orders') }};")), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_every_extra_statement_fails() {
+        let violations = MultipleStatements.violations(&ModelNode::from_test_sql("test_model", "SELECT 1;\nSELECT id FROM orders;\nselect 2 as b"));
+        let messages: Vec<&str> = violations.iter().map(|violation| violation.message.as_str()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The model contains 3 statements, and this is statement 2. dbt expects exactly one SELECT per model.",
+                "The model contains 3 statements, and this is statement 3. dbt expects exactly one SELECT per model.",
+            ]
+        );
+        assert_eq!(violations[0].location, Some(Location { line: 2, column: 1 }));
+        assert_eq!(violations[1].location, Some(Location { line: 3, column: 1 }));
+    }
+}
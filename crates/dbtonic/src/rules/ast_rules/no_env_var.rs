@@ -0,0 +1,81 @@
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::ast_query::ast_nodes;
+
+pub struct NoEnvVar;
+
+impl Rule for NoEnvVar {
+    fn name(&self) -> String {
+        "no_env_var".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models should not read environment variables with env_var(). Set them once in dbt_project.yml or profiles.yml and pass them in with var().".to_string()
+    }
+
+    // `env_var()` has been rendered away once compiled
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        ast_nodes(&model_node.data.ast)
+            .into_iter()
+            .filter(|node| node.kind == "env_vars")
+            .map(|node| {
+                Violation::new(
+                    self.name(),
+                    format!(
+                        "The model reads the environment variable '{}' with `{}`. Set it in dbt_project.yml or profiles.yml and use var() instead.",
+                        node.attribute("name").unwrap_or_default(),
+                        node.text
+                    ),
+                    None,
+                )
+                .with_span(&node.span)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vars_pass() {
+        let model_node = ModelNode::from_test_sql("test_model", "SELECT id FROM {{ ref('orders') }} WHERE created_at >= {{ var('start_date') }}");
+        assert_eq!(NoEnvVar.run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_env_vars_fail() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "SELECT id, {{ env_var('DBT_REGION', 'eu') }} AS region FROM {{ ref('orders') }} \
+             WHERE tenant = {{ env_var('DBT_TENANT') }}",
+        );
+
+        let messages: Vec<String> = NoEnvVar
+            .violations(&model_node)
+            .into_iter()
+            .map(|violation| violation.message)
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The model reads the environment variable 'DBT_REGION' with `{{ env_var('DBT_REGION', 'eu') }}`. Set it in dbt_project.yml or profiles.yml and use var() instead.".to_string(),
+                "The model reads the environment variable 'DBT_TENANT' with `{{ env_var('DBT_TENANT') }}`. Set it in dbt_project.yml or profiles.yml and use var() instead.".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use dbtranslate::ast::{JoinConstraint, JoinOperator, Spanned, TableFactor, TableWithJoins};
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::selects::selects;
+use crate::parser::ast_query::relation_name;
+
+// Flags joins that pair every row with every row without saying so: a JOIN
+// with no ON or USING, or a relation listed after a comma in FROM. Written
+// as CROSS JOIN, a cartesian product is fine. Lateral relations and
+// UNNEST after a comma are fine too, since they read from the rows before
+// them.
+pub struct NoImplicitCrossJoins;
+
+impl Rule for NoImplicitCrossJoins {
+    fn name(&self) -> String {
+        "no_implicit_cross_joins".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Joins must have an ON or USING condition, or be written as CROSS JOIN.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let mut violations = vec![];
+        for select in selects(&model_node.data.ast) {
+            for (index, table_with_joins) in select.from.iter().enumerate() {
+                if index > 0 && !is_lateral(&table_with_joins.relation) {
+                    let name = relation_name(&table_with_joins.relation);
+                    violations.push(
+                        Violation::new(
+                            self.name(),
+                            format!(
+                                "`{}` follows a comma in FROM, which cross joins it with the relations before it. \
+                                 Join it with JOIN ... ON, or write CROSS JOIN if every combination of rows is intended.",
+                                name
+                            ),
+                            None,
+                        )
+                        .with_span(&table_with_joins.relation.span()),
+                    );
+                }
+                self.collect_joins(table_with_joins, &mut violations);
+            }
+        }
+        violations
+    }
+}
+
+impl NoImplicitCrossJoins {
+    fn collect_joins(&self, table_with_joins: &TableWithJoins, violations: &mut Vec<Violation>) {
+        let relations = std::iter::once(&table_with_joins.relation)
+            .chain(table_with_joins.joins.iter().map(|join| &join.relation));
+        for relation in relations {
+            if let TableFactor::NestedJoin { table_with_joins, .. } = relation {
+                self.collect_joins(table_with_joins, violations);
+            }
+        }
+
+        for join in &table_with_joins.joins {
+            let constraint = match &join.join_operator {
+                JoinOperator::Inner(constraint)
+                | JoinOperator::LeftOuter(constraint)
+                | JoinOperator::RightOuter(constraint)
+                | JoinOperator::FullOuter(constraint)
+                | JoinOperator::LeftSemi(constraint)
+                | JoinOperator::RightSemi(constraint)
+                | JoinOperator::LeftAnti(constraint)
+                | JoinOperator::RightAnti(constraint) => constraint,
+                _ => continue,
+            };
+            if *constraint == JoinConstraint::None && !is_lateral(&join.relation) {
+                violations.push(
+                    Violation::new(
+                        self.name(),
+                        format!(
+                            "The join to `{}` has no ON or USING condition, so it pairs every row with every row \
+                             before it. Add a condition, or write CROSS JOIN if that's intended.",
+                            relation_name(&join.relation)
+                        ),
+                        None,
+                    )
+                    .with_span(&join.span()),
+                );
+            }
+        }
+    }
+}
+
+fn is_lateral(relation: &TableFactor) -> bool {
+    matches!(
+        relation,
+        TableFactor::Derived { lateral: true, .. }
+            | TableFactor::Function { lateral: true, .. }
+            | TableFactor::UNNEST { .. }
+            | TableFactor::TableFunction { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_joins_pass() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "SELECT o.id FROM {{ ref('orders') }} AS o \
+             JOIN {{ ref('customers') }} AS c ON o.customer_id = c.id \
+             LEFT JOIN {{ ref('payments') }} AS p USING (order_id) \
+             CROSS JOIN {{ ref('dates') }} AS d, \
+             UNNEST(o.items) AS item",
+        );
+        assert_eq!(NoImplicitCrossJoins.run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_implicit_cross_joins_fail() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "WITH paid AS (\
+             SELECT o.id FROM {{ ref('orders') }} AS o, {{ source('stripe', 'payments') }} AS p WHERE o.id = p.order_id\
+             ) \
+             SELECT paid.id FROM paid JOIN {{ ref('customers') }} AS c",
+        );
+
+        let messages: Vec<String> = NoImplicitCrossJoins
+            .violations(&model_node)
+            .into_iter()
+            .map(|violation| violation.message)
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "`stripe.payments` follows a comma in FROM, which cross joins it with the relations before it. \
+                 Join it with JOIN ... ON, or write CROSS JOIN if every combination of rows is intended."
+                    .to_string(),
+                "The join to `customers` has no ON or USING condition, so it pairs every row with every row \
+                 before it. Add a condition, or write CROSS JOIN if that's intended."
+                    .to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use dbtranslate::ast::{Spanned, Statement};
+use crate::configuration::dbtonic_config::OrderByConfig;
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::{ModelNode, ResourceType};
+
+// Flags an ORDER BY on a model's outermost query. Warehouses don't keep the
+// order of a table or view, so sorting a model that others select from is
+// wasted compute. Models nothing refs are left alone, since they're read
+// by people and BI tools. So is an ORDER BY with a LIMIT, which picks rows
+// rather than sorting them, and the models in the rule's `allow` list.
+pub struct NoOrderBy {
+    pub config: OrderByConfig,
+}
+
+impl Rule for NoOrderBy {
+    fn name(&self) -> String {
+        "no_order_by".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models must not sort their results with a top-level ORDER BY.".to_string()
+    }
+
+    // Analyses are read by people, so sorting them is fine
+    fn applies_to(&self, resource_type: ResourceType) -> bool {
+        resource_type == ResourceType::Model
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn applies_to_leaf_models(&self) -> bool {
+        false
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        if self.config.allow.contains(&model_node.model_name) {
+            return vec![];
+        }
+
+        model_node
+            .data
+            .ast
+            .iter()
+            .filter_map(|statement| {
+                let Statement::Query(query) = statement;
+                if query.order_by.is_empty() || query.limit.is_some() {
+                    return None;
+                }
+                let order_by: Vec<String> = query.order_by.iter().map(|expr| expr.to_string()).collect();
+                let violation = Violation::new(
+                    self.name(),
+                    format!(
+                        "`ORDER BY {}` sorts the whole model, but the warehouse won't keep that order. Remove it, or sort where the model is queried.",
+                        order_by.join(", ")
+                    ),
+                    None,
+                )
+                .with_span(&query.order_by.span());
+                Some(violation)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_by_in_ctes_windows_and_with_limit_passes() {
+        let rule = NoOrderBy { config: OrderByConfig::default() };
+        let sql = "WITH latest AS (SELECT id FROM {{ ref('orders') }} ORDER BY created_at) \
+                   SELECT id, ROW_NUMBER() OVER (ORDER BY id) AS n FROM latest";
+
+        assert_eq!(rule.run(&ModelNode::from_test_sql("orders", sql)), RuleResult::Pass);
+        assert_eq!(rule.run(&ModelNode::from_test_sql("orders", "SELECT id FROM latest ORDER BY id LIMIT 10")), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_top_level_order_by_fails_unless_allowed() {
+        let sql = "SELECT id, created_at FROM {{ ref('orders') }} ORDER BY created_at DESC, id";
+
+        assert_eq!(
+            NoOrderBy { config: OrderByConfig::default() }.run(&ModelNode::from_test_sql("orders", sql)),
+            RuleResult::Fail(
+                "`ORDER BY created_at DESC, id` sorts the whole model, but the warehouse won't keep that order. Remove it, or sort where the model is queried.".to_string()
+            )
+        );
+
+        let rule = NoOrderBy { config: OrderByConfig { allow: vec!["orders".to_string()] } };
+        assert_eq!(rule.run(&ModelNode::from_test_sql("orders", sql)), RuleResult::Pass);
+    }
+}
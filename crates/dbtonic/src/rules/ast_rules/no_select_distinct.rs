@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use dbtranslate::ast::Spanned;
+use glob::Pattern;
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::selects::final_selects;
+
+// Flags `SELECT DISTINCT` in the final SELECT of marts models, the ones
+// matching the `marts` globs in `[rules.layered_architecture]`. DISTINCT
+// there usually papers over a join that fans out, which is better fixed
+// where it happens. CTEs and subqueries can still use it.
+pub struct NoSelectDistinct {
+    pub marts: Vec<Pattern>,
+    pub base_path: PathBuf,
+}
+
+impl Rule for NoSelectDistinct {
+    fn name(&self) -> String {
+        "no_select_distinct".to_string()
+    }
+
+    fn description(&self) -> String {
+        "The final SELECT of a marts model must not use DISTINCT.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let relative_path = model_node.path.strip_prefix(&self.base_path).unwrap_or(&model_node.path);
+        if !self.marts.iter().any(|pattern| pattern.matches_path(relative_path)) {
+            return vec![];
+        }
+
+        final_selects(&model_node.data.ast)
+            .into_iter()
+            .filter(|select| select.distinct)
+            .map(|select| {
+                Violation::new(
+                    self.name(),
+                    "The model's final SELECT uses DISTINCT, which hides duplicate rows rather than fixing them. \
+                     Find the join or source that duplicates them and fix the grain there."
+                        .to_string(),
+                    None,
+                )
+                .with_span(&select.projection.span())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::dbtonic_config::LayersConfig;
+
+    fn model_node(path: &str, sql: &str) -> ModelNode {
+        let mut model_node = ModelNode::from_test_sql("test_model", sql);
+        model_node.path = PathBuf::from(path);
+        model_node
+    }
+
+    fn rule() -> NoSelectDistinct {
+        NoSelectDistinct { marts: LayersConfig::default().marts, base_path: PathBuf::from("/project") }
+    }
+
+    #[test]
+    fn test_distinct_outside_the_final_select_or_marts_passes() {
+        let in_cte = model_node(
+            "/project/models/marts/customers.sql",
+            "WITH ids AS (SELECT DISTINCT customer_id FROM {{ ref('orders') }}) SELECT customer_id FROM ids",
+        );
+        assert_eq!(rule().run(&in_cte), RuleResult::Pass);
+
+        let in_staging = model_node(
+            "/project/models/staging/stg_customers.sql",
+            "SELECT DISTINCT id FROM {{ source('shop', 'customers') }}",
+        );
+        assert_eq!(rule().run(&in_staging), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_distinct_in_the_final_select_of_marts_fails() {
+        let model_node = model_node(
+            "/project/models/marts/customers.sql",
+            "SELECT DISTINCT c.id FROM {{ ref('customers') }} AS c JOIN {{ ref('orders') }} AS o ON c.id = o.customer_id",
+        );
+        assert_eq!(
+            rule().run(&model_node),
+            RuleResult::Fail(
+                "The model's final SELECT uses DISTINCT, which hides duplicate rows rather than fixing them. \
+                 Find the join or source that duplicates them and fix the grain there."
+                    .to_string()
+            )
+        );
+    }
+}
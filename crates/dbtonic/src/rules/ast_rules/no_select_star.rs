@@ -0,0 +1,109 @@
+use dbtranslate::ast::{SelectItem, Spanned};
+use crate::configuration::dbtonic_config::SelectStarConfig;
+use crate::rules::rules_engine::{Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+use crate::parser::selects::{final_selects, selects};
+
+// Flags `SELECT *` and `SELECT alias.*`, which make a model's columns change
+// whenever its parents' do. The rule's block in dbtonic.toml decides whether
+// CTEs and staging models may still use them.
+pub struct NoSelectStar {
+    pub config: SelectStarConfig,
+}
+
+impl Rule for NoSelectStar {
+    fn name(&self) -> String {
+        "no_select_star".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models must list the columns they select instead of using SELECT *.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        if self.config.allow_in_staging && model_node.is_staging() {
+            return vec![];
+        }
+
+        let selects = if self.config.allow_in_ctes {
+            final_selects(&model_node.data.ast)
+        } else {
+            selects(&model_node.data.ast)
+        };
+
+        selects
+            .into_iter()
+            .flat_map(|select| select.projection.iter())
+            .filter(|item| matches!(item, SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..)))
+            .map(|item| {
+                Violation::new(
+                    self.name(),
+                    format!(
+                        "`SELECT {}` selects every column, so the model's columns change whenever its parents' do. List the columns instead.",
+                        item
+                    ),
+                    None,
+                )
+                .with_span(&item.span())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use dbtranslate::tokens::Location;
+
+    const SQL: &str = "WITH orders AS (SELECT * FROM {{ ref('orders') }})\nSELECT o.*, 1 AS one FROM orders AS o";
+
+    fn model_node(model_name: &str, path: &str) -> ModelNode {
+        let mut model_node = ModelNode::from_test_sql(model_name, SQL);
+        model_node.path = PathBuf::from(path);
+        model_node
+    }
+
+    fn messages(rule: &NoSelectStar, model_node: &ModelNode) -> Vec<String> {
+        rule.violations(model_node).into_iter().map(|violation| violation.message).collect()
+    }
+
+    #[test]
+    fn test_only_the_final_select_is_checked_by_default() {
+        let rule = NoSelectStar { config: SelectStarConfig::default() };
+        let model_node = model_node("orders_summary", "models/marts/orders_summary.sql");
+
+        let violations = rule.violations(&model_node);
+        assert_eq!(
+            violations.iter().map(|violation| violation.message.as_str()).collect::<Vec<&str>>(),
+            vec!["`SELECT o.*` selects every column, so the model's columns change whenever its parents' do. List the columns instead."]
+        );
+        assert_eq!(violations[0].location, Some(Location { line: 2, column: 8 }));
+        assert_eq!(violations[0].end_location, Some(Location { line: 2, column: 11 }));
+    }
+
+    #[test]
+    fn test_ctes_are_checked_when_not_allowed() {
+        let rule = NoSelectStar { config: SelectStarConfig { allow_in_ctes: false, allow_in_staging: false } };
+        let model_node = model_node("orders_summary", "models/marts/orders_summary.sql");
+
+        assert_eq!(messages(&rule, &model_node).len(), 2);
+    }
+
+    #[test]
+    fn test_staging_models_can_be_allowed() {
+        let rule = NoSelectStar { config: SelectStarConfig { allow_in_ctes: false, allow_in_staging: true } };
+
+        assert!(messages(&rule, &model_node("stg_orders", "models/stg_orders.sql")).is_empty());
+        assert!(messages(&rule, &model_node("orders", "models/staging/orders.sql")).is_empty());
+        assert_eq!(messages(&rule, &model_node("orders_summary", "models/marts/orders_summary.sql")).len(), 2);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::configuration::dbtonic_config::MaterializationConfig;
+use crate::parser::dbt_project_yaml::DbtProjectYaml;
+use crate::rules::rules_engine::{Rule, RuleResult, Severity};
+use crate::parser::model_node::{ModelNode, ResourceType};
+
+// Flags models that don't say how they're materialized, which leaves them as
+// dbt's default view whatever folder they're in. A `{{ config() }}` block or
+// the model's yml can set it, and so can the model's folder in
+// dbt_project.yml unless the rule's `allow_inherited` is turned off.
+pub struct RequireMaterialization {
+    pub config: MaterializationConfig,
+    pub project: DbtProjectYaml,
+}
+
+impl Rule for RequireMaterialization {
+    fn name(&self) -> String {
+        "require_materialization".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models must set `materialized` in their config, or inherit it from their folder in dbt_project.yml.".to_string()
+    }
+
+    // The `{{ config() }}` block is gone once compiled
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    // Singular tests and analyses aren't materialized
+    fn applies_to(&self, resource_type: ResourceType) -> bool {
+        resource_type == ResourceType::Model
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        if config_block_materialization(model_node).is_some() || yaml_materialization(model_node).is_some() {
+            return RuleResult::Pass;
+        }
+
+        if !self.config.allow_inherited {
+            return RuleResult::Fail(
+                "The model doesn't set `materialized` in a `{{ config() }}` block or its yml.".to_string()
+            );
+        }
+
+        match self.project.model_config(&model_node.path, "materialized") {
+            Some(_) => RuleResult::Pass,
+            None => RuleResult::Fail(
+                "The model doesn't set `materialized` in a `{{ config() }}` block, its yml or its folder in dbt_project.yml, so dbt builds it as a view.".to_string()
+            ),
+        }
+    }
+}
+
+// How the model is materialized, if it says so, dbt's manifest does or its
+// folder in dbt_project.yml does
+pub fn materialization(model_node: &ModelNode, project: &DbtProjectYaml) -> Option<String> {
+    config_block_materialization(model_node)
+        .or_else(|| yaml_materialization(model_node))
+        .map(|materialized| materialized.to_string())
+        .or_else(|| model_node.data.manifest.as_ref()?.materialized.clone())
+        .or_else(|| project.model_config(&model_node.path, "materialized"))
+}
+
+fn config_block_materialization(model_node: &ModelNode) -> Option<&str> {
+    model_node.config_value("materialized")?.as_str()
+}
+
+fn yaml_materialization(model_node: &ModelNode) -> Option<&str> {
+    model_node.data.yaml.as_ref()?.config.as_ref()?.materialized.as_deref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const PROJECT_YAML: &str = "name: jaffle_shop\nmodels:\n  jaffle_shop:\n    marts:\n      +materialized: table\n";
+
+    fn model_node(path: &str, sql: &str) -> ModelNode {
+        let mut model_node = ModelNode::from_test_sql("orders", sql);
+        model_node.path = PathBuf::from(path);
+        model_node
+    }
+
+    fn rule(allow_inherited: bool) -> RequireMaterialization {
+        RequireMaterialization {
+            config: MaterializationConfig { allow_inherited },
+            project: serde_yaml::from_str(PROJECT_YAML).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_config_block_passes() {
+        let model_node = model_node(
+            "/project/models/staging/orders.sql",
+            "{{ config(materialized='incremental') }} SELECT id FROM {{ ref('raw_orders') }}",
+        );
+
+        assert_eq!(rule(false).run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_folder_config_is_inherited() {
+        let model_node = model_node("/project/models/marts/orders.sql", "SELECT id FROM {{ ref('stg_orders') }}");
+
+        assert_eq!(rule(true).run(&model_node), RuleResult::Pass);
+        assert_eq!(
+            rule(false).run(&model_node),
+            RuleResult::Fail("The model doesn't set `materialized` in a `{{ config() }}` block or its yml.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_missing_materialization_fails() {
+        let model_node = model_node("/project/models/staging/orders.sql", "SELECT id FROM {{ ref('raw_orders') }}");
+
+        assert_eq!(
+            rule(true).run(&model_node),
+            RuleResult::Fail(
+                "The model doesn't set `materialized` in a `{{ config() }}` block, its yml or its folder in dbt_project.yml, so dbt builds it as a view.".to_string()
+            )
+        );
+    }
+}
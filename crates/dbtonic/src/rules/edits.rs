@@ -0,0 +1,219 @@
+use dbtranslate::tokens::Location;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::ser::SerializeStruct;
+use crate::rules::rules_engine::Violation;
+
+// A change to a model's SQL that fixes a violation: replace the text from
+// `start` up to (but not including) `end` with `replacement`. Edits are
+// included in the json output so editors can offer them as quick fixes.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TextEdit {
+    #[serde(serialize_with = "serialize_position", deserialize_with = "deserialize_position")]
+    pub start: Location,
+    #[serde(serialize_with = "serialize_position", deserialize_with = "deserialize_position")]
+    pub end: Location,
+    pub replacement: String,
+}
+
+// dbtranslate's Location isn't serializable, so write it out as
+// `{"line": .., "column": ..}` ourselves
+pub(crate) fn serialize_position<S: Serializer>(location: &Location, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct("Location", 2)?;
+    state.serialize_field("line", &location.line)?;
+    state.serialize_field("column", &location.column)?;
+    state.end()
+}
+
+#[derive(Deserialize)]
+struct Position {
+    line: u64,
+    column: u64,
+}
+
+// Reads back what `serialize_position` wrote, for the cache
+pub(crate) fn deserialize_position<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Location, D::Error> {
+    let position = Position::deserialize(deserializer)?;
+    Ok(Location { line: position.line, column: position.column })
+}
+
+// The edits that fix the violations
+pub fn fix_edits(violations: Vec<Violation>) -> Vec<TextEdit> {
+    violations.into_iter().flat_map(|violation| violation.edits).collect()
+}
+
+// Applies `edits` to `sql`. Edits must not overlap; ones that point outside
+// of `sql` are ignored.
+pub fn apply_edits(sql: &str, edits: &[TextEdit]) -> String {
+    let mut offsets: Vec<(usize, usize, &str)> = edits
+        .iter()
+        .filter_map(|edit| {
+            let start = byte_offset(sql, &edit.start)?;
+            let end = byte_offset(sql, &edit.end)?;
+            Some((start, end, edit.replacement.as_str()))
+        })
+        .collect();
+
+    // Apply from the end of the file so earlier offsets stay valid
+    offsets.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+
+    let mut fixed = sql.to_string();
+    for (start, end, replacement) in offsets {
+        fixed.replace_range(start..end, replacement);
+    }
+    fixed
+}
+
+// Drops edits that overlap one kept before them, so the rest can be
+// applied together. Whatever was dropped can be fixed on another run.
+pub fn without_overlaps(mut edits: Vec<TextEdit>) -> Vec<TextEdit> {
+    edits.sort_by_key(|edit| (edit.start.line, edit.start.column));
+
+    let mut kept: Vec<TextEdit> = vec![];
+    for edit in edits {
+        let overlaps = kept.last().is_some_and(|last| {
+            (edit.start.line, edit.start.column) < (last.end.line, last.end.column) || edit.start == last.start
+        });
+        if !overlaps {
+            kept.push(edit);
+        }
+    }
+    kept
+}
+
+// A unified diff of the lines that changed between `before` and `after`,
+// without context lines, for `dbtonic fix --dry-run`
+pub fn diff(path: &str, before: &str, after: &str) -> String {
+    let before: Vec<&str> = before.lines().collect();
+    let after: Vec<&str> = after.lines().collect();
+
+    // The longest common subsequence of lines, filled in from the end
+    let mut common = vec![vec![0; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            common[i][j] = if before[i] == after[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![format!("--- {}", path), format!("+++ {}", path)];
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        // Collect a run of changed lines into one hunk
+        let (hunk_i, hunk_j) = (i, j);
+        let mut removed = vec![];
+        let mut added = vec![];
+        while (i < before.len() || j < after.len()) && !(i < before.len() && j < after.len() && before[i] == after[j]) {
+            if j == after.len() || (i < before.len() && common[i + 1][j] >= common[i][j + 1]) {
+                removed.push(format!("-{}", before[i]));
+                i += 1;
+            } else {
+                added.push(format!("+{}", after[j]));
+                j += 1;
+            }
+        }
+        lines.push(format!("@@ -{},{} +{},{} @@", hunk_i + 1, removed.len(), hunk_j + 1, added.len()));
+        lines.extend(removed);
+        lines.extend(added);
+    }
+    lines.join("\n")
+}
+
+// Lines and columns start at 1 and columns count characters, matching the
+// locations the tokenizer produces
+pub(crate) fn byte_offset(sql: &str, location: &Location) -> Option<usize> {
+    let mut line_start = 0;
+    for _ in 1..location.line {
+        line_start += sql[line_start..].find('\n')? + 1;
+    }
+
+    let line = &sql[line_start..];
+    let column = location.column.checked_sub(1)? as usize;
+    let line_length = line.find('\n').unwrap_or(line.len());
+    let offset = match line.char_indices().nth(column) {
+        Some((offset, _)) => offset,
+        None if column == line[..line_length].chars().count() => line_length,
+        None => return None,
+    };
+
+    if offset > line_length {
+        return None;
+    }
+    Some(line_start + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(start: (u64, u64), end: (u64, u64), replacement: &str) -> TextEdit {
+        TextEdit {
+            start: Location { line: start.0, column: start.1 },
+            end: Location { line: end.0, column: end.1 },
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let sql = "SELECT \"id\"\nFROM \"orders\"";
+
+        let fixed = apply_edits(sql, &[edit((1, 8), (1, 12), "id"), edit((2, 6), (2, 14), "orders")]);
+
+        assert_eq!(fixed, "SELECT id\nFROM orders");
+    }
+
+    #[test]
+    fn test_text_edit_serializes_span_and_replacement() {
+        let json = serde_json::to_value(edit((1, 8), (1, 12), "id")).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "start": {"line": 1, "column": 8},
+                "end": {"line": 1, "column": 12},
+                "replacement": "id",
+            })
+        );
+    }
+
+    #[test]
+    fn test_without_overlaps_keeps_the_first_edit() {
+        let edits = vec![edit((1, 8), (1, 14), "b"), edit((1, 1), (1, 10), "a"), edit((1, 20), (1, 20), " AS c")];
+
+        assert_eq!(without_overlaps(edits), vec![edit((1, 1), (1, 10), "a"), edit((1, 20), (1, 20), " AS c")]);
+    }
+
+    #[test]
+    fn test_diff_shows_changed_lines() {
+        let before = "SELECT \"id\"\nFROM orders\nWHERE id > 1;";
+        let after = "SELECT id\nFROM orders\nWHERE id > 1";
+
+        assert_eq!(
+            diff("models/orders.sql", before, after),
+            "--- models/orders.sql\n\
+             +++ models/orders.sql\n\
+             @@ -1,1 +1,1 @@\n\
+             -SELECT \"id\"\n\
+             +SELECT id\n\
+             @@ -3,1 +3,1 @@\n\
+             -WHERE id > 1;\n\
+             +WHERE id > 1"
+        );
+    }
+
+    #[test]
+    fn test_apply_edits_ignores_out_of_range_edits() {
+        let sql = "SELECT 1";
+
+        assert_eq!(apply_edits(sql, &[edit((3, 1), (3, 2), "x")]), sql);
+    }
+}
@@ -0,0 +1,38 @@
+use dbtranslate::tokens::{Token, TokenWithLocation};
+
+// A `#`, which some dialects read as the start of a word. The rules that
+// skip over `{# #}` comments in the tokens look for it on both ends.
+pub fn is_sharp(token: &Token) -> bool {
+    match token {
+        Token::Sharp => true,
+        Token::Word(word) => word.quote_style.is_none() && word.value.starts_with('#'),
+        _ => false,
+    }
+}
+
+// The tokens outside Jinja. `{{ }}` expressions, `{% %}` tags and `{# #}`
+// comments are dropped along with their delimiters, so the strings in
+// `ref("orders")` or `{% set status = "paid" %}` aren't read as SQL.
+pub fn outside_jinja(tokens: Vec<TokenWithLocation<'_>>) -> Vec<TokenWithLocation<'_>> {
+    let mut sql_tokens = vec![];
+    // What closes the Jinja the tokens are in, if they're in some
+    let mut closing: Option<Token> = None;
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let next = tokens.peek().map(|token| &token.token);
+        if let Some(end) = &closing {
+            let comment_end = *end == Token::Sharp && is_sharp(&token.token) && next == Some(&Token::RBrace);
+            if token.token == *end || comment_end {
+                closing = None;
+            }
+            continue;
+        }
+        match (&token.token, next) {
+            (Token::DoubleLBrace, _) => closing = Some(Token::DoubleRBrace),
+            (Token::LJinjaIterator, _) => closing = Some(Token::RJinjaIterator),
+            (Token::LBrace, Some(next)) if is_sharp(next) => closing = Some(Token::Sharp),
+            _ => sql_tokens.push(token),
+        }
+    }
+    sql_tokens
+}
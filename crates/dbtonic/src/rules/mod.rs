@@ -1,4 +1,8 @@
 pub mod rules_engine;
 pub mod ast_rules;
 pub mod sql_rules;
-pub mod yml_rules;
\ No newline at end of file
+pub mod yml_rules;
+pub mod project_rules;
+pub mod edits;
+pub mod suppressions;
+pub mod jinja_tokens;
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use crate::rules::rules_engine::{ProjectRule, Severity, Violation};
+use crate::parser::dag::DAG;
+use crate::parser::normalize::{query_fingerprints, QueryFingerprint};
+use crate::parser::model_node::ModelNode;
+
+// Reports CTEs and subqueries whose logic is repeated in more than one model.
+// The violation points at the first occurrence and lists the others.
+pub struct DuplicateLogic;
+
+impl ProjectRule for DuplicateLogic {
+    fn name(&self) -> String {
+        "duplicate_logic".to_string()
+    }
+
+    fn description(&self) -> String {
+        "The same CTE or subquery logic should not be repeated across models.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, dag: &DAG) -> Vec<Violation> {
+        let mut occurrences: BTreeMap<String, Vec<(&ModelNode, QueryFingerprint)>> = BTreeMap::new();
+
+        for model_node in &dag.model_nodes {
+            for query in query_fingerprints(&model_node.data.ast) {
+                occurrences.entry(query.fingerprint.clone()).or_default().push((model_node, query));
+            }
+        }
+
+        occurrences
+            .into_values()
+            .filter(|found_in| {
+                let first_model = &found_in[0].0.model_name;
+                found_in.iter().any(|(model_node, _)| &model_node.model_name != first_model)
+            })
+            .map(|found_in| {
+                let places: Vec<String> = found_in
+                    .iter()
+                    .map(|(model_node, query)| format!("{} ({})", model_node.model_name, query.label))
+                    .collect();
+                let (first_model, first_query) = &found_in[0];
+                Violation::new(
+                    self.name(),
+                    format!(
+                        "The same logic appears in {}. Consider extracting it into an upstream model or macro.",
+                        places.join(", ")
+                    ),
+                    None,
+                )
+                .in_model(first_model)
+                .with_span(&first_query.span)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model_node::ModelNode;
+
+    #[test]
+    fn test_duplicate_logic_across_models() {
+        let dag = DAG::from_test_models(vec![
+            ModelNode::from_test_sql(
+                "customers",
+                "WITH orders AS (SELECT * FROM {{ ref('stg_orders') }}), \
+                 totals AS (SELECT customer_id, SUM(amount) AS total FROM orders GROUP BY customer_id) \
+                 SELECT * FROM totals",
+            ),
+            ModelNode::from_test_sql(
+                "customer_ltv",
+                "WITH orders AS (SELECT * FROM {{ ref('stg_orders') }}), \
+                 lifetime AS (select customer_id, sum(amount) as total from orders group by customer_id) \
+                 SELECT * FROM lifetime",
+            ),
+        ]);
+
+        let violations = DuplicateLogic.run(&dag);
+        let messages: Vec<&str> = violations.iter().map(|v| v.message.as_str()).collect();
+
+        assert_eq!(
+            messages,
+            vec!["The same logic appears in customers (CTE totals), customer_ltv (CTE lifetime). Consider extracting it into an upstream model or macro."]
+        );
+        assert_eq!(violations[0].model.as_deref(), Some("customers"));
+        assert_eq!(violations[0].location.as_ref().map(|location| location.line), Some(1));
+    }
+
+    #[test]
+    fn test_different_string_literals_pass() {
+        let dag = DAG::from_test_models(vec![
+            ModelNode::from_test_sql(
+                "paid_orders",
+                "WITH paid AS (SELECT order_id FROM {{ ref('stg_orders') }} WHERE status = 'Paid') SELECT * FROM paid",
+            ),
+            ModelNode::from_test_sql(
+                "unpaid_orders",
+                "WITH paid AS (SELECT order_id FROM {{ ref('stg_orders') }} WHERE status = 'paid') SELECT * FROM paid",
+            ),
+        ]);
+
+        assert!(DuplicateLogic.run(&dag).is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use glob::Pattern;
+use crate::rules::rules_engine::{ProjectRule, Severity, Violation};
+use crate::parser::dag::DAG;
+use crate::parser::model_node::ModelNode;
+use crate::parser::exposure_yaml::{ExposureDependency, ExposureYaml};
+use crate::reporting::suggestions::with_suggestion;
+
+const VALID_MATURITIES: [&str; 3] = ["high", "medium", "low"];
+
+// Which models are staging comes from the `staging` globs of
+// layered_architecture, matched against the model's path relative to the
+// project, as well as dbt's `stg_` and `staging` directory conventions.
+pub struct ExposureValidation {
+    pub staging: Vec<Pattern>,
+    pub base_path: PathBuf,
+}
+
+impl ProjectRule for ExposureValidation {
+    fn name(&self) -> String {
+        "exposure_validation".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Exposures must declare an owner and maturity, only depend on things that exist and not depend on staging models.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, dag: &DAG) -> Vec<Violation> {
+        dag.exposures
+            .iter()
+            .flat_map(|exposure| self.validate_exposure(exposure, dag))
+            .map(|message| Violation::new(self.name(), message, None))
+            .collect()
+    }
+}
+
+impl ExposureValidation {
+    fn validate_exposure(&self, exposure: &ExposureYaml, dag: &DAG) -> Vec<String> {
+        let mut errors = vec![];
+        // What a ref can point at, as in missing_refs
+        let known: BTreeSet<&str> = dag
+            .model_nodes
+            .iter()
+            .chain(&dag.snapshots)
+            .map(|node| node.model_name.as_str())
+            .chain(dag.seeds.iter().map(|seed| seed.name.as_str()))
+            .collect();
+
+        let has_owner = exposure.owner
+            .as_ref()
+            .map(|owner| owner.name.is_some() || owner.email.is_some())
+            .unwrap_or(false);
+        if !has_owner {
+            errors.push(format!("Exposure '{}' does not declare an owner.", exposure.name));
+        }
+
+        match &exposure.maturity {
+            None => errors.push(format!("Exposure '{}' does not declare a maturity.", exposure.name)),
+            Some(maturity) if !VALID_MATURITIES.contains(&maturity.to_lowercase().as_str()) => {
+                errors.push(format!(
+                    "Exposure '{}' has an invalid maturity '{}'. Expected one of: high, medium, low.",
+                    exposure.name, maturity
+                ));
+            }
+            Some(_) => {}
+        }
+
+        for dependency in exposure.dependencies() {
+            match dependency {
+                // Another package's models aren't read, so there's nothing
+                // to check its refs against
+                ExposureDependency::Ref(target) if !target.is_in_project(&dag.project.name) => {}
+                ExposureDependency::Ref(target) => {
+                    let model_name = target.name;
+                    let model_node = dag.model_nodes.iter().find(|node| node.model_name == model_name);
+                    if !known.contains(model_name.as_str()) {
+                        errors.push(with_suggestion(
+                            format!(
+                                "Exposure '{}' depends on model '{}' which does not exist.",
+                                exposure.name, model_name
+                            ),
+                            &model_name,
+                            known.iter().copied(),
+                        ));
+                    } else if model_node.is_some_and(|model_node| self.is_staging(model_node)) {
+                        errors.push(format!(
+                            "Exposure '{}' depends on staging model '{}'. Exposures should depend on marts models instead.",
+                            exposure.name, model_name
+                        ));
+                    }
+                }
+                ExposureDependency::Metric(metric_name) => {
+                    if !dag.semantic_layer.metrics.iter().any(|metric| metric.name == metric_name) {
+                        errors.push(with_suggestion(
+                            format!(
+                                "Exposure '{}' depends on metric '{}' which does not exist.",
+                                exposure.name, metric_name
+                            ),
+                            &metric_name,
+                            dag.semantic_layer.metrics.iter().map(|metric| metric.name.as_str()),
+                        ));
+                    }
+                }
+                ExposureDependency::Source(source_name, table_name) => {
+                    if !dag.declares_source(&source_name, &table_name) {
+                        errors.push(format!(
+                            "Exposure '{}' depends on source '{}.{}' which is not declared in the yml.",
+                            exposure.name, source_name, table_name
+                        ));
+                    }
+                }
+                ExposureDependency::Unknown(value) => {
+                    errors.push(format!(
+                        "Exposure '{}' has a depends_on entry '{}' that is not a ref, source or metric.",
+                        exposure.name, value
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn is_staging(&self, model_node: &ModelNode) -> bool {
+        let relative_path = model_node.path.strip_prefix(&self.base_path).unwrap_or(&model_node.path);
+        model_node.is_staging() || self.staging.iter().any(|pattern| pattern.matches_path(relative_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::exposure_yaml::ExposureOwner;
+    use crate::parser::seed::SeedNode;
+
+    fn rule() -> ExposureValidation {
+        ExposureValidation { staging: vec![Pattern::new("models/base/**").unwrap()], base_path: PathBuf::new() }
+    }
+
+    fn dag_with_exposure(exposure: ExposureYaml) -> DAG {
+        let mut base_orders = ModelNode::from_test_sql("base_orders", "");
+        base_orders.path = PathBuf::from("models/base/base_orders.sql");
+        let mut orders = ModelNode::from_test_sql("orders", "");
+        orders.path = PathBuf::from("models/staging/orders.sql");
+        DAG {
+            exposures: vec![exposure],
+            sources: serde_yaml::from_str("- name: shop\n  tables:\n    - name: orders\n").unwrap(),
+            snapshots: vec![ModelNode::from_test_sql("orders_snapshot", "")],
+            seeds: vec![SeedNode { name: "country_codes".to_string(), path: PathBuf::new(), columns: vec![] }],
+            project: serde_yaml::from_str("name: shop").unwrap(),
+            ..DAG::from_test_models(vec![
+                ModelNode::from_test_sql("fct_orders", ""),
+                ModelNode::from_test_sql("stg_orders", ""),
+                base_orders,
+                orders,
+            ])
+        }
+    }
+
+    #[test]
+    fn test_valid_exposure_passes() {
+        let dag = dag_with_exposure(ExposureYaml {
+            name: "weekly_metrics".to_string(),
+            maturity: Some("high".to_string()),
+            owner: Some(ExposureOwner {
+                name: Some("Callum".to_string()),
+                email: None,
+            }),
+            depends_on: vec![
+                "ref('fct_orders')".to_string(),
+                "ref('shop', 'fct_orders', v=2)".to_string(),
+                "ref('orders_snapshot')".to_string(),
+                "ref('country_codes')".to_string(),
+                "ref('payments', 'stg_payments')".to_string(),
+                "source('shop', 'orders')".to_string(),
+            ],
+            ..Default::default()
+        });
+
+        assert_eq!(rule().run(&dag), vec![]);
+    }
+
+    #[test]
+    fn test_invalid_exposure_fails() {
+        let dag = dag_with_exposure(ExposureYaml {
+            name: "weekly_metrics".to_string(),
+            depends_on: vec![
+                "ref('stg_orders')".to_string(),
+                "ref('base_orders')".to_string(),
+                "ref('orders')".to_string(),
+                "ref('missing_model')".to_string(),
+                "ref('fct_order')".to_string(),
+                "source('shop', 'payments')".to_string(),
+            ],
+            ..Default::default()
+        });
+
+        let messages: Vec<String> = rule()
+            .run(&dag)
+            .into_iter()
+            .map(|violation| violation.message)
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Exposure 'weekly_metrics' does not declare an owner.".to_string(),
+                "Exposure 'weekly_metrics' does not declare a maturity.".to_string(),
+                "Exposure 'weekly_metrics' depends on staging model 'stg_orders'. Exposures should depend on marts models instead.".to_string(),
+                "Exposure 'weekly_metrics' depends on staging model 'base_orders'. Exposures should depend on marts models instead.".to_string(),
+                "Exposure 'weekly_metrics' depends on staging model 'orders'. Exposures should depend on marts models instead.".to_string(),
+                "Exposure 'weekly_metrics' depends on model 'missing_model' which does not exist.".to_string(),
+                "Exposure 'weekly_metrics' depends on model 'fct_order' which does not exist. Did you mean 'fct_orders'?".to_string(),
+                "Exposure 'weekly_metrics' depends on source 'shop.payments' which is not declared in the yml.".to_string(),
+            ]
+        );
+    }
+
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::configuration::dbtonic_config::LayersConfig;
+use crate::rules::rules_engine::{ProjectRule, Severity, Violation};
+use crate::parser::dag::DAG;
+use crate::parser::lineage::LineageNode;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Layer {
+    Staging,
+    Intermediate,
+    Marts,
+}
+
+impl Layer {
+    fn label(&self) -> &'static str {
+        match self {
+            Layer::Staging => "staging",
+            Layer::Intermediate => "intermediate",
+            Layer::Marts => "marts",
+        }
+    }
+}
+
+// Flags lineage edges that skip or break the project's layers: staging
+// models that ref other models, and intermediate or marts models that
+// select from a source directly. Which layer a model is in comes from the
+// rule's globs, matched against its path relative to the project.
+pub struct LayeredArchitecture {
+    pub config: LayersConfig,
+    pub base_path: PathBuf,
+}
+
+impl ProjectRule for LayeredArchitecture {
+    fn name(&self) -> String {
+        "layered_architecture".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Staging models must only select from sources, and intermediate and marts models only from refs.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, dag: &DAG) -> Vec<Violation> {
+        let layers: HashMap<&str, Layer> = dag
+            .model_nodes
+            .iter()
+            .filter_map(|model_node| Some((model_node.model_name.as_str(), self.layer(&model_node.path)?)))
+            .collect();
+
+        dag.lineage
+            .edges()
+            .into_iter()
+            .filter_map(|(parent, model)| {
+                let LineageNode::Model(model_name) = model else {
+                    return None;
+                };
+                let layer = *layers.get(model_name.as_str())?;
+                match (layer, parent) {
+                    (Layer::Staging, LineageNode::Model(parent_name)) => Some(format!(
+                        "The staging model '{}' refs '{}'. Staging models should only select from sources, with \
+                         `{{{{ source() }}}}`.",
+                        model_name, parent_name
+                    )),
+                    (Layer::Intermediate | Layer::Marts, LineageNode::Source(..)) => Some(format!(
+                        "The {} model '{}' selects from the source '{}' directly. Intermediate and marts models \
+                         should build on staging models, with `{{{{ ref() }}}}`.",
+                        layer.label(), model_name, parent
+                    )),
+                    _ => None,
+                }
+            })
+            .map(|message| Violation::new(self.name(), message, None))
+            .collect()
+    }
+}
+
+impl LayeredArchitecture {
+    // The first layer whose globs match the model's path
+    fn layer(&self, path: &Path) -> Option<Layer> {
+        let relative_path = path.strip_prefix(&self.base_path).unwrap_or(path);
+        [
+            (Layer::Staging, &self.config.staging),
+            (Layer::Intermediate, &self.config.intermediate),
+            (Layer::Marts, &self.config.marts),
+        ]
+        .into_iter()
+        .find(|(_, patterns)| patterns.iter().any(|pattern| pattern.matches_path(relative_path)))
+        .map(|(layer, _)| layer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model_node::ModelNode;
+
+    fn model_node(path: &str, sql: &str) -> ModelNode {
+        let path = PathBuf::from(path);
+        let mut model_node = ModelNode::from_test_sql(&path.file_stem().unwrap().to_string_lossy(), sql);
+        model_node.path = path;
+        model_node
+    }
+
+    fn dag(model_nodes: Vec<ModelNode>) -> DAG {
+        DAG::from_test_models(model_nodes)
+    }
+
+    fn rule() -> LayeredArchitecture {
+        LayeredArchitecture { config: LayersConfig::default(), base_path: PathBuf::from("/project") }
+    }
+
+    #[test]
+    fn test_layered_models_pass() {
+        let dag = dag(vec![
+            model_node("/project/models/staging/stg_orders.sql", "SELECT id FROM {{ source('shop', 'orders') }}"),
+            model_node("/project/models/intermediate/int_orders.sql", "SELECT id FROM {{ ref('stg_orders') }}"),
+            model_node("/project/models/marts/orders.sql", "SELECT id FROM {{ ref('int_orders') }}"),
+            // Models outside the layers can select from anything
+            model_node("/project/models/utils/all_orders.sql", "SELECT id FROM {{ source('shop', 'orders') }}"),
+        ]);
+        assert!(rule().run(&dag).is_empty());
+    }
+
+    #[test]
+    fn test_models_crossing_layers_fail() {
+        let dag = dag(vec![
+            model_node(
+                "/project/models/staging/stg_orders.sql",
+                "SELECT o.id FROM {{ source('shop', 'orders') }} AS o JOIN {{ ref('stg_customers') }} AS c ON o.customer_id = c.id",
+            ),
+            model_node("/project/models/staging/stg_customers.sql", "SELECT id FROM {{ source('shop', 'customers') }}"),
+            model_node("/project/models/marts/orders.sql", "SELECT id FROM {{ source('shop', 'orders') }}"),
+        ]);
+
+        let messages: Vec<String> = rule().run(&dag).into_iter().map(|violation| violation.message).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The staging model 'stg_orders' refs 'stg_customers'. Staging models should only select from sources, \
+                 with `{{ source() }}`."
+                    .to_string(),
+                "The marts model 'orders' selects from the source 'shop.orders' directly. Intermediate and marts \
+                 models should build on staging models, with `{{ ref() }}`."
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_configured_layers() {
+        let config: LayersConfig = toml::from_str("staging = [\"models/base/**\"]").unwrap();
+        let rule = LayeredArchitecture { config, base_path: PathBuf::from("/project") };
+        let dag = dag(vec![
+            model_node("/project/models/base/base_orders.sql", "SELECT id FROM {{ ref('raw_orders') }}"),
+            model_node("/project/models/staging/stg_orders.sql", "SELECT id FROM {{ ref('raw_orders') }}"),
+        ]);
+
+        let messages: Vec<String> = rule.run(&dag).into_iter().map(|violation| violation.message).collect();
+        assert_eq!(
+            messages,
+            vec!["The staging model 'base_orders' refs 'raw_orders'. Staging models should only select from sources, \
+                  with `{{ source() }}`."
+                .to_string()]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::{BTreeMap, BTreeSet};
+use crate::rules::rules_engine::{ProjectRule, Severity, Violation};
+use crate::parser::ast_query::ast_nodes;
+use crate::parser::dag::DAG;
+use crate::reporting::suggestions::with_suggestion;
+
+// Flags refs to names that aren't a model, snapshot or seed in the project,
+// which dbt fails to compile. Refs to another package are left alone, since
+// its models aren't read, and so are nodes that didn't parse.
+pub struct MissingRefs;
+
+impl ProjectRule for MissingRefs {
+    fn name(&self) -> String {
+        "missing_refs".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Every ref() must point to a model, snapshot or seed that exists in the project.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, dag: &DAG) -> Vec<Violation> {
+        let known: BTreeSet<&str> = dag
+            .model_nodes
+            .iter()
+            .chain(&dag.snapshots)
+            .map(|node| node.model_name.as_str())
+            .chain(dag.seeds.iter().map(|seed| seed.name.as_str()))
+            .collect();
+
+        let nodes = dag.model_nodes.iter().chain(&dag.snapshots).chain(&dag.singular_tests).chain(&dag.analyses);
+        let mut violations = vec![];
+        for node in nodes {
+            // The first ref() to each, which the violation points at
+            let mut missing = BTreeMap::new();
+            for reference in ast_nodes(&node.data.ast).into_iter().filter(|ast_node| ast_node.kind == "refs") {
+                let package = reference.attribute("package").unwrap_or_default();
+                let Some(name) = reference.attribute("name") else {
+                    continue;
+                };
+                if (package.is_empty() || package == dag.project.name) && !known.contains(name) {
+                    missing.entry(name.to_string()).or_insert_with(|| reference.span.clone());
+                }
+            }
+            for (name, span) in missing {
+                let message = format!(
+                    "The {} '{}' refs '{}', which isn't a model, snapshot or seed in the project.",
+                    node.resource_type.label(),
+                    node.model_name,
+                    name
+                );
+                let message = with_suggestion(message, &name, known.iter().copied());
+                violations.push(Violation::new(self.name(), message, None).in_model(node).with_span(&span));
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::parser::model_node::{ModelNode, ResourceType};
+    use crate::parser::seed::SeedNode;
+
+    fn model_node(name: &str, resource_type: ResourceType, sql: &str) -> ModelNode {
+        let mut model_node = ModelNode::from_test_sql(name, sql);
+        model_node.resource_type = resource_type;
+        model_node
+    }
+
+    fn dag(model_nodes: Vec<ModelNode>, snapshots: Vec<ModelNode>, analyses: Vec<ModelNode>) -> DAG {
+        DAG {
+            analyses,
+            snapshots,
+            seeds: vec![SeedNode { name: "country_codes".to_string(), path: PathBuf::new(), columns: vec![] }],
+            project: serde_yaml::from_str("name: shop").unwrap(),
+            ..DAG::from_test_models(model_nodes)
+        }
+    }
+
+    #[test]
+    fn test_refs_to_models_snapshots_and_seeds_pass() {
+        let dag = dag(
+            vec![
+                model_node("stg_orders", ResourceType::Model, "SELECT id FROM {{ source('shop', 'orders') }}"),
+                model_node(
+                    "orders",
+                    ResourceType::Model,
+                    "SELECT o.id FROM {{ ref('stg_orders') }} AS o JOIN {{ ref('country_codes') }} AS c ON o.id = c.id \
+                     JOIN {{ ref('orders_snapshot') }} AS s ON o.id = s.id JOIN {{ ref('shop', 'stg_orders') }} AS p ON o.id = p.id \
+                     JOIN {{ ref('dbt_utils', 'date_spine') }} AS d ON o.id = d.id",
+                ),
+            ],
+            vec![model_node("orders_snapshot", ResourceType::Snapshot, "SELECT id FROM {{ ref('stg_orders') }}")],
+            vec![],
+        );
+        assert!(MissingRefs.run(&dag).is_empty());
+    }
+
+    #[test]
+    fn test_missing_refs_fail() {
+        let dag = dag(
+            vec![
+                model_node("stg_orders", ResourceType::Model, "SELECT id FROM {{ source('shop', 'orders') }}"),
+                model_node(
+                    "orders",
+                    ResourceType::Model,
+                    "SELECT o.id FROM {{ ref('stg_order') }} AS o JOIN {{ ref('stg_order') }} AS p ON o.id = p.id \
+                     JOIN {{ ref('shop', 'payments') }} AS pay ON o.id = pay.id",
+                ),
+            ],
+            vec![],
+            vec![model_node("revenue", ResourceType::Analysis, "SELECT id FROM {{ ref('country_code') }}")],
+        );
+
+        let violations = MissingRefs.run(&dag);
+        let messages: Vec<String> = violations.iter().map(|violation| violation.message.clone()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The model 'orders' refs 'payments', which isn't a model, snapshot or seed in the project.".to_string(),
+                "The model 'orders' refs 'stg_order', which isn't a model, snapshot or seed in the project. Did you \
+                 mean 'stg_orders'?"
+                    .to_string(),
+                "The analysis 'revenue' refs 'country_code', which isn't a model, snapshot or seed in the project. \
+                 Did you mean 'country_codes'?"
+                    .to_string(),
+            ]
+        );
+
+        // Each points at the first ref() to the name
+        let places: Vec<String> = violations
+            .iter()
+            .map(|violation| {
+                let location = violation.location.as_ref().unwrap();
+                format!("{}:{}:{}", violation.path.as_ref().unwrap().display(), location.line, location.column)
+            })
+            .collect();
+        assert_eq!(places, vec!["models/orders.sql:1:106", "models/orders.sql:1:25", "models/revenue.sql:1:23"]);
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::BTreeSet;
+use dbtranslate::dialect::GenericDialect;
+use dbtranslate::tokenizer::Tokenizer;
+use dbtranslate::tokens::{Location, Span, Token};
+use dbtranslate::suggestions::did_you_mean;
+use crate::rules::rules_engine::{ProjectRule, Severity, Violation};
+use crate::parser::ast_query::ast_nodes;
+use crate::parser::dag::DAG;
+use crate::parser::model_node::ModelNode;
+
+// The configs dbt knows, including the adapter specific ones most projects
+// use. Projects can set configs of their own, so only keys that are a typo
+// of one of these are flagged.
+const CONFIG_KEYS: &[&str] = &[
+    "materialized", "schema", "database", "alias", "tags", "enabled", "meta", "docs", "persist_docs",
+    "full_refresh", "pre_hook", "post_hook", "grants", "contract", "group", "access", "sql_header",
+    "unique_key", "incremental_strategy", "on_schema_change", "merge_update_columns", "merge_exclude_columns",
+    "strategy", "updated_at", "check_cols", "target_schema", "target_database", "invalidate_hard_deletes",
+    "severity", "store_failures", "warn_if", "error_if", "limit", "where", "cluster_by", "partition_by",
+    "transient", "snowflake_warehouse", "sort", "dist", "sort_type", "bind", "indexes", "unlogged",
+];
+
+// The functions dbt's Jinja context has, which parse as macro calls in a
+// `{{ }}`
+const JINJA_FUNCTIONS: &[&str] = &[
+    "ref", "source", "config", "var", "env_var", "is_incremental", "run_query", "log", "return", "doc",
+    "statement", "as_bool", "as_native", "as_number", "as_text", "fromjson", "tojson", "fromyaml", "toyaml",
+    "zip", "local_md5", "print",
+];
+
+// Flags `config()` keys and `{{ }}` calls that look like typos of dbt's own,
+// e.g. `materalized` or `reff()`. dbt takes an unknown config as a custom
+// one and fails on an unknown function, either way without saying which
+// name was meant. Calls to the project's macros and namespaced calls, like
+// `dbt_utils.star()`, are left alone.
+pub struct MisspelledJinja;
+
+impl ProjectRule for MisspelledJinja {
+    fn name(&self) -> String {
+        "misspelled_jinja".to_string()
+    }
+
+    fn description(&self) -> String {
+        "config() keys and Jinja functions must not be misspellings of dbt's own.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, dag: &DAG) -> Vec<Violation> {
+        let macros: BTreeSet<&str> = dag.macros.iter().map(|macro_node| macro_node.name.as_str()).collect();
+        let functions = || JINJA_FUNCTIONS.iter().copied().chain(macros.iter().copied());
+
+        let nodes = dag.model_nodes.iter().chain(&dag.snapshots).chain(&dag.singular_tests).chain(&dag.analyses);
+        let mut violations = vec![];
+        for node in nodes {
+            for key in node.config_keys().into_iter().filter(|key| !CONFIG_KEYS.contains(key)) {
+                let Some(suggestion) = did_you_mean(key, CONFIG_KEYS.iter().copied()) else {
+                    continue;
+                };
+                let message = format!(
+                    "The {} '{}' sets the config '{}', which isn't one of dbt's. Did you mean '{}'?",
+                    node.resource_type.label(),
+                    node.model_name,
+                    key,
+                    suggestion
+                );
+                let mut violation = Violation::new(self.name(), message, None).in_model(node);
+                if let Some(span) = config_key_span(node, key) {
+                    violation = violation.with_span(&span);
+                }
+                violations.push(violation);
+            }
+
+            for call in ast_nodes(&node.data.ast).into_iter().filter(|ast_node| ast_node.kind == "macros") {
+                let name = call.attribute("name").unwrap_or_default();
+                if !call.attribute("namespace").unwrap_or_default().is_empty() || functions().any(|function| function == name) {
+                    continue;
+                }
+                let Some(suggestion) = did_you_mean(name, functions()) else {
+                    continue;
+                };
+                let message = format!(
+                    "The {} '{}' calls '{}', which isn't one of dbt's functions or a macro in the project. Did you mean '{}'?",
+                    node.resource_type.label(),
+                    node.model_name,
+                    name,
+                    suggestion
+                );
+                violations.push(Violation::new(self.name(), message, None).in_model(node).with_span(&call.span));
+            }
+        }
+        violations
+    }
+}
+
+// Where the key is set, i.e. the first `key =` in the SQL. The parsed
+// config doesn't keep its keys' spans.
+fn config_key_span(model_node: &ModelNode, key: &str) -> Option<Span> {
+    let tokens = Tokenizer::new(&GenericDialect {}, &model_node.data.sql).tokenize_with_location().ok()?;
+    let mut tokens = tokens.iter().filter(|token| !matches!(token.token, Token::Whitespace(_))).peekable();
+    while let Some(token) = tokens.next() {
+        let is_key = matches!(&token.token, Token::Word(word) if word.value == key);
+        if is_key && tokens.peek().is_some_and(|next| next.token == Token::Eq) {
+            let start = token.location.clone();
+            let end = Location { line: start.line, column: start.column + key.chars().count() as u64 };
+            return Some(Span::new(start, end));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::parser::macro_node::MacroNode;
+    use crate::configuration::dbtonic_config::SqlDialect;
+
+    fn dag(model_nodes: Vec<ModelNode>, macros: Vec<MacroNode>) -> DAG {
+        DAG {
+            macros,
+            ..DAG::from_test_models(model_nodes)
+        }
+    }
+
+    #[test]
+    fn test_dbt_configs_and_functions_pass() {
+        let sql = "{{ config(materialized='table', unique_key='id', my_custom_flag=true) }}\n\
+                   SELECT id, {{ cents_to_dollars('amount') }} AS amount FROM {{ ref('stg_orders') }} AS o\n\
+                   JOIN {{ dbt_utils.reff('payments') }} AS p ON o.id = p.id";
+        let macros = MacroNode::from_sql(
+            PathBuf::from("macros/cents_to_dollars.sql"),
+            "{% macro cents_to_dollars(column_name) %}({{ column_name }} / 100){% endmacro %}",
+            SqlDialect::Generic,
+        )
+        .unwrap();
+
+        assert!(MisspelledJinja.run(&dag(vec![ModelNode::from_test_sql("orders", sql)], macros)).is_empty());
+    }
+
+    #[test]
+    fn test_misspellings_fail() {
+        let sql = "{{ config(materalized='table') }}\nSELECT id FROM {{ reff('stg_orders') }} AS o";
+
+        let violations = MisspelledJinja.run(&dag(vec![ModelNode::from_test_sql("orders", sql)], vec![]));
+        let messages: Vec<&str> = violations.iter().map(|violation| violation.message.as_str()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The model 'orders' sets the config 'materalized', which isn't one of dbt's. Did you mean 'materialized'?",
+                "The model 'orders' calls 'reff', which isn't one of dbt's functions or a macro in the project. Did you mean 'ref'?",
+            ]
+        );
+        assert_eq!(violations[0].location, Some(Location { line: 1, column: 11 }));
+        assert_eq!(violations[1].location.as_ref().map(|location| location.line), Some(2));
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use dbtranslate::tokens::Location;
+use crate::configuration::dbtonic_config::{IdentifierFolding, SqlDialect};
+use crate::rules::rules_engine::{ProjectRule, Severity, Violation};
+use crate::rules::sql_rules::identifier_quoting::{identifiers, quotes_needed};
+use crate::parser::dag::DAG;
+use crate::parser::model_node::ModelNode;
+
+// Flags identifiers that are written both as `"Column"` and `column` across
+// the project. Where the quotes matter these are two different identifiers
+// on the warehouse, which is rarely what was meant.
+pub struct MixedIdentifierQuoting {
+    pub dialect: SqlDialect,
+    pub folding: IdentifierFolding,
+}
+
+// Where one identifier is used across the project
+#[derive(Default)]
+struct Usage<'a> {
+    quoted_form: String,
+    // The first place it's quoted, which the violation points at
+    first_quoted: Option<(&'a ModelNode, Location, Location)>,
+    quoted: Vec<String>,
+    unquoted: Vec<String>,
+}
+
+impl ProjectRule for MixedIdentifierQuoting {
+    fn name(&self) -> String {
+        "mixed_identifier_quoting".to_string()
+    }
+
+    fn description(&self) -> String {
+        "An identifier should be written with the same quoting everywhere in the project.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, dag: &DAG) -> Vec<Violation> {
+        // Keyed on the lower cased identifier. Only quotes that matter count
+        // as quoted.
+        let mut usages: BTreeMap<String, Usage> = BTreeMap::new();
+
+        for model_node in &dag.model_nodes {
+            for (word, location) in identifiers(&model_node.data.sql, self.dialect) {
+                let usage = usages.entry(word.value.to_lowercase()).or_default();
+                if word.quote_style.is_none() {
+                    push_unique(&mut usage.unquoted, &model_node.model_name);
+                    continue;
+                }
+                if !quotes_needed(&word.value, self.folding) {
+                    continue;
+                }
+                if usage.first_quoted.is_none() {
+                    let end = Location { line: location.line, column: location.column + word.to_string().chars().count() as u64 };
+                    usage.quoted_form = word.to_string();
+                    usage.first_quoted = Some((model_node, location, end));
+                }
+                push_unique(&mut usage.quoted, &model_node.model_name);
+            }
+        }
+
+        usages
+            .into_values()
+            .filter(|usage| !usage.unquoted.is_empty())
+            .filter_map(|usage| {
+                let (model_node, start, end) = usage.first_quoted?;
+                let message = format!(
+                    "The identifier {} is quoted in {} but unquoted in {}, which refers to a different identifier.",
+                    usage.quoted_form,
+                    usage.quoted.join(", "),
+                    usage.unquoted.join(", ")
+                );
+                Some(Violation::new(self.name(), message, Some(start)).with_end_location(end).in_model(model_node))
+            })
+            .collect()
+    }
+}
+
+fn push_unique(models: &mut Vec<String>, model_name: &str) {
+    if !models.iter().any(|m| m == model_name) {
+        models.push(model_name.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::parser::model_node::ModelNode;
+
+    #[test]
+    fn test_mixed_quoting_across_models() {
+        let dag = DAG::from_test_models(vec![
+            ModelNode::from_test_sql("orders", "SELECT \"Amount\", \"id\" FROM raw"),
+            ModelNode::from_test_sql("payments", "SELECT amount, id FROM raw"),
+        ]);
+        let rule = MixedIdentifierQuoting { dialect: SqlDialect::Generic, folding: IdentifierFolding::Lower };
+
+        let violations = rule.run(&dag);
+        let messages: Vec<&str> = violations.iter().map(|v| v.message.as_str()).collect();
+
+        assert_eq!(
+            messages,
+            vec!["The identifier \"Amount\" is quoted in orders but unquoted in payments, which refers to a different identifier."]
+        );
+        assert_eq!(violations[0].model.as_deref(), Some("orders"));
+        assert_eq!(violations[0].path, Some(PathBuf::from("models/orders.sql")));
+        assert_eq!(violations[0].location, Some(Location { line: 1, column: 8 }));
+        assert_eq!(violations[0].end_location, Some(Location { line: 1, column: 16 }));
+    }
+}
@@ -0,0 +1,9 @@
+pub mod exposure_validation;
+pub mod semantic_layer_references;
+pub mod mixed_identifier_quoting;
+pub mod duplicate_logic;
+pub mod layered_architecture;
+pub mod ref_cycles;
+pub mod missing_refs;
+pub mod undeclared_sources;
+pub mod misspelled_jinja;
@@ -0,0 +1,127 @@
+use crate::rules::rules_engine::{ProjectRule, Severity, Violation};
+use crate::parser::ast_query::ast_nodes;
+use crate::parser::dag::DAG;
+use crate::parser::lineage::LineageNode;
+
+// Flags models that ref themselves, and groups of models that ref each
+// other in a loop. dbt refuses to build a project with either, so the
+// message spells out the whole loop to show which ref to break, and the
+// violation points at the first model's ref() to the next.
+pub struct RefCycles;
+
+impl ProjectRule for RefCycles {
+    fn name(&self) -> String {
+        "ref_cycles".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models must not ref themselves or ref each other in a cycle.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, dag: &DAG) -> Vec<Violation> {
+        // Metrics built on each other in a loop are the semantic layer's to
+        // report, not a ref cycle
+        dag.lineage
+            .cycle_paths()
+            .into_iter()
+            .filter(|path| path.iter().all(|node| matches!(node, LineageNode::Model(_))))
+            .map(|path| {
+                let message = if path.len() == 2 {
+                    format!(
+                        "The model '{}' refs itself, which dbt can't build. Use `{{{{ this }}}}` to read the \
+                         model's own table, e.g. in an incremental model.",
+                        path[0]
+                    )
+                } else {
+                    let names: Vec<String> = path.iter().map(|node| node.to_string()).collect();
+                    format!(
+                        "The models {} ref each other in a cycle, which dbt can't build: {}. Each model refs the \
+                         next, so one of the refs has to go.",
+                        names[..names.len() - 1].join(", "),
+                        names.join(" -> ")
+                    )
+                };
+                in_first_ref(Violation::new(self.name(), message, None), dag, path[0], path[1])
+            })
+            .collect()
+    }
+}
+
+// Puts the violation in `model`, at its ref() to `next`
+fn in_first_ref(violation: Violation, dag: &DAG, model: &LineageNode, next: &LineageNode) -> Violation {
+    let (LineageNode::Model(model_name), LineageNode::Model(next_name)) = (model, next) else {
+        return violation;
+    };
+    let Some(model_node) = dag.model_nodes.iter().chain(&dag.snapshots).find(|node| &node.model_name == model_name) else {
+        return violation;
+    };
+    let violation = violation.in_model(model_node);
+    let reference = ast_nodes(&model_node.data.ast)
+        .into_iter()
+        .find(|ast_node| ast_node.kind == "refs" && ast_node.attribute("name") == Some(next_name.as_str()));
+    match reference {
+        Some(reference) => violation.with_span(&reference.span),
+        None => violation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model_node::ModelNode;
+
+    fn dag(model_nodes: Vec<ModelNode>) -> DAG {
+        DAG::from_test_models(model_nodes)
+    }
+
+    #[test]
+    fn test_acyclic_models_pass() {
+        let dag = dag(vec![
+            ModelNode::from_test_sql("stg_orders", "SELECT id FROM {{ source('shop', 'orders') }}"),
+            ModelNode::from_test_sql("orders", "SELECT id FROM {{ ref('stg_orders') }}"),
+            ModelNode::from_test_sql("revenue", "SELECT o.id FROM {{ ref('orders') }} AS o JOIN {{ ref('stg_orders') }} AS s ON o.id = s.id"),
+        ]);
+        assert!(RefCycles.run(&dag).is_empty());
+    }
+
+    #[test]
+    fn test_cycles_fail() {
+        let dag = dag(vec![
+            ModelNode::from_test_sql("orders", "SELECT id FROM {{ ref('payments') }}"),
+            ModelNode::from_test_sql("payments", "SELECT id FROM {{ ref('refunds') }}"),
+            ModelNode::from_test_sql("refunds", "SELECT id FROM {{ ref('orders') }}"),
+            ModelNode::from_test_sql("customers", "SELECT id FROM {{ ref('customers') }}"),
+        ]);
+
+        let violations = RefCycles.run(&dag);
+        let messages: Vec<String> = violations.iter().map(|violation| violation.message.clone()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The model 'customers' refs itself, which dbt can't build. Use `{{ this }}` to read the model's own \
+                 table, e.g. in an incremental model."
+                    .to_string(),
+                "The models orders, payments, refunds ref each other in a cycle, which dbt can't build: orders -> \
+                 payments -> refunds -> orders. Each model refs the next, so one of the refs has to go."
+                    .to_string(),
+            ]
+        );
+
+        // Each points at the first model's ref() to the next one
+        let places: Vec<String> = violations
+            .iter()
+            .map(|violation| {
+                let location = violation.location.as_ref().unwrap();
+                let path = violation.path.as_ref().unwrap();
+                format!("{} in {}:{}:{}", violation.model.as_ref().unwrap(), path.display(), location.line, location.column)
+            })
+            .collect();
+        assert_eq!(places, vec!["customers in models/customers.sql:1:23", "orders in models/orders.sql:1:23"]);
+        assert!(violations.iter().all(|violation| violation.end_location.is_some()));
+    }
+}
@@ -0,0 +1,26 @@
+use crate::rules::rules_engine::{ProjectRule, Severity, Violation};
+use crate::parser::dag::DAG;
+
+pub struct SemanticLayerReferences;
+
+impl ProjectRule for SemanticLayerReferences {
+    fn name(&self) -> String {
+        "semantic_layer_references".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Semantic models, metrics and saved queries must only reference models, columns, measures and metrics that exist.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, dag: &DAG) -> Vec<Violation> {
+        dag.semantic_layer
+            .validate(&dag.model_nodes, &dag.project.name)
+            .into_iter()
+            .map(|message| Violation::new(self.name(), message, None))
+            .collect()
+    }
+}
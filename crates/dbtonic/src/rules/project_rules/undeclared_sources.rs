@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use crate::rules::rules_engine::{ProjectRule, Severity, Violation};
+use crate::parser::ast_query::ast_nodes;
+use crate::parser::dag::DAG;
+use crate::reporting::suggestions::with_suggestion;
+
+// Flags `{{ source('a', 'b') }}` calls whose source and table aren't
+// declared in a `sources:` block in the yml, which dbt fails to compile.
+pub struct UndeclaredSources;
+
+impl ProjectRule for UndeclaredSources {
+    fn name(&self) -> String {
+        "undeclared_sources".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Every source() must be declared in a sources block in the yml.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, dag: &DAG) -> Vec<Violation> {
+        // As `source.table`, so a typo in either half can be suggested
+        let declared: Vec<String> = dag
+            .sources
+            .iter()
+            .flat_map(|source| source.table_names().map(move |table_name| format!("{}.{}", source.name, table_name)))
+            .collect();
+
+        let nodes = dag.model_nodes.iter().chain(&dag.snapshots).chain(&dag.singular_tests).chain(&dag.analyses);
+        let mut violations = vec![];
+        for node in nodes {
+            // The first source() for each, which the violation points at
+            let mut undeclared = BTreeMap::new();
+            for source in ast_nodes(&node.data.ast).into_iter().filter(|ast_node| ast_node.kind == "sources") {
+                let (Some(source_name), Some(table_name)) = (source.attribute("source"), source.attribute("table")) else {
+                    continue;
+                };
+                if !dag.declares_source(source_name, table_name) {
+                    undeclared.entry(format!("{}.{}", source_name, table_name)).or_insert_with(|| source.span.clone());
+                }
+            }
+            for (source, span) in undeclared {
+                let message = format!(
+                    "The {} '{}' selects from the source '{}', which isn't declared in a `sources:` block in the yml.",
+                    node.resource_type.label(),
+                    node.model_name,
+                    source
+                );
+                let message = with_suggestion(message, &source, declared.iter().map(|declared| declared.as_str()));
+                violations.push(Violation::new(self.name(), message, None).in_model(node).with_span(&span));
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model_node::ModelNode;
+
+    fn dag(model_nodes: Vec<ModelNode>) -> DAG {
+        DAG {
+            sources: serde_yaml::from_str(
+                "- name: shop\n  tables:\n    - name: orders\n    - name: customers\n\
+                 - name: stripe\n  tables:\n    - name: payments\n",
+            )
+            .unwrap(),
+            ..DAG::from_test_models(model_nodes)
+        }
+    }
+
+    #[test]
+    fn test_declared_sources_pass() {
+        let dag = dag(vec![
+            ModelNode::from_test_sql("stg_orders", "SELECT id FROM {{ source('shop', 'orders') }}"),
+            ModelNode::from_test_sql("stg_payments", "SELECT id FROM {{ source('stripe', 'payments') }}"),
+        ]);
+        assert!(UndeclaredSources.run(&dag).is_empty());
+    }
+
+    #[test]
+    fn test_undeclared_sources_fail() {
+        let dag = dag(vec![
+            ModelNode::from_test_sql(
+                "stg_orders",
+                "SELECT o.id FROM {{ source('shop', 'order') }} AS o JOIN {{ source('shop', 'order') }} AS p ON o.id = p.id",
+            ),
+            ModelNode::from_test_sql("stg_refunds", "SELECT id FROM {{ source('stripe', 'refunds') }}"),
+            ModelNode::from_test_sql("stg_events", "SELECT id FROM {{ source('segment', 'events') }}"),
+        ]);
+
+        let violations = UndeclaredSources.run(&dag);
+        let messages: Vec<String> = violations.iter().map(|violation| violation.message.clone()).collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "The model 'stg_orders' selects from the source 'shop.order', which isn't declared in a `sources:` \
+                 block in the yml. Did you mean 'shop.orders'?"
+                    .to_string(),
+                "The model 'stg_refunds' selects from the source 'stripe.refunds', which isn't declared in a \
+                 `sources:` block in the yml."
+                    .to_string(),
+                "The model 'stg_events' selects from the source 'segment.events', which isn't declared in a \
+                 `sources:` block in the yml."
+                    .to_string(),
+            ]
+        );
+
+        // Each points at the first source() for it
+        let places: Vec<String> = violations
+            .iter()
+            .map(|violation| {
+                let location = violation.location.as_ref().unwrap();
+                format!("{}:{}:{}", violation.path.as_ref().unwrap().display(), location.line, location.column)
+            })
+            .collect();
+        assert_eq!(places, vec!["models/stg_orders.sql:1:28", "models/stg_refunds.sql:1:26", "models/stg_events.sql:1:26"]);
+    }
+}
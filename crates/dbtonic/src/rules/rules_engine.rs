@@ -1,13 +1,248 @@
-use crate::parser::model_node::ModelNode;
-use crate::configuration::dbtonic_config::DbtonicConfig;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::path::{Path, PathBuf};
+use dbtranslate::tokens::{Location, Span};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::parser::dag::DAG;
+use crate::parser::lineage::{Lineage, LineageNode};
+use crate::rules::edits::{deserialize_position, fix_edits, serialize_position, TextEdit};
+use crate::rules::suppressions::{apply, suppressions, UNUSED_SUPPRESSION};
+use crate::reporting::timing::{timed, RuleTiming};
+use crate::parser::model_node::{ModelNode, ResourceType};
+use crate::validation::dbt_project_operations::DbtProject;
+use crate::configuration::dbtonic_config::{DbtonicConfig, Rules};
 use crate::rules::yml_rules::model_primary_key_tests::UniqueNotNullOrCombinationRule;
 use crate::rules::yml_rules::model_yaml_defined::ModelYamlExists;
+use crate::rules::yml_rules::model_has_tests::ModelHasTests;
+use crate::rules::ast_rules::multiple_statements::MultipleStatements;
+use crate::rules::ast_rules::explicit_aliases::ExplicitAliases;
+use crate::rules::ast_rules::custom_rule::CustomRule;
+use crate::rules::ast_rules::no_env_var::NoEnvVar;
+use crate::rules::ast_rules::no_select_star::NoSelectStar;
+use crate::rules::ast_rules::hardcoded_references::HardcodedReferences;
+use crate::rules::ast_rules::cross_database_references::CrossDatabaseReferences;
+use crate::rules::ast_rules::ambiguous_columns::AmbiguousColumns;
+use crate::rules::ast_rules::no_implicit_cross_joins::NoImplicitCrossJoins;
+use crate::rules::ast_rules::duplicate_column_aliases::DuplicateColumnAliases;
+use crate::rules::ast_rules::explicit_union::ExplicitUnion;
+use crate::rules::ast_rules::forbidden_functions::ForbiddenFunctions;
+use crate::rules::ast_rules::require_materialization::{materialization, RequireMaterialization};
+use crate::rules::ast_rules::incremental_filter::{IncrementalFilter, UnusedIncrementalFilter};
+use crate::rules::ast_rules::final_select_from_cte::FinalSelectFromCte;
+use crate::rules::ast_rules::no_select_distinct::NoSelectDistinct;
+use crate::rules::ast_rules::max_joins::MaxJoins;
+use crate::rules::ast_rules::model_complexity::ModelComplexity;
+use crate::rules::ast_rules::no_order_by::NoOrderBy;
+use crate::rules::sql_rules::identifier_quoting::IdentifierQuoting;
+use crate::rules::sql_rules::quote_style::IdentifierQuoteStyle;
+use crate::rules::sql_rules::keyword_case::KeywordCaseRule;
+use crate::rules::sql_rules::ref_aliases::RefAliases;
+use crate::rules::sql_rules::trailing_semicolon::TrailingSemicolon;
+use crate::rules::project_rules::exposure_validation::ExposureValidation;
+use crate::rules::project_rules::mixed_identifier_quoting::MixedIdentifierQuoting;
+use crate::rules::project_rules::duplicate_logic::DuplicateLogic;
+use crate::rules::project_rules::semantic_layer_references::SemanticLayerReferences;
+use crate::rules::project_rules::layered_architecture::LayeredArchitecture;
+use crate::rules::project_rules::ref_cycles::RefCycles;
+use crate::rules::project_rules::missing_refs::MissingRefs;
+use crate::rules::project_rules::undeclared_sources::UndeclaredSources;
+use crate::rules::project_rules::misspelled_jinja::MisspelledJinja;
 
 pub trait Rule: Send + Sync{
     // TODO: Alter this to account for first rule
     fn name(&self) -> String;
     fn description(&self) -> String;
     fn run(&self, model_node: &ModelNode) -> RuleResult;
+
+    // Style rules override this so they don't fail `dbtonic lint` unless
+    // it's run with `--fail-level warn`. The `[severity]` section of
+    // dbtonic.toml takes precedence.
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    // Rules that can fire more than once in a model (e.g. once per offending
+    // token) override this to report every occurrence along with its location.
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        match self.run(model_node) {
+            RuleResult::Pass => vec![],
+            RuleResult::Fail(message) => vec![Violation::new(self.name(), message, None)],
+        }
+    }
+
+    // Rules that implement `Fix` return themselves here so `dbtonic fix`
+    // can find them
+    fn as_fix(&self) -> Option<&dyn Fix> {
+        None
+    }
+
+    // Rules about things only models have, such as yml or materializations,
+    // override this so they don't run on singular tests and analyses
+    fn applies_to(&self, _resource_type: ResourceType) -> bool {
+        true
+    }
+
+    // Rules about how the model's source is written, such as refs versus
+    // table names or keyword case, override this so they don't run with
+    // `lint --compiled`. Compiling turns every ref into a table name and
+    // drops the `{{ config() }}` block.
+    fn applies_to_compiled(&self) -> bool {
+        true
+    }
+
+    // Rules about one kind of materialization, such as incremental models,
+    // override this. Models that don't say how they're materialized are
+    // views, like they are in dbt.
+    fn applies_to_materialization(&self, _materialized: &str) -> bool {
+        true
+    }
+
+    // Rules about models that other models select from override this, so
+    // they skip the models nothing refs, such as the ones BI tools read
+    fn applies_to_leaf_models(&self) -> bool {
+        true
+    }
+
+    // Rules about whether the model is tested override this, so they skip
+    // the models a singular test refs. The yml doesn't list those tests.
+    fn applies_to_models_with_singular_tests(&self) -> bool {
+        true
+    }
+}
+
+// Rules whose violations carry the edits that fix them. `dbtonic fix`
+// applies the edits to the model files.
+pub trait Fix: Rule {
+    // The edits that fix the model's violations. Violations that are
+    // suppressed in the model aren't fixed.
+    fn fixes(&self, model_node: &ModelNode) -> Vec<TextEdit> {
+        let suppressions = suppressions(&model_node.data.trivia);
+        fix_edits(apply(&suppressions, self.violations(model_node)))
+    }
+}
+
+// Rules that look at the project as a whole (exposures, the semantic layer,
+// etc.) rather than at a single model.
+pub trait ProjectRule: Send + Sync {
+    fn name(&self) -> String;
+    fn description(&self) -> String;
+    fn run(&self, dag: &DAG) -> Vec<Violation>;
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+}
+
+// The engines for a project with `[overrides]`: one for every combination
+// of overrides that some model matches, keyed by the overrides' indexes.
+// Models no override matches, and the project rules, use the engine for
+// the config as written.
+pub struct RulesEngines {
+    config: DbtonicConfig,
+    base_path: PathBuf,
+    engines: HashMap<Vec<usize>, RulesEngine>,
+}
+
+impl RulesEngines {
+    pub fn create(config: &DbtonicConfig, plugins: &[Plugin], project: &DbtProject, model_nodes: &[&ModelNode]) -> Self {
+        let mut engines = HashMap::new();
+        engines.insert(vec![], RulesEngine::with_plugins(config, project, plugins));
+        for model_node in model_nodes {
+            let overrides = config.matching_overrides(relative_path(&model_node.path, &project.root));
+            engines
+                .entry(overrides)
+                .or_insert_with_key(|overrides| RulesEngine::with_plugins(&config.with_overrides(overrides), project, plugins));
+        }
+        RulesEngines { config: config.clone(), base_path: project.root.clone(), engines }
+    }
+
+    // The engine for the overrides the model's path matches
+    pub fn for_model(&self, model_node: &ModelNode) -> &RulesEngine {
+        let overrides = self.config.matching_overrides(relative_path(&model_node.path, &self.base_path));
+        self.engines.get(&overrides).unwrap_or_else(|| self.project())
+    }
+
+    // The engine without any overrides
+    pub fn project(&self) -> &RulesEngine {
+        &self.engines[&vec![]]
+    }
+
+    // Drops the rules that don't apply to compiled SQL, for `lint --compiled`
+    pub fn for_compiled_sql(mut self) -> Self {
+        for engine in self.engines.values_mut() {
+            engine.rules.retain(|rule| rule.applies_to_compiled());
+        }
+        self
+    }
+
+    // Tells the engines which models nothing refs, for the rules that skip
+    // them. Until then every model counts as having dependents.
+    pub fn with_lineage(mut self, lineage: &Lineage) -> Self {
+        let leaf_models: HashSet<String> = lineage
+            .nodes()
+            .filter_map(|node| match node {
+                LineageNode::Model(model_name) if lineage.children(model_name).is_empty() => Some(model_name.clone()),
+                _ => None,
+            })
+            .collect();
+        for engine in self.engines.values_mut() {
+            engine.leaf_models = leaf_models.clone();
+        }
+        self
+    }
+
+    // Tells the engines which models the singular tests ref, for the rules
+    // that skip them
+    pub fn with_singular_tests(mut self, singular_tests: &[ModelNode]) -> Self {
+        let tested_models: HashSet<String> =
+            singular_tests.iter().flat_map(|singular_test| singular_test.data.refs.iter().cloned()).collect();
+        for engine in self.engines.values_mut() {
+            engine.tested_models = tested_models.clone();
+        }
+        self
+    }
+
+    // Whether nothing refs the model, as far as the engines know
+    pub fn is_leaf_model(&self, model_node: &ModelNode) -> bool {
+        self.project().leaf_models.contains(&model_node.model_name)
+    }
+}
+
+fn relative_path<'a>(path: &'a Path, base_path: &Path) -> &'a Path {
+    path.strip_prefix(base_path).unwrap_or(path)
+}
+
+// How serious a rule failing is. Ordered so that `Error > Warn > Info`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    #[serde(alias = "warning")]
+    Warn,
+    Error,
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warn" | "warning" => Ok(Severity::Warn),
+            "error" => Ok(Severity::Error),
+            other => Err(format!("Unknown severity '{}', expected 'info', 'warn' or 'error'", other)),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -16,37 +251,659 @@ pub enum RuleResult {
     Fail(String), // The String holds the error message.
 }
 
+impl RuleResult {
+    // For rules that override `violations`: the model fails with the first
+    // violation's message
+    pub fn from_violations(violations: Vec<Violation>) -> RuleResult {
+        match violations.into_iter().next() {
+            Some(violation) => RuleResult::Fail(violation.message),
+            None => RuleResult::Pass,
+        }
+    }
+}
+
+// A single occurrence of a rule failing within a model.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Violation {
+    pub rule_name: String,
+    pub message: String,
+    // Taken from the rule by the engine, so rules don't set it themselves
+    pub severity: Severity,
+    #[serde(serialize_with = "serialize_location", deserialize_with = "deserialize_location")]
+    pub location: Option<Location>,
+    // Where the offending text ends, for rules that know. Together with
+    // `location` this is the span editors and code scanning highlight.
+    #[serde(serialize_with = "serialize_location", deserialize_with = "deserialize_location")]
+    pub end_location: Option<Location>,
+    // Edits that fix the violation, for rules that know how. Left out of
+    // the json when there are none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edits: Vec<TextEdit>,
+    // The model a project rule's violation is in, by name and file, so it's
+    // reported there rather than against the project. Model rules leave it
+    // out, since the engine knows which model they ran on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+pub(crate) fn serialize_location<S: Serializer>(location: &Option<Location>, serializer: S) -> Result<S::Ok, S::Error> {
+    match location {
+        Some(location) => serialize_position(location, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn deserialize_location<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Location>, D::Error> {
+    #[derive(Deserialize)]
+    struct OptionalPosition(#[serde(deserialize_with = "deserialize_position")] Location);
+
+    Ok(Option::<OptionalPosition>::deserialize(deserializer)?.map(|position| position.0))
+}
+
+impl Violation {
+    pub fn new(rule_name: String, message: String, location: Option<Location>) -> Self {
+        Violation {
+            rule_name,
+            message,
+            severity: Severity::Error,
+            location,
+            end_location: None,
+            edits: vec![],
+            model: None,
+            path: None,
+        }
+    }
+
+    pub fn with_end_location(mut self, end_location: Location) -> Self {
+        self.end_location = Some(end_location);
+        self
+    }
+
+    // Points the violation at an AST node. Nodes the parser couldn't place
+    // leave the violation without a location.
+    pub fn with_span(mut self, span: &Span) -> Self {
+        if !span.is_empty() {
+            self.location = Some(span.start.clone());
+            self.end_location = Some(span.end.clone());
+        }
+        self
+    }
+
+    // For project rules, the model the violation is in
+    pub fn in_model(mut self, model_node: &ModelNode) -> Self {
+        self.model = Some(model_node.model_name.clone());
+        self.path = Some(model_node.path.clone());
+        self
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_edits(mut self, edits: Vec<TextEdit>) -> Self {
+        self.edits = edits;
+        self
+    }
+}
+
+// Registers rules from outside dbtonic with the engine, see `dbtonic::run_with_plugins`
+pub type Plugin = fn(&mut RulesEngine);
+
 pub struct RulesEngine {
     rules: Vec<Box<dyn Rule>>,
+    project_rules: Vec<Box<dyn ProjectRule>>,
+    // Severities set in dbtonic.toml, which win over the rule's own
+    severities: HashMap<String, Severity>,
+    // Its root, which the layered_architecture globs are relative to, and its
+    // dbt_project.yml for the folder configs, such as `+materialized`
+    project: DbtProject,
+    // The models nothing refs, see `RulesEngines::with_lineage`
+    leaf_models: HashSet<String>,
+    // The models a singular test refs, see `RulesEngines::with_singular_tests`
+    tested_models: HashSet<String>,
 }
 
 impl RulesEngine {
-    pub fn create(config: &DbtonicConfig) -> Self {
-        let mut rules_engine = RulesEngine { rules: Vec::new() };
+    pub fn create(config: &DbtonicConfig, project: &DbtProject) -> Self {
+        let mut rules_engine = RulesEngine {
+            rules: Vec::new(),
+            project_rules: Vec::new(),
+            severities: config.severity.clone(),
+            project: project.clone(),
+            leaf_models: HashSet::new(),
+            tested_models: HashSet::new(),
+        };
         rules_engine.add_rules_from_config(config);
         rules_engine
     }
+
+    // The rules from the config, followed by the ones each plugin registers
+    pub fn with_plugins(config: &DbtonicConfig, project: &DbtProject, plugins: &[Plugin]) -> Self {
+        let mut rules_engine = RulesEngine::create(config, project);
+        for plugin in plugins {
+            plugin(&mut rules_engine);
+        }
+        rules_engine
+    }
     fn add_rules_from_config(&mut self, config: &DbtonicConfig) {
         if config.rules.unique_not_null_or_combination_rule {
-            self.add_rule(Box::new(UniqueNotNullOrCombinationRule {}));
+            self.register(Box::new(UniqueNotNullOrCombinationRule {}));
         }
 
         if config.rules.model_yaml_exists {
-            self.add_rule(Box::new(ModelYamlExists {}));
+            self.register(Box::new(ModelYamlExists { config: config.rule_options.model_yaml_exists }));
+        }
+
+        if config.rules.model_has_tests {
+            self.register(Box::new(ModelHasTests {}));
+        }
+
+        if config.rules.multiple_statements {
+            self.register(Box::new(MultipleStatements));
+        }
+
+        if config.rules.trailing_semicolon {
+            self.register(Box::new(TrailingSemicolon { dialect: config.dialect }));
+        }
+
+        if config.rules.explicit_aliases {
+            self.register(Box::new(ExplicitAliases {}));
+        }
+
+        if config.rules.no_env_var {
+            self.register(Box::new(NoEnvVar {}));
+        }
+
+        if config.rules.ref_aliases {
+            self.register(Box::new(RefAliases {}));
+        }
+
+        if config.rules.no_select_star {
+            self.register(Box::new(NoSelectStar { config: config.rule_options.no_select_star }));
+        }
+
+        if config.rules.hardcoded_references {
+            self.register(Box::new(HardcodedReferences { skip_cross_database: config.rules.cross_database_references }));
+        }
+
+        if config.rules.cross_database_references {
+            self.register(Box::new(CrossDatabaseReferences {}));
+        }
+
+        if config.rules.ambiguous_columns {
+            self.register(Box::new(AmbiguousColumns {}));
+        }
+
+        if config.rules.no_implicit_cross_joins {
+            self.register(Box::new(NoImplicitCrossJoins {}));
+        }
+
+        if config.rules.duplicate_column_aliases {
+            self.register(Box::new(DuplicateColumnAliases {}));
+        }
+
+        if config.rules.explicit_union {
+            self.register(Box::new(ExplicitUnion {}));
+        }
+
+        if config.rules.forbidden_functions && !config.forbidden_functions.is_empty() {
+            let project = self.project.yaml.clone();
+            self.register(Box::new(ForbiddenFunctions { functions: config.forbidden_functions.clone(), project }));
+        }
+
+        if config.rules.require_materialization {
+            let project = self.project.yaml.clone();
+            self.register(Box::new(RequireMaterialization { config: config.rule_options.require_materialization, project }));
+        }
+
+        if config.rules.incremental_filter {
+            self.register(Box::new(IncrementalFilter {}));
+        }
+
+        if config.rules.unused_incremental_filter {
+            self.register(Box::new(UnusedIncrementalFilter {}));
+        }
+
+        if config.rules.final_select_from_cte {
+            self.register(Box::new(FinalSelectFromCte {}));
+        }
+
+        if config.rules.no_select_distinct {
+            let base_path = self.project.root.clone();
+            self.register(Box::new(NoSelectDistinct { marts: config.rule_options.layered_architecture.marts.clone(), base_path }));
+        }
+
+        if config.rules.max_joins {
+            self.register(Box::new(MaxJoins { config: config.rule_options.max_joins }));
+        }
+
+        if config.rules.model_complexity {
+            self.register(Box::new(ModelComplexity { config: config.rule_options.model_complexity }));
+        }
+
+        if config.rules.no_order_by {
+            self.register(Box::new(NoOrderBy { config: config.rule_options.no_order_by.clone() }));
+        }
+
+        if config.rules.identifier_quoting {
+            let folding = config.dialect.identifier_folding();
+            self.register(Box::new(IdentifierQuoting { dialect: config.dialect, folding }));
+            self.register_project_rule(Box::new(MixedIdentifierQuoting { dialect: config.dialect, folding }));
+        }
+
+        if config.rules.quote_style {
+            let style = config.rule_options.quote_style.style.unwrap_or(config.dialect.quote_style());
+            let skip_unneeded = config.rules.identifier_quoting.then(|| config.dialect.identifier_folding());
+            self.register(Box::new(IdentifierQuoteStyle { dialect: config.dialect, style, skip_unneeded }));
+        }
+
+        if config.rules.keyword_case {
+            self.register(Box::new(KeywordCaseRule { config: config.rule_options.keyword_case }));
+        }
+
+        if config.rules.duplicate_logic {
+            self.register_project_rule(Box::new(DuplicateLogic {}));
+        }
+
+        if config.rules.exposure_validation {
+            let staging = config.rule_options.layered_architecture.staging.clone();
+            self.register_project_rule(Box::new(ExposureValidation { staging, base_path: self.project.root.clone() }));
+        }
+
+        if config.rules.semantic_layer_references {
+            self.register_project_rule(Box::new(SemanticLayerReferences {}));
+        }
+
+        if config.rules.ref_cycles {
+            self.register_project_rule(Box::new(RefCycles));
+        }
+
+        if config.rules.missing_refs {
+            self.register_project_rule(Box::new(MissingRefs));
+        }
+
+        if config.rules.undeclared_sources {
+            self.register_project_rule(Box::new(UndeclaredSources));
+        }
+
+        if config.rules.misspelled_jinja {
+            self.register_project_rule(Box::new(MisspelledJinja));
+        }
+
+        if config.rules.layered_architecture {
+            let base_path = self.project.root.clone();
+            self.register_project_rule(Box::new(LayeredArchitecture { config: config.rule_options.layered_architecture.clone(), base_path }));
+        }
+
+        for custom_rule in &config.custom_rules {
+            self.register(Box::new(CustomRule { config: custom_rule.clone() }));
         }
     }
 
-    pub fn add_rule(&mut self, rule: Box<dyn Rule>) {
+    // Adds a rule to run on every model. Crates that build on dbtonic use
+    // this, through a `Plugin`, to run their own rules alongside the
+    // built-in ones.
+    pub fn register(&mut self, rule: Box<dyn Rule>) {
         self.rules.push(rule);
     }
 
-    pub fn run_rules(&self, model_node: &ModelNode) -> Vec<(String, RuleResult)> {
-        self.rules
-            .iter()
+    pub fn register_project_rule(&mut self, rule: Box<dyn ProjectRule>) {
+        self.project_rules.push(rule);
+    }
+
+    // The names of the rules that run on each model, in the order they run
+    pub fn rule_names(&self) -> Vec<String> {
+        self.rules.iter().map(|rule| rule.name()).collect()
+    }
+
+    // The name of every rule there is, whether or not the config turns it
+    // on, project rules and the plugins' rules included
+    pub fn known_rule_names(config: &DbtonicConfig, plugins: &[Plugin]) -> Vec<String> {
+        let config = DbtonicConfig { rules: Rules::all_enabled(), ..config.clone() };
+        let rules_engine = RulesEngine::with_plugins(&config, &DbtProject::default(), plugins);
+        let project_rule_names = rules_engine.project_rules.iter().map(|rule| rule.name());
+        rules_engine.rule_names().into_iter().chain(project_rule_names).collect()
+    }
+
+    pub fn run_rules(&self, model_node: &ModelNode) -> Vec<(String, Severity, RuleResult)> {
+        self.rules_for(model_node)
             .map(|rule| {
                 let result = rule.run(model_node);
-                (rule.name(), result)
+                (rule.name(), self.severity(&rule.name(), rule.severity()), result)
             })
             .collect()
     }
-}
\ No newline at end of file
+
+    // Returns every individual violation for the model. Unlike `run_rules`, a
+    // rule can appear several times here if it fired more than once.
+    pub fn run_violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        self.run_violations_with(model_node, vec![])
+    }
+
+    // The same as `run_violations`, with the project rules' violations in
+    // the model suppressed and reported along with its own
+    pub fn run_violations_with(&self, model_node: &ModelNode, project_violations: Vec<Violation>) -> Vec<Violation> {
+        let violations = self.rules_for(model_node)
+            .flat_map(|rule| with_severity(rule.violations(model_node), self.severity(&rule.name(), rule.severity())))
+            .chain(project_violations)
+            .collect();
+        self.suppress(model_node, violations)
+    }
+
+    pub fn run_project_rules(&self, dag: &DAG) -> Vec<Violation> {
+        self.project_rules
+            .iter()
+            .flat_map(|rule| with_severity(rule.run(dag), self.severity(&rule.name(), rule.severity())))
+            .collect()
+    }
+
+    // The same as `run_violations`, along with how long each rule took. The
+    // model's suppressions aren't applied, so the caller can `suppress` them
+    // together with the project violations in the model.
+    pub fn run_violations_timed(&self, model_node: &ModelNode) -> (Vec<Violation>, Vec<RuleTiming>) {
+        time_rules(self.rules_for(model_node).map(|rule| {
+            let severity = self.severity(&rule.name(), rule.severity());
+            (rule.name(), move || with_severity(rule.violations(model_node), severity))
+        }))
+    }
+
+    // The same as `run_project_rules`, along with how long each rule took
+    pub fn run_project_rules_timed(&self, dag: &DAG) -> (Vec<Violation>, Vec<RuleTiming>) {
+        time_rules(self.project_rules.iter().map(|rule| {
+            let severity = self.severity(&rule.name(), rule.severity());
+            (rule.name(), move || with_severity(rule.run(dag), severity))
+        }))
+    }
+
+    // The edits every fixable rule has for the model, by rule
+    pub fn run_fixes(&self, model_node: &ModelNode) -> Vec<(String, Vec<TextEdit>)> {
+        self.rules_for(model_node)
+            .filter_map(|rule| rule.as_fix())
+            .map(|fix| (fix.name(), fix.fixes(model_node)))
+            .filter(|(_, edits)| !edits.is_empty())
+            .collect()
+    }
+
+    // The rules that apply to the kind of resource the model is, how it's
+    // materialized and whether anything refs it
+    fn rules_for<'a>(&'a self, model_node: &'a ModelNode) -> impl Iterator<Item = &'a Box<dyn Rule>> + 'a {
+        let materialized = materialization(model_node, &self.project.yaml).unwrap_or_else(|| "view".to_string());
+        let is_leaf = self.leaf_models.contains(&model_node.model_name);
+        let is_tested = self.tested_models.contains(&model_node.model_name);
+        self.rules.iter().filter(move |rule| {
+            rule.applies_to(model_node.resource_type)
+                && rule.applies_to_materialization(&materialized)
+                && (!is_leaf || rule.applies_to_leaf_models())
+                && (!is_tested || rule.applies_to_models_with_singular_tests())
+        })
+    }
+
+    // Drops the violations the model's `dbtonic:ignore` comments cover and
+    // reports the comments that didn't cover any
+    pub fn suppress(&self, model_node: &ModelNode, violations: Vec<Violation>) -> Vec<Violation> {
+        let suppressions = suppressions(&model_node.data.trivia);
+        if suppressions.is_empty() {
+            return violations;
+        }
+        let unused_severity = self.severity(UNUSED_SUPPRESSION, Severity::Warn);
+        apply(&suppressions, violations)
+            .into_iter()
+            .map(|violation| match violation.rule_name == UNUSED_SUPPRESSION {
+                true => violation.with_severity(unused_severity),
+                false => violation,
+            })
+            .collect()
+    }
+
+    // The severity configured for the rule, falling back to its default
+    fn severity(&self, rule_name: &str, default: Severity) -> Severity {
+        self.severities.get(rule_name).copied().unwrap_or(default)
+    }
+}
+
+fn with_severity(violations: Vec<Violation>, severity: Severity) -> Vec<Violation> {
+    violations
+        .into_iter()
+        .map(|violation| violation.with_severity(severity))
+        .collect()
+}
+
+fn time_rules<F>(rules: impl Iterator<Item = (String, F)>) -> (Vec<Violation>, Vec<RuleTiming>)
+where
+    F: FnOnce() -> Vec<Violation>,
+{
+    let mut violations = vec![];
+    let mut timings = vec![];
+    for (rule_name, run) in rules {
+        let (rule_violations, duration) = timed(run);
+        violations.extend(rule_violations);
+        timings.push(RuleTiming { rule_name, duration });
+    }
+    (violations, timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::parser::model_node::ResourceType;
+    use crate::parser::dbt_project_yaml::DbtProjectYaml;
+
+    fn project() -> DbtProject {
+        DbtProject { root: PathBuf::from("/project"), yaml: DbtProjectYaml::default() }
+    }
+
+    fn severities(config: &DbtonicConfig, sql: &str) -> Vec<(String, Severity)> {
+        let mut rules_engine = RulesEngine { rules: vec![], project_rules: vec![], severities: config.severity.clone(), project: DbtProject::default(), leaf_models: HashSet::new(), tested_models: HashSet::new() };
+        rules_engine.register(Box::new(MultipleStatements));
+        rules_engine.register(Box::new(ExplicitAliases {}));
+
+        rules_engine
+            .run_violations(&ModelNode::from_test_sql("test_model", sql))
+            .into_iter()
+            .map(|violation| (violation.rule_name, violation.severity))
+            .collect()
+    }
+
+    #[test]
+    fn test_violations_take_the_rule_severity() {
+        assert_eq!(
+            severities(&DbtonicConfig::default(), "SELECT 1; SELECT COUNT(id) FROM orders"),
+            vec![
+                ("multiple_statements".to_string(), Severity::Error),
+                ("explicit_aliases".to_string(), Severity::Warn),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_configured_severity_wins() {
+        let mut config = DbtonicConfig::default();
+        config.severity.insert("multiple_statements".to_string(), Severity::Info);
+        config.severity.insert("explicit_aliases".to_string(), Severity::Error);
+
+        assert_eq!(
+            severities(&config, "SELECT 1; SELECT COUNT(id) FROM orders"),
+            vec![
+                ("multiple_statements".to_string(), Severity::Info),
+                ("explicit_aliases".to_string(), Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_model_only_rules_skip_singular_tests() {
+        let rules_engine = RulesEngine::create(&DbtonicConfig::default(), &DbtProject::default());
+        let mut singular_test = ModelNode::from_test_sql("test_model", "SELECT id FROM {{ ref('orders') }} AS orders WHERE amount < 0");
+        singular_test.resource_type = ResourceType::SingularTest;
+
+        let rule_names: Vec<String> = rules_engine
+            .run_violations(&singular_test)
+            .into_iter()
+            .map(|violation| violation.rule_name)
+            .collect();
+        assert!(rule_names.is_empty(), "{:?}", rule_names);
+
+        let rule_names: Vec<String> = rules_engine
+            .run_violations(&ModelNode::from_test_sql("test_model", "SELECT id FROM {{ ref('orders') }} AS orders WHERE amount < 0"))
+            .into_iter()
+            .map(|violation| violation.rule_name)
+            .collect();
+        assert!(rule_names.contains(&"yaml_exists".to_string()));
+    }
+
+    #[test]
+    fn test_models_use_the_engine_for_their_overrides() {
+        let config: DbtonicConfig = toml::from_str(
+            r#"
+[rules]
+unique_not_null_or_combination_rule = false
+model_yaml_exists = false
+
+[overrides."models/staging/**".rules]
+explicit_aliases = false
+"#,
+        )
+        .unwrap();
+
+        let mut staging = ModelNode::from_test_sql("test_model", "SELECT COUNT(id) FROM orders");
+        staging.path = PathBuf::from("/project/models/staging/stg_orders.sql");
+        let mut marts = ModelNode::from_test_sql("test_model", "SELECT COUNT(id) FROM orders");
+        marts.path = PathBuf::from("/project/models/marts/orders.sql");
+
+        let rules_engines = RulesEngines::create(&config, &[], &project(), &[&staging, &marts]);
+        let rule_names = |model_node: &ModelNode| -> Vec<String> {
+            rules_engines
+                .for_model(model_node)
+                .run_violations(model_node)
+                .into_iter()
+                .map(|violation| violation.rule_name)
+                .collect()
+        };
+
+        assert!(!rule_names(&staging).contains(&"explicit_aliases".to_string()));
+        assert!(rule_names(&marts).contains(&"explicit_aliases".to_string()));
+    }
+
+    #[test]
+    fn test_compiled_sql_skips_rules_about_the_source() {
+        let model_node = ModelNode::from_test_sql("test_model", "select id from raw.orders");
+        let rules_engines = RulesEngines::create(&DbtonicConfig::default(), &[], &project(), &[&model_node]);
+        let rule_names = |rules_engines: &RulesEngines| -> Vec<String> {
+            rules_engines
+                .for_model(&model_node)
+                .run_violations(&model_node)
+                .into_iter()
+                .map(|violation| violation.rule_name)
+                .collect()
+        };
+
+        assert!(rule_names(&rules_engines).contains(&"hardcoded_references".to_string()));
+        let rule_names = rule_names(&rules_engines.for_compiled_sql());
+        assert!(!rule_names.contains(&"hardcoded_references".to_string()));
+        assert!(rule_names.contains(&"yaml_exists".to_string()));
+    }
+
+    #[test]
+    fn test_rules_only_run_on_the_materializations_they_apply_to() {
+        let rules_engine = RulesEngine::create(&DbtonicConfig::default(), &DbtProject::default());
+        let rule_names = |sql: &str| -> Vec<String> {
+            rules_engine.run_rules(&ModelNode::from_test_sql("test_model", sql)).into_iter().map(|(rule_name, _, _)| rule_name).collect()
+        };
+
+        let incremental = rule_names("{{ config(materialized='incremental') }} SELECT id FROM {{ ref('events') }}");
+        assert!(incremental.contains(&"incremental_filter".to_string()));
+        assert!(!incremental.contains(&"unused_incremental_filter".to_string()));
+
+        let view = rule_names("SELECT id FROM {{ ref('events') }}");
+        assert!(!view.contains(&"incremental_filter".to_string()));
+        assert!(view.contains(&"unused_incremental_filter".to_string()));
+    }
+
+    #[test]
+    fn test_engines_use_the_project_they_are_given() {
+        let yaml: DbtProjectYaml = serde_yaml::from_str("name: jaffle_shop\nmodels:\n  jaffle_shop:\n    +materialized: incremental\n").unwrap();
+        let project = DbtProject { root: PathBuf::from("/project"), yaml };
+        let mut model_node = ModelNode::from_test_sql("events", "SELECT id FROM {{ ref('raw_events') }}");
+        model_node.path = PathBuf::from("/project/models/events.sql");
+
+        let rules_engines = RulesEngines::create(&DbtonicConfig::default(), &[], &project, &[&model_node]);
+        let rule_names: Vec<String> =
+            rules_engines.for_model(&model_node).run_rules(&model_node).into_iter().map(|(rule_name, _, _)| rule_name).collect();
+        assert!(rule_names.contains(&"incremental_filter".to_string()));
+    }
+
+    #[test]
+    fn test_layers_are_relative_to_the_project_root() {
+        let mut model_node = ModelNode::from_test_sql("stg_orders", "SELECT id FROM {{ ref('raw_orders') }}");
+        model_node.path = PathBuf::from("/project/models/staging/stg_orders.sql");
+        let model_nodes = vec![model_node];
+        let dag = DAG::from_test_models(model_nodes);
+
+        let mut config = DbtonicConfig::default();
+        config.rules.layered_architecture = true;
+        let rule_names: Vec<String> = RulesEngine::create(&config, &project())
+            .run_project_rules(&dag)
+            .into_iter()
+            .map(|violation| violation.rule_name)
+            .collect();
+        assert!(rule_names.contains(&"layered_architecture".to_string()), "{:?}", rule_names);
+    }
+
+    #[test]
+    fn test_marts_are_relative_to_the_project_root() {
+        let mut model_node = ModelNode::from_test_sql("orders", "SELECT DISTINCT id FROM {{ ref('stg_orders') }}");
+        model_node.path = PathBuf::from("/project/models/marts/orders.sql");
+
+        let mut config = DbtonicConfig::default();
+        config.rules.no_select_distinct = true;
+        let rule_names: Vec<String> = RulesEngine::create(&config, &project())
+            .run_violations(&model_node)
+            .into_iter()
+            .map(|violation| violation.rule_name)
+            .collect();
+        assert!(rule_names.contains(&"no_select_distinct".to_string()), "{:?}", rule_names);
+    }
+
+    #[test]
+    fn test_leaf_models_skip_the_rules_about_dependents() {
+        let orders = ModelNode::from_test_sql("orders", "SELECT id FROM {{ ref('stg_orders') }} ORDER BY id");
+        let report = ModelNode::from_test_sql("orders_report", "SELECT id FROM {{ ref('orders') }} ORDER BY id");
+        let lineage = Lineage::from_models([&orders, &report]);
+        let rules_engines = RulesEngines::create(&DbtonicConfig::default(), &[], &project(), &[&orders, &report]).with_lineage(&lineage);
+        let rule_names = |model_node: &ModelNode| -> Vec<String> {
+            rules_engines.for_model(model_node).run_violations(model_node).into_iter().map(|violation| violation.rule_name).collect()
+        };
+
+        assert!(rule_names(&orders).contains(&"no_order_by".to_string()));
+        assert!(!rule_names(&report).contains(&"no_order_by".to_string()));
+        assert!(rules_engines.is_leaf_model(&report));
+    }
+
+    #[test]
+    fn test_models_with_singular_tests_have_tests() {
+        let orders = ModelNode::from_test_sql("orders", "SELECT id FROM {{ ref('stg_orders') }} AS stg_orders");
+        let mut assert_orders = ModelNode::from_test_sql("assert_orders_have_ids", "SELECT id FROM {{ ref('orders') }} WHERE id IS NULL");
+        assert_orders.resource_type = ResourceType::SingularTest;
+        let rules_engines = RulesEngines::create(&DbtonicConfig::default(), &[], &project(), &[&orders]);
+        let has_tests_violation = |rules_engines: &RulesEngines| {
+            rules_engines.for_model(&orders).run_violations(&orders).iter().any(|violation| violation.rule_name == "model_has_tests")
+        };
+
+        assert!(has_tests_violation(&rules_engines));
+        assert!(!has_tests_violation(&rules_engines.with_singular_tests(&[assert_orders])));
+    }
+
+    #[test]
+    fn test_model_suppressions_cover_project_violations() {
+        let model_node = ModelNode::from_test_sql("orders", "SELECT id\n-- dbtonic:ignore missing_refs\nFROM {{ ref('nope') }} AS n");
+        let missing_ref = Violation::new("missing_refs".to_string(), "The model refs 'nope'.".to_string(), Some(Location { line: 3, column: 8 }))
+            .in_model(&model_node);
+
+        let rules_engine = RulesEngine::create(&DbtonicConfig::default(), &project());
+        assert!(rules_engine.suppress(&model_node, vec![missing_ref]).is_empty());
+    }
+}
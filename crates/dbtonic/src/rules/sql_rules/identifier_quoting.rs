@@ -0,0 +1,179 @@
+use dbtranslate::keywords::ALL_KEYWORDS;
+use dbtranslate::tokenizer::Tokenizer;
+use dbtranslate::tokens::{Location, Token, TokenWithLocation, Word};
+use crate::configuration::dbtonic_config::{IdentifierFolding, SqlDialect};
+use crate::rules::edits::TextEdit;
+use crate::rules::jinja_tokens::outside_jinja;
+use crate::rules::rules_engine::{Fix, Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+
+// Flags quoted identifiers that would mean the same thing unquoted, e.g.
+// `"orders"` on a warehouse that folds identifiers to lower case. The fix
+// removes the quotes. Only the quotes the dialect reads as identifiers are
+// looked at, so a BigQuery string in double quotes is left alone.
+pub struct IdentifierQuoting {
+    pub dialect: SqlDialect,
+    pub folding: IdentifierFolding,
+}
+
+impl Rule for IdentifierQuoting {
+    fn name(&self) -> String {
+        "identifier_quoting".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Identifiers should only be quoted when the quotes change their meaning.".to_string()
+    }
+
+    // The quotes to fix are in the source, not the ones dbt renders
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        identifiers(&model_node.data.sql, self.dialect)
+            .into_iter()
+            .filter(|(word, _)| word.quote_style.is_some() && !quotes_needed(&word.value, self.folding))
+            .map(|(word, location)| {
+                let edit = TextEdit {
+                    end: Location {
+                        line: location.line,
+                        column: location.column + word.to_string().chars().count() as u64,
+                    },
+                    start: location.clone(),
+                    replacement: word.value.to_string(),
+                };
+                Violation::new(
+                    self.name(),
+                    format!("The identifier {} doesn't need quotes. Use {} instead.", word, word.value),
+                    Some(location),
+                )
+                .with_end_location(edit.end.clone())
+                .with_edits(vec![edit])
+            })
+            .collect()
+    }
+
+    fn as_fix(&self) -> Option<&dyn Fix> {
+        Some(self)
+    }
+}
+
+impl Fix for IdentifierQuoting {}
+
+// Every identifier in the SQL along with where it starts, as the dialect
+// tokenizes it. Jinja is skipped, so the strings in `ref("orders")` aren't
+// read as identifiers. The words are owned since the tokens also borrow the
+// dialect, which is made here.
+pub fn identifiers(sql: &str, dialect: SqlDialect) -> Vec<(Word<'static>, Location)> {
+    let dialect = dialect.parser_dialect();
+    let tokens: Vec<TokenWithLocation> = Tokenizer::new(&*dialect, sql)
+        .tokenize_with_location()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|token| !matches!(token.token, Token::Whitespace(_)))
+        .collect();
+
+    outside_jinja(tokens)
+        .into_iter()
+        .filter_map(|token| match token.token {
+            Token::Word(word) => Some((word.into_owned(), token.location)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Quotes are needed when the identifier isn't a plain word, clashes with a
+// keyword, or is written in a case the warehouse would fold away
+pub fn quotes_needed(value: &str, folding: IdentifierFolding) -> bool {
+    let mut chars = value.chars();
+    let is_plain_word = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_plain_word {
+        return true;
+    }
+
+    if ALL_KEYWORDS.binary_search(&value.to_uppercase().as_str()).is_ok() {
+        return true;
+    }
+
+    match folding {
+        IdentifierFolding::Lower => value != value.to_lowercase(),
+        IdentifierFolding::Upper => value != value.to_uppercase(),
+        IdentifierFolding::None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::configuration::dbtonic_config::ParsingConfig;
+    use crate::rules::edits::apply_edits;
+
+    #[test]
+    fn test_quotes_needed() {
+        assert!(!quotes_needed("orders", IdentifierFolding::Lower));
+        assert!(quotes_needed("Orders", IdentifierFolding::Lower));
+        assert!(quotes_needed("orders", IdentifierFolding::Upper));
+        assert!(!quotes_needed("ORDERS", IdentifierFolding::Upper));
+        assert!(!quotes_needed("Orders", IdentifierFolding::None));
+        assert!(quotes_needed("order", IdentifierFolding::Lower));
+        assert!(quotes_needed("order id", IdentifierFolding::Lower));
+    }
+
+    #[test]
+    fn test_unnecessary_quotes_are_fixed() {
+        let sql = "SELECT \"id\", \"Amount\"\nFROM \"orders\"";
+        let model_node = ModelNode::from_test_sql("test_model", sql);
+        let rule = IdentifierQuoting { dialect: SqlDialect::Generic, folding: IdentifierFolding::Lower };
+
+        let violations = rule.violations(&model_node);
+        let messages: Vec<&str> = violations.iter().map(|v| v.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "The identifier \"id\" doesn't need quotes. Use id instead.",
+                "The identifier \"orders\" doesn't need quotes. Use orders instead.",
+            ]
+        );
+
+        let edits: Vec<TextEdit> = violations.into_iter().flat_map(|v| v.edits).collect();
+        assert_eq!(apply_edits(sql, &edits), "SELECT id, \"Amount\"\nFROM orders");
+    }
+
+    #[test]
+    fn test_strings_in_the_dialect_are_left_alone() {
+        // BigQuery reads `"paid"` as a string, so removing its quotes would
+        // turn it into a column
+        let sql = "SELECT `id` FROM orders WHERE status = \"paid\"";
+        let model_node = ModelNode::from_sql(PathBuf::from("models/test_model.sql"), sql.to_string(), &ParsingConfig::default(), SqlDialect::Bigquery);
+        let rule = IdentifierQuoting { dialect: SqlDialect::Bigquery, folding: IdentifierFolding::None };
+
+        let edits: Vec<TextEdit> = rule.violations(&model_node).into_iter().flat_map(|v| v.edits).collect();
+        assert_eq!(apply_edits(sql, &edits), "SELECT id FROM orders WHERE status = \"paid\"");
+    }
+
+    #[test]
+    fn test_jinja_strings_are_left_alone() {
+        // Generic reads double quotes as identifiers, which the strings in
+        // Jinja aren't
+        let sql = "{{ config(schema=\"marts\") }}\n{%- set pay = \"credit\" -%}\nSELECT \"id\" FROM {{ ref(\"b\") }}";
+        let model_node = ModelNode::from_test_sql("test_model", sql);
+        let rule = IdentifierQuoting { dialect: SqlDialect::Generic, folding: IdentifierFolding::Lower };
+
+        let edits: Vec<TextEdit> = rule.violations(&model_node).into_iter().flat_map(|v| v.edits).collect();
+        assert_eq!(
+            apply_edits(sql, &edits),
+            "{{ config(schema=\"marts\") }}\n{%- set pay = \"credit\" -%}\nSELECT id FROM {{ ref(\"b\") }}"
+        );
+    }
+}
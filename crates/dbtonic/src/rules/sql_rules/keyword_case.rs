@@ -0,0 +1,178 @@
+use dbtranslate::dialect::GenericDialect;
+use dbtranslate::tokenizer::Tokenizer;
+use dbtranslate::tokens::{Location, Token, TokenWithLocation};
+use crate::configuration::dbtonic_config::{KeywordCase, KeywordsConfig};
+use crate::rules::edits::TextEdit;
+use crate::rules::jinja_tokens::outside_jinja;
+use crate::rules::rules_engine::{Fix, Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+
+// The keywords whose case is checked. Words that are often column names
+// too, like `date` or `first`, are left out.
+const KEYWORDS: &[&str] = &[
+    "ALL", "AND", "AS", "ASC", "BETWEEN", "BY", "CASE", "CROSS", "DESC", "DISTINCT", "ELSE", "END", "EXCEPT",
+    "EXISTS", "FALSE", "FROM", "FULL", "GROUP", "HAVING", "ILIKE", "IN", "INNER", "INTERSECT", "INTERVAL", "IS",
+    "JOIN", "LATERAL", "LEFT", "LIKE", "LIMIT", "NOT", "NULL", "NULLS", "ON", "OR", "ORDER", "OUTER", "OVER",
+    "PARTITION", "QUALIFY", "RIGHT", "SELECT", "THEN", "TRUE", "UNION", "USING", "WHEN", "WHERE", "WITH",
+];
+
+// Flags keywords written in another case than the configured one, or, by
+// default, than the model's first keyword. Jinja is skipped, so the `in`
+// of a `{% for %}` loop is left alone. The fix rewrites the keyword.
+pub struct KeywordCaseRule {
+    pub config: KeywordsConfig,
+}
+
+impl Rule for KeywordCaseRule {
+    fn name(&self) -> String {
+        "keyword_case".to_string()
+    }
+
+    fn description(&self) -> String {
+        "SQL keywords should all be written in the same case.".to_string()
+    }
+
+    // Style is fixed in the source, not in what dbt rendered
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let keywords = keywords(&model_node.data.sql);
+        let upper = match self.config.case {
+            KeywordCase::Upper => true,
+            KeywordCase::Lower => false,
+            KeywordCase::Consistent => {
+                let first = keywords.iter().find(|(keyword, _)| {
+                    *keyword == keyword.to_uppercase() || *keyword == keyword.to_lowercase()
+                });
+                match first {
+                    Some((keyword, _)) => *keyword == keyword.to_uppercase(),
+                    None => return vec![],
+                }
+            }
+        };
+        let (case, because) = match (upper, self.config.case) {
+            (true, KeywordCase::Consistent) => ("upper", " like the model's first keyword"),
+            (false, KeywordCase::Consistent) => ("lower", " like the model's first keyword"),
+            (true, _) => ("upper", ""),
+            (false, _) => ("lower", ""),
+        };
+
+        keywords
+            .into_iter()
+            .filter_map(|(keyword, location)| {
+                let replacement = if upper { keyword.to_uppercase() } else { keyword.to_lowercase() };
+                if keyword == replacement {
+                    return None;
+                }
+                let edit = TextEdit {
+                    end: Location { line: location.line, column: location.column + keyword.chars().count() as u64 },
+                    start: location.clone(),
+                    replacement: replacement.clone(),
+                };
+                let violation = Violation::new(
+                    self.name(),
+                    format!("The keyword `{}` should be written in {} case{}, `{}`.", keyword, case, because, replacement),
+                    Some(location),
+                )
+                .with_end_location(edit.end.clone())
+                .with_edits(vec![edit]);
+                Some(violation)
+            })
+            .collect()
+    }
+
+    fn as_fix(&self) -> Option<&dyn Fix> {
+        Some(self)
+    }
+}
+
+impl Fix for KeywordCaseRule {}
+
+// The keywords in the SQL, as written, and where each starts. Words in
+// Jinja, quoted words and the parts of qualified names like `o.order` don't
+// count, and neither do `left(` and `right(`, which are function calls.
+fn keywords(sql: &str) -> Vec<(String, Location)> {
+    let tokens: Vec<TokenWithLocation> = Tokenizer::new(&GenericDialect {}, sql)
+        .tokenize_with_location()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|token| !matches!(token.token, Token::Whitespace(_)))
+        .collect();
+    let tokens = outside_jinja(tokens);
+
+    let mut keywords = vec![];
+    for (index, token) in tokens.iter().enumerate() {
+        let previous = index.checked_sub(1).map(|index| &tokens[index].token);
+        let next = tokens.get(index + 1).map(|token| &token.token);
+        if let Token::Word(word) = &token.token {
+            let upper = word.value.to_uppercase();
+            let qualified = previous == Some(&Token::Period) || next == Some(&Token::Period);
+            let function = (upper == "LEFT" || upper == "RIGHT") && next == Some(&Token::LParen);
+            if word.quote_style.is_none() && !qualified && !function && KEYWORDS.contains(&upper.as_str()) {
+                keywords.push((word.value.to_string(), token.location.clone()));
+            }
+        }
+    }
+    keywords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::edits::apply_edits;
+
+    #[test]
+    fn test_keywords_skip_jinja_and_qualified_names() {
+        let sql = "{# select in #}\nselect o.order, left(name, 1) as initial\n\
+                   from {{ ref('orders') }} as o\n{% for status in ['paid'] %} where true {% endfor %}";
+        let keywords: Vec<String> = keywords(sql).into_iter().map(|(keyword, _)| keyword).collect();
+        assert_eq!(keywords, vec!["select", "as", "from", "as", "where", "true"]);
+    }
+
+    #[test]
+    fn test_consistent_keywords_pass() {
+        let rule = KeywordCaseRule { config: KeywordsConfig::default() };
+        assert_eq!(rule.run(&ModelNode::from_test_sql("test_model", "SELECT id FROM orders WHERE id IS NOT NULL")), RuleResult::Pass);
+        assert_eq!(rule.run(&ModelNode::from_test_sql("test_model", "select id from orders where id is not null")), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_inconsistent_keywords_are_fixed() {
+        let sql = "SELECT id\nfrom orders Where id > 1";
+        let rule = KeywordCaseRule { config: KeywordsConfig::default() };
+
+        let violations = rule.violations(&ModelNode::from_test_sql("test_model", sql));
+        let messages: Vec<&str> = violations.iter().map(|v| v.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "The keyword `from` should be written in upper case like the model's first keyword, `FROM`.",
+                "The keyword `Where` should be written in upper case like the model's first keyword, `WHERE`.",
+            ]
+        );
+
+        let edits: Vec<TextEdit> = violations.into_iter().flat_map(|v| v.edits).collect();
+        assert_eq!(apply_edits(sql, &edits), "SELECT id\nFROM orders WHERE id > 1");
+    }
+
+    #[test]
+    fn test_configured_case() {
+        let sql = "SELECT id FROM orders";
+        let rule = KeywordCaseRule { config: KeywordsConfig { case: KeywordCase::Lower } };
+
+        let violations = rule.violations(&ModelNode::from_test_sql("test_model", sql));
+        assert_eq!(violations[0].message, "The keyword `SELECT` should be written in lower case, `select`.");
+        let edits: Vec<TextEdit> = violations.into_iter().flat_map(|v| v.edits).collect();
+        assert_eq!(apply_edits(sql, &edits), "select id from orders");
+    }
+}
@@ -0,0 +1,5 @@
+pub mod identifier_quoting;
+pub mod ref_aliases;
+pub mod quote_style;
+pub mod keyword_case;
+pub mod trailing_semicolon;
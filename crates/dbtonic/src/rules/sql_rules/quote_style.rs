@@ -0,0 +1,142 @@
+use dbtranslate::tokens::Location;
+use crate::configuration::dbtonic_config::{IdentifierFolding, QuoteStyle, SqlDialect};
+use crate::rules::sql_rules::identifier_quoting::{identifiers, quotes_needed};
+use crate::rules::edits::TextEdit;
+use crate::rules::rules_engine::{Fix, Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+
+// Flags quoted identifiers written with other quotes than the project's,
+// e.g. `` `orders` `` in a project that uses `"orders"`. Only the quotes the
+// dialect reads as identifiers outside Jinja are looked at, so a BigQuery
+// string in double quotes or the name in `ref("orders")` is left alone. The
+// fix swaps the quotes.
+pub struct IdentifierQuoteStyle {
+    pub dialect: SqlDialect,
+    pub style: QuoteStyle,
+    // Set while identifier_quoting is on, which reports the identifiers that
+    // don't need quotes at all
+    pub skip_unneeded: Option<IdentifierFolding>,
+}
+
+impl Rule for IdentifierQuoteStyle {
+    fn name(&self) -> String {
+        "quote_style".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Quoted identifiers should use the same quotes across the project.".to_string()
+    }
+
+    // dbt picks the quotes for the names it renders refs to
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        let quote = self.style.quote();
+        identifiers(&model_node.data.sql, self.dialect)
+            .into_iter()
+            // An identifier with the project's quote in it can't be requoted
+            .filter(|(word, _)| {
+                word.quote_style.is_some_and(|style| style != quote && style != '[') && !word.value.contains(quote)
+            })
+            .filter(|(word, _)| self.skip_unneeded.is_none_or(|folding| quotes_needed(&word.value, folding)))
+            .map(|(word, location)| {
+                let replacement = format!("{}{}{}", quote, word.value, quote);
+                let edit = TextEdit {
+                    end: Location {
+                        line: location.line,
+                        column: location.column + word.to_string().chars().count() as u64,
+                    },
+                    start: location.clone(),
+                    replacement: replacement.clone(),
+                };
+                Violation::new(
+                    self.name(),
+                    format!(
+                        "The identifier {} is quoted differently from the rest of the project. Use {} instead.",
+                        word, replacement
+                    ),
+                    Some(location),
+                )
+                .with_end_location(edit.end.clone())
+                .with_edits(vec![edit])
+            })
+            .collect()
+    }
+
+    fn as_fix(&self) -> Option<&dyn Fix> {
+        Some(self)
+    }
+}
+
+impl Fix for IdentifierQuoteStyle {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::edits::apply_edits;
+
+    #[test]
+    fn test_other_quotes_are_fixed() {
+        let sql = "SELECT `Amount`, \"Status\"\nFROM `raw`.`Orders`";
+        let rule = IdentifierQuoteStyle { dialect: SqlDialect::Generic, style: QuoteStyle::Double, skip_unneeded: None };
+
+        let violations = rule.violations(&ModelNode::from_test_sql("test_model", sql));
+        let messages: Vec<&str> = violations.iter().map(|v| v.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "The identifier `Amount` is quoted differently from the rest of the project. Use \"Amount\" instead.",
+                "The identifier `raw` is quoted differently from the rest of the project. Use \"raw\" instead.",
+                "The identifier `Orders` is quoted differently from the rest of the project. Use \"Orders\" instead.",
+            ]
+        );
+
+        let edits: Vec<TextEdit> = violations.into_iter().flat_map(|v| v.edits).collect();
+        assert_eq!(apply_edits(sql, &edits), "SELECT \"Amount\", \"Status\"\nFROM \"raw\".\"Orders\"");
+    }
+
+    #[test]
+    fn test_strings_in_the_dialect_are_left_alone() {
+        let sql = "SELECT `Amount` FROM `raw.orders` WHERE status = \"paid\"";
+        let rule = IdentifierQuoteStyle { dialect: SqlDialect::Bigquery, style: QuoteStyle::Backtick, skip_unneeded: None };
+        assert_eq!(rule.run(&ModelNode::from_test_sql("test_model", sql)), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_unneeded_quotes_are_left_to_identifier_quoting() {
+        let sql = "SELECT `amount`, `Status` FROM orders";
+        let rule = IdentifierQuoteStyle {
+            dialect: SqlDialect::Generic,
+            style: QuoteStyle::Double,
+            skip_unneeded: Some(IdentifierFolding::Lower),
+        };
+
+        let messages: Vec<String> = rule.violations(&ModelNode::from_test_sql("test_model", sql)).into_iter().map(|v| v.message).collect();
+        assert_eq!(
+            messages,
+            vec!["The identifier `Status` is quoted differently from the rest of the project. Use \"Status\" instead.".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_jinja_strings_are_left_alone() {
+        let sql = "{{ config(schema=`marts`) }}\n{% set pay = `credit` %}\nSELECT `Amount` FROM {{ ref(`b`) }}";
+        let rule = IdentifierQuoteStyle { dialect: SqlDialect::Generic, style: QuoteStyle::Double, skip_unneeded: None };
+
+        let edits: Vec<TextEdit> = rule.violations(&ModelNode::from_test_sql("test_model", sql)).into_iter().flat_map(|v| v.edits).collect();
+        assert_eq!(
+            apply_edits(sql, &edits),
+            "{{ config(schema=`marts`) }}\n{% set pay = `credit` %}\nSELECT \"Amount\" FROM {{ ref(`b`) }}"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use dbtranslate::dialect::GenericDialect;
+use dbtranslate::keywords::{Keyword, RESERVED_FOR_TABLE_ALIAS};
+use dbtranslate::tokenizer::Tokenizer;
+use dbtranslate::tokens::{Location, Token, TokenWithLocation};
+use crate::rules::edits::{byte_offset, TextEdit};
+use crate::rules::rules_engine::{Fix, Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+
+// Flags `{{ ref() }}` and `{{ source() }}` relations that are joined to
+// something else but have no alias, so their columns can be qualified with
+// a name that doesn't change when the model does. A lone relation, like the
+// one in an import CTE, doesn't need one. The fix aliases them as the model
+// or table name.
+pub struct RefAliases;
+
+impl Rule for RefAliases {
+    fn name(&self) -> String {
+        "ref_aliases".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Every ref() and source() that is joined to another relation must have an alias.".to_string()
+    }
+
+    // There are no refs left to alias once compiled
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        unaliased_relations(&model_node.data.sql)
+            .into_iter()
+            .map(|relation| {
+                let edit = TextEdit {
+                    start: relation.end.clone(),
+                    end: relation.end.clone(),
+                    replacement: format!(" AS {}", relation.alias),
+                };
+                Violation::new(
+                    self.name(),
+                    format!("`{}` has no alias. Add `AS {}`.", relation.text, relation.alias),
+                    Some(relation.start),
+                )
+                .with_end_location(relation.end)
+                .with_edits(vec![edit])
+            })
+            .collect()
+    }
+
+    fn as_fix(&self) -> Option<&dyn Fix> {
+        Some(self)
+    }
+}
+
+impl Fix for RefAliases {}
+
+// A `{{ ref() }}` or `{{ source() }}` that isn't followed by an alias
+struct UnaliasedRelation {
+    text: String,
+    // The alias the fix adds: the last argument, i.e. the model or table name
+    alias: String,
+    start: Location,
+    // Just after the closing `}}`
+    end: Location,
+}
+
+fn unaliased_relations(sql: &str) -> Vec<UnaliasedRelation> {
+    let tokens: Vec<TokenWithLocation> = Tokenizer::new(&GenericDialect {}, sql)
+        .tokenize_with_location()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|token| !matches!(token.token, Token::Whitespace(_)))
+        .collect();
+
+    let mut relations = vec![];
+    for (i, token) in tokens.iter().enumerate() {
+        let is_relation = token.token == Token::DoubleLBrace
+            && matches!(tokens.get(i + 1).map(|t| &t.token), Some(Token::Word(w)) if w.value == "ref" || w.value == "source");
+        if !is_relation {
+            continue;
+        }
+
+        let Some(close) = tokens[i..].iter().position(|t| t.token == Token::DoubleRBrace).map(|close| i + close) else {
+            continue;
+        };
+        let alias = tokens[i..close].iter().rev().find_map(|t| match &t.token {
+            Token::SingleQuotedString(s) | Token::DoubleQuotedString(s) => Some(s.to_string()),
+            _ => None,
+        });
+        let Some(alias) = alias else {
+            continue;
+        };
+
+        let previous = i.checked_sub(1).map(|previous| &tokens[previous].token);
+        let next = tokens.get(close + 1).map(|t| &t.token);
+        if !has_alias(next) && (is_join_keyword(previous) || is_join_keyword(next)) {
+            let closing = &tokens[close];
+            let start = token.location.clone();
+            let end = Location { line: closing.location.line, column: closing.location.column + 2 };
+            let text = match (byte_offset(sql, &start), byte_offset(sql, &end)) {
+                (Some(start), Some(end)) => sql[start..end].to_string(),
+                _ => continue,
+            };
+            relations.push(UnaliasedRelation { text, alias, start, end });
+        }
+    }
+    relations
+}
+
+// Whether the token after a relation starts an alias, i.e. `AS` or a name
+// that isn't the start of the next clause
+fn has_alias(next: Option<&Token>) -> bool {
+    match next {
+        Some(Token::Word(w)) => w.keyword == Keyword::AS || !RESERVED_FOR_TABLE_ALIAS.contains(&w.keyword),
+        _ => false,
+    }
+}
+
+// Whether the token next to a relation means it's part of a join, e.g.
+// `JOIN {{ ref('a') }}` or `{{ ref('a') }} LEFT JOIN`
+fn is_join_keyword(token: Option<&Token>) -> bool {
+    match token {
+        Some(Token::Word(w)) => matches!(
+            w.keyword,
+            Keyword::JOIN | Keyword::INNER | Keyword::LEFT | Keyword::RIGHT | Keyword::FULL | Keyword::CROSS | Keyword::NATURAL
+        ),
+        Some(Token::Comma) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::edits::{apply_edits, fix_edits};
+
+    #[test]
+    fn test_aliased_and_lone_relations_pass() {
+        let model_node = ModelNode::from_test_sql(
+            "test_model",
+            "WITH customers AS (SELECT * FROM {{ ref('customers') }}) \
+             SELECT o.id FROM {{ ref('orders') }} AS o JOIN {{ source('stripe', 'payments') }} p ON o.id = p.order_id",
+        );
+        assert_eq!(RefAliases.run(&model_node), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_unaliased_relations_are_fixed() {
+        let sql = "SELECT id FROM {{ ref('orders') }}\nLEFT JOIN {{ source('stripe', 'payments') }} ON id = order_id";
+        let model_node = ModelNode::from_test_sql("test_model", sql);
+
+        let violations = RefAliases.violations(&model_node);
+        let messages: Vec<&str> = violations.iter().map(|v| v.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`{{ ref('orders') }}` has no alias. Add `AS orders`.",
+                "`{{ source('stripe', 'payments') }}` has no alias. Add `AS payments`.",
+            ]
+        );
+
+        assert_eq!(
+            apply_edits(sql, &fix_edits(RefAliases.violations(&model_node))),
+            "SELECT id FROM {{ ref('orders') }} AS orders\nLEFT JOIN {{ source('stripe', 'payments') }} AS payments ON id = order_id"
+        );
+    }
+}
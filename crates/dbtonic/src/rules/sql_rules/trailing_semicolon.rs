@@ -0,0 +1,206 @@
+use dbtranslate::tokenizer::{Tokenizer, TokenizerError};
+use dbtranslate::tokens::{Location, Token, TokenWithLocation};
+use crate::configuration::dbtonic_config::SqlDialect;
+use crate::rules::edits::TextEdit;
+use crate::rules::jinja_tokens::is_sharp;
+use crate::rules::rules_engine::{Fix, Rule, RuleResult, Severity, Violation};
+use crate::parser::model_node::ModelNode;
+
+// Flags semicolons in a model, e.g. a trailing `;` left over from running
+// the query by hand. dbt wraps the model's SELECT in its own statement, so
+// most adapters fail on one. The fix removes them. The semicolons between
+// statements are multiple_statements' to report.
+pub struct TrailingSemicolon {
+    // The dialect the model is tokenized with, which decides what's a
+    // string or a comment
+    pub dialect: SqlDialect,
+}
+
+impl Rule for TrailingSemicolon {
+    fn name(&self) -> String {
+        "trailing_semicolon".to_string()
+    }
+
+    fn description(&self) -> String {
+        "The model must not contain semicolons, since dbt wraps the model's SELECT in its own statement.".to_string()
+    }
+
+    // The semicolons to remove are in the source
+    fn applies_to_compiled(&self) -> bool {
+        false
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        RuleResult::from_violations(self.violations(model_node))
+    }
+
+    fn violations(&self, model_node: &ModelNode) -> Vec<Violation> {
+        if !model_node.data.sql.contains(';') {
+            return vec![];
+        }
+        // The parser reads the model with the same tokenizer, so a model it
+        // can't tokenize is already reported as skipped
+        let Ok(semicolons) = semicolons(&model_node.data.sql, self.dialect) else {
+            return vec![];
+        };
+        let separate_statements = model_node.data.ast.len() > 1;
+
+        semicolons
+            .into_iter()
+            .filter(|(_, trailing)| *trailing || !separate_statements)
+            .map(|(location, trailing)| {
+                let message = match trailing {
+                    true => "The model ends with a semicolon, which will fail when dbt wraps it in its own statement.",
+                    false => "The model contains a semicolon, which will fail when dbt wraps it in its own statement.",
+                };
+                let edit = TextEdit {
+                    start: location.clone(),
+                    end: Location { line: location.line, column: location.column + 1 },
+                    replacement: String::new(),
+                };
+                Violation::new(self.name(), message.to_string(), Some(location))
+                    .with_end_location(edit.end.clone())
+                    .with_edits(vec![edit])
+            })
+            .collect()
+    }
+
+    fn as_fix(&self) -> Option<&dyn Fix> {
+        Some(self)
+    }
+}
+
+impl Fix for TrailingSemicolon {}
+
+// The semicolons outside the SQL's strings, comments and Jinja tags, and
+// whether each is at the end, with nothing but whitespace, comments and
+// Jinja tags after it, e.g. the one in `WHERE id > 1; {% endif %}`. A `{{ }}`
+// expression after one renders SQL, so the semicolon isn't at the end.
+fn semicolons(sql: &str, dialect: SqlDialect) -> Result<Vec<(Location, bool)>, TokenizerError> {
+    let dialect = dialect.parser_dialect();
+    let tokens: Vec<TokenWithLocation> = Tokenizer::new(&*dialect, sql)
+        .tokenize_with_location()?
+        .into_iter()
+        .filter(|token| !matches!(token.token, Token::Whitespace(_) | Token::EOF))
+        .collect();
+
+    let mut sql_tokens = vec![];
+    // What closes the tag or comment the tokens are in, if they're in one
+    let mut closing: Option<Token> = None;
+    for (index, token) in tokens.iter().enumerate() {
+        let next = tokens.get(index + 1).map(|token| &token.token);
+        match &closing {
+            Some(end) => {
+                let comment_end = *end == Token::Sharp && is_sharp(&token.token) && next == Some(&Token::RBrace);
+                if token.token == *end || comment_end {
+                    closing = None;
+                }
+            }
+            None => match (&token.token, next) {
+                (Token::LJinjaIterator, _) => closing = Some(Token::RJinjaIterator),
+                (Token::LBrace, Some(next)) if is_sharp(next) => closing = Some(Token::Sharp),
+                _ => sql_tokens.push(token),
+            },
+        }
+    }
+
+    let trailing = sql_tokens.iter().rev().take_while(|token| token.token == Token::SemiColon).count();
+    let first_trailing = sql_tokens.len() - trailing;
+    Ok(sql_tokens
+        .into_iter()
+        .enumerate()
+        .filter(|(_, token)| token.token == Token::SemiColon)
+        .map(|(index, token)| (token.location.clone(), index >= first_trailing))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::configuration::dbtonic_config::ParsingConfig;
+    use crate::rules::edits::apply_edits;
+
+    fn fixed(sql: &str) -> String {
+        let edits = TrailingSemicolon { dialect: SqlDialect::Generic }.fixes(&ModelNode::from_test_sql("test_model", sql));
+        apply_edits(sql, &edits)
+    }
+
+    #[test]
+    fn test_no_semicolons_pass() {
+        let rule = TrailingSemicolon { dialect: SqlDialect::Generic };
+        assert_eq!(rule.run(&ModelNode::from_test_sql("test_model", "SELECT * FROM {{ ref('orders') }}")), RuleResult::Pass);
+
+        let sql = "SELECT id\nFROM {{ ref('orders') }}\n{% set statuses = ['paid'] %}{# ; #}\nWHERE status != ';'";
+        assert_eq!(rule.run(&ModelNode::from_test_sql("test_model", sql)), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_trailing_semicolon_is_fixed() {
+        let rule = TrailingSemicolon { dialect: SqlDialect::Generic };
+        let sql = "SELECT id\nFROM {{ ref('orders') }};\n";
+
+        let violations = rule.violations(&ModelNode::from_test_sql("test_model", sql));
+        assert_eq!(
+            violations[0].message,
+            "The model ends with a semicolon, which will fail when dbt wraps it in its own statement."
+        );
+        assert_eq!(violations[0].location, Some(Location { line: 2, column: 25 }));
+        assert_eq!(fixed(sql), "SELECT id\nFROM {{ ref('orders') }}\n");
+
+        let sql = "SELECT id\nFROM {{ ref('orders') }}\nWHERE {% if is_incremental() %}id > 1;{% endif %}\n";
+        assert_eq!(fixed(sql), "SELECT id\nFROM {{ ref('orders') }}\nWHERE {% if is_incremental() %}id > 1{% endif %}\n");
+    }
+
+    #[test]
+    fn test_stray_semicolons_are_fixed() {
+        let rule = TrailingSemicolon { dialect: SqlDialect::Generic };
+        let sql = ";\nSELECT id\nFROM {{ ref('orders') }};;";
+
+        let violations = rule.violations(&ModelNode::from_test_sql("test_model", sql));
+        assert_eq!(violations.len(), 3);
+        assert_eq!(
+            violations[0].message,
+            "The model contains a semicolon, which will fail when dbt wraps it in its own statement."
+        );
+        assert_eq!(violations[0].location, Some(Location { line: 1, column: 1 }));
+        assert_eq!(fixed(sql), "\nSELECT id\nFROM {{ ref('orders') }}");
+    }
+
+    #[test]
+    fn test_semicolons_between_statements_are_left_alone() {
+        let rule = TrailingSemicolon { dialect: SqlDialect::Generic };
+
+        assert!(rule.violations(&ModelNode::from_test_sql("test_model", "SELECT 1; SELECT 2")).is_empty());
+        assert_eq!(fixed("SELECT 1; SELECT 2;"), "SELECT 1; SELECT 2");
+    }
+
+    #[test]
+    fn test_trailing_semicolon_is_found_with_the_dialect() {
+        // Snowflake reads `#` as the start of a comment, so the semicolon is
+        // the last thing in the model
+        let sql = "SELECT id\nFROM {{ ref('orders') }}; # the orders\n";
+        let model_node = ModelNode::from_sql(PathBuf::from("models/test_model.sql"), sql.to_string(), &ParsingConfig::default(), SqlDialect::Snowflake);
+
+        assert_eq!(
+            apply_edits(sql, &TrailingSemicolon { dialect: SqlDialect::Snowflake }.fixes(&model_node)),
+            "SELECT id\nFROM {{ ref('orders') }} # the orders\n"
+        );
+    }
+
+    #[test]
+    fn test_suppressed_semicolons_are_not_fixed() {
+        assert_eq!(fixed("SELECT 1; -- dbtonic:ignore trailing_semicolon\n"), "SELECT 1; -- dbtonic:ignore trailing_semicolon\n");
+    }
+
+    #[test]
+    fn test_untokenizable_models_are_skipped() {
+        assert!(semicolons("SELECT 'unterminated;", SqlDialect::Generic).is_err());
+        let rule = TrailingSemicolon { dialect: SqlDialect::Generic };
+        assert!(rule.violations(&ModelNode::from_test_sql("test_model", "SELECT 'unterminated;")).is_empty());
+    }
+}
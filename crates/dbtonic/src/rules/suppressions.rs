@@ -0,0 +1,240 @@
+use dbtranslate::tokens::{Location, Token, Trivia, Whitespace};
+use crate::rules::rules_engine::{Severity, Violation};
+
+// The rule name unused suppressions are reported under
+pub const UNUSED_SUPPRESSION: &str = "unused_suppression";
+
+// What a suppression comment covers. `-- dbtonic:ignore-file` covers the
+// whole model, `-- dbtonic:ignore` the line it ends, or the next line when
+// the comment is on a line of its own.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Scope {
+    File,
+    Line(u64),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Suppression {
+    pub scope: Scope,
+    // The rules the comment names, or none to suppress every rule
+    pub rule_names: Vec<String>,
+    // Where the comment starts, to report it when it's unused
+    pub location: Location,
+}
+
+impl Suppression {
+    fn covers(&self, rule_name: &str, violation: &Violation) -> bool {
+        let in_scope = match self.scope {
+            Scope::File => true,
+            Scope::Line(line) => violation.location.as_ref().map(|location| location.line) == Some(line),
+        };
+        in_scope && rule_name == violation.rule_name
+    }
+
+    fn covers_any(&self, violation: &Violation) -> bool {
+        match self.rule_names.is_empty() {
+            true => self.covers(&violation.rule_name, violation),
+            false => self.rule_names.iter().any(|rule_name| self.covers(rule_name, violation)),
+        }
+    }
+}
+
+// Finds the `dbtonic:ignore` and `dbtonic:ignore-file` comments in a
+// model's trivia. `--`, `/* */` and Jinja's `{# #}` comments all work, and
+// rule names can be separated by commas or spaces.
+pub fn suppressions(trivia: &Trivia) -> Vec<Suppression> {
+    // Each token's comments, then the ones after the last token
+    let leading = trivia.leading.iter().map(|(location, trivia)| (Some(location), trivia));
+    let trailing = std::iter::once((None, &trivia.trailing));
+
+    let mut suppressions = vec![];
+    // The last line a token other than whitespace or a comment was on
+    let mut last_code_line = 0;
+    for (code_location, trivia) in leading.chain(trailing) {
+        for token in trivia {
+            let comment = match &token.token {
+                Token::Whitespace(Whitespace::SingleLineComment { comment, .. }) => comment,
+                Token::Whitespace(Whitespace::MultiLineComment(comment) | Whitespace::JinjaComment(comment)) => comment,
+                _ => continue,
+            };
+            if let Some(suppression) = suppression(comment, &token.location, last_code_line) {
+                suppressions.push(suppression);
+            }
+        }
+        if let Some(location) = code_location {
+            last_code_line = location.line;
+        }
+    }
+    suppressions
+}
+
+fn suppression(comment: &str, location: &Location, last_code_line: u64) -> Option<Suppression> {
+
+    let (file_scope, rule_names) = parse_comment(comment)?;
+    let line = location.line;
+    let scope = if file_scope {
+        Scope::File
+    } else if last_code_line == line {
+        Scope::Line(line)
+    } else {
+        Scope::Line(line + comment.trim_end().matches('\n').count() as u64 + 1)
+    };
+    Some(Suppression { scope, rule_names, location: location.clone() })
+}
+
+// Whether the comment is an `ignore-file`, and the rules it names
+fn parse_comment(comment: &str) -> Option<(bool, Vec<String>)> {
+    let directive = comment.trim().strip_prefix("dbtonic:")?;
+    let (file_scope, rest) = match directive.strip_prefix("ignore-file") {
+        Some(rest) => (true, rest),
+        None => (false, directive.strip_prefix("ignore")?),
+    };
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let rule_names = rest
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|rule_name| !rule_name.is_empty())
+        .map(|rule_name| rule_name.to_string())
+        .collect();
+    Some((file_scope, rule_names))
+}
+
+// Drops the violations the suppressions cover. Each suppression, or rule
+// named in one, that didn't cover anything is reported as a violation of
+// its own so stale comments get cleaned up.
+pub fn apply(suppressions: &[Suppression], violations: Vec<Violation>) -> Vec<Violation> {
+    let (suppressed, mut kept): (Vec<Violation>, Vec<Violation>) = violations
+        .into_iter()
+        .partition(|violation| suppressions.iter().any(|suppression| suppression.covers_any(violation)));
+
+    for suppression in suppressions {
+        // `None` for a suppression of every rule
+        let unused: Vec<Option<&str>> = if suppression.rule_names.is_empty() {
+            match suppressed.iter().any(|violation| suppression.covers_any(violation)) {
+                true => vec![],
+                false => vec![None],
+            }
+        } else {
+            suppression
+                .rule_names
+                .iter()
+                .filter(|rule_name| !suppressed.iter().any(|violation| suppression.covers(rule_name, violation)))
+                .map(|rule_name| Some(rule_name.as_str()))
+                .collect()
+        };
+
+        for rule_name in unused {
+            let scope = match suppression.scope {
+                Scope::File => "in this model".to_string(),
+                Scope::Line(line) => format!("on line {}", line),
+            };
+            kept.push(
+                Violation::new(
+                    UNUSED_SUPPRESSION.to_string(),
+                    format!("The suppression for {} doesn't match a violation {}. Remove it.", describe(rule_name), scope),
+                    Some(suppression.location.clone()),
+                )
+                .with_severity(Severity::Warn),
+            );
+        }
+    }
+    kept
+}
+
+fn describe(rule_name: Option<&str>) -> String {
+    match rule_name {
+        Some(rule_name) => format!("`{}`", rule_name),
+        None => "any rule".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::dialect::GenericDialect;
+    use dbtranslate::tokenizer::Tokenizer;
+
+    const SQL: &str = "-- dbtonic:ignore-file no_select_star\n\
+                       SELECT SUM(amount) -- dbtonic:ignore explicit_aliases, ref_aliases\n\
+                       /* dbtonic:ignore */\n\
+                       FROM orders\n\
+                       -- dbtonic:ignored is not a suppression";
+
+    fn trivia(sql: &str) -> Trivia<'_> {
+        Tokenizer::new(&GenericDialect {}, sql).tokenize_with_trivia().unwrap().1
+    }
+
+    fn violation(rule_name: &str, line: Option<u64>) -> Violation {
+        Violation::new(rule_name.to_string(), "Failed.".to_string(), line.map(|line| Location { line, column: 1 }))
+    }
+
+    #[test]
+    fn test_suppressions() {
+        assert_eq!(
+            suppressions(&trivia(SQL)),
+            vec![
+                Suppression {
+                    scope: Scope::File,
+                    rule_names: vec!["no_select_star".to_string()],
+                    location: Location { line: 1, column: 1 },
+                },
+                Suppression {
+                    scope: Scope::Line(2),
+                    rule_names: vec!["explicit_aliases".to_string(), "ref_aliases".to_string()],
+                    location: Location { line: 2, column: 20 },
+                },
+                Suppression { scope: Scope::Line(4), rule_names: vec![], location: Location { line: 3, column: 1 } },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_drops_suppressed_violations_and_reports_unused_ones() {
+        let violations = vec![
+            violation("no_select_star", None),
+            violation("explicit_aliases", Some(2)),
+            violation("explicit_aliases", Some(5)),
+            violation("hardcoded_references", Some(4)),
+        ];
+
+        let messages: Vec<(String, String)> = apply(&suppressions(&trivia(SQL)), violations)
+            .into_iter()
+            .map(|violation| (violation.rule_name, violation.message))
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                ("explicit_aliases".to_string(), "Failed.".to_string()),
+                (
+                    UNUSED_SUPPRESSION.to_string(),
+                    "The suppression for `ref_aliases` doesn't match a violation on line 2. Remove it.".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unused_suppression_for_every_rule() {
+        let violations = apply(&suppressions(&trivia("SELECT 1 AS one -- dbtonic:ignore")), vec![]);
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].message, "The suppression for any rule doesn't match a violation on line 1. Remove it.");
+        assert_eq!(violations[0].severity, Severity::Warn);
+    }
+
+    #[test]
+    fn test_suppressions_in_jinja_comments() {
+        assert_eq!(
+            suppressions(&trivia("{# dbtonic:ignore-file no_select_star #}
+SELECT * FROM orders")),
+            vec![Suppression {
+                scope: Scope::File,
+                rule_names: vec!["no_select_star".to_string()],
+                location: Location { line: 1, column: 1 },
+            }]
+        );
+    }
+}
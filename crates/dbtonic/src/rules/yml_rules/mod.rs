@@ -1,2 +1,3 @@
 pub mod model_primary_key_tests;
-pub mod model_yaml_defined;
\ No newline at end of file
+pub mod model_yaml_defined;
+pub mod model_has_tests;
\ No newline at end of file
@@ -0,0 +1,96 @@
+use crate::rules::rules_engine::{Rule, RuleResult, Severity};
+use crate::parser::model_node::{ModelNode, ResourceType};
+
+// With a manifest.json this counts every test dbt knows about, including
+// singular tests and tests from packages. Without one it falls back to the
+// tests in the model's yml, and the engine skips the models a singular test
+// refs.
+pub struct ModelHasTests;
+
+impl Rule for ModelHasTests {
+    fn name(&self) -> String {
+        "model_has_tests".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Each model should have at least one test.".to_string()
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn applies_to(&self, resource_type: ResourceType) -> bool {
+        resource_type == ResourceType::Model
+    }
+
+    fn applies_to_models_with_singular_tests(&self) -> bool {
+        false
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        let has_tests = match (&model_node.data.manifest, &model_node.data.yaml) {
+            (Some(manifest), _) => !manifest.tests.is_empty(),
+            (None, Some(yaml)) => {
+                let model_tests = yaml.tests.as_ref().is_some_and(|tests| !tests.is_empty());
+                let column_tests = yaml.columns.iter().flatten().any(|column| {
+                    column.tests.as_ref().is_some_and(|tests| !tests.is_empty())
+                });
+                model_tests || column_tests
+            }
+            (None, None) => false,
+        };
+
+        if has_tests {
+            RuleResult::Pass
+        } else {
+            RuleResult::Fail(
+                "The model has no tests. Add a test to a column in its yml, or a singular test that refs it.".to_string(),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::manifest::{ManifestModel, ManifestTest};
+    use crate::parser::model_yaml::{ModelYaml, Tests};
+
+    fn model_node(yaml: Option<ModelYaml>, manifest: Option<ManifestModel>) -> ModelNode {
+        let mut model_node = ModelNode::from_test_sql("orders", "");
+        model_node.data.yaml = yaml;
+        model_node.data.manifest = manifest;
+        model_node
+    }
+
+    fn manifest(tests: Vec<ManifestTest>) -> ManifestModel {
+        ManifestModel { unique_id: "model.shop.orders".to_string(), materialized: None, tags: vec![], tests }
+    }
+
+    #[test]
+    fn test_model_has_tests_from_manifest() {
+        let rule = ModelHasTests {};
+        let singular_test = ManifestTest { name: "assert_orders_have_ids".to_string(), column_name: None };
+
+        assert_eq!(rule.run(&model_node(None, Some(manifest(vec![singular_test])))), RuleResult::Pass);
+        // The manifest knows about every test, so the yml isn't checked
+        let yaml = ModelYaml {
+            name: "orders".to_string(),
+            tests: Some(vec![Tests::String("unique".to_string())]),
+            ..Default::default()
+        };
+        assert!(matches!(rule.run(&model_node(Some(yaml), Some(manifest(vec![])))), RuleResult::Fail(_)));
+    }
+
+    #[test]
+    fn test_model_has_tests_from_yaml() {
+        let rule = ModelHasTests {};
+        let yaml: ModelYaml = serde_yaml::from_str("name: orders\ncolumns:\n  - name: id\n    tests:\n      - not_null\n").unwrap();
+        assert_eq!(rule.run(&model_node(Some(yaml), None)), RuleResult::Pass);
+
+        let yaml: ModelYaml = serde_yaml::from_str("name: orders\ncolumns:\n  - name: id\n").unwrap();
+        assert!(matches!(rule.run(&model_node(Some(yaml), None)), RuleResult::Fail(_)));
+        assert!(matches!(rule.run(&model_node(None, None)), RuleResult::Fail(_)));
+    }
+}
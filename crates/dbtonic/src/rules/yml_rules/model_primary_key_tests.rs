@@ -1,8 +1,7 @@
-use serde_yaml::Value;
-
 use crate::rules::rules_engine::*;
-use crate::parser::model_node::ModelNode;
-use crate::parser::model_yaml::Tests;
+use crate::parser::model_node::{ModelNode, ResourceType};
+
+const UNIQUE_COMBINATION_TEST: &str = "dbt_utils.unique_combination_of_columns";
 
 pub struct UniqueNotNullOrCombinationRule;
 
@@ -15,48 +14,43 @@ impl Rule for UniqueNotNullOrCombinationRule {
         "Each model should contain either a single column with the unique and not_null test OR the dbt_utils.unique_combination_of_columns test at the model level.".to_string()
     }
 
-    fn run(&self, model_node: &ModelNode) -> RuleResult {
-        let yaml = match &model_node.data.yaml {
-            Some(yaml) => yaml,
-            None => return RuleResult::Fail("Model does not have an associated YAML".to_string()),
-        };
+    // Singular tests and analyses aren't described in yml
+    fn applies_to(&self, resource_type: ResourceType) -> bool {
+        resource_type == ResourceType::Model
+    }
 
-        let mut unique_not_null = false;
-
-        if let Some(columns) = &yaml.columns {
-            for column in columns {
-                if let Some(tests) = &column.tests {
-                    let unique_test = tests.iter().any(|test| match test {
-                        Tests::String(s) => s == "unique",
-                        _ => false,
-                    });
-                    let not_null_test = tests.iter().any(|test| match test {
-                        Tests::String(s) => s == "not_null",
-                        _ => false,
-                    });
-    
-                    if unique_test && not_null_test {
-                        unique_not_null = true;
-                        break;
-                    }
-                }
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        // The manifest also has the tests defined outside the model's yml
+        // entry, e.g. in a package
+        let has_primary_key_tests = match (&model_node.data.manifest, &model_node.data.yaml) {
+            (Some(manifest), _) => {
+                let column_tests = |column: &str, name: &str| {
+                    manifest.tests.iter().any(|test| test.name == name && test.column_name.as_deref() == Some(column))
+                };
+                let unique_not_null = manifest
+                    .tests
+                    .iter()
+                    .filter_map(|test| test.column_name.as_deref())
+                    .any(|column| column_tests(column, "unique") && column_tests(column, "not_null"));
+                let unique_combination = manifest.tests.iter().any(|test| test.name == UNIQUE_COMBINATION_TEST);
+                unique_not_null || unique_combination
             }
-        }
-
-        let unique_combination_test = if let Some(tests) = &yaml.tests {
-            let unique_combination_test_key = Value::String("dbt_utils.unique_combination_of_columns".to_string());
-            tests.iter().any(|test| match test {
-                Tests::CustomTest(value) => value
-                    .as_mapping()
-                    .map(|map| map.contains_key(&unique_combination_test_key))
-                    .unwrap_or(false),
-                _ => false,
-            })
-        } else {
-            false
+            (None, Some(yaml)) => {
+                let unique_not_null = yaml.columns.iter().flatten().any(|column| {
+                    let names: Vec<&str> = column.tests.iter().flatten().filter_map(|test| test.name()).collect();
+                    names.contains(&"unique") && names.contains(&"not_null")
+                });
+                let unique_combination = yaml
+                    .tests
+                    .iter()
+                    .flatten()
+                    .any(|test| test.name() == Some(UNIQUE_COMBINATION_TEST));
+                unique_not_null || unique_combination
+            }
+            (None, None) => return RuleResult::Fail("Model does not have an associated YAML".to_string()),
         };
 
-        if unique_not_null || unique_combination_test {
+        if has_primary_key_tests {
             RuleResult::Pass
         } else {
             RuleResult::Fail(
@@ -75,10 +69,11 @@ mod tests {
         ModelYaml,
         NotNullProperties, 
         NotNullTestContents, 
+        Tests,
         UniqueTestContents, 
         UniqueProperties
     };
-    use crate::parser::model_node::ModelData;
+    use crate::parser::manifest::{ManifestModel, ManifestTest};
 
     #[test]
     fn test_unique_combination_of_columns_present() {
@@ -95,17 +90,7 @@ mod tests {
             ..Default::default()
         };
 
-        let model_node = ModelNode {
-            model_name: "test_model".to_string(),
-            data: ModelData {
-                ast: vec![],
-                tokens: vec![],
-                sql: String::new(),
-                compiled_sql: Some(String::new()),
-                yaml: Some(model_yaml),
-                errors: None
-            },
-        };
+        let model_node = model_node(Some(model_yaml), None);
 
         let result = rule.run(&model_node);
         assert_eq!(result, RuleResult::Pass);
@@ -140,17 +125,7 @@ mod tests {
             ..Default::default()
         };
 
-        let model_node = ModelNode {
-            model_name: "test_model".to_string(),
-            data: ModelData {
-                ast: vec![],
-                tokens: vec![],
-                sql: String::new(),
-                compiled_sql: Some(String::new()),
-                yaml: Some(model_yaml),
-                errors: None
-            },
-        };
+        let model_node = model_node(Some(model_yaml), None);
 
         let result = rule.run(&model_node);
         assert_ne!(result, RuleResult::Pass);
@@ -166,20 +141,46 @@ mod tests {
             ..Default::default()
         };
 
-        let model_node = ModelNode {
-            model_name: "test_model".to_string(),
-            data: ModelData {
-                ast: vec![],
-                tokens: vec![],
-                sql: String::new(),
-                compiled_sql: Some(String::new()),
-                yaml: Some(model_yaml),
-                errors: None
-            },
-        };
+        let model_node = model_node(Some(model_yaml), None);
 
         let result = rule.run(&model_node);
         assert_ne!(result, RuleResult::Pass);
     }
 
+    fn model_node(yaml: Option<ModelYaml>, manifest: Option<ManifestModel>) -> ModelNode {
+        let mut model_node = ModelNode::from_test_sql("orders", "");
+        model_node.data.yaml = yaml;
+        model_node.data.manifest = manifest;
+        model_node
+    }
+
+    #[test]
+    fn test_column_tests_with_configs() {
+        let rule = UniqueNotNullOrCombinationRule {};
+        let yaml: ModelYaml = serde_yaml::from_str(
+            "name: orders\ncolumns:\n  - name: id\n    data_tests:\n      - unique:\n          config:\n            severity: warn\n      - not_null\n",
+        )
+        .unwrap();
+
+        assert_eq!(rule.run(&model_node(Some(yaml), None)), RuleResult::Pass);
+    }
+
+    #[test]
+    fn test_manifest_tests() {
+        let rule = UniqueNotNullOrCombinationRule {};
+        let test = |name: &str, column_name: &str| ManifestTest { name: name.to_string(), column_name: Some(column_name.to_string()) };
+        let manifest = |tests: Vec<ManifestTest>| ManifestModel {
+            unique_id: "model.shop.orders".to_string(),
+            materialized: None,
+            tags: vec![],
+            tests,
+        };
+
+        // The tests can come from anywhere, so the model doesn't need yml
+        assert_eq!(rule.run(&model_node(None, Some(manifest(vec![test("unique", "id"), test("not_null", "id")])))), RuleResult::Pass);
+        assert_ne!(
+            rule.run(&model_node(None, Some(manifest(vec![test("unique", "id"), test("not_null", "customer_id")])))),
+            RuleResult::Pass
+        );
+    }
 }
@@ -1,7 +1,10 @@
 use crate::rules::rules_engine::{Rule,RuleResult};
-use crate::parser::model_node::ModelNode;
+use crate::parser::model_node::{ModelNode, ResourceType};
+use crate::configuration::dbtonic_config::YamlConfig;
 
-pub struct ModelYamlExists;
+pub struct ModelYamlExists {
+    pub config: YamlConfig,
+}
 
 impl Rule for ModelYamlExists {
     fn name(&self) -> String {
@@ -12,12 +15,39 @@ impl Rule for ModelYamlExists {
         "The ModelNode must contain data in the yaml property.".to_string()
     }
 
+    // Singular tests and analyses aren't described in yml
+    fn applies_to(&self, resource_type: ResourceType) -> bool {
+        resource_type == ResourceType::Model
+    }
+
     fn run(&self, model_node: &ModelNode) -> RuleResult {
-        if model_node.data.yaml.is_some() {
-            RuleResult::Pass
-        } else {
-            RuleResult::Fail("The ModelNode does not contain data in the yaml property.".to_string())
+        let yaml = match &model_node.data.yaml {
+            Some(yaml) => yaml,
+            None => return RuleResult::Fail("The ModelNode does not contain data in the yaml property.".to_string()),
+        };
+        let is_blank = |description: &Option<String>| description.as_deref().is_none_or(|description| description.trim().is_empty());
+
+        if self.config.require_description && is_blank(&yaml.description) {
+            return RuleResult::Fail("The model's yml doesn't have a description.".to_string());
         }
+        if self.config.require_column_descriptions {
+            let columns = yaml.columns.as_deref().unwrap_or_default();
+            if columns.is_empty() {
+                return RuleResult::Fail("The model's yml doesn't list its columns.".to_string());
+            }
+            let undescribed: Vec<&str> = columns
+                .iter()
+                .filter(|column| is_blank(&column.description))
+                .map(|column| column.name.as_str())
+                .collect();
+            if !undescribed.is_empty() {
+                return RuleResult::Fail(format!(
+                    "These columns don't have a description in the model's yml: {}.",
+                    undescribed.join(", ")
+                ));
+            }
+        }
+        RuleResult::Pass
     }
 }
 
@@ -25,11 +55,10 @@ impl Rule for ModelYamlExists {
 mod tests {
     use super::*;
     use crate::parser::model_yaml::ModelYaml;
-    use crate::parser::model_node::ModelData;
 
     #[test]
     fn test_yaml_exists_rule_pass() {
-        let rule = ModelYamlExists {};
+        let rule = ModelYamlExists { config: YamlConfig::default() };
 
         let model_yaml = ModelYaml {
             name: "test_model".to_string(),
@@ -39,17 +68,8 @@ mod tests {
             ..Default::default()
         };
 
-        let model_node = ModelNode {
-            model_name: "test_model".to_string(),
-            data: ModelData {
-                ast: vec![],
-                tokens: vec![],
-                sql: String::new(),
-                compiled_sql: Some(String::new()),
-                yaml: Some(model_yaml),
-                errors: None
-            },
-        };
+        let mut model_node = ModelNode::from_test_sql("test_model", "");
+        model_node.data.yaml = Some(model_yaml);
 
         let result = rule.run(&model_node);
         assert_eq!(result, RuleResult::Pass);
@@ -57,19 +77,9 @@ mod tests {
 
     #[test]
     fn test_yaml_exists_rule_fail() {
-        let rule = ModelYamlExists {};
-
-        let model_node = ModelNode {
-            model_name: "test_model".to_string(),
-            data: ModelData {
-                ast: vec![],
-                tokens: vec![],
-                sql: String::new(),
-                compiled_sql: Some(String::new()),
-                yaml: None,
-                errors: None
-            },
-        };
+        let rule = ModelYamlExists { config: YamlConfig::default() };
+
+        let model_node = ModelNode::from_test_sql("test_model", "");
 
         let result = rule.run(&model_node);
         assert_eq!(
@@ -78,4 +88,32 @@ mod tests {
         );
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_yaml_exists_rule_requires_descriptions() {
+        let rule = ModelYamlExists { config: YamlConfig { require_description: true, require_column_descriptions: true } };
+        let model_node = |yaml: &str| {
+            let mut model_node = ModelNode::from_test_sql("orders", "");
+            model_node.data.yaml = Some(serde_yaml::from_str(yaml).unwrap());
+            model_node
+        };
+
+        assert_eq!(
+            rule.run(&model_node("name: orders\ncolumns:\n  - name: id\n")),
+            RuleResult::Fail("The model's yml doesn't have a description.".to_string())
+        );
+        assert_eq!(
+            rule.run(&model_node("name: orders\ndescription: One row per order.\n")),
+            RuleResult::Fail("The model's yml doesn't list its columns.".to_string())
+        );
+        assert_eq!(
+            rule.run(&model_node(
+                "name: orders\ndescription: One row per order.\ncolumns:\n  - name: id\n    description: The primary key.\n  - name: status\n  - name: amount\n    description: ' '\n"
+            )),
+            RuleResult::Fail("These columns don't have a description in the model's yml: status, amount.".to_string())
+        );
+        assert_eq!(
+            rule.run(&model_node("name: orders\ndescription: One row per order.\ncolumns:\n  - name: id\n    description: The primary key.\n")),
+            RuleResult::Pass
+        );
+    }
+}
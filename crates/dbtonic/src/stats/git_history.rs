@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::Serialize;
+use crate::parser::dag::DAG;
+
+const SECONDS_PER_MONTH: i64 = 30 * 24 * 60 * 60;
+
+// A commit that touched at least one file in the project's model paths
+#[derive(Debug, PartialEq, Clone)]
+pub struct GitCommit {
+    pub timestamp: i64,
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct GitHistory {
+    pub commits: Vec<GitCommit>,
+}
+
+// A model nobody has touched in a while that other models still build on
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct StaleModel {
+    pub model_name: String,
+    pub months_unchanged: i64,
+    pub dependents: Vec<String>,
+}
+
+// Two models that usually change in the same commit, which suggests they
+// might be better off as a single model
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct CoChangedModels {
+    pub models: (String, String),
+    pub commits_together: usize,
+}
+
+impl GitHistory {
+    // Reads the history of the `model_paths` for the project in
+    // `project_dir`. File paths are relative to `project_dir`, even when the
+    // project lives in a subdirectory of the repository.
+    pub fn read(project_dir: &Path, model_paths: &[String]) -> io::Result<Self> {
+        let output = Command::new("git")
+            .args(["log", "--relative", "--format=%x00%ct", "--name-only", "--"])
+            .args(model_paths)
+            .current_dir(project_dir)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+
+        Ok(GitHistory::parse(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    // Parses `git log --format=%x00%ct --name-only` output, where every
+    // commit starts with a NUL byte followed by its unix timestamp
+    pub fn parse(log: &str) -> Self {
+        let commits = log
+            .split('\0')
+            .filter_map(|commit| {
+                let mut lines = commit.lines();
+                let timestamp = lines.next()?.trim().parse().ok()?;
+                let files = lines
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(PathBuf::from)
+                    .collect();
+                Some(GitCommit { timestamp, files })
+            })
+            .collect();
+
+        GitHistory { commits }
+    }
+
+    pub fn last_changed(&self, path: &Path) -> Option<i64> {
+        self.commits
+            .iter()
+            .filter(|commit| commit.files.iter().any(|file| file == path))
+            .map(|commit| commit.timestamp)
+            .max()
+    }
+
+    // Models that haven't changed for at least `months` but are still ref'd
+    // by other models. `project_dir` is what the model paths are relative to.
+    pub fn stale_models(&self, dag: &DAG, project_dir: &Path, now: i64, months: i64) -> Vec<StaleModel> {
+        let mut stale_models: Vec<StaleModel> = dag.model_nodes
+            .iter()
+            .filter_map(|model_node| {
+                let path = model_node.path.strip_prefix(project_dir).unwrap_or(&model_node.path);
+                let months_unchanged = (now - self.last_changed(path)?) / SECONDS_PER_MONTH;
+                if months_unchanged < months {
+                    return None;
+                }
+
+                let dependents: Vec<String> = dag.model_dependents(&model_node.model_name)
+                    .into_iter()
+                    .map(|dependent| dependent.model_name.clone())
+                    .collect();
+                if dependents.is_empty() {
+                    return None;
+                }
+
+                Some(StaleModel {
+                    model_name: model_node.model_name.clone(),
+                    months_unchanged,
+                    dependents,
+                })
+            })
+            .collect();
+
+        stale_models.sort_by_key(|stale_model| std::cmp::Reverse(stale_model.months_unchanged));
+        stale_models
+    }
+
+    // Pairs of models changed in the same commit at least `min_commits` times
+    pub fn co_changed_models(&self, min_commits: usize) -> Vec<CoChangedModels> {
+        let mut counts: HashMap<(String, String), usize> = HashMap::new();
+
+        for commit in &self.commits {
+            let mut models: Vec<String> = commit.files
+                .iter()
+                .filter(|file| file.extension().is_some_and(|extension| extension == "sql"))
+                .filter_map(|file| file.file_stem())
+                .map(|stem| stem.to_string_lossy().to_string())
+                .collect();
+            models.sort();
+            models.dedup();
+
+            for (i, first) in models.iter().enumerate() {
+                for second in &models[i + 1..] {
+                    *counts.entry((first.clone(), second.clone())).or_default() += 1;
+                }
+            }
+        }
+
+        let mut co_changed: Vec<CoChangedModels> = counts
+            .into_iter()
+            .filter(|(_, commits_together)| *commits_together >= min_commits)
+            .map(|(models, commits_together)| CoChangedModels { models, commits_together })
+            .collect();
+
+        co_changed.sort_by(|a, b| b.commits_together.cmp(&a.commits_together).then(a.models.cmp(&b.models)));
+        co_changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::model_node::ModelNode;
+
+    const LOG: &str = "\x00300000000\n\nmodels/orders.sql\nmodels/customers.sql\n\
+                       \x00200000000\n\nmodels/orders.sql\nmodels/customers.sql\nmodels/schema.yml\n\
+                       \x00100000000\n\nmodels/stg_orders.sql\n";
+
+    fn model_node(name: &str, sql: &str) -> ModelNode {
+        let mut model_node = ModelNode::from_test_sql(name, sql);
+        model_node.path = PathBuf::from(format!("/project/models/{}.sql", name));
+        model_node
+    }
+
+    #[test]
+    fn test_parse_git_log() {
+        let history = GitHistory::parse(LOG);
+
+        assert_eq!(history.commits.len(), 3);
+        assert_eq!(history.commits[1].timestamp, 200000000);
+        assert_eq!(history.commits[1].files.len(), 3);
+        assert_eq!(history.last_changed(Path::new("models/orders.sql")), Some(300000000));
+        assert_eq!(history.last_changed(Path::new("models/payments.sql")), None);
+    }
+
+    #[test]
+    fn test_stale_models() {
+        let history = GitHistory::parse(LOG);
+        let model_nodes = vec![
+            model_node("stg_orders", "SELECT 1"),
+            model_node("orders", "SELECT * FROM {{ ref('stg_orders') }}"),
+            model_node("customers", "SELECT * FROM {{ ref('orders') }}"),
+        ];
+        let dag = DAG::from_test_models(model_nodes);
+        let now = 100000000 + 7 * SECONDS_PER_MONTH;
+
+        let stale_models = history.stale_models(&dag, Path::new("/project"), now, 6);
+
+        assert_eq!(
+            stale_models,
+            vec![StaleModel {
+                model_name: "stg_orders".to_string(),
+                months_unchanged: 7,
+                dependents: vec!["orders".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_co_changed_models() {
+        let history = GitHistory::parse(LOG);
+
+        assert_eq!(
+            history.co_changed_models(2),
+            vec![CoChangedModels {
+                models: ("customers".to_string(), "orders".to_string()),
+                commits_together: 2,
+            }]
+        );
+        assert!(history.co_changed_models(3).is_empty());
+    }
+
+    #[test]
+    fn test_read_configured_model_paths() {
+        let project_dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=dbtonic", "-c", "user.email=dbtonic@example.com"])
+                .args(args)
+                .current_dir(project_dir.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        std::fs::create_dir_all(project_dir.path().join("src/models")).unwrap();
+        std::fs::create_dir_all(project_dir.path().join("models")).unwrap();
+        std::fs::write(project_dir.path().join("src/models/orders.sql"), "select 1").unwrap();
+        std::fs::write(project_dir.path().join("models/notes.sql"), "select 1").unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "Add models"]);
+
+        let history = GitHistory::read(project_dir.path(), &["src/models".to_string()]).unwrap();
+
+        assert_eq!(history.commits.len(), 1);
+        assert_eq!(history.commits[0].files, vec![PathBuf::from("src/models/orders.sql")]);
+    }
+}
@@ -0,0 +1,7 @@
+// The stats module
+
+// Publishes the analyses of the project's git history
+pub mod git_history;
+
+// Counts and extremes of the project's SQL, for `dbtonic stats`
+pub mod project_stats;
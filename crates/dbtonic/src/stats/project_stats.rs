@@ -0,0 +1,320 @@
+use serde::Serialize;
+use crate::configuration::dbtonic_config::ComplexityConfig;
+use crate::parser::dag::DAG;
+use crate::parser::lineage::LineageNode;
+use crate::parser::model_node::ModelNode;
+use crate::parser::selects::selects;
+use crate::rules::ast_rules::model_complexity::Complexity;
+
+// How many of the most referenced models are listed
+const MOST_REFERENCED: usize = 5;
+
+// What a model's SQL looks like, from its AST
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct ModelStats {
+    pub model_name: String,
+    pub ctes: usize,
+    pub joins: usize,
+    // 0 when the model has no subqueries, 1 when none are nested, ...
+    pub subquery_depth: usize,
+    // The number of columns of the model's widest SELECT
+    pub widest_select: usize,
+    // The models that ref it
+    pub referenced_by: usize,
+    // The score the model_complexity rule gives it
+    pub complexity: usize,
+}
+
+// The model with the most of something
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct TopModel {
+    pub model_name: String,
+    pub value: usize,
+}
+
+// The numbers `dbtonic stats` prints
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct ProjectStats {
+    pub project: String,
+    pub models: usize,
+    // Models with a yml
+    pub documented: usize,
+    pub semantic_nodes: usize,
+    pub exposures: usize,
+    pub singular_tests: usize,
+    pub analyses: usize,
+    pub snapshots: usize,
+    pub seeds: usize,
+    pub macros: usize,
+    // Models that couldn't be parsed, which the SQL numbers leave out
+    pub skipped: usize,
+    pub average_ctes: f64,
+    pub total_joins: usize,
+    pub most_joins: Option<TopModel>,
+    pub deepest_subquery: Option<TopModel>,
+    pub widest_select: Option<TopModel>,
+    pub most_complex: Option<TopModel>,
+    pub most_referenced: Vec<TopModel>,
+    // Sorted by name
+    pub model_stats: Vec<ModelStats>,
+}
+
+impl ModelStats {
+    pub fn create(model_node: &ModelNode, dag: &DAG, complexity_config: &ComplexityConfig) -> Self {
+        let complexity = Complexity::of(&model_node.data.ast);
+        let referenced_by = dag
+            .lineage
+            .children(&model_node.model_name)
+            .into_iter()
+            .filter(|node| matches!(node, LineageNode::Model(_)))
+            .count();
+
+        ModelStats {
+            model_name: model_node.model_name.clone(),
+            ctes: complexity.ctes,
+            joins: complexity.joins,
+            subquery_depth: complexity.subquery_depth,
+            widest_select: selects(&model_node.data.ast).iter().map(|select| select.projection.len()).max().unwrap_or(0),
+            referenced_by,
+            complexity: complexity.score(complexity_config),
+        }
+    }
+}
+
+impl ProjectStats {
+    // Complexity is scored with `complexity_config`'s weights
+    pub fn create(dag: &DAG, complexity_config: &ComplexityConfig) -> Self {
+        let mut model_stats: Vec<ModelStats> = dag
+            .model_nodes
+            .iter()
+            .filter(|model_node| !model_node.parse_failed())
+            .map(|model_node| ModelStats::create(model_node, dag, complexity_config))
+            .collect();
+        model_stats.sort_by(|a, b| a.model_name.cmp(&b.model_name));
+
+        let average_ctes = match model_stats.len() {
+            0 => 0.0,
+            parsed => model_stats.iter().map(|stats| stats.ctes).sum::<usize>() as f64 / parsed as f64,
+        };
+        let mut most_referenced: Vec<TopModel> = model_stats
+            .iter()
+            .filter(|stats| stats.referenced_by > 0)
+            .map(|stats| TopModel { model_name: stats.model_name.clone(), value: stats.referenced_by })
+            .collect();
+        // Sorting is stable, so ties stay in name order
+        most_referenced.sort_by_key(|top_model| std::cmp::Reverse(top_model.value));
+        most_referenced.truncate(MOST_REFERENCED);
+
+        ProjectStats {
+            project: dag.project.name.clone(),
+            models: dag.model_nodes.len(),
+            documented: dag.model_nodes.iter().filter(|model_node| model_node.data.yaml.is_some()).count(),
+            semantic_nodes: dag.semantic_nodes.len(),
+            exposures: dag.exposures.len(),
+            singular_tests: dag.singular_tests.len(),
+            analyses: dag.analyses.len(),
+            snapshots: dag.snapshots.len(),
+            seeds: dag.seeds.len(),
+            macros: dag.macros.len(),
+            skipped: dag.model_nodes.len() - model_stats.len(),
+            average_ctes,
+            total_joins: model_stats.iter().map(|stats| stats.joins).sum(),
+            most_joins: top_model(&model_stats, |stats| stats.joins),
+            deepest_subquery: top_model(&model_stats, |stats| stats.subquery_depth),
+            widest_select: top_model(&model_stats, |stats| stats.widest_select),
+            most_complex: top_model(&model_stats, |stats| stats.complexity),
+            most_referenced,
+            model_stats,
+        }
+    }
+
+    // The numbers as text, with a table of the models at the end
+    pub fn render(&self) -> String {
+        let mut lines = vec![
+            format!("Project: {}", self.project),
+            format!("Models: {} ({} documented in yaml)", self.models, self.documented),
+            format!("Semantic models, metrics and saved queries: {}", self.semantic_nodes),
+            format!("Exposures: {}", self.exposures),
+            format!("Singular tests: {}", self.singular_tests),
+            format!("Analyses: {}", self.analyses),
+            format!("Snapshots: {}", self.snapshots),
+            format!("Seeds: {}", self.seeds),
+            format!("Macros: {}", self.macros),
+            String::new(),
+        ];
+        if self.skipped > 0 {
+            lines.push(format!("Models left out of the numbers below because they couldn't be parsed: {}", self.skipped));
+        }
+
+        lines.push(format!("CTEs per model: {:.1} on average", self.average_ctes));
+        lines.push(match &self.most_joins {
+            Some(top_model) => format!("Joins: {}, most in {} ({})", self.total_joins, top_model.model_name, top_model.value),
+            None => "Joins: 0".to_string(),
+        });
+        lines.push(match &self.deepest_subquery {
+            Some(top_model) => format!("Deepest subquery nesting: {}, in {}", top_model.value, top_model.model_name),
+            None => "Deepest subquery nesting: 0".to_string(),
+        });
+        if let Some(top_model) = &self.widest_select {
+            lines.push(format!("Widest SELECT: {} column(s), in {}", top_model.value, top_model.model_name));
+        }
+        if let Some(top_model) = &self.most_complex {
+            lines.push(format!("Most complex model: {}, scoring {}", top_model.model_name, top_model.value));
+        }
+        let most_referenced: Vec<String> = self
+            .most_referenced
+            .iter()
+            .map(|top_model| format!("{} ({})", top_model.model_name, top_model.value))
+            .collect();
+        if !most_referenced.is_empty() {
+            lines.push(format!("Most referenced models: {}", most_referenced.join(", ")));
+        }
+
+        if !self.model_stats.is_empty() {
+            lines.push(String::new());
+            let rows: Vec<Vec<String>> = self
+                .model_stats
+                .iter()
+                .map(|stats| {
+                    vec![
+                        stats.model_name.clone(),
+                        stats.ctes.to_string(),
+                        stats.joins.to_string(),
+                        stats.subquery_depth.to_string(),
+                        stats.widest_select.to_string(),
+                        stats.referenced_by.to_string(),
+                        stats.complexity.to_string(),
+                    ]
+                })
+                .collect();
+            lines.extend(table(&["Model", "CTEs", "Joins", "Subquery depth", "Widest SELECT", "Referenced by", "Complexity"], &rows));
+        }
+        lines.join("\n")
+    }
+}
+
+// The first model, by name, with the highest `value`, unless it's 0
+fn top_model(model_stats: &[ModelStats], value: impl Fn(&ModelStats) -> usize) -> Option<TopModel> {
+    let mut top: Option<TopModel> = None;
+    for stats in model_stats {
+        let value = value(stats);
+        if value > top.as_ref().map_or(0, |top_model| top_model.value) {
+            top = Some(TopModel { model_name: stats.model_name.clone(), value });
+        }
+    }
+    top
+}
+
+// The first column left aligned, the numbers after it right aligned
+fn table(headers: &[&str], rows: &[Vec<String>]) -> Vec<String> {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(index, header)| rows.iter().map(|row| row[index].len()).chain([header.len()]).max().unwrap_or(0))
+        .collect();
+    let line = |cells: Vec<&str>| -> String {
+        cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| match index {
+                0 => format!("{:<width$}", cell, width = widths[0]),
+                _ => format!("{:>width$}", cell, width = widths[index]),
+            })
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![line(headers.to_vec())];
+    lines.extend(rows.iter().map(|row| line(row.iter().map(|cell| cell.as_str()).collect())));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::dbt_project_yaml::DbtProjectYaml;
+
+    fn dag() -> DAG {
+        let model_nodes = vec![
+            ModelNode::from_test_sql("stg_orders", "SELECT id, customer_id, amount FROM {{ source('shop', 'orders') }}"),
+            ModelNode::from_test_sql("stg_customers", "SELECT id, name FROM {{ source('shop', 'customers') }}"),
+            ModelNode::from_test_sql(
+                "orders",
+                "WITH o AS (SELECT * FROM {{ ref('stg_orders') }}), c AS (SELECT * FROM {{ ref('stg_customers') }}) \
+                 SELECT o.id, c.name FROM o JOIN c ON o.customer_id = c.id \
+                 WHERE o.id IN (SELECT id FROM (SELECT id FROM o) AS x)",
+            ),
+            ModelNode::from_test_sql(
+                "revenue",
+                "SELECT SUM(amount) AS revenue FROM {{ ref('stg_orders') }} AS o \
+                 JOIN {{ ref('orders') }} AS r ON o.id = r.id",
+            ),
+        ];
+        DAG {
+            project: DbtProjectYaml { name: "shop".to_string(), ..Default::default() },
+            ..DAG::from_test_models(model_nodes)
+        }
+    }
+
+    #[test]
+    fn test_project_stats() {
+        let stats = ProjectStats::create(&dag(), &ComplexityConfig::default());
+
+        assert_eq!(
+            stats.model_stats[0],
+            ModelStats {
+                model_name: "orders".to_string(),
+                ctes: 2,
+                joins: 1,
+                subquery_depth: 2,
+                widest_select: 2,
+                referenced_by: 1,
+                complexity: 10,
+            }
+        );
+        assert_eq!(stats.average_ctes, 0.5);
+        assert_eq!(stats.total_joins, 2);
+        assert_eq!(stats.most_joins, Some(TopModel { model_name: "orders".to_string(), value: 1 }));
+        assert_eq!(stats.deepest_subquery, Some(TopModel { model_name: "orders".to_string(), value: 2 }));
+        assert_eq!(stats.widest_select, Some(TopModel { model_name: "stg_orders".to_string(), value: 3 }));
+        assert_eq!(
+            stats.most_referenced,
+            vec![
+                TopModel { model_name: "stg_orders".to_string(), value: 2 },
+                TopModel { model_name: "orders".to_string(), value: 1 },
+                TopModel { model_name: "stg_customers".to_string(), value: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            ProjectStats::create(&dag(), &ComplexityConfig::default()).render(),
+            "Project: shop\n\
+             Models: 4 (0 documented in yaml)\n\
+             Semantic models, metrics and saved queries: 0\n\
+             Exposures: 0\n\
+             Singular tests: 0\n\
+             Analyses: 0\n\
+             Snapshots: 0\n\
+             Seeds: 0\n\
+             Macros: 0\n\
+             \n\
+             CTEs per model: 0.5 on average\n\
+             Joins: 2, most in orders (1)\n\
+             Deepest subquery nesting: 2, in orders\n\
+             Widest SELECT: 3 column(s), in stg_orders\n\
+             Most complex model: orders, scoring 10\n\
+             Most referenced models: stg_orders (2), orders (1), stg_customers (1)\n\
+             \n\
+             Model          CTEs  Joins  Subquery depth  Widest SELECT  Referenced by  Complexity\n\
+             orders            2      1               2              2              1          10\n\
+             revenue           0      1               0              1              0           2\n\
+             stg_customers     0      0               0              2              1           0\n\
+             stg_orders        0      0               0              3              2           0"
+        );
+    }
+}
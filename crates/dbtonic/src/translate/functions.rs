@@ -0,0 +1,179 @@
+use crate::configuration::dbtonic_config::SqlDialect;
+
+// What each warehouse calls a function, or None when it has nothing that
+// takes the same arguments. Functions every warehouse spells the same way
+// aren't listed.
+pub struct Equivalent {
+    pub postgres: Option<&'static str>,
+    pub redshift: Option<&'static str>,
+    pub snowflake: Option<&'static str>,
+    pub bigquery: Option<&'static str>,
+    pub duckdb: Option<&'static str>,
+    pub trino: Option<&'static str>,
+}
+
+impl Equivalent {
+    // The generic dialect isn't a warehouse, so it has no names of its own
+    pub fn name(&self, dialect: SqlDialect) -> Option<&'static str> {
+        match dialect {
+            SqlDialect::Generic => None,
+            SqlDialect::Postgres => self.postgres,
+            SqlDialect::Redshift => self.redshift,
+            SqlDialect::Snowflake => self.snowflake,
+            SqlDialect::Bigquery => self.bigquery,
+            SqlDialect::Duckdb => self.duckdb,
+            SqlDialect::Trino => self.trino,
+        }
+    }
+}
+
+// Synonyms get their own row, so a function is found by any of its names.
+// The first row that has the name for the dialect being translated from wins.
+pub const EQUIVALENTS: &[Equivalent] = &[
+    Equivalent {
+        postgres: None,
+        redshift: None,
+        snowflake: Some("IFF"),
+        bigquery: Some("IF"),
+        duckdb: Some("IF"),
+        trino: Some("IF"),
+    },
+    Equivalent {
+        postgres: Some("COALESCE"),
+        redshift: Some("NVL"),
+        snowflake: Some("NVL"),
+        bigquery: Some("IFNULL"),
+        duckdb: Some("IFNULL"),
+        trino: Some("COALESCE"),
+    },
+    Equivalent {
+        postgres: Some("COALESCE"),
+        redshift: Some("NVL"),
+        snowflake: Some("IFNULL"),
+        bigquery: Some("IFNULL"),
+        duckdb: Some("IFNULL"),
+        trino: Some("COALESCE"),
+    },
+    Equivalent {
+        postgres: Some("LENGTH"),
+        redshift: Some("LEN"),
+        snowflake: Some("LEN"),
+        bigquery: Some("LENGTH"),
+        duckdb: Some("LENGTH"),
+        trino: Some("LENGTH"),
+    },
+    Equivalent {
+        postgres: Some("STRING_AGG"),
+        redshift: Some("LISTAGG"),
+        snowflake: Some("LISTAGG"),
+        bigquery: Some("STRING_AGG"),
+        duckdb: Some("STRING_AGG"),
+        trino: None,
+    },
+    Equivalent {
+        postgres: None,
+        redshift: Some("REGEXP_SUBSTR"),
+        snowflake: Some("REGEXP_SUBSTR"),
+        bigquery: Some("REGEXP_EXTRACT"),
+        duckdb: Some("REGEXP_EXTRACT"),
+        trino: Some("REGEXP_EXTRACT"),
+    },
+    Equivalent {
+        postgres: Some("STARTS_WITH"),
+        redshift: None,
+        snowflake: Some("STARTSWITH"),
+        bigquery: Some("STARTS_WITH"),
+        duckdb: Some("STARTS_WITH"),
+        trino: Some("STARTS_WITH"),
+    },
+    Equivalent {
+        postgres: Some("CARDINALITY"),
+        redshift: Some("GET_ARRAY_LENGTH"),
+        snowflake: Some("ARRAY_SIZE"),
+        bigquery: Some("ARRAY_LENGTH"),
+        duckdb: Some("ARRAY_LENGTH"),
+        trino: Some("CARDINALITY"),
+    },
+    Equivalent {
+        postgres: Some("SPLIT_PART"),
+        redshift: Some("SPLIT_PART"),
+        snowflake: Some("SPLIT_PART"),
+        bigquery: None,
+        duckdb: Some("SPLIT_PART"),
+        trino: Some("SPLIT_PART"),
+    },
+    // The date functions take their arguments in a different order, or an
+    // INTERVAL, in each warehouse
+    Equivalent {
+        postgres: None,
+        redshift: Some("DATEADD"),
+        snowflake: Some("DATEADD"),
+        bigquery: None,
+        duckdb: None,
+        trino: None,
+    },
+    Equivalent {
+        postgres: None,
+        redshift: Some("DATEDIFF"),
+        snowflake: Some("DATEDIFF"),
+        bigquery: None,
+        duckdb: None,
+        trino: None,
+    },
+    // BigQuery takes the date first and the part unquoted, as in
+    // `DATE_TRUNC(created_at, MONTH)`
+    Equivalent {
+        postgres: Some("DATE_TRUNC"),
+        redshift: Some("DATE_TRUNC"),
+        snowflake: Some("DATE_TRUNC"),
+        bigquery: None,
+        duckdb: Some("DATE_TRUNC"),
+        trino: Some("DATE_TRUNC"),
+    },
+    Equivalent {
+        postgres: None,
+        redshift: None,
+        snowflake: None,
+        bigquery: Some("DATE_TRUNC"),
+        duckdb: None,
+        trino: None,
+    },
+    Equivalent {
+        postgres: None,
+        redshift: None,
+        snowflake: Some("ZEROIFNULL"),
+        bigquery: None,
+        duckdb: None,
+        trino: None,
+    },
+    Equivalent {
+        postgres: None,
+        redshift: None,
+        snowflake: Some("DIV0"),
+        bigquery: None,
+        duckdb: None,
+        trino: None,
+    },
+    Equivalent {
+        postgres: None,
+        redshift: None,
+        snowflake: None,
+        bigquery: Some("SAFE_DIVIDE"),
+        duckdb: None,
+        trino: None,
+    },
+];
+
+// Every warehouse has these, so they never need translating, even though
+// the rows above turn other warehouses' functions into them
+const UNIVERSAL: &[&str] = &["COALESCE"];
+
+// The row for the function called `name` in `dialect`
+pub fn equivalent(name: &str, dialect: SqlDialect) -> Option<&'static Equivalent> {
+    if UNIVERSAL.iter().any(|universal| universal.eq_ignore_ascii_case(name)) {
+        return None;
+    }
+    EQUIVALENTS
+        .iter()
+        .find(|equivalent| equivalent.name(dialect).is_some_and(|known| known.eq_ignore_ascii_case(name)))
+}
@@ -0,0 +1,250 @@
+pub mod functions;
+
+use core::ops::ControlFlow;
+use dbtranslate::ast::{visit_expressions_mut, DataType, Expr, Function, Ident, ObjectName, Statement};
+use dbtranslate::tokenizer::Tokenizer;
+use dbtranslate::tokens::Token;
+use crate::configuration::dbtonic_config::SqlDialect;
+use crate::parser::selects::selects;
+
+// Something in a model the dialect it's translated to has no equivalent
+// for. It's left as it was, for someone to rewrite by hand.
+#[derive(Debug, PartialEq)]
+pub struct Untranslatable {
+    // As it's written in the translated SQL
+    pub sql: String,
+    pub reason: String,
+}
+
+#[derive(Debug)]
+pub struct Translation {
+    pub sql: String,
+    pub untranslatable: Vec<Untranslatable>,
+}
+
+// Re-renders statements parsed with one dialect for another warehouse:
+// functions are renamed using the table in `functions`, casts use the
+// warehouse's type names and quoted identifiers its quotes. The SQL comes
+// from the AST's Display impls, so the model's own formatting and comments
+// aren't kept. Refs, sources and other Jinja stay as they are.
+pub fn translate(mut statements: Vec<Statement>, from: SqlDialect, to: SqlDialect) -> Translation {
+    let mut untranslatable = vec![];
+    for statement in &mut statements {
+        let _ = visit_expressions_mut(statement, |expr| {
+            translate_expr(expr, from, to, &mut untranslatable);
+            ControlFlow::<()>::Continue(())
+        });
+    }
+    if !supports_qualify(to) {
+        for select in selects(&statements) {
+            if let Some(qualify) = &select.qualify {
+                untranslatable.push(Untranslatable {
+                    sql: format!("QUALIFY {}", qualify),
+                    reason: format!("{} doesn't support QUALIFY", to.name()),
+                });
+            }
+        }
+    }
+
+    let sql = statements.iter().map(|statement| statement.to_string()).collect::<Vec<String>>().join(";\n");
+    Translation { sql: requote(&sql, from, to), untranslatable }
+}
+
+fn supports_qualify(dialect: SqlDialect) -> bool {
+    !matches!(dialect, SqlDialect::Postgres | SqlDialect::Trino)
+}
+
+fn translate_expr(expr: &mut Expr, from: SqlDialect, to: SqlDialect, untranslatable: &mut Vec<Untranslatable>) {
+    match expr {
+        Expr::Function(function) => translate_function(function, from, to, untranslatable),
+        Expr::Cast { data_type, .. } => translate_data_type(data_type, to),
+        Expr::TryCast { expr: inner, data_type } if to == SqlDialect::Bigquery => {
+            translate_data_type(data_type, to);
+            *expr = Expr::SafeCast { expr: inner.clone(), data_type: data_type.clone() };
+        }
+        Expr::SafeCast { expr: inner, data_type } if to != SqlDialect::Bigquery => {
+            translate_data_type(data_type, to);
+            match to {
+                SqlDialect::Postgres | SqlDialect::Redshift => untranslatable.push(Untranslatable {
+                    sql: expr.to_string(),
+                    reason: format!("{} has no cast that returns NULL on failure", to.name()),
+                }),
+                _ => *expr = Expr::TryCast { expr: inner.clone(), data_type: data_type.clone() },
+            }
+        }
+        Expr::TryCast { data_type, .. } | Expr::SafeCast { data_type, .. } => translate_data_type(data_type, to),
+        _ => {}
+    }
+}
+
+// Only functions with a single part name: `schema.my_udf()` is the project's own
+fn translate_function(function: &mut Function, from: SqlDialect, to: SqlDialect, untranslatable: &mut Vec<Untranslatable>) {
+    if from == SqlDialect::Generic || to == SqlDialect::Generic {
+        return;
+    }
+    let [name] = function.name.0.as_slice() else {
+        return;
+    };
+    let Some(equivalent) = functions::equivalent(&name.value, from) else {
+        return;
+    };
+    match equivalent.name(to) {
+        Some(translated) if !translated.eq_ignore_ascii_case(&name.value) => {
+            // In the case it was written in
+            let translated = match name.value.chars().any(|c| c.is_ascii_uppercase()) {
+                true => translated.to_string(),
+                false => translated.to_ascii_lowercase(),
+            };
+            function.name = ObjectName(vec![Ident::new(translated)]);
+        }
+        Some(_) => {}
+        None => untranslatable.push(Untranslatable {
+            sql: function.to_string(),
+            reason: format!("{} has no equivalent of {}", to.name(), name.value.to_ascii_uppercase()),
+        }),
+    }
+}
+
+// BigQuery has its own names for the common types, and the others don't
+// know them
+fn translate_data_type(data_type: &mut DataType, to: SqlDialect) {
+    let custom = |name: &str| DataType::Custom(ObjectName(vec![Ident::new(name)]), vec![]);
+    let translated = match (to, &*data_type) {
+        (SqlDialect::Generic, _) => None,
+        (
+            SqlDialect::Bigquery,
+            DataType::Varchar(_)
+            | DataType::Nvarchar(_)
+            | DataType::Char(_)
+            | DataType::Character(_)
+            | DataType::CharacterVarying(_)
+            | DataType::CharVarying(_)
+            | DataType::Text,
+        ) => Some(DataType::String),
+        (SqlDialect::Bigquery, DataType::Int(_) | DataType::Integer(_) | DataType::BigInt(_)) => Some(custom("INT64")),
+        (SqlDialect::Bigquery, DataType::Float(_) | DataType::Double | DataType::DoublePrecision) => Some(custom("FLOAT64")),
+        (SqlDialect::Bigquery, DataType::Boolean) => Some(custom("BOOL")),
+        (SqlDialect::Bigquery, _) => None,
+        (_, DataType::String) => Some(DataType::Varchar(None)),
+        (_, DataType::Custom(name, modifiers)) if modifiers.is_empty() => match name.to_string().to_ascii_uppercase().as_str() {
+            "INT64" => Some(DataType::BigInt(None)),
+            "FLOAT64" => Some(DataType::Double),
+            "BOOL" => Some(DataType::Boolean),
+            _ => None,
+        },
+        _ => None,
+    };
+    if let Some(translated) = translated {
+        *data_type = translated;
+    }
+}
+
+// Swaps the quotes around identifiers for the ones the warehouse uses. This
+// works on the tokens since aliases, which the AST visitor doesn't reach, can
+// be quoted too. Whatever is inside Jinja tags is left alone.
+fn requote(sql: &str, from: SqlDialect, to: SqlDialect) -> String {
+    let quote = to.quote_style().quote();
+    let dialect = from.parser_dialect();
+    let Ok(tokens) = Tokenizer::new(dialect.as_ref(), sql).tokenize() else {
+        return sql.to_string();
+    };
+    let mut in_jinja = false;
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            Token::DoubleLBrace | Token::LJinjaIterator => {
+                in_jinja = true;
+                token.to_string()
+            }
+            Token::DoubleRBrace | Token::RJinjaIterator => {
+                in_jinja = false;
+                token.to_string()
+            }
+            Token::Word(mut word) if !in_jinja && matches!(word.quote_style, Some('"') | Some('`')) => {
+                word.quote_style = Some(quote);
+                word.to_string()
+            }
+            token => token.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbtranslate::parser::Parser;
+
+    fn translated(sql: &str, from: SqlDialect, to: SqlDialect) -> Translation {
+        let statements = Parser::parse_sql(from.parser_dialect().as_ref(), sql).unwrap();
+        translate(statements, from, to)
+    }
+
+    #[test]
+    fn test_translate_snowflake_to_bigquery() {
+        let translation = translated(
+            "SELECT IFF(a > 1, 'big', 'small') AS \"Size\", nvl(b, 0), CAST(c AS VARCHAR), TRY_CAST(d AS INTEGER) \
+             FROM {{ ref(\"orders\") }}",
+            SqlDialect::Snowflake,
+            SqlDialect::Bigquery,
+        );
+
+        assert_eq!(
+            translation.sql,
+            "SELECT IF(a > 1, 'big', 'small') AS `Size`, ifnull(b, 0), CAST(c AS STRING), SAFE_CAST(d AS INT64) \
+             FROM {{ ref(\"orders\") }}"
+        );
+        assert_eq!(translation.untranslatable, vec![]);
+    }
+
+    #[test]
+    fn test_translate_bigquery_to_snowflake() {
+        let translation = translated(
+            "SELECT `id`, SAFE_CAST(amount AS FLOAT64), STRING_AGG(name, ',') FROM orders",
+            SqlDialect::Bigquery,
+            SqlDialect::Snowflake,
+        );
+
+        assert_eq!(translation.sql, "SELECT \"id\", TRY_CAST(amount AS DOUBLE), LISTAGG(name, ',') FROM orders");
+    }
+
+    #[test]
+    fn test_translate_reports_what_has_no_equivalent() {
+        let translation = translated(
+            "SELECT DATEADD(day, 1, created_at), COALESCE(a, b, c) FROM orders QUALIFY ROW_NUMBER() OVER (PARTITION BY id) = 1",
+            SqlDialect::Snowflake,
+            SqlDialect::Postgres,
+        );
+
+        assert_eq!(
+            translation.untranslatable,
+            vec![
+                Untranslatable {
+                    sql: "DATEADD(day, 1, created_at)".to_string(),
+                    reason: "postgres has no equivalent of DATEADD".to_string(),
+                },
+                Untranslatable {
+                    sql: "QUALIFY ROW_NUMBER() OVER (PARTITION BY id) = 1".to_string(),
+                    reason: "postgres doesn't support QUALIFY".to_string(),
+                },
+            ]
+        );
+        assert!(translation.sql.contains("DATEADD(day, 1, created_at), COALESCE(a, b, c)"));
+    }
+
+    #[test]
+    fn test_translate_reports_date_trunc_to_and_from_bigquery() {
+        let to_bigquery = translated("SELECT DATE_TRUNC('month', created_at) FROM orders", SqlDialect::Snowflake, SqlDialect::Bigquery);
+        let from_bigquery = translated("SELECT DATE_TRUNC(created_at, MONTH) FROM orders", SqlDialect::Bigquery, SqlDialect::Snowflake);
+        let to_duckdb = translated("SELECT DATE_TRUNC('month', created_at) FROM orders", SqlDialect::Snowflake, SqlDialect::Duckdb);
+
+        assert_eq!(
+            to_bigquery.untranslatable,
+            vec![Untranslatable {
+                sql: "DATE_TRUNC('month', created_at)".to_string(),
+                reason: "bigquery has no equivalent of DATE_TRUNC".to_string(),
+            }]
+        );
+        assert_eq!(from_bigquery.untranslatable.len(), 1);
+        assert_eq!(to_duckdb.untranslatable, vec![]);
+    }
+}
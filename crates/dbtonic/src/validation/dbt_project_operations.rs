@@ -1,19 +1,52 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use crate::parser::dbt_project_yaml::DbtProjectYaml;
 
-pub struct DbtProject;
+// The dbt project dbtonic runs on: the directory with its dbt_project.yml,
+// which every path dbtonic reads is relative to, and what the yml says
+#[derive(Debug, Clone, Default)]
+pub struct DbtProject {
+    pub root: PathBuf,
+    pub yaml: DbtProjectYaml,
+}
 
 impl DbtProject {
-    pub fn validate(&self) {
-        if !Path::new("dbt_project.yml").exists() {
+    // Run from somewhere inside a project, e.g. `models/staging`, dbtonic
+    // reads the dbt_project.yml at the project's root once. Every path is
+    // resolved against that root, so the working directory stays put.
+    pub fn validate(run_dir: &Path) -> DbtProject {
+        let Some(root) = DbtProject::find_root(run_dir) else {
             eprintln!("Hey friend, it looks like you're not in a dbt project right now. \
                 How about you navigate your way over to a dbt project and give this another shot?");
             std::process::exit(1);
+        };
+
+        match DbtProject::load(&root) {
+            Ok(project) => project,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
         }
     }
 
+    // dbtonic finds the models through dbt_project.yml, so it has to make sense
+    pub fn load(root: &Path) -> Result<DbtProject, String> {
+        match DbtProjectYaml::from_file(root.join("dbt_project.yml")) {
+            Ok(yaml) if yaml.name.is_empty() => Err("dbt_project.yml doesn't set the project's `name`, which dbt requires.".to_string()),
+            Ok(yaml) => Ok(DbtProject { root: root.to_path_buf(), yaml }),
+            Err(e) => Err(format!("Error reading dbt_project.yml: {:?}", e)),
+        }
+    }
+
+    // The nearest directory with a dbt_project.yml, starting from `start`
+    pub fn find_root(start: &Path) -> Option<PathBuf> {
+        start.ancestors().find(|dir| dir.join("dbt_project.yml").is_file()).map(Path::to_path_buf)
+    }
+
     pub fn check_dbt_version(&self) {
         let dbt_version_output = std::process::Command::new("dbt")
             .arg("--version")
+            .current_dir(&self.root)
             .output()
             .expect("Failed to execute 'dbt --version'");
 
@@ -28,6 +61,7 @@ impl DbtProject {
     pub fn run_dbt_compile(&self) {
         let dbt_compile_output = std::process::Command::new("dbt")
             .arg("compile")
+            .current_dir(&self.root)
             .output()
             .expect("Failed to execute 'dbt compile'");
 
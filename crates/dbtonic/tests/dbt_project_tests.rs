@@ -16,12 +16,22 @@ fn setup_dbt_project(temp_dir: &tempfile::TempDir) {
 fn test_validate() {
     let temp_dir = tempdir().expect("Failed to create temporary directory");
     setup_dbt_project(&temp_dir);
-    
+    let models_dir = temp_dir.path().join("models").join("staging");
+    std::fs::create_dir_all(&models_dir).expect("Failed to create the models directory");
     let current_dir = env::current_dir().expect("Failed to get current directory");
-    env::set_current_dir(temp_dir.path()).expect("Failed to change to temporary directory");
 
-    let dbt_project = DbtProject;
-    dbt_project.validate();
-    
-    env::set_current_dir(current_dir).expect("Failed to change back to original directory");
+    let dbt_project = DbtProject::validate(&models_dir);
+
+    assert_eq!(dbt_project.yaml.name, "test_project");
+    assert_eq!(dbt_project.root, temp_dir.path());
+    assert_eq!(env::current_dir().expect("Failed to get current directory"), current_dir);
+}
+
+#[test]
+fn test_load_reports_a_malformed_dbt_project_yml() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory");
+    std::fs::write(temp_dir.path().join("dbt_project.yml"), "name: [test_project\n").expect("Failed to write to dbt_project.yml");
+
+    let error = DbtProject::load(temp_dir.path()).unwrap_err();
+    assert!(error.starts_with("Error reading dbt_project.yml"), "{}", error);
 }
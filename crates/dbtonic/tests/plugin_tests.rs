@@ -0,0 +1,53 @@
+use std::fs;
+
+use tempfile::tempdir;
+use dbtonic::configuration::dbtonic_config::{DbtonicConfig, ParsingConfig, SqlDialect};
+use dbtonic::parser::model_node::ModelNode;
+use dbtonic::rules::rules_engine::{Rule, RuleResult, RulesEngine, Severity};
+use dbtonic::validation::dbt_project_operations::DbtProject;
+
+// A rule the way a crate depending on dbtonic would write one
+struct NoLegacySchema;
+
+impl Rule for NoLegacySchema {
+    fn name(&self) -> String {
+        "no_legacy_schema".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Models must not read from the legacy schema.".to_string()
+    }
+
+    fn run(&self, model_node: &ModelNode) -> RuleResult {
+        if model_node.data.sql.contains("legacy.") {
+            RuleResult::Fail("The model reads from the legacy schema.".to_string())
+        } else {
+            RuleResult::Pass
+        }
+    }
+}
+
+fn register(rules_engine: &mut RulesEngine) {
+    rules_engine.register(Box::new(NoLegacySchema));
+}
+
+#[test]
+fn test_plugin_rules_run_alongside_the_built_ins() {
+    let temp_dir = tempdir().expect("Failed to create temporary directory");
+    let model_path = temp_dir.path().join("orders.sql");
+    fs::write(&model_path, "SELECT id, SUM(amount) FROM legacy.orders GROUP BY id").unwrap();
+    let model_node = ModelNode::from_path(model_path, &ParsingConfig::default(), SqlDialect::Generic).unwrap();
+
+    let mut config = DbtonicConfig::default();
+    config.severity.insert("no_legacy_schema".to_string(), Severity::Info);
+    let rules_engine = RulesEngine::with_plugins(&config, &DbtProject::default(), &[register]);
+
+    let violations = rules_engine.run_violations(&model_node);
+    let plugin_violation = violations
+        .iter()
+        .find(|violation| violation.rule_name == "no_legacy_schema")
+        .expect("The plugin's rule didn't run");
+    assert_eq!(plugin_violation.message, "The model reads from the legacy schema.");
+    assert_eq!(plugin_violation.severity, Severity::Info);
+    assert!(violations.iter().any(|violation| violation.rule_name == "explicit_aliases"));
+}
@@ -53,8 +53,8 @@ fn derive_visit(
 
     let expanded = quote! {
         // The generated impl.
-        impl #impl_generics sqlparser::ast::#visit_trait for #name #ty_generics #where_clause {
-            fn visit<V: sqlparser::ast::#visitor_trait>(
+        impl #impl_generics dbtranslate::ast::#visit_trait for #name #ty_generics #where_clause {
+            fn visit<V: dbtranslate::ast::#visitor_trait>(
                 &#modifier self,
                 visitor: &mut V
             ) -> ::std::ops::ControlFlow<V::Break> {
@@ -128,7 +128,7 @@ impl Attributes {
 fn add_trait_bounds(mut generics: Generics, VisitType{visit_trait, ..}: &VisitType) -> Generics {
     for param in &mut generics.params {
         if let GenericParam::Type(ref mut type_param) = *param {
-            type_param.bounds.push(parse_quote!(sqlparser::ast::#visit_trait));
+            type_param.bounds.push(parse_quote!(dbtranslate::ast::#visit_trait));
         }
     }
     generics
@@ -143,7 +143,7 @@ fn visit_children(data: &Data, VisitType{visit_trait, modifier, ..}: &VisitType)
                     let name = &f.ident;
                     let attributes = Attributes::parse(&f.attrs);
                     let (pre_visit, post_visit) = attributes.visit(quote!(&#modifier self.#name));
-                    quote_spanned!(f.span() => #pre_visit sqlparser::ast::#visit_trait::visit(&#modifier self.#name, visitor)?; #post_visit)
+                    quote_spanned!(f.span() => #pre_visit dbtranslate::ast::#visit_trait::visit(&#modifier self.#name, visitor)?; #post_visit)
                 });
                 quote! {
                     #(#recurse)*
@@ -154,7 +154,7 @@ fn visit_children(data: &Data, VisitType{visit_trait, modifier, ..}: &VisitType)
                     let index = Index::from(i);
                     let attributes = Attributes::parse(&f.attrs);
                     let (pre_visit, post_visit) = attributes.visit(quote!(&self.#index));
-                    quote_spanned!(f.span() => #pre_visit sqlparser::ast::#visit_trait::visit(&#modifier self.#index, visitor)?; #post_visit)
+                    quote_spanned!(f.span() => #pre_visit dbtranslate::ast::#visit_trait::visit(&#modifier self.#index, visitor)?; #post_visit)
                 });
                 quote! {
                     #(#recurse)*
@@ -174,7 +174,7 @@ fn visit_children(data: &Data, VisitType{visit_trait, modifier, ..}: &VisitType)
                             let name = &f.ident;
                             let attributes = Attributes::parse(&f.attrs);
                             let (pre_visit, post_visit) = attributes.visit(name.to_token_stream());
-                            quote_spanned!(f.span() => #pre_visit sqlparser::ast::#visit_trait::visit(#name, visitor)?; #post_visit)
+                            quote_spanned!(f.span() => #pre_visit dbtranslate::ast::#visit_trait::visit(#name, visitor)?; #post_visit)
                         });
 
                         quote!(
@@ -189,7 +189,7 @@ fn visit_children(data: &Data, VisitType{visit_trait, modifier, ..}: &VisitType)
                             let name = format_ident!("_{}", i);
                             let attributes = Attributes::parse(&f.attrs);
                             let (pre_visit, post_visit) = attributes.visit(name.to_token_stream());
-                            quote_spanned!(f.span() => #pre_visit sqlparser::ast::#visit_trait::visit(#name, visitor)?; #post_visit)
+                            quote_spanned!(f.span() => #pre_visit dbtranslate::ast::#visit_trait::visit(#name, visitor)?; #post_visit)
                         });
 
                         quote! {
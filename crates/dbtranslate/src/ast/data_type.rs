@@ -18,9 +18,9 @@ use core::fmt;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "visitor")]
-use sqlparser_derive::{Visit, VisitMut};
+use dbtranslate_derive::{Visit, VisitMut};
 
-use crate::ast::ObjectName;
+use crate::ast::{display_comma_separated, Ident, ObjectName};
 
 use super::value::escape_single_quote_string;
 
@@ -161,7 +161,11 @@ pub enum DataType {
     /// Custom type such as enums
     Custom(ObjectName, Vec<String>),
     /// Arrays
-    Array(Option<Box<DataType>>),
+    Array(ArrayElemTypeDef),
+    /// BigQuery structs, e.g. `STRUCT<a INT64, b STRING>`
+    Struct(Vec<StructField>),
+    /// Trino rows, e.g. `ROW(a INTEGER, b VARCHAR)`
+    Row(Vec<StructField>),
     /// Enums
     Enum(Vec<String>),
     /// Set
@@ -261,13 +265,11 @@ impl fmt::Display for DataType {
             DataType::Text => write!(f, "TEXT"),
             DataType::String => write!(f, "STRING"),
             DataType::Bytea => write!(f, "BYTEA"),
-            DataType::Array(ty) => {
-                if let Some(t) = &ty {
-                    write!(f, "{t}[]")
-                } else {
-                    write!(f, "ARRAY")
-                }
-            }
+            DataType::Array(ArrayElemTypeDef::None) => write!(f, "ARRAY"),
+            DataType::Array(ArrayElemTypeDef::AngleBracket(t)) => write!(f, "ARRAY<{t}>"),
+            DataType::Array(ArrayElemTypeDef::SquareBracket(t)) => write!(f, "{t}[]"),
+            DataType::Struct(fields) => write!(f, "STRUCT<{}>", display_comma_separated(fields)),
+            DataType::Row(fields) => write!(f, "ROW({})", display_comma_separated(fields)),
             DataType::Custom(ty, modifiers) => {
                 if modifiers.is_empty() {
                     write!(f, "{ty}")
@@ -476,3 +478,35 @@ impl fmt::Display for CharLengthUnits {
         }
     }
 }
+
+/// How the type of an array's elements is written
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+pub enum ArrayElemTypeDef {
+    /// Snowflake's untyped `ARRAY`
+    None,
+    /// `ARRAY<INT64>`, as in BigQuery and Hive
+    AngleBracket(Box<DataType>),
+    /// `INT[]`, as in Postgres
+    SquareBracket(Box<DataType>),
+}
+
+/// A field of a [`DataType::Struct`] or [`DataType::Row`]. BigQuery lets the name be left out,
+/// e.g. `STRUCT<INT64, STRING>`.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+pub struct StructField {
+    pub field_name: Option<Ident>,
+    pub field_type: DataType,
+}
+
+impl fmt::Display for StructField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.field_name {
+            write!(f, "{name} ")?;
+        }
+        write!(f, "{}", self.field_type)
+    }
+}
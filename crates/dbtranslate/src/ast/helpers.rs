@@ -0,0 +1,215 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Answers to the questions lint rules most often ask of a query, so they
+//! don't each have to walk the AST themselves.
+//!
+//! The walks are built on the [Visitor](crate::ast::Visitor), so they reach
+//! every part of the query: CTEs, both sides of set operations, joins, and
+//! subqueries inside expressions like `IN (SELECT ...)`.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+
+use crate::ast::{
+    visit_table_factors, Expr, Ident, Query, Select, SelectItem, TableFactor, Visit, Visitor,
+};
+
+/// The models the query selects from with `{{ ref() }}`, each once, in the
+/// order they're first referenced.
+///
+/// ```
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{helpers::referenced_models, Statement};
+/// let sql = "WITH o AS (SELECT * FROM {{ ref('orders') }}) \
+///            SELECT * FROM o WHERE id IN (SELECT id FROM {{ ref('customers') }})";
+/// let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+/// let Statement::Query(query) = &statements[0];
+///
+/// let models: Vec<String> = referenced_models(query).iter().map(|ident| ident.value.to_string()).collect();
+/// assert_eq!(models, vec!["orders", "customers"]);
+/// ```
+pub fn referenced_models(query: &Query) -> Vec<Ident> {
+    let mut models: Vec<Ident> = Vec::new();
+    let _ = visit_table_factors(query, |table_factor| {
+        if let TableFactor::DbtRef { model_name, .. } = table_factor {
+            // `ref('orders')` and `ref("orders")` are the same model
+            if !models.iter().any(|model| model.value == model_name.value) {
+                models.push(model_name.clone());
+            }
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    models
+}
+
+/// The names of the columns the SELECT returns, one per item in its
+/// projection.
+///
+/// The name is `None` when the warehouse picks it, e.g. for an unaliased
+/// `SUM(amount)`, and for `*` and `alias.*`, whose columns depend on the
+/// relations they expand.
+pub fn output_columns(select: &Select) -> Vec<Option<&Ident>> {
+    select
+        .projection
+        .iter()
+        .map(|item| match item {
+            SelectItem::ExprWithAlias { alias, .. } => Some(alias),
+            SelectItem::UnnamedExpr(Expr::Identifier(ident)) => Some(ident),
+            SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => idents.last(),
+            SelectItem::UnnamedExpr(_)
+            | SelectItem::QualifiedWildcard(..)
+            | SelectItem::Wildcard(_) => None,
+        })
+        .collect()
+}
+
+/// How deeply subqueries nest in the query: 0 without any, 1 when there
+/// are subqueries but none inside another one, and so on.
+///
+/// Subqueries are those in FROM clauses and in expressions. CTEs aren't
+/// subqueries, so a subquery directly in a CTE has a depth of 1.
+pub fn max_subquery_depth(query: &Query) -> usize {
+    let mut visitor = SubqueryDepth::default();
+    let _ = query.visit(&mut visitor);
+    visitor.max
+}
+
+#[derive(Default)]
+struct SubqueryDepth {
+    depth: usize,
+    max: usize,
+}
+
+impl SubqueryDepth {
+    fn enter(&mut self) {
+        self.depth += 1;
+        self.max = self.max.max(self.depth);
+    }
+}
+
+fn is_subquery_expr(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Subquery(_) | Expr::Exists { .. } | Expr::InSubquery { .. } | Expr::ArraySubquery(_)
+    )
+}
+
+impl Visitor for SubqueryDepth {
+    type Break = ();
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<Self::Break> {
+        if let TableFactor::Derived { .. } = table_factor {
+            self.enter();
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<Self::Break> {
+        if let TableFactor::Derived { .. } = table_factor {
+            self.depth -= 1;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<Self::Break> {
+        if is_subquery_expr(expr) {
+            self.enter();
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_expr(&mut self, expr: &Expr) -> ControlFlow<Self::Break> {
+        if is_subquery_expr(expr) {
+            self.depth -= 1;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Statement;
+    use crate::dialect::GenericDialect;
+    use crate::parser::Parser;
+
+    fn query(sql: &str) -> Query {
+        let Statement::Query(query) = Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .remove(0);
+        *query
+    }
+
+    fn select(sql: &str) -> Select {
+        match *query(sql).body {
+            crate::ast::SetExpr::Select(select) => *select,
+            other => panic!("expected a SELECT, got {other}"),
+        }
+    }
+
+    #[test]
+    fn test_referenced_models() {
+        let query = query(
+            "SELECT * FROM {{ ref('orders') }} AS o \
+             JOIN (SELECT * FROM {{ ref(\"orders\") }}) AS p ON o.id = p.id \
+             JOIN {{ source('stripe', 'payments') }} AS s ON o.id = s.id \
+             UNION ALL SELECT * FROM {{ ref('refunds') }}",
+        );
+
+        let models: Vec<String> = referenced_models(&query)
+            .iter()
+            .map(|ident| ident.value.to_string())
+            .collect();
+
+        assert_eq!(models, vec!["orders", "refunds"]);
+    }
+
+    #[test]
+    fn test_output_columns() {
+        let select = select("SELECT id, o.amount, SUM(x) AS total, SUM(y), *, o.* FROM o");
+
+        let names: Vec<Option<String>> = output_columns(&select)
+            .into_iter()
+            .map(|ident| ident.map(|ident| ident.value.to_string()))
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                Some("id".to_string()),
+                Some("amount".to_string()),
+                Some("total".to_string()),
+                None,
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_subquery_depth() {
+        assert_eq!(max_subquery_depth(&query("SELECT 1")), 0);
+        assert_eq!(
+            max_subquery_depth(&query("WITH a AS (SELECT * FROM (SELECT 1) AS b) SELECT * FROM a")),
+            1
+        );
+        assert_eq!(
+            max_subquery_depth(&query(
+                "SELECT * FROM (SELECT * FROM t WHERE id IN (SELECT id FROM u)) AS v \
+                 WHERE EXISTS (SELECT 1)"
+            )),
+            2
+        );
+    }
+}
@@ -17,22 +17,33 @@ use alloc::{
     string::{String, ToString},
     vec::Vec,
 };
+use core::cmp::Ordering;
 use core::fmt;
+use core::hash::{Hash, Hasher};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "visitor")]
-use sqlparser_derive::{Visit, VisitMut};
+use dbtranslate_derive::{Visit, VisitMut};
+
+use crate::tokens::Span;
 
 pub use self::data_type::{
-    CharLengthUnits, CharacterLength, DataType, ExactNumberInfo, TimezoneInfo,
+    ArrayElemTypeDef, CharLengthUnits, CharacterLength, DataType, ExactNumberInfo, StructField,
+    TimezoneInfo,
 };
 pub use self::operator::{BinaryOperator, UnaryOperator};
 pub use self::query::{
-    Cte, ExceptSelectItem, ExcludeSelectItem, IdentWithAlias, Join, JoinConstraint,
-    JoinOperator, LateralView, Offset, OffsetRows, OrderByExpr,
+    Cte, DbtConfig, DbtConfigValue, DbtMacroCall, ExceptSelectItem, ExcludeSelectItem, IdentWithAlias, JinjaBlock, JinjaBranch, JinjaConditional, JinjaFor, JinjaMacro, JinjaMacroArg, JinjaValue,
+    JinjaVariable, Join,
+    JoinConstraint, JoinOperator, LateralView, Offset, OffsetRows, OrderByExpr,
     Query, RenameSelectItem, ReplaceSelectElement, ReplaceSelectItem, Select, SelectInto,
     SelectItem, SetExpr, SetOperator, SetQuantifier, Table, TableAlias, TableFactor,
     TableWithJoins, Top, Values, WildcardAdditionalOptions, With,
 };
+pub use self::spans::{AttachedSpan, Spanned};
+pub use self::symbol::Symbol;
 pub use self::value::{
     escape_quoted_string, DateTimeField, DollarQuotedString, TrimWhereField, Value,
 };
@@ -41,8 +52,12 @@ pub use self::value::{
 pub use visitor::*;
 
 mod data_type;
+#[cfg(feature = "visitor")]
+pub mod helpers;
 mod operator;
 pub mod query;
+mod spans;
+mod symbol;
 mod value;
 
 #[cfg(feature = "visitor")]
@@ -86,26 +101,31 @@ where
 }
 
 /// An identifier, decomposed into its value or character data and the quote style.
-#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
 pub struct Ident {
-    /// The value of the identifier without quotes.
-    pub value: String,
+    /// The value of the identifier without quotes, interned.
+    pub value: Symbol,
     /// The starting quote if any. Valid quote characters are the single quote,
     /// double quote, backtick, and opening square bracket.
     pub quote_style: Option<char>,
+    /// Where the identifier is in the source, including any quotes. It isn't
+    /// compared or hashed, so identifiers with the same value are equal
+    /// wherever they come from.
+    pub span: Span,
 }
 
 impl Ident {
     /// Create a new identifier with the given value and no quotes.
     pub fn new<S>(value: S) -> Self
     where
-        S: Into<String>,
+        S: Into<Symbol>,
     {
         Ident {
             value: value.into(),
             quote_style: None,
+            span: Span::empty(),
         }
     }
 
@@ -113,22 +133,68 @@ impl Ident {
     /// panics if the given quote is not a valid quote character.
     pub fn with_quote<S>(quote: char, value: S) -> Self
     where
-        S: Into<String>,
+        S: Into<Symbol>,
+    {
+        Ident::with_quote_and_span(quote, Span::empty(), value)
+    }
+
+    /// Create a new identifier with the given value and span and no quotes.
+    pub fn with_span<S>(span: Span, value: S) -> Self
+    where
+        S: Into<Symbol>,
+    {
+        Ident {
+            value: value.into(),
+            quote_style: None,
+            span,
+        }
+    }
+
+    /// Create a new quoted identifier with the given quote, span and value.
+    /// This function panics if the given quote is not a valid quote character.
+    pub fn with_quote_and_span<S>(quote: char, span: Span, value: S) -> Self
+    where
+        S: Into<Symbol>,
     {
         assert!(quote == '\'' || quote == '"' || quote == '`' || quote == '[');
         Ident {
             value: value.into(),
             quote_style: Some(quote),
+            span,
         }
     }
 }
 
+impl PartialEq for Ident {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.quote_style == other.quote_style
+    }
+}
+
+impl Eq for Ident {}
+
+impl Hash for Ident {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+        self.quote_style.hash(state);
+    }
+}
+
+impl PartialOrd for Ident {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ident {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.value, self.quote_style).cmp(&(&other.value, other.quote_style))
+    }
+}
+
 impl From<&str> for Ident {
     fn from(value: &str) -> Self {
-        Ident {
-            value: value.to_string(),
-            quote_style: None,
-        }
+        Ident::new(value)
     }
 }
 
@@ -139,7 +205,7 @@ impl fmt::Display for Ident {
                 let escaped = value::escape_quoted_string(&self.value, q);
                 write!(f, "{q}{escaped}{q}")
             }
-            Some(q) if q == '[' => write!(f, "[{}]", self.value),
+            Some('[') => write!(f, "[{}]", self.value),
             None => f.write_str(&self.value),
             _ => panic!("unexpected quote style"),
         }
@@ -476,6 +542,18 @@ pub enum Expr {
     Rollup(Vec<Vec<Expr>>),
     /// ROW / TUPLE a single value, such as `SELECT (1, 2)`
     Tuple(Vec<Expr>),
+    /// A BigQuery struct literal, e.g. `STRUCT(1 AS a, 'x' AS b)` or
+    /// `STRUCT<a INT64, b STRING>(1, 'x')`
+    Struct {
+        values: Vec<Expr>,
+        /// Empty unless the fields are typed
+        fields: Vec<StructField>,
+    },
+    /// A value given a name inside a struct literal, e.g. `1 AS a`
+    Named { expr: Box<Expr>, name: Ident },
+    /// A lambda passed to a higher-order function, e.g. `x -> x > 1` in
+    /// Trino's `filter(items, x -> x > 1)` or `(k, v) -> v` in `map_filter`
+    Lambda { params: Vec<Ident>, body: Box<Expr> },
     /// An array index expression e.g. `(ARRAY[1, 2])[1]` or `(current_schemas(FALSE))[1]`
     ArrayIndex { obj: Box<Expr>, indexes: Vec<Expr> },
     /// An array expression e.g. `ARRAY[1, 2]`
@@ -499,6 +577,28 @@ pub enum Expr {
         /// or as `__ TO SECOND(x)`.
         fractional_seconds_precision: Option<u64>,
     },
+    /// A Jinja `{% if %}` or `{% for %}` block in an expression. `expr` is
+    /// the SQL in front of the block that it extends, e.g. `a = 1` in
+    /// `WHERE a = 1 {% if x %} AND b = 2 {% endif %}`.
+    JinjaBlock {
+        expr: Option<Box<Expr>>,
+        block: JinjaBlock,
+    },
+    /// A dbt `{{ var('name') }}` call, with an optional default value as in
+    /// `{{ var('start_date', '2020-01-01') }}`
+    DbtVar {
+        name: Ident,
+        default: Option<Box<Expr>>,
+    },
+    /// A dbt `{{ env_var('DBT_SCHEMA') }}` call, with an optional default
+    /// value as in `{{ env_var('DBT_SCHEMA', 'analytics') }}`
+    DbtEnvVar {
+        name: Ident,
+        default: Option<Box<Expr>>,
+    },
+    /// Any other macro used as an expression, e.g.
+    /// `{{ dbt_utils.safe_divide('revenue', 'orders') }}`
+    DbtMacroCall(DbtMacroCall),
 }
 
 impl fmt::Display for Expr {
@@ -800,6 +900,23 @@ impl fmt::Display for Expr {
             Expr::Tuple(exprs) => {
                 write!(f, "({})", display_comma_separated(exprs))
             }
+            Expr::Struct { values, fields } => {
+                if fields.is_empty() {
+                    write!(f, "STRUCT({})", display_comma_separated(values))
+                } else {
+                    write!(
+                        f,
+                        "STRUCT<{}>({})",
+                        display_comma_separated(fields),
+                        display_comma_separated(values)
+                    )
+                }
+            }
+            Expr::Named { expr, name } => write!(f, "{expr} AS {name}"),
+            Expr::Lambda { params, body } => match params.as_slice() {
+                [param] => write!(f, "{param} -> {body}"),
+                _ => write!(f, "({}) -> {body}", display_comma_separated(params)),
+            },
             Expr::ArrayIndex { obj, indexes } => {
                 write!(f, "{obj}")?;
                 for i in indexes {
@@ -867,6 +984,19 @@ impl fmt::Display for Expr {
                 }
                 Ok(())
             }
+            Expr::JinjaBlock { expr, block } => match expr {
+                Some(expr) => write!(f, "{expr} {block}"),
+                None => write!(f, "{block}"),
+            },
+            Expr::DbtVar { name, default } => match default {
+                Some(default) => write!(f, "{{{{ var({name}, {default}) }}}}"),
+                None => write!(f, "{{{{ var({name}) }}}}"),
+            },
+            Expr::DbtEnvVar { name, default } => match default {
+                Some(default) => write!(f, "{{{{ env_var({name}, {default}) }}}}"),
+                None => write!(f, "{{{{ env_var({name}) }}}}"),
+            },
+            Expr::DbtMacroCall(call) => write!(f, "{call}"),
         }
     }
 }
@@ -1254,6 +1384,23 @@ impl fmt::Display for ArrayAgg {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_statements_round_trip_through_json() {
+        use crate::dialect::GenericDialect;
+        use crate::parser::Parser;
+
+        let sql = "WITH o AS (SELECT id, SUM(amount) AS total FROM {{ ref('orders') }} GROUP BY id) \
+                   SELECT * FROM o JOIN {{ source('stripe', 'payments') }} AS p ON o.id = p.order_id";
+        let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+
+        let json = serde_json::to_string(&statements).unwrap();
+        let round_tripped: Vec<Statement> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(round_tripped, statements);
+        assert!(json.contains("\"DbtRef\""));
+    }
+
     #[test]
     fn test_window_frame_default() {
         let window_frame = WindowFrame::default();
@@ -19,7 +19,7 @@ use alloc::{string::String, vec::Vec};
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "visitor")]
-use sqlparser_derive::{Visit, VisitMut};
+use dbtranslate_derive::{Visit, VisitMut};
 
 use super::display_separated;
 
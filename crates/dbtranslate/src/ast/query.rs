@@ -17,10 +17,11 @@ use alloc::{boxed::Box, vec::Vec};
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "visitor")]
-use sqlparser_derive::{Visit, VisitMut};
+use dbtranslate_derive::{Visit, VisitMut};
 
 use crate::ast::*;
-use std::collections::HashMap;
+use crate::tokens::Token;
+use std::collections::{BTreeMap, HashMap};
 use std::cmp::Ordering;
 use std::hash::Hash;
 
@@ -48,10 +49,19 @@ pub struct Query {
     pub offset: Option<Offset>,
     /// JinjaVariables - such as {% set variable = "string" %}
     pub jinja_variables: Vec<JinjaVariable>,
+    /// Jinja blocks before the query, e.g. an `{% if %}` around
+    /// `{{ config() }}`
+    pub jinja_blocks: Vec<JinjaBlock>,
 }
 
 impl fmt::Display for Query {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for jinja_variable in &self.jinja_variables {
+            write!(f, "{jinja_variable} ")?;
+        }
+        for jinja_block in &self.jinja_blocks {
+            write!(f, "{jinja_block} ")?;
+        }
         if let Some(ref config) = self.config {
             write!(f, "{config} ")?;
         }
@@ -118,26 +128,54 @@ impl Hash for DbtConfig {
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+/// A value passed to `config()`, as Jinja reads it
 pub enum DbtConfigValue {
     String(String),
-    List(Vec<String>),
+    /// `true` or `false`, or Python's `True` and `False`
+    Boolean(bool),
+    /// A number, kept as it was written
+    Number(String),
+    /// `none` or `None`
+    Null,
+    /// e.g. `tags=['nightly', 'finance']`
+    List(Vec<DbtConfigValue>),
+    /// e.g. `persist_docs={'relation': true, 'columns': true}`
+    Dict(BTreeMap<String, DbtConfigValue>),
+    /// Anything worked out when dbt renders the model, e.g.
+    /// `enabled=var('run_heavy_models')` or `enabled=target.name == 'prod'`
+    Jinja(Expr),
+}
+
+impl DbtConfigValue {
+    /// The value if it's a string, e.g. `materialized='table'`
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            DbtConfigValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for DbtConfigValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             DbtConfigValue::String(s) => write!(f, "{}", s),
-            DbtConfigValue::List(v) => {
-                write!(f, "[")?;
+            DbtConfigValue::Boolean(b) => write!(f, "{}", b),
+            DbtConfigValue::Number(n) => write!(f, "{}", n),
+            DbtConfigValue::Null => write!(f, "none"),
+            DbtConfigValue::Jinja(expr) => write!(f, "{}", expr),
+            DbtConfigValue::List(values) => write!(f, "[{}]", display_comma_separated(values)),
+            DbtConfigValue::Dict(entries) => {
+                write!(f, "{{")?;
                 let mut first = true;
-                for s in v {
+                for (key, value) in entries {
                     if !first {
                         write!(f, ", ")?;
                     }
                     first = false;
-                    write!(f, "{}", s)?;
+                    write!(f, "{}: {}", key, value)?;
                 }
-                write!(f, "]")
+                write!(f, "}}")
             }
         }
     }
@@ -161,10 +199,15 @@ pub enum SetExpr {
         set_quantifier: SetQuantifier,
         left: Box<SetExpr>,
         right: Box<SetExpr>,
+        /// Where the operator's keyword, e.g. `UNION`, is
+        op_span: AttachedSpan,
     },
     Values(Values),
     Insert(Statement),
     Table(Box<Table>),
+    /// A Jinja block wrapping the whole query, e.g.
+    /// `{% if target.name == 'prod' %} SELECT 1 {% else %} SELECT 2 {% endif %}`
+    JinjaBlock(JinjaBlock),
 }
 
 impl fmt::Display for SetExpr {
@@ -175,11 +218,13 @@ impl fmt::Display for SetExpr {
             SetExpr::Values(v) => write!(f, "{v}"),
             SetExpr::Insert(v) => write!(f, "{v}"),
             SetExpr::Table(t) => write!(f, "{t}"),
+            SetExpr::JinjaBlock(block) => write!(f, "{block}"),
             SetExpr::SetOperation {
                 left,
                 right,
                 op,
                 set_quantifier,
+                ..
             } => {
                 write!(f, "{left} {op}")?;
                 match set_quantifier {
@@ -266,6 +311,8 @@ impl fmt::Display for Table {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
 pub struct Select {
+    /// Where the `SELECT` keyword is
+    pub select_span: AttachedSpan,
     pub distinct: bool,
     /// MSSQL syntax: `TOP (<N>) [ PERCENT ] [ WITH TIES ]`
     pub top: Option<Top>,
@@ -291,6 +338,9 @@ pub struct Select {
     pub having: Option<Expr>,
     /// QUALIFY (Snowflake)
     pub qualify: Option<Expr>,
+    /// Jinja blocks wrapping whole clauses after the FROM clause, e.g.
+    /// `{% if is_incremental() %} WHERE updated_at > ... {% endif %}`
+    pub jinja_blocks: Vec<JinjaBlock>,
 }
 
 impl fmt::Display for Select {
@@ -313,6 +363,9 @@ impl fmt::Display for Select {
                 write!(f, "{lv}")?;
             }
         }
+        for jinja_block in &self.jinja_blocks {
+            write!(f, " {jinja_block}")?;
+        }
         if let Some(ref selection) = self.selection {
             write!(f, " WHERE {selection}")?;
         }
@@ -472,6 +525,8 @@ pub struct WildcardAdditionalOptions {
     /// `[REPLACE]`
     ///  BigQuery syntax: <https://cloud.google.com/bigquery/docs/reference/standard-sql/query-syntax#select_replace>
     pub opt_replace: Option<ReplaceSelectItem>,
+    /// Where the `*` is in the source
+    pub wildcard_span: AttachedSpan,
 }
 
 impl fmt::Display for WildcardAdditionalOptions {
@@ -692,7 +747,11 @@ impl fmt::Display for TableWithJoins {
 /// A table name or a parenthesized subquery with an optional alias
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+#[cfg_attr(
+    feature = "visitor",
+    derive(Visit, VisitMut),
+    visit(with = "visit_table_factor")
+)]
 pub enum TableFactor {
     Table {
         #[cfg_attr(feature = "visitor", visit(with = "visit_relation"))]
@@ -714,8 +773,22 @@ pub enum TableFactor {
         subquery: Box<Query>,
         alias: Option<TableAlias>,
     },
+    /// `LATERAL <function>(<args>)[ AS <alias> ]`, e.g. Snowflake's
+    /// `LATERAL FLATTEN(input => t.items) AS f`
+    Function {
+        lateral: bool,
+        name: ObjectName,
+        args: Vec<FunctionArg>,
+        alias: Option<TableAlias>,
+    },
+    /// `{{ ref([package, ]model[, v=version]) }}`
     DbtRef {
+        /// The package the model is in, for `ref('package', 'model')`
+        package: Option<Ident>,
         model_name: Ident,
+        /// The model version, for `ref('model', v=2)`. dbt also takes
+        /// `version=2`, which is displayed as `v=2`.
+        version: Option<Value>,
         alias: Option<TableAlias>,
     },
     DbtSource {
@@ -723,6 +796,19 @@ pub enum TableFactor {
         table_name: Ident,
         alias: Option<TableAlias>,
     },
+    /// `{{ this }}`, the relation the model itself builds, e.g. in an
+    /// incremental model's `SELECT MAX(updated_at) FROM {{ this }}`
+    DbtThis {
+        /// The `this`, for where it is in the model
+        this: Ident,
+        alias: Option<TableAlias>,
+    },
+    /// Any other macro used as a table, e.g.
+    /// `{{ dbt_utils.date_spine('day', '2020-01-01', '2021-01-01') }}`
+    DbtMacro {
+        call: DbtMacroCall,
+        alias: Option<TableAlias>,
+    },
     /// `TABLE(<expr>)[ AS <alias> ]`
     TableFunction {
         expr: Expr,
@@ -738,9 +824,17 @@ pub enum TableFactor {
     /// | 30      | 2      |
     /// +---------+--------+
     /// ```
+    ///
+    /// Trino and Postgres can unnest several arrays side by side and number
+    /// the rows with `WITH ORDINALITY` instead:
+    ///
+    /// ```sql
+    /// SELECT * FROM UNNEST(ARRAY['a', 'b'], ARRAY[1, 2]) WITH ORDINALITY AS t (letter, number, n);
+    /// ```
     UNNEST {
         alias: Option<TableAlias>,
-        array_expr: Box<Expr>,
+        array_exprs: Vec<Expr>,
+        with_ordinality: bool,
         with_offset: bool,
         with_offset_alias: Option<Ident>,
     },
@@ -791,8 +885,16 @@ impl fmt::Display for TableFactor {
             }
             // This is for dbt ref. Why are there 4 curlies?
             // To include literal curly braces in a formatted string, you need to escape them by doubling them.
-            TableFactor::DbtRef { model_name, alias } => {
-                write!(f, "{{{{ ref({}) }}}}", model_name)?;
+            TableFactor::DbtRef { package, model_name, version, alias } => {
+                write!(f, "{{{{ ref(")?;
+                if let Some(package) = package {
+                    write!(f, "{package}, ")?;
+                }
+                write!(f, "{model_name}")?;
+                if let Some(version) = version {
+                    write!(f, ", v={version}")?;
+                }
+                write!(f, ") }}}}")?;
                 if let Some(alias) = alias {
                     write!(f, " AS {alias}")?;
                 }
@@ -809,6 +911,22 @@ impl fmt::Display for TableFactor {
                 Ok(())
             }
 
+            TableFactor::DbtThis { alias, .. } => {
+                write!(f, "{{{{ this }}}}")?;
+                if let Some(alias) = alias {
+                    write!(f, " AS {alias}")?;
+                }
+                Ok(())
+            }
+
+            TableFactor::DbtMacro { call, alias } => {
+                write!(f, "{call}")?;
+                if let Some(alias) = alias {
+                    write!(f, " AS {alias}")?;
+                }
+                Ok(())
+            }
+
             TableFactor::Derived {
                 lateral,
                 subquery,
@@ -823,6 +941,21 @@ impl fmt::Display for TableFactor {
                 }
                 Ok(())
             }
+            TableFactor::Function {
+                lateral,
+                name,
+                args,
+                alias,
+            } => {
+                if *lateral {
+                    write!(f, "LATERAL ")?;
+                }
+                write!(f, "{name}({})", display_comma_separated(args))?;
+                if let Some(alias) = alias {
+                    write!(f, " AS {alias}")?;
+                }
+                Ok(())
+            }
             TableFactor::TableFunction { expr, alias } => {
                 write!(f, "TABLE({expr})")?;
                 if let Some(alias) = alias {
@@ -832,11 +965,15 @@ impl fmt::Display for TableFactor {
             }
             TableFactor::UNNEST {
                 alias,
-                array_expr,
+                array_exprs,
+                with_ordinality,
                 with_offset,
                 with_offset_alias,
             } => {
-                write!(f, "UNNEST({array_expr})")?;
+                write!(f, "UNNEST({})", display_comma_separated(array_exprs))?;
+                if *with_ordinality {
+                    write!(f, " WITH ORDINALITY")?;
+                }
                 if let Some(alias) = alias {
                     write!(f, " AS {alias}")?;
                 }
@@ -971,6 +1108,7 @@ impl fmt::Display for Join {
                 suffix(constraint)
             ),
             JoinOperator::CrossJoin => write!(f, " CROSS JOIN {}", self.relation),
+            JoinOperator::PositionalJoin => write!(f, " POSITIONAL JOIN {}", self.relation),
             JoinOperator::LeftSemi(constraint) => write!(
                 f,
                 " {}LEFT SEMI JOIN {}{}",
@@ -1026,6 +1164,8 @@ pub enum JoinOperator {
     CrossApply,
     /// OUTER APPLY (non-standard)
     OuterApply,
+    /// POSITIONAL JOIN (DuckDB)
+    PositionalJoin,
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -1178,23 +1318,204 @@ pub struct JinjaVariable {
 
 impl fmt::Display for JinjaVariable {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{{% set {} = {} %}}", self.key, self.value)
+        match &self.value {
+            JinjaValue::Block(body) => write!(f, "{{% set {} %}}{}{{% endset %}}", self.key, display_tokens(body)),
+            value => write!(f, "{{% set {} = {} %}}", self.key, value),
+        }
     }
 }
 
+/// A Jinja block that wraps SQL. The SQL inside can be any fragment (a
+/// trailing `AND ...`, a whole `WHERE` clause, a column followed by a
+/// comma), so it is kept as tokens rather than parsed.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+pub enum JinjaBlock {
+    If(JinjaConditional),
+    For(JinjaFor),
+}
+
+impl JinjaBlock {
+    /// Whether the SQL inside ends with a comma, as in
+    /// `{% if x %} b, {% endif %}`
+    pub fn ends_with_comma(&self) -> bool {
+        match self {
+            JinjaBlock::If(conditional) => conditional
+                .branches
+                .iter()
+                .any(|branch| ends_with_comma(&branch.body)),
+            JinjaBlock::For(for_loop) => ends_with_comma(&for_loop.body),
+        }
+    }
+}
+
+impl fmt::Display for JinjaBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JinjaBlock::If(conditional) => write!(f, "{conditional}"),
+            JinjaBlock::For(for_loop) => write!(f, "{for_loop}"),
+        }
+    }
+}
+
+/// `{% if ... %} ... {% elif ... %} ... {% else %} ... {% endif %}`
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+pub struct JinjaConditional {
+    pub branches: Vec<JinjaBranch>,
+}
+
+impl fmt::Display for JinjaConditional {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, branch) in self.branches.iter().enumerate() {
+            match (&branch.condition, i) {
+                (Some(condition), 0) => write!(f, "{{% if {} %}}", display_tokens(condition))?,
+                (Some(condition), _) => write!(f, "{{% elif {} %}}", display_tokens(condition))?,
+                (None, _) => write!(f, "{{% else %}}")?,
+            }
+            write!(f, "{}", display_tokens(&branch.body))?;
+        }
+        write!(f, "{{% endif %}}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+pub struct JinjaBranch {
+    /// The Jinja expression after `if`/`elif`, or `None` for `else`
+    pub condition: Option<Vec<Token<'static>>>,
+    /// Everything up to the next `elif`/`else`/`endif`, whitespace included
+    pub body: Vec<Token<'static>>,
+}
+
+/// `{% for ... in ... %} ... {% endfor %}`
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+pub struct JinjaFor {
+    /// The loop variables, e.g. `key` and `value` in
+    /// `{% for key, value in columns.items() %}`
+    pub variables: Vec<Ident>,
+    /// The Jinja expression after `in`
+    pub iterable: Vec<Token<'static>>,
+    /// Everything up to the matching `endfor`, whitespace included
+    pub body: Vec<Token<'static>>,
+}
+
+impl fmt::Display for JinjaFor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{% for {} in {} %}}{}{{% endfor %}}",
+            display_comma_separated(&self.variables),
+            display_tokens(&self.iterable),
+            display_tokens(&self.body)
+        )
+    }
+}
+
+/// `{% macro name(args) %} ... {% endmacro %}`, from a file in a dbt
+/// project's macro paths
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+pub struct JinjaMacro {
+    pub name: Ident,
+    pub args: Vec<JinjaMacroArg>,
+    /// Everything up to the matching `endmacro`, whitespace included
+    pub body: Vec<Token<'static>>,
+}
+
+impl fmt::Display for JinjaMacro {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{{% macro {}({}) %}}{}{{% endmacro %}}",
+            self.name,
+            display_comma_separated(&self.args),
+            display_tokens(&self.body)
+        )
+    }
+}
+
+/// An argument of a macro, e.g. `column_name` or `precision=2`
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+pub struct JinjaMacroArg {
+    pub name: Ident,
+    /// The Jinja expression after `=`
+    pub default: Option<Vec<Token<'static>>>,
+}
+
+impl fmt::Display for JinjaMacroArg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(default) = &self.default {
+            write!(f, "={}", display_tokens(default))?;
+        }
+        Ok(())
+    }
+}
+
+/// A call to a macro inside `{{ }}` that dbtonic doesn't know the meaning
+/// of, e.g. `{{ dbt_utils.star(ref('orders')) }}`. The arguments can be any
+/// Jinja expression, so they are kept as tokens.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
+pub struct DbtMacroCall {
+    /// The package the macro comes from, e.g. `dbt_utils`
+    pub namespace: Option<Ident>,
+    pub name: Ident,
+    /// Everything between the parentheses, whitespace included
+    pub args: Vec<Token<'static>>,
+}
+
+impl fmt::Display for DbtMacroCall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{{{ ")?;
+        if let Some(namespace) = &self.namespace {
+            write!(f, "{namespace}.")?;
+        }
+        write!(f, "{}({}) }}}}", self.name, display_tokens(&self.args))
+    }
+}
+
+fn ends_with_comma(tokens: &[Token]) -> bool {
+    tokens
+        .iter()
+        .rev()
+        .find(|token| !matches!(token, Token::Whitespace(_)))
+        == Some(&Token::Comma)
+}
+
+fn display_tokens(tokens: &[Token]) -> String {
+    tokens.iter().map(|token| token.to_string()).collect()
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
 pub enum JinjaValue {
     Str(String),
+    Number(String),
     List(Vec<JinjaValue>),
-    // Add other value types here as needed, e.g., Function, etc.
+    /// Any other Jinja expression, e.g. `var('start_date')`, kept as tokens
+    Expr(Vec<Token<'static>>),
+    /// Everything between `{% set x %}` and `{% endset %}`, whitespace
+    /// included
+    Block(Vec<Token<'static>>),
 }
 
 impl fmt::Display for JinjaValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            JinjaValue::Str(s) => write!(f, "{}", s),
+            JinjaValue::Str(s) => write!(f, "'{}'", escape_quoted_string(s, '\'')),
+            JinjaValue::Number(n) => write!(f, "{}", n),
             JinjaValue::List(list) => {
                 let list_str = list
                     .iter()
@@ -1203,7 +1524,7 @@ impl fmt::Display for JinjaValue {
                     .join(", ");
                 write!(f, "[{}]", list_str)
             }
-            // Add other value types here as needed, e.g., Function, etc.
+            JinjaValue::Expr(tokens) | JinjaValue::Block(tokens) => write!(f, "{}", display_tokens(tokens)),
         }
     }
 }
\ No newline at end of file
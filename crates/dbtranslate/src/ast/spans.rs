@@ -0,0 +1,373 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Source locations for AST nodes.
+//!
+//! Identifiers, wildcards and the `SELECT` and set operator keywords keep
+//! the [Span] of the tokens they were parsed from. Every other node works
+//! its span out from its children with the [Spanned] trait, so it covers the
+//! first to the last of those in it. Literals and other keywords don't keep
+//! a span, which means `SUM(amount)` spans `SUM(amount`, `1 + 2` has an
+//! empty span and `SELECT 1 + 2` only spans the `SELECT`.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::ast::*;
+use crate::tokens::Span;
+
+/// A [Span] kept on a node for a token that otherwise wouldn't make it into
+/// the AST, like the `*` of a wildcard. Like an [Ident]'s span, it isn't
+/// compared or hashed, so the same query is equal wherever it was parsed.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AttachedSpan(pub Span);
+
+impl PartialEq for AttachedSpan {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for AttachedSpan {}
+
+impl Hash for AttachedSpan {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl PartialOrd for AttachedSpan {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AttachedSpan {
+    fn cmp(&self, _: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+/// A node that knows where it is in the source
+pub trait Spanned {
+    /// The span of the node, or an empty span if nothing in it has one
+    fn span(&self) -> Span;
+}
+
+impl<T: Spanned> Spanned for Box<T> {
+    fn span(&self) -> Span {
+        self.as_ref().span()
+    }
+}
+
+impl<T: Spanned> Spanned for Option<T> {
+    fn span(&self) -> Span {
+        self.as_ref().map(Spanned::span).unwrap_or_default()
+    }
+}
+
+impl<T: Spanned> Spanned for Vec<T> {
+    fn span(&self) -> Span {
+        Span::union_iter(self.iter().map(Spanned::span))
+    }
+}
+
+impl Spanned for Ident {
+    fn span(&self) -> Span {
+        self.span.clone()
+    }
+}
+
+impl Spanned for ObjectName {
+    fn span(&self) -> Span {
+        self.0.span()
+    }
+}
+
+impl Spanned for Statement {
+    fn span(&self) -> Span {
+        match self {
+            Statement::Query(query) => query.span(),
+        }
+    }
+}
+
+impl Spanned for Query {
+    fn span(&self) -> Span {
+        Span::union_iter([
+            self.with.as_ref().map(|with| with.cte_tables.span()).unwrap_or_default(),
+            self.body.span(),
+            self.order_by.span(),
+            self.limit.span(),
+            self.offset.as_ref().map(|offset| offset.value.span()).unwrap_or_default(),
+        ])
+    }
+}
+
+impl Spanned for Cte {
+    fn span(&self) -> Span {
+        Span::union_iter([self.alias.span(), self.query.span(), self.from.span()])
+    }
+}
+
+impl Spanned for SetExpr {
+    fn span(&self) -> Span {
+        match self {
+            SetExpr::Select(select) => select.span(),
+            SetExpr::Query(query) => query.span(),
+            SetExpr::SetOperation { left, right, op_span, .. } => {
+                Span::union_iter([left.span(), op_span.0.clone(), right.span()])
+            }
+            SetExpr::Values(values) => Span::union_iter(values.rows.iter().map(Spanned::span)),
+            SetExpr::Insert(statement) => statement.span(),
+            SetExpr::Table(_) => Span::empty(),
+            SetExpr::JinjaBlock(block) => block.span(),
+        }
+    }
+}
+
+impl Spanned for Select {
+    fn span(&self) -> Span {
+        Span::union_iter([
+            self.select_span.0.clone(),
+            self.projection.span(),
+            self.from.span(),
+            self.selection.span(),
+            self.group_by.span(),
+            self.having.span(),
+            self.qualify.span(),
+        ])
+    }
+}
+
+impl Spanned for SelectItem {
+    fn span(&self) -> Span {
+        match self {
+            SelectItem::UnnamedExpr(expr) => expr.span(),
+            SelectItem::ExprWithAlias { expr, alias } => expr.span().union(&alias.span),
+            SelectItem::QualifiedWildcard(prefix, options) => prefix.span().union(&options.wildcard_span.0),
+            SelectItem::Wildcard(options) => options.wildcard_span.0.clone(),
+        }
+    }
+}
+
+impl Spanned for TableWithJoins {
+    fn span(&self) -> Span {
+        self.relation.span().union(&self.joins.span())
+    }
+}
+
+impl Spanned for Join {
+    fn span(&self) -> Span {
+        let constraint = match &self.join_operator {
+            JoinOperator::Inner(constraint)
+            | JoinOperator::LeftOuter(constraint)
+            | JoinOperator::RightOuter(constraint)
+            | JoinOperator::FullOuter(constraint)
+            | JoinOperator::LeftSemi(constraint)
+            | JoinOperator::RightSemi(constraint)
+            | JoinOperator::LeftAnti(constraint)
+            | JoinOperator::RightAnti(constraint) => match constraint {
+                JoinConstraint::On(expr) => expr.span(),
+                JoinConstraint::Using(columns) => columns.span(),
+                JoinConstraint::Natural | JoinConstraint::None => Span::empty(),
+            },
+            JoinOperator::CrossJoin
+            | JoinOperator::CrossApply
+            | JoinOperator::OuterApply
+            | JoinOperator::PositionalJoin => Span::empty(),
+        };
+        self.relation.span().union(&constraint)
+    }
+}
+
+impl Spanned for TableAlias {
+    fn span(&self) -> Span {
+        self.name.span().union(&self.columns.span())
+    }
+}
+
+impl Spanned for TableFactor {
+    fn span(&self) -> Span {
+        match self {
+            TableFactor::Table { name, alias, args, with_hints } => {
+                Span::union_iter([name.span(), alias.span(), args.span(), with_hints.span()])
+            }
+            TableFactor::Derived { subquery, alias, .. } => subquery.span().union(&alias.span()),
+            TableFactor::DbtRef { package, model_name, alias, .. } => {
+                Span::union_iter([package.span(), model_name.span(), alias.span()])
+            }
+            TableFactor::DbtSource { source_name, table_name, alias } => {
+                Span::union_iter([source_name.span(), table_name.span(), alias.span()])
+            }
+            TableFactor::DbtThis { this, alias } => this.span().union(&alias.span()),
+            TableFactor::DbtMacro { call, alias } => call.span().union(&alias.span()),
+            TableFactor::Function { name, args, alias, .. } => {
+                Span::union_iter([name.span(), args.span(), alias.span()])
+            }
+            TableFactor::TableFunction { expr, alias } => expr.span().union(&alias.span()),
+            TableFactor::UNNEST { alias, array_exprs, with_offset_alias, .. } => {
+                Span::union_iter([array_exprs.span(), alias.span(), with_offset_alias.span()])
+            }
+            TableFactor::NestedJoin { table_with_joins, alias } => table_with_joins.span().union(&alias.span()),
+            TableFactor::Pivot { name, table_alias, aggregate_function, value_column, pivot_alias, .. } => {
+                Span::union_iter([
+                    name.span(),
+                    table_alias.span(),
+                    aggregate_function.span(),
+                    value_column.span(),
+                    pivot_alias.span(),
+                ])
+            }
+        }
+    }
+}
+
+impl Spanned for DbtMacroCall {
+    fn span(&self) -> Span {
+        self.namespace.span().union(&self.name.span)
+    }
+}
+
+impl Spanned for OrderByExpr {
+    fn span(&self) -> Span {
+        self.expr.span()
+    }
+}
+
+impl Spanned for Function {
+    fn span(&self) -> Span {
+        let over = self
+            .over
+            .as_ref()
+            .map(|over| over.partition_by.span().union(&over.order_by.span()))
+            .unwrap_or_default();
+        Span::union_iter([self.name.span(), self.args.span(), over])
+    }
+}
+
+impl Spanned for FunctionArg {
+    fn span(&self) -> Span {
+        match self {
+            FunctionArg::Named { name, arg } => name.span().union(&arg.span()),
+            FunctionArg::Unnamed(arg) => arg.span(),
+        }
+    }
+}
+
+impl Spanned for FunctionArgExpr {
+    fn span(&self) -> Span {
+        match self {
+            FunctionArgExpr::Expr(expr) => expr.span(),
+            FunctionArgExpr::QualifiedWildcard(prefix) => prefix.span(),
+            FunctionArgExpr::Wildcard => Span::empty(),
+        }
+    }
+}
+
+impl Spanned for JinjaBlock {
+    fn span(&self) -> Span {
+        match self {
+            JinjaBlock::If(_) => Span::empty(),
+            JinjaBlock::For(jinja_for) => jinja_for.variables.span(),
+        }
+    }
+}
+
+impl Spanned for Expr {
+    fn span(&self) -> Span {
+        match self {
+            Expr::Identifier(ident) => ident.span(),
+            Expr::CompoundIdentifier(idents) => idents.span(),
+            Expr::JsonAccess { left, right, .. } => left.span().union(&right.span()),
+            Expr::CompositeAccess { expr, key } => expr.span().union(&key.span),
+            Expr::IsFalse(expr)
+            | Expr::IsNotFalse(expr)
+            | Expr::IsTrue(expr)
+            | Expr::IsNotTrue(expr)
+            | Expr::IsNull(expr)
+            | Expr::IsNotNull(expr)
+            | Expr::IsUnknown(expr)
+            | Expr::IsNotUnknown(expr)
+            | Expr::AnyOp(expr)
+            | Expr::AllOp(expr)
+            | Expr::Nested(expr) => expr.span(),
+            Expr::IsDistinctFrom(left, right) | Expr::IsNotDistinctFrom(left, right) => left.span().union(&right.span()),
+            Expr::InList { expr, list, .. } => expr.span().union(&list.span()),
+            Expr::InSubquery { expr, subquery, .. } => expr.span().union(&subquery.span()),
+            Expr::InUnnest { expr, array_expr, .. } => expr.span().union(&array_expr.span()),
+            Expr::Between { expr, low, high, .. } => Span::union_iter([expr.span(), low.span(), high.span()]),
+            Expr::BinaryOp { left, right, .. } => left.span().union(&right.span()),
+            Expr::Like { expr, pattern, .. }
+            | Expr::ILike { expr, pattern, .. }
+            | Expr::SimilarTo { expr, pattern, .. } => expr.span().union(&pattern.span()),
+            Expr::UnaryOp { expr, .. }
+            | Expr::Cast { expr, .. }
+            | Expr::TryCast { expr, .. }
+            | Expr::SafeCast { expr, .. }
+            | Expr::Extract { expr, .. }
+            | Expr::Ceil { expr, .. }
+            | Expr::Floor { expr, .. } => expr.span(),
+            Expr::AtTimeZone { timestamp, .. } => timestamp.span(),
+            Expr::Position { expr, r#in } => expr.span().union(&r#in.span()),
+            Expr::Substring { expr, substring_from, substring_for } => {
+                Span::union_iter([expr.span(), substring_from.span(), substring_for.span()])
+            }
+            Expr::Trim { expr, trim_what, .. } => expr.span().union(&trim_what.span()),
+            Expr::Overlay { expr, overlay_what, overlay_from, overlay_for } => Span::union_iter([
+                expr.span(),
+                overlay_what.span(),
+                overlay_from.span(),
+                overlay_for.span(),
+            ]),
+            Expr::Collate { expr, collation } => expr.span().union(&collation.span()),
+            Expr::Value(_) | Expr::IntroducedString { .. } | Expr::TypedString { .. } => Span::empty(),
+            Expr::MapAccess { column, keys } => column.span().union(&keys.span()),
+            Expr::Function(function) => function.span(),
+            Expr::AggregateExpressionWithFilter { expr, filter } => expr.span().union(&filter.span()),
+            Expr::Case { operand, conditions, results, else_result } => Span::union_iter([
+                operand.span(),
+                conditions.span(),
+                results.span(),
+                else_result.span(),
+            ]),
+            Expr::Exists { subquery, .. } => subquery.span(),
+            Expr::Subquery(query) | Expr::ArraySubquery(query) => query.span(),
+            Expr::ListAgg(list_agg) => Span::union_iter([
+                list_agg.expr.span(),
+                list_agg.separator.span(),
+                list_agg.within_group.span(),
+            ]),
+            Expr::ArrayAgg(array_agg) => Span::union_iter([
+                array_agg.expr.span(),
+                array_agg.order_by.span(),
+                array_agg.limit.span(),
+            ]),
+            Expr::GroupingSets(sets) | Expr::Cube(sets) | Expr::Rollup(sets) => {
+                Span::union_iter(sets.iter().map(Spanned::span))
+            }
+            Expr::Tuple(exprs) => exprs.span(),
+            Expr::Struct { values, .. } => values.span(),
+            Expr::Named { expr, name } => expr.span().union(&name.span),
+            Expr::Lambda { params, body } => params.span().union(&body.span()),
+            Expr::ArrayIndex { obj, indexes } => obj.span().union(&indexes.span()),
+            Expr::Array(array) => array.elem.span(),
+            Expr::Interval { value, .. } => value.span(),
+            Expr::JinjaBlock { expr, block } => expr.span().union(&block.span()),
+            Expr::DbtVar { name, default } | Expr::DbtEnvVar { name, default } => name.span().union(&default.span()),
+            Expr::DbtMacroCall(call) => call.span(),
+        }
+    }
+}
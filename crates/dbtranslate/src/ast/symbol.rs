@@ -0,0 +1,291 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interned strings for identifiers.
+//!
+//! Large projects repeat the same column and table names thousands of times,
+//! so each distinct name is stored once and identifiers hold a [`Symbol`]
+//! pointing at it. That saves an allocation per identifier, and comparing
+//! two identifiers compares two pointers rather than their text.
+
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ops::Deref;
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::sync::{Arc, OnceLock, RwLock};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// An interned string. Symbols for the same text point at the same
+/// interned copy of it, which is freed once the last symbol for it is
+/// dropped and the interner next cleans up.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+// Threads parsing models in parallel would all queue on a single lock, so
+// names are spread over shards by their hash
+const SHARDS: usize = 16;
+
+// Below this a shard isn't worth cleaning up
+const MIN_CLEANUP_LEN: usize = 1024;
+
+// The names interned by one shard. Nothing but the shard holding a name
+// means every identifier with it has been dropped, so it can be freed. That
+// happens whenever the shard doubles in size, which keeps the interner in
+// proportion to the ASTs alive in long running processes like `lsp`.
+#[derive(Default)]
+struct Shard {
+    names: HashSet<Arc<str>>,
+    cleanup_len: usize,
+}
+
+impl Shard {
+    fn insert(&mut self, string: &str) -> Arc<str> {
+        if self.names.len() >= self.cleanup_len.max(MIN_CLEANUP_LEN) {
+            self.names.retain(|name| Arc::strong_count(name) > 1);
+            self.cleanup_len = self.names.len() * 2;
+        }
+        let interned: Arc<str> = Arc::from(string);
+        self.names.insert(interned.clone());
+        interned
+    }
+}
+
+struct Interner {
+    hasher: RandomState,
+    shards: [RwLock<Shard>; SHARDS],
+}
+
+fn interner() -> &'static Interner {
+    static INTERNER: OnceLock<Interner> = OnceLock::new();
+    INTERNER.get_or_init(|| Interner {
+        hasher: RandomState::new(),
+        shards: Default::default(),
+    })
+}
+
+impl Interner {
+    fn shard(&self, string: &str) -> &RwLock<Shard> {
+        &self.shards[self.hasher.hash_one(string) as usize % SHARDS]
+    }
+
+    // The number of names interned across the shards, freed or not
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.read().unwrap().names.len()).sum()
+    }
+}
+
+impl Symbol {
+    /// The symbol for `string`, interning it the first time it's seen.
+    pub fn intern(string: &str) -> Symbol {
+        let shard = interner().shard(string);
+        // Most names have been seen before, and a read lock lets the threads
+        // parsing models in parallel look them up at the same time
+        if let Some(interned) = shard.read().unwrap().names.get(string) {
+            return Symbol(interned.clone());
+        }
+        let mut shard = shard.write().unwrap();
+        // Another thread may have interned it while we waited for the lock
+        if let Some(interned) = shard.names.get(string) {
+            return Symbol(interned.clone());
+        }
+        Symbol(shard.insert(string))
+    }
+
+    /// The text the symbol was interned from.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(string: &str) -> Self {
+        Symbol::intern(string)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(string: &String) -> Self {
+        Symbol::intern(string)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(string: String) -> Self {
+        Symbol::intern(&string)
+    }
+}
+
+impl From<Cow<'_, str>> for Symbol {
+    fn from(string: Cow<'_, str>) -> Self {
+        Symbol::intern(&string)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.as_str().to_string()
+    }
+}
+
+// Only one copy of each text is interned, so the pointers are enough
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+// Hashed as the text, so symbols can be looked up in maps keyed by `str`
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+// Ordered by the text rather than by when it was interned, so sorting
+// identifiers doesn't depend on the order models were parsed in
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self == other {
+            true => Ordering::Equal,
+            false => self.as_str().cmp(other.as_str()),
+        }
+    }
+}
+
+// Printed like the string it holds, so the AST's Debug output is unchanged
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Symbol::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let orders = Symbol::intern("orders");
+        assert_eq!(orders, Symbol::from(String::from("orders")));
+        assert_ne!(orders, Symbol::intern("Orders"));
+        assert_eq!(orders.as_str(), "orders");
+        assert_eq!(orders, "orders");
+        assert_eq!(orders.to_uppercase(), "ORDERS");
+        assert_eq!(format!("{} {:?}", orders, orders), "orders \"orders\"");
+
+        let mut symbols = vec![Symbol::intern("zeta"), Symbol::intern("alpha"), orders];
+        symbols.sort();
+        assert_eq!(symbols, vec!["alpha", "orders", "zeta"]);
+    }
+
+    #[test]
+    fn test_dropped_symbols_are_freed() {
+        let interned = 20 * MIN_CLEANUP_LEN;
+        for i in 0..interned {
+            Symbol::intern(&format!("dropped_{}", i));
+        }
+        let kept = Symbol::intern("kept");
+
+        // Each shard cleans up as it passes its threshold, so only the names
+        // interned since are still around, plus whatever other tests hold
+        assert!(interner().len() < interned / 2, "{} names interned", interner().len());
+        assert_eq!(kept, Symbol::intern("kept"));
+        assert_eq!(Symbol::intern("dropped_0"), "dropped_0");
+    }
+
+    #[test]
+    fn test_parse_in_parallel() {
+        use crate::dialect::GenericDialect;
+        use crate::parser::Parser;
+
+        let sql = "SELECT orders.id, customers.name FROM orders JOIN customers ON orders.customer_id = customers.id";
+        let parse = || Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+
+        let statements: Vec<_> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..8)
+                .map(|_| scope.spawn(move || (0..100).map(|_| parse()).collect::<Vec<_>>()))
+                .collect();
+            threads.into_iter().flat_map(|thread| thread.join().unwrap()).collect()
+        });
+
+        for statement in &statements {
+            assert_eq!(statement, &parse());
+            assert_eq!(statement[0].to_string(), sql);
+        }
+    }
+}
@@ -12,8 +12,10 @@
 
 //! Recursive visitors for ast Nodes. See [`Visitor`] for more details.
 
-use crate::ast::{Expr, ObjectName, Statement};
+use crate::ast::{Expr, ObjectName, Statement, TableFactor};
 use core::ops::ControlFlow;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 
 /// A type that can be visited by a [`Visitor`]. See [`Visitor`] for
 /// recursively visiting parsed SQL statements.
@@ -21,7 +23,7 @@ use core::ops::ControlFlow;
 /// # Note
 ///
 /// This trait should be automatically derived for sqlparser AST nodes
-/// using the [Visit](sqlparser_derive::Visit) proc macro.
+/// using the [Visit](dbtranslate_derive::Visit) proc macro.
 ///
 /// ```text
 /// #[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
@@ -36,7 +38,7 @@ pub trait Visit {
 /// # Note
 ///
 /// This trait should be automatically derived for sqlparser AST nodes
-/// using the [VisitMut](sqlparser_derive::VisitMut) proc macro.
+/// using the [VisitMut](dbtranslate_derive::VisitMut) proc macro.
 ///
 /// ```text
 /// #[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
@@ -63,6 +65,25 @@ impl<T: Visit> Visit for Vec<T> {
     }
 }
 
+// Visits the values only, in no particular order
+impl<K, T: Visit> Visit for HashMap<K, T> {
+    fn visit<V: Visitor>(&self, visitor: &mut V) -> ControlFlow<V::Break> {
+        for v in self.values() {
+            v.visit(visitor)?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl<K, T: Visit> Visit for BTreeMap<K, T> {
+    fn visit<V: Visitor>(&self, visitor: &mut V) -> ControlFlow<V::Break> {
+        for v in self.values() {
+            v.visit(visitor)?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
 impl<T: Visit> Visit for Box<T> {
     fn visit<V: Visitor>(&self, visitor: &mut V) -> ControlFlow<V::Break> {
         T::visit(self, visitor)
@@ -87,6 +108,24 @@ impl<T: VisitMut> VisitMut for Vec<T> {
     }
 }
 
+impl<K, T: VisitMut> VisitMut for HashMap<K, T> {
+    fn visit<V: VisitorMut>(&mut self, visitor: &mut V) -> ControlFlow<V::Break> {
+        for v in self.values_mut() {
+            v.visit(visitor)?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl<K, T: VisitMut> VisitMut for BTreeMap<K, T> {
+    fn visit<V: VisitorMut>(&mut self, visitor: &mut V) -> ControlFlow<V::Break> {
+        for v in self.values_mut() {
+            v.visit(visitor)?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
 impl<T: VisitMut> VisitMut for Box<T> {
     fn visit<V: VisitorMut>(&mut self, visitor: &mut V) -> ControlFlow<V::Break> {
         T::visit(self, visitor)
@@ -108,7 +147,10 @@ macro_rules! visit_noop {
     };
 }
 
-visit_noop!(u8, u16, u32, u64, i8, i16, i32, i64, char, bool, String);
+visit_noop!(u8, u16, u32, u64, i8, i16, i32, i64, char, bool, String, crate::ast::Symbol);
+
+// Token text and spans say where things are in the SQL, not what's in it
+visit_noop!(Cow<'_, str>, crate::tokens::Span, crate::ast::AttachedSpan);
 
 #[cfg(feature = "bigdecimal")]
 visit_noop!(bigdecimal::BigDecimal);
@@ -123,14 +165,15 @@ visit_noop!(bigdecimal::BigDecimal);
 ///
 /// These methods provide a more concise way of visiting nodes of a certain type:
 /// * [visit_relations]
+/// * [visit_table_factors]
 /// * [visit_expressions]
 /// * [visit_statements]
 ///
 /// # Example
 /// ```
-/// # use sqlparser::parser::Parser;
-/// # use sqlparser::dialect::GenericDialect;
-/// # use sqlparser::ast::{Visit, Visitor, ObjectName, Expr};
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{Visit, Visitor, ObjectName, Expr};
 /// # use core::ops::ControlFlow;
 /// // A structure that records statements and relations
 /// #[derive(Default)]
@@ -189,6 +232,17 @@ pub trait Visitor {
         ControlFlow::Continue(())
     }
 
+    /// Invoked for anything read from in a `FROM` clause or join (tables,
+    /// `ref()`s, `source()`s, subqueries, ...) before visiting children
+    fn pre_visit_table_factor(&mut self, _table_factor: &TableFactor) -> ControlFlow<Self::Break> {
+        ControlFlow::Continue(())
+    }
+
+    /// Invoked for anything read from in a `FROM` clause or join after visiting children
+    fn post_visit_table_factor(&mut self, _table_factor: &TableFactor) -> ControlFlow<Self::Break> {
+        ControlFlow::Continue(())
+    }
+
     /// Invoked for any expressions that appear in the AST before visiting children
     fn pre_visit_expr(&mut self, _expr: &Expr) -> ControlFlow<Self::Break> {
         ControlFlow::Continue(())
@@ -220,14 +274,15 @@ pub trait Visitor {
 ///
 /// These methods provide a more concise way of visiting nodes of a certain type:
 /// * [visit_relations_mut]
+/// * [visit_table_factors_mut]
 /// * [visit_expressions_mut]
 /// * [visit_statements_mut]
 ///
 /// # Example
 /// ```
-/// # use sqlparser::parser::Parser;
-/// # use sqlparser::dialect::GenericDialect;
-/// # use sqlparser::ast::{VisitMut, VisitorMut, ObjectName, Expr, Ident};
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{VisitMut, VisitorMut, ObjectName, Expr, Ident};
 /// # use core::ops::ControlFlow;
 ///
 /// // A visitor that replaces "to_replace" with "replaced" in all expressions
@@ -239,7 +294,7 @@ pub trait Visitor {
 ///
 ///   fn post_visit_expr(&mut self, expr: &mut Expr) -> ControlFlow<Self::Break> {
 ///     if let Expr::Identifier(Ident{ value, ..}) = expr {
-///         *value = value.replace("to_replace", "replaced")
+///         *value = value.replace("to_replace", "replaced").into()
 ///     }
 ///     ControlFlow::Continue(())
 ///   }
@@ -267,6 +322,17 @@ pub trait VisitorMut {
         ControlFlow::Continue(())
     }
 
+    /// Invoked for anything read from in a `FROM` clause or join (tables,
+    /// `ref()`s, `source()`s, subqueries, ...) before visiting children
+    fn pre_visit_table_factor(&mut self, _table_factor: &mut TableFactor) -> ControlFlow<Self::Break> {
+        ControlFlow::Continue(())
+    }
+
+    /// Invoked for anything read from in a `FROM` clause or join after visiting children
+    fn post_visit_table_factor(&mut self, _table_factor: &mut TableFactor) -> ControlFlow<Self::Break> {
+        ControlFlow::Continue(())
+    }
+
     /// Invoked for any expressions that appear in the AST before visiting children
     fn pre_visit_expr(&mut self, _expr: &mut Expr) -> ControlFlow<Self::Break> {
         ControlFlow::Continue(())
@@ -310,9 +376,9 @@ impl<E, F: FnMut(&mut ObjectName) -> ControlFlow<E>> VisitorMut for RelationVisi
 ///
 /// # Example
 /// ```
-/// # use sqlparser::parser::Parser;
-/// # use sqlparser::dialect::GenericDialect;
-/// # use sqlparser::ast::{visit_relations};
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{visit_relations};
 /// # use core::ops::ControlFlow;
 /// let sql = "SELECT a FROM foo where x IN (SELECT y FROM bar)";
 /// let statements = Parser::parse_sql(&GenericDialect{}, sql)
@@ -350,9 +416,9 @@ where
 ///
 /// # Example
 /// ```
-/// # use sqlparser::parser::Parser;
-/// # use sqlparser::dialect::GenericDialect;
-/// # use sqlparser::ast::{ObjectName, visit_relations_mut};
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{ObjectName, visit_relations_mut};
 /// # use core::ops::ControlFlow;
 /// let sql = "SELECT a FROM foo";
 /// let mut statements = Parser::parse_sql(&GenericDialect{}, sql)
@@ -360,7 +426,7 @@ where
 ///
 /// // visit statements, renaming table foo to bar
 /// visit_relations_mut(&mut statements, |table| {
-///   table.0[0].value = table.0[0].value.replace("foo", "bar");
+///   table.0[0].value = table.0[0].value.replace("foo", "bar").into();
 ///   ControlFlow::<()>::Continue(())
 /// });
 ///
@@ -376,6 +442,71 @@ where
     ControlFlow::Continue(())
 }
 
+struct TableFactorVisitor<F>(F);
+
+impl<E, F: FnMut(&TableFactor) -> ControlFlow<E>> Visitor for TableFactorVisitor<F> {
+    type Break = E;
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<Self::Break> {
+        self.0(table_factor)
+    }
+}
+
+impl<E, F: FnMut(&mut TableFactor) -> ControlFlow<E>> VisitorMut for TableFactorVisitor<F> {
+    type Break = E;
+
+    fn post_visit_table_factor(&mut self, table_factor: &mut TableFactor) -> ControlFlow<Self::Break> {
+        self.0(table_factor)
+    }
+}
+
+/// Invokes the provided closure on all table factors present in `v`: every
+/// table, `ref()`, `source()`, subquery and so on that's read from, including
+/// the ones in CTEs, subqueries and parenthesized joins.
+///
+/// # Example
+/// ```
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{visit_table_factors, TableFactor};
+/// # use core::ops::ControlFlow;
+/// let sql = "SELECT a FROM {{ ref('orders') }} JOIN (SELECT b FROM customers) AS c ON true";
+/// let statements = Parser::parse_sql(&GenericDialect{}, sql).unwrap();
+///
+/// let mut visited = vec![];
+/// visit_table_factors(&statements, |table_factor| {
+///   match table_factor {
+///     TableFactor::DbtRef { model_name, .. } => visited.push(format!("REF: {}", model_name.value)),
+///     TableFactor::Table { name, .. } => visited.push(format!("TABLE: {}", name)),
+///     TableFactor::Derived { .. } => visited.push("SUBQUERY".to_string()),
+///     _ => {}
+///   }
+///   ControlFlow::<()>::Continue(())
+/// });
+///
+/// assert_eq!(visited, vec!["REF: orders", "SUBQUERY", "TABLE: customers"]);
+/// ```
+pub fn visit_table_factors<V, E, F>(v: &V, f: F) -> ControlFlow<E>
+where
+    V: Visit,
+    F: FnMut(&TableFactor) -> ControlFlow<E>,
+{
+    let mut visitor = TableFactorVisitor(f);
+    v.visit(&mut visitor)?;
+    ControlFlow::Continue(())
+}
+
+/// Invokes the provided closure with a mutable reference to all table
+/// factors present in `v`, after their children have been visited
+pub fn visit_table_factors_mut<V, E, F>(v: &mut V, f: F) -> ControlFlow<E>
+where
+    V: VisitMut,
+    F: FnMut(&mut TableFactor) -> ControlFlow<E>,
+{
+    v.visit(&mut TableFactorVisitor(f))?;
+    ControlFlow::Continue(())
+}
+
 struct ExprVisitor<F>(F);
 
 impl<E, F: FnMut(&Expr) -> ControlFlow<E>> Visitor for ExprVisitor<F> {
@@ -398,9 +529,9 @@ impl<E, F: FnMut(&mut Expr) -> ControlFlow<E>> VisitorMut for ExprVisitor<F> {
 ///
 /// # Example
 /// ```
-/// # use sqlparser::parser::Parser;
-/// # use sqlparser::dialect::GenericDialect;
-/// # use sqlparser::ast::{visit_expressions};
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{visit_expressions};
 /// # use core::ops::ControlFlow;
 /// let sql = "SELECT a FROM foo where x IN (SELECT y FROM bar)";
 /// let statements = Parser::parse_sql(&GenericDialect{}, sql)
@@ -442,9 +573,9 @@ where
 ///
 /// ## Remove all select limits in sub-queries
 /// ```
-/// # use sqlparser::parser::Parser;
-/// # use sqlparser::dialect::GenericDialect;
-/// # use sqlparser::ast::{Expr, visit_expressions_mut, visit_statements_mut};
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{Expr, visit_expressions_mut, visit_statements_mut};
 /// # use core::ops::ControlFlow;
 /// let sql = "SELECT (SELECT y FROM z LIMIT 9) FROM t LIMIT 3";
 /// let mut statements = Parser::parse_sql(&GenericDialect{}, sql).unwrap();
@@ -467,9 +598,9 @@ where
 /// [`std::mem`](std::mem) family of functions.
 ///
 /// ```
-/// # use sqlparser::parser::Parser;
-/// # use sqlparser::dialect::GenericDialect;
-/// # use sqlparser::ast::{Expr, Function, FunctionArg, FunctionArgExpr, Ident, ObjectName, Value, visit_expressions_mut, visit_statements_mut};
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{Expr, Function, FunctionArg, FunctionArgExpr, Ident, ObjectName, Value, visit_expressions_mut, visit_statements_mut};
 /// # use core::ops::ControlFlow;
 /// let sql = "SELECT x, y FROM t";
 /// let mut statements = Parser::parse_sql(&GenericDialect{}, sql).unwrap();
@@ -520,11 +651,11 @@ impl<E, F: FnMut(&mut Statement) -> ControlFlow<E>> VisitorMut for StatementVisi
 ///
 /// # Example
 /// ```
-/// # use sqlparser::parser::Parser;
-/// # use sqlparser::dialect::GenericDialect;
-/// # use sqlparser::ast::{visit_statements};
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{visit_statements};
 /// # use core::ops::ControlFlow;
-/// let sql = "SELECT a FROM foo where x IN (SELECT y FROM bar); CREATE TABLE baz(q int)";
+/// let sql = "SELECT a FROM foo where x IN (SELECT y FROM bar); SELECT q FROM baz";
 /// let statements = Parser::parse_sql(&GenericDialect{}, sql)
 ///    .unwrap();
 ///
@@ -537,7 +668,7 @@ impl<E, F: FnMut(&mut Statement) -> ControlFlow<E>> VisitorMut for StatementVisi
 ///
 /// let expected : Vec<_> = [
 ///   "STATEMENT: SELECT a FROM foo WHERE x IN (SELECT y FROM bar)",
-///   "STATEMENT: CREATE TABLE baz (q INT)"
+///   "STATEMENT: SELECT q FROM baz"
 /// ]
 ///   .into_iter().map(|s| s.to_string()).collect();
 ///
@@ -557,9 +688,9 @@ where
 ///
 /// # Example
 /// ```
-/// # use sqlparser::parser::Parser;
-/// # use sqlparser::dialect::GenericDialect;
-/// # use sqlparser::ast::{Statement, visit_statements_mut};
+/// # use dbtranslate::parser::Parser;
+/// # use dbtranslate::dialect::GenericDialect;
+/// # use dbtranslate::ast::{Statement, visit_statements_mut};
 /// # use core::ops::ControlFlow;
 /// let sql = "SELECT x FROM foo LIMIT 9+$limit; SELECT * FROM t LIMIT f()";
 /// let mut statements = Parser::parse_sql(&GenericDialect{}, sql).unwrap();
@@ -640,7 +771,7 @@ mod tests {
             .unwrap();
 
         let mut visitor = TestVisitor::default();
-        s.visit(&mut visitor);
+        let _ = s.visit(&mut visitor);
         visitor.visited
     }
 
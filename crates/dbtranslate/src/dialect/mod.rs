@@ -12,10 +12,12 @@
 
 mod ansi;
 mod bigquery;
+mod duckdb;
 mod generic;
 mod postgresql;
 mod redshift;
 mod snowflake;
+mod trino;
 
 use crate::ast::{Expr, Statement};
 use core::any::{Any, TypeId};
@@ -25,10 +27,12 @@ use core::str::Chars;
 
 pub use self::ansi::AnsiDialect;
 pub use self::bigquery::BigQueryDialect;
+pub use self::duckdb::DuckDbDialect;
 pub use self::generic::GenericDialect;
 pub use self::postgresql::PostgreSqlDialect;
 pub use self::redshift::RedshiftSqlDialect;
 pub use self::snowflake::SnowflakeDialect;
+pub use self::trino::TrinoDialect;
 pub use crate::keywords;
 use crate::parser::{Parser, ParserError};
 
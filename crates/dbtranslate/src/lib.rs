@@ -56,6 +56,7 @@ pub mod ast;
 pub mod dialect;
 pub mod keywords;
 pub mod parser;
+pub mod suggestions;
 pub mod tokenizer;
 pub mod tokens;
 
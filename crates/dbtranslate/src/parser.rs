@@ -21,6 +21,8 @@ use alloc::{
     vec::Vec,
 };
 use core::fmt;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
 use log::debug;
 
@@ -30,9 +32,10 @@ use IsOptional::*;
 use crate::ast::*;
 use crate::dialect::*;
 use crate::keywords::{self, Keyword};
+use crate::suggestions::did_you_mean;
 use crate::tokenizer::*;
 use crate::tokens::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use crate::parser::query::{DbtConfigValue,DbtConfig};
 
 
@@ -41,6 +44,10 @@ pub enum ParserError {
     TokenizerError(String),
     ParserError(String),
     RecursionLimitExceeded,
+    /// The input exceeded one of the limits set with
+    /// [`Parser::with_token_limit`], [`Parser::with_statement_length_limit`]
+    /// or [`Parser::with_time_limit`]
+    ResourceLimitExceeded(String),
 }
 
 // Use `Parser::expected` instead, if possible
@@ -174,6 +181,9 @@ impl From<WildcardExpr> for FunctionArgExpr {
 
 impl From<TokenizerError> for ParserError {
     fn from(e: TokenizerError) -> Self {
+        if e.limit_exceeded {
+            return ParserError::ResourceLimitExceeded(e.message);
+        }
         ParserError::TokenizerError(e.to_string())
     }
 }
@@ -187,6 +197,7 @@ impl fmt::Display for ParserError {
                 ParserError::TokenizerError(s) => s,
                 ParserError::ParserError(s) => s,
                 ParserError::RecursionLimitExceeded => "recursion limit exceeded",
+                ParserError::ResourceLimitExceeded(s) => s,
             }
         )
     }
@@ -195,6 +206,24 @@ impl fmt::Display for ParserError {
 #[cfg(feature = "std")]
 impl std::error::Error for ParserError {}
 
+/// A [ParserError] with the [Location] the parser had reached when it
+/// happened, as returned by [`Parser::parse_statements_recovering`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserErrorWithLocation {
+    pub error: ParserError,
+    pub location: Location,
+}
+
+impl fmt::Display for ParserErrorWithLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.error, self.location.line, self.location.column
+        )
+    }
+}
+
 // By default, allow expressions up to this deep before erroring
 const DEFAULT_REMAINING_DEPTH: usize = 50;
 
@@ -204,7 +233,7 @@ pub struct ParserOptions {
 }
 
 pub struct Parser<'a> {
-    tokens: Vec<TokenWithLocation>,
+    tokens: Vec<TokenWithLocation<'a>>,
     /// The index of the first unprocessed token in `self.tokens`
     index: usize,
     /// The current dialect to use
@@ -214,6 +243,16 @@ pub struct Parser<'a> {
     options: ParserOptions,
     /// ensure the stack does not overflow by limiting recursion depth
     recursion_counter: RecursionCounter,
+    /// The maximum number of tokens the input may tokenize to
+    token_limit: Option<usize>,
+    /// The maximum length of each statement in the SQL text, in bytes
+    statement_length_limit: Option<usize>,
+    /// The maximum time [`Parser::parse_statements`] may take
+    #[cfg(feature = "std")]
+    time_limit: Option<Duration>,
+    /// When the time limit runs out, set once parsing starts
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>,
 }
 
 impl<'a> Parser<'a> {
@@ -239,6 +278,12 @@ impl<'a> Parser<'a> {
             dialect,
             recursion_counter: RecursionCounter::new(DEFAULT_REMAINING_DEPTH),
             options: ParserOptions::default(),
+            token_limit: None,
+            statement_length_limit: None,
+            #[cfg(feature = "std")]
+            time_limit: None,
+            #[cfg(feature = "std")]
+            deadline: None,
         }
     }
 
@@ -267,6 +312,60 @@ impl<'a> Parser<'a> {
         self
     }
 
+    /// Specify the maximum number of tokens the SQL may tokenize to.
+    ///
+    /// [`Parser::try_with_sql`] returns
+    /// [`ParserError::ResourceLimitExceeded`] if the input has more tokens
+    /// than this, whitespace included.
+    ///
+    /// Example:
+    /// ```
+    /// # use dbtranslate::{parser::{Parser, ParserError}, dialect::GenericDialect};
+    /// let dialect = GenericDialect{};
+    /// let result = Parser::new(&dialect)
+    ///   .with_token_limit(3)
+    ///   .try_with_sql("SELECT * FROM foo");
+    /// assert!(matches!(result, Err(ParserError::ResourceLimitExceeded(_))));
+    /// ```
+    pub fn with_token_limit(mut self, token_limit: usize) -> Self {
+        self.token_limit = Some(token_limit);
+        self
+    }
+
+    /// Specify the maximum length, in bytes, of each statement in the SQL
+    /// passed to [`Parser::try_with_sql`].
+    ///
+    /// Statements are split on the `;` tokens between them, so a file of
+    /// many short statements is fine while one oversized statement is
+    /// rejected with [`ParserError::ResourceLimitExceeded`]. Inputs that are
+    /// under the limit as a whole are let through without measuring them.
+    ///
+    /// Example:
+    /// ```
+    /// # use dbtranslate::{parser::{Parser, ParserError}, dialect::GenericDialect};
+    /// let dialect = GenericDialect{};
+    /// let result = Parser::new(&dialect)
+    ///   .with_statement_length_limit(20)
+    ///   .try_with_sql("SELECT * FROM foo; SELECT * FROM bar");
+    /// assert!(result.is_ok());
+    /// ```
+    pub fn with_statement_length_limit(mut self, statement_length_limit: usize) -> Self {
+        self.statement_length_limit = Some(statement_length_limit);
+        self
+    }
+
+    /// Specify the maximum time [`Parser::parse_statements`] may take.
+    ///
+    /// The clock starts when parsing starts and is checked as each statement,
+    /// query and expression is parsed. Once it runs out parsing stops with
+    /// [`ParserError::ResourceLimitExceeded`]. Tokenizing isn't timed, it's
+    /// bounded by [`Parser::with_token_limit`] instead.
+    #[cfg(feature = "std")]
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
     /// Specify additional parser options
     ///
     ///
@@ -293,14 +392,14 @@ impl<'a> Parser<'a> {
     }
 
     /// Reset this parser to parse the specified token stream
-    pub fn with_tokens_with_locations(mut self, tokens: Vec<TokenWithLocation>) -> Self {
+    pub fn with_tokens_with_locations(mut self, tokens: Vec<TokenWithLocation<'a>>) -> Self {
         self.tokens = tokens;
         self.index = 0;
         self
     }
 
     /// Reset this parser state to parse the specified tokens
-    pub fn with_tokens(self, tokens: Vec<Token>) -> Self {
+    pub fn with_tokens(self, tokens: Vec<Token<'a>>) -> Self {
         // Put in dummy locations
         let tokens_with_locations: Vec<TokenWithLocation> = tokens
             .into_iter()
@@ -312,17 +411,49 @@ impl<'a> Parser<'a> {
         self.with_tokens_with_locations(tokens_with_locations)
     }
 
+    /// A [`Tokenizer`] for `sql` in this parser's dialect that stops at the
+    /// token and statement length limits, for callers that tokenize the SQL
+    /// themselves and pass the tokens to [`Parser::try_with_tokens`]
+    pub fn tokenizer(&self, sql: &'a str) -> Tokenizer<'a> {
+        let mut tokenizer = Tokenizer::new(self.dialect, sql);
+        if let Some(limit) = self.token_limit {
+            tokenizer = tokenizer.with_token_limit(limit);
+        }
+        if let Some(limit) = self.statement_length_limit {
+            tokenizer = tokenizer.with_statement_length_limit(limit);
+        }
+        tokenizer
+    }
+
     /// Tokenize the sql string and sets this [`Parser`]'s state to
     /// parse the resulting tokens
     ///
-    /// Returns an error if there was an error tokenizing the SQL string.
+    /// Returns an error if there was an error tokenizing the SQL string,
+    /// or if it's over the token or statement length limits. Tokenizing
+    /// stops as soon as it goes over one.
     ///
     /// See example on [`Parser::new()`] for an example
-    pub fn try_with_sql(self, sql: &str) -> Result<Self, ParserError> {
+    pub fn try_with_sql(self, sql: &'a str) -> Result<Self, ParserError> {
         debug!("Parsing sql '{}'...", sql);
-        let mut tokenizer = Tokenizer::new(self.dialect, sql);
-        let tokens = tokenizer.tokenize()?;
-        Ok(self.with_tokens(tokens))
+        let tokens = self.tokenizer(sql).tokenize_with_location()?;
+        Ok(self.with_tokens_with_locations(tokens))
+    }
+
+    /// Sets this [`Parser`]'s state to parse `tokens`, which the caller
+    /// has already tokenized with [`Parser::tokenizer`] so they're within
+    /// the same limits as [`Parser::try_with_sql`]'s.
+    ///
+    /// Together with [`Parser::into_tokens`] this lets a caller that needs
+    /// the tokens as well as the AST tokenize the SQL only once.
+    pub fn try_with_tokens(self, tokens: Vec<TokenWithLocation<'a>>) -> Result<Self, ParserError> {
+        if let Some(limit) = self.token_limit {
+            if tokens.len() > limit {
+                return Err(ParserError::ResourceLimitExceeded(format!(
+                    "token count exceeds the limit of {limit}"
+                )));
+            }
+        }
+        Ok(self.with_tokens_with_locations(tokens))
     }
 
     /// Parse potentially multiple statements
@@ -341,6 +472,10 @@ impl<'a> Parser<'a> {
     /// # }
     /// ```
     pub fn parse_statements(&mut self) -> Result<Vec<Statement>, ParserError> {
+        #[cfg(feature = "std")]
+        {
+            self.deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        }
         let mut stmts = Vec::new();
         let mut expecting_statement_delimiter = false;
         loop {
@@ -363,6 +498,76 @@ impl<'a> Parser<'a> {
         Ok(stmts)
     }
 
+    /// Parse potentially multiple statements, carrying on past the ones
+    /// that fail to parse. After an error the parser skips to the next `;`
+    /// and starts again from there, so the statements around a broken one
+    /// still make it into the AST.
+    ///
+    /// Running into one of the limits set with [`Parser::with_token_limit`]
+    /// and friends stops parsing altogether, as carrying on would defeat
+    /// the point of the limit.
+    pub fn parse_statements_recovering(
+        &mut self,
+    ) -> (Vec<Statement>, Vec<ParserErrorWithLocation>) {
+        #[cfg(feature = "std")]
+        {
+            self.deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        }
+        let mut stmts = Vec::new();
+        let mut errors = Vec::new();
+        let mut expecting_statement_delimiter = false;
+        loop {
+            while self.consume_token(&Token::SemiColon) {
+                expecting_statement_delimiter = false;
+            }
+
+            if self.peek_token() == Token::EOF {
+                break;
+            }
+            let start = self.index;
+            let statement = match expecting_statement_delimiter {
+                true => self.expected("end of statement", self.peek_token()),
+                false => self.parse_statement(),
+            };
+
+            match statement {
+                Ok(statement) => {
+                    stmts.push(statement);
+                    expecting_statement_delimiter = true;
+                }
+                Err(error) => {
+                    let fatal = matches!(error, ParserError::ResourceLimitExceeded(_));
+                    errors.push(ParserErrorWithLocation {
+                        error,
+                        location: self.current_location(),
+                    });
+                    if fatal {
+                        break;
+                    }
+                    // The error may come after the statement's `;`, as in
+                    // `SELECT (;`, so the skip starts over from its beginning
+                    self.index = start;
+                    self.skip_to_statement_end();
+                    expecting_statement_delimiter = false;
+                }
+            }
+        }
+        (stmts, errors)
+    }
+
+    /// Skip a statement that failed to parse, stopping before the `;` that
+    /// ends it
+    fn skip_to_statement_end(&mut self) {
+        loop {
+            match self.peek_token().token {
+                Token::SemiColon | Token::EOF => break,
+                _ => {
+                    self.next_token();
+                }
+            }
+        }
+    }
+
     /// Convenience method to parse a string with one or more SQL
     /// statements into produce an Abstract Syntax Tree (AST).
     ///
@@ -386,6 +591,7 @@ impl<'a> Parser<'a> {
     /// stopping before the statement separator, if any.
     pub fn parse_statement(&mut self) -> Result<Statement, ParserError> {
         let _guard = self.recursion_counter.try_decrease()?;
+        self.check_time_limit()?;
 
         // allow the dialect to override statement parsing
         if let Some(statement) = self.dialect.parse_statement(self) {
@@ -396,27 +602,10 @@ impl<'a> Parser<'a> {
         // This is where we need to add dbt for config?.
         let next_token = self.next_token();
         match &next_token.token {
-            Token::DoubleLBrace => {
-                //TODO: Some parse_config function? After R brace it should run parse query
-                // self.prev_token();
-                let config = self.parse_config()?;
-                Ok(Statement::Query(Box::new(self.parse_query(Some(config))?)))
+            Token::DoubleLBrace | Token::LJinjaIterator => {
+                self.prev_token();
+                Ok(Statement::Query(Box::new(self.parse_dbt_query()?)))
             }
-            // Token::LJinjaIterator => {
-            //     let next_token: TokenWithLocation = self.next_token();
-            //     match next_token.token {
-            //         Token::Word(w) if w.value.eq_ignore_ascii_case("set") => {
-            //             self.next_token(); // Consume the "set" word token
-            //             let jinja_variables = self.parse_jinja_variables()?;
-            //             // Do something with the jinja_variables or add it to a relevant struct
-            //             Ok(Statement::Query(Box::new(self.parse_query(None)?)))
-            //         }
-            //         Token::Word(w) => {
-            //             parser_err!(format!("Expected 'set', found '{}'", w.value))
-            //         }
-            //         _ => parser_err!("Expected 'set'"),
-            //     }
-            // }
             Token::Word(w) => match w.keyword {
                 Keyword::KILL => {
                     parser_err!(format!("KILL is not supported by dbtranslate"))
@@ -526,7 +715,7 @@ impl<'a> Parser<'a> {
                 Keyword::MERGE => {
                     parser_err!(format!("MERGE is not supported by dbtranslate"))
                 },
-                _ => self.expected("an SQL statement", next_token),
+                _ => self.expected_one_of("an SQL statement", next_token, &["SELECT", "WITH", "VALUES"]),
             },
             Token::LParen => {
                 self.prev_token();
@@ -541,14 +730,16 @@ impl<'a> Parser<'a> {
         let index = self.index;
 
         let next_token = self.next_token();
+        let span = next_token.span();
         match next_token.token {
             Token::Word(w) if self.peek_token().token == Token::Period => {
-                let mut id_parts: Vec<Ident> = vec![w.to_ident()];
+                let mut id_parts: Vec<Ident> = vec![w.to_ident(span)];
 
                 while self.consume_token(&Token::Period) {
                     let next_token = self.next_token();
+                    let span = next_token.span();
                     match next_token.token {
-                        Token::Word(w) => id_parts.push(w.to_ident()),
+                        Token::Word(w) => id_parts.push(w.to_ident(span)),
                         Token::Mul => {
                             return Ok(WildcardExpr::QualifiedWildcard(ObjectName(id_parts)));
                         }
@@ -571,6 +762,7 @@ impl<'a> Parser<'a> {
     /// Parse a new expression
     pub fn parse_expr(&mut self) -> Result<Expr, ParserError> {
         let _guard = self.recursion_counter.try_decrease()?;
+        self.check_time_limit()?;
         self.parse_subexpr(0)
     }
 
@@ -623,10 +815,29 @@ impl<'a> Parser<'a> {
     }
 
     /// Parse a ref function
-    fn parse_ref(&mut self) -> Result<Ident, ParserError> {
-        let model_name = self.parse_identifier()?;
+    /// Parse the `[package, ]model[, v=version])` arguments of `ref()`. dbt
+    /// takes the version as `v` or `version`.
+    fn parse_ref(&mut self) -> Result<(Option<Ident>, Ident, Option<Value>), ParserError> {
+        let mut names = vec![self.parse_identifier()?];
+        let mut version = None;
+        while self.consume_token(&Token::Comma) {
+            let is_version = matches!(
+                (&self.peek_token().token, &self.peek_nth_token(1).token),
+                (Token::Word(w), Token::Eq) if w.quote_style.is_none() && matches!(w.value.to_lowercase().as_str(), "v" | "version")
+            );
+            if is_version && version.is_none() {
+                self.next_token();
+                self.next_token();
+                version = Some(self.parse_value()?);
+            } else if !is_version && version.is_none() && names.len() < 2 {
+                names.push(self.parse_identifier()?);
+            } else {
+                return self.expected("')' after the arguments of ref()", self.peek_token());
+            }
+        }
         self.expect_token(&Token::RParen)?;
-        Ok(model_name)
+        let model_name = names.pop().expect("ref() has at least one name");
+        Ok((names.pop(), model_name, version))
     }
 
     // Add a new method parse_source
@@ -639,70 +850,375 @@ impl<'a> Parser<'a> {
         Ok((source_name, table_name))
     }
 
-    // // Parse a jinja set expression
-    // fn parse_jinja_variables(&mut self) -> Result<Vec<JinjaVariable>, ParserError> {
-    //     let mut jinja_variables = Vec::new();
-    
-    //     loop {
-    //         // Parse variable name
-    //         let key = self.parse_identifier()?.value;
-            
-    //         // Consume the equal sign
-    //         // TODO: Change this to any operator
-    //         self.expect_token(&Token::Eq)?;
-    
-    //         // Parse Jinja value
-    //         let value = self.parse_jinja_value()?;
-    
-    //         // Create JinjaVariable and push it to the list
-    //         jinja_variables.push(JinjaVariable { key, value });
-    
-    //         // Check if there's another Jinja variable to parse
-    //         // TODO: change this to and / or
-    //         if !self.consume_token(&Token::Comma) {
-    //             break;
-    //         }
-    //     }
-    
-    //     // Consume the closing Jinja delimiter
-    //     self.expect_token(&Token::RJinjaIterator)?;
-    
-    //     Ok(jinja_variables)
-    // }
-
-    // fn parse_jinja_value(&mut self) -> Result<JinjaValue, ParserError> {
-    //     match self.next_token().token {
-    //         Token::DoubleQuotedString(_) => {
-    //             self.prev_token();
-    //             let value = self.parse_expr()?;
-    //             Ok(JinjaValue::Str(value))
-    //         }
-    //         Token::LBracket => {
-    //             let mut list = Vec::new();
-    //             loop {
-    //                 let current_token = self.peek_token();
-    //                 match current_token.token {
-    //                     Token::RBracket => {
-    //                         self.next_token(); // Consume the RBracket
-    //                         break;
-    //                     }
-    //                     None => return self.expected("a Jinja value or ']', found EOF", None),
-    //                     _ => {
-    //                         let value = self.parse_jinja_value()?;
-    //                         list.push(value);
-    //                         if !self.consume_token(&Token::Comma) {
-    //                             self.expect_token(&Token::RBracket)?;
-    //                             break;
-    //                         }
-    //                     }
-    //                 }
-    //             }
-    //             Ok(JinjaValue::List(list))
-    //         }
-    //         _ => self.expected("a Jinja value", self.peek_token()),
-    //     }
-    // }
-    
+    /// Parse a dbt call inside `{{ }}` that is used as an expression, e.g.
+    /// `{{ var('start_date') }}`, `{{ env_var('DBT_SCHEMA') }}` or any other
+    /// macro. Assumes the `{{` has been consumed.
+    fn parse_dbt_expr(&mut self) -> Result<Expr, ParserError> {
+        let expr = self.parse_dbt_call()?;
+        self.expect_token(&Token::DoubleRBrace)?;
+        Ok(expr)
+    }
+
+    /// Parse a dbt call without the `{{ }}` around it, which `config()`
+    /// values are written as, e.g. `enabled=var('run_heavy_models')`
+    fn parse_dbt_call(&mut self) -> Result<Expr, ParserError> {
+        let next_token = self.next_token();
+        let expr = match &next_token.token {
+            Token::Word(w) if w.value.to_lowercase() == "var" => {
+                self.expect_token(&Token::LParen)?;
+                let (name, default) = self.parse_dbt_var_args()?;
+                Expr::DbtVar { name, default }
+            }
+            Token::Word(w) if w.value.to_lowercase() == "env_var" => {
+                self.expect_token(&Token::LParen)?;
+                let (name, default) = self.parse_dbt_var_args()?;
+                Expr::DbtEnvVar { name, default }
+            }
+            Token::Word(w) => Expr::DbtMacroCall(self.parse_dbt_macro_call(w.to_ident(next_token.span()))?),
+            _ => return self.expected("a macro call after '{{'", next_token),
+        };
+        Ok(expr)
+    }
+
+    /// Parse the `'name'[, default])` arguments of `var()` and `env_var()`
+    fn parse_dbt_var_args(&mut self) -> Result<(Ident, Option<Box<Expr>>), ParserError> {
+        let name = self.parse_identifier()?;
+        let default = if self.consume_token(&Token::Comma) {
+            Some(Box::new(self.parse_expr()?))
+        } else {
+            None
+        };
+        self.expect_token(&Token::RParen)?;
+        Ok((name, default))
+    }
+
+    /// Parse `[namespace.]name(args)` of a macro call, where `first` is the
+    /// word after `{{`, which has been consumed. The arguments are kept as
+    /// tokens.
+    fn parse_dbt_macro_call(&mut self, first: Ident) -> Result<DbtMacroCall, ParserError> {
+        let (namespace, name) = if self.consume_token(&Token::Period) {
+            (Some(first), self.parse_identifier()?)
+        } else {
+            (None, first)
+        };
+        self.expect_token(&Token::LParen)?;
+
+        let mut args = vec![];
+        let mut depth = 0;
+        loop {
+            let token = match self.next_token_no_skip().map(|token| token.token.clone().into_owned()) {
+                None | Some(Token::EOF) => return self.expected(")", self.peek_token()),
+                Some(Token::RParen) if depth == 0 => break,
+                Some(token) => token,
+            };
+            match token {
+                Token::LParen => depth += 1,
+                Token::RParen => depth -= 1,
+                _ => {}
+            }
+            args.push(token);
+        }
+
+        trim_whitespace(&mut args);
+
+        Ok(DbtMacroCall { namespace, name, args })
+    }
+
+    /// Parse a query that starts with Jinja. `{{ config() }}`, `{% set %}`
+    /// and `{% if %}`/`{% for %}` blocks at the top of a model can come in
+    /// any order and belong to the query that follows them. A block with
+    /// nothing after it wraps the whole query and becomes its body.
+    pub fn parse_dbt_query(&mut self) -> Result<Query, ParserError> {
+        let mut config: Option<DbtConfig> = None;
+        let mut jinja_variables = vec![];
+        let mut jinja_blocks = vec![];
+        loop {
+            if self.peek_jinja_tag("set") {
+                jinja_variables.extend(self.parse_jinja_variables()?);
+            } else if self.consume_token(&Token::DoubleLBrace) {
+                let values = self.parse_config()?.values;
+                config.get_or_insert_with(|| DbtConfig { values: HashMap::new() }).values.extend(values);
+            } else if self.peek_jinja_block() {
+                let block = self.parse_jinja_block()?;
+                if matches!(self.peek_token().token, Token::EOF | Token::SemiColon) {
+                    return Ok(Query {
+                        config,
+                        with: None,
+                        body: Box::new(SetExpr::JinjaBlock(block)),
+                        order_by: vec![],
+                        limit: None,
+                        offset: None,
+                        jinja_variables,
+                        jinja_blocks,
+                    });
+                }
+                jinja_blocks.push(block);
+            } else {
+                break;
+            }
+        }
+
+        let mut query = self.parse_query(config)?;
+        query.jinja_variables = jinja_variables;
+        query.jinja_blocks = jinja_blocks;
+        Ok(query)
+    }
+
+    /// Parse one or more `{% set key = value %}` and
+    /// `{% set key %} ... {% endset %}` blocks, stopping before the first tag
+    /// that isn't `set`
+    pub fn parse_jinja_variables(&mut self) -> Result<Vec<JinjaVariable>, ParserError> {
+        let mut jinja_variables = Vec::new();
+
+        while self.peek_jinja_tag("set") {
+            self.next_token();
+            self.next_token();
+
+            let key = self.parse_identifier()?.value.to_string();
+            let value = if self.consume_token(&Token::RJinjaIterator) {
+                let (body, _) = self.parse_jinja_body(&["endset"])?;
+                self.expect_token(&Token::RJinjaIterator)?;
+                JinjaValue::Block(body)
+            } else {
+                self.expect_token(&Token::Eq)?;
+                self.parse_jinja_set_value()?
+            };
+
+            jinja_variables.push(JinjaVariable { key, value });
+        }
+
+        Ok(jinja_variables)
+    }
+
+    /// The value after `=` in a `{% set %}` block, up to and including the
+    /// `%}`. Literals are parsed, any other expression is kept as tokens.
+    fn parse_jinja_set_value(&mut self) -> Result<JinjaValue, ParserError> {
+        let literal = self.maybe_parse(|parser| {
+            let value = parser.parse_jinja_value()?;
+            parser.expect_token(&Token::RJinjaIterator)?;
+            Ok(value)
+        });
+        if let Some(value) = literal {
+            return Ok(value);
+        }
+
+        if self.peek_token().token == Token::RJinjaIterator {
+            return self.expected("a value", self.peek_token());
+        }
+        Ok(JinjaValue::Expr(self.parse_jinja_tag_contents()?))
+    }
+
+    /// Parse the value of a `{% set %}` block: a string, a number or a
+    /// list of those
+    pub fn parse_jinja_value(&mut self) -> Result<JinjaValue, ParserError> {
+        let next_token = self.next_token();
+        match next_token.token {
+            Token::SingleQuotedString(s) | Token::DoubleQuotedString(s) => Ok(JinjaValue::Str(s.into_owned())),
+            // Jinja strings can use double quotes, which most dialects
+            // tokenize as a quoted identifier
+            Token::Word(w) if w.quote_style == Some('"') => Ok(JinjaValue::Str(w.value.into_owned())),
+            Token::Number(n, _) => Ok(JinjaValue::Number(n.into_owned())),
+            Token::LBracket => {
+                let mut list = Vec::new();
+                while !self.consume_token(&Token::RBracket) {
+                    list.push(self.parse_jinja_value()?);
+                    if !self.consume_token(&Token::Comma) {
+                        self.expect_token(&Token::RBracket)?;
+                        break;
+                    }
+                }
+                Ok(JinjaValue::List(list))
+            }
+            _ => self.expected("a string, number or list", next_token),
+        }
+    }
+
+    /// Whether the next tokens open a Jinja block with the given tag, e.g.
+    /// `{% if`
+    pub fn peek_jinja_tag(&self, tag: &str) -> bool {
+        self.peek_nth_token(0).token == Token::LJinjaIterator
+            && matches!(self.peek_nth_token(1).token, Token::Word(w) if w.value == tag)
+    }
+
+    /// Whether the next tokens open a `{% if %}` or `{% for %}` block
+    pub fn peek_jinja_block(&self) -> bool {
+        self.peek_jinja_tag("if") || self.peek_jinja_tag("for")
+    }
+
+    /// Parse a `{% if %}` or `{% for %}` block
+    pub fn parse_jinja_block(&mut self) -> Result<JinjaBlock, ParserError> {
+        if self.peek_jinja_tag("for") {
+            Ok(JinjaBlock::For(self.parse_jinja_for()?))
+        } else {
+            Ok(JinjaBlock::If(self.parse_jinja_conditional()?))
+        }
+    }
+
+    /// Parse `{% if ... %} ... {% elif ... %} ... {% else %} ... {% endif %}`,
+    /// keeping the condition and body of every branch as tokens
+    pub fn parse_jinja_conditional(&mut self) -> Result<JinjaConditional, ParserError> {
+        if !self.peek_jinja_tag("if") {
+            return self.expected("{% if", self.peek_token());
+        }
+        self.next_token();
+        self.next_token();
+
+        let mut branches = vec![];
+        let mut condition = Some(self.parse_jinja_tag_contents()?);
+        loop {
+            let (body, tag) = self.parse_jinja_body(&["elif", "else", "endif"])?;
+            branches.push(JinjaBranch { condition, body });
+            match tag.as_str() {
+                "elif" => condition = Some(self.parse_jinja_tag_contents()?),
+                "else" => {
+                    self.expect_token(&Token::RJinjaIterator)?;
+                    condition = None;
+                }
+                _ => {
+                    self.expect_token(&Token::RJinjaIterator)?;
+                    break;
+                }
+            }
+        }
+
+        Ok(JinjaConditional { branches })
+    }
+
+    /// Parse `{% for x in iterable %} ... {% endfor %}`, keeping the
+    /// iterable and body as tokens
+    pub fn parse_jinja_for(&mut self) -> Result<JinjaFor, ParserError> {
+        if !self.peek_jinja_tag("for") {
+            return self.expected("{% for", self.peek_token());
+        }
+        self.next_token();
+        self.next_token();
+
+        let variables = self.parse_comma_separated(Parser::parse_identifier)?;
+        self.expect_keyword(Keyword::IN)?;
+        let iterable = self.parse_jinja_tag_contents()?;
+        let (body, _) = self.parse_jinja_body(&["endfor"])?;
+        self.expect_token(&Token::RJinjaIterator)?;
+
+        Ok(JinjaFor { variables, iterable, body })
+    }
+
+    /// Parse every `{% macro %}` block in a file from a dbt project's macro
+    /// paths. Anything outside the blocks is skipped, as dbt does.
+    pub fn parse_jinja_macros(&mut self) -> Result<Vec<JinjaMacro>, ParserError> {
+        let mut macros = vec![];
+        loop {
+            if self.peek_jinja_tag("macro") {
+                macros.push(self.parse_jinja_macro()?);
+            } else if self.next_token().token == Token::EOF {
+                break;
+            }
+        }
+        Ok(macros)
+    }
+
+    /// Parse `{% macro name(args) %} ... {% endmacro %}`, keeping the body as
+    /// tokens
+    pub fn parse_jinja_macro(&mut self) -> Result<JinjaMacro, ParserError> {
+        if !self.peek_jinja_tag("macro") {
+            return self.expected("{% macro", self.peek_token());
+        }
+        self.next_token();
+        self.next_token();
+
+        let name = self.parse_identifier()?;
+        self.expect_token(&Token::LParen)?;
+        let mut args = vec![];
+        while !self.consume_token(&Token::RParen) {
+            let name = self.parse_identifier()?;
+            let default = match self.consume_token(&Token::Eq) {
+                true => Some(self.parse_jinja_macro_default()?),
+                false => None,
+            };
+            args.push(JinjaMacroArg { name, default });
+            if !self.consume_token(&Token::Comma) {
+                self.expect_token(&Token::RParen)?;
+                break;
+            }
+        }
+        self.expect_token(&Token::RJinjaIterator)?;
+        let (body, _) = self.parse_jinja_body(&["endmacro"])?;
+        self.expect_token(&Token::RJinjaIterator)?;
+
+        Ok(JinjaMacro { name, args, body })
+    }
+
+    /// The tokens of a macro argument's default, up to the `,` or `)` after
+    /// it, which isn't consumed
+    fn parse_jinja_macro_default(&mut self) -> Result<Vec<Token<'static>>, ParserError> {
+        let mut tokens = vec![];
+        let mut depth = 0;
+        loop {
+            let token = match self.tokens.get(self.index) {
+                Some(token) => token.token.clone().into_owned(),
+                None => return self.expected("')'", self.peek_token()),
+            };
+            match token {
+                Token::Comma | Token::RParen if depth == 0 => break,
+                Token::EOF => return self.expected("')'", self.peek_token()),
+                Token::LParen | Token::LBracket | Token::LBrace => depth += 1,
+                Token::RParen | Token::RBracket | Token::RBrace => depth -= 1,
+                _ => {}
+            }
+            self.index += 1;
+            tokens.push(token);
+        }
+
+        trim_whitespace(&mut tokens);
+        Ok(tokens)
+    }
+
+    /// The tokens up to and including the `%}` that closes a Jinja tag.
+    /// Only the tokens before `%}` are returned, without surrounding
+    /// whitespace.
+    fn parse_jinja_tag_contents(&mut self) -> Result<Vec<Token<'static>>, ParserError> {
+        let mut tokens = vec![];
+        loop {
+            match self.next_token_no_skip().map(|token| token.token.clone().into_owned()) {
+                Some(Token::RJinjaIterator) => break,
+                None | Some(Token::EOF) => return self.expected("%}", self.peek_token()),
+                Some(token) => tokens.push(token),
+            }
+        }
+
+        trim_whitespace(&mut tokens);
+        Ok(tokens)
+    }
+
+    /// The tokens inside a block, up to the first of the `end_tags` (e.g.
+    /// `{% endif`) that isn't part of a nested block. Returns the body and
+    /// the tag that ended it, which has been consumed.
+    fn parse_jinja_body(&mut self, end_tags: &[&str]) -> Result<(Vec<Token<'static>>, String), ParserError> {
+        let mut body = vec![];
+        let mut depth = 0;
+        loop {
+            let token = match self.next_token_no_skip().map(|token| token.token.clone().into_owned()) {
+                None | Some(Token::EOF) => {
+                    return self.expected(&format!("{{% {} %}}", end_tags[end_tags.len() - 1]), self.peek_token())
+                }
+                Some(token) => token,
+            };
+
+            if token == Token::LJinjaIterator {
+                if let Token::Word(w) = self.peek_token().token {
+                    match w.value.as_ref() {
+                        tag if depth == 0 && end_tags.contains(&tag) => {
+                            self.next_token();
+                            return Ok((body, w.value.into_owned()));
+                        }
+                        "if" | "for" => depth += 1,
+                        "endif" | "endfor" => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            body.push(token);
+        }
+    }
 
     /// Parse an expression prefix
     pub fn parse_prefix(&mut self) -> Result<Expr, ParserError> {
@@ -745,7 +1261,15 @@ impl<'a> Parser<'a> {
             }
         }));
 
+        if self.peek_jinja_block() {
+            return Ok(Expr::JinjaBlock {
+                expr: None,
+                block: self.parse_jinja_block()?,
+            });
+        }
+
         let next_token = self.next_token();
+        let span = next_token.span();
         let expr = match next_token.token {
             Token::Word(w) => match w.keyword {
                 Keyword::TRUE | Keyword::FALSE | Keyword::NULL => {
@@ -759,7 +1283,7 @@ impl<'a> Parser<'a> {
                     if dialect_of!(self is PostgreSqlDialect | GenericDialect) =>
                 {
                     Ok(Expr::Function(Function {
-                        name: ObjectName(vec![w.to_ident()]),
+                        name: ObjectName(vec![w.to_ident(span)]),
                         args: vec![],
                         over: None,
                         distinct: false,
@@ -771,7 +1295,7 @@ impl<'a> Parser<'a> {
                 | Keyword::CURRENT_DATE
                 | Keyword::LOCALTIME
                 | Keyword::LOCALTIMESTAMP => {
-                    self.parse_time_functions(ObjectName(vec![w.to_ident()]))
+                    self.parse_time_functions(ObjectName(vec![w.to_ident(span)]))
                 }
                 Keyword::CASE => self.parse_case_expr(),
                 Keyword::CAST => self.parse_cast_expr(),
@@ -781,7 +1305,7 @@ impl<'a> Parser<'a> {
                 Keyword::EXTRACT => self.parse_extract_expr(),
                 Keyword::CEIL => self.parse_ceil_floor_expr(true),
                 Keyword::FLOOR => self.parse_ceil_floor_expr(false),
-                Keyword::POSITION => self.parse_position_expr(),
+                Keyword::POSITION if self.peek_token() == Token::LParen => self.parse_position_expr(),
                 Keyword::SUBSTRING => self.parse_substring_expr(),
                 Keyword::OVERLAY => self.parse_overlay_expr(),
                 Keyword::TRIM => self.parse_trim_expr(),
@@ -799,6 +1323,12 @@ impl<'a> Parser<'a> {
                     self.parse_array_subquery()
                 }
                 Keyword::ARRAY_AGG => self.parse_array_agg_expr(),
+                Keyword::STRUCT
+                    if dialect_of!(self is BigQueryDialect | GenericDialect)
+                        && (self.peek_token() == Token::LParen || self.peek_token() == Token::Lt) =>
+                {
+                    self.parse_struct_literal()
+                }
                 Keyword::NOT => self.parse_not(),
                 Keyword::MATCH if dialect_of!(self is GenericDialect) => {
                     parser_err!(format!("MATCH is not supported by dbtranslate"))
@@ -807,11 +1337,12 @@ impl<'a> Parser<'a> {
                 // identifier, a function call, or a simple identifier:
                 _ => match self.peek_token().token {
                     Token::LParen | Token::Period => {
-                        let mut id_parts: Vec<Ident> = vec![w.to_ident()];
+                        let mut id_parts: Vec<Ident> = vec![w.to_ident(span)];
                         while self.consume_token(&Token::Period) {
                             let next_token = self.next_token();
+                            let span = next_token.span();
                             match next_token.token {
-                                Token::Word(w) => id_parts.push(w.to_ident()),
+                                Token::Word(w) => id_parts.push(w.to_ident(span)),
                                 _ => {
                                     return self
                                         .expected("an identifier or a '*' after '.'", next_token);
@@ -833,11 +1364,11 @@ impl<'a> Parser<'a> {
                         if w.value.starts_with('_') =>
                     {
                         Ok(Expr::IntroducedString {
-                            introducer: w.value,
+                            introducer: w.value.into_owned(),
                             value: self.parse_introduced_string_value()?,
                         })
                     }
-                    _ => Ok(Expr::Identifier(w.to_ident())),
+                    _ => Ok(Expr::Identifier(w.to_ident(span))),
                 },
             }, // End of Token::Word
             // array `[1, 2, 3]`
@@ -908,8 +1439,9 @@ impl<'a> Parser<'a> {
                     Ok(expr)
                 } else {
                     let tok = self.next_token();
+                    let span = tok.span();
                     let key = match tok.token {
-                        Token::Word(word) => word.to_ident(),
+                        Token::Word(word) => word.to_ident(span),
                         _ => return parser_err!(format!("Expected identifier, found: {tok}")),
                     };
                     Ok(Expr::CompositeAccess {
@@ -922,6 +1454,7 @@ impl<'a> Parser<'a> {
                 self.prev_token();
                 Ok(Expr::Value(self.parse_value()?))
             }
+            Token::DoubleLBrace => self.parse_dbt_expr(),
             _ => self.expected("an expression:", next_token),
         }?;
 
@@ -938,39 +1471,18 @@ impl<'a> Parser<'a> {
     pub fn parse_config(&mut self) -> Result<DbtConfig, ParserError> {
         let mut config_values = HashMap::new();
         
-        self.expect_token(&Token::Word(Word {
-            value: "config".to_string(),
-            quote_style: None,
-            keyword: Keyword::NoKeyword,
-        }))?;
+        let next_token = self.next_token();
+        match &next_token.token {
+            Token::Word(w) if w.value == "config" => {}
+            _ => return self.expected_one_of("config", next_token, &["config"]),
+        }
     
         self.expect_token(&Token::LParen)?;
     
         while self.peek_token() != Token::RParen {
             let key = self.parse_identifier()?.to_string();
             self.expect_token(&Token::Eq)?;
-            let value = match self.next_token().token {
-                Token::Word(w) => DbtConfigValue::String(w.value),
-                Token::SingleQuotedString(s) => DbtConfigValue::String(s),
-                Token::NationalStringLiteral(s) => DbtConfigValue::String(s),
-                Token::HexStringLiteral(s) => DbtConfigValue::String(s),
-                Token::LBracket => {
-                    let mut values = Vec::new();
-                    while self.peek_token() != Token::RBracket {
-                        if let Token::Word(w) = self.next_token().token {
-                            values.push(w.value);
-                        } else {
-                            return self.expected("a string value inside the list", self.peek_token());
-                        }
-                        if self.peek_token() != Token::RBracket {
-                            self.expect_token(&Token::Comma)?;
-                        }
-                    }
-                    self.expect_token(&Token::RBracket)?;
-                    DbtConfigValue::List(values)
-                }
-                _ => return self.expected("a string value or a list", self.peek_token()),
-            };
+            let value = self.parse_config_value()?;
     
             config_values.insert(key, value);
     
@@ -987,6 +1499,99 @@ impl<'a> Parser<'a> {
         })
     }
     
+    /// Parse a value passed to `config()`: a string, boolean, number or
+    /// `none`, a list or dict of them, or a Jinja expression like
+    /// `var('run_heavy_models')` or `target.name == 'prod'`
+    fn parse_config_value(&mut self) -> Result<DbtConfigValue, ParserError> {
+        let _guard = self.recursion_counter.try_decrease()?;
+        let start = self.index;
+        if let Ok(value) = self.parse_config_literal() {
+            if matches!(
+                self.peek_token().token,
+                Token::Comma | Token::RParen | Token::RBracket | Token::RBrace | Token::DoubleRBrace | Token::EOF
+            ) {
+                return Ok(value);
+            }
+        }
+        // Something more than a literal, like a comparison, which is parsed
+        // as SQL, since Jinja's operators mostly read the same
+        self.index = start;
+        Ok(DbtConfigValue::Jinja(self.parse_expr()?))
+    }
+
+    fn parse_config_literal(&mut self) -> Result<DbtConfigValue, ParserError> {
+        if let (Token::Word(w), Token::LParen) = (&self.peek_token().token, &self.peek_nth_token(1).token) {
+            if w.quote_style.is_none() {
+                return Ok(DbtConfigValue::Jinja(self.parse_dbt_call()?));
+            }
+        }
+        let next_token = self.next_token();
+        let value = match next_token.token {
+            Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case("true") => DbtConfigValue::Boolean(true),
+            Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case("false") => DbtConfigValue::Boolean(false),
+            Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case("none") => DbtConfigValue::Null,
+            Token::Word(w) => DbtConfigValue::String(w.value.into_owned()),
+            Token::SingleQuotedString(s) => DbtConfigValue::String(s.into_owned()),
+            Token::NationalStringLiteral(s) => DbtConfigValue::String(s.into_owned()),
+            Token::HexStringLiteral(s) => DbtConfigValue::String(s.into_owned()),
+            Token::Number(n, _) => DbtConfigValue::Number(n.into_owned()),
+            Token::Minus => match self.next_token().token {
+                Token::Number(n, _) => DbtConfigValue::Number(format!("-{n}")),
+                _ => return self.expected("a number after '-'", self.peek_token()),
+            },
+            Token::LBracket => {
+                let mut values = Vec::new();
+                while self.peek_token() != Token::RBracket {
+                    values.push(self.parse_config_value()?);
+                    if self.peek_token() != Token::RBracket {
+                        self.expect_token(&Token::Comma)?;
+                    }
+                }
+                self.expect_token(&Token::RBracket)?;
+                DbtConfigValue::List(values)
+            }
+            Token::LBrace => {
+                let mut entries = BTreeMap::new();
+                while !self.consume_config_dict_end() {
+                    let key = match self.next_token().token {
+                        Token::Word(w) => w.value.into_owned(),
+                        Token::SingleQuotedString(s) => s.into_owned(),
+                        _ => return self.expected("a key inside the dict", self.peek_token()),
+                    };
+                    self.expect_token(&Token::Colon)?;
+                    entries.insert(key, self.parse_config_value()?);
+                    if !matches!(self.peek_token().token, Token::RBrace | Token::DoubleRBrace) {
+                        self.expect_token(&Token::Comma)?;
+                    }
+                }
+                DbtConfigValue::Dict(entries)
+            }
+            _ => return self.expected("a string, boolean, number, list or dict", next_token),
+        };
+        Ok(value)
+    }
+
+    /// Consume the `}` that ends a dict in `config()`. A nested dict ends
+    /// with `}}`, which the tokenizer reads as the end of a Jinja tag, so
+    /// that's split in two and only its first half consumed. The tag itself
+    /// can't end inside the dict, since `config(` is still open.
+    fn consume_config_dict_end(&mut self) -> bool {
+        match self.peek_token().token {
+            Token::RBrace => {
+                self.next_token();
+                true
+            }
+            Token::DoubleRBrace => {
+                self.next_token();
+                let index = self.index - 1;
+                self.tokens[index].token = Token::RBrace;
+                self.index = index;
+                true
+            }
+            _ => false,
+        }
+    }
+
     pub fn parse_function(&mut self, name: ObjectName) -> Result<Expr, ParserError> {
         self.expect_token(&Token::LParen)?;
         let distinct = self.parse_all_or_distinct()?;
@@ -1101,7 +1706,7 @@ impl<'a> Parser<'a> {
     /// parse a group by expr. a group by expr can be one of group sets, roll up, cube, or simple
     /// expr.
     fn parse_group_by_expr(&mut self) -> Result<Expr, ParserError> {
-        if dialect_of!(self is PostgreSqlDialect | GenericDialect) {
+        if dialect_of!(self is PostgreSqlDialect | DuckDbDialect | GenericDialect) {
             if self.parse_keywords(&[Keyword::GROUPING, Keyword::SETS]) {
                 self.expect_token(&Token::LParen)?;
                 let result = self.parse_comma_separated(|p| p.parse_tuple(false, true))?;
@@ -1346,9 +1951,7 @@ impl<'a> Parser<'a> {
         self.expect_token(&Token::LParen)?;
         let mut trim_where = None;
         if let Token::Word(word) = self.peek_token().token {
-            if [Keyword::BOTH, Keyword::LEADING, Keyword::TRAILING]
-                .iter()
-                .any(|d| word.keyword == *d)
+            if [Keyword::BOTH, Keyword::LEADING, Keyword::TRAILING].contains(&word.keyword)
             {
                 trim_where = Some(self.parse_trim_where()?);
             }
@@ -1641,8 +2244,7 @@ impl<'a> Parser<'a> {
                     Keyword::TIMEZONE_HOUR,
                     Keyword::TIMEZONE_MINUTE,
                 ]
-                .iter()
-                .any(|d| kw.keyword == *d) =>
+                .contains(&kw.keyword) =>
             {
                 Some(self.parse_date_time_field()?)
             }
@@ -1690,6 +2292,13 @@ impl<'a> Parser<'a> {
             return infix;
         }
 
+        if self.peek_jinja_block() {
+            return Ok(Expr::JinjaBlock {
+                expr: Some(Box::new(expr)),
+                block: self.parse_jinja_block()?,
+            });
+        }
+
         let tok = self.next_token();
 
         let regular_binary_operator = match &tok.token {
@@ -1718,10 +2327,10 @@ impl<'a> Parser<'a> {
             }
             Token::Ampersand => Some(BinaryOperator::BitwiseAnd),
             Token::Div => Some(BinaryOperator::Divide),
-            Token::ShiftLeft if dialect_of!(self is PostgreSqlDialect | GenericDialect) => {
+            Token::ShiftLeft if dialect_of!(self is PostgreSqlDialect | DuckDbDialect | GenericDialect) => {
                 Some(BinaryOperator::PGBitwiseShiftLeft)
             }
-            Token::ShiftRight if dialect_of!(self is PostgreSqlDialect | GenericDialect) => {
+            Token::ShiftRight if dialect_of!(self is PostgreSqlDialect | DuckDbDialect | GenericDialect) => {
                 Some(BinaryOperator::PGBitwiseShiftRight)
             }
             Token::Sharp if dialect_of!(self is PostgreSqlDialect) => {
@@ -1823,7 +2432,7 @@ impl<'a> Parser<'a> {
                                 log::trace!("Peek token: {:?}", self.peek_token());
                                 Ok(Expr::AtTimeZone {
                                     timestamp: Box::new(expr),
-                                    time_zone,
+                                    time_zone: time_zone.into_owned(),
                                 })
                             }
                             _ => self.expected(
@@ -1877,6 +2486,19 @@ impl<'a> Parser<'a> {
             }
         } else if Token::DoubleColon == tok {
             self.parse_pg_cast(expr)
+        } else if Token::Period == tok {
+            // BigQuery field access on anything that returns a struct, e.g.
+            // `items[OFFSET(0)].name` or `PARSE_JSON(payload).id`
+            let tok = self.next_token();
+            let span = tok.span();
+            let key = match tok.token {
+                Token::Word(word) => word.to_ident(span),
+                _ => return parser_err!(format!("Expected identifier, found: {tok}")),
+            };
+            Ok(Expr::CompositeAccess {
+                expr: Box::new(expr),
+                key,
+            })
         } else if Token::ExclamationMark == tok {
             // PostgreSQL factorial operation
             Ok(Expr::UnaryOp {
@@ -1884,16 +2506,25 @@ impl<'a> Parser<'a> {
                 expr: Box::new(expr),
             })
         } else if Token::LBracket == tok {
-            if dialect_of!(self is PostgreSqlDialect | GenericDialect) {
+            if dialect_of!(self is PostgreSqlDialect | DuckDbDialect | TrinoDialect | GenericDialect | SnowflakeDialect) {
                 // parse index
                 return self.parse_array_index(expr);
             }
             self.parse_map_access(expr)
         } else if Token::Colon == tok {
+            // Keys of semi-structured data can be any word, keywords like
+            // `user` or `date` included
+            let key = match self.peek_token().token {
+                Token::Word(w) if w.quote_style.is_none() && dialect_of!(self is SnowflakeDialect | GenericDialect) => {
+                    self.next_token();
+                    Value::UnQuotedString(w.value.into_owned())
+                }
+                _ => self.parse_value()?,
+            };
             Ok(Expr::JsonAccess {
                 left: Box::new(expr),
                 operator: JsonOperator::Colon,
-                right: Box::new(Expr::Value(self.parse_value()?)),
+                right: Box::new(Expr::Value(self.parse_json_path(key)?)),
             })
         } else if Token::Arrow == tok
             || Token::LongArrow == tok
@@ -1952,6 +2583,36 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parse the rest of a Snowflake semi-structured path after its first
+    /// key, e.g. `.c[0].d` in `a:b.c[0].d`. A path longer than one key is
+    /// kept whole as an unquoted string.
+    pub fn parse_json_path(&mut self, key: Value) -> Result<Value, ParserError> {
+        let mut path = key.to_string();
+        let mut nested = false;
+        loop {
+            match (self.peek_token().token, self.peek_nth_token(1).token) {
+                (Token::Period, Token::Word(word)) => {
+                    self.next_token();
+                    self.next_token();
+                    path = format!("{path}.{word}");
+                }
+                (Token::LBracket, _) => {
+                    self.next_token();
+                    let index = self.parse_expr()?;
+                    self.expect_token(&Token::RBracket)?;
+                    path = format!("{path}[{index}]");
+                }
+                _ => break,
+            }
+            nested = true;
+        }
+        if nested {
+            Ok(Value::UnQuotedString(path))
+        } else {
+            Ok(key)
+        }
+    }
+
     pub fn parse_map_access(&mut self, expr: Expr) -> Result<Expr, ParserError> {
         let key = self.parse_map_key()?;
         let tok = self.consume_token(&Token::RBracket);
@@ -1963,13 +2624,10 @@ impl<'a> Parser<'a> {
             debug!("Tok: {}", tok);
             key_parts.push(key);
         }
-        match expr {
-            e @ Expr::Identifier(_) | e @ Expr::CompoundIdentifier(_) => Ok(Expr::MapAccess {
-                column: Box::new(e),
-                keys: key_parts,
-            }),
-            _ => Ok(expr),
-        }
+        Ok(Expr::MapAccess {
+            column: Box::new(expr),
+            keys: key_parts,
+        })
     }
 
     /// Parses the parens following the `[ NOT ] IN` operator
@@ -2052,6 +2710,11 @@ impl<'a> Parser<'a> {
         let token_1 = self.peek_nth_token(1);
         let token_2 = self.peek_nth_token(2);
         debug!("0: {token_0} 1: {token_1} 2: {token_2}");
+        // A Jinja block after an expression extends all of it, so it binds
+        // as loosely as OR
+        if self.peek_jinja_block() {
+            return Ok(Self::OR_PREC);
+        }
         match token.token {
             Token::Word(w) if w.keyword == Keyword::OR => Ok(Self::OR_PREC),
             Token::Word(w) if w.keyword == Keyword::AND => Ok(Self::AND_PREC),
@@ -2107,6 +2770,7 @@ impl<'a> Parser<'a> {
             Token::Mul | Token::Div | Token::Mod | Token::StringConcat => Ok(40),
             Token::DoubleColon => Ok(50),
             Token::Colon => Ok(50),
+            Token::Period if dialect_of!(self is BigQueryDialect) => Ok(50),
             Token::ExclamationMark => Ok(50),
             Token::LBracket
             | Token::LongArrow
@@ -2124,12 +2788,12 @@ impl<'a> Parser<'a> {
 
     /// Return the first non-whitespace token that has not yet been processed
     /// (or None if reached end-of-file)
-    pub fn peek_token(&self) -> TokenWithLocation {
+    pub fn peek_token(&self) -> TokenWithLocation<'a> {
         self.peek_nth_token(0)
     }
 
     /// Return nth non-whitespace token that has not yet been processed
-    pub fn peek_nth_token(&self, mut n: usize) -> TokenWithLocation {
+    pub fn peek_nth_token(&self, mut n: usize) -> TokenWithLocation<'a> {
         let mut index = self.index;
         loop {
             index += 1;
@@ -2154,7 +2818,7 @@ impl<'a> Parser<'a> {
     /// Return the first non-whitespace token that has not yet been processed
     /// (or None if reached end-of-file) and mark it as processed. OK to call
     /// repeatedly after reaching EOF.
-    pub fn next_token(&mut self) -> TokenWithLocation {
+    pub fn next_token(&mut self) -> TokenWithLocation<'a> {
         loop {
             self.index += 1;
             match self.tokens.get(self.index - 1) {
@@ -2172,7 +2836,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Return the first unprocessed token, possibly whitespace.
-    pub fn next_token_no_skip(&mut self) -> Option<&TokenWithLocation> {
+    pub fn next_token_no_skip(&mut self) -> Option<&TokenWithLocation<'a>> {
         self.index += 1;
         self.tokens.get(self.index - 1)
     }
@@ -2195,11 +2859,71 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Return the location of the most recently consumed non-whitespace
+    /// token. After a [`ParserError`] this is where parsing stopped, which
+    /// lets callers point users at the offending part of the input.
+    pub fn current_location(&self) -> Location {
+        let consumed = self.tokens[..self.index.min(self.tokens.len())]
+            .iter()
+            .rev()
+            .find(|t| !matches!(t.token, Token::Whitespace(_)));
+        match consumed {
+            Some(token) => token.location.clone(),
+            None => self.peek_token().location,
+        }
+    }
+
+    /// Return the span of the most recently consumed non-whitespace token,
+    /// or an empty span if nothing has been consumed yet
+    pub fn prev_token_span(&self) -> Span {
+        self.tokens[..self.index.min(self.tokens.len())]
+            .iter()
+            .rev()
+            .find(|t| !matches!(t.token, Token::Whitespace(_)))
+            .map(TokenWithLocation::span)
+            .unwrap_or_default()
+    }
+
+    /// Return an error once the time limit set with
+    /// [`Parser::with_time_limit`] has run out
+    fn check_time_limit(&self) -> Result<(), ParserError> {
+        #[cfg(feature = "std")]
+        if let (Some(deadline), Some(limit)) = (self.deadline, self.time_limit) {
+            if Instant::now() >= deadline {
+                return Err(ParserError::ResourceLimitExceeded(format!(
+                    "parse time exceeded the limit of {}ms",
+                    limit.as_millis()
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Report unexpected token
     pub fn expected<T>(&self, expected: &str, found: TokenWithLocation) -> Result<T, ParserError> {
         parser_err!(format!("Expected {expected}, found: {found}"))
     }
 
+    /// Report unexpected token, suggesting the closest of `candidates` when
+    /// the token looks like a misspelling of one of them
+    pub fn expected_one_of<T>(
+        &self,
+        expected: &str,
+        found: TokenWithLocation,
+        candidates: &[&str],
+    ) -> Result<T, ParserError> {
+        let suggestion = match &found.token {
+            Token::Word(w) => did_you_mean(&w.value, candidates.iter().copied()),
+            _ => None,
+        };
+        match suggestion {
+            Some(suggestion) => parser_err!(format!(
+                "Expected {expected}, found: {found}, did you mean '{suggestion}'?"
+            )),
+            None => self.expected(expected, found),
+        }
+    }
+
     /// Look for an expected keyword and consume it if it exists
     #[must_use]
     pub fn parse_keyword(&mut self, expected: Keyword) -> bool {
@@ -2306,12 +3030,36 @@ impl<'a> Parser<'a> {
         let old_value = self.options.trailing_commas;
         self.options.trailing_commas |= dialect_of!(self is BigQueryDialect);
 
-        let ret = self.parse_comma_separated(|p| p.parse_select_item());
+        let ret = self.parse_comma_separated(|p| p.parse_select_item()).and_then(|mut items| {
+            // `{% if x %} b, {% endif %}` brings its own comma, so the next
+            // item follows the block without one
+            while matches!(items.last(), Some(SelectItem::UnnamedExpr(expr)) if Self::ends_with_jinja_comma(expr))
+                && !self.peek_end_of_projection()
+            {
+                items.extend(self.parse_comma_separated(|p| p.parse_select_item())?);
+            }
+            Ok(items)
+        });
         self.options.trailing_commas = old_value;
 
         ret
     }
 
+    fn ends_with_jinja_comma(expr: &Expr) -> bool {
+        match expr {
+            Expr::JinjaBlock { block, .. } => block.ends_with_comma(),
+            _ => false,
+        }
+    }
+
+    fn peek_end_of_projection(&self) -> bool {
+        match self.peek_token().token {
+            Token::Word(w) => keywords::RESERVED_FOR_COLUMN_ALIAS.contains(&w.keyword),
+            Token::RParen | Token::SemiColon | Token::EOF => true,
+            _ => false,
+        }
+    }
+
     /// Parse a comma-separated list of 1+ items accepted by `F`
     pub fn parse_comma_separated<T, F>(&mut self, mut f: F) -> Result<Vec<T>, ParserError>
     where
@@ -2325,9 +3073,7 @@ impl<'a> Parser<'a> {
             } else if self.options.trailing_commas {
                 match self.peek_token().token {
                     Token::Word(kw)
-                        if keywords::RESERVED_FOR_COLUMN_ALIAS
-                            .iter()
-                            .any(|d| kw.keyword == *d) =>
+                        if keywords::RESERVED_FOR_COLUMN_ALIAS.contains(&kw.keyword) =>
                     {
                         break;
                     }
@@ -2403,8 +3149,8 @@ impl<'a> Parser<'a> {
                 Keyword::FALSE => Ok(Value::Boolean(false)),
                 Keyword::NULL => Ok(Value::Null),
                 Keyword::NoKeyword if w.quote_style.is_some() => match w.quote_style {
-                    Some('"') => Ok(Value::DoubleQuotedString(w.value)),
-                    Some('\'') => Ok(Value::SingleQuotedString(w.value)),
+                    Some('"') => Ok(Value::DoubleQuotedString(w.value.into_owned())),
+                    Some('\'') => Ok(Value::SingleQuotedString(w.value.into_owned())),
                     _ => self.expected(
                         "A value?",
                         TokenWithLocation {
@@ -2415,7 +3161,7 @@ impl<'a> Parser<'a> {
                 },
                 // Case when Snowflake Semi-structured data like key:value
                 Keyword::NoKeyword | Keyword::LOCATION | Keyword::TYPE if dialect_of!(self is SnowflakeDialect | GenericDialect) => {
-                    Ok(Value::UnQuotedString(w.value))
+                    Ok(Value::UnQuotedString(w.value.into_owned()))
                 }
                 _ => self.expected(
                     "a concrete value",
@@ -2436,12 +3182,12 @@ impl<'a> Parser<'a> {
             Token::DoubleQuotedString(ref s) => Ok(Value::DoubleQuotedString(s.to_string())),
             Token::DollarQuotedString(ref s) => Ok(Value::DollarQuotedString(s.clone())),
             Token::SingleQuotedByteStringLiteral(ref s) => {
-                Ok(Value::SingleQuotedByteStringLiteral(s.clone()))
+                Ok(Value::SingleQuotedByteStringLiteral(s.to_string()))
             }
             Token::DoubleQuotedByteStringLiteral(ref s) => {
-                Ok(Value::DoubleQuotedByteStringLiteral(s.clone()))
+                Ok(Value::DoubleQuotedByteStringLiteral(s.to_string()))
             }
-            Token::RawStringLiteral(ref s) => Ok(Value::RawStringLiteral(s.clone())),
+            Token::RawStringLiteral(ref s) => Ok(Value::RawStringLiteral(s.to_string())),
             Token::NationalStringLiteral(ref s) => Ok(Value::NationalStringLiteral(s.to_string())),
             Token::EscapedStringLiteral(ref s) => Ok(Value::EscapedStringLiteral(s.to_string())),
             Token::HexStringLiteral(ref s) => Ok(Value::HexStringLiteral(s.to_string())),
@@ -2504,11 +3250,11 @@ impl<'a> Parser<'a> {
     pub fn parse_literal_string(&mut self) -> Result<String, ParserError> {
         let next_token = self.next_token();
         match next_token.token {
-            Token::Word(Word { value, keyword, .. }) if keyword == Keyword::NoKeyword => Ok(value),
-            Token::SingleQuotedString(s) => Ok(s),
-            Token::DoubleQuotedString(s) => Ok(s),
+            Token::Word(Word { value, keyword: Keyword::NoKeyword, .. }) => Ok(value.into_owned()),
+            Token::SingleQuotedString(s) => Ok(s.into_owned()),
+            Token::DoubleQuotedString(s) => Ok(s.into_owned()),
             Token::EscapedStringLiteral(s) if dialect_of!(self is PostgreSqlDialect | GenericDialect) => {
-                Ok(s)
+                Ok(s.into_owned())
             }
             _ => self.expected("literal string", next_token),
         }
@@ -2517,22 +3263,23 @@ impl<'a> Parser<'a> {
     /// Parse a map key string
     pub fn parse_map_key(&mut self) -> Result<Expr, ParserError> {
         let next_token = self.next_token();
+        let span = next_token.span();
         match next_token.token {
             // handle bigquery offset subscript operator which overlaps with OFFSET operator
             Token::Word(Word { value, keyword, .. })
                 if (dialect_of!(self is BigQueryDialect) && keyword == Keyword::OFFSET) =>
             {
-                self.parse_function(ObjectName(vec![Ident::new(value)]))
+                self.parse_function(ObjectName(vec![Ident::with_span(span, value)]))
             }
             Token::Word(Word { value, keyword, .. }) if (keyword == Keyword::NoKeyword) => {
                 if self.peek_token() == Token::LParen {
-                    return self.parse_function(ObjectName(vec![Ident::new(value)]));
+                    return self.parse_function(ObjectName(vec![Ident::with_span(span, value)]));
                 }
-                Ok(Expr::Value(Value::SingleQuotedString(value)))
+                Ok(Expr::Value(Value::SingleQuotedString(value.into_owned())))
             }
-            Token::SingleQuotedString(s) => Ok(Expr::Value(Value::SingleQuotedString(s))),
+            Token::SingleQuotedString(s) => Ok(Expr::Value(Value::SingleQuotedString(s.into_owned()))),
             #[cfg(not(feature = "bigdecimal"))]
-            Token::Number(s, _) => Ok(Expr::Value(Value::Number(s, false))),
+            Token::Number(s, _) => Ok(Expr::Value(Value::Number(s.into_owned(), false))),
             #[cfg(feature = "bigdecimal")]
             Token::Number(s, _) => Ok(Expr::Value(Value::Number(s.parse().unwrap(), false))),
             _ => self.expected("literal string, number or function", next_token),
@@ -2697,17 +3444,26 @@ impl<'a> Parser<'a> {
                 Keyword::SET => Ok(DataType::Set(self.parse_string_values()?)),
                 Keyword::ARRAY => {
                     if dialect_of!(self is SnowflakeDialect) {
-                        Ok(DataType::Array(None))
+                        Ok(DataType::Array(ArrayElemTypeDef::None))
                     } else {
-                        // Hive array syntax. Note that nesting arrays - or other Hive syntax
-                        // that ends with > will fail due to "C++" problem - >> is parsed as
-                        // Token::ShiftRight
+                        // BigQuery and Hive array syntax
                         self.expect_token(&Token::Lt)?;
                         let inside_type = self.parse_data_type()?;
-                        self.expect_token(&Token::Gt)?;
-                        Ok(DataType::Array(Some(Box::new(inside_type))))
+                        self.expect_closing_angle_bracket()?;
+                        Ok(DataType::Array(ArrayElemTypeDef::AngleBracket(Box::new(inside_type))))
                     }
                 }
+                Keyword::STRUCT if self.peek_token() == Token::Lt => {
+                    Ok(DataType::Struct(self.parse_struct_type_def()?))
+                }
+                Keyword::ROW
+                    if dialect_of!(self is TrinoDialect | GenericDialect) && self.peek_token() == Token::LParen =>
+                {
+                    self.expect_token(&Token::LParen)?;
+                    let fields = self.parse_comma_separated(Parser::parse_struct_field)?;
+                    self.expect_token(&Token::RParen)?;
+                    Ok(DataType::Row(fields))
+                }
                 _ => {
                     self.prev_token();
                     let type_name = self.parse_object_name()?;
@@ -2725,18 +3481,81 @@ impl<'a> Parser<'a> {
         // Keyword::ARRAY syntax from above
         while self.consume_token(&Token::LBracket) {
             self.expect_token(&Token::RBracket)?;
-            data = DataType::Array(Some(Box::new(data)))
+            data = DataType::Array(ArrayElemTypeDef::SquareBracket(Box::new(data)))
         }
         Ok(data)
     }
 
+    /// Parse the `<...>` of a BigQuery `STRUCT<a INT64, b STRING>`, whose
+    /// fields may be left unnamed
+    pub fn parse_struct_type_def(&mut self) -> Result<Vec<StructField>, ParserError> {
+        self.expect_token(&Token::Lt)?;
+        let fields = self.parse_comma_separated(Parser::parse_struct_field)?;
+        self.expect_closing_angle_bracket()?;
+        Ok(fields)
+    }
+
+    /// Parse one field of a struct or row type, with or without a name
+    pub fn parse_struct_field(&mut self) -> Result<StructField, ParserError> {
+        // A name is a word followed by the type, which starts with a word too
+        let field_name = match (self.peek_token().token, self.peek_nth_token(1).token) {
+            (Token::Word(_), Token::Word(_)) => Some(self.parse_identifier()?),
+            _ => None,
+        };
+        let field_type = self.parse_data_type()?;
+        Ok(StructField { field_name, field_type })
+    }
+
+    /// Expect the `>` that closes a type like `ARRAY<INT64>`. Nested types
+    /// can end in `>>`, which is tokenized as a shift, so it's split in two
+    /// and the second `>` is left for the outer type. The split stays if
+    /// the parser backtracks, which is fine since `>>` can't follow a type.
+    fn expect_closing_angle_bracket(&mut self) -> Result<(), ParserError> {
+        if self.peek_token() == Token::ShiftRight {
+            let shift = self.next_token();
+            self.tokens[self.index - 1].token = Token::Gt;
+            self.tokens.insert(self.index, TokenWithLocation { token: Token::Gt, ..shift });
+            return Ok(());
+        }
+        self.expect_token(&Token::Gt)
+    }
+
+    /// Parse a BigQuery struct literal after the `STRUCT` keyword, e.g.
+    /// `STRUCT(1 AS a, 'x' AS b)` or `STRUCT<a INT64, b STRING>(1, 'x')`
+    pub fn parse_struct_literal(&mut self) -> Result<Expr, ParserError> {
+        let fields = if self.peek_token() == Token::Lt {
+            self.parse_struct_type_def()?
+        } else {
+            vec![]
+        };
+        self.expect_token(&Token::LParen)?;
+        let values = if self.consume_token(&Token::RParen) {
+            vec![]
+        } else {
+            let values = self.parse_comma_separated(|parser| {
+                let expr = parser.parse_expr()?;
+                if parser.parse_keyword(Keyword::AS) {
+                    Ok(Expr::Named {
+                        expr: Box::new(expr),
+                        name: parser.parse_identifier()?,
+                    })
+                } else {
+                    Ok(expr)
+                }
+            })?;
+            self.expect_token(&Token::RParen)?;
+            values
+        };
+        Ok(Expr::Struct { values, fields })
+    }
+
     pub fn parse_string_values(&mut self) -> Result<Vec<String>, ParserError> {
         self.expect_token(&Token::LParen)?;
         let mut values = Vec::new();
         loop {
             let next_token = self.next_token();
             match next_token.token {
-                Token::SingleQuotedString(value) => values.push(value),
+                Token::SingleQuotedString(value) => values.push(value.into_owned()),
                 _ => self.expected("a string", next_token)?,
             }
             let next_token = self.next_token();
@@ -2766,6 +3585,7 @@ impl<'a> Parser<'a> {
     ) -> Result<Option<Ident>, ParserError> {
         let after_as = self.parse_keyword(Keyword::AS);
         let next_token = self.next_token();
+        let span = next_token.span();
         match next_token.token {
             // Accept any identifier after `AS` (though many dialects have restrictions on
             // keywords that may appear here). If there's no `AS`: don't parse keywords,
@@ -2773,7 +3593,7 @@ impl<'a> Parser<'a> {
             // (For example, in `FROM t1 JOIN` the `JOIN` will always be parsed as a keyword,
             // not an alias.)
             Token::Word(w) if after_as || !reserved_kwds.contains(&w.keyword) => {
-                Ok(Some(w.to_ident()))
+                Ok(Some(w.to_ident(span)))
             }
             // MSSQL supports single-quoted strings as aliases for columns
             // We accept them as table aliases too, although MSSQL does not.
@@ -2787,9 +3607,9 @@ impl<'a> Parser<'a> {
             //    character. When it sees such a <literal>, your DBMS will
             //    ignore the <separator> and treat the multiple strings as
             //    a single <literal>."
-            Token::SingleQuotedString(s) => Ok(Some(Ident::with_quote('\'', s))),
+            Token::SingleQuotedString(s) => Ok(Some(Ident::with_quote_and_span('\'', span, s))),
             // Support for MySql dialect double quoted string, `AS "HOUR"` for example
-            Token::DoubleQuotedString(s) => Ok(Some(Ident::with_quote('\"', s))),
+            Token::DoubleQuotedString(s) => Ok(Some(Ident::with_quote_and_span('\"', span, s))),
             _ => {
                 if after_as {
                     return self.expected("an identifier after AS", next_token);
@@ -2834,9 +3654,11 @@ impl<'a> Parser<'a> {
     pub fn parse_identifiers(&mut self) -> Result<Vec<Ident>, ParserError> {
         let mut idents = vec![];
         loop {
-            match self.peek_token().token {
+            let token = self.peek_token();
+            let span = token.span();
+            match token.token {
                 Token::Word(w) => {
-                    idents.push(w.to_ident());
+                    idents.push(w.to_ident(span));
                 }
                 Token::EOF | Token::Eq => break,
                 _ => {}
@@ -2849,10 +3671,11 @@ impl<'a> Parser<'a> {
     /// Parse a simple one-word identifier (possibly quoted, possibly a keyword)
     pub fn parse_identifier(&mut self) -> Result<Ident, ParserError> {
         let next_token = self.next_token();
+        let span = next_token.span();
         match next_token.token {
-            Token::Word(w) => Ok(w.to_ident()),
-            Token::SingleQuotedString(s) => Ok(Ident::with_quote('\'', s)),
-            Token::DoubleQuotedString(s) => Ok(Ident::with_quote('\"', s)),
+            Token::Word(w) => Ok(w.to_ident(span)),
+            Token::SingleQuotedString(s) => Ok(Ident::with_quote_and_span('\'', span, s)),
+            Token::DoubleQuotedString(s) => Ok(Ident::with_quote_and_span('\"', span, s)),
             _ => self.expected("identifier", next_token),
         }
     }
@@ -2967,8 +3790,8 @@ impl<'a> Parser<'a> {
                 let next_token = self.next_token();
                 match next_token.token {
                     Token::Word(w) => modifiers.push(w.to_string()),
-                    Token::Number(n, _) => modifiers.push(n),
-                    Token::SingleQuotedString(s) => modifiers.push(s),
+                    Token::Number(n, _) => modifiers.push(n.into_owned()),
+                    Token::SingleQuotedString(s) => modifiers.push(s.into_owned()),
 
                     Token::Comma => {
                         continue;
@@ -2994,6 +3817,7 @@ impl<'a> Parser<'a> {
     /// expect the initial keyword to be already consumed
     pub fn parse_query(&mut self, config: Option<DbtConfig>) -> Result<Query, ParserError> {
         let _guard = self.recursion_counter.try_decrease()?;
+        self.check_time_limit()?;
         let with = if self.parse_keyword(Keyword::WITH) {
             Some(With {
                 recursive: self.parse_keyword(Keyword::RECURSIVE),
@@ -3038,13 +3862,14 @@ impl<'a> Parser<'a> {
         }
 
         Ok(Query {
-            config: config,
+            config,
             with,
             body,
             order_by,
             limit,
             offset,
             jinja_variables: vec![],
+            jinja_blocks: vec![],
         })
     }
 
@@ -3126,12 +3951,14 @@ impl<'a> Parser<'a> {
                 break;
             }
             self.next_token(); // skip past the set operator
+            let op_span = self.prev_token_span();
             let set_quantifier = self.parse_set_quantifier(&op);
             expr = SetExpr::SetOperation {
                 left: Box::new(expr),
                 op: op.unwrap(),
                 set_quantifier,
                 right: Box::new(self.parse_query_body(next_precedence)?),
+                op_span: AttachedSpan(op_span),
             };
         }
 
@@ -3174,6 +4001,8 @@ impl<'a> Parser<'a> {
     /// Parse a restricted `SELECT` statement (no CTEs / `UNION` / `ORDER BY`),
     /// assuming the initial `SELECT` was already consumed
     pub fn parse_select(&mut self) -> Result<Select, ParserError> {
+        // The `SELECT` was just read
+        let select_span = self.prev_token_span();
         let distinct = self.parse_all_or_distinct()?;
 
         let top = if self.parse_keyword(Keyword::TOP) {
@@ -3243,6 +4072,11 @@ impl<'a> Parser<'a> {
             }
         }
 
+        let mut jinja_blocks = vec![];
+        while self.peek_jinja_block() {
+            jinja_blocks.push(self.parse_jinja_block()?);
+        }
+
         let selection = if self.parse_keyword(Keyword::WHERE) {
             Some(self.parse_expr()?)
         } else {
@@ -3286,6 +4120,7 @@ impl<'a> Parser<'a> {
         };
 
         Ok(Select {
+            select_span: AttachedSpan(select_span),
             distinct,
             top,
             projection,
@@ -3299,6 +4134,7 @@ impl<'a> Parser<'a> {
             sort_by,
             having,
             qualify,
+            jinja_blocks,
         })
     }
 
@@ -3322,6 +4158,14 @@ impl<'a> Parser<'a> {
                     relation: self.parse_table_factor()?,
                     join_operator,
                 }
+            } else if dialect_of!(self is DuckDbDialect | GenericDialect)
+                && self.parse_keywords(&[Keyword::POSITIONAL, Keyword::JOIN])
+            {
+                // DuckDB extension, joining rows by their position
+                Join {
+                    relation: self.parse_table_factor()?,
+                    join_operator: JoinOperator::PositionalJoin,
+                }
             } else if self.parse_keyword(Keyword::OUTER) {
                 // MSSQL extension, similar to LEFT JOIN LATERAL .. ON 1=1
                 self.expect_keyword(Keyword::APPLY)?;
@@ -3420,11 +4264,21 @@ impl<'a> Parser<'a> {
     /// A table name or a parenthesized subquery, followed by optional `[AS] alias`
     pub fn parse_table_factor(&mut self) -> Result<TableFactor, ParserError> {
         if self.parse_keyword(Keyword::LATERAL) {
-            // LATERAL must always be followed by a subquery.
-            if !self.consume_token(&Token::LParen) {
-                self.expected("subquery after LATERAL", self.peek_token())?;
+            // LATERAL must be followed by a subquery or a function call
+            if self.consume_token(&Token::LParen) {
+                self.parse_derived_table_factor(Lateral)
+            } else {
+                let name = self.parse_object_name()?;
+                self.expect_token(&Token::LParen)?;
+                let args = self.parse_optional_args()?;
+                let alias = self.parse_optional_table_alias(keywords::RESERVED_FOR_TABLE_ALIAS)?;
+                Ok(TableFactor::Function {
+                    lateral: true,
+                    name,
+                    args,
+                    alias,
+                })
             }
-            self.parse_derived_table_factor(Lateral)
         } else if self.parse_keyword(Keyword::TABLE) {
             // parse table function (SELECT * FROM TABLE (<expr>) [ AS <alias> ])
             self.expect_token(&Token::LParen)?;
@@ -3440,10 +4294,10 @@ impl<'a> Parser<'a> {
                 Token::Word(w) if w.value.to_lowercase() == "ref" => {
                     self.next_token(); // Consume the "ref" keyword
                     self.expect_token(&Token::LParen)?;
-                    let model_name = self.parse_ref()?;
+                    let (package, model_name, version) = self.parse_ref()?;
                     self.expect_token(&Token::DoubleRBrace)?;
                     let alias = self.parse_optional_table_alias(keywords::RESERVED_FOR_TABLE_ALIAS)?;
-                    return Ok(TableFactor::DbtRef { model_name, alias });
+                    Ok(TableFactor::DbtRef { package, model_name, version, alias })
                 }
                 Token::Word(w) if w.value.to_lowercase() == "source" => {
                     self.next_token(); // Consume the "source" keyword
@@ -3452,16 +4306,30 @@ impl<'a> Parser<'a> {
                     self.expect_token(&Token::DoubleRBrace)?;
                     let alias = self.parse_optional_table_alias(keywords::RESERVED_FOR_TABLE_ALIAS)?;
 
-                    return Ok(TableFactor::DbtSource {
+                    Ok(TableFactor::DbtSource {
                         source_name,
                         table_name,
                         alias,
-                    });
+                    })
+                }
+                Token::Word(w) if w.value.to_lowercase() == "this" => {
+                    let this = w.to_ident(next_token.span());
+                    self.next_token(); // Consume the "this" keyword
+                    self.expect_token(&Token::DoubleRBrace)?;
+                    let alias = self.parse_optional_table_alias(keywords::RESERVED_FOR_TABLE_ALIAS)?;
+
+                    Ok(TableFactor::DbtThis { this, alias })
+                }
+                Token::Word(w) => {
+                    let first = w.to_ident(next_token.span());
+                    self.next_token();
+                    let call = self.parse_dbt_macro_call(first)?;
+                    self.expect_token(&Token::DoubleRBrace)?;
+                    let alias = self.parse_optional_table_alias(keywords::RESERVED_FOR_TABLE_ALIAS)?;
+
+                    Ok(TableFactor::DbtMacro { call, alias })
                 }
-                _ => return Err(ParserError::ParserError(format!(
-                    "Expected `ref` or `source` keyword after '{{', found: {}",
-                    next_token.token
-                ))),
+                _ => self.expected("a macro call after '{{'", next_token),
             }
             // let model_name = self.parse_ref()?;
             // let alias = self.parse_optional_table_alias(keywords::RESERVED_FOR_TABLE_ALIAS)?;
@@ -3539,8 +4407,11 @@ impl<'a> Parser<'a> {
                         | TableFactor::Table { alias, .. }
                         | TableFactor::DbtRef { alias, .. }
                         | TableFactor::DbtSource { alias, .. }
+                        | TableFactor::DbtThis { alias, .. }
+                        | TableFactor::DbtMacro { alias, .. }
                         | TableFactor::UNNEST { alias, .. }
                         | TableFactor::TableFunction { alias, .. }
+                        | TableFactor::Function { alias, .. }
                         | TableFactor::Pivot {
                             pivot_alias: alias, ..
                         }
@@ -3565,13 +4436,15 @@ impl<'a> Parser<'a> {
                 // appearing alone in parentheses (e.g. `FROM (mytable)`)
                 self.expected("joined table", self.peek_token())
             }
-        } else if dialect_of!(self is BigQueryDialect | GenericDialect)
+        } else if dialect_of!(self is BigQueryDialect | TrinoDialect | GenericDialect)
             && self.parse_keyword(Keyword::UNNEST)
         {
             self.expect_token(&Token::LParen)?;
-            let expr = self.parse_expr()?;
+            let array_exprs = self.parse_comma_separated(Parser::parse_expr)?;
             self.expect_token(&Token::RParen)?;
 
+            let with_ordinality = self.parse_keywords(&[Keyword::WITH, Keyword::ORDINALITY]);
+
             let alias = match self.parse_optional_table_alias(keywords::RESERVED_FOR_TABLE_ALIAS) {
                 Ok(Some(alias)) => Some(alias),
                 Ok(None) => None,
@@ -3595,7 +4468,8 @@ impl<'a> Parser<'a> {
 
             Ok(TableFactor::UNNEST {
                 alias,
-                array_expr: Box::new(expr),
+                array_exprs,
+                with_ordinality,
                 with_offset,
                 with_offset_alias,
             })
@@ -3659,11 +4533,13 @@ impl<'a> Parser<'a> {
         table_alias: Option<TableAlias>,
     ) -> Result<TableFactor, ParserError> {
         self.expect_token(&Token::LParen)?;
-        let function_name = match self.next_token().token {
+        let next_token = self.next_token();
+        let span = next_token.span();
+        let function_name = match next_token.token {
             Token::Word(w) => Ok(w.value),
             _ => self.expected("an aggregate function name", self.peek_token()),
         }?;
-        let function = self.parse_function(ObjectName(vec![Ident::new(function_name)]))?;
+        let function = self.parse_function(ObjectName(vec![Ident::with_span(span, function_name)]))?;
         self.expect_keyword(Keyword::FOR)?;
         let value_column = self.parse_object_name()?.0;
         self.expect_keyword(Keyword::IN)?;
@@ -3698,7 +4574,9 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse_function_args(&mut self) -> Result<FunctionArg, ParserError> {
-        if self.peek_nth_token(1) == Token::RArrow {
+        if dialect_of!(self is TrinoDialect | DuckDbDialect) && self.peek_lambda() {
+            Ok(FunctionArg::Unnamed(FunctionArgExpr::Expr(self.parse_lambda()?)))
+        } else if self.peek_nth_token(1) == Token::RArrow {
             let name = self.parse_identifier()?;
 
             self.expect_token(&Token::RArrow)?;
@@ -3710,6 +4588,41 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Whether the next tokens start a lambda, `x ->` or `(x, y) ->`
+    fn peek_lambda(&self) -> bool {
+        match self.peek_token().token {
+            Token::Word(_) => self.peek_nth_token(1) == Token::Arrow,
+            Token::LParen => {
+                let mut n = 1;
+                loop {
+                    if !matches!(self.peek_nth_token(n).token, Token::Word(_)) {
+                        return false;
+                    }
+                    match self.peek_nth_token(n + 1).token {
+                        Token::Comma => n += 2,
+                        Token::RParen => return self.peek_nth_token(n + 2) == Token::Arrow,
+                        _ => return false,
+                    }
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse a lambda such as `x -> x > 1` or `(k, v) -> v IS NOT NULL`
+    pub fn parse_lambda(&mut self) -> Result<Expr, ParserError> {
+        let params = if self.consume_token(&Token::LParen) {
+            let params = self.parse_comma_separated(Parser::parse_identifier)?;
+            self.expect_token(&Token::RParen)?;
+            params
+        } else {
+            vec![self.parse_identifier()?]
+        };
+        self.expect_token(&Token::Arrow)?;
+        let body = self.parse_expr()?;
+        Ok(Expr::Lambda { params, body: Box::new(body) })
+    }
+
     pub fn parse_optional_args(&mut self) -> Result<Vec<FunctionArg>, ParserError> {
         if self.consume_token(&Token::RParen) {
             Ok(vec![])
@@ -3742,19 +4655,30 @@ impl<'a> Parser<'a> {
                 } else {
                     expr
                 };
+                // What follows a block ending in a comma is the next item,
+                // not an alias
+                if Self::ends_with_jinja_comma(&expr) {
+                    return Ok(SelectItem::UnnamedExpr(expr));
+                }
                 self.parse_optional_alias(keywords::RESERVED_FOR_COLUMN_ALIAS)
                     .map(|alias| match alias {
                         Some(alias) => SelectItem::ExprWithAlias { expr, alias },
                         None => SelectItem::UnnamedExpr(expr),
                     })
             }
-            WildcardExpr::QualifiedWildcard(prefix) => Ok(SelectItem::QualifiedWildcard(
-                prefix,
-                self.parse_wildcard_additional_options()?,
-            )),
-            WildcardExpr::Wildcard => Ok(SelectItem::Wildcard(
-                self.parse_wildcard_additional_options()?,
-            )),
+            WildcardExpr::QualifiedWildcard(prefix) => {
+                let wildcard_span = self.prev_token_span();
+                Ok(SelectItem::QualifiedWildcard(
+                    prefix,
+                    self.parse_wildcard_additional_options(wildcard_span)?,
+                ))
+            }
+            WildcardExpr::Wildcard => {
+                let wildcard_span = self.prev_token_span();
+                Ok(SelectItem::Wildcard(
+                    self.parse_wildcard_additional_options(wildcard_span)?,
+                ))
+            }
         }
     }
 
@@ -3763,8 +4687,9 @@ impl<'a> Parser<'a> {
     /// If it is not possible to parse it, will return an option.
     pub fn parse_wildcard_additional_options(
         &mut self,
+        wildcard_span: Span,
     ) -> Result<WildcardAdditionalOptions, ParserError> {
-        let opt_exclude = if dialect_of!(self is GenericDialect | SnowflakeDialect) {
+        let opt_exclude = if dialect_of!(self is GenericDialect | DuckDbDialect | SnowflakeDialect) {
             self.parse_optional_select_item_exclude()?
         } else {
             None
@@ -3780,7 +4705,7 @@ impl<'a> Parser<'a> {
             None
         };
 
-        let opt_replace = if dialect_of!(self is GenericDialect | BigQueryDialect) {
+        let opt_replace = if dialect_of!(self is GenericDialect | DuckDbDialect | BigQueryDialect) {
             self.parse_optional_select_item_replace()?
         } else {
             None
@@ -3791,6 +4716,7 @@ impl<'a> Parser<'a> {
             opt_except,
             opt_rename,
             opt_replace,
+            wildcard_span: AttachedSpan(wildcard_span),
         })
     }
 
@@ -3992,17 +4918,37 @@ impl<'a> Parser<'a> {
     pub fn index(&self) -> usize {
         self.index
     }
+
+    /// The tokens this parser was given, whitespace included, once it's
+    /// done with them. A `>>` that closed two nested types, as in
+    /// `ARRAY<STRUCT<a INT64>>`, comes back as two `>` tokens.
+    pub fn into_tokens(self) -> Vec<TokenWithLocation<'a>> {
+        self.tokens
+    }
 }
 
-impl Word {
-    pub fn to_ident(&self) -> Ident {
+impl Word<'_> {
+    pub fn to_ident(&self, span: Span) -> Ident {
         Ident {
-            value: self.value.clone(),
+            value: Symbol::intern(&self.value),
             quote_style: self.quote_style,
+            span,
         }
     }
 }
 
+/// Strip the whitespace from both ends of tokens kept from a Jinja tag
+fn trim_whitespace(tokens: &mut Vec<Token<'static>>) {
+    while matches!(tokens.last(), Some(Token::Whitespace(_))) {
+        tokens.pop();
+    }
+    let leading_whitespace = tokens
+        .iter()
+        .take_while(|token| matches!(token, Token::Whitespace(_)))
+        .count();
+    tokens.drain(..leading_whitespace);
+}
+
 #[cfg(test)]
 mod tests {
     use crate::test_utils::{all_dialects, TestedDialects};
@@ -4409,6 +5355,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_current_location_after_error() {
+        let sql = "SELECT 1\nFROM foo\nWHERE a IS b";
+        let mut parser = Parser::new(&GenericDialect).try_with_sql(sql).unwrap();
+        assert!(parser.parse_statements().is_err());
+        assert_eq!(parser.current_location(), Location { line: 3, column: 9 });
+    }
+
+    #[test]
+    fn test_parse_statements_recovering() {
+        let sql = "SELECT 1;\nSELECT a IS b FROM foo;\nSELECT 2 3;\nSELECT 4";
+        let mut parser = Parser::new(&GenericDialect).try_with_sql(sql).unwrap();
+        let (statements, errors) = parser.parse_statements_recovering();
+
+        let statements: Vec<String> = statements.iter().map(|s| s.to_string()).collect();
+        assert_eq!(statements, vec!["SELECT 1", "SELECT 2", "SELECT 4"]);
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "sql parser error: Expected [NOT] NULL or TRUE|FALSE or [NOT] DISTINCT FROM after IS, found: b at line 2, column 10",
+                "sql parser error: Expected end of statement, found: 3 at line 3, column 8",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_statements_recovering_after_the_semicolon() {
+        // Both errors are found on the `;`, which has been read by then
+        let sql = "SELECT (;\nSELECT id FROM foo;\nSELECT FROM FROM;\nSELECT 2";
+        let mut parser = Parser::new(&GenericDialect).try_with_sql(sql).unwrap();
+        let (statements, errors) = parser.parse_statements_recovering();
+
+        let statements: Vec<String> = statements.iter().map(|s| s.to_string()).collect();
+        assert_eq!(statements, vec!["SELECT id FROM foo", "SELECT 2"]);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_statements_recovering_stops_at_limits() {
+        let sql = "SELECT 1 +;\nSELECT 2";
+        let mut parser = Parser::new(&GenericDialect)
+            .with_time_limit(Duration::ZERO)
+            .try_with_sql(sql)
+            .unwrap();
+        let (statements, errors) = parser.parse_statements_recovering();
+        assert!(statements.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].error, ParserError::ResourceLimitExceeded(_)));
+    }
+
+    #[test]
+    fn test_parser_error_suggestions() {
+        assert_eq!(
+            Parser::parse_sql(&GenericDialect, "SELCT 1"),
+            Err(ParserError::ParserError(
+                "Expected an SQL statement, found: SELCT, did you mean 'SELECT'?".to_string()
+            ))
+        );
+        assert_eq!(
+            Parser::parse_sql(&GenericDialect, "SELECT 1 FROM {{ 'model' }}"),
+            Err(ParserError::ParserError(
+                "Expected a macro call after '{{', found: 'model'".to_string()
+            ))
+        );
+        assert_eq!(
+            Parser::parse_sql(&GenericDialect, "{{ confg(materialized='table') }} SELECT 1"),
+            Err(ParserError::ParserError(
+                "Expected config, found: confg, did you mean 'config'?".to_string()
+            ))
+        );
+    }
+
     #[test]
     fn parse_simple_jinja_ref() {
         let sql = "SELECT 1 FROM {{ ref('model') }}";
@@ -4423,7 +5442,9 @@ mod tests {
                 matches!(
                     &table_with_joins.relation,
                     TableFactor::DbtRef {
+                        package: None,
                         model_name,
+                        version: None,
                         alias: None
                     } if model_name.value == "model" && model_name.quote_style == Some('\'')
                 )
@@ -4466,4 +5487,80 @@ SELECT * FROM some_table;"#;
         );
 
     }
+
+    #[test]
+    fn test_dbt_config_list_parsing() {
+        let sql = "{{ config(tags=['nightly', 'finance']) }} SELECT 1";
+
+        let statements = Parser::parse_sql(&GenericDialect, sql).unwrap();
+        let Statement::Query(query) = &statements[0];
+
+        assert_eq!(
+            &DbtConfigValue::List(vec![
+                DbtConfigValue::String("nightly".to_string()),
+                DbtConfigValue::String("finance".to_string()),
+            ]),
+            query.config.as_ref().unwrap().values.get("tags").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_dbt_config_typed_and_nested_values() {
+        let sql = "{{ config(enabled=false, full_refresh=True, limit=-10, alias=none, \
+                   persist_docs={'relation': true, 'columns': {'nested': 1.5}}, \
+                   partitions=[['2020', '2021'], []]) }} SELECT 1";
+
+        let statements = Parser::parse_sql(&GenericDialect, sql).unwrap();
+        let Statement::Query(query) = &statements[0];
+        let values = &query.config.as_ref().unwrap().values;
+
+        assert_eq!(values["enabled"], DbtConfigValue::Boolean(false));
+        assert_eq!(values["full_refresh"], DbtConfigValue::Boolean(true));
+        assert_eq!(values["limit"], DbtConfigValue::Number("-10".to_string()));
+        assert_eq!(values["alias"], DbtConfigValue::Null);
+        assert_eq!(
+            values["persist_docs"],
+            DbtConfigValue::Dict(BTreeMap::from([
+                ("relation".to_string(), DbtConfigValue::Boolean(true)),
+                (
+                    "columns".to_string(),
+                    DbtConfigValue::Dict(BTreeMap::from([("nested".to_string(), DbtConfigValue::Number("1.5".to_string()))])),
+                ),
+            ]))
+        );
+        assert_eq!(values["persist_docs"].to_string(), "{columns: {nested: 1.5}, relation: true}");
+        assert_eq!(
+            values["partitions"],
+            DbtConfigValue::List(vec![
+                DbtConfigValue::List(vec![
+                    DbtConfigValue::String("2020".to_string()),
+                    DbtConfigValue::String("2021".to_string()),
+                ]),
+                DbtConfigValue::List(vec![]),
+            ])
+        );
+        assert_eq!(query.body.to_string(), "SELECT 1");
+    }
+
+    #[test]
+    fn test_dbt_config_jinja_values() {
+        let sql = "{{ config(enabled=var('run_heavy_models', false), schema=env_var('SCHEMA'), \
+                   full_refresh=target.name != 'prod', tags=['nightly', my_tags()]) }} SELECT 1";
+
+        let statements = Parser::parse_sql(&GenericDialect, sql).unwrap();
+        let Statement::Query(query) = &statements[0];
+        let values = &query.config.as_ref().unwrap().values;
+
+        assert_eq!(
+            values["enabled"],
+            DbtConfigValue::Jinja(Expr::DbtVar {
+                name: Ident::with_quote('\'', "run_heavy_models"),
+                default: Some(Box::new(Expr::Value(Value::Boolean(false)))),
+            })
+        );
+        assert!(matches!(&values["schema"], DbtConfigValue::Jinja(Expr::DbtEnvVar { .. })));
+        assert_eq!(values["full_refresh"].to_string(), "target.name <> 'prod'");
+        assert_eq!(values["tags"].to_string(), "[nightly, {{ my_tags() }}]");
+        assert_eq!(query.body.to_string(), "SELECT 1");
+    }
 }
\ No newline at end of file
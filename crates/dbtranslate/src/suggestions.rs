@@ -0,0 +1,82 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! "Did you mean" suggestions for misspelled keywords, functions and names
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// Case-insensitive Levenshtein distance between two strings
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().flat_map(char::to_lowercase).collect();
+    let b: Vec<char> = b.chars().flat_map(char::to_lowercase).collect();
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        core::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Returns the candidate closest to `word` when it is close enough to be a
+/// plausible typo, i.e. within a third of the word's length (at least one
+/// edit). Exact matches are not suggestions, so they return `None`.
+///
+/// Example:
+/// ```
+/// # use dbtranslate::suggestions::did_you_mean;
+/// let models = ["stg_orders", "stg_customers"];
+/// assert_eq!(did_you_mean("stg_order", models), Some("stg_orders"));
+/// assert_eq!(did_you_mean("payments", models), None);
+/// ```
+pub fn did_you_mean<'a, I>(word: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = (word.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(word, candidate), candidate))
+        .filter(|(distance, _)| *distance > 0 && *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("select", "SELECT"), 0);
+        assert_eq!(edit_distance("selct", "select"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "ref"), 3);
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        assert_eq!(did_you_mean("SELCT", ["SELECT", "WITH", "VALUES"]), Some("SELECT"));
+        assert_eq!(did_you_mean("sourc", ["ref", "source"]), Some("source"));
+        assert_eq!(did_you_mean("ref", ["ref", "source"]), None);
+        assert_eq!(did_you_mean("grant", ["ref", "source"]), None);
+    }
+}
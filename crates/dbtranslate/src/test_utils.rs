@@ -83,10 +83,10 @@ impl TestedDialects {
     /// that:
     ///
     /// 1. parsing `sql` results in the same [`Statement`] as parsing
-    /// `canonical`.
+    ///    `canonical`.
     ///
     /// 2. re-serializing the result of parsing `sql` produces the same
-    /// `canonical` sql string
+    ///    `canonical` sql string
     pub fn one_statement_parses_to(&self, sql: &str, canonical: &str) -> Statement {
         let mut statements = self.parse_sql_statements(sql).unwrap();
         assert_eq!(statements.len(), 1);
@@ -179,7 +179,7 @@ pub fn number(n: &'static str) -> Value {
     Value::Number(n.parse().unwrap(), false)
 }
 
-pub fn table_alias(name: impl Into<String>) -> Option<TableAlias> {
+pub fn table_alias(name: impl Into<Symbol>) -> Option<TableAlias> {
     Some(TableAlias {
         name: Ident::new(name),
         columns: vec![],
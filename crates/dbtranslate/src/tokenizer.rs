@@ -24,20 +24,18 @@ use alloc::{
     vec,
     vec::Vec,
 };
+#[cfg(not(feature = "std"))]
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
 use core::fmt;
 use core::iter::Peekable;
 use core::str::Chars;
 
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-
-#[cfg(feature = "visitor")]
-use sqlparser_derive::{Visit, VisitMut};
-
 use crate::ast::DollarQuotedString;
 use crate::dialect::{BigQueryDialect, GenericDialect, SnowflakeDialect};
 use crate::dialect::{Dialect};
-use crate::tokens::{Token, Location, TokenWithLocation, Whitespace, Word};
+use crate::tokens::{Token, Location, TokenWithLocation, Trivia, Whitespace, Word};
 
 /// Tokenizer error
 #[derive(Debug, PartialEq, Eq)]
@@ -45,6 +43,10 @@ pub struct TokenizerError {
     pub message: String,
     pub line: u64,
     pub col: u64,
+    /// Whether tokenizing stopped at one of the limits set with
+    /// [`Tokenizer::with_token_limit`] and
+    /// [`Tokenizer::with_statement_length_limit`] rather than on bad SQL
+    pub limit_exceeded: bool,
 }
 
 impl fmt::Display for TokenizerError {
@@ -61,16 +63,30 @@ impl fmt::Display for TokenizerError {
 impl std::error::Error for TokenizerError {}
 
 struct State<'a> {
+    source: &'a str,
     peekable: Peekable<Chars<'a>>,
+    /// The byte offset of the next char in `source`
+    offset: usize,
     pub line: u64,
     pub col: u64,
 }
 
 impl<'a> State<'a> {
+    pub fn new(source: &'a str) -> Self {
+        State {
+            source,
+            peekable: source.chars().peekable(),
+            offset: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
     pub fn next(&mut self) -> Option<char> {
         match self.peekable.next() {
             None => None,
             Some(s) => {
+                self.offset += s.len_utf8();
                 if s == '\n' {
                     self.line += 1;
                     self.col = 1;
@@ -92,26 +108,92 @@ impl<'a> State<'a> {
             column: self.col,
         }
     }
+
+    /// The text from the byte offset `start` up to the next char
+    pub fn slice_from(&self, start: usize) -> &'a str {
+        &self.source[start..self.offset]
+    }
+}
+
+/// Text borrowed from the SQL until an escape sequence means it no longer
+/// matches the source, when it's copied
+struct Unescaped<'a> {
+    source: &'a str,
+    start: usize,
+    owned: Option<String>,
+}
+
+impl<'a> Unescaped<'a> {
+    fn new(source: &'a str, start: usize) -> Self {
+        Unescaped {
+            source,
+            start,
+            owned: None,
+        }
+    }
+
+    /// Replaces the escape sequence from `from` to `to` with `ch`
+    fn escape(&mut self, from: usize, to: usize, ch: char) {
+        let owned = self.owned.get_or_insert_with(String::new);
+        owned.push_str(&self.source[self.start..from]);
+        owned.push(ch);
+        self.start = to;
+    }
+
+    /// The text up to the byte offset `end`
+    fn finish(self, end: usize) -> Cow<'a, str> {
+        match self.owned {
+            None => Cow::Borrowed(&self.source[self.start..end]),
+            Some(mut owned) => {
+                owned.push_str(&self.source[self.start..end]);
+                Cow::Owned(owned)
+            }
+        }
+    }
 }
 
 /// SQL Tokenizer
 pub struct Tokenizer<'a> {
     dialect: &'a dyn Dialect,
     query: &'a str,
+    /// The maximum number of tokens to read before giving up
+    token_limit: Option<usize>,
+    /// The maximum length of each statement, in bytes
+    statement_length_limit: Option<usize>,
 }
 
 impl<'a> Tokenizer<'a> {
     /// Create a new SQL tokenizer for the specified SQL statement
     pub fn new(dialect: &'a dyn Dialect, query: &'a str) -> Self {
-        Self { dialect, query }
+        Self {
+            dialect,
+            query,
+            token_limit: None,
+            statement_length_limit: None,
+        }
+    }
+
+    /// Stop with an error, rather than reading the rest of the SQL, once
+    /// there are more than `token_limit` tokens
+    pub fn with_token_limit(mut self, token_limit: usize) -> Self {
+        self.token_limit = Some(token_limit);
+        self
+    }
+
+    /// Stop with an error once a statement is longer than
+    /// `statement_length_limit` bytes. Statements are split on the `;`
+    /// tokens between them, and the whitespace and comments around them
+    /// don't count.
+    pub fn with_statement_length_limit(mut self, statement_length_limit: usize) -> Self {
+        self.statement_length_limit = Some(statement_length_limit);
+        self
     }
 
     /// Tokenize the statement and produce a vector of tokens
-    pub fn tokenize(&mut self) -> Result<Vec<Token>, TokenizerError> {
+    pub fn tokenize(&mut self) -> Result<Vec<Token<'a>>, TokenizerError> {
         let twl = self.tokenize_with_location()?;
 
-        let mut tokens: Vec<Token> = vec![];
-        tokens.reserve(twl.len());
+        let mut tokens: Vec<Token<'a>> = Vec::with_capacity(twl.len());
         for token_with_location in twl {
             tokens.push(token_with_location.token);
         }
@@ -119,29 +201,62 @@ impl<'a> Tokenizer<'a> {
     }
 
     /// Tokenize the statement and produce a vector of tokens with location information
-    pub fn tokenize_with_location(&mut self) -> Result<Vec<TokenWithLocation>, TokenizerError> {
-        let mut state = State {
-            peekable: self.query.chars().peekable(),
-            line: 1,
-            col: 1,
-        };
+    pub fn tokenize_with_location(&mut self) -> Result<Vec<TokenWithLocation<'a>>, TokenizerError> {
+        let mut state = State::new(self.query);
 
-        let mut tokens: Vec<TokenWithLocation> = vec![];
+        let mut tokens: Vec<TokenWithLocation<'a>> = vec![];
+        // SQL shorter than the limit can't have a statement longer than it
+        let statement_length_limit = self.statement_length_limit.filter(|limit| self.query.len() > *limit);
+        // The byte offset and location where the current statement starts
+        let mut statement_start: Option<(usize, Location)> = None;
 
         let mut location = state.location();
+        let mut offset = state.offset;
         while let Some(token) = self.next_token(&mut state)? {
+            if let Some(limit) = self.token_limit {
+                if tokens.len() == limit {
+                    return Err(limit_error(
+                        &location,
+                        format!("token count exceeds the limit of {limit}"),
+                    ));
+                }
+            }
+            if let Some(limit) = statement_length_limit {
+                match &token {
+                    Token::SemiColon => statement_start = None,
+                    Token::Whitespace(_) => {}
+                    _ => {
+                        let (start, start_location) =
+                            statement_start.get_or_insert_with(|| (offset, location.clone()));
+                        if state.offset - *start > limit {
+                            return Err(limit_error(
+                                start_location,
+                                format!("statement length exceeds the limit of {limit} bytes"),
+                            ));
+                        }
+                    }
+                }
+            }
+
             tokens.push(TokenWithLocation {
                 token,
                 location: location.clone(),
             });
 
             location = state.location();
+            offset = state.offset;
         }
         Ok(tokens)
     }
 
+    /// Tokenize the statement into the tokens the parser reads, with the
+    /// whitespace and comments between them kept aside as [Trivia]
+    pub fn tokenize_with_trivia(&mut self) -> Result<(Vec<TokenWithLocation<'a>>, Trivia<'a>), TokenizerError> {
+        Ok(Trivia::split(self.tokenize_with_location()?))
+    }
+
     /// Get the next token or return None
-    fn next_token(&self, chars: &mut State) -> Result<Option<Token>, TokenizerError> {
+    fn next_token(&self, chars: &mut State<'a>) -> Result<Option<Token<'a>>, TokenizerError> {
         //println!("next_token: {:?}", chars.peek());
         match chars.peek() {
             Some(&ch) => match ch {
@@ -171,7 +286,7 @@ impl<'a> Tokenizer<'a> {
                         _ => {
                             // regular identifier starting with an "b" or "B"
                             let s = self.tokenize_word(b, chars);
-                            Ok(Some(Token::make_word(&s, None)))
+                            Ok(Some(Token::make_word(s, None)))
                         }
                     }
                 }
@@ -190,7 +305,7 @@ impl<'a> Tokenizer<'a> {
                         _ => {
                             // regular identifier starting with an "r" or "R"
                             let s = self.tokenize_word(b, chars);
-                            Ok(Some(Token::make_word(&s, None)))
+                            Ok(Some(Token::make_word(s, None)))
                         }
                     }
                 }
@@ -206,7 +321,7 @@ impl<'a> Tokenizer<'a> {
                         _ => {
                             // regular identifier starting with an "N"
                             let s = self.tokenize_word(n, chars);
-                            Ok(Some(Token::make_word(&s, None)))
+                            Ok(Some(Token::make_word(s, None)))
                         }
                     }
                 }
@@ -223,7 +338,7 @@ impl<'a> Tokenizer<'a> {
                         _ => {
                             // regular identifier starting with an "E" or "e"
                             let s = self.tokenize_word(x, chars);
-                            Ok(Some(Token::make_word(&s, None)))
+                            Ok(Some(Token::make_word(s, None)))
                         }
                     }
                 }
@@ -240,7 +355,7 @@ impl<'a> Tokenizer<'a> {
                         _ => {
                             // regular identifier starting with an "X"
                             let s = self.tokenize_word(x, chars);
-                            Ok(Some(Token::make_word(&s, None)))
+                            Ok(Some(Token::make_word(s, None)))
                         }
                     }
                 }
@@ -251,20 +366,12 @@ impl<'a> Tokenizer<'a> {
 
                     // TODO: implement parsing of exponent here
                     if word.chars().all(|x| x.is_ascii_digit() || x == '.') {
-                        let mut inner_state = State {
-                            peekable: word.chars().peekable(),
-                            line: 0,
-                            col: 0,
-                        };
-                        let mut s = peeking_take_while(&mut inner_state, |ch| {
-                            matches!(ch, '0'..='9' | '.')
-                        });
-                        let s2 = peeking_take_while(chars, |ch| matches!(ch, '0'..='9' | '.'));
-                        s += s2.as_str();
-                        return Ok(Some(Token::Number(s, false)));
+                        let start = chars.offset - word.len();
+                        peeking_take_while(chars, |ch| matches!(ch, '0'..='9' | '.'));
+                        return Ok(Some(Token::Number(chars.slice_from(start).into(), false)));
                     }
 
-                    Ok(Some(Token::make_word(&word, None)))
+                    Ok(Some(Token::make_word(word, None)))
                 }
                 // single quoted string
                 '\'' => {
@@ -293,7 +400,7 @@ impl<'a> Tokenizer<'a> {
                     let (s, last_char) = parse_quoted_ident(chars, quote_end);
 
                     if last_char == Some(quote_end) {
-                        Ok(Some(Token::make_word(&s, Some(quote_start))))
+                        Ok(Some(Token::make_word(s, Some(quote_start))))
                     } else {
                         self.tokenizer_error(
                             error_loc,
@@ -303,59 +410,55 @@ impl<'a> Tokenizer<'a> {
                 }
                 // numbers and period
                 '0'..='9' | '.' => {
-                    let mut s = peeking_take_while(chars, |ch| ch.is_ascii_digit());
+                    let start = chars.offset;
+                    let s = peeking_take_while(chars, |ch| ch.is_ascii_digit());
 
                     // match binary literal that starts with 0x
                     if s == "0" && chars.peek() == Some(&'x') {
                         chars.next();
                         let s2 = peeking_take_while(
                             chars,
-                            |ch| matches!(ch, '0'..='9' | 'A'..='F' | 'a'..='f'),
+                            |ch| ch.is_ascii_hexdigit(),
                         );
-                        return Ok(Some(Token::HexStringLiteral(s2)));
+                        return Ok(Some(Token::HexStringLiteral(s2.into())));
                     }
 
                     // match one period
                     if let Some('.') = chars.peek() {
-                        s.push('.');
                         chars.next();
                     }
-                    s += &peeking_take_while(chars, |ch| ch.is_ascii_digit());
+                    peeking_take_while(chars, |ch| ch.is_ascii_digit());
 
                     // No number -> Token::Period
-                    if s == "." {
+                    if chars.slice_from(start) == "." {
                         return Ok(Some(Token::Period));
                     }
 
                     // Parse exponent as number
                     if chars.peek() == Some(&'e') || chars.peek() == Some(&'E') {
                         let mut char_clone = chars.peekable.clone();
-                        let mut exponent_part = String::new();
-                        exponent_part.push(char_clone.next().unwrap());
+                        char_clone.next();
+                        let mut exponent_length = 1;
 
                         // Optional sign
-                        match char_clone.peek() {
-                            Some(&c) if matches!(c, '+' | '-') => {
-                                exponent_part.push(c);
-                                char_clone.next();
-                            }
-                            _ => (),
+                        if let Some('+' | '-') = char_clone.peek() {
+                            exponent_length += 1;
+                            char_clone.next();
                         }
 
                         match char_clone.peek() {
                             // Definitely an exponent, get original iterator up to speed and use it
                             Some(&c) if c.is_ascii_digit() => {
-                                for _ in 0..exponent_part.len() {
+                                for _ in 0..exponent_length {
                                     chars.next();
                                 }
-                                exponent_part +=
-                                    &peeking_take_while(chars, |ch| ch.is_ascii_digit());
-                                s += exponent_part.as_str();
+                                peeking_take_while(chars, |ch| ch.is_ascii_digit());
                             }
                             // Not an exponent, discard the work done
                             _ => (),
                         }
                     }
+                    let s = chars.slice_from(start);
 
                     let long = if chars.peek() == Some(&'L') {
                         chars.next();
@@ -363,13 +466,21 @@ impl<'a> Tokenizer<'a> {
                     } else {
                         false
                     };
-                    Ok(Some(Token::Number(s, long)))
+                    Ok(Some(Token::Number(s.into(), long)))
                 }
                 // punctuation
                 '(' => self.consume_and_return(chars, Token::LParen),
                 ')' => self.consume_and_return(chars, Token::RParen),
                 ',' => self.consume_and_return(chars, Token::Comma),
                 // operators
+                // Jinja's whitespace control, `{{-`, `{%-`, `-}}` and `-%}`
+                '-' if chars.source[..chars.offset].ends_with("{{")
+                    || chars.source[..chars.offset].ends_with("{%")
+                    || chars.source[chars.offset + 1..].starts_with("}}")
+                    || chars.source[chars.offset + 1..].starts_with("%}") =>
+                {
+                    self.consume_and_return(chars, Token::Whitespace(Whitespace::JinjaTrim))
+                }
                 '-' => {
                     chars.next(); // consume the '-'
                     match chars.peek() {
@@ -377,7 +488,7 @@ impl<'a> Tokenizer<'a> {
                             chars.next(); // consume the second '-', starting a single-line comment
                             let comment = self.tokenize_single_line_comment(chars);
                             Ok(Some(Token::Whitespace(Whitespace::SingleLineComment {
-                                prefix: "--".to_owned(),
+                                prefix: "--".into(),
                                 comment,
                             })))
                         }
@@ -406,7 +517,7 @@ impl<'a> Tokenizer<'a> {
                             chars.next(); // consume the second '/', starting a snowflake single-line comment
                             let comment = self.tokenize_single_line_comment(chars);
                             Ok(Some(Token::Whitespace(Whitespace::SingleLineComment {
-                                prefix: "//".to_owned(),
+                                prefix: "//".into(),
                                 comment,
                             })))
                         }
@@ -509,6 +620,10 @@ impl<'a> Tokenizer<'a> {
                         Some('{') => {
                             self.consume_and_return(chars, Token::DoubleLBrace)
                         }
+                        Some('#') => {
+                            chars.next(); // consume the '#', starting a Jinja comment
+                            self.tokenize_jinja_comment(chars)
+                        }
                         _ => Ok(Some(Token::LBrace)),
                     }
                 }
@@ -525,7 +640,7 @@ impl<'a> Tokenizer<'a> {
                     chars.next(); // consume the '#', starting a snowflake single-line comment
                     let comment = self.tokenize_single_line_comment(chars);
                     Ok(Some(Token::Whitespace(Whitespace::SingleLineComment {
-                        prefix: "#".to_owned(),
+                        prefix: "#".into(),
                         comment,
                     })))
                 }
@@ -563,9 +678,10 @@ impl<'a> Tokenizer<'a> {
                     }
                 }
                 '?' => {
+                    let start = chars.offset;
                     chars.next();
-                    let s = peeking_take_while(chars, |ch| ch.is_numeric());
-                    Ok(Some(Token::Placeholder(String::from("?") + &s)))
+                    peeking_take_while(chars, |ch| ch.is_numeric());
+                    Ok(Some(Token::Placeholder(chars.slice_from(start).into())))
                 }
                 '$' => Ok(Some(self.tokenize_dollar_preceded_value(chars)?)),
 
@@ -580,9 +696,9 @@ impl<'a> Tokenizer<'a> {
     }
 
     /// Tokenize dollar preceded value (i.e: a string/placeholder)
-    fn tokenize_dollar_preceded_value(&self, chars: &mut State) -> Result<Token, TokenizerError> {
+    fn tokenize_dollar_preceded_value(&self, chars: &mut State<'a>) -> Result<Token<'a>, TokenizerError> {
         let mut s = String::new();
-        let mut value = String::new();
+        let start = chars.offset;
 
         chars.next();
 
@@ -610,27 +726,27 @@ impl<'a> Tokenizer<'a> {
                 chars.next();
             }
 
-            return if chars.peek().is_none() && !is_terminated {
+            if chars.peek().is_none() && !is_terminated {
                 self.tokenizer_error(chars.location(), "Unterminated dollar-quoted string")
             } else {
                 Ok(Token::DollarQuotedString(DollarQuotedString {
                     value: s,
                     tag: None,
                 }))
-            };
+            }
         } else {
-            value.push_str(&peeking_take_while(chars, |ch| {
+            let value = peeking_take_while(chars, |ch| {
                 ch.is_alphanumeric() || ch == '_'
-            }));
+            });
 
             if let Some('$') = chars.peek() {
                 chars.next();
-                s.push_str(&peeking_take_while(chars, |ch| ch != '$'));
+                s.push_str(peeking_take_while(chars, |ch| ch != '$'));
 
                 match chars.peek() {
                     Some('$') => {
                         chars.next();
-                        for (_, c) in value.chars().enumerate() {
+                        for c in value.chars() {
                             let next_char = chars.next();
                             if Some(c) != next_char {
                                 return self.tokenizer_error(
@@ -659,14 +775,14 @@ impl<'a> Tokenizer<'a> {
                     }
                 }
             } else {
-                return Ok(Token::Placeholder(String::from("$") + &value));
+                return Ok(Token::Placeholder(chars.slice_from(start).into()));
             }
-        }
 
-        Ok(Token::DollarQuotedString(DollarQuotedString {
-            value: s,
-            tag: if value.is_empty() { None } else { Some(value) },
-        }))
+            Ok(Token::DollarQuotedString(DollarQuotedString {
+                value: s,
+                tag: if value.is_empty() { None } else { Some(value.to_string()) },
+            }))
+        }
     }
 
     fn tokenizer_error<R>(
@@ -678,87 +794,61 @@ impl<'a> Tokenizer<'a> {
             message: message.into(),
             col: loc.column,
             line: loc.line,
+            limit_exceeded: false,
         })
     }
 
     // Consume characters until newline
-    fn tokenize_single_line_comment(&self, chars: &mut State) -> String {
-        let mut comment = peeking_take_while(chars, |ch| ch != '\n');
+    fn tokenize_single_line_comment(&self, chars: &mut State<'a>) -> Cow<'a, str> {
+        let start = chars.offset;
+        peeking_take_while(chars, |ch| ch != '\n');
         if let Some(ch) = chars.next() {
             assert_eq!(ch, '\n');
-            comment.push(ch);
         }
-        comment
+        chars.slice_from(start).into()
     }
 
     /// Tokenize an identifier or keyword, after the first char is already consumed.
-    fn tokenize_word(&self, first_char: char, chars: &mut State) -> String {
-        let mut s = first_char.to_string();
-        s.push_str(&peeking_take_while(chars, |ch| {
-            self.dialect.is_identifier_part(ch)
-        }));
-        s
+    fn tokenize_word(&self, first_char: char, chars: &mut State<'a>) -> &'a str {
+        let start = chars.offset - first_char.len_utf8();
+        peeking_take_while(chars, |ch| self.dialect.is_identifier_part(ch));
+        chars.slice_from(start)
     }
 
     /// Read a single quoted string, starting with the opening quote.
     fn tokenize_escaped_single_quoted_string(
         &self,
         starting_loc: Location,
-        chars: &mut State,
-    ) -> Result<String, TokenizerError> {
-        let mut s = String::new();
-
-        // This case is a bit tricky
-
+        chars: &mut State<'a>,
+    ) -> Result<Cow<'a, str>, TokenizerError> {
         chars.next(); // consume the opening quote
+        let mut s = Unescaped::new(chars.source, chars.offset);
 
-        // slash escaping
-        let mut is_escaped = false;
-        while let Some(&ch) = chars.peek() {
-            macro_rules! escape_control_character {
-                ($ESCAPED:expr) => {{
-                    if is_escaped {
-                        s.push($ESCAPED);
-                        is_escaped = false;
-                    } else {
-                        s.push(ch);
-                    }
-
-                    chars.next();
-                }};
-            }
-
+        // slash escaping, and a doubled quote for a quote
+        loop {
+            let from = chars.offset;
+            let Some(ch) = chars.next() else {
+                break;
+            };
             match ch {
-                '\'' => {
-                    chars.next(); // consume
-                    if is_escaped {
-                        s.push(ch);
-                        is_escaped = false;
-                    } else if chars.peek().map(|c| *c == '\'').unwrap_or(false) {
-                        s.push(ch);
-                        chars.next();
-                    } else {
-                        return Ok(s);
-                    }
-                }
-                '\\' => {
-                    if is_escaped {
-                        s.push('\\');
-                        is_escaped = false;
-                    } else {
-                        is_escaped = true;
-                    }
-
+                '\'' if chars.peek() == Some(&'\'') => {
                     chars.next();
+                    s.escape(from, chars.offset, '\'');
                 }
-                'r' => escape_control_character!('\r'),
-                'n' => escape_control_character!('\n'),
-                't' => escape_control_character!('\t'),
-                _ => {
-                    is_escaped = false;
-                    chars.next(); // consume
-                    s.push(ch);
-                }
+                '\'' => return Ok(s.finish(from)),
+                '\\' => match chars.next() {
+                    Some(escaped) => {
+                        let unescaped = match escaped {
+                            'r' => '\r',
+                            'n' => '\n',
+                            't' => '\t',
+                            other => other,
+                        };
+                        s.escape(from, chars.offset, unescaped);
+                    }
+                    None => break,
+                },
+                _ => {}
             }
         }
         self.tokenizer_error(starting_loc, "Unterminated encoded string literal")
@@ -767,37 +857,24 @@ impl<'a> Tokenizer<'a> {
     /// Read a single quoted string, starting with the opening quote.
     fn tokenize_quoted_string(
         &self,
-        chars: &mut State,
+        chars: &mut State<'a>,
         quote_style: char,
-    ) -> Result<String, TokenizerError> {
-        let mut s = String::new();
+    ) -> Result<Cow<'a, str>, TokenizerError> {
         let error_loc = chars.location();
 
         chars.next(); // consume the opening quote
+        let mut s = Unescaped::new(chars.source, chars.offset);
 
-        // slash escaping is specific to MySQL dialect
-        let mut is_escaped = false;
         while let Some(&ch) = chars.peek() {
-            match ch {
-                char if char == quote_style => {
-                    chars.next(); // consume
-                    if is_escaped {
-                        s.push(ch);
-                        is_escaped = false;
-                    } else if chars.peek().map(|c| *c == quote_style).unwrap_or(false) {
-                        s.push(ch);
-                        chars.next();
-                    } else {
-                        return Ok(s);
-                    }
-                }
-                '\\' => {
-                    s.push(ch);
+            let from = chars.offset;
+            chars.next(); // consume
+            if ch == quote_style {
+                // a doubled quote is a quote in the string
+                if chars.peek() == Some(&quote_style) {
                     chars.next();
-                }
-                _ => {
-                    chars.next(); // consume
-                    s.push(ch);
+                    s.escape(from, chars.offset, ch);
+                } else {
+                    return Ok(s.finish(from));
                 }
             }
         }
@@ -806,9 +883,9 @@ impl<'a> Tokenizer<'a> {
 
     fn tokenize_multiline_comment(
         &self,
-        chars: &mut State,
-    ) -> Result<Option<Token>, TokenizerError> {
-        let mut s = String::new();
+        chars: &mut State<'a>,
+    ) -> Result<Option<Token<'a>>, TokenizerError> {
+        let start = chars.offset;
         let mut nested = 1;
         let mut last_ch = ' ';
 
@@ -820,11 +897,11 @@ impl<'a> Tokenizer<'a> {
                     } else if last_ch == '*' && ch == '/' {
                         nested -= 1;
                         if nested == 0 {
-                            s.pop();
-                            break Ok(Some(Token::Whitespace(Whitespace::MultiLineComment(s))));
+                            // without the closing `*/`
+                            let s = &chars.source[start..chars.offset - 2];
+                            break Ok(Some(Token::Whitespace(Whitespace::MultiLineComment(s.into()))));
                         }
                     }
-                    s.push(ch);
                     last_ch = ch;
                 }
                 None => {
@@ -837,50 +914,77 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    /// Tokenize a Jinja comment, after its `{#`. Unlike `/* */`, Jinja
+    /// comments don't nest.
+    fn tokenize_jinja_comment(&self, chars: &mut State<'a>) -> Result<Option<Token<'a>>, TokenizerError> {
+        let start = chars.offset;
+        let mut last_ch = ' ';
+        loop {
+            match chars.next() {
+                // without the closing `#}`
+                Some('}') if last_ch == '#' => {
+                    let s = &chars.source[start..chars.offset - 2];
+                    break Ok(Some(Token::Whitespace(Whitespace::JinjaComment(s.into()))));
+                }
+                Some(ch) => last_ch = ch,
+                None => break self.tokenizer_error(chars.location(), "Unexpected EOF while in a Jinja comment"),
+            }
+        }
+    }
+
     #[allow(clippy::unnecessary_wraps)]
     fn consume_and_return(
         &self,
-        chars: &mut State,
-        t: Token,
-    ) -> Result<Option<Token>, TokenizerError> {
+        chars: &mut State<'a>,
+        t: Token<'a>,
+    ) -> Result<Option<Token<'a>>, TokenizerError> {
         chars.next();
         Ok(Some(t))
     }
 }
 
+/// The error for SQL over one of the tokenizer's limits, at `location`
+fn limit_error(location: &Location, message: String) -> TokenizerError {
+    TokenizerError {
+        message,
+        line: location.line,
+        col: location.column,
+        limit_exceeded: true,
+    }
+}
+
 /// Read from `chars` until `predicate` returns `false` or EOF is hit.
-/// Return the characters read as String, and keep the first non-matching
-/// char available as `chars.next()`.
-fn peeking_take_while(chars: &mut State, mut predicate: impl FnMut(char) -> bool) -> String {
-    let mut s = String::new();
+/// Return the characters read as a slice of the SQL, and keep the first
+/// non-matching char available as `chars.next()`.
+fn peeking_take_while<'a>(chars: &mut State<'a>, mut predicate: impl FnMut(char) -> bool) -> &'a str {
+    let start = chars.offset;
     while let Some(&ch) = chars.peek() {
         if predicate(ch) {
             chars.next(); // consume
-            s.push(ch);
         } else {
             break;
         }
     }
-    s
+    chars.slice_from(start)
 }
 
-fn parse_quoted_ident(chars: &mut State, quote_end: char) -> (String, Option<char>) {
-    let mut last_char = None;
-    let mut s = String::new();
-    while let Some(ch) = chars.next() {
-        if ch == quote_end {
-            if chars.peek() == Some(&quote_end) {
-                chars.next();
-                s.push(ch);
-            } else {
-                last_char = Some(quote_end);
-                break;
+fn parse_quoted_ident<'a>(chars: &mut State<'a>, quote_end: char) -> (Cow<'a, str>, Option<char>) {
+    let mut s = Unescaped::new(chars.source, chars.offset);
+    loop {
+        let from = chars.offset;
+        match chars.next() {
+            Some(ch) if ch == quote_end => {
+                if chars.peek() == Some(&quote_end) {
+                    chars.next();
+                    s.escape(from, chars.offset, ch);
+                } else {
+                    return (s.finish(from), Some(quote_end));
+                }
             }
-        } else {
-            s.push(ch);
+            Some(_) => {}
+            None => return (s.finish(chars.offset), None),
         }
     }
-    (s, last_char)
 }
 
 #[cfg(test)]
@@ -894,6 +998,7 @@ mod tests {
             message: "test".into(),
             line: 1,
             col: 1,
+            limit_exceeded: false,
         };
         #[cfg(feature = "std")]
         {
@@ -913,7 +1018,7 @@ mod tests {
         let expected = vec![
             Token::make_keyword("SELECT"),
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("1"), false),
+            Token::Number("1".into(), false),
         ];
 
         compare(expected, tokens);
@@ -929,7 +1034,7 @@ mod tests {
         let expected = vec![
             Token::make_keyword("SELECT"),
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from(".1"), false),
+            Token::Number(".1".into(), false),
         ];
 
         compare(expected, tokens);
@@ -971,6 +1076,89 @@ mod tests {
         compare(expected, tokens);
     }
 
+    #[test]
+    fn tokenize_jinja_whitespace_control() {
+        let sql = String::from("{%- if x -%}{{- y -}}{% endif %} SELECT 1 - 2");
+        let dialect = GenericDialect {};
+        let mut tokenizer = Tokenizer::new(&dialect, &sql);
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let expected = vec![
+            Token::LJinjaIterator,
+            Token::Whitespace(Whitespace::JinjaTrim),
+            Token::Whitespace(Whitespace::Space),
+            Token::make_word("if", None),
+            Token::Whitespace(Whitespace::Space),
+            Token::make_word("x", None),
+            Token::Whitespace(Whitespace::Space),
+            Token::Whitespace(Whitespace::JinjaTrim),
+            Token::RJinjaIterator,
+            Token::DoubleLBrace,
+            Token::Whitespace(Whitespace::JinjaTrim),
+            Token::Whitespace(Whitespace::Space),
+            Token::make_word("y", None),
+            Token::Whitespace(Whitespace::Space),
+            Token::Whitespace(Whitespace::JinjaTrim),
+            Token::DoubleRBrace,
+            Token::LJinjaIterator,
+            Token::Whitespace(Whitespace::Space),
+            Token::make_word("endif", None),
+            Token::Whitespace(Whitespace::Space),
+            Token::RJinjaIterator,
+            Token::Whitespace(Whitespace::Space),
+            Token::make_keyword("SELECT"),
+            Token::Whitespace(Whitespace::Space),
+            Token::Number("1".into(), false),
+            Token::Whitespace(Whitespace::Space),
+            Token::Minus,
+            Token::Whitespace(Whitespace::Space),
+            Token::Number("2".into(), false),
+        ];
+
+        assert_eq!(tokens.iter().map(|token| token.to_string()).collect::<String>(), sql);
+        compare(expected, tokens);
+    }
+
+    #[test]
+    fn tokenize_stops_at_the_limits() {
+        // Stopping early, the unterminated string at the end is never read
+        let sql = "SELECT 1;\nSELECT a, b FROM t; 'unterminated";
+        let dialect = GenericDialect {};
+
+        let err = Tokenizer::new(&dialect, sql).with_token_limit(3).tokenize().unwrap_err();
+        assert_eq!(err.message, "token count exceeds the limit of 3");
+        assert_eq!((err.line, err.col, err.limit_exceeded), (1, 9, true));
+
+        let err = Tokenizer::new(&dialect, sql).with_statement_length_limit(10).tokenize().unwrap_err();
+        assert_eq!(err.message, "statement length exceeds the limit of 10 bytes");
+        assert_eq!((err.line, err.col, err.limit_exceeded), (2, 1, true));
+
+        let err = Tokenizer::new(&dialect, sql).with_statement_length_limit(20).tokenize().unwrap_err();
+        assert!(!err.limit_exceeded);
+    }
+
+    #[test]
+    fn tokenize_jinja_comment() {
+        let sql = String::from("SELECT {# a {{ ref('b') }} -- c #}1{#- d -#}");
+        let dialect = GenericDialect {};
+        let mut tokenizer = Tokenizer::new(&dialect, &sql);
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let expected = vec![
+            Token::make_keyword("SELECT"),
+            Token::Whitespace(Whitespace::Space),
+            Token::Whitespace(Whitespace::JinjaComment(" a {{ ref('b') }} -- c ".into())),
+            Token::Number("1".into(), false),
+            Token::Whitespace(Whitespace::JinjaComment("- d -".into())),
+        ];
+
+        assert_eq!(tokens.iter().map(|token| token.to_string()).collect::<String>(), sql);
+        compare(expected, tokens);
+
+        let mut tokenizer = Tokenizer::new(&dialect, "SELECT 1 {# unclosed");
+        assert!(tokenizer.tokenize().is_err());
+    }
+
     #[test]
     fn tokenize_long_jinja_query() {
         let sql = String::from("SELECT {% if type in type_list %} {{ type }} {% endif %}");
@@ -1019,26 +1207,26 @@ mod tests {
         let expected = vec![
             Token::make_keyword("SELECT"),
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("1e10"), false),
+            Token::Number("1e10".into(), false),
             Token::Comma,
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("1e-10"), false),
+            Token::Number("1e-10".into(), false),
             Token::Comma,
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("1e+10"), false),
+            Token::Number("1e+10".into(), false),
             Token::Comma,
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("1"), false),
+            Token::Number("1".into(), false),
             Token::make_word("ea", None),
             Token::Comma,
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("1e-10"), false),
+            Token::Number("1e-10".into(), false),
             Token::make_word("a", None),
             Token::Comma,
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("1e-10"), false),
+            Token::Number("1e-10".into(), false),
             Token::Minus,
-            Token::Number(String::from("10"), false),
+            Token::Number("10".into(), false),
         ];
 
         compare(expected, tokens);
@@ -1056,7 +1244,7 @@ mod tests {
             Token::Whitespace(Whitespace::Space),
             Token::make_word("sqrt", None),
             Token::LParen,
-            Token::Number(String::from("1"), false),
+            Token::Number("1".into(), false),
             Token::RParen,
         ];
 
@@ -1073,11 +1261,11 @@ mod tests {
         let expected = vec![
             Token::make_keyword("SELECT"),
             Token::Whitespace(Whitespace::Space),
-            Token::SingleQuotedString(String::from("a")),
+            Token::SingleQuotedString("a".into()),
             Token::Whitespace(Whitespace::Space),
             Token::StringConcat,
             Token::Whitespace(Whitespace::Space),
-            Token::SingleQuotedString(String::from("b")),
+            Token::SingleQuotedString("b".into()),
         ];
 
         compare(expected, tokens);
@@ -1168,11 +1356,11 @@ mod tests {
             Token::Whitespace(Whitespace::Space),
             Token::Eq,
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("1"), false),
+            Token::Number("1".into(), false),
             Token::Whitespace(Whitespace::Space),
             Token::make_keyword("LIMIT"),
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("5"), false),
+            Token::Number("5".into(), false),
         ];
 
         compare(expected, tokens);
@@ -1202,7 +1390,7 @@ mod tests {
             Token::Whitespace(Whitespace::Space),
             Token::Eq,
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("1"), false),
+            Token::Number("1".into(), false),
         ];
 
         compare(expected, tokens);
@@ -1234,7 +1422,7 @@ mod tests {
             Token::Whitespace(Whitespace::Space),
             Token::Eq,
             Token::Whitespace(Whitespace::Space),
-            Token::Number(String::from("1"), false),
+            Token::Number("1".into(), false),
         ];
 
         compare(expected, tokens);
@@ -1262,7 +1450,7 @@ mod tests {
             Token::Whitespace(Whitespace::Space),
             Token::Neq,
             Token::Whitespace(Whitespace::Space),
-            Token::SingleQuotedString(String::from("Not Provided")),
+            Token::SingleQuotedString("Not Provided".into()),
         ];
 
         compare(expected, tokens);
@@ -1291,7 +1479,7 @@ mod tests {
         let dialect = GenericDialect {};
         let mut tokenizer = Tokenizer::new(&dialect, &sql);
         let tokens = tokenizer.tokenize().unwrap();
-        let expected = vec![Token::SingleQuotedString("foo\r\nbar\nbaz".to_string())];
+        let expected = vec![Token::SingleQuotedString("foo\r\nbar\nbaz".into())];
         compare(expected, tokens);
     }
 
@@ -1306,7 +1494,8 @@ mod tests {
             Err(TokenizerError {
                 message: "Unterminated string literal".to_string(),
                 line: 1,
-                col: 8
+                col: 8,
+                limit_exceeded: false
             })
         );
     }
@@ -1322,7 +1511,8 @@ mod tests {
             Err(TokenizerError {
                 message: "Unterminated string literal".to_string(),
                 line: 1,
-                col: 35
+                col: 35,
+                limit_exceeded: false
             })
         );
     }
@@ -1395,12 +1585,12 @@ mod tests {
         let mut tokenizer = Tokenizer::new(&dialect, &sql);
         let tokens = tokenizer.tokenize().unwrap();
         let expected = vec![
-            Token::Number("0".to_string(), false),
+            Token::Number("0".into(), false),
             Token::Whitespace(Whitespace::SingleLineComment {
-                prefix: "--".to_string(),
-                comment: "this is a comment\n".to_string(),
+                prefix: "--".into(),
+                comment: "this is a comment\n".into(),
             }),
-            Token::Number("1".to_string(), false),
+            Token::Number("1".into(), false),
         ];
         compare(expected, tokens);
     }
@@ -1413,8 +1603,8 @@ mod tests {
         let mut tokenizer = Tokenizer::new(&dialect, &sql);
         let tokens = tokenizer.tokenize().unwrap();
         let expected = vec![Token::Whitespace(Whitespace::SingleLineComment {
-            prefix: "--".to_string(),
-            comment: "this is a comment".to_string(),
+            prefix: "--".into(),
+            comment: "this is a comment".into(),
         })];
         compare(expected, tokens);
     }
@@ -1427,11 +1617,11 @@ mod tests {
         let mut tokenizer = Tokenizer::new(&dialect, &sql);
         let tokens = tokenizer.tokenize().unwrap();
         let expected = vec![
-            Token::Number("0".to_string(), false),
+            Token::Number("0".into(), false),
             Token::Whitespace(Whitespace::MultiLineComment(
-                "multi-line\n* /comment".to_string(),
+                "multi-line\n* /comment".into(),
             )),
-            Token::Number("1".to_string(), false),
+            Token::Number("1".into(), false),
         ];
         compare(expected, tokens);
     }
@@ -1444,11 +1634,11 @@ mod tests {
         let mut tokenizer = Tokenizer::new(&dialect, &sql);
         let tokens = tokenizer.tokenize().unwrap();
         let expected = vec![
-            Token::Number("0".to_string(), false),
+            Token::Number("0".into(), false),
             Token::Whitespace(Whitespace::MultiLineComment(
-                "multi-line\n* \n/* comment \n /*comment*/*/ */ /comment".to_string(),
+                "multi-line\n* \n/* comment \n /*comment*/*/ */ /comment".into(),
             )),
-            Token::Number("1".to_string(), false),
+            Token::Number("1".into(), false),
         ];
         compare(expected, tokens);
     }
@@ -1462,7 +1652,7 @@ mod tests {
         let tokens = tokenizer.tokenize().unwrap();
         let expected = vec![
             Token::Whitespace(Whitespace::Newline),
-            Token::Whitespace(Whitespace::MultiLineComment("* Comment *".to_string())),
+            Token::Whitespace(Whitespace::MultiLineComment("* Comment *".into())),
             Token::Whitespace(Whitespace::Newline),
         ];
         compare(expected, tokens);
@@ -1494,7 +1684,8 @@ mod tests {
             Err(TokenizerError {
                 message: "Expected close delimiter '\"' before EOF.".to_string(),
                 line: 1,
-                col: 1
+                col: 1,
+                limit_exceeded: false
             })
         );
     }
@@ -1576,6 +1767,35 @@ mod tests {
         compare(expected, tokens);
     }
 
+    #[test]
+    fn tokenize_borrows_from_input() {
+        let sql = "SELECT 'it''s', name -- done";
+        let dialect = GenericDialect {};
+        let mut tokenizer = Tokenizer::new(&dialect, sql);
+        let tokens = tokenizer.tokenize().unwrap();
+
+        // Only the string that needed its quotes unescaped was copied
+        let mut owned = vec![];
+        for token in &tokens {
+            match token {
+                Token::Word(w) if matches!(w.value, Cow::Owned(_)) => owned.push(token),
+                Token::SingleQuotedString(Cow::Owned(_)) => owned.push(token),
+                Token::Whitespace(Whitespace::SingleLineComment { comment: Cow::Owned(_), .. }) => {
+                    owned.push(token)
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(owned, vec![&Token::SingleQuotedString("it's".into())]);
+        assert_eq!(
+            tokens.last(),
+            Some(&Token::Whitespace(Whitespace::SingleLineComment {
+                comment: " done".into(),
+                prefix: "--".into(),
+            }))
+        );
+    }
+
     #[test]
     fn tokenize_with_location() {
         let sql = "SELECT a,\n b";
@@ -1594,6 +1814,32 @@ mod tests {
         compare(expected, tokens);
     }
 
+    #[test]
+    fn tokenize_with_trivia() {
+        let sql = "-- orders\nSELECT a, /* the id */ b\nFROM t -- done\n";
+        let dialect = GenericDialect {};
+        let mut tokenizer = Tokenizer::new(&dialect, sql);
+        let (tokens, trivia) = tokenizer.tokenize_with_trivia().unwrap();
+
+        assert!(tokens.iter().all(|token| !matches!(token.token, Token::Whitespace(_))));
+        assert_eq!(
+            trivia.before(&Location { line: 2, column: 1 }),
+            vec![
+                TokenWithLocation::new(
+                    Token::Whitespace(Whitespace::SingleLineComment {
+                        comment: " orders\n".into(),
+                        prefix: "--".into(),
+                    }),
+                    1,
+                    1
+                ),
+            ]
+        );
+        let comments: Vec<String> = trivia.comments().map(|comment| comment.to_string()).collect();
+        assert_eq!(comments, vec!["-- orders\n", "/* the id */", "-- done\n"]);
+        assert_eq!(trivia.render(&tokens), sql);
+    }
+
     fn compare<T: PartialEq + std::fmt::Debug>(expected: Vec<T>, actual: Vec<T>) {
         //println!("------------------------------");
         //println!("tokens   = {:?}", actual);
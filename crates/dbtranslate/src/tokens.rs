@@ -1,42 +1,60 @@
+#[cfg(not(feature = "std"))]
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+use core::cmp::Ordering;
 use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "visitor")]
+use dbtranslate_derive::{Visit, VisitMut};
+
 use crate::ast::DollarQuotedString;
 use crate::keywords::{Keyword, ALL_KEYWORDS, ALL_KEYWORDS_INDEX};
 
-/// SQL Token enumeration
+/// SQL Token enumeration. The text of words, numbers, strings and comments
+/// is borrowed from the SQL being tokenized wherever it can be, so most
+/// tokens don't allocate. [Token::into_owned] detaches a token from the SQL.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
-pub enum Token {
+pub enum Token<'a> {
     /// An end-of-file marker, not a real token
     EOF,
     /// A keyword (like SELECT) or an optionally quoted SQL identifier
-    Word(Word),
+    Word(Word<'a>),
     /// An unsigned numeric literal
-    Number(String, bool),
+    Number(Cow<'a, str>, bool),
     /// A character that could not be tokenized
     Char(char),
     /// Single quoted string: i.e: 'string'
-    SingleQuotedString(String),
+    SingleQuotedString(Cow<'a, str>),
     /// Double quoted string: i.e: "string"
-    DoubleQuotedString(String),
+    DoubleQuotedString(Cow<'a, str>),
     /// Dollar quoted string: i.e: $$string$$ or $tag_name$string$tag_name$
     DollarQuotedString(DollarQuotedString),
     /// Byte string literal: i.e: b'string' or B'string'
-    SingleQuotedByteStringLiteral(String),
+    SingleQuotedByteStringLiteral(Cow<'a, str>),
     /// Byte string literal: i.e: b"string" or B"string"
-    DoubleQuotedByteStringLiteral(String),
+    DoubleQuotedByteStringLiteral(Cow<'a, str>),
     /// Raw string literal: i.e: r'string' or R'string' or r"string" or R"string"
-    RawStringLiteral(String),
+    RawStringLiteral(Cow<'a, str>),
     /// "National" string literal: i.e: N'string'
-    NationalStringLiteral(String),
+    NationalStringLiteral(Cow<'a, str>),
     /// "escaped" string literal, which are an extension to the SQL standard: i.e: e'first \n second' or E 'first \n second'
-    EscapedStringLiteral(String),
+    EscapedStringLiteral(Cow<'a, str>),
     /// Hexadecimal string literal: i.e.: X'deadbeef'
-    HexStringLiteral(String),
+    HexStringLiteral(Cow<'a, str>),
     /// Comma
     Comma,
     /// Whitespace (space, tab, etc)
-    Whitespace(Whitespace),
+    Whitespace(Whitespace<'a>),
     /// Double equals sign `==`
     DoubleEq,
     /// Equality operator `=`
@@ -128,7 +146,7 @@ pub enum Token {
     /// `||/` , a cube root math operator in PostgreSQL
     PGCubeRoot,
     /// `?` or `$` , a prepared statement arg placeholder
-    Placeholder(String),
+    Placeholder(Cow<'a, str>),
     /// ->, used as a operator to extract json field in PostgreSQL
     Arrow,
     /// ->>, used as a operator to extract json field as text in PostgreSQL
@@ -153,7 +171,7 @@ pub enum Token {
     AtAt,
 }
 
-impl fmt::Display for Token {
+impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Token::EOF => f.write_str("EOF"),
@@ -201,8 +219,8 @@ impl fmt::Display for Token {
             Token::DoubleLBrace => f.write_str("{{"),
             Token::RBrace => f.write_str("}"),
             Token::DoubleRBrace => f.write_str("}}"),
-            Token::LJinjaIterator => f.write_str("%}"),
-            Token::RJinjaIterator => f.write_str("{%"),
+            Token::LJinjaIterator => f.write_str("{%"),
+            Token::RJinjaIterator => f.write_str("%}"),
             Token::RArrow => f.write_str("=>"),
             Token::Sharp => f.write_str("#"),
             Token::ExclamationMark => f.write_str("!"),
@@ -230,34 +248,117 @@ impl fmt::Display for Token {
     }
 }
 
-impl Token {
-    pub fn make_keyword(keyword: &str) -> Self {
+impl<'a> Token<'a> {
+    pub fn make_keyword(keyword: &'a str) -> Self {
         Token::make_word(keyword, None)
     }
 
-    pub fn make_word(word: &str, quote_style: Option<char>) -> Self {
-        let word_uppercase = word.to_uppercase();
+    pub fn make_word(word: impl Into<Cow<'a, str>>, quote_style: Option<char>) -> Self {
+        let word = word.into();
+        let keyword = match quote_style {
+            None => ALL_KEYWORDS
+                .binary_search_by(|keyword| cmp_uppercase(keyword, &word))
+                .map_or(Keyword::NoKeyword, |x| ALL_KEYWORDS_INDEX[x]),
+            Some(_) => Keyword::NoKeyword,
+        };
         Token::Word(Word {
-            value: word.to_string(),
+            value: word,
             quote_style,
-            keyword: if quote_style.is_none() {
-                let keyword = ALL_KEYWORDS.binary_search(&word_uppercase.as_str());
-                keyword.map_or(Keyword::NoKeyword, |x| ALL_KEYWORDS_INDEX[x])
-            } else {
-                Keyword::NoKeyword
-            },
+            keyword,
         })
     }
+
+    /// The same token, with its own copy of any text it borrows from the SQL
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::EOF => Token::EOF,
+            Token::Word(word) => Token::Word(word.into_owned()),
+            Token::Number(n, long) => Token::Number(Cow::Owned(n.into_owned()), long),
+            Token::Char(x) => Token::Char(x),
+            Token::SingleQuotedString(s) => Token::SingleQuotedString(Cow::Owned(s.into_owned())),
+            Token::DoubleQuotedString(s) => Token::DoubleQuotedString(Cow::Owned(s.into_owned())),
+            Token::DollarQuotedString(x) => Token::DollarQuotedString(x),
+            Token::SingleQuotedByteStringLiteral(s) => Token::SingleQuotedByteStringLiteral(Cow::Owned(s.into_owned())),
+            Token::DoubleQuotedByteStringLiteral(s) => Token::DoubleQuotedByteStringLiteral(Cow::Owned(s.into_owned())),
+            Token::RawStringLiteral(s) => Token::RawStringLiteral(Cow::Owned(s.into_owned())),
+            Token::NationalStringLiteral(s) => Token::NationalStringLiteral(Cow::Owned(s.into_owned())),
+            Token::EscapedStringLiteral(s) => Token::EscapedStringLiteral(Cow::Owned(s.into_owned())),
+            Token::HexStringLiteral(s) => Token::HexStringLiteral(Cow::Owned(s.into_owned())),
+            Token::Comma => Token::Comma,
+            Token::Whitespace(whitespace) => Token::Whitespace(whitespace.into_owned()),
+            Token::DoubleEq => Token::DoubleEq,
+            Token::Eq => Token::Eq,
+            Token::Neq => Token::Neq,
+            Token::Lt => Token::Lt,
+            Token::Gt => Token::Gt,
+            Token::LtEq => Token::LtEq,
+            Token::GtEq => Token::GtEq,
+            Token::Spaceship => Token::Spaceship,
+            Token::Plus => Token::Plus,
+            Token::Minus => Token::Minus,
+            Token::Mul => Token::Mul,
+            Token::Div => Token::Div,
+            Token::Mod => Token::Mod,
+            Token::StringConcat => Token::StringConcat,
+            Token::LParen => Token::LParen,
+            Token::RParen => Token::RParen,
+            Token::Period => Token::Period,
+            Token::Colon => Token::Colon,
+            Token::DoubleColon => Token::DoubleColon,
+            Token::SemiColon => Token::SemiColon,
+            Token::Backslash => Token::Backslash,
+            Token::LBracket => Token::LBracket,
+            Token::RBracket => Token::RBracket,
+            Token::Ampersand => Token::Ampersand,
+            Token::Pipe => Token::Pipe,
+            Token::Caret => Token::Caret,
+            Token::LBrace => Token::LBrace,
+            Token::DoubleLBrace => Token::DoubleLBrace,
+            Token::RBrace => Token::RBrace,
+            Token::DoubleRBrace => Token::DoubleRBrace,
+            Token::LJinjaIterator => Token::LJinjaIterator,
+            Token::RJinjaIterator => Token::RJinjaIterator,
+            Token::RArrow => Token::RArrow,
+            Token::Sharp => Token::Sharp,
+            Token::Tilde => Token::Tilde,
+            Token::TildeAsterisk => Token::TildeAsterisk,
+            Token::ExclamationMarkTilde => Token::ExclamationMarkTilde,
+            Token::ExclamationMarkTildeAsterisk => Token::ExclamationMarkTildeAsterisk,
+            Token::ShiftLeft => Token::ShiftLeft,
+            Token::ShiftRight => Token::ShiftRight,
+            Token::ExclamationMark => Token::ExclamationMark,
+            Token::DoubleExclamationMark => Token::DoubleExclamationMark,
+            Token::AtSign => Token::AtSign,
+            Token::PGSquareRoot => Token::PGSquareRoot,
+            Token::PGCubeRoot => Token::PGCubeRoot,
+            Token::Placeholder(s) => Token::Placeholder(Cow::Owned(s.into_owned())),
+            Token::Arrow => Token::Arrow,
+            Token::LongArrow => Token::LongArrow,
+            Token::HashArrow => Token::HashArrow,
+            Token::HashLongArrow => Token::HashLongArrow,
+            Token::AtArrow => Token::AtArrow,
+            Token::ArrowAt => Token::ArrowAt,
+            Token::HashMinus => Token::HashMinus,
+            Token::AtQuestion => Token::AtQuestion,
+            Token::AtAt => Token::AtAt,
+        }
+    }
+}
+
+// Compares a keyword with a word as if the word was uppercase, without
+// allocating an uppercase copy of every word
+fn cmp_uppercase(keyword: &str, word: &str) -> Ordering {
+    keyword.bytes().cmp(word.bytes().map(|b| b.to_ascii_uppercase()))
 }
 
 /// A keyword (like SELECT) or an optionally quoted SQL identifier
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
-pub struct Word {
+pub struct Word<'a> {
     /// The value of the token, without the enclosing quotes, and with the
     /// escape sequences (if any) processed (TODO: escapes are not handled)
-    pub value: String,
+    pub value: Cow<'a, str>,
     /// An identifier can be "quoted" (&lt;delimited identifier> in ANSI parlance).
     /// The standard and most implementations allow using double quotes for this,
     /// but some implementations support other quoting styles as well (e.g. \[MS SQL])
@@ -267,7 +368,7 @@ pub struct Word {
     pub keyword: Keyword,
 }
 
-impl fmt::Display for Word {
+impl fmt::Display for Word<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.quote_style {
             Some(s) if s == '"' || s == '[' || s == '`' => {
@@ -279,7 +380,16 @@ impl fmt::Display for Word {
     }
 }
 
-impl Word {
+impl Word<'_> {
+    /// The same word, with its own copy of its value
+    pub fn into_owned(self) -> Word<'static> {
+        Word {
+            value: Cow::Owned(self.value.into_owned()),
+            quote_style: self.quote_style,
+            keyword: self.keyword,
+        }
+    }
+
     pub fn matching_end_quote(ch: char) -> char {
         match ch {
             '"' => '"', // ANSI and most dialects
@@ -293,15 +403,38 @@ impl Word {
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "visitor", derive(Visit, VisitMut))]
-pub enum Whitespace {
+pub enum Whitespace<'a> {
     Space,
     Newline,
     Tab,
-    SingleLineComment { comment: String, prefix: String },
-    MultiLineComment(String),
+    SingleLineComment { comment: Cow<'a, str>, prefix: Cow<'a, str> },
+    MultiLineComment(Cow<'a, str>),
+    /// The `-` in `{{-`, `-}}`, `{%-` and `-%}`, which has Jinja strip the
+    /// whitespace next to the tag when it renders the model
+    JinjaTrim,
+    /// A Jinja comment, `{# ... #}`, without the `{#` and `#}`
+    JinjaComment(Cow<'a, str>),
+}
+
+impl Whitespace<'_> {
+    /// The same whitespace, with its own copy of any comment
+    pub fn into_owned(self) -> Whitespace<'static> {
+        match self {
+            Whitespace::Space => Whitespace::Space,
+            Whitespace::Newline => Whitespace::Newline,
+            Whitespace::Tab => Whitespace::Tab,
+            Whitespace::SingleLineComment { comment, prefix } => Whitespace::SingleLineComment {
+                comment: Cow::Owned(comment.into_owned()),
+                prefix: Cow::Owned(prefix.into_owned()),
+            },
+            Whitespace::MultiLineComment(s) => Whitespace::MultiLineComment(Cow::Owned(s.into_owned())),
+            Whitespace::JinjaTrim => Whitespace::JinjaTrim,
+            Whitespace::JinjaComment(s) => Whitespace::JinjaComment(Cow::Owned(s.into_owned())),
+        }
+    }
 }
 
-impl fmt::Display for Whitespace {
+impl fmt::Display for Whitespace<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Whitespace::Space => f.write_str(" "),
@@ -309,12 +442,15 @@ impl fmt::Display for Whitespace {
             Whitespace::Tab => f.write_str("\t"),
             Whitespace::SingleLineComment { prefix, comment } => write!(f, "{prefix}{comment}"),
             Whitespace::MultiLineComment(s) => write!(f, "/*{s}*/"),
+            Whitespace::JinjaTrim => f.write_str("-"),
+            Whitespace::JinjaComment(s) => write!(f, "{{#{s}#}}"),
         }
     }
 }
 
 /// Location in input string
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Location {
     /// Line number, starting from 1
     pub line: u64,
@@ -322,40 +458,195 @@ pub struct Location {
     pub column: u64,
 }
 
+/// The part of the input string a token or AST node covers, from its first
+/// character to just after its last. Nodes built without locations, such as
+/// the ones in tests, have an empty span, which is all zeroes.
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Hash, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+impl Span {
+    pub fn new(start: Location, end: Location) -> Span {
+        Span { start, end }
+    }
+
+    pub fn empty() -> Span {
+        Span::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start.line == 0
+    }
+
+    /// The smallest span that covers both spans. An empty span doesn't
+    /// cover anything, so the union with one is the other span.
+    pub fn union(&self, other: &Span) -> Span {
+        match (self.is_empty(), other.is_empty()) {
+            (true, _) => other.clone(),
+            (_, true) => self.clone(),
+            _ => Span {
+                start: self.start.clone().min(other.start.clone()),
+                end: self.end.clone().max(other.end.clone()),
+            },
+        }
+    }
+
+    /// The union of every span, or an empty span if there are none
+    pub fn union_iter<I: IntoIterator<Item = Span>>(spans: I) -> Span {
+        spans
+            .into_iter()
+            .fold(Span::empty(), |union, span| union.union(&span))
+    }
+}
+
 /// A [Token] with [Location] attached to it
 #[derive(Debug, Eq, PartialEq, Clone)]
-pub struct TokenWithLocation {
-    pub token: Token,
+pub struct TokenWithLocation<'a> {
+    pub token: Token<'a>,
     pub location: Location,
 }
 
-impl TokenWithLocation {
-    pub fn new(token: Token, line: u64, column: u64) -> TokenWithLocation {
+impl<'a> TokenWithLocation<'a> {
+    pub fn new(token: Token<'a>, line: u64, column: u64) -> TokenWithLocation<'a> {
         TokenWithLocation {
             token,
             location: Location { line, column },
         }
     }
 
-    pub fn wrap(token: Token) -> TokenWithLocation {
+    pub fn wrap(token: Token<'a>) -> TokenWithLocation<'a> {
         TokenWithLocation::new(token, 0, 0)
     }
+
+    /// The same token, with its own copy of any text it borrows from the SQL
+    pub fn into_owned(self) -> TokenWithLocation<'static> {
+        TokenWithLocation {
+            token: self.token.into_owned(),
+            location: self.location,
+        }
+    }
+
+    /// The span of the token's text. Tokens without a location, like the
+    /// ones from [TokenWithLocation::wrap], have an empty span.
+    pub fn span(&self) -> Span {
+        if self.location.line == 0 {
+            return Span::empty();
+        }
+        let mut end = self.location.clone();
+        for c in self.token.to_string().chars() {
+            if c == '\n' {
+                end.line += 1;
+                end.column = 1;
+            } else {
+                end.column += 1;
+            }
+        }
+        Span::new(self.location.clone(), end)
+    }
 }
 
-impl PartialEq<Token> for TokenWithLocation {
-    fn eq(&self, other: &Token) -> bool {
+impl<'a> PartialEq<Token<'a>> for TokenWithLocation<'a> {
+    fn eq(&self, other: &Token<'a>) -> bool {
         &self.token == other
     }
 }
 
-impl PartialEq<TokenWithLocation> for Token {
-    fn eq(&self, other: &TokenWithLocation) -> bool {
+impl<'a> PartialEq<TokenWithLocation<'a>> for Token<'a> {
+    fn eq(&self, other: &TokenWithLocation<'a>) -> bool {
         self == &other.token
     }
 }
 
-impl fmt::Display for TokenWithLocation {
+impl fmt::Display for TokenWithLocation<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.token.fmt(f)
     }
-}
\ No newline at end of file
+}
+/// The whitespace and comments the parser skips over, attached to the token
+/// that follows them. Keeping them alongside the AST means comments can be
+/// read, e.g. for suppressions, and the SQL can be written back out with its
+/// comments and layout.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Trivia<'a> {
+    /// The whitespace and comments before each token, keyed by where the
+    /// token starts. Every token has an entry, even when nothing comes
+    /// before it.
+    pub leading: BTreeMap<Location, Vec<TokenWithLocation<'a>>>,
+    /// The whitespace and comments after the last token
+    pub trailing: Vec<TokenWithLocation<'a>>,
+}
+
+impl<'a> Trivia<'a> {
+    /// Splits the output of [Tokenizer::tokenize_with_location] into the
+    /// tokens the parser reads and the trivia between them
+    ///
+    /// [Tokenizer::tokenize_with_location]: crate::tokenizer::Tokenizer::tokenize_with_location
+    pub fn split(tokens: Vec<TokenWithLocation<'a>>) -> (Vec<TokenWithLocation<'a>>, Trivia<'a>) {
+        let mut significant = Vec::with_capacity(tokens.len());
+        let mut trivia = Trivia::default();
+        for token in tokens {
+            match token.token {
+                Token::Whitespace(_) => trivia.trailing.push(token),
+                _ => {
+                    let leading = core::mem::take(&mut trivia.trailing);
+                    trivia.leading.insert(token.location.clone(), leading);
+                    significant.push(token);
+                }
+            }
+        }
+        (significant, trivia)
+    }
+
+    /// The whitespace and comments before the token starting at `location`
+    pub fn before(&self, location: &Location) -> &[TokenWithLocation<'a>] {
+        self.leading.get(location).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Every comment, in the order they're written
+    pub fn comments(&self) -> impl Iterator<Item = &TokenWithLocation<'a>> {
+        self.leading
+            .values()
+            .flatten()
+            .chain(&self.trailing)
+            .filter(|token| {
+                matches!(
+                    token.token,
+                    Token::Whitespace(
+                        Whitespace::SingleLineComment { .. } | Whitespace::MultiLineComment(_) | Whitespace::JinjaComment(_)
+                    )
+                )
+            })
+    }
+
+    /// Writes the tokens back out with their whitespace and comments. For
+    /// tokens straight from [Trivia::split] this gives back the original SQL,
+    /// apart from `\r\n` line endings, which become `\n`.
+    pub fn render(&self, tokens: &[TokenWithLocation<'a>]) -> String {
+        let mut sql = String::new();
+        for token in tokens {
+            for trivia in self.before(&token.location) {
+                sql.push_str(&trivia.to_string());
+            }
+            sql.push_str(&token.to_string());
+        }
+        for trivia in &self.trailing {
+            sql.push_str(&trivia.to_string());
+        }
+        sql
+    }
+
+    /// The same trivia, with its own copy of any text it borrows from the SQL
+    pub fn into_owned(self) -> Trivia<'static> {
+        Trivia {
+            leading: self
+                .leading
+                .into_iter()
+                .map(|(location, tokens)| (location, tokens.into_iter().map(TokenWithLocation::into_owned).collect()))
+                .collect(),
+            trailing: self.trailing.into_iter().map(TokenWithLocation::into_owned).collect(),
+        }
+    }
+}
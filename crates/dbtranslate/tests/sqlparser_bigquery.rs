@@ -14,6 +14,8 @@
 mod test_utils;
 
 use dbtranslate::ast::*;
+#[cfg(not(feature = "bigdecimal"))]
+use dbtranslate::tokens::Span;
 use dbtranslate::dialect::{BigQueryDialect, GenericDialect};
 use test_utils::*;
 
@@ -163,10 +165,11 @@ fn parse_join_constraint_unnest_alias() {
         vec![Join {
             relation: TableFactor::UNNEST {
                 alias: table_alias("f"),
-                array_expr: Box::new(Expr::CompoundIdentifier(vec![
+                array_exprs: vec![Expr::CompoundIdentifier(vec![
                     Ident::new("t1"),
                     Ident::new("a")
-                ])),
+                ])],
+                with_ordinality: false,
                 with_offset: false,
                 with_offset_alias: None
             },
@@ -427,8 +430,9 @@ fn parse_map_access_offset() {
         _select.projection[0],
         SelectItem::UnnamedExpr(Expr::MapAccess {
             column: Box::new(Expr::Identifier(Ident {
-                value: "d".to_string(),
+                value: "d".into(),
                 quote_style: None,
+                span: Span::empty(),
             })),
             keys: vec![Expr::Function(Function {
                 name: ObjectName(vec!["offset".into()]),
@@ -451,3 +455,78 @@ fn parse_map_access_offset() {
         bigquery().verified_only_select(sql);
     }
 }
+
+#[test]
+fn parse_struct_literals() {
+    let select = bigquery_and_generic().verified_only_select("SELECT STRUCT(1 AS a, 'x' AS b), STRUCT(id)");
+    assert_eq!(
+        expr_from_projection(&select.projection[0]),
+        &Expr::Struct {
+            values: vec![
+                Expr::Named {
+                    expr: Box::new(Expr::Value(number("1"))),
+                    name: Ident::new("a"),
+                },
+                Expr::Named {
+                    expr: Box::new(Expr::Value(Value::SingleQuotedString("x".to_string()))),
+                    name: Ident::new("b"),
+                },
+            ],
+            fields: vec![],
+        }
+    );
+
+    let select = bigquery_and_generic().verified_only_select("SELECT STRUCT<a INT64, STRING>(1, 'x')");
+    assert_eq!(
+        expr_from_projection(&select.projection[0]),
+        &Expr::Struct {
+            values: vec![Expr::Value(number("1")), Expr::Value(Value::SingleQuotedString("x".to_string()))],
+            fields: vec![
+                StructField {
+                    field_name: Some(Ident::new("a")),
+                    field_type: DataType::Custom(ObjectName(vec![Ident::new("INT64")]), vec![]),
+                },
+                StructField {
+                    field_name: None,
+                    field_type: DataType::String,
+                },
+            ],
+        }
+    );
+
+    // `>>` closes both types
+    bigquery().verified_stmt("SELECT STRUCT<a ARRAY<STRING>, b STRUCT<c INT64>>([], STRUCT(1))");
+    bigquery().verified_stmt("SELECT CAST(payload AS STRUCT<id INT64, tags ARRAY<STRING>>) FROM t");
+    bigquery().one_statement_parses_to(
+        "SELECT STRUCT<b STRUCT<c INT64> >(STRUCT(1))",
+        "SELECT STRUCT<b STRUCT<c INT64>>(STRUCT(1))",
+    );
+}
+
+#[test]
+fn parse_safe_prefix() {
+    let select = bigquery().verified_only_select("SELECT SAFE.PARSE_DATE('%Y%m%d', d) FROM t");
+    match expr_from_projection(&select.projection[0]) {
+        Expr::Function(function) => {
+            assert_eq!(function.name, ObjectName(vec![Ident::new("SAFE"), Ident::new("PARSE_DATE")]))
+        }
+        expr => panic!("Expected a function, found: {expr}"),
+    }
+}
+
+#[test]
+fn parse_nested_field_access() {
+    bigquery().verified_stmt("SELECT t.payload.customer.id FROM `project.dataset.events` AS t");
+
+    let select = bigquery().verified_only_select("SELECT items[OFFSET(0)].sku.id FROM t");
+    match expr_from_projection(&select.projection[0]) {
+        Expr::CompositeAccess { expr, key } => {
+            assert_eq!(key, &Ident::new("id"));
+            assert!(matches!(expr.as_ref(), Expr::CompositeAccess { expr, .. } if matches!(expr.as_ref(), Expr::MapAccess { .. })));
+        }
+        expr => panic!("Expected field access, found: {expr}"),
+    }
+
+    bigquery().verified_stmt("SELECT JSON_QUERY_ARRAY(payload)[SAFE_OFFSET(1)].name FROM t");
+    bigquery().verified_stmt("SELECT (SELECT payload FROM t LIMIT 1).customer.id");
+}
@@ -23,6 +23,7 @@ use matches::assert_matches;
 use dbtranslate::ast::SelectItem::UnnamedExpr;
 use dbtranslate::ast::TableFactor::Pivot;
 use dbtranslate::ast::*;
+use dbtranslate::tokens::Span;
 use dbtranslate::dialect::{
     AnsiDialect, BigQueryDialect, GenericDialect,
     PostgreSqlDialect, RedshiftSqlDialect, SnowflakeDialect,
@@ -607,10 +608,7 @@ fn parse_exponent_in_select() -> Result<(), ParserError> {
     let sql = "SELECT 10e-20, 1e3, 1e+3, 1e3a, 1e, 0.5e2";
     let mut select = dialects.parse_sql_statements(sql)?;
 
-    let select = match select.pop().unwrap() {
-        Statement::Query(inner) => *inner,
-        _ => panic!("Expected Query"),
-    };
+    let Statement::Query(select) = select.pop().unwrap();
     let select = match *select.body {
         SetExpr::Select(inner) => *inner,
         _ => panic!("Expected SetExpr::Select"),
@@ -645,6 +643,7 @@ fn parse_select_with_date_column_name() {
         &Expr::Identifier(Ident {
             value: "date".into(),
             quote_style: None,
+            span: Span::empty(),
         }),
         expr_from_projection(only(&select.projection)),
     );
@@ -869,8 +868,9 @@ fn parse_null_like() {
                 escape_char: None,
             },
             alias: Ident {
-                value: "col_null".to_owned(),
+                value: "col_null".into(),
                 quote_style: None,
+                span: Span::empty(),
             },
         },
         select.projection[0]
@@ -884,8 +884,9 @@ fn parse_null_like() {
                 escape_char: None,
             },
             alias: Ident {
-                value: "null_col".to_owned(),
+                value: "null_col".into(),
                 quote_style: None,
+                span: Span::empty(),
             },
         },
         select.projection[1]
@@ -1729,16 +1730,18 @@ fn parse_listagg() {
     let within_group = vec![
         OrderByExpr {
             expr: Expr::Identifier(Ident {
-                value: "id".to_string(),
+                value: "id".into(),
                 quote_style: None,
+                span: Span::empty(),
             }),
             asc: None,
             nulls_first: None,
         },
         OrderByExpr {
             expr: Expr::Identifier(Ident {
-                value: "username".to_string(),
+                value: "username".into(),
                 quote_style: None,
+                span: Span::empty(),
             }),
             asc: None,
             nulls_first: None,
@@ -2158,18 +2161,21 @@ fn parse_interval_and_or_xor() {
         config: None,
         with: None,
         body: Box::new(SetExpr::Select(Box::new(Select {
+            select_span: AttachedSpan::default(),
             distinct: false,
             top: None,
             projection: vec![UnnamedExpr(Expr::Identifier(Ident {
-                value: "col".to_string(),
+                value: "col".into(),
                 quote_style: None,
+                span: Span::empty(),
             }))],
             into: None,
             from: vec![TableWithJoins {
                 relation: TableFactor::Table {
                     name: ObjectName(vec![Ident {
-                        value: "test".to_string(),
+                        value: "test".into(),
                         quote_style: None,
+                        span: Span::empty(),
                     }]),
                     alias: None,
                     args: None,
@@ -2181,14 +2187,16 @@ fn parse_interval_and_or_xor() {
             selection: Some(Expr::BinaryOp {
                 left: Box::new(Expr::BinaryOp {
                     left: Box::new(Expr::Identifier(Ident {
-                        value: "d3_date".to_string(),
+                        value: "d3_date".into(),
                         quote_style: None,
+                        span: Span::empty(),
                     })),
                     op: BinaryOperator::Gt,
                     right: Box::new(Expr::BinaryOp {
                         left: Box::new(Expr::Identifier(Ident {
-                            value: "d1_date".to_string(),
+                            value: "d1_date".into(),
                             quote_style: None,
+                            span: Span::empty(),
                         })),
                         op: BinaryOperator::Plus,
                         right: Box::new(Expr::Interval {
@@ -2205,14 +2213,16 @@ fn parse_interval_and_or_xor() {
                 op: BinaryOperator::And,
                 right: Box::new(Expr::BinaryOp {
                     left: Box::new(Expr::Identifier(Ident {
-                        value: "d2_date".to_string(),
+                        value: "d2_date".into(),
                         quote_style: None,
+                        span: Span::empty(),
                     })),
                     op: BinaryOperator::Gt,
                     right: Box::new(Expr::BinaryOp {
                         left: Box::new(Expr::Identifier(Ident {
-                            value: "d1_date".to_string(),
+                            value: "d1_date".into(),
                             quote_style: None,
+                            span: Span::empty(),
                         })),
                         op: BinaryOperator::Plus,
                         right: Box::new(Expr::Interval {
@@ -2233,11 +2243,13 @@ fn parse_interval_and_or_xor() {
             sort_by: vec![],
             having: None,
             qualify: None,
+            jinja_blocks: vec![],
         }))),
         order_by: vec![],
         limit: None,
         offset: None,
         jinja_variables: vec![],
+        jinja_blocks: vec![],
     }))];
 
     assert_eq!(actual_ast, expected_ast);
@@ -2270,8 +2282,9 @@ fn parse_at_timezone() {
         &Expr::AtTimeZone {
             timestamp: Box::new(Expr::Function(Function {
                 name: ObjectName(vec![Ident {
-                    value: "FROM_UNIXTIME".to_string(),
+                    value: "FROM_UNIXTIME".into(),
                     quote_style: None,
+                    span: Span::empty(),
                 }]),
                 args: vec![FunctionArg::Unnamed(FunctionArgExpr::Expr(zero.clone()))],
                 over: None,
@@ -2289,15 +2302,17 @@ fn parse_at_timezone() {
         &SelectItem::ExprWithAlias {
             expr: Expr::Function(Function {
                 name: ObjectName(vec![Ident {
-                    value: "DATE_FORMAT".to_string(),
+                    value: "DATE_FORMAT".into(),
                     quote_style: None,
+                    span: Span::empty(),
                 },],),
                 args: vec![
                     FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::AtTimeZone {
                         timestamp: Box::new(Expr::Function(Function {
                             name: ObjectName(vec![Ident {
-                                value: "FROM_UNIXTIME".to_string(),
+                                value: "FROM_UNIXTIME".into(),
                                 quote_style: None,
+                                span: Span::empty(),
                             },],),
                             args: vec![FunctionArg::Unnamed(FunctionArgExpr::Expr(zero))],
                             over: None,
@@ -2315,8 +2330,9 @@ fn parse_at_timezone() {
                 special: false,
             },),
             alias: Ident {
-                value: "hour".to_string(),
+                value: "hour".into(),
                 quote_style: Some('"'),
+                span: Span::empty(),
             },
         },
         only(&select.projection),
@@ -2527,7 +2543,8 @@ fn parse_unnest() {
                     name: Ident::new("numbers"),
                     columns: vec![],
                 }),
-                array_expr: Box::new(Expr::Identifier(Ident::new("expr"))),
+                array_exprs: vec![Expr::Identifier(Ident::new("expr"))],
+                with_ordinality: false,
                 with_offset: true,
                 with_offset_alias: None,
             },
@@ -2543,7 +2560,8 @@ fn parse_unnest() {
         vec![TableWithJoins {
             relation: TableFactor::UNNEST {
                 alias: None,
-                array_expr: Box::new(Expr::Identifier(Ident::new("expr"))),
+                array_exprs: vec![Expr::Identifier(Ident::new("expr"))],
+                with_ordinality: false,
                 with_offset: false,
                 with_offset_alias: None,
             },
@@ -2559,7 +2577,8 @@ fn parse_unnest() {
         vec![TableWithJoins {
             relation: TableFactor::UNNEST {
                 alias: None,
-                array_expr: Box::new(Expr::Identifier(Ident::new("expr"))),
+                array_exprs: vec![Expr::Identifier(Ident::new("expr"))],
+                with_ordinality: false,
                 with_offset: true,
                 with_offset_alias: None,
             },
@@ -2578,7 +2597,8 @@ fn parse_unnest() {
                     name: Ident::new("numbers"),
                     columns: vec![],
                 }),
-                array_expr: Box::new(Expr::Identifier(Ident::new("expr"))),
+                array_exprs: vec![Expr::Identifier(Ident::new("expr"))],
+                with_ordinality: false,
                 with_offset: false,
                 with_offset_alias: None,
             },
@@ -3111,12 +3131,14 @@ fn parse_recursive_cte() {
     let expected = Cte {
         alias: TableAlias {
             name: Ident {
-                value: "nums".to_string(),
+                value: "nums".into(),
                 quote_style: None,
+                span: Span::empty(),
             },
             columns: vec![Ident {
-                value: "val".to_string(),
+                value: "val".into(),
                 quote_style: None,
+                span: Span::empty(),
             }],
         },
         query: Box::new(cte_query),
@@ -3476,14 +3498,8 @@ fn lateral_derived() {
     chk(false);
     chk(true);
 
-    let sql = "SELECT * FROM customer LEFT JOIN LATERAL generate_series(1, customer.id)";
-    let res = parse_sql_statements(sql);
-    assert_eq!(
-        ParserError::ParserError(
-            "Expected subquery after LATERAL, found: generate_series".to_string()
-        ),
-        res.unwrap_err()
-    );
+    // A function after LATERAL is a table function rather than a subquery
+    verified_stmt("SELECT * FROM customer CROSS JOIN LATERAL generate_series(1, customer.id) AS s");
 
     let sql = "SELECT * FROM a LEFT JOIN LATERAL (b CROSS JOIN c)";
     let res = parse_sql_statements(sql);
@@ -3875,7 +3891,7 @@ fn parse_with_recursion_limit() {
         .expect("tokenize to work")
         .parse_statements();
 
-    assert!(matches!(res, Ok(_)), "{res:?}");
+    assert!(res.is_ok(), "{res:?}");
 
     // limit recursion to something smaller, expect parsing to fail
     let res = Parser::new(&dialect)
@@ -3893,7 +3909,81 @@ fn parse_with_recursion_limit() {
         .with_recursion_limit(50)
         .parse_statements();
 
-    assert!(matches!(res, Ok(_)), "{res:?}");
+    assert!(res.is_ok(), "{res:?}");
+}
+
+#[test]
+fn parse_with_resource_limits() {
+    let dialect = GenericDialect {};
+    let sql = "SELECT id, user_id FROM test WHERE id = 1";
+
+    // Expect the statement to parse within generous limits
+    let res = Parser::new(&dialect)
+        .with_token_limit(100)
+        .with_statement_length_limit(100)
+        .with_time_limit(std::time::Duration::from_secs(60))
+        .try_with_sql(sql)
+        .expect("tokenize to work")
+        .parse_statements();
+
+    assert!(res.is_ok(), "{res:?}");
+
+    let res = Parser::new(&dialect)
+        .with_token_limit(10)
+        .try_with_sql(sql)
+        .map(|_| ());
+
+    assert_eq!(
+        res,
+        Err(ParserError::ResourceLimitExceeded(
+            "token count exceeds the limit of 10".to_string()
+        ))
+    );
+
+    let res = Parser::new(&dialect)
+        .with_statement_length_limit(10)
+        .try_with_sql(sql)
+        .map(|_| ());
+
+    assert_eq!(
+        res,
+        Err(ParserError::ResourceLimitExceeded(
+            "statement length exceeds the limit of 10 bytes".to_string()
+        ))
+    );
+
+    // The limit applies to each statement rather than the whole input
+    let res = Parser::new(&dialect)
+        .with_statement_length_limit(50)
+        .try_with_sql(&format!("{sql};\n{sql};\n{sql}"))
+        .map(|_| ());
+
+    assert!(res.is_ok(), "{res:?}");
+
+    let res = Parser::new(&dialect)
+        .with_statement_length_limit(50)
+        .try_with_sql(&format!("SELECT 1;\n{sql} AND name = 'a;b'\n;SELECT 2"))
+        .map(|_| ());
+
+    assert_eq!(
+        res,
+        Err(ParserError::ResourceLimitExceeded(
+            "statement length exceeds the limit of 50 bytes".to_string()
+        ))
+    );
+
+    let res = Parser::new(&dialect)
+        .with_time_limit(std::time::Duration::ZERO)
+        .try_with_sql(sql)
+        .expect("tokenize to work")
+        .parse_statements();
+
+    assert_eq!(
+        res,
+        Err(ParserError::ResourceLimitExceeded(
+            "parse time exceeded the limit of 0ms".to_string()
+        ))
+    );
 }
 
 #[test]
@@ -3930,8 +4020,9 @@ fn parse_pivot_table() {
             ],
             pivot_alias: Some(TableAlias {
                 name: Ident {
-                    value: "p".to_string(),
-                    quote_style: None
+                    value: "p".into(),
+                    quote_style: None,
+                    span: Span::empty(),
                 },
                 columns: vec![Ident::new("c"), Ident::new("d")],
             }),
@@ -4000,13 +4091,195 @@ fn parse_jinja_ref() {
     assert_eq!(
         stmt.from[0].relation,
         TableFactor::DbtRef{
+            package: None,
             model_name: Ident::with_quote('\'', "model"),
+            version: None,
             alias: None
         }
     );
     assert_eq!(verified_stmt(sql).to_string(), sql);
 }
 
+#[test]
+fn parse_jinja_var() {
+    let sql = "SELECT id FROM orders WHERE created_at >= {{ var('start_date') }}";
+
+    let select = verified_only_select(sql);
+    assert_eq!(
+        select.selection,
+        Some(Expr::BinaryOp {
+            left: Box::new(Expr::Identifier(Ident::new("created_at"))),
+            op: BinaryOperator::GtEq,
+            right: Box::new(Expr::DbtVar {
+                name: Ident::with_quote('\'', "start_date"),
+                default: None,
+            }),
+        })
+    );
+
+    let sql = "SELECT {{ var('status', 'placed') }}";
+    let select = verified_only_select(sql);
+    assert_eq!(
+        expr_from_projection(only(&select.projection)),
+        &Expr::DbtVar {
+            name: Ident::with_quote('\'', "status"),
+            default: Some(Box::new(Expr::Value(Value::SingleQuotedString("placed".to_string())))),
+        }
+    );
+
+}
+
+#[test]
+fn parse_jinja_env_var() {
+    let sql = "SELECT id FROM orders WHERE schema_name = {{ env_var('DBT_SCHEMA') }}";
+
+    let select = verified_only_select(sql);
+    assert_eq!(
+        select.selection,
+        Some(Expr::BinaryOp {
+            left: Box::new(Expr::Identifier(Ident::new("schema_name"))),
+            op: BinaryOperator::Eq,
+            right: Box::new(Expr::DbtEnvVar {
+                name: Ident::with_quote('\'', "DBT_SCHEMA"),
+                default: None,
+            }),
+        })
+    );
+
+    let sql = "SELECT {{ env_var('DBT_SCHEMA', 'analytics') }}";
+    let select = verified_only_select(sql);
+    assert_eq!(
+        expr_from_projection(only(&select.projection)),
+        &Expr::DbtEnvVar {
+            name: Ident::with_quote('\'', "DBT_SCHEMA"),
+            default: Some(Box::new(Expr::Value(Value::SingleQuotedString("analytics".to_string())))),
+        }
+    );
+}
+
+#[test]
+fn parse_jinja_macro_call() {
+    let sql = "SELECT {{ dbt_utils.star(ref('orders'), except=['id']) }}";
+
+    let select = verified_only_select(sql);
+    match expr_from_projection(only(&select.projection)) {
+        Expr::DbtMacroCall(DbtMacroCall { namespace, name, args }) => {
+            assert_eq!(namespace, &Some(Ident::new("dbt_utils")));
+            assert_eq!(name, &Ident::new("star"));
+            assert_eq!(
+                args.iter().map(|token| token.to_string()).collect::<String>(),
+                "ref('orders'), except=['id']"
+            );
+        }
+        other => panic!("Expected a macro call, got {other:?}"),
+    }
+
+    // Whitespace around the arguments is dropped
+    let sql = "SELECT id FROM orders WHERE amount > {{ cents_to_dollars('amount') }}";
+    one_statement_parses_to("SELECT id FROM orders WHERE amount > {{ cents_to_dollars( 'amount' ) }}", sql);
+    let select = verified_only_select(sql);
+    match select.selection {
+        Some(Expr::BinaryOp { right, .. }) => assert_eq!(
+            *right,
+            Expr::DbtMacroCall(DbtMacroCall {
+                namespace: None,
+                name: Ident::new("cents_to_dollars"),
+                args: vec![dbtranslate::tokens::Token::SingleQuotedString("amount".into())],
+            })
+        ),
+        other => panic!("Expected a comparison, got {other:?}"),
+    }
+
+    let res = parse_sql_statements("SELECT {{ 'amount' }}");
+    assert_eq!(
+        ParserError::ParserError("Expected a macro call after '{{', found: 'amount'".to_string()),
+        res.unwrap_err()
+    );
+}
+
+#[test]
+fn parse_jinja_macro_table() {
+    let sql = "SELECT date_day FROM {{ dbt_utils.date_spine('day', '2020-01-01', '2021-01-01') }} AS days";
+
+    let select = verified_only_select(sql);
+    match &select.from[0].relation {
+        TableFactor::DbtMacro { call, alias } => {
+            assert_eq!(call.namespace, Some(Ident::new("dbt_utils")));
+            assert_eq!(call.name, Ident::new("date_spine"));
+            assert_eq!(alias.as_ref().map(|alias| alias.name.to_string()), Some("days".to_string()));
+        }
+        other => panic!("Expected a macro table, got {other:?}"),
+    }
+}
+
+#[test]
+fn parse_jinja_this() {
+    let sql = "SELECT * FROM {{ ref('events') }} WHERE id > (SELECT MAX(id) FROM {{ this }} AS t)";
+
+    let select = verified_only_select(sql);
+    let Some(Expr::BinaryOp { right, .. }) = &select.selection else {
+        panic!("Expected a comparison, got {:?}", select.selection);
+    };
+    let Expr::Subquery(subquery) = right.as_ref() else {
+        panic!("Expected a subquery, got {right:?}");
+    };
+    let SetExpr::Select(inner) = subquery.body.as_ref() else {
+        panic!("Expected a SELECT, got {:?}", subquery.body);
+    };
+    match &inner.from[0].relation {
+        TableFactor::DbtThis { this, alias } => {
+            assert_eq!(this, &Ident::new("this"));
+            assert_eq!(alias.as_ref().map(|alias| alias.name.to_string()), Some("t".to_string()));
+        }
+        other => panic!("Expected {{{{ this }}}}, got {other:?}"),
+    }
+}
+
+#[test]
+fn parse_jinja_ref_with_package_and_version() {
+    let sql = "SELECT 1 FROM {{ ref('core', 'customers', v=2) }}";
+
+    let stmt = verified_only_select(sql);
+    assert_eq!(
+        stmt.from[0].relation,
+        TableFactor::DbtRef{
+            package: Some(Ident::with_quote('\'', "core")),
+            model_name: Ident::with_quote('\'', "customers"),
+            version: Some(number("2")),
+            alias: None
+        }
+    );
+
+    verified_stmt("SELECT 1 FROM {{ ref('core', 'customers') }}");
+    verified_stmt("SELECT 1 FROM {{ ref('customers', v='latest') }} AS c");
+    one_statement_parses_to(
+        "SELECT 1 FROM {{ ref('customers', version=2) }}",
+        "SELECT 1 FROM {{ ref('customers', v=2) }}",
+    );
+    assert!(parse_sql_statements("SELECT 1 FROM {{ ref('a', 'b', 'c') }}").is_err());
+    assert!(parse_sql_statements("SELECT 1 FROM {{ ref('a', v=1, 'b') }}").is_err());
+}
+
+#[test]
+fn parse_jinja_whitespace_control() {
+    let sql = "SELECT id FROM {{- ref('orders') -}} WHERE id > {{- var('min_id') }}\n\
+               {%- if is_incremental() -%} AND updated_at > (SELECT MAX(updated_at) FROM {{ this }}) {%- endif %}";
+
+    one_statement_parses_to(
+        sql,
+        "SELECT id FROM {{ ref('orders') }} WHERE id > {{ var('min_id') }} \
+         {% if is_incremental() %} AND updated_at > (SELECT MAX(updated_at) FROM {{ this }}) {% endif %}",
+    );
+}
+
+#[test]
+fn parse_jinja_comment() {
+    one_statement_parses_to(
+        "{# TODO: use {{ ref('customers') }} #}\nSELECT id {#- the key -#} FROM {{ ref('orders') }}",
+        "SELECT id FROM {{ ref('orders') }}",
+    );
+}
+
 #[test]
 fn parse_jinja_ref_as() {
     let sql = "SELECT 1 FROM {{ ref('model') }} AS my_model";
@@ -4015,8 +4288,10 @@ fn parse_jinja_ref_as() {
     assert_eq!(
         stmt.from[0].relation,
         TableFactor::DbtRef{
+            package: None,
             model_name: Ident::with_quote('\'', "model"),
-            alias: Some(TableAlias { name: Ident { value: "my_model".to_string(), quote_style: None }, columns: vec![] })
+            version: None,
+            alias: Some(TableAlias { name: Ident { value: "my_model".into(), quote_style: None, span: Span::empty() }, columns: vec![] })
         }
     );
     assert_eq!(verified_stmt(sql).to_string(), sql);
@@ -4048,8 +4323,298 @@ fn parse_jinja_source_as() {
         TableFactor::DbtSource{
             source_name: Ident::with_quote('\'', "source_name"),
             table_name: Ident::with_quote('\'', "table_name"),
-            alias: Some(TableAlias { name: Ident { value: "my_source".to_string(), quote_style: None }, columns: vec![] })
+            alias: Some(TableAlias { name: Ident { value: "my_source".into(), quote_style: None, span: Span::empty() }, columns: vec![] })
         }
     );
     assert_eq!(verified_stmt(sql).to_string(), sql);
-}
\ No newline at end of file
+}
+
+#[test]
+fn parse_jinja_set() {
+    let sql = "{% set payment_methods = ['credit_card', 'coupon'] %} {% set days = 7 %} SELECT 1 FROM {{ ref('payments') }}";
+
+    let query = verified_query(sql);
+    assert_eq!(
+        query.jinja_variables,
+        vec![
+            JinjaVariable {
+                key: "payment_methods".to_string(),
+                value: JinjaValue::List(vec![
+                    JinjaValue::Str("credit_card".to_string()),
+                    JinjaValue::Str("coupon".to_string()),
+                ]),
+            },
+            JinjaVariable { key: "days".to_string(), value: JinjaValue::Number("7".to_string()) },
+        ]
+    );
+}
+
+#[test]
+fn parse_jinja_set_after_config() {
+    let sql = "{{ config(materialized='table') }} {% set cols = ['a', 'b'] %} SELECT 1 AS x";
+
+    let statements = parse_sql_statements(sql).unwrap();
+    let Statement::Query(query) = &statements[0];
+    assert!(query.config.as_ref().unwrap().values.contains_key("materialized"));
+    assert_eq!(query.jinja_variables.len(), 1);
+    assert_eq!(query.jinja_variables[0].key, "cols");
+    assert_eq!(query.body.to_string(), "SELECT 1 AS x");
+}
+
+#[test]
+fn parse_jinja_set_errors() {
+    let res = parse_sql_statements("{% do log('x') %} SELECT 1");
+    assert_eq!(
+        ParserError::ParserError("Expected SELECT, VALUES, or a subquery in the query body, found: {%".to_string()),
+        res.unwrap_err()
+    );
+
+    let res = parse_sql_statements("{% set x = %} SELECT 1");
+    assert_eq!(
+        ParserError::ParserError("Expected a value, found: %}".to_string()),
+        res.unwrap_err()
+    );
+
+    let res = parse_sql_statements("{% set x %} 'a' SELECT 1");
+    assert_eq!(
+        ParserError::ParserError("Expected {% endset %}, found: EOF".to_string()),
+        res.unwrap_err()
+    );
+}
+
+#[test]
+fn parse_jinja_set_expression() {
+    let sql = "{% set start_date = var('start_date') %} {% set columns = dbt_utils.get_filtered_columns_in_relation(ref('orders'), except=['id']) %} SELECT 1 FROM {{ ref('payments') }}";
+
+    let query = verified_query(sql);
+    let values: Vec<String> = query.jinja_variables.iter().map(|variable| variable.value.to_string()).collect();
+    assert_eq!(
+        values,
+        vec!["var('start_date')", "dbt_utils.get_filtered_columns_in_relation(ref('orders'), except=['id'])"]
+    );
+    assert!(matches!(query.jinja_variables[0].value, JinjaValue::Expr(_)));
+
+    // A literal followed by more of the expression isn't a literal
+    let query = verified_query("{% set name = 'stg_' ~ model_name %} SELECT 1");
+    assert_eq!(query.jinja_variables[0].value.to_string(), "'stg_' ~ model_name");
+}
+
+#[test]
+fn parse_jinja_set_block() {
+    let sql = "{% set statuses %}'placed', 'shipped'{% endset %} SELECT id FROM {{ ref('orders') }}";
+
+    let query = verified_query(sql);
+    assert_eq!(query.jinja_variables[0].key, "statuses");
+    match &query.jinja_variables[0].value {
+        JinjaValue::Block(body) => {
+            assert_eq!(body.iter().map(|token| token.to_string()).collect::<String>(), "'placed', 'shipped'");
+        }
+        other => panic!("Expected a set block, found: {other:?}"),
+    }
+    assert_eq!(query.body.to_string(), "SELECT id FROM {{ ref('orders') }}");
+}
+
+#[test]
+fn parse_jinja_conditional_after_expression() {
+    let sql = "SELECT id FROM {{ ref('orders') }} WHERE status = 'placed' {% if target.name == 'dev' %} AND id < 100 {% endif %}";
+
+    let select = verified_only_select(sql);
+    match select.selection {
+        Some(Expr::JinjaBlock { expr: Some(expr), block: JinjaBlock::If(conditional) }) => {
+            assert_eq!(expr.to_string(), "status = 'placed'");
+            assert_eq!(conditional.branches.len(), 1);
+            assert_eq!(conditional.to_string(), "{% if target.name == 'dev' %} AND id < 100 {% endif %}");
+        }
+        other => panic!("Expected a Jinja conditional, found: {other:?}"),
+    }
+}
+
+#[test]
+fn parse_jinja_conditional_branches() {
+    let sql = "SELECT {% if target.name == 'prod' %} amount {% elif target.name == 'ci' %} 0 {% else %} NULL {% endif %} AS amount FROM payments";
+
+    let select = verified_only_select(sql);
+    let conditional = match &select.projection[0] {
+        SelectItem::ExprWithAlias { expr: Expr::JinjaBlock { expr: None, block: JinjaBlock::If(conditional) }, .. } => conditional,
+        other => panic!("Expected a Jinja conditional, found: {other:?}"),
+    };
+    let branches: Vec<(Option<String>, String)> = conditional
+        .branches
+        .iter()
+        .map(|branch| {
+            let render = |tokens: &[dbtranslate::tokens::Token]| tokens.iter().map(|token| token.to_string()).collect::<String>();
+            (branch.condition.as_deref().map(render), render(&branch.body))
+        })
+        .collect();
+    assert_eq!(
+        branches,
+        vec![
+            (Some("target.name == 'prod'".to_string()), " amount ".to_string()),
+            (Some("target.name == 'ci'".to_string()), " 0 ".to_string()),
+            (None, " NULL ".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn parse_jinja_conditional_clause() {
+    let sql = "SELECT * FROM events {% if is_incremental() %} WHERE loaded_at > (SELECT max(loaded_at) FROM {{ this }}) {% if var('full') %} OR TRUE {% endif %} {% endif %}";
+
+    let select = verified_only_select(sql);
+    assert_eq!(select.selection, None);
+    assert_eq!(select.jinja_blocks.len(), 1);
+}
+
+#[test]
+fn parse_jinja_conditional_in_projection() {
+    let sql = "SELECT id, {% if include_email %} email, {% endif %} name FROM customers";
+
+    let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+    let Statement::Query(query) = &statements[0];
+    let SetExpr::Select(select) = query.body.as_ref() else { panic!("Expected a SELECT") };
+    assert_eq!(select.projection.len(), 3);
+    assert_eq!(select.projection[2].to_string(), "name");
+    assert_eq!(select.from[0].to_string(), "customers");
+}
+
+#[test]
+fn parse_jinja_conditional_statement() {
+    let sql = "{% if target.name == 'prod' %} SELECT 1 AS x {% else %} SELECT 2 AS x {% endif %}";
+
+    let query = verified_query(sql);
+    match query.body.as_ref() {
+        SetExpr::JinjaBlock(JinjaBlock::If(conditional)) => assert_eq!(conditional.branches.len(), 2),
+        other => panic!("Expected a Jinja conditional, found: {other:?}"),
+    }
+}
+
+#[test]
+fn parse_jinja_conditional_config() {
+    let sql = "{% if target.name == 'prod' %}{{ config(materialized='table') }}{% endif %} SELECT 1 AS x";
+
+    let statements = parse_sql_statements(sql).unwrap();
+    let Statement::Query(query) = &statements[0];
+    assert_eq!(query.config, None);
+    assert_eq!(query.jinja_blocks.len(), 1);
+    assert_eq!(query.jinja_blocks[0].to_string(), "{% if target.name == 'prod' %}{{ config(materialized='table') }}{% endif %}");
+    assert_eq!(query.body.to_string(), "SELECT 1 AS x");
+}
+
+#[test]
+fn parse_jinja_conditional_errors() {
+    let res = parse_sql_statements("SELECT a FROM t WHERE {% if x %} b = 1");
+    assert_eq!(
+        ParserError::ParserError("Expected {% endif %}, found: EOF".to_string()),
+        res.unwrap_err()
+    );
+}
+
+#[test]
+fn parse_jinja_for() {
+    let sql = "SELECT order_id, {% for method in ['card', 'coupon'] %} SUM(CASE WHEN payment_method = '{{ method }}' THEN amount END) AS amount_{{ method }}{% if not loop.last %},{% endif %} {% endfor %} FROM {{ ref('payments') }} GROUP BY 1";
+
+    let select = verified_only_select(sql);
+    assert_eq!(select.projection.len(), 2);
+    let for_loop = match expr_from_projection(&select.projection[1]) {
+        Expr::JinjaBlock { expr: None, block: JinjaBlock::For(for_loop) } => for_loop,
+        other => panic!("Expected a Jinja for loop, found: {other:?}"),
+    };
+    assert_eq!(for_loop.variables, vec![Ident::new("method")]);
+    assert_eq!(
+        for_loop.iterable.iter().map(|token| token.to_string()).collect::<String>(),
+        "['card', 'coupon']"
+    );
+    assert!(for_loop.body.contains(&dbtranslate::tokens::Token::LJinjaIterator));
+}
+
+#[test]
+fn parse_jinja_for_with_trailing_comma() {
+    let sql = "SELECT {% for key, value in columns.items() %} {{ value }} AS {{ key }}, {% endfor %} id FROM customers";
+
+    let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+    let Statement::Query(query) = &statements[0];
+    let SetExpr::Select(select) = query.body.as_ref() else { panic!("Expected a SELECT") };
+    assert_eq!(select.projection.len(), 2);
+    match expr_from_projection(&select.projection[0]) {
+        Expr::JinjaBlock { block: JinjaBlock::For(for_loop), .. } => {
+            assert_eq!(for_loop.variables, vec![Ident::new("key"), Ident::new("value")]);
+        }
+        other => panic!("Expected a Jinja for loop, found: {other:?}"),
+    }
+
+    let res = parse_sql_statements("SELECT {% for x in xs %} {{ x }}");
+    assert_eq!(
+        ParserError::ParserError("Expected {% endfor %}, found: EOF".to_string()),
+        res.unwrap_err()
+    );
+}
+
+#[test]
+fn parse_jinja_macros() {
+    let sql = "{# Money #}\n\
+               {% macro cents_to_dollars(column_name, precision=2, columns=['a', 'b']) -%}\n\
+               ({{ column_name }} / 100)::numeric(16, {{ precision }})\n\
+               {%- endmacro %}\n\n\
+               {% macro no_args() %}{% if target.name == 'prod' %}1{% endif %}{% endmacro %}";
+
+    let macros = Parser::new(&GenericDialect {}).try_with_sql(sql).unwrap().parse_jinja_macros().unwrap();
+    assert_eq!(macros.len(), 2);
+    assert_eq!(macros[0].name, Ident::new("cents_to_dollars"));
+    assert_eq!(
+        macros[0].args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>(),
+        vec!["column_name", "precision=2", "columns=['a', 'b']"]
+    );
+    assert_eq!(
+        macros[0].body.iter().map(|token| token.to_string()).collect::<String>(),
+        "\n({{ column_name }} / 100)::numeric(16, {{ precision }})\n"
+    );
+    assert_eq!(
+        macros[1].to_string(),
+        "{% macro no_args() %}{% if target.name == 'prod' %}1{% endif %}{% endmacro %}"
+    );
+
+    let res = Parser::new(&GenericDialect {}).try_with_sql("{% macro m() %} 1").unwrap().parse_jinja_macros();
+    assert_eq!(
+        ParserError::ParserError("Expected {% endmacro %}, found: EOF".to_string()),
+        res.unwrap_err()
+    );
+}
+
+#[test]
+fn parse_spans() {
+    use dbtranslate::tokens::Location;
+
+    fn span(start: (u64, u64), end: (u64, u64)) -> Span {
+        Span::new(
+            Location { line: start.0, column: start.1 },
+            Location { line: end.0, column: end.1 },
+        )
+    }
+
+    let sql = "SELECT id,\n  SUM(o.amount) AS total,\n  *\nFROM {{ ref('orders') }} AS o";
+    let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+    let Statement::Query(query) = &statements[0];
+    let SetExpr::Select(select) = query.body.as_ref() else { panic!("Expected a SELECT") };
+
+    assert_eq!(select.projection[0].span(), span((1, 8), (1, 10)));
+    let SelectItem::ExprWithAlias { expr, .. } = &select.projection[1] else { panic!("Expected an aliased expression") };
+    assert_eq!(expr.span(), span((2, 3), (2, 15)));
+    assert_eq!(select.projection[1].span(), span((2, 3), (2, 25)));
+    assert_eq!(select.projection[2].span(), span((3, 3), (3, 4)));
+    assert_eq!(select.from[0].relation.span(), span((4, 13), (4, 30)));
+    assert_eq!(statements[0].span(), span((1, 1), (4, 30)));
+
+    // The SELECT and set operator keywords keep their spans, so statements
+    // of literals have one and a union starts at its first SELECT
+    let statements = Parser::parse_sql(&GenericDialect {}, "SELECT 1;\nSELECT 2 UNION SELECT 3").unwrap();
+    assert_eq!(statements[0].span(), span((1, 1), (1, 7)));
+    let Statement::Query(query) = &statements[1];
+    let SetExpr::SetOperation { op_span, .. } = query.body.as_ref() else { panic!("Expected a UNION") };
+    assert_eq!(op_span.0, span((2, 10), (2, 15)));
+    assert_eq!(statements[1].span(), span((2, 1), (2, 22)));
+
+    // Spans don't take part in comparisons, so nodes built by hand still
+    // equal parsed ones
+    assert_eq!(select.projection[0], UnnamedExpr(Expr::Identifier(Ident::new("id"))));
+    assert_eq!(Expr::Value(number("1")).span(), Span::empty());
+}
@@ -0,0 +1,90 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![warn(clippy::all)]
+//! Test SQL syntax specific to DuckDB. The parser based on the generic
+//! dialect is also tested (on the inputs it can handle).
+
+#[macro_use]
+mod test_utils;
+
+use test_utils::*;
+
+use dbtranslate::ast::*;
+use dbtranslate::dialect::{DuckDbDialect, GenericDialect};
+
+fn duckdb() -> TestedDialects {
+    TestedDialects {
+        dialects: vec![Box::new(DuckDbDialect {})],
+    }
+}
+
+fn duckdb_and_generic() -> TestedDialects {
+    TestedDialects {
+        dialects: vec![Box::new(DuckDbDialect {}), Box::new(GenericDialect {})],
+    }
+}
+
+#[test]
+fn test_select_wildcard_with_exclude() {
+    let select = duckdb_and_generic().verified_only_select("SELECT * EXCLUDE (col_a, col_b) FROM data");
+    let expected = SelectItem::Wildcard(WildcardAdditionalOptions {
+        opt_exclude: Some(ExcludeSelectItem::Multiple(vec![Ident::new("col_a"), Ident::new("col_b")])),
+        ..Default::default()
+    });
+    assert_eq!(expected, select.projection[0]);
+
+    duckdb().verified_stmt("SELECT name.* EXCLUDE department_id FROM employee_table AS name");
+    duckdb().verified_stmt("SELECT * REPLACE (lower(city) AS city) FROM addresses");
+}
+
+#[test]
+fn test_columns_expression() {
+    let select = duckdb().verified_only_select("SELECT COLUMNS(*) FROM data");
+    assert_eq!(
+        &Expr::Function(Function {
+            name: ObjectName(vec![Ident::new("COLUMNS")]),
+            args: vec![FunctionArg::Unnamed(FunctionArgExpr::Wildcard)],
+            over: None,
+            distinct: false,
+            special: false,
+        }),
+        expr_from_projection(&select.projection[0])
+    );
+
+    duckdb().verified_stmt("SELECT MIN(COLUMNS('price_.*')) FROM sales");
+    duckdb().verified_stmt("SELECT COLUMNS(c -> c LIKE '%num%') FROM addresses");
+}
+
+#[test]
+fn test_positional_join() {
+    let select = duckdb_and_generic().verified_only_select("SELECT * FROM t1 POSITIONAL JOIN t2");
+    assert_eq!(
+        select.from[0].joins,
+        vec![Join {
+            relation: TableFactor::Table {
+                name: ObjectName(vec![Ident::new("t2")]),
+                alias: None,
+                args: None,
+                with_hints: vec![],
+            },
+            join_operator: JoinOperator::PositionalJoin,
+        }]
+    );
+}
+
+#[test]
+fn test_list_indexing() {
+    duckdb().verified_stmt("SELECT tags[1], tags[2][1] FROM t");
+    duckdb().verified_stmt("SELECT count(*) FILTER (WHERE status = 'shipped') FROM orders");
+    duckdb().verified_stmt("SELECT region, sum(amount) FROM orders GROUP BY GROUPING SETS ((region), ())");
+}
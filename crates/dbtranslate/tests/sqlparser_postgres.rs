@@ -19,6 +19,7 @@ mod test_utils;
 use test_utils::*;
 
 use dbtranslate::ast::*;
+use dbtranslate::tokens::Span;
 use dbtranslate::dialect::{GenericDialect, PostgreSqlDialect};
 
 #[test]
@@ -159,12 +160,14 @@ fn parse_array_index_expr() {
             indexes: vec![
                 num[0].clone(),
                 Expr::Identifier(Ident {
-                    value: "baz".to_string(),
-                    quote_style: Some('"')
+                    value: "baz".into(),
+                    quote_style: Some('"'),
+                    span: Span::empty(),
                 }),
                 Expr::Identifier(Ident {
-                    value: "fooz".to_string(),
-                    quote_style: Some('"')
+                    value: "fooz".into(),
+                    quote_style: Some('"'),
+                    span: Span::empty(),
                 })
             ],
         },
@@ -183,9 +186,9 @@ fn parse_array_index_expr() {
                     })],
                     named: true,
                 })),
-                data_type: DataType::Array(Some(Box::new(DataType::Array(Some(Box::new(
-                    DataType::Int(None)
-                ))))))
+                data_type: DataType::Array(ArrayElemTypeDef::SquareBracket(Box::new(
+                    DataType::Array(ArrayElemTypeDef::SquareBracket(Box::new(DataType::Int(None))))
+                )))
             }))),
             indexes: vec![num[1].clone(), num[2].clone()],
         },
@@ -215,6 +218,7 @@ fn parse_array_subquery_expr() {
                 op: SetOperator::Union,
                 set_quantifier: SetQuantifier::None,
                 left: Box::new(SetExpr::Select(Box::new(Select {
+                    select_span: AttachedSpan::default(),
                     distinct: false,
                     top: None,
                     projection: vec![SelectItem::UnnamedExpr(Expr::Value(Value::Number(
@@ -234,8 +238,10 @@ fn parse_array_subquery_expr() {
                     sort_by: vec![],
                     having: None,
                     qualify: None,
+                    jinja_blocks: vec![],
                 }))),
                 right: Box::new(SetExpr::Select(Box::new(Select {
+                    select_span: AttachedSpan::default(),
                     distinct: false,
                     top: None,
                     projection: vec![SelectItem::UnnamedExpr(Expr::Value(Value::Number(
@@ -255,12 +261,15 @@ fn parse_array_subquery_expr() {
                     sort_by: vec![],
                     having: None,
                     qualify: None,
+                    jinja_blocks: vec![],
                 }))),
+                op_span: AttachedSpan::default(),
             }),
             order_by: vec![],
             limit: None,
             offset: None,
             jinja_variables: vec![],
+            jinja_blocks: vec![],
         })),
         expr_from_projection(only(&select.projection)),
     );
@@ -582,6 +591,7 @@ fn parse_custom_operator() {
             left: Box::new(Expr::Identifier(Ident {
                 value: "relname".into(),
                 quote_style: None,
+                span: Span::empty(),
             })),
             op: BinaryOperator::PGCustomBinaryOperator(vec![
                 "database".into(),
@@ -601,6 +611,7 @@ fn parse_custom_operator() {
             left: Box::new(Expr::Identifier(Ident {
                 value: "relname".into(),
                 quote_style: None,
+                span: Span::empty(),
             })),
             op: BinaryOperator::PGCustomBinaryOperator(vec!["pg_catalog".into(), "~".into()]),
             right: Box::new(Expr::Value(Value::SingleQuotedString("^(table)$".into())))
@@ -616,6 +627,7 @@ fn parse_custom_operator() {
             left: Box::new(Expr::Identifier(Ident {
                 value: "relname".into(),
                 quote_style: None,
+                span: Span::empty(),
             })),
             op: BinaryOperator::PGCustomBinaryOperator(vec!["~".into()]),
             right: Box::new(Expr::Value(Value::SingleQuotedString("^(table)$".into())))
@@ -788,7 +800,7 @@ fn parse_dollar_quoted_string() {
     let sql = "SELECT $$hello$$, $tag_name$world$tag_name$, $$Foo$Bar$$, $$Foo$Bar$$col_name, $$$$, $tag_name$$tag_name$";
 
     let stmts = pg().parse_sql_statements(sql).unwrap();
-    let Statement::Query(query) = stmts.get(0).unwrap();
+    let Statement::Query(query) = stmts.first().unwrap();
 
     let projection = if let SetExpr::Select(select) = &*query.body {
         &select.projection
@@ -830,6 +842,7 @@ fn parse_dollar_quoted_string() {
             alias: Ident {
                 value: "col_name".into(),
                 quote_style: None,
+                span: Span::empty(),
             },
         }
     );
@@ -16,6 +16,7 @@ mod test_utils;
 use test_utils::*;
 
 use dbtranslate::ast::*;
+use dbtranslate::tokens::Span;
 use dbtranslate::dialect::RedshiftSqlDialect;
 
 #[test]
@@ -24,8 +25,9 @@ fn test_square_brackets_over_db_schema_table_name() {
     assert_eq!(
         select.projection[0],
         SelectItem::UnnamedExpr(Expr::Identifier(Ident {
-            value: "col1".to_string(),
-            quote_style: Some('[')
+            value: "col1".into(),
+            quote_style: Some('['),
+            span: Span::empty(),
         })),
     );
     assert_eq!(
@@ -34,12 +36,14 @@ fn test_square_brackets_over_db_schema_table_name() {
             relation: TableFactor::Table {
                 name: ObjectName(vec![
                     Ident {
-                        value: "test_schema".to_string(),
-                        quote_style: Some('[')
+                        value: "test_schema".into(),
+                        quote_style: Some('['),
+                        span: Span::empty(),
                     },
                     Ident {
-                        value: "test_table".to_string(),
-                        quote_style: Some('[')
+                        value: "test_table".into(),
+                        quote_style: Some('['),
+                        span: Span::empty(),
                     }
                 ]),
                 alias: None,
@@ -68,8 +72,9 @@ fn test_double_quotes_over_db_schema_table_name() {
     assert_eq!(
         select.projection[0],
         SelectItem::UnnamedExpr(Expr::Identifier(Ident {
-            value: "col1".to_string(),
-            quote_style: Some('"')
+            value: "col1".into(),
+            quote_style: Some('"'),
+            span: Span::empty(),
         })),
     );
     assert_eq!(
@@ -78,12 +83,14 @@ fn test_double_quotes_over_db_schema_table_name() {
             relation: TableFactor::Table {
                 name: ObjectName(vec![
                     Ident {
-                        value: "test_schema".to_string(),
-                        quote_style: Some('"')
+                        value: "test_schema".into(),
+                        quote_style: Some('"'),
+                        span: Span::empty(),
                     },
                     Ident {
-                        value: "test_table".to_string(),
-                        quote_style: Some('"')
+                        value: "test_table".into(),
+                        quote_style: Some('"'),
+                        span: Span::empty(),
                     }
                 ]),
                 alias: None,
@@ -109,7 +109,7 @@ fn parse_array() {
     assert_eq!(
         &Expr::Cast {
             expr: Box::new(Expr::Identifier(Ident::new("a"))),
-            data_type: DataType::Array(None),
+            data_type: DataType::Array(ArrayElemTypeDef::None),
         },
         expr_from_projection(only(&select.projection))
     );
@@ -331,6 +331,62 @@ fn test_array_agg_func() {
     )
 }
 
+#[test]
+fn parse_semi_structured_paths() {
+    let select = snowflake().verified_only_select("SELECT a:b.c[0].d FROM t");
+    assert_eq!(
+        SelectItem::UnnamedExpr(Expr::JsonAccess {
+            left: Box::new(Expr::Identifier(Ident::new("a"))),
+            operator: JsonOperator::Colon,
+            right: Box::new(Expr::Value(Value::UnQuotedString("b.c[0].d".to_string()))),
+        }),
+        select.projection[0]
+    );
+
+    snowflake().verified_stmt("SELECT t.a:b.c FROM t");
+    snowflake().verified_stmt("SELECT a:user.date FROM t");
+    snowflake().verified_stmt("SELECT a['b'], a[0][1] FROM t");
+    snowflake().one_statement_parses_to(
+        "SELECT a:b.c::string, a:b[0]::int, f.value:id::varchar FROM t",
+        "SELECT CAST(a:b.c AS STRING), CAST(a:b[0] AS INT), CAST(f.value:id AS VARCHAR) FROM t",
+    );
+}
+
+#[test]
+fn parse_lateral_flatten() {
+    let sql = "SELECT f.value FROM t, LATERAL FLATTEN(input => t.items) AS f";
+    let select = snowflake().verified_only_select(sql);
+    assert_eq!(
+        TableFactor::Function {
+            lateral: true,
+            name: ObjectName(vec![Ident::new("FLATTEN")]),
+            args: vec![FunctionArg::Named {
+                name: Ident::new("input"),
+                arg: FunctionArgExpr::Expr(Expr::CompoundIdentifier(vec![Ident::new("t"), Ident::new("items")])),
+            }],
+            alias: Some(TableAlias { name: Ident::new("f"), columns: vec![] }),
+        },
+        select.from[1].relation
+    );
+
+    snowflake().one_statement_parses_to(
+        "SELECT f.value FROM t, LATERAL FLATTEN(INPUT => t.items, OUTER => TRUE) f",
+        "SELECT f.value FROM t, LATERAL FLATTEN(INPUT => t.items, OUTER => true) AS f",
+    );
+    snowflake().verified_stmt("SELECT f.value FROM t CROSS JOIN LATERAL FLATTEN(input => t.items) AS f");
+}
+
+#[test]
+fn parse_like_any() {
+    for sql in [
+        "SELECT * FROM t WHERE name ILIKE ANY('%a', '%b')",
+        "SELECT * FROM t WHERE name LIKE ANY('%a', '%b')",
+        "SELECT * FROM t WHERE name NOT ILIKE ALL('%a', '%b')",
+    ] {
+        snowflake().verified_stmt(sql);
+    }
+}
+
 fn snowflake() -> TestedDialects {
     TestedDialects {
         dialects: vec![Box::new(SnowflakeDialect {})],
@@ -0,0 +1,97 @@
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![warn(clippy::all)]
+//! Test SQL syntax specific to Trino and Presto.
+
+#[macro_use]
+mod test_utils;
+
+use test_utils::*;
+
+use dbtranslate::ast::*;
+use dbtranslate::dialect::TrinoDialect;
+
+fn trino() -> TestedDialects {
+    TestedDialects {
+        dialects: vec![Box::new(TrinoDialect {})],
+    }
+}
+
+#[test]
+fn parse_unnest_with_ordinality() {
+    let select = trino().verified_only_select(
+        "SELECT item, n FROM orders CROSS JOIN UNNEST(orders.items, orders.prices) WITH ORDINALITY AS i (item, price, n)",
+    );
+    assert_eq!(
+        select.from[0].joins[0].relation,
+        TableFactor::UNNEST {
+            alias: Some(TableAlias {
+                name: Ident::new("i"),
+                columns: vec![Ident::new("item"), Ident::new("price"), Ident::new("n")],
+            }),
+            array_exprs: vec![
+                Expr::CompoundIdentifier(vec![Ident::new("orders"), Ident::new("items")]),
+                Expr::CompoundIdentifier(vec![Ident::new("orders"), Ident::new("prices")]),
+            ],
+            with_ordinality: true,
+            with_offset: false,
+            with_offset_alias: None,
+        }
+    );
+
+    trino().verified_stmt("SELECT x FROM UNNEST(ARRAY[1, 2, 3]) AS t (x)");
+}
+
+#[test]
+fn parse_lambdas() {
+    let select = trino().verified_only_select("SELECT filter(items, x -> x > 1) FROM orders");
+    match expr_from_projection(&select.projection[0]) {
+        Expr::Function(Function { args, .. }) => assert_eq!(
+            args[1],
+            FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::Lambda {
+                params: vec![Ident::new("x")],
+                body: Box::new(Expr::BinaryOp {
+                    left: Box::new(Expr::Identifier(Ident::new("x"))),
+                    op: BinaryOperator::Gt,
+                    right: Box::new(Expr::Value(number("1"))),
+                }),
+            }))
+        ),
+        expr => panic!("Expected a function, got {expr:?}"),
+    }
+
+    trino().verified_stmt("SELECT transform_values(attributes, (k, v) -> upper(v)) FROM products");
+    trino().verified_stmt("SELECT reduce(prices, 0, (s, x) -> s + x, s -> s) FROM orders");
+}
+
+#[test]
+fn parse_try_and_row_types() {
+    trino().verified_stmt("SELECT TRY(CAST(amount AS INTEGER)), TRY_CAST(amount AS DOUBLE) FROM orders");
+
+    let select = trino().verified_only_select("SELECT CAST(customer AS ROW(id BIGINT, name VARCHAR)) FROM orders");
+    assert_eq!(
+        expr_from_projection(&select.projection[0]),
+        &Expr::Cast {
+            expr: Box::new(Expr::Identifier(Ident::new("customer"))),
+            data_type: DataType::Row(vec![
+                StructField { field_name: Some(Ident::new("id")), field_type: DataType::BigInt(None) },
+                StructField { field_name: Some(Ident::new("name")), field_type: DataType::Varchar(None) },
+            ]),
+        }
+    );
+}
+
+#[test]
+fn parse_subscripts() {
+    trino().verified_stmt("SELECT attributes['color'], sizes[1] FROM products");
+}